@@ -3,19 +3,40 @@
 //! Main application state and egui integration
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc::{Receiver, Sender};
 use std::sync::Arc;
+use alacritty_terminal::grid::Dimensions;
+use alacritty_terminal::index::Line;
 use arboard::Clipboard;
 use egui::{CentralPanel, Context, Event, Frame, ImeEvent, Key, SidePanel, TopBottomPanel, Widget};
-use egui_term::{BackendCommand, BackendSettings, PtyEvent, TerminalBackend, TerminalView};
+use egui_term::{BackendCommand, BackendSettings, FontSettings, OutputTap, PtyEvent, TerminalBackend, TerminalFont, TerminalMode, TerminalView};
 use tokio::runtime::Runtime;
-use crate::config::{Config, RuntimeTheme};
-use crate::directory_scanner::scan_directory;
-use crate::layout::{LayoutNode, PaneId, SplitDirection, ComputedLayout, DIVIDER_WIDTH, DEFAULT_SPLIT_RATIO};
+use crate::config::{Config, MarkdownMode, NewTabPosition, NewTerminalDir, RuntimeTheme, SidebarMode, SidebarOpenAction};
+use crate::config_store::ConfigStore;
+use crate::diagnostics::{Diagnostic, PaneDiagnostics};
+use crate::directory_scanner::{list_directory, scan_directory, ScanOptions, TreeVisibility};
+use crate::file_loader::{FileLoad, LoadLimits, LoadMode, LoadTier};
+use crate::input::{InputAction, InputRouter, KeyboardOwner, KeyboardSurfaces};
+use crate::layout::{split_node, DividerDrag, DropZone, LayoutNode, PaneDirection, PaneId, PaneMeta, SplitDirection, SplitSize, ComputedLayout, DIVIDER_WIDTH};
 use crate::menu::{self, MenuAction};
+use crate::pane_diff::{DiffScope, LineKind, PaneDiff};
+use crate::thumbnails::ThumbnailCache;
+use crate::presentation::PresentationMode;
+use crate::nav_history::NavHistory;
+use crate::root_switch::RootSwitchGovernor;
+use crate::scrollback_marks::ScrollbackMarks;
+use crate::session_log::{LineCapture, SessionLogWriter};
+use crate::startup::StartupReport;
+use crate::progress::ProgressFeed;
+use crate::terminal_backend::{MockBackend, PtyBackend, TerminalBackendHandle};
 use crate::theme;
-use crate::ui::{FileEntry, Sidebar, StatusBar, TabBar, TabInfo, CommandPalette};
+use crate::tree_state::TreeState;
+use crate::ui::{cd_command, BreadcrumbBar, SearchPanel, FileEntry, FilterView, GitStatusSync, GIT_STATUS_CHUNK, OutputNote, OutputNoteWindow, Sidebar, SidebarResponse, ContextStatus, StatusBar, TabBar, TabInfo, CommandPalette, SnippetPalette, RunAllDialog, RunAllRequest};
+
+#[cfg(test)]
+mod headless;
 
 /// State for pane drag-and-drop repositioning
 #[derive(Debug, Clone)]
@@ -39,30 +60,6 @@ struct TabDragState {
     drag_active: bool,  // true after 5px threshold
 }
 
-/// Where a pane can be dropped
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DropZone {
-    /// Drop at top edge (creates vertical split, new pane above)
-    Top(PaneId),
-    /// Drop at bottom edge (creates vertical split, new pane below)
-    Bottom(PaneId),
-    /// Drop at left edge (creates horizontal split, new pane left)
-    Left(PaneId),
-    /// Drop at right edge (creates horizontal split, new pane right)
-    Right(PaneId),
-}
-
-/// Drop zone with rendering info
-#[derive(Debug)]
-pub struct DropZoneInfo {
-    /// The zone type
-    pub zone: DropZone,
-    /// Hit-test rectangle (25% of edge)
-    pub rect: egui::Rect,
-    /// Visual highlight rectangle (50% preview)
-    pub highlight_rect: egui::Rect,
-}
-
 /// Content type for a tab
 #[derive(Debug)]
 pub enum TabContent {
@@ -72,13 +69,433 @@ pub enum TabContent {
     FileViewer {
         path: PathBuf,
         content: String,
-        scroll_offset: f32,
+        /// Tab width and `content` with its tabs expanded (`None` without
+        /// any), worked out on the first draw after each load
+        expanded: Option<(u8, Option<String>)>,
+        /// Where it was scrolled to when last drawn
+        scroll_offset: egui::Vec2,
+        state: ViewerState,
+        /// Line to scroll to once loaded (1-based)
+        goto_line: Option<u32>,
+        /// Rendered/source toggle, for Markdown files
+        markdown: Option<MarkdownPane>,
+        /// Line wrapping picked for this pane (`None` follows the config)
+        wrap: Option<bool>,
+        /// Git blame column, while turned on
+        blame: Option<BlameView>,
+    },
+    /// No content yet: the first keypress starts a shell, an opened file shows here
+    Empty,
+    /// Diff of two terminals' output
+    Diff(DiffView),
+    /// Usage Stats charts (`None` while the stats file is read)
+    Stats(Option<crate::stats::StatsSummary>),
+    /// Terminal Color Test preview
+    ColorTest(Vec<crate::color_test::Section>),
+}
+
+impl TabContent {
+    /// Empty file viewer for `path`, filled in by `VibeTermApp::load_file_view`
+    fn file_viewer(path: PathBuf) -> Self {
+        TabContent::FileViewer {
+            path,
+            content: String::new(),
+            expanded: None,
+            scroll_offset: egui::Vec2::ZERO,
+            state: ViewerState::Loading(LoadMode::Preview),
+            goto_line: None,
+            markdown: None,
+            wrap: None,
+            blame: None,
+        }
+    }
+}
+
+/// Load state of a file viewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewerState {
+    /// Contents are being read in the background
+    Loading(LoadMode),
+    /// Contents are shown
+    Loaded {
+        mode: LoadMode,
+        /// Bytes of the file left out
+        skipped: u64,
+        /// Invalid UTF-8 was replaced
+        lossy: bool,
     },
 }
 
+/// Two terminals' text and the diff between them
+#[derive(Debug, Clone)]
+pub struct DiffView {
+    left: DiffSide,
+    right: DiffSide,
+    scope: DiffScope,
+    /// Request the shown diff is waiting for
+    pending: Option<u64>,
+    /// Diff of the current snapshots, once computed
+    diff: Option<PaneDiff>,
+}
+
+impl DiffView {
+    /// Short name: the two sides' labels
+    fn label(&self) -> String {
+        format!("{} ⇄ {}", self.left.label, self.right.label)
+    }
+}
+
+/// One side of a pane diff
+#[derive(Debug, Clone)]
+struct DiffSide {
+    terminal_id: u64,
+    label: String,
+    /// Snapshot of the terminal's text
+    text: String,
+}
+
+/// Button pressed in a diff pane's header
+#[derive(Debug, Clone, Copy)]
+enum DiffCommand {
+    /// Snapshot both terminals again
+    Rerun,
+    /// Swap the left and right sides
+    Swap,
+}
+
+/// Background diff for a diff pane
+struct DiffResult {
+    request: u64,
+    diff: PaneDiff,
+}
+
+/// What picking a pane in the pane number overlay does
+enum PanePick {
+    /// Send this selection to the picked pane
+    Send(String),
+    /// Diff two terminal panes; the first pick is the left side
+    Diff { scope: DiffScope, first: Option<PaneId> },
+}
+
+/// Markdown view of a file viewer pane
+#[derive(Debug)]
+pub struct MarkdownPane {
+    mode: MarkdownMode,
+    /// Parsed when first rendered after a load
+    document: Option<crate::ui::MarkdownDocument>,
+}
+
+/// Git blame column of a file viewer pane
+#[derive(Debug)]
+pub enum BlameView {
+    /// Waiting for the background blame
+    Loading(crate::blame::BlameJob),
+    Ready(Vec<crate::blame::BlameRun>),
+    /// Why there's no blame to show
+    Unavailable(String),
+    /// The file reloaded; blamed again when next drawn
+    Stale,
+}
+
+/// Background blame for a file viewer pane
+struct BlameResult {
+    request: u64,
+    result: Result<Vec<crate::blame::BlameRun>, String>,
+}
+
+/// Rendered/Source switch above a Markdown file
+///
+/// Returns the mode the user switched to, if any.
+fn show_markdown_toggle(ui: &mut egui::Ui, mode: MarkdownMode, colors: &RuntimeTheme) -> Option<MarkdownMode> {
+    let mut chosen = None;
+    ui.horizontal(|ui| {
+        for (option, label) in [(MarkdownMode::Rendered, "Rendered"), (MarkdownMode::Source, "Source")] {
+            let color = if option == mode { colors.text } else { colors.text_dim };
+            let text = egui::RichText::new(label).font(theme::ui_font(11.0)).color(color);
+            if ui.selectable_label(option == mode, text).clicked() && option != mode {
+                chosen = Some(option);
+            }
+        }
+    });
+    chosen
+}
+
+/// Wrap switch above a file's text
+///
+/// Returns whether the user flipped it.
+fn show_wrap_toggle(ui: &mut egui::Ui, wrap: bool, colors: &RuntimeTheme) -> bool {
+    let color = if wrap { colors.text } else { colors.text_dim };
+    let text = egui::RichText::new("Wrap").font(theme::ui_font(11.0)).color(color);
+    ui.selectable_label(wrap, text)
+        .on_hover_text("Wrap long lines in this pane")
+        .clicked()
+}
+
+/// Blame switch above a file's text, with the blame's progress
+///
+/// Returns whether the user flipped it.
+fn show_blame_toggle(ui: &mut egui::Ui, blame: Option<&BlameView>, colors: &RuntimeTheme) -> bool {
+    let color = if blame.is_some() { colors.text } else { colors.text_dim };
+    let text = egui::RichText::new("Blame").font(theme::ui_font(11.0)).color(color);
+    let clicked = ui.selectable_label(blame.is_some(), text)
+        .on_hover_text("Show who last changed each line")
+        .clicked();
+    match blame {
+        Some(BlameView::Loading(_) | BlameView::Stale) => {
+            ui.spinner();
+        }
+        Some(BlameView::Unavailable(reason)) => {
+            ui.label(egui::RichText::new(reason).font(theme::ui_font(11.0)).color(colors.text_dim));
+        }
+        Some(BlameView::Ready(_)) | None => {}
+    }
+    clicked
+}
+
+/// Top of each paragraph (file line) of `galley`, relative to it
+fn paragraph_tops(galley: &egui::Galley) -> Vec<f32> {
+    let mut tops = Vec::new();
+    let mut starts_paragraph = true;
+    for row in &galley.rows {
+        if starts_paragraph {
+            tops.push(row.min_y());
+        }
+        starts_paragraph = row.ends_with_newline;
+    }
+    tops
+}
+
+/// One blame label per run of lines, in the gutter left of the text at `text_pos`
+///
+/// Returns the commit hash the user clicked, if any.
+fn show_blame_gutter(
+    ui: &mut egui::Ui,
+    runs: &[crate::blame::BlameRun],
+    left: f32,
+    text_pos: egui::Pos2,
+    galley: &egui::Galley,
+    colors: &RuntimeTheme,
+) -> Option<String> {
+    let tops = paragraph_tops(galley);
+    let width = theme::ui_scaled(BLAME_GUTTER_WIDTH) - 8.0;
+    let visible = ui.clip_rect();
+    let now = std::time::SystemTime::now();
+    let mut clicked = None;
+    for run in runs {
+        let Some(&top) = tops.get(run.start) else {
+            break;
+        };
+        let bottom = tops.get(run.end).copied().unwrap_or(galley.size().y);
+        let rect = egui::Rect::from_x_y_ranges(left..=left + width, text_pos.y + top..=text_pos.y + bottom);
+        if !rect.intersects(visible) {
+            continue;
+        }
+
+        let response = ui.interact(rect, ui.id().with(("blame", run.start)), egui::Sense::click());
+        let color = if response.hovered() { colors.text } else { colors.text_dim };
+        let label = ui.painter().layout(run.commit.label(now), theme::ui_font(11.0), color, width);
+        ui.painter().with_clip_rect(rect).galley(rect.min, label, color);
+        // A hairline marks where a run ends
+        ui.painter().hline(rect.x_range(), rect.bottom(), egui::Stroke::new(1.0, colors.border));
+
+        let response = response.on_hover_text(run.commit.details());
+        if response.clicked() {
+            clicked = run.commit.id.clone();
+        }
+    }
+    clicked
+}
+
+/// Header and colored lines of a diff pane
+///
+/// Returns the header button pressed, if any.
+fn show_diff_view(ui: &mut egui::Ui, view: &DiffView, pane_id: PaneId, colors: &RuntimeTheme) -> Option<DiffCommand> {
+    let font = theme::ui_font(12.0);
+    let mut command = None;
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new(view.label()).font(theme::ui_font(11.0)).color(colors.text));
+        ui.label(egui::RichText::new(view.scope.label()).font(theme::ui_font(11.0)).color(colors.text_dim));
+        if let Some(diff) = &view.diff {
+            ui.label(egui::RichText::new(format!("-{}", diff.removed)).font(theme::ui_font(11.0)).color(colors.red));
+            ui.label(egui::RichText::new(format!("+{}", diff.added)).font(theme::ui_font(11.0)).color(colors.green));
+            if ui.small_button("Copy").on_hover_text("Copy the diff as text").clicked() {
+                ui.ctx().copy_text(crate::pane_diff::format_diff(diff));
+            }
+        }
+        if ui.small_button("Re-run").on_hover_text("Snapshot both panes again").clicked() {
+            command = Some(DiffCommand::Rerun);
+        }
+        if ui.small_button("Swap").on_hover_text("Swap the left and right sides").clicked() {
+            command = Some(DiffCommand::Swap);
+        }
+    });
+
+    let Some(diff) = view.diff.as_ref().filter(|_| view.pending.is_none()) else {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(egui::RichText::new("Diffing…").font(theme::ui_font(12.0)).color(colors.text_dim));
+        });
+        return command;
+    };
+    if diff.added == 0 && diff.removed == 0 {
+        ui.label(egui::RichText::new("No differences").font(theme::ui_font(11.0)).color(colors.text_dim));
+    }
+
+    let row_height = ui.fonts(|f| f.row_height(&font));
+    egui::ScrollArea::both()
+        .id_salt(format!("diff_scroll_{}", pane_id.0))
+        .auto_shrink([false, false])
+        .show_rows(ui, row_height, diff.lines.len(), |ui, rows| {
+            ui.spacing_mut().item_spacing.y = 0.0;
+            for line in &diff.lines[rows] {
+                let (prefix, color) = match line.kind {
+                    LineKind::Same => ("  ", None),
+                    LineKind::Removed => ("- ", Some(colors.red)),
+                    LineKind::Added => ("+ ", Some(colors.green)),
+                };
+                let text_color = color.unwrap_or(colors.text);
+                let mut job = egui::text::LayoutJob::default();
+                job.append(prefix, 0.0, egui::TextFormat::simple(font.clone(), text_color));
+                // Changed words stand out when only part of the line changed
+                let whole = line.segments.iter().all(|(changed, _)| *changed);
+                for (changed, text) in &line.segments {
+                    let mut format = egui::TextFormat::simple(font.clone(), text_color);
+                    if let Some(color) = color.filter(|_| *changed && !whole) {
+                        format.background = color.gamma_multiply(0.35);
+                    }
+                    job.append(text, 0.0, format);
+                }
+
+                let galley = ui.fonts(|f| f.layout_job(job));
+                let width = galley.size().x.max(ui.available_width());
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(width, row_height), egui::Sense::hover());
+                if let Some(color) = color {
+                    ui.painter().rect_filled(rect, 0.0, color.gamma_multiply(0.12));
+                }
+                ui.painter().galley(rect.min, galley, text_color);
+            }
+        });
+    command
+}
+
+/// Output rate in a terminal pane's footer, with a chip while it's throttled
+fn paint_throughput_footer(
+    ui: &egui::Ui,
+    throughput: &crate::throughput::ThroughputTracker,
+    terminal_id: u64,
+    rect: egui::Rect,
+    colors: &RuntimeTheme,
+) {
+    let rate = throughput.rate(terminal_id, std::time::Instant::now());
+    // Below this it's someone typing, not a stream
+    if rate < 1024.0 {
+        return;
+    }
+    let painter = ui.painter();
+    let mut right = rect.right_bottom() - egui::vec2(6.0, 4.0);
+    let label = painter.text(
+        right,
+        egui::Align2::RIGHT_BOTTOM,
+        crate::throughput::format_rate(rate),
+        theme::ui_font(10.0),
+        colors.text_dim,
+    );
+    right.x = label.left() - 6.0;
+
+    if throughput.is_firehose(terminal_id) {
+        let chip = painter.layout_no_wrap("output throttled".to_string(), theme::ui_font(10.0), colors.background);
+        let chip_rect = egui::Rect::from_min_size(
+            right - chip.size() - egui::vec2(8.0, 2.0),
+            chip.size() + egui::vec2(8.0, 2.0),
+        );
+        painter.rect_filled(chip_rect, 3.0, colors.yellow);
+        painter.galley(chip_rect.min + egui::vec2(4.0, 1.0), chip, colors.background);
+    }
+}
+
+/// Reported percentage in a terminal pane's footer, in the progress color
+fn paint_progress_footer(ui: &egui::Ui, progress: crate::progress::Progress, rect: egui::Rect, colors: &RuntimeTheme) {
+    let Some(percent) = progress.percent else {
+        return;
+    };
+    let label = match progress.state {
+        crate::progress::ProgressState::Error => format!("{}% failed", percent),
+        crate::progress::ProgressState::Paused => format!("{}% paused", percent),
+        _ => format!("{}%", percent),
+    };
+    ui.painter().text(
+        rect.left_bottom() + egui::vec2(6.0, -4.0),
+        egui::Align2::LEFT_BOTTOM,
+        label,
+        theme::ui_font(10.0),
+        crate::ui::progress_color(progress, colors, ui.input(|i| i.time)),
+    );
+}
+
+/// Banner for a truncated or lossily decoded file
+///
+/// Returns the load mode the user picked, if any.
+fn show_file_notice(ui: &mut egui::Ui, state: ViewerState, colors: &RuntimeTheme) -> Option<LoadMode> {
+    let ViewerState::Loaded { mode, skipped, lossy } = state else {
+        return None;
+    };
+    let mut chosen = None;
+    if skipped > 0 {
+        ui.horizontal(|ui| {
+            let what = if mode == LoadMode::Tail { "showing the end" } else { "showing head and tail" };
+            let hidden_mb = skipped as f64 / (1024.0 * 1024.0);
+            ui.label(egui::RichText::new(format!("Truncated — {}, {:.1} MB not shown", what, hidden_mb))
+                .font(theme::ui_font(11.0))
+                .color(colors.yellow));
+            if ui.small_button("Open Full").clicked() {
+                chosen = Some(LoadMode::Full);
+            }
+            if mode != LoadMode::Tail && ui.small_button("Open Tail").clicked() {
+                chosen = Some(LoadMode::Tail);
+            }
+        });
+    }
+    if lossy {
+        ui.label(egui::RichText::new("Not valid UTF-8 — invalid bytes are shown as \u{FFFD}")
+            .font(theme::ui_font(11.0))
+            .color(colors.text_dim));
+    }
+    chosen
+}
+
+/// Apply a finished read to a file viewer's content
+fn apply_file_load(
+    content: &mut String,
+    expanded: &mut Option<(u8, Option<String>)>,
+    state: &mut ViewerState,
+    markdown: &mut Option<MarkdownPane>,
+    blame: &mut Option<BlameView>,
+    mode: LoadMode,
+    result: Result<FileLoad, String>,
+) {
+    *expanded = None;
+    if let Some(markdown) = markdown {
+        markdown.document = None;
+    }
+    if let Some(blame) = blame {
+        *blame = BlameView::Stale;
+    }
+    match result {
+        Ok(load) => {
+            *content = load.content;
+            *state = ViewerState::Loaded { mode, skipped: load.skipped, lossy: load.lossy };
+        }
+        Err(e) => {
+            log::warn!("{}", e);
+            *content = format!("Error: {}", e);
+            *state = ViewerState::Loaded { mode, skipped: 0, lossy: false };
+        }
+    }
+}
+
 /// Terminal instance with its backend
 struct TerminalInstance {
-    backend: TerminalBackend,
+    backend: Box<dyn TerminalBackendHandle>,
     id: u64,
     /// Current working directory (tracked for sidebar root switching)
     current_dir: PathBuf,
@@ -86,6 +503,307 @@ struct TerminalInstance {
     project_root: Option<PathBuf>,
     /// PTY process tracker for CWD monitoring (None if tracking unavailable)
     pty_tracker: Option<crate::pty_tracker::PtyTracker>,
+    /// Size last sent to the PTY (None until first layout)
+    committed_size: Option<egui::Vec2>,
+    /// Newer size waiting for the resize debounce
+    pending_size: Option<egui::Vec2>,
+    /// When the pending size last changed
+    last_size_change: std::time::Instant,
+    /// Label from a project layout file
+    name: Option<String>,
+    /// Output capture while session logging is on
+    session_log: Option<LineCapture>,
+    /// Per-pane logging choice overriding the config (`None` follows it)
+    logging_override: Option<bool>,
+    /// Output arrived since the last log capture
+    output_pending: bool,
+    /// Compiler locations in the output of the last command
+    diagnostics: PaneDiagnostics,
+    /// Output arrived since the last diagnostics scan
+    diagnostics_pending: bool,
+    /// When output last arrived
+    last_output: Option<std::time::Instant>,
+    /// When the tracked CWD last changed (`None` if not since launch)
+    cwd_changed_at: Option<std::time::Instant>,
+    /// History capture measuring the output rate (from the first draw)
+    output_capture: Option<LineCapture>,
+    /// Output arrived since the rate was last measured
+    throughput_pending: bool,
+    /// Last drawn frame, replayed while firehose mode holds back redraws
+    firehose_frame: Option<PaneFrame>,
+    /// Named positions in the scrollback
+    marks: ScrollbackMarks,
+    /// History capture keeping the marks on their lines (from the first mark)
+    marks_capture: Option<LineCapture>,
+    /// Output arrived since the marks were last synced
+    marks_pending: bool,
+    /// Display offset saved while its tab is in the background
+    saved_offset: Option<usize>,
+    /// Directory hook banner over the top of the pane, until dismissed
+    dir_banner: Option<String>,
+    /// Whether the view follows new output, and the lines printed below it
+    follow: crate::pane_view::Follow,
+    /// Display offset as of the last follow update
+    follow_offset: usize,
+    /// History capture counting lines printed below a scrolled-back view
+    follow_capture: Option<LineCapture>,
+    /// Output arrived since the follow state was last updated
+    follow_pending: bool,
+    /// Input was written since the follow state was last updated
+    typed: bool,
+    /// Shell the PTY runs
+    shell: String,
+    /// When the shell was started
+    spawned_at: std::time::Instant,
+    /// Shells in a row that exited right after starting, this one's included once it exits
+    quick_exits: u32,
+    /// Exit status of the shell, once it exited
+    exit_code: Option<i32>,
+    /// The shell kept exiting right away and isn't restarted
+    failure: Option<ShellFailure>,
+    /// Progress its program last reported (OSC 9;4)
+    progress: Option<crate::progress::ProgressStatus>,
+}
+
+/// Shell that kept exiting right after starting
+struct ShellFailure {
+    shell: String,
+    exit_code: Option<i32>,
+}
+
+/// Button clicked on a shell failure panel
+enum ShellFailureAction {
+    OpenPreferences,
+    Retry,
+}
+
+/// Shapes a terminal view painted, for replaying without a redraw
+struct PaneFrame {
+    rect: egui::Rect,
+    drawn_at: std::time::Instant,
+    shapes: Vec<egui::epaint::ClippedShape>,
+}
+
+impl TerminalInstance {
+    fn new(backend: Box<dyn TerminalBackendHandle>, current_dir: PathBuf, project_root: Option<PathBuf>) -> Self {
+        Self {
+            id: backend.id(),
+            backend,
+            current_dir,
+            project_root,
+            // Attached asynchronously once the shell process is found
+            pty_tracker: None,
+            committed_size: None,
+            pending_size: None,
+            last_size_change: std::time::Instant::now(),
+            name: None,
+            session_log: None,
+            logging_override: None,
+            output_pending: false,
+            diagnostics: PaneDiagnostics::default(),
+            diagnostics_pending: false,
+            last_output: None,
+            cwd_changed_at: None,
+            output_capture: None,
+            throughput_pending: false,
+            firehose_frame: None,
+            marks: ScrollbackMarks::new(),
+            marks_capture: None,
+            marks_pending: false,
+            saved_offset: None,
+            dir_banner: None,
+            follow: crate::pane_view::Follow::Pinned,
+            follow_offset: 0,
+            follow_capture: None,
+            follow_pending: false,
+            typed: false,
+            shell: crate::shell_path::shell(),
+            spawned_at: std::time::Instant::now(),
+            quick_exits: 0,
+            exit_code: None,
+            failure: None,
+            progress: None,
+        }
+    }
+
+    /// Log output that scrolled into the history since the last capture
+    fn capture_output(&mut self, writer: &SessionLogWriter, strip_ansi: bool) {
+        let Some(capture) = &mut self.session_log else {
+            return;
+        };
+        self.output_pending = false;
+        self.backend.sync();
+        // Fullscreen apps draw on the alternate screen, which has no history
+        if !self.backend.mode().contains(TerminalMode::ALT_SCREEN) {
+            writer.write(self.id, capture.scrolled_lines(self.backend.grid(), strip_ansi));
+        }
+    }
+
+    /// Log the rest of the output and close the session log
+    fn stop_logging(&mut self, writer: &SessionLogWriter, strip_ansi: bool) {
+        let Some(mut capture) = self.session_log.take() else {
+            return;
+        };
+        self.backend.sync();
+        if !self.backend.mode().contains(TerminalMode::ALT_SCREEN) {
+            let grid = self.backend.grid();
+            let mut text = capture.scrolled_lines(grid, strip_ansi);
+            text.push_str(&crate::session_log::screen_lines(grid, strip_ansi));
+            writer.write(self.id, text);
+        }
+        writer.close(self.id);
+    }
+
+    /// Bytes of output that scrolled into the history since the last call
+    ///
+    /// Call after the view synced the grid. Output redrawn in place
+    /// (progress bars, fullscreen apps) isn't counted.
+    fn measure_output(&mut self) -> usize {
+        if !std::mem::take(&mut self.throughput_pending) || self.is_alt_screen() {
+            return 0;
+        }
+        let grid = self.backend.grid();
+        let capture = self.output_capture.get_or_insert_with(|| LineCapture::new(grid));
+        // More than the whole history scrolled by; counts all of it
+        let lines = capture.advance(grid).unwrap_or(usize::MAX);
+        crate::session_log::history_bytes(grid, lines)
+    }
+
+    /// Move the marks along with output that scrolled into the history
+    ///
+    /// Call after syncing the backend. The alternate screen has no history
+    /// of its own, so the marks wait for the main screen.
+    fn sync_marks(&mut self) {
+        if self.is_alt_screen() {
+            return;
+        }
+        self.marks_pending = false;
+        let grid = self.backend.grid();
+        let capture = self.marks_capture.get_or_insert_with(|| LineCapture::new(grid));
+        self.marks.sync(capture.advance(grid), grid.history_size());
+    }
+
+    /// Grid line a new mark goes on: the cursor's, or the top one when scrolled back
+    fn mark_line(&self) -> i32 {
+        let grid = self.backend.grid();
+        match grid.display_offset() {
+            0 => grid.cursor.point.line.0,
+            offset => -(offset as i32),
+        }
+    }
+
+    /// Top grid line in view (negative in the history)
+    fn top_line(&self) -> i32 {
+        -(self.backend.grid().display_offset() as i32)
+    }
+
+    /// Remember the scroll position before the tab goes to the background
+    fn save_view(&mut self) {
+        self.saved_offset = Some(self.backend.grid().display_offset());
+    }
+
+    /// Scroll back to the position saved by `save_view`, if it was lost
+    fn restore_view(&mut self) {
+        let Some(saved) = self.saved_offset.take() else {
+            return;
+        };
+        // The grid isn't synced while the tab is in the background
+        self.backend.sync();
+        let grid = self.backend.grid();
+        if let Some(target) = crate::pane_view::restored_offset(saved, grid.display_offset(), grid.history_size()) {
+            self.backend.process_command(BackendCommand::Scroll(target as i32));
+        }
+    }
+
+    /// Detach from or re-pin to the bottom after the view synced
+    fn update_follow(&mut self, triggers: crate::pane_view::FollowTriggers) {
+        let typed = std::mem::take(&mut self.typed);
+        let pending = std::mem::take(&mut self.follow_pending);
+        let grid = self.backend.grid();
+        let new_lines = match (&mut self.follow_capture, pending) {
+            // More than the whole history went by; counts all of it
+            (Some(capture), true) => capture.advance(grid).unwrap_or(grid.history_size()),
+            _ => 0,
+        };
+        let offset = grid.display_offset();
+        let before = std::mem::replace(&mut self.follow_offset, offset);
+        if let Some(target) = self.follow.step(before, offset, new_lines, typed, triggers) {
+            self.backend.process_command(BackendCommand::Scroll(target as i32 - offset as i32));
+            self.follow_offset = target;
+        }
+
+        // Only a scrolled-back view counts the lines below it
+        if self.follow.unseen().is_none() {
+            self.follow_capture = None;
+        } else if self.follow_capture.is_none() {
+            self.follow_capture = Some(LineCapture::new(self.backend.grid()));
+        }
+    }
+
+    /// Scroll so grid line `line` is at the top, or to the bottom for screen lines
+    fn scroll_to_line(&mut self, line: i32) {
+        let target = (-line).max(0);
+        let offset = self.backend.grid().display_offset() as i32;
+        self.backend.process_command(BackendCommand::Scroll(target - offset));
+    }
+
+    /// The foreground job runs as root (only detected with `ui.highlight_root_panes`)
+    fn is_elevated(&self) -> bool {
+        self.pty_tracker.as_ref().is_some_and(|tracker| tracker.is_elevated())
+    }
+
+    /// A fullscreen app (vim, htop, ...) is using the alternate screen
+    fn is_alt_screen(&self) -> bool {
+        self.backend.mode().contains(TerminalMode::ALT_SCREEN)
+    }
+
+    /// Scroll the history by whole screens (positive is up)
+    fn scroll_pages(&mut self, pages: i32) {
+        let cell_height = self.backend.cell_height().max(1) as f32;
+        let height = self.committed_size.map_or(0.0, |size| size.y);
+        let lines = ((height / cell_height) as i32).max(1);
+        self.backend.process_command(BackendCommand::Scroll(pages * lines));
+    }
+
+    /// Size to give the terminal view this frame
+    ///
+    /// Returns the committed size and, if a resize is still held back, how
+    /// long until it should be checked again. Wrapped lines on the main
+    /// screen reflow on each resize, so narrowing and widening a pane gives
+    /// them back; holding back the sizes in between spares the shell a
+    /// redraw for every frame of a drag.
+    fn debounced_size(
+        &mut self,
+        target: egui::Vec2,
+        debounce: std::time::Duration,
+        drag_ended: bool,
+    ) -> (egui::Vec2, Option<std::time::Duration>) {
+        let now = std::time::Instant::now();
+
+        let Some(committed) = self.committed_size else {
+            // First layout: nothing to debounce
+            self.committed_size = Some(target);
+            return (target, None);
+        };
+
+        if self.pending_size.unwrap_or(committed) != target {
+            self.pending_size = Some(target);
+            self.last_size_change = now;
+        }
+
+        let Some(pending) = self.pending_size else {
+            return (committed, None);
+        };
+
+        let since_change = now.duration_since(self.last_size_change);
+        if crate::layout::should_commit_resize(since_change, debounce, drag_ended) {
+            self.committed_size = Some(pending);
+            self.pending_size = None;
+            (pending, None)
+        } else {
+            (committed, Some(debounce - since_change))
+        }
+    }
 }
 
 impl std::fmt::Debug for TerminalInstance {
@@ -101,17 +819,124 @@ impl std::fmt::Debug for TerminalInstance {
 
 /// Message types for async directory loading
 struct DirLoadRequest {
-    workspace_id: usize,
+    workspace_id: u64,
     path: PathBuf,
 }
 
 struct DirLoadResult {
-    workspace_id: usize,
+    /// [`Workspace::id`]
+    workspace_id: u64,
     entries: Vec<FileEntry>,
+    /// Selection put back with the project's open directories
+    selected: Option<usize>,
+    /// The root is a project root, whose tree state is kept
+    project_root: bool,
+    /// Deep scan used as the sidebar filter source
+    for_filter: bool,
+}
+
+/// Background thumbnail decode for the sidebar
+struct ThumbnailResult {
+    path: PathBuf,
+    modified: Option<std::time::SystemTime>,
+    result: Result<crate::thumbnails::ThumbnailImage, String>,
+}
+
+/// Sidebar image entry under the pointer
+struct ThumbnailHover {
+    /// Path as listed in the sidebar
+    path: PathBuf,
+    since: std::time::Instant,
+    /// Cache key (canonical path), once the dwell passed and it was requested
+    key: Option<PathBuf>,
+}
+
+/// Background file read for a file viewer pane
+struct FileLoadResult {
+    /// [`Workspace::id`]
+    workspace_id: u64,
+    pane_id: PaneId,
+    path: PathBuf,
+    mode: LoadMode,
+    result: Result<FileLoad, String>,
+}
+
+/// Sidebar filter state for a workspace
+///
+/// Filtering works on a separate deep scan so the regular tree (and its
+/// expansion state) is untouched and comes back when the filter is cleared.
+struct SidebarFilter {
+    query: String,
+    /// Entries the filter runs over
+    source: Vec<FileEntry>,
+    /// Deep scan has been requested
+    scan_started: bool,
+    /// Deep scan in progress
+    scanning: bool,
+    /// Filtered entries shown in place of the tree
+    visible: Vec<FileEntry>,
+    /// Matched name characters per visible entry
+    highlights: Vec<Vec<usize>>,
+    /// Selected visible entry
+    selected: Option<usize>,
+    /// Focus the filter box on the next frame
+    focus_pending: bool,
+}
+
+impl SidebarFilter {
+    fn new(source: Vec<FileEntry>) -> Self {
+        let mut filter = Self {
+            query: String::new(),
+            source,
+            scan_started: false,
+            scanning: false,
+            visible: Vec::new(),
+            highlights: Vec::new(),
+            selected: None,
+            focus_pending: true,
+        };
+        filter.refresh();
+        filter
+    }
+
+    /// Recompute visible entries from the source and query
+    fn refresh(&mut self) {
+        let matches = crate::ui::filter_entries(&self.source, &self.query);
+        let filtering = !self.query.trim().is_empty();
+
+        self.visible.clear();
+        self.highlights.clear();
+        for m in matches {
+            let mut entry = self.source[m.index].clone();
+            // Show the path to every match
+            if filtering && entry.is_dir {
+                entry.is_expanded = true;
+            }
+            self.visible.push(entry);
+            self.highlights.push(m.positions);
+        }
+
+        // Fix tree connectors now that siblings may be hidden
+        crate::ui::update_tree_flags(&mut self.visible);
+
+        self.selected = None;
+    }
+
+    /// First matching file, if any
+    fn first_file_match(&self) -> Option<&FileEntry> {
+        self.visible
+            .iter()
+            .zip(&self.highlights)
+            .find(|(entry, positions)| !entry.is_dir && !positions.is_empty())
+            .map(|(entry, _)| entry)
+    }
 }
 
 /// Workspace containing panes in a binary split tree
 struct Workspace {
+    /// Stays the same as tabs open, close and move; background results find
+    /// their tab by it
+    id: u64,
     name: String,
     root: LayoutNode<TabContent>,
     focused_pane: PaneId,
@@ -122,80 +947,71 @@ struct Workspace {
     selected_sidebar_entry: Option<usize>,
     /// Current sidebar root path
     sidebar_root: PathBuf,
+    /// Active sidebar filter
+    sidebar_filter: Option<SidebarFilter>,
+    /// Pinned output notes floating over the panes
+    notes: Vec<OutputNote>,
+    /// Rate limits sidebar root switches while panes change directory
+    root_governor: RootSwitchGovernor,
+    /// Project root whose sidebar expansion is saved across sessions
+    tracked_tree_root: Option<PathBuf>,
+    /// Earlier and later sidebar roots, for the sidebar's back/forward buttons
+    root_history: NavHistory<PathBuf>,
+    /// Expansion or selection changed since the tree state was saved
+    tree_state_dirty: bool,
+    /// The next load comes from Expand All and opens every directory
+    expand_all_pending: bool,
+    /// Dotfiles and ignored entries shown in the sidebar (not persisted)
+    tree_visibility: TreeVisibility,
+    /// Progress of applying git statuses to `sidebar_entries`
+    git_sync: GitStatusSync,
+    /// File viewer pane that shows the sidebar's selected file
+    follow_pane: Option<PaneId>,
+    /// Selection waiting to load into `follow_pane`, and when it's due
+    follow_pending: Option<(PathBuf, std::time::Instant)>,
+    /// Tints and other per-pane settings, following each pane's ID
+    pane_meta: HashMap<PaneId, PaneMeta>,
+    /// This is the tab drawn; changes save or restore its panes' scroll positions
+    shown: bool,
+    /// File viewers go back to their `scroll_offset` when next drawn
+    restore_scroll: bool,
+    /// Drawn as a compact chip, ahead of every unpinned tab
+    pinned_tab: bool,
+    /// Terminal focused last, which new panes inherit a directory from
+    /// while a file viewer is focused
+    last_terminal: Option<u64>,
+    /// Terminals not started yet; the root holds an empty pane until then
+    dormant: Option<crate::dormant::DormantLayout>,
 }
 
-/// Transform a LayoutNode by splitting a target leaf
-fn split_node<T>(
-    node: LayoutNode<T>,
-    target_id: PaneId,
-    direction: SplitDirection,
-    new_pane_id: PaneId,
-    new_content: Option<T>,
-) -> (LayoutNode<T>, Option<T>) {
-    match node {
-        LayoutNode::Leaf { id, content } if id == target_id => {
-            // Found the target - split it, consume new_content
-            let new_content = new_content.expect("new_content should be available when target is found");
-            (LayoutNode::Split {
-                direction,
-                ratio: DEFAULT_SPLIT_RATIO,
-                first: Box::new(LayoutNode::Leaf { id, content }),
-                second: Box::new(LayoutNode::Leaf { id: new_pane_id, content: new_content }),
-            }, None)
-        }
-        LayoutNode::Leaf { id, content } => {
-            // Not the target, return unchanged with content passed through
-            (LayoutNode::Leaf { id, content }, new_content)
-        }
-        LayoutNode::Split { direction: dir, ratio, first, second } => {
-            // Recurse into first child
-            let (new_first, remaining) = split_node(*first, target_id, direction, new_pane_id, new_content);
-            // Recurse into second child with whatever content is remaining
-            let (new_second, remaining) = split_node(*second, target_id, direction, new_pane_id, remaining);
-            (LayoutNode::Split {
-                direction: dir,
-                ratio,
-                first: Box::new(new_first),
-                second: Box::new(new_second),
-            }, remaining)
-        }
-    }
-}
-
-/// Remove a pane from the tree, promoting its sibling
-fn close_node<T>(node: LayoutNode<T>, target_id: PaneId) -> Option<LayoutNode<T>> {
-    match node {
-        LayoutNode::Leaf { id, .. } if id == target_id => None,
-        LayoutNode::Leaf { id, content } => Some(LayoutNode::Leaf { id, content }),
-        LayoutNode::Split { direction, ratio, first, second } => {
-            // Check if either direct child is the target
-            if let LayoutNode::Leaf { id, .. } = first.as_ref() {
-                if *id == target_id {
-                    return Some(*second);
-                }
-            }
-            if let LayoutNode::Leaf { id, .. } = second.as_ref() {
-                if *id == target_id {
-                    return Some(*first);
-                }
-            }
+/// Source of [`Workspace`] IDs
+static NEXT_WORKSPACE_ID: AtomicU64 = AtomicU64::new(0);
 
-            // Recurse
-            let new_first = close_node(*first, target_id);
-            let new_second = close_node(*second, target_id);
+fn next_workspace_id() -> u64 {
+    NEXT_WORKSPACE_ID.fetch_add(1, Ordering::Relaxed)
+}
 
-            match (new_first, new_second) {
-                (Some(f), Some(s)) => Some(LayoutNode::Split {
-                    direction,
-                    ratio,
-                    first: Box::new(f),
-                    second: Box::new(s),
-                }),
-                (Some(f), None) => Some(f),
-                (None, Some(s)) => Some(s),
-                (None, None) => None,
-            }
-        }
+/// Turn a layout description into a pane tree
+///
+/// Pane IDs are the description's pane indices; each pane's content is
+/// taken out of `contents` when its leaf is built (indices are validated unique).
+fn build_project_node(
+    spec: &crate::layout::LayoutSpec,
+    contents: &mut [Option<TabContent>],
+) -> LayoutNode<TabContent> {
+    use crate::layout::LayoutSpec;
+
+    match spec {
+        LayoutSpec::Pane(index) => LayoutNode::Leaf {
+            id: PaneId(*index as u64),
+            content: contents[*index].take().expect("pane index used once"),
+        },
+        LayoutSpec::Split { direction, size, first, second } => LayoutNode::Split {
+            direction: *direction,
+            size: *size,
+            first: Box::new(build_project_node(first, contents)),
+            second: Box::new(build_project_node(second, contents)),
+        },
     }
 }
 
@@ -203,142 +1019,306 @@ impl Workspace {
     fn new(
         name: impl Into<String>,
         terminal_id: u64,
-        ctx: &Context,
         pty_sender: Sender<(u64, PtyEvent)>,
+        current_dir: PathBuf,
     ) -> anyhow::Result<Self> {
         let name = name.into();
-        let backend = create_terminal_backend(terminal_id, ctx, pty_sender)?;
+        let backend = create_terminal_backend(terminal_id, pty_sender, Some(current_dir.clone()))?;
         let pane_id = PaneId(0);
-        let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
         let project_root = crate::project::detect_project_root(&current_dir);
 
-        // Try to find and track the shell process
-        // The shell was just spawned, so we look for recently started shell processes
-        let pty_tracker = find_shell_pid().and_then(crate::pty_tracker::PtyTracker::new);
-
         let sidebar_root = project_root.as_ref().unwrap_or(&current_dir).clone();
 
         Ok(Self {
+            id: next_workspace_id(),
             name,
             root: LayoutNode::Leaf {
                 id: pane_id,
-                content: TabContent::Terminal(TerminalInstance {
+                content: TabContent::Terminal(TerminalInstance::new(
                     backend,
-                    id: terminal_id,
                     current_dir,
                     project_root,
-                    pty_tracker,
-                }),
+                )),
             },
             focused_pane: pane_id,
             next_pane_id: 1,
             sidebar_entries: Vec::new(),
             selected_sidebar_entry: None,
             sidebar_root,
+            sidebar_filter: None,
+            notes: Vec::new(),
+            root_governor: RootSwitchGovernor::default(),
+            tracked_tree_root: None,
+            root_history: NavHistory::new(ROOT_HISTORY_LEN),
+            tree_state_dirty: false,
+            expand_all_pending: false,
+            tree_visibility: TreeVisibility::default(),
+            git_sync: GitStatusSync::default(),
+            follow_pane: None,
+            follow_pending: None,
+            pane_meta: HashMap::new(),
+            shown: false,
+            restore_scroll: false,
+            pinned_tab: false,
+            last_terminal: None,
+            dormant: None,
         })
     }
 
-    /// Split focused pane in given direction
-    /// Existing content moves to first child (left/top)
-    /// New terminal goes to second child (right/bottom)
-    fn split_focused(
-        &mut self,
-        direction: SplitDirection,
-        terminal_id: u64,
-        ctx: &Context,
+    /// Workspace with a single pane showing `content`
+    fn with_content(name: impl Into<String>, content: TabContent, sidebar_root: PathBuf) -> Self {
+        let pane_id = PaneId(0);
+        Self {
+            id: next_workspace_id(),
+            name: name.into(),
+            root: LayoutNode::Leaf { id: pane_id, content },
+            focused_pane: pane_id,
+            next_pane_id: 1,
+            sidebar_entries: Vec::new(),
+            selected_sidebar_entry: None,
+            sidebar_root,
+            sidebar_filter: None,
+            notes: Vec::new(),
+            root_governor: RootSwitchGovernor::default(),
+            tracked_tree_root: None,
+            root_history: NavHistory::new(ROOT_HISTORY_LEN),
+            tree_state_dirty: false,
+            expand_all_pending: false,
+            tree_visibility: TreeVisibility::default(),
+            git_sync: GitStatusSync::default(),
+            follow_pane: None,
+            follow_pending: None,
+            pane_meta: HashMap::new(),
+            shown: false,
+            restore_scroll: false,
+            pinned_tab: false,
+            last_terminal: None,
+            dormant: None,
+        }
+    }
+
+    /// Build a workspace from a project layout file
+    ///
+    /// Terminal IDs are assigned from `first_terminal_id` in pane order.
+    fn from_project(
+        layout: &crate::project::ProjectLayout,
+        first_terminal_id: u64,
         pty_sender: Sender<(u64, PtyEvent)>,
-    ) -> anyhow::Result<()> {
-        let backend = create_terminal_backend(terminal_id, ctx, pty_sender)?;
-        let new_pane_id = PaneId(self.next_pane_id);
-        self.next_pane_id += 1;
-        let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
-        let project_root = crate::project::detect_project_root(&current_dir);
+    ) -> anyhow::Result<Self> {
+        let mut terminals = Vec::with_capacity(layout.panes.len());
+        for (index, pane) in layout.panes.iter().enumerate() {
+            let terminal_id = first_terminal_id + index as u64;
+            let dir = pane.working_directory.clone();
+            let backend = create_terminal_backend(terminal_id, pty_sender.clone(), Some(dir.clone()))?;
+            let project_root = crate::project::detect_project_root(&dir);
+            let mut terminal = TerminalInstance::new(backend, dir, project_root);
+            terminal.name = pane.name.clone();
+            terminals.push(Some(TabContent::Terminal(terminal)));
+        }
 
-        let target_id = self.focused_pane;
+        let pane_meta = layout.panes
+            .iter()
+            .enumerate()
+            .filter(|(_, pane)| pane.tint.is_some())
+            .map(|(index, pane)| (PaneId(index as u64), PaneMeta { tint: pane.tint }))
+            .collect();
 
-        // Try to find and track the shell process
-        let pty_tracker = find_shell_pid().and_then(crate::pty_tracker::PtyTracker::new);
+        let name = layout.root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "project".to_string());
 
-        let new_content = TabContent::Terminal(TerminalInstance {
-            backend,
-            id: terminal_id,
-            current_dir,
+        Ok(Self {
+            id: next_workspace_id(),
+            name,
+            root: build_project_node(&layout.spec, &mut terminals),
+            focused_pane: PaneId(layout.spec.pane_indices()[0] as u64),
+            next_pane_id: layout.panes.len() as u64,
+            sidebar_entries: Vec::new(),
+            selected_sidebar_entry: None,
+            sidebar_root: layout.root.clone(),
+            sidebar_filter: None,
+            notes: Vec::new(),
+            root_governor: RootSwitchGovernor::default(),
+            tracked_tree_root: None,
+            root_history: NavHistory::new(ROOT_HISTORY_LEN),
+            tree_state_dirty: false,
+            expand_all_pending: false,
+            tree_visibility: TreeVisibility::default(),
+            git_sync: GitStatusSync::default(),
+            follow_pane: None,
+            follow_pending: None,
+            pane_meta,
+            shown: false,
+            restore_scroll: false,
+            pinned_tab: false,
+            last_terminal: None,
+            dormant: None,
+        })
+    }
+
+    /// Dormant tab in `dir`, started by `VibeTermApp::wake_workspace`
+    fn dormant(name: impl Into<String>, dir: PathBuf, since: std::time::Instant) -> Self {
+        let sidebar_root = crate::project::detect_project_root(&dir).unwrap_or_else(|| dir.clone());
+        let mut workspace = Self::with_content(name, TabContent::Empty, sidebar_root);
+        workspace.dormant = Some(crate::dormant::DormantLayout::single(dir, since));
+        workspace
+    }
+
+    /// Note whether this is the tab drawn, saving or restoring its pane views when that changes
+    fn set_shown(&mut self, shown: bool) {
+        if self.shown == shown {
+            return;
+        }
+        self.shown = shown;
+        for (_, content) in self.root.collect_contents_mut() {
+            if let TabContent::Terminal(terminal) = content {
+                if shown {
+                    terminal.restore_view();
+                } else {
+                    terminal.save_view();
+                }
+            }
+        }
+        // File viewers note their offset whenever they're drawn
+        self.restore_scroll = shown;
+    }
+
+    /// Split focused pane in given direction
+    /// Existing content moves to first child (left/top)
+    /// New terminal goes to second child (right/bottom)
+    fn split_focused(
+        &mut self,
+        direction: SplitDirection,
+        terminal_id: u64,
+        pty_sender: Sender<(u64, PtyEvent)>,
+        current_dir: PathBuf,
+    ) -> anyhow::Result<()> {
+        let backend = create_terminal_backend(terminal_id, pty_sender, Some(current_dir.clone()))?;
+        let project_root = crate::project::detect_project_root(&current_dir);
+
+        let new_content = TabContent::Terminal(TerminalInstance::new(
+            backend,
+            current_dir,
             project_root,
-            pty_tracker,
-        });
+        ));
+        self.split_focused_with(direction, new_content);
+
+        Ok(())
+    }
+
+    /// Split the focused pane, putting `content` in the new (focused) pane
+    fn split_focused_with(&mut self, direction: SplitDirection, content: TabContent) {
+        let new_pane_id = PaneId(self.next_pane_id);
+        self.next_pane_id += 1;
+        let target_id = self.focused_pane;
 
         // Take ownership, transform, put back
-        let old_root = std::mem::replace(&mut self.root, LayoutNode::Leaf {
-            id: PaneId(u64::MAX),
-            content: TabContent::FileViewer { path: PathBuf::new(), content: String::new(), scroll_offset: 0.0 },
-        });
-        let (new_root, _) = split_node(old_root, target_id, direction, new_pane_id, Some(new_content));
+        let old_root = std::mem::replace(&mut self.root, placeholder_root());
+        let (new_root, _) = split_node(old_root, target_id, direction, new_pane_id, Some(content));
         self.root = new_root;
 
         // Focus the new pane
         self.focused_pane = new_pane_id;
-
-        Ok(())
     }
 
     /// Close a pane by ID, returns true if closed
     fn close_pane(&mut self, pane_id: PaneId) -> bool {
-        // Get all pane IDs to find next focus target
-        let mut pane_ids = Vec::new();
-        self.root.collect_pane_ids(&mut pane_ids);
+        let old_root = std::mem::replace(&mut self.root, placeholder_root());
+
+        match crate::layout::close_pane(old_root, pane_id) {
+            Ok((new_root, new_focus)) => {
+                self.root = new_root;
+                self.focused_pane = new_focus;
+                crate::layout::prune_pane_meta(&mut self.pane_meta, &self.root);
+                true
+            }
+            Err(unchanged) => {
+                // Last pane or unknown id
+                self.root = unchanged;
+                false
+            }
+        }
+    }
 
-        if pane_ids.len() <= 1 {
-            // Don't close the last pane
-            return false;
+    /// Move focus to next pane (DFS order)
+    fn focus_next(&mut self) {
+        if let Some(next) = crate::layout::cycle_focus(&self.pane_ids(), self.focused_pane, true) {
+            self.focused_pane = next;
         }
+    }
 
-        // Find index of closing pane
-        let closing_idx = match pane_ids.iter().position(|id| *id == pane_id) {
-            Some(idx) => idx,
-            None => return false,
-        };
+    /// Move focus to previous pane (DFS order)
+    fn focus_prev(&mut self) {
+        if let Some(prev) = crate::layout::cycle_focus(&self.pane_ids(), self.focused_pane, false) {
+            self.focused_pane = prev;
+        }
+    }
 
-        // Determine new focus (prefer previous, else next)
-        let new_focus = if closing_idx > 0 {
-            pane_ids[closing_idx - 1]
-        } else {
-            pane_ids[1]
-        };
+    /// Move the focused pane past its neighbor in a direction
+    fn move_focused_pane(&mut self, direction: PaneDirection) {
+        let old_root = std::mem::replace(&mut self.root, placeholder_root());
+        self.root = crate::layout::move_pane(old_root, self.focused_pane, direction);
+    }
 
-        // Close the pane
-        let old_root = std::mem::replace(&mut self.root, LayoutNode::Leaf {
-            id: PaneId(u64::MAX),
-            content: TabContent::FileViewer { path: PathBuf::new(), content: String::new(), scroll_offset: 0.0 },
-        });
+    /// Next (or previous) pane in DFS order, excluding the focused one
+    fn neighbor_pane(&self, forward: bool) -> Option<PaneId> {
+        let pane_ids = self.pane_ids();
+        if pane_ids.len() < 2 {
+            return None;
+        }
+        crate::layout::cycle_focus(&pane_ids, self.focused_pane, forward)
+    }
 
-        if let Some(new_root) = close_node(old_root, pane_id) {
-            self.root = new_root;
-            self.focused_pane = new_focus;
-            true
-        } else {
-            false
+    /// Selected text in a pane, if any
+    fn selection_text(&self, pane_id: PaneId) -> Option<String> {
+        match self.get_content(pane_id)? {
+            TabContent::Terminal(terminal) => {
+                let text = terminal.backend.selectable_content();
+                (!text.is_empty()).then_some(text)
+            }
+            // File viewer has no text selection yet
+            TabContent::FileViewer { .. } | TabContent::Empty | TabContent::Diff(_) | TabContent::Stats(_) | TabContent::ColorTest(_) => None,
         }
     }
 
-    /// Move focus to next pane (DFS order)
-    fn focus_next(&mut self) {
-        let mut pane_ids = Vec::new();
-        self.root.collect_pane_ids(&mut pane_ids);
+    /// Is a fullscreen app running in the pane's terminal?
+    fn is_alt_screen(&self, pane_id: PaneId) -> bool {
+        matches!(self.get_content(pane_id), Some(TabContent::Terminal(terminal)) if terminal.is_alt_screen())
+    }
 
-        if let Some(idx) = pane_ids.iter().position(|id| *id == self.focused_pane) {
-            let next_idx = (idx + 1) % pane_ids.len();
-            self.focused_pane = pane_ids[next_idx];
+    /// Is any terminal in the workspace running as root?
+    fn has_elevated_pane(&self) -> bool {
+        self.pane_ids().into_iter().any(|pane_id| {
+            matches!(self.get_content(pane_id), Some(TabContent::Terminal(terminal)) if terminal.is_elevated())
+        })
+    }
+
+    /// Sidebar entry at `idx` of the tree or, while filtering, the filtered view
+    fn sidebar_entry(&self, idx: usize) -> Option<&FileEntry> {
+        match &self.sidebar_filter {
+            Some(filter) => filter.visible.get(idx),
+            None => self.sidebar_entries.get(idx),
         }
     }
 
-    /// Move focus to previous pane (DFS order)
-    fn focus_prev(&mut self) {
-        let mut pane_ids = Vec::new();
-        self.root.collect_pane_ids(&mut pane_ids);
+    /// File selected in the sidebar (tree or filtered view)
+    fn selected_sidebar_file(&self) -> Option<PathBuf> {
+        let entry = match &self.sidebar_filter {
+            Some(filter) => filter.visible.get(filter.selected?),
+            None => self.sidebar_entries.get(self.selected_sidebar_entry?),
+        }?;
+        (!entry.is_dir).then(|| entry.path.clone())
+    }
 
-        if let Some(idx) = pane_ids.iter().position(|id| *id == self.focused_pane) {
-            let prev_idx = if idx == 0 { pane_ids.len() - 1 } else { idx - 1 };
-            self.focused_pane = pane_ids[prev_idx];
+    /// Write text to the terminal in a pane
+    fn write_to_pane(&mut self, pane_id: PaneId, text: &str) -> bool {
+        match self.get_content_mut(pane_id) {
+            Some(TabContent::Terminal(terminal)) => {
+                terminal.backend.process_command(BackendCommand::Write(text.as_bytes().to_vec()));
+                true
+            }
+            _ => false,
         }
     }
 
@@ -352,26 +1332,45 @@ impl Workspace {
         self.root.get_content(pane_id)
     }
 
+    /// Get terminal instance by terminal ID
+    fn get_terminal_mut(&mut self, terminal_id: u64) -> Option<&mut TerminalInstance> {
+        let pane_id = self.find_pane_by_terminal_id(terminal_id)?;
+        match self.get_content_mut(pane_id)? {
+            TabContent::Terminal(terminal) => Some(terminal),
+            _ => None,
+        }
+    }
+
+    /// Terminal ID of a terminal pane
+    fn terminal_id(&self, pane_id: PaneId) -> Option<u64> {
+        match self.get_content(pane_id)? {
+            TabContent::Terminal(terminal) => Some(terminal.id),
+            _ => None,
+        }
+    }
+
     /// Find pane by terminal ID
     fn find_pane_by_terminal_id(&self, terminal_id: u64) -> Option<PaneId> {
-        fn find_in_node(node: &LayoutNode<TabContent>, terminal_id: u64) -> Option<PaneId> {
-            match node {
-                LayoutNode::Leaf { id, content } => {
-                    if let TabContent::Terminal(t) = content {
-                        if t.id == terminal_id {
-                            return Some(*id);
-                        }
-                    }
-                    None
-                }
-                LayoutNode::Split { first, second, .. } => {
-                    find_in_node(first, terminal_id)
-                        .or_else(|| find_in_node(second, terminal_id))
-                }
-            }
+        self.root.find_pane(&|content| matches!(content, TabContent::Terminal(t) if t.id == terminal_id))
+    }
+
+    /// Remember the focused pane's terminal, if it is one
+    fn note_focused_terminal(&mut self) {
+        if let Some(id) = self.terminal_id(self.focused_pane) {
+            self.last_terminal = Some(id);
         }
+    }
 
-        find_in_node(&self.root, terminal_id)
+    /// Directory of the focused terminal, or of the last focused one
+    fn focused_terminal_dir(&self) -> Option<PathBuf> {
+        let pane_id = match self.terminal_id(self.focused_pane) {
+            Some(_) => self.focused_pane,
+            None => self.find_pane_by_terminal_id(self.last_terminal?)?,
+        };
+        match self.get_content(pane_id)? {
+            TabContent::Terminal(terminal) => Some(terminal.current_dir.clone()),
+            _ => None,
+        }
     }
 
     /// Count panes
@@ -385,12 +1384,216 @@ impl Workspace {
         self.root.collect_pane_ids(&mut ids);
         ids
     }
+
+    /// Short name of a pane: its terminal name or directory, or its file
+    fn pane_label(&self, pane_id: PaneId) -> String {
+        match self.get_content(pane_id) {
+            Some(TabContent::Terminal(terminal)) => {
+                terminal.name.clone().unwrap_or_else(|| dir_label(&terminal.current_dir))
+            }
+            Some(TabContent::FileViewer { path, .. }) => dir_label(path),
+            Some(TabContent::Empty) => "empty".to_string(),
+            Some(TabContent::Diff(view)) => view.label(),
+            Some(TabContent::Stats(_)) => "usage stats".to_string(),
+            Some(TabContent::ColorTest(_)) => "color test".to_string(),
+            None => String::new(),
+        }
+    }
+
+    /// Progress of the workspace's terminals, taken together
+    fn progress(&self) -> Option<crate::progress::Progress> {
+        let jobs = self.pane_ids().into_iter().filter_map(|pane_id| match self.get_content(pane_id) {
+            Some(TabContent::Terminal(terminal)) => terminal.progress.map(|status| status.progress),
+            _ => None,
+        });
+        crate::progress::combine(jobs)
+    }
+
+    /// Latest output of any terminal in the workspace
+    fn last_output(&self) -> Option<std::time::Instant> {
+        latest_output(&self.root)
+    }
+
+    /// Hover list of each terminal and how long ago it printed output
+    fn show_activity(&self, ui: &mut egui::Ui, now: std::time::Instant, theme: &RuntimeTheme) {
+        egui::Grid::new("tab_activity").num_columns(2).spacing([12.0, 2.0]).show(ui, |ui| {
+            for pane_id in self.pane_ids() {
+                let Some(TabContent::Terminal(terminal)) = self.get_content(pane_id) else {
+                    continue;
+                };
+                ui.label(egui::RichText::new(self.pane_label(pane_id)).font(theme::ui_font(11.0)).color(theme.text));
+                let age = match terminal.last_output {
+                    Some(at) => format!("last output {}", crate::ui::activity_age(now.saturating_duration_since(at))),
+                    None => "no output yet".to_string(),
+                };
+                ui.label(egui::RichText::new(age).font(theme::ui_font(11.0)).color(theme.text_dim));
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Tint of a pane, if it has one
+    fn pane_tint(&self, pane_id: PaneId) -> Option<egui::Color32> {
+        self.pane_meta.get(&pane_id).and_then(|meta| meta.tint)
+    }
+
+    /// Tint a pane, or clear its tint with `None`
+    fn set_pane_tint(&mut self, pane_id: PaneId, tint: Option<egui::Color32>) {
+        self.pane_meta.entry(pane_id).or_default().tint = tint;
+        crate::layout::prune_pane_meta(&mut self.pane_meta, &self.root);
+    }
+}
+
+/// Command being sent to several terminals, one at a time
+struct RunAllJob {
+    command: String,
+    /// Terminals still to receive the command (ID, label)
+    pending: std::collections::VecDeque<(u64, String)>,
+    /// When the next terminal is due
+    next_at: std::time::Instant,
+    sent: usize,
+    /// Terminals closed before their turn
+    closed: usize,
+    /// Read-only panes left out
+    skipped: usize,
+}
+
+/// Minimum time between two session log captures
+const LOG_CAPTURE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Minimum time between two diagnostics scans of a terminal
+const DIAGNOSTICS_SCAN_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Delay before a following pane loads the sidebar selection, so stepping
+/// through the tree doesn't read every file passed
+const FOLLOW_SELECTION_DELAY: std::time::Duration = std::time::Duration::from_millis(150);
+
+/// Opacity of a pane's tint wash, low enough to keep text readable
+const PANE_TINT_OPACITY: f32 = 0.08;
+
+/// Widths the sidebar can be dragged to (px), as in preferences
+const SIDEBAR_WIDTH_RANGE: std::ops::RangeInclusive<f32> = 150.0..=400.0;
+
+/// Sidebar roots remembered per workspace for going back
+const ROOT_HISTORY_LEN: usize = 20;
+
+/// Delay before sidebar expansion changes are written to disk
+const TREE_STATE_SAVE_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Time between two checks of config.toml for outside edits
+const CONFIG_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How often the session file is saved while running, so a crash loses little
+const SESSION_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Program that opens a path in the system file manager
+#[cfg(target_os = "macos")]
+const SYSTEM_OPEN: &str = "open";
+
+/// Program that opens a path in the system file manager
+#[cfg(not(target_os = "macos"))]
+const SYSTEM_OPEN: &str = "xdg-open";
+
+/// How long a toast stays visible
+const TOAST_DURATION: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// How long a toast with a button stays visible
+const ACTION_TOAST_DURATION: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// A shell failing sooner than this after starting is restarted
+const QUICK_EXIT_WINDOW: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Quick exits in a row that are restarted before showing the error panel
+const MAX_SHELL_RESTARTS: u32 = 2;
+
+/// Width of a file viewer's blame column
+const BLAME_GUTTER_WIDTH: f32 = 110.0;
+
+/// Longest preview of a marked line
+const MARK_PREVIEW_CHARS: usize = 80;
+
+/// Width of the gutter tick on marked lines
+const MARK_TICK_WIDTH: f32 = 3.0;
+
+/// Width of the marker on rows a long line wrapped onto
+const WRAP_MARKER_WIDTH: f32 = 1.5;
+
+/// Frame interval while a tab's progress pulses (indeterminate)
+const PROGRESS_PULSE_FRAME: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// How long a delete can be undone from its toast
+const UNDO_TOAST_DURATION: std::time::Duration = std::time::Duration::from_secs(8);
+
+/// Button offered on a toast
+#[derive(Debug, Clone)]
+enum ToastAction {
+    /// Open the `.vibeterm.toml` layout of a project root
+    OpenProjectLayout(PathBuf),
+    /// Restore items moved to the trash
+    UndoDelete(Vec<crate::trash::Trashed>),
+}
+
+impl ToastAction {
+    fn label(&self) -> &'static str {
+        match self {
+            ToastAction::OpenProjectLayout(_) => "Open Project Layout",
+            ToastAction::UndoDelete(_) => "Undo",
+        }
+    }
+
+    fn duration(&self) -> std::time::Duration {
+        match self {
+            ToastAction::OpenProjectLayout(_) => ACTION_TOAST_DURATION,
+            ToastAction::UndoDelete(_) => UNDO_TOAST_DURATION,
+        }
+    }
+}
+
+/// Scrollback mark waiting for its label
+struct MarkPrompt {
+    terminal_id: u64,
+    /// Absolute line index from `ScrollbackMarks::position`
+    position: i64,
+    preview: String,
+    input: String,
+}
+
+/// Short message shown above the status bar
+struct Toast {
+    message: String,
+    posted: std::time::Instant,
+    action: Option<ToastAction>,
+}
+
+impl Toast {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            posted: std::time::Instant::now(),
+            action: None,
+        }
+    }
+
+    fn with_action(message: impl Into<String>, action: ToastAction) -> Self {
+        let mut toast = Self::new(message);
+        toast.action = Some(action);
+        toast
+    }
+}
+
+impl Drop for Toast {
+    /// Undo is gone (expired, replaced or quit), so are the backups it needed
+    fn drop(&mut self) {
+        if let Some(ToastAction::UndoDelete(items)) = &self.action {
+            items.iter().for_each(crate::trash::Trashed::discard_backup);
+        }
+    }
 }
 
 /// Main application state
 pub struct VibeTermApp {
-    /// Configuration
-    config: Config,
+    /// Configuration, saved shortly after it changes
+    config: ConfigStore,
     /// Runtime theme (parsed colors)
     theme: RuntimeTheme,
     /// All workspaces (tabs)
@@ -399,8 +1602,18 @@ pub struct VibeTermApp {
     active_workspace: usize,
     /// Terminal ID counter
     next_terminal_id: u64,
-    /// Sidebar visibility
+    /// Sidebar visibility (user's toggle)
     sidebar_visible: bool,
+    /// Pinned sidebar shown only while the project search is open
+    search_reveals_sidebar: bool,
+    /// Overlay sidebar of the auto-hide and hidden sidebar modes
+    sidebar_reveal: crate::sidebar_reveal::SidebarReveal,
+    /// Chrome shrunk or hidden for a small window
+    responsive: crate::responsive::Responsive,
+    /// Area the panes were last laid out in
+    pane_area: egui::Rect,
+    /// Chrome hidden for presenting (per window, not saved)
+    presentation: PresentationMode,
     /// Project root path (deprecated - now per workspace)
     project_root: Option<PathBuf>,
     /// PTY event channel
@@ -408,47 +1621,268 @@ pub struct VibeTermApp {
     pty_receiver: Receiver<(u64, PtyEvent)>,
     /// egui context for creating new terminals
     ctx: Context,
-    /// Divider being dragged (workspace_idx, divider_idx)
-    dragging_divider: Option<(usize, usize)>,
+    /// Divider being dragged
+    dragging_divider: Option<DividerDrag>,
     /// Pane being dragged for repositioning
     dragging_pane: Option<PaneDragState>,
     /// Tab being dragged
     dragging_tab: Option<TabDragState>,
+    /// Tab bar area and tab rects from the last frame
+    tab_bar_rect: egui::Rect,
+    tab_rects: Vec<(usize, egui::Rect)>,
     /// Preferences window
     preferences_window: crate::ui::PreferencesWindow,
-    /// IME is currently composing (preedit active)
-    ime_composing: bool,
-    /// Cached terminal theme (regenerated when config changes)
-    cached_terminal_theme: egui_term::TerminalTheme,
+    /// What works on this machine; everything until probed in `new`
+    capabilities: crate::capabilities::Capabilities,
+    /// Routes input events and tracks IME composition
+    input_router: InputRouter,
+    /// Cached terminal theme (swapped when the theme changes)
+    cached_terminal_theme: Arc<egui_term::TerminalTheme>,
+    /// Faded terminal theme for unfocused panes
+    cached_dim_terminal_theme: Arc<egui_term::TerminalTheme>,
+    /// A Preferences theme preset is shown instead of the config's theme
+    theme_preview: bool,
     /// Channel for async directory loading
     dir_load_tx: tokio::sync::mpsc::UnboundedSender<DirLoadResult>,
     dir_load_rx: tokio::sync::mpsc::UnboundedReceiver<DirLoadResult>,
+    file_load_tx: tokio::sync::mpsc::UnboundedSender<FileLoadResult>,
+    file_load_rx: tokio::sync::mpsc::UnboundedReceiver<FileLoadResult>,
+    /// Channel for background `git fetch` results (repo root, outcome)
+    git_fetch_tx: tokio::sync::mpsc::UnboundedSender<(PathBuf, Result<(), String>)>,
+    git_fetch_rx: tokio::sync::mpsc::UnboundedReceiver<(PathBuf, Result<(), String>)>,
+    /// Project search progress (search ID, message)
+    search_tx: tokio::sync::mpsc::UnboundedSender<(u64, crate::project_search::SearchMessage)>,
+    search_rx: tokio::sync::mpsc::UnboundedReceiver<(u64, crate::project_search::SearchMessage)>,
+    /// ID of the latest project search; older results are dropped
+    search_id: u64,
+    /// Stops the latest project search
+    search_cancel: Arc<std::sync::atomic::AtomicBool>,
+    /// "Search in Project" panel, shown in place of the sidebar tree
+    search_panel: SearchPanel,
     /// Loading state per workspace
-    loading_dirs: HashMap<usize, bool>,
+    loading_dirs: HashMap<u64, bool>,
     /// Command palette
     command_palette: CommandPalette,
+    /// Snippet palette
+    snippet_palette: SnippetPalette,
+    /// Text copied inside VibeTerm
+    clipboard_history: crate::clipboard_history::ClipboardHistory,
+    /// Clipboard history palette
+    clipboard_history_palette: crate::ui::ClipboardHistoryPalette,
+    /// Commands submitted at shell prompts
+    command_history: crate::command_history::CommandHistory,
+    /// Local usage stats, when `advanced.local_stats` is on
+    stats: Option<crate::stats::StatsRecorder>,
+    /// History peek overlay
+    history_peek: crate::ui::HistoryPeek,
+    /// "Connect to Host…" palette
+    ssh_palette: crate::ui::SshPalette,
+    /// Bells and notices, and Do Not Disturb
+    notifier: crate::notifier::Notifier,
+    /// "Run in All Panes" dialog
+    run_all_dialog: RunAllDialog,
+    /// Component picker for importing a settings bundle
+    import_dialog: crate::ui::ImportDialog,
+    /// Per-glyph font coverage report
+    font_diagnostics_dialog: crate::ui::FontDiagnosticsDialog,
+    /// Command being sent to several terminals
+    run_all_job: Option<RunAllJob>,
+    /// Short message shown above the status bar
+    toast: Option<Toast>,
+    /// Project roots whose layout file was already offered in a toast
+    offered_layouts: std::collections::HashSet<PathBuf>,
+    /// Pane context menu (pane, where it was opened)
+    pane_menu: Option<(PaneId, egui::Pos2)>,
+    /// Terminal grid line the pane menu was opened on
+    pane_menu_line: Option<i32>,
+    /// Terminal pane waiting for a file path to convert to a viewer
+    file_viewer_prompt: Option<(PaneId, String)>,
+    /// Scrollback mark waiting for its label
+    mark_prompt: Option<MarkPrompt>,
+    /// "Jump to Mark" list of the focused pane
+    mark_list: crate::ui::MarkList,
+    /// Sidebar item waiting for confirmation to be deleted for good
+    delete_confirm: Option<PathBuf>,
+    /// First press of its Delete button, waiting for a second or held one
+    delete_confirmation: crate::ui::ConfirmState<PathBuf>,
+    /// Project layout waiting for command consent
+    consent_prompt: Option<crate::project::ProjectLayout>,
+    /// Projects whose layout commands may run
+    project_consent: crate::project::ProjectConsent,
+    /// Directory hooks that fired once per terminal and directory
+    dir_hook_tracker: crate::dir_hooks::DirHookTracker,
+    /// Directory hook commands waiting for consent, and the terminals
+    /// they're for (the first one is asked about)
+    dir_hook_prompts: std::collections::VecDeque<(u64, crate::config::DirHook)>,
+    /// Directory hook commands that may be typed
+    dir_hook_consent: crate::dir_hooks::DirHookConsent,
+    /// What quitting would interrupt, while the quit dialog is open
+    quit_prompt: Option<crate::quit::QuitSummary>,
+    /// The user confirmed quitting; the next close request goes through
+    quit_confirmed: bool,
+    /// When dormant tabs get started if the app stays idle
+    dormant_wake_at: Option<std::time::Instant>,
+    /// Session log file writer (started on first use)
+    session_log_writer: Option<SessionLogWriter>,
+    /// Terminals with an open session log
+    logging_terminals: std::collections::HashSet<u64>,
+    /// Last session log capture
+    last_log_capture: std::time::Instant,
+    /// Last diagnostics scan
+    last_diagnostics_scan: std::time::Instant,
+    /// Monospace font has Nerd Font icons (probed on the first frame)
+    nerd_glyphs: Option<bool>,
     /// Tokio runtime for async operations
     tokio_runtime: Arc<Runtime>,
     /// Context manager for filesystem and git tracking
     context_manager: crate::context::ContextManager,
+    /// Channel for update check results (newer version string)
+    update_rx: tokio::sync::mpsc::UnboundedReceiver<String>,
+    /// Newer release version, if one was found
+    available_update: Option<String>,
+    /// Sidebar has keyboard focus (terminals don't grab it back)
+    sidebar_focused: bool,
+    /// Pane number overlay waiting for a pick
+    pane_pick: Option<PanePick>,
+    /// Channel for background pane diffs
+    diff_tx: tokio::sync::mpsc::UnboundedSender<DiffResult>,
+    diff_rx: tokio::sync::mpsc::UnboundedReceiver<DiffResult>,
+    /// ID of the next pane diff request
+    next_diff_request: u64,
+    /// Channel for usage stats summarized in the background
+    stats_tx: tokio::sync::mpsc::UnboundedSender<crate::stats::StatsSummary>,
+    stats_rx: tokio::sync::mpsc::UnboundedReceiver<crate::stats::StatsSummary>,
+    /// Channel for background git blames of file viewers
+    blame_tx: tokio::sync::mpsc::UnboundedSender<BlameResult>,
+    blame_rx: tokio::sync::mpsc::UnboundedReceiver<BlameResult>,
+    /// ID of the next blame request
+    next_blame_request: u64,
+    /// Pastes waiting for the clipboard, and the channel their contents come back on
+    pastes: crate::clipboard_paste::PasteQueue,
+    paste_tx: tokio::sync::mpsc::UnboundedSender<crate::clipboard_paste::PasteReady>,
+    paste_rx: tokio::sync::mpsc::UnboundedReceiver<crate::clipboard_paste::PasteReady>,
+    /// Sidebar hover thumbnails, and the channel decoded ones come back on
+    thumbnails: ThumbnailCache<egui::TextureHandle>,
+    thumbnail_tx: tokio::sync::mpsc::UnboundedSender<ThumbnailResult>,
+    thumbnail_rx: tokio::sync::mpsc::UnboundedReceiver<ThumbnailResult>,
+    thumbnail_hover: Option<ThumbnailHover>,
+    /// Follow-up repaint to catch the PTY echo of a keystroke
+    echo_repaint_at: Option<std::time::Instant>,
+    /// When dirty sidebar tree states are due to be saved
+    tree_state_save_at: Option<std::time::Instant>,
+    /// config.toml modification time last seen
+    config_modified: Option<std::time::SystemTime>,
+    /// When config.toml is next checked for outside edits
+    config_check_at: std::time::Instant,
+    /// Next time the session file is brought up to date
+    session_save_at: std::time::Instant,
+    /// Session last written, to skip unchanged saves
+    saved_session: Option<crate::session::Session>,
+    /// Generation of the last save queued, and of the last written; a
+    /// background write that finishes late never replaces a newer one
+    session_generation: u64,
+    session_written: Arc<std::sync::Mutex<u64>>,
+    /// The same for config.toml, which flushes write in the foreground
+    config_generation: u64,
+    config_written: Arc<std::sync::Mutex<u64>>,
+    /// Progress shown on the Dock icon
+    dock_progress: Option<crate::progress::Progress>,
+    /// Keypress → glyph latency measurement for the overlay
+    latency_probe: crate::latency::LatencyProbe,
+    /// Output rate and firehose state of each terminal
+    throughput: crate::throughput::ThroughputTracker,
+    /// Output note ID counter
+    next_note_id: u64,
+    /// An output note's title is being edited
+    note_editing: bool,
+    /// Shell PIDs already attached to a PtyTracker
+    claimed_pids: crate::pty_tracker::ClaimedPids,
+    /// Channel for async shell discovery results (terminal_id, shell_pid)
+    shell_pid_tx: tokio::sync::mpsc::UnboundedSender<(u64, u32)>,
+    shell_pid_rx: tokio::sync::mpsc::UnboundedReceiver<(u64, u32)>,
+    /// Channel for background diagnostics scans (terminal_id, command, diagnostics)
+    diagnostics_tx: tokio::sync::mpsc::UnboundedSender<(u64, u64, Vec<Diagnostic>)>,
+    diagnostics_rx: tokio::sync::mpsc::UnboundedReceiver<(u64, u64, Vec<Diagnostic>)>,
+    /// Requests from later launches (single-instance mode)
+    ipc_server: Option<crate::ipc::IpcServer>,
+    /// Paths from the OS waiting to be opened
+    open_requests: Vec<PathBuf>,
+    /// Terminal shown read-only in the mirror window
+    mirror: Option<u64>,
+    /// Preferences changed the sidebar width; the panel takes it next frame
+    sidebar_width_changed: bool,
+    /// The sidebar's edge is being dragged
+    sidebar_resizing: bool,
+    /// How long startup took, complete after the first frame
+    startup: StartupReport,
+    startup_report_dialog: crate::ui::StartupReportDialog,
+    /// CJK fallback font, once read
+    cjk_font: Option<Vec<u8>>,
+    /// Reads the CJK fallback font off the UI thread at startup
+    cjk_loader: Option<crate::theme::CjkFontLoader>,
 }
 
 impl VibeTermApp {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        // Load configuration
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        ipc_server: Option<crate::ipc::IpcServer>,
+        mut startup: StartupReport,
+    ) -> Self {
         let config = Config::load();
-        let theme = RuntimeTheme::from(&config.theme);
-        let cached_terminal_theme = theme::get_terminal_theme(&config);
-
-        // Apply VibeTerm theme
-        crate::theme::apply_theme(&cc.egui_ctx, &theme);
-        crate::theme::configure_fonts(&cc.egui_ctx);
+        startup.lap("config");
+        let mut app = Self::with_config(&cc.egui_ctx, config, ipc_server, startup);
+        let watcher = app.context_manager.watcher_state();
+        app.capabilities = crate::capabilities::Capabilities::probe(watcher.last_error.as_deref(), cc.gl.is_some());
+        app.preferences_window.set_capabilities(app.capabilities.clone());
+        app.startup.lap("capabilities");
+        app
+    }
 
-        // Create PTY event channel
+    /// App drawing into `ctx` with the given configuration, timing its
+    /// phases into `startup`
+    fn with_config(
+        ctx: &Context,
+        config: Config,
+        ipc_server: Option<crate::ipc::IpcServer>,
+        mut startup: StartupReport,
+    ) -> Self {
+        let theme = RuntimeTheme::from(&config.theme);
+        let cached_terminal_theme = Arc::new(theme::get_terminal_theme(&config));
+        let cached_dim_terminal_theme = Arc::new(theme::get_dim_terminal_theme(&config));
+
+        // Apply VibeTerm theme; the CJK font is added once it has been read
+        crate::theme::apply_theme(ctx, &theme);
+        crate::theme::configure_fonts(ctx, &config.font, None);
+        let cjk_loader = (config.font.cjk_fallback && !crate::terminal_backend::headless())
+            .then(|| crate::theme::CjkFontLoader::start(crate::theme::CJK_FONT_PATHS));
+        startup.lap("theme and fonts");
+
+        // Create PTY event channel; shells' events pass through the repaint
+        // gate, which wakes the UI less often for firehose output
+        let throughput = crate::throughput::ThroughputTracker::new();
         let (pty_sender, pty_receiver) = std::sync::mpsc::channel();
+        let (pty_sender, pty_receiver) = if crate::terminal_backend::headless() {
+            (pty_sender, pty_receiver)
+        } else {
+            let (gate_sender, gated) = std::sync::mpsc::channel();
+            throughput.repaint_gate().forward(ctx.clone(), gated, pty_sender);
+            (gate_sender, pty_receiver)
+        };
 
         // Create async directory loading channel
         let (dir_load_tx, dir_load_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (file_load_tx, file_load_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (diff_tx, diff_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (stats_tx, stats_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (blame_tx, blame_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (paste_tx, paste_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (thumbnail_tx, thumbnail_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (git_fetch_tx, git_fetch_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (search_tx, search_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        // Create async shell discovery channel
+        let (shell_pid_tx, shell_pid_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (diagnostics_tx, diagnostics_rx) = tokio::sync::mpsc::unbounded_channel();
 
         // Create tokio runtime for async operations
         let tokio_runtime = Arc::new(
@@ -458,6 +1892,22 @@ impl VibeTermApp {
                 .expect("Failed to create tokio runtime")
         );
 
+        // Opt-in update check (at most once per day, cached between launches)
+        let (update_tx, update_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut available_update = None;
+        if config.advanced.check_for_updates {
+            let state = crate::update_check::UpdateState::load();
+            if crate::update_check::should_check_now(
+                state.last_checked,
+                crate::update_check::now_secs(),
+                crate::update_check::CHECK_INTERVAL,
+            ) {
+                crate::update_check::spawn_check(&tokio_runtime, update_tx);
+            } else {
+                available_update = state.available_update().map(|v| v.to_string());
+            }
+        }
+
         // Create context manager
         let mut context_manager = crate::context::ContextManager::new(config.context.clone());
 
@@ -466,41 +1916,187 @@ impl VibeTermApp {
             let _ = context_manager.set_active_directory(&cwd);
         }
 
-        // Create initial workspace
-        let workspace = Workspace::new("shell", 0, &cc.egui_ctx, pty_sender.clone())
-            .expect("Failed to create initial workspace");
+        startup.lap("context");
+
+        // A missing shell is replaced before the first terminal starts
+        let shell = crate::shell_path::configure(&config.terminal.shell);
+
+        // Last run's tabs, or the initial workspace (headless runs leave saved state alone)
+        let session = (config.ui.restore_session && !crate::terminal_backend::headless())
+            .then(|| crate::session::Session::load(&crate::session::session_path()))
+            .flatten();
+        let workspaces = match session {
+            Some(_) => Vec::new(),
+            None => vec![Workspace::new("shell", 0, pty_sender.clone(), launch_dir())
+                .expect("Failed to create initial workspace")],
+        };
 
         // Load sidebar entries from current directory
         let project_root = std::env::current_dir().ok();
 
         let mut app = Self {
-            config: config.clone(),
+            config: ConfigStore::new(config.clone(), Config::config_path()),
             theme,
-            workspaces: vec![workspace],
+            workspaces,
             active_workspace: 0,
             next_terminal_id: 1,
             sidebar_visible: true,
+            search_reveals_sidebar: false,
+            sidebar_reveal: Default::default(),
+            responsive: Default::default(),
+            pane_area: egui::Rect::NOTHING,
+            presentation: PresentationMode::default(),
             project_root,
             pty_sender,
             pty_receiver,
-            ctx: cc.egui_ctx.clone(),
+            ctx: ctx.clone(),
             dragging_divider: None,
             dragging_pane: None,
             dragging_tab: None,
+            tab_bar_rect: egui::Rect::NOTHING,
+            tab_rects: Vec::new(),
             preferences_window: crate::ui::PreferencesWindow::new(config.clone()),
-            ime_composing: false,
+            capabilities: crate::capabilities::Capabilities::all(),
+            input_router: InputRouter::new(),
             cached_terminal_theme,
+            cached_dim_terminal_theme,
+            theme_preview: false,
             dir_load_tx,
             dir_load_rx,
+            file_load_tx,
+            file_load_rx,
+            git_fetch_tx,
+            git_fetch_rx,
+            search_tx,
+            search_rx,
+            search_id: 0,
+            search_cancel: Arc::default(),
+            search_panel: SearchPanel::new(),
             loading_dirs: HashMap::new(),
             command_palette: CommandPalette::new(),
+            snippet_palette: SnippetPalette::new(),
+            clipboard_history: crate::clipboard_history::ClipboardHistory::new(&config.terminal.clipboard_history),
+            clipboard_history_palette: crate::ui::ClipboardHistoryPalette::new(),
+            command_history: crate::command_history::CommandHistory::new(),
+            stats: config.advanced.local_stats
+                .then(|| crate::stats::StatsRecorder::new(crate::stats::StatsRecorder::stats_path())),
+            history_peek: crate::ui::HistoryPeek::new(),
+            ssh_palette: crate::ui::SshPalette::new(),
+            notifier: crate::notifier::Notifier::new(&config.ui.dnd_schedule),
+            run_all_dialog: RunAllDialog::new(),
+            import_dialog: crate::ui::ImportDialog::new(),
+            font_diagnostics_dialog: crate::ui::FontDiagnosticsDialog::new(),
+            run_all_job: None,
+            toast: None,
+            offered_layouts: Default::default(),
+            pane_menu: None,
+            pane_menu_line: None,
+            file_viewer_prompt: None,
+            mark_prompt: None,
+            mark_list: crate::ui::MarkList::new(),
+            delete_confirm: None,
+            delete_confirmation: crate::ui::ConfirmState::new(),
+            consent_prompt: None,
+            project_consent: crate::project::ProjectConsent::load(),
+            dir_hook_tracker: crate::dir_hooks::DirHookTracker::new(),
+            dir_hook_prompts: Default::default(),
+            dir_hook_consent: crate::dir_hooks::DirHookConsent::load(),
+            quit_prompt: None,
+            quit_confirmed: false,
+            dormant_wake_at: None,
+            session_log_writer: None,
+            logging_terminals: Default::default(),
+            last_log_capture: std::time::Instant::now(),
+            last_diagnostics_scan: std::time::Instant::now(),
+            nerd_glyphs: None,
             tokio_runtime,
             context_manager,
+            update_rx,
+            available_update,
+            sidebar_focused: false,
+            pane_pick: None,
+            diff_tx,
+            diff_rx,
+            next_diff_request: 0,
+            stats_tx,
+            stats_rx,
+            blame_tx,
+            blame_rx,
+            next_blame_request: 0,
+            pastes: crate::clipboard_paste::PasteQueue::default(),
+            paste_tx,
+            paste_rx,
+            thumbnails: ThumbnailCache::new(crate::thumbnails::MAX_THUMBNAILS, crate::thumbnails::MAX_THUMBNAIL_BYTES),
+            thumbnail_tx,
+            thumbnail_rx,
+            thumbnail_hover: None,
+            echo_repaint_at: None,
+            tree_state_save_at: None,
+            config_modified: Config::modified(),
+            config_check_at: std::time::Instant::now() + CONFIG_CHECK_INTERVAL,
+            session_save_at: std::time::Instant::now() + SESSION_SAVE_INTERVAL,
+            saved_session: None,
+            session_generation: 0,
+            session_written: Arc::new(std::sync::Mutex::new(0)),
+            config_generation: 0,
+            config_written: Arc::new(std::sync::Mutex::new(0)),
+            dock_progress: None,
+            latency_probe: crate::latency::LatencyProbe::new(),
+            throughput,
+            next_note_id: 0,
+            note_editing: false,
+            claimed_pids: Default::default(),
+            shell_pid_tx,
+            shell_pid_rx,
+            diagnostics_tx,
+            diagnostics_rx,
+            ipc_server,
+            open_requests: Vec::new(),
+            mirror: None,
+            sidebar_width_changed: false,
+            sidebar_resizing: false,
+            startup,
+            startup_report_dialog: crate::ui::StartupReportDialog::new(),
+            cjk_font: None,
+            cjk_loader,
         };
 
-        // Trigger initial directory load for the first workspace
-        let initial_root = app.workspaces[0].sidebar_root.clone();
-        app.load_directory_async(0, initial_root);
+        if let Some(server) = &app.ipc_server {
+            server.attach(ctx);
+        }
+        crate::open_events::attach(ctx);
+        app.toast = shell_replaced_toast(&shell);
+
+        if let Some(session) = session {
+            app.restore_session(session);
+        } else {
+            // Start tracking the initial shell
+            app.discover_shell_pid(0);
+
+            // Trigger initial directory load for the first workspace
+            let initial_root = app.workspaces[0].sidebar_root.clone();
+            app.load_directory_async(0, initial_root);
+        }
+        app.startup.lap("terminals");
+
+        // Headless runs leave saved state alone
+        if !crate::terminal_backend::headless() {
+            app.tokio_runtime.spawn_blocking(|| {
+                let dir = crate::tree_state::state_dir();
+                let removed = crate::tree_state::prune(&dir, std::time::SystemTime::now(), crate::tree_state::MAX_AGE);
+                if removed > 0 {
+                    log::info!("Removed {} stale sidebar tree states", removed);
+                }
+            });
+            let paste_image = app.config.terminal.paste_image.clone();
+            app.tokio_runtime.spawn_blocking(move || {
+                let removed = crate::paste_image::cleanup(&paste_image, std::time::SystemTime::now());
+                if removed > 0 {
+                    log::info!("Removed {} old pasted images", removed);
+                }
+            });
+        }
+        app.startup.lap("background tasks");
 
         app
     }
@@ -517,111 +2113,479 @@ impl VibeTermApp {
 
     /// Get tab info for UI
     fn get_tabs(&self) -> Vec<TabInfo> {
+        let activity = self.config.ui.show_activity_strip;
         self.workspaces
             .iter()
-            .map(|ws| TabInfo::new(&ws.name))
+            .map(|ws| {
+                let tab = if ws.has_elevated_pane() {
+                    TabInfo::new(format!("⚠ {}", ws.name))
+                } else {
+                    TabInfo::new(&ws.name)
+                };
+                tab.last_output(if activity { ws.last_output() } else { None })
+                    .progress(ws.progress())
+                    .pinned(ws.pinned_tab)
+                    .dormant(ws.dormant.is_some())
+            })
             .collect()
     }
 
     /// Create a new workspace/tab with terminal
     fn create_new_tab(&mut self) {
+        self.create_new_tab_in(self.new_terminal_dir(), self.config.ui.new_tab_position);
+    }
+
+    /// Directory a new split or tab starts in (`terminal.new_terminal_dir`)
+    fn new_terminal_dir(&self) -> PathBuf {
+        let home = || dirs::home_dir().unwrap_or_else(launch_dir);
+        match self.config.terminal.new_terminal_dir {
+            NewTerminalDir::Inherit => match self.current_workspace().focused_terminal_dir() {
+                Some(dir) if dir.is_dir() => dir,
+                Some(_) => home(),
+                None => launch_dir(),
+            },
+            NewTerminalDir::Home => home(),
+            NewTerminalDir::Launch => launch_dir(),
+        }
+    }
+
+    /// Create a new workspace/tab with a terminal in `dir`
+    fn create_new_tab_in(&mut self, dir: PathBuf, position: NewTabPosition) {
         let id = self.next_terminal_id;
         self.next_terminal_id += 1;
 
         let name = format!("shell-{}", self.workspaces.len() + 1);
-        if let Ok(workspace) = Workspace::new(name, id, &self.ctx, self.pty_sender.clone()) {
-            self.workspaces.push(workspace);
-            self.active_workspace = self.workspaces.len() - 1;
+        if let Ok(workspace) = Workspace::new(name, id, self.pty_sender.clone(), dir) {
+            self.add_workspace(workspace, position);
+            self.discover_shell_pid(id);
         }
     }
 
-    /// Create a new workspace/tab with file
-    fn create_file_tab(&mut self, path: PathBuf) {
-        let name = path.file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| "File".to_string());
-
-        let content = std::fs::read_to_string(&path).unwrap_or_else(|e| format!("Error: {}", e));
-        let pane_id = PaneId(0);
+    /// Reopen the tabs of a saved session, with new shells in their directories
+    ///
+    /// Only the active tab starts at once; the others come back dormant, like
+    /// folders opened in bulk. Directories deleted since fall back to their
+    /// closest parent, files to an empty pane. If no tab comes back, the usual
+    /// single tab opens.
+    fn restore_session(&mut self, session: crate::session::Session) {
+        use crate::dormant::{DormantLayout, DormantPane};
+        use crate::session::{existing_dir, SerializableLayout};
+
+        let fallback_dir = launch_dir();
+        let now = std::time::Instant::now();
+        for tab in session.tabs.into_iter().filter(|tab| !tab.layout.is_blank()) {
+            let (spec, panes) = tab.layout.to_spec();
+            // Pane IDs of a woken tab are its pane indices
+            let pane_meta: HashMap<PaneId, PaneMeta> = panes
+                .iter()
+                .enumerate()
+                .filter_map(|(index, pane)| Some((PaneId(index as u64), crate::config::hex_to_color32(pane.tint()?)?)))
+                .map(|(pane_id, tint)| (pane_id, PaneMeta { tint: Some(tint) }))
+                .collect();
+            let panes: Vec<DormantPane> = panes
+                .into_iter()
+                .map(|pane| match pane {
+                    SerializableLayout::Terminal { dir, name, .. } => DormantPane::Terminal {
+                        dir: existing_dir(&dir).unwrap_or_else(|| fallback_dir.clone()),
+                        name,
+                    },
+                    SerializableLayout::File { path, .. } if path.is_file() => DormantPane::File(path),
+                    _ => DormantPane::Empty,
+                })
+                .collect();
+            // Pane indices are in pane order, as `focused` is counted
+            let focused = if tab.focused < panes.len() { tab.focused } else { 0 };
+
+            let sidebar_root = existing_dir(&tab.sidebar_root).unwrap_or_else(|| fallback_dir.clone());
+            let mut workspace = Workspace::with_content(tab.name, TabContent::Empty, sidebar_root);
+            workspace.pinned_tab = tab.pinned;
+            workspace.pane_meta = pane_meta;
+            for text in tab.notes.into_iter().take(crate::ui::MAX_NOTES_PER_WORKSPACE) {
+                workspace.notes.push(OutputNote::new(self.next_note_id, text));
+                self.next_note_id += 1;
+            }
+            workspace.dormant = Some(DormantLayout { spec, panes, focused, since: now });
+            self.workspaces.push(workspace);
+        }
 
-        // Create a new workspace with a file viewer
-        let sidebar_root = path.parent().unwrap_or(std::path::Path::new("/")).to_path_buf();
-        let workspace = Workspace {
-            name,
-            root: LayoutNode::Leaf {
-                id: pane_id,
-                content: TabContent::FileViewer {
-                    path,
-                    content,
-                    scroll_offset: 0.0,
-                },
-            },
-            focused_pane: pane_id,
-            next_pane_id: 1,
-            sidebar_entries: Vec::new(),
-            selected_sidebar_entry: None,
-            sidebar_root,
-        };
+        if self.workspaces.is_empty() {
+            log::warn!("Nothing to restore from the saved session");
+            let id = self.next_terminal_id;
+            self.next_terminal_id += 1;
+            let workspace = Workspace::new("shell", id, self.pty_sender.clone(), launch_dir())
+                .expect("Failed to create initial workspace");
+            self.workspaces.push(workspace);
+            self.discover_shell_pid(id);
+        } else {
+            log::info!("Restored {} tabs from the last session", self.workspaces.len());
+        }
+        self.active_workspace = session.active.min(self.workspaces.len() - 1);
+        self.wake_workspace(self.active_workspace);
+        if self.workspaces.len() > 1 {
+            self.dormant_wake_at = Some(now + std::time::Duration::from_millis(self.config.ui.dormant_wake_delay_ms));
+        }
 
-        self.workspaces.push(workspace);
-        self.active_workspace = self.workspaces.len() - 1;
+        for index in 0..self.workspaces.len() {
+            let root = self.workspaces[index].sidebar_root.clone();
+            self.load_directory_async(index, root);
+        }
     }
 
-    /// Close a tab
-    fn close_tab(&mut self, index: usize) {
-        if self.workspaces.len() > 1 {
-            self.workspaces.remove(index);
-            if self.active_workspace >= self.workspaces.len() {
-                self.active_workspace = self.workspaces.len() - 1;
+    /// The open tabs as the next launch would restore them
+    fn capture_session(&self) -> crate::session::Session {
+        use crate::dormant::DormantPane;
+        use crate::session::{SerializableLayout, Session, SessionTab};
+
+        let mut session = Session::default();
+        for (index, ws) in self.workspaces.iter().enumerate() {
+            let tint = |pane_id: PaneId| ws.pane_tint(pane_id).map(crate::config::color32_to_hex);
+            let (layout, focused) = match &ws.dormant {
+                Some(dormant) => {
+                    let layout = SerializableLayout::from_spec(&dormant.spec, &|pane| {
+                        let tint = tint(PaneId(pane as u64));
+                        match &dormant.panes[pane] {
+                            DormantPane::Terminal { dir, name } => SerializableLayout::Terminal {
+                                dir: dir.clone(),
+                                name: name.clone(),
+                                tint,
+                            },
+                            DormantPane::File(path) => SerializableLayout::File { path: path.clone(), tint },
+                            DormantPane::Empty => SerializableLayout::Empty { tint },
+                        }
+                    });
+                    let focused = dormant.spec.pane_indices().iter().position(|&pane| pane == dormant.focused);
+                    (layout, focused)
+                }
+                None => {
+                    let layout = SerializableLayout::capture(&ws.root, &|pane_id, content| match content {
+                        TabContent::Terminal(terminal) => SerializableLayout::Terminal {
+                            dir: terminal.current_dir.clone(),
+                            name: terminal.name.clone(),
+                            tint: tint(pane_id),
+                        },
+                        TabContent::FileViewer { path, .. } => SerializableLayout::File { path: path.clone(), tint: tint(pane_id) },
+                        // Diffs, stats and the color test are rebuilt on demand
+                        _ => SerializableLayout::Empty { tint: tint(pane_id) },
+                    });
+                    let mut pane_ids = Vec::new();
+                    ws.root.collect_pane_ids(&mut pane_ids);
+                    (layout, pane_ids.iter().position(|&pane_id| pane_id == ws.focused_pane))
+                }
+            };
+            if layout.is_blank() {
+                continue;
             }
+            if index <= self.active_workspace {
+                session.active = session.tabs.len();
+            }
+            session.tabs.push(SessionTab {
+                name: ws.name.clone(),
+                sidebar_root: ws.sidebar_root.clone(),
+                pinned: ws.pinned_tab,
+                focused: focused.unwrap_or(0),
+                layout,
+                notes: ws.notes.iter().map(|note| note.text.clone()).collect(),
+            });
         }
+        session
     }
 
-    /// Move tab from one position to another
-    fn move_tab(&mut self, from: usize, to: usize) {
-        if from != to && from < self.workspaces.len() && to < self.workspaces.len() {
-            let workspace = self.workspaces.remove(from);
-            self.workspaces.insert(to, workspace);
-            if self.active_workspace == from {
-                self.active_workspace = to;
-            } else if from < self.active_workspace && to >= self.active_workspace {
-                self.active_workspace -= 1;
-            } else if from > self.active_workspace && to <= self.active_workspace {
-                self.active_workspace += 1;
+    /// Write the session file if it changed, at most every `SESSION_SAVE_INTERVAL`
+    /// unless `now` (on exit)
+    fn save_session(&mut self, now: bool) {
+        if !self.config.ui.restore_session || crate::terminal_backend::headless() {
+            return;
+        }
+        if !now && std::time::Instant::now() < self.session_save_at {
+            return;
+        }
+        self.session_save_at = std::time::Instant::now() + SESSION_SAVE_INTERVAL;
+
+        let session = self.capture_session();
+        if self.saved_session.as_ref() == Some(&session) {
+            return;
+        }
+        self.saved_session = Some(session.clone());
+        self.session_generation += 1;
+        let generation = self.session_generation;
+        let written = Arc::clone(&self.session_written);
+        let save = move || {
+            // Held through the write, so saves never share the temporary file
+            let mut written = written.lock().unwrap_or_else(|e| e.into_inner());
+            if *written > generation {
+                return;
             }
+            match session.save(&crate::session::session_path()) {
+                Ok(()) => *written = generation,
+                Err(e) => log::warn!("{}", e),
+            }
+        };
+        if now {
+            save();
+        } else {
+            self.tokio_runtime.spawn_blocking(save);
         }
     }
 
-    /// Split current pane horizontally (add new terminal to the right)
-    fn split_pane_horizontal(&mut self) {
-        let id = self.next_terminal_id;
-        self.next_terminal_id += 1;
+    /// Add a tab at `position` and switch to it; returns its index
+    fn add_workspace(&mut self, workspace: Workspace, position: NewTabPosition) -> usize {
+        let index = crate::ui::new_tab_index(position, self.active_workspace, self.workspaces.len(), self.pinned_tab_count());
+        self.insert_workspace(index, workspace);
+        index
+    }
 
-        // Clone before mutable borrow to satisfy borrow checker
-        let ctx = self.ctx.clone();
+    /// Index of the tab with [`Workspace::id`] `id`, if it's still open
+    fn workspace_index(&self, id: u64) -> Option<usize> {
+        self.workspaces.iter().position(|ws| ws.id == id)
+    }
+
+    /// Insert a tab at `index` and switch to it, keeping other tab indices in step
+    fn insert_workspace(&mut self, index: usize, workspace: Workspace) {
+        self.workspaces.insert(index, workspace);
+        self.active_workspace = index;
+        if let Some(drag) = &mut self.dragging_tab {
+            drag.source_index = crate::ui::index_after_insert(drag.source_index, index);
+        }
+        if let Some(drag) = &mut self.dragging_divider {
+            drag.workspace = crate::ui::index_after_insert(drag.workspace, index);
+        }
+    }
+
+    /// Create a new workspace/tab with file
+    fn create_file_tab(&mut self, path: PathBuf) {
+        let name = path.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "File".to_string());
+
+        // Create a new workspace with a file viewer
+        let sidebar_root = path.parent().unwrap_or(std::path::Path::new("/")).to_path_buf();
+        let workspace = Workspace::with_content(name, TabContent::file_viewer(path), sidebar_root);
+        let pane_id = workspace.focused_pane;
+
+        let index = self.add_workspace(workspace, self.config.ui.new_tab_position);
+        self.load_file_view(index, pane_id, LoadMode::Preview);
+    }
+
+    /// Open a file in the focused pane if it's empty, else in a new tab
+    fn open_file(&mut self, path: PathBuf) {
+        // The overlay sidebar gets out of the way of the file
+        self.sidebar_reveal.close(std::time::Instant::now());
+        let ws = self.current_workspace();
+        let pane_id = ws.focused_pane;
+        if matches!(ws.get_content(pane_id), Some(TabContent::Empty)) {
+            self.show_file_in_pane(self.active_workspace, pane_id, path);
+        } else {
+            self.create_file_tab(path);
+        }
+    }
+
+    /// Open a file picked in the sidebar the way `ui.sidebar_open_action`,
+    /// or a modifier held with the click, says
+    fn open_sidebar_file(&mut self, path: PathBuf, modifiers: egui::Modifiers) {
+        let ws = self.current_workspace();
+        let viewer = match ws.get_content(ws.focused_pane) {
+            Some(TabContent::FileViewer { .. }) => Some(ws.focused_pane),
+            _ => ws.root.find_pane(&|content| matches!(content, TabContent::FileViewer { .. })),
+        };
+        let focused_empty = matches!(ws.get_content(ws.focused_pane), Some(TabContent::Empty));
+        let direction = match self.config.ui.sidebar_open_action.for_click(modifiers, viewer.is_some()) {
+            SidebarOpenAction::NewTab => return self.open_file(path),
+            SidebarOpenAction::ExternalEditor => return self.open_in_editor(&path, 1, 1),
+            SidebarOpenAction::ReplaceCurrentFileViewer => {
+                let Some(pane_id) = viewer else { return };
+                self.sidebar_reveal.close(std::time::Instant::now());
+                let ws = &mut self.workspaces[self.active_workspace];
+                if ws.root.replace_content(pane_id, TabContent::file_viewer(path)).is_ok() {
+                    ws.focused_pane = pane_id;
+                    self.load_file_view(self.active_workspace, pane_id, LoadMode::Preview);
+                }
+                return;
+            }
+            SidebarOpenAction::SplitRight => SplitDirection::Horizontal,
+            SidebarOpenAction::SplitDown => SplitDirection::Vertical,
+        };
+        // An empty pane needs no split
+        if focused_empty {
+            return self.open_file(path);
+        }
+        if !self.can_split_focused(direction) {
+            return;
+        }
+        self.sidebar_reveal.close(std::time::Instant::now());
+        let ws = &mut self.workspaces[self.active_workspace];
+        ws.split_focused_with(direction, TabContent::file_viewer(path));
+        let pane_id = ws.focused_pane;
+        self.load_file_view(self.active_workspace, pane_id, LoadMode::Preview);
+    }
+
+    /// Show a file in an empty pane
+    fn show_file_in_pane(&mut self, workspace_id: usize, pane_id: PaneId, path: PathBuf) {
+        let ws = &mut self.workspaces[workspace_id];
+        if !matches!(ws.get_content(pane_id), Some(TabContent::Empty)) {
+            return;
+        }
+        let _ = ws.root.replace_content(pane_id, TabContent::file_viewer(path));
+        self.load_file_view(workspace_id, pane_id, LoadMode::Preview);
+    }
+
+    /// Move a pane of the active workspace into a new tab at `index`
+    ///
+    /// The content moves as is, so a terminal keeps its shell. The pane's
+    /// sibling takes its place in the old tab.
+    fn move_pane_to_new_tab(&mut self, pane_id: PaneId, index: usize) {
+        let ws = &mut self.workspaces[self.active_workspace];
+        if ws.pane_count() < 2 {
+            self.toast = Some(Toast::new("Pane is already the only one in its tab"));
+            return;
+        }
+        let old_root = std::mem::replace(&mut ws.root, placeholder_root());
+        let content = match crate::layout::detach_pane(old_root, pane_id) {
+            Ok((rest, content, new_focus)) => {
+                ws.root = rest;
+                ws.focused_pane = new_focus;
+                content
+            }
+            Err(unchanged) => {
+                log::warn!("Failed to detach pane {} for a new tab", pane_id.0);
+                ws.root = unchanged;
+                return;
+            }
+        };
+
+        let (name, sidebar_root) = match &content {
+            TabContent::Terminal(terminal) => (
+                terminal.name.clone().unwrap_or_else(|| dir_label(&terminal.current_dir)),
+                terminal.project_root.as_ref().unwrap_or(&terminal.current_dir).clone(),
+            ),
+            TabContent::FileViewer { path, .. } => (
+                dir_label(path),
+                path.parent().unwrap_or(Path::new("/")).to_path_buf(),
+            ),
+            TabContent::Empty => ("empty".to_string(), ws.sidebar_root.clone()),
+            TabContent::Diff(view) => (view.label(), ws.sidebar_root.clone()),
+            TabContent::Stats(_) => ("usage stats".to_string(), ws.sidebar_root.clone()),
+            TabContent::ColorTest(_) => ("color test".to_string(), ws.sidebar_root.clone()),
+        };
+        let meta = ws.pane_meta.remove(&pane_id);
+        let mut workspace = Workspace::with_content(name, content, sidebar_root.clone());
+        if let Some(meta) = meta {
+            workspace.pane_meta.insert(workspace.focused_pane, meta);
+        }
+        let index = index.clamp(self.pinned_tab_count(), self.workspaces.len());
+        self.insert_workspace(index, workspace);
+        self.load_directory_async(index, sidebar_root);
+    }
+
+    /// Close a tab
+    fn close_tab(&mut self, index: usize) {
+        if self.workspaces.len() > 1 {
+            self.workspaces.remove(index);
+            if self.active_workspace >= self.workspaces.len() {
+                self.active_workspace = self.workspaces.len() - 1;
+            }
+        }
+    }
+
+    /// Number of pinned tabs, which come first
+    fn pinned_tab_count(&self) -> usize {
+        self.workspaces.iter().filter(|ws| ws.pinned_tab).count()
+    }
+
+    /// Pin or unpin a tab, moving it to the edge of the pinned group
+    fn toggle_tab_pinned(&mut self, index: usize) {
+        let Some(ws) = self.workspaces.get_mut(index) else {
+            return;
+        };
+        ws.pinned_tab = !ws.pinned_tab;
+        let pinned = ws.pinned_tab;
+        let count = self.pinned_tab_count();
+        self.move_tab(index, if pinned { count - 1 } else { count });
+    }
+
+    /// Move tab from one position to another, on its side of the pinned group
+    fn move_tab(&mut self, from: usize, to: usize) {
+        if from >= self.workspaces.len() || to >= self.workspaces.len() {
+            return;
+        }
+        let pinned: Vec<bool> = self.workspaces.iter().map(|ws| ws.pinned_tab).collect();
+        let to = crate::ui::clamp_tab_move(&pinned, from, to);
+        if from != to {
+            let workspace = self.workspaces.remove(from);
+            self.workspaces.insert(to, workspace);
+            self.active_workspace = crate::ui::index_after_move(self.active_workspace, from, to);
+        }
+    }
+
+    /// Would splitting the focused pane leave both halves usable?
+    ///
+    /// Shows a toast when the split is refused.
+    fn can_split_focused(&mut self, direction: SplitDirection) -> bool {
+        let ws = self.current_workspace();
+        let max_depth = self.config.ui.max_split_depth;
+        if ws.root.pane_depth(ws.focused_pane).is_some_and(|depth| depth >= max_depth) {
+            self.toast = Some(Toast::new(format!("Splits can nest at most {} levels deep", max_depth)));
+            return false;
+        }
+        let mut layout = ComputedLayout::new();
+        ws.root.compute_layout(self.pane_area, DIVIDER_WIDTH, &mut Vec::new(), &mut layout);
+        let pane = layout.pane_rects.get(&ws.focused_pane).copied().unwrap_or(egui::Rect::NOTHING);
+        let min_size = egui::vec2(self.config.ui.min_pane_width, self.config.ui.min_pane_height);
+
+        let allowed = crate::responsive::split_allowed(pane, direction, DIVIDER_WIDTH, min_size);
+        if !allowed {
+            self.toast = Some(Toast::new("Not enough room to split this pane"));
+        }
+        allowed
+    }
+
+    /// Split current pane horizontally (add new terminal to the right)
+    fn split_pane_horizontal(&mut self) {
+        if !self.can_split_focused(SplitDirection::Horizontal) {
+            return;
+        }
+        let id = self.next_terminal_id;
+        self.next_terminal_id += 1;
+
+        // Clone before mutable borrow to satisfy borrow checker
         let pty_sender = self.pty_sender.clone();
-        let _ = self.current_workspace_mut().split_focused(
+        let dir = self.new_terminal_dir();
+        if self.current_workspace_mut().split_focused(
             SplitDirection::Horizontal,
             id,
-            &ctx,
             pty_sender,
-        );
+            dir,
+        ).is_ok() {
+            self.discover_shell_pid(id);
+        }
+    }
+
+    /// Split current pane horizontally with an empty pane
+    fn split_pane_empty(&mut self) {
+        if !self.can_split_focused(SplitDirection::Horizontal) {
+            return;
+        }
+        self.current_workspace_mut().split_focused_with(SplitDirection::Horizontal, TabContent::Empty);
     }
 
     /// Split current pane vertically (add new terminal below)
     fn split_pane_vertical(&mut self) {
+        if !self.can_split_focused(SplitDirection::Vertical) {
+            return;
+        }
         let id = self.next_terminal_id;
         self.next_terminal_id += 1;
 
         // Clone before mutable borrow to satisfy borrow checker
-        let ctx = self.ctx.clone();
         let pty_sender = self.pty_sender.clone();
-        let _ = self.current_workspace_mut().split_focused(
+        let dir = self.new_terminal_dir();
+        if self.current_workspace_mut().split_focused(
             SplitDirection::Vertical,
             id,
-            &ctx,
             pty_sender,
-        );
+            dir,
+        ).is_ok() {
+            self.discover_shell_pid(id);
+        }
     }
 
     /// Close current pane
@@ -637,62 +2601,110 @@ impl VibeTermApp {
     }
 
     /// Handle keyboard shortcuts
-    fn handle_shortcuts(&mut self, ctx: &Context) {
+    ///
+    /// Returns a key the terminal shouldn't also see: a shortcut that acts
+    /// on the terminal, whose Ctrl form would otherwise reach the shell.
+    fn handle_shortcuts(&mut self, ctx: &Context) -> Option<Key> {
         let modifiers = ctx.input(|i| i.modifiers);
+        // Cmd on macOS, Ctrl elsewhere
+        let primary = crate::shortcuts::primary_modifier(modifiers);
+        let mut swallowed = None;
+        // A palette or dialog keeps its keys: Cmd+W there mustn't close a pane
+        if !self.keyboard_owner().takes_shortcuts() {
+            return None;
+        }
 
         ctx.input(|i| {
             // Cmd+T: New tab
-            if i.key_pressed(Key::T) && modifiers.command {
+            if i.key_pressed(Key::T) && primary {
                 self.create_new_tab();
             }
 
             // Cmd+W: Close pane or tab
-            if i.key_pressed(Key::W) && modifiers.command {
+            if i.key_pressed(Key::W) && primary {
                 self.close_current_pane();
             }
 
             // Cmd+D: Split pane horizontally (left/right)
-            if i.key_pressed(Key::D) && modifiers.command && !modifiers.shift {
+            if i.key_pressed(Key::D) && primary && !modifiers.shift {
                 self.split_pane_horizontal();
             }
 
             // Cmd+Shift+D: Split pane vertically (top/bottom)
-            if i.key_pressed(Key::D) && modifiers.command && modifiers.shift {
+            if i.key_pressed(Key::D) && primary && modifiers.shift {
                 self.split_pane_vertical();
             }
 
             // Cmd+B: Toggle sidebar
-            if i.key_pressed(Key::B) && modifiers.command {
-                self.sidebar_visible = !self.sidebar_visible;
+            if i.key_pressed(Key::B) && primary {
+                self.toggle_sidebar();
             }
 
             // Debug key input for collapse all
-            if modifiers.shift && (modifiers.command || modifiers.ctrl) {
+            if modifiers.shift && (primary || modifiers.ctrl) {
                 for key in &i.keys_down {
                     log::info!("Shift+Cmd pressed, key: {:?}", key);
                 }
             }
 
-            // Cmd+Shift+[: Collapse all directories in sidebar (original)
-            if i.key_pressed(Key::OpenBracket) && (modifiers.command || modifiers.ctrl) && modifiers.shift {
-                log::info!("Collapse all triggered via OpenBracket!");
-                self.collapse_all_directories();
+            // Cmd+Shift+[: Previous mark in a focused terminal, otherwise
+            // collapse all directories in sidebar (original)
+            let open_bracket = bracket_pressed(i, Key::OpenBracket, Key::OpenCurlyBracket);
+            if let (Some(key), true) = (open_bracket, (primary || modifiers.ctrl) && modifiers.shift) {
+                if self.terminal_has_keyboard_focus() {
+                    self.step_mark(false);
+                    swallowed = Some(key);
+                } else {
+                    log::info!("Collapse all triggered via OpenBracket!");
+                    self.collapse_all_directories();
+                }
+            }
+
+            // Cmd+Shift+]: Next mark
+            let close_bracket = bracket_pressed(i, Key::CloseBracket, Key::CloseCurlyBracket);
+            if let (Some(key), true) = (close_bracket, primary && modifiers.shift && self.terminal_has_keyboard_focus()) {
+                self.step_mark(true);
+                swallowed = Some(key);
+            }
+
+            // Cmd+Shift+M: Mark the current line
+            if i.key_pressed(Key::M) && primary && modifiers.shift && self.terminal_has_keyboard_focus() {
+                self.open_mark_prompt();
+                swallowed = Some(Key::M);
+            }
+
+            // Cmd+[ / Cmd+]: Previous / next sidebar root (sidebar focused)
+            if self.sidebar_shown() && self.sidebar_focused && primary && !modifiers.shift {
+                if i.key_pressed(Key::OpenBracket) {
+                    self.navigate_sidebar_root(false);
+                } else if i.key_pressed(Key::CloseBracket) {
+                    self.navigate_sidebar_root(true);
+                }
             }
 
             // Cmd+Shift+C: Collapse all directories in sidebar (alternative binding)
-            if i.key_pressed(Key::C) && (modifiers.command || modifiers.ctrl) && modifiers.shift {
+            if i.key_pressed(Key::C) && (primary || modifiers.ctrl) && modifiers.shift {
                 log::info!("Collapse all triggered via C!");
                 self.collapse_all_directories();
             }
 
             // Cmd+Shift+E: Expand all directories in sidebar
-            if i.key_pressed(Key::E) && (modifiers.command || modifiers.ctrl) && modifiers.shift {
+            if i.key_pressed(Key::E) && (primary || modifiers.ctrl) && modifiers.shift {
                 log::info!("Expand all triggered via E!");
                 self.expand_all_directories();
             }
 
+            // Cmd+Shift+F: Toggle sidebar filter (sidebar focused), otherwise search in project
+            if i.key_pressed(Key::F) && (primary || modifiers.ctrl) && modifiers.shift {
+                if self.sidebar_shown() && self.sidebar_focused && !self.search_panel.is_visible() {
+                    self.toggle_sidebar_filter();
+                } else {
+                    self.open_project_search();
+                }
+            }
+
             // Cmd+,: Preferences
-            if i.key_pressed(Key::Comma) && modifiers.command {
+            if i.key_pressed(Key::Comma) && primary {
                 self.preferences_window.open(self.config.clone());
             }
 
@@ -710,7 +2722,7 @@ impl VibeTermApp {
                     9 => Key::Num9,
                     _ => continue,
                 };
-                if i.key_pressed(key) && modifiers.command {
+                if i.key_pressed(key) && primary {
                     if n - 1 < self.workspaces.len() {
                         self.active_workspace = n - 1;
                     }
@@ -727,96 +2739,271 @@ impl VibeTermApp {
                 self.workspaces[self.active_workspace].focus_prev();
             }
 
-            // Cmd+V: Smart paste (images or text)
-            if i.key_pressed(Key::V) && modifiers.command && !modifiers.shift {
+            // Cmd+Shift+Right/Left: Send selection to next/previous pane
+            if i.key_pressed(Key::ArrowRight) && primary && modifiers.shift && !modifiers.alt {
+                self.send_selection_to_neighbor(true, false);
+            }
+            if i.key_pressed(Key::ArrowLeft) && primary && modifiers.shift && !modifiers.alt {
+                self.send_selection_to_neighbor(false, false);
+            }
+
+            // Cmd+Alt+Shift+Arrow: Move the focused pane
+            if primary && modifiers.alt && modifiers.shift {
+                let moves = [
+                    (Key::ArrowLeft, PaneDirection::Left),
+                    (Key::ArrowRight, PaneDirection::Right),
+                    (Key::ArrowUp, PaneDirection::Up),
+                    (Key::ArrowDown, PaneDirection::Down),
+                ];
+                for (key, direction) in moves {
+                    if i.key_pressed(key) {
+                        self.workspaces[self.active_workspace].move_focused_pane(direction);
+                    }
+                }
+            }
+
+            // Cmd+Shift+J: Jump to the next compiler diagnostic
+            if i.key_pressed(Key::J) && primary && modifiers.shift {
+                self.jump_to_next_diagnostic();
+            }
+
+            // Cmd+Shift+Enter: Send selection to next pane followed by a newline
+            if i.key_pressed(Key::Enter) && primary && modifiers.shift {
+                self.send_selection_to_neighbor(true, true);
+            }
+
+            // Cmd+V: Smart paste (images or text); the sidebar's text fields paste for themselves
+            let text_field = matches!(self.keyboard_owner(), KeyboardOwner::SidebarFilter | KeyboardOwner::SearchBar);
+            if i.key_pressed(Key::V) && primary && !modifiers.shift && !text_field {
                 self.handle_smart_paste();
             }
+
+            // Cmd+Shift+V: Clipboard history
+            if i.key_pressed(Key::V) && primary && modifiers.shift {
+                self.clipboard_history_palette.open(self.clipboard_history.entries().to_vec());
+            }
+
+            // Cmd+R (Ctrl+Shift+R off macOS, leaving the shell its Ctrl+R
+            // reverse search): Search earlier commands of all panes
+            if i.key_pressed(Key::R) && crate::shortcuts::primary_beside_shell(modifiers) {
+                self.open_history_peek();
+                if self.history_peek.is_visible() {
+                    swallowed = Some(Key::R);
+                }
+            }
+
+            // Copy Last Output (Cmd+Alt+C unless configured otherwise)
+            let copy_output = crate::last_output::parse_shortcut(&self.config.terminal.copy_last_output.shortcut);
+            if copy_output.is_some_and(|s| i.key_pressed(s.logical_key) && modifiers.matches_exact(s.modifiers)) {
+                self.copy_last_output();
+            }
         });
 
-        // Shift+Enter: Insert newline in terminal
-        // Handle this AFTER the input closure to prevent the terminal from also processing Enter
-        if ctx.input(|i| i.key_pressed(Key::Enter)) && modifiers.shift && !modifiers.command && !modifiers.ctrl {
-            if let Some(ws) = self.workspaces.get_mut(self.active_workspace) {
-                let focused = ws.focused_pane;
-                if let Some(content) = ws.get_content_mut(focused) {
-                    if let TabContent::Terminal(terminal) = content {
-                        // Send a proper newline character to the terminal
-                        terminal.backend.process_command(
-                            BackendCommand::Write(b"\n".to_vec())
-                        );
-                    }
+        // Cmd+Alt+Plus/Minus: Scale the UI chrome (`process_input` drops
+        // them, so egui's own Cmd+Plus zoom doesn't also act on them)
+        let steps: Vec<i32> = ctx.input(|i| i.events.iter().filter_map(ui_scale_step).collect());
+        for steps in steps {
+            self.step_ui_scale(steps);
+        }
+
+        // Cmd+Alt+Enter: Toggle presentation mode (sends viewport commands,
+        // so not from inside the input closure above)
+        if ctx.input(|i| i.key_pressed(Key::Enter)) && primary && modifiers.alt && !modifiers.shift {
+            self.toggle_presentation_mode(ctx);
+        }
+
+        swallowed
+    }
+
+    /// Make the UI chrome 10% larger or smaller
+    fn step_ui_scale(&mut self, steps: i32) {
+        let range = crate::config::FontConfig::UI_SCALES;
+        // Whole tenths, so repeated steps don't drift
+        let scale = ((self.config.font.ui_scale * 10.0).round() + steps as f32) / 10.0;
+        let scale = scale.clamp(*range.start(), *range.end());
+        self.config.edit(std::time::Instant::now(), |config| config.font.ui_scale = scale);
+        self.toast = Some(Toast::new(format!("UI scale {:.0}%", scale * 100.0)));
+    }
+
+    /// Is the sidebar drawn this frame?
+    fn sidebar_shown(&self) -> bool {
+        if self.presentation.is_active() {
+            return false;
+        }
+        match self.config.ui.sidebar_mode {
+            SidebarMode::Pinned => self.responsive.sidebar_shown(self.sidebar_visible || self.search_reveals_sidebar),
+            SidebarMode::AutoHide | SidebarMode::Hidden => self.sidebar_reveal.is_open(),
+        }
+    }
+
+    /// Cmd+B: show or hide the pinned sidebar, or slide the overlay in or out
+    fn toggle_sidebar(&mut self) {
+        match self.config.ui.sidebar_mode {
+            SidebarMode::Pinned => {
+                let visible = self.sidebar_visible || std::mem::take(&mut self.search_reveals_sidebar);
+                self.sidebar_visible = self.responsive.toggle_sidebar(visible);
+            }
+            SidebarMode::AutoHide | SidebarMode::Hidden => {
+                self.sidebar_reveal.toggle(std::time::Instant::now());
+                if !self.sidebar_reveal.is_open() {
+                    self.sidebar_focused = false;
                 }
             }
+        }
+    }
+
+    /// Hide or restore the chrome around the panes
+    fn toggle_presentation_mode(&mut self, ctx: &Context) {
+        if let Some(restore) = self.presentation.exit() {
+            self.sidebar_visible = restore.sidebar_visible;
+            if restore.leave_fullscreen {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+            }
+            return;
+        }
+
+        let was_fullscreen = ctx.input(|i| i.viewport().fullscreen.unwrap_or(false));
+        let enter_fullscreen = self.presentation.enter(
+            self.sidebar_visible,
+            self.config.ui.presentation_fullscreen,
+            was_fullscreen,
+            std::time::Instant::now(),
+        );
+        if enter_fullscreen {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+        }
+        self.sidebar_focused = false;
+    }
 
-            // Consume the Enter event to prevent the terminal from processing it
-            ctx.input_mut(|i| {
-                i.events.retain(|e| !matches!(e, Event::Key { key: Key::Enter, pressed: true, .. }));
+    /// Brief "how to exit" hint after entering presentation mode
+    fn show_presentation_hint(&self, ctx: &Context) {
+        let Some(remaining) = self.presentation.hint_remaining(std::time::Instant::now()) else {
+            return;
+        };
+        egui::Area::new(egui::Id::new("presentation_hint"))
+            .order(egui::Order::Tooltip)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 12.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                Frame::NONE
+                    .fill(self.theme.surface)
+                    .stroke(egui::Stroke::new(1.0, self.theme.border))
+                    .inner_margin(egui::Margin::symmetric(12, 6))
+                    .show(ui, |ui| {
+                        ui.label(egui::RichText::new(format!(
+                            "Presentation mode · {} to exit",
+                            crate::shortcuts::shortcut_label(crate::presentation::SHORTCUT_LABEL)
+                        ))
+                            .font(theme::ui_font(11.0))
+                            .color(self.theme.text_dim));
+                    });
             });
+        ctx.request_repaint_after(remaining);
+    }
+
+    /// Start or stop mirroring a terminal pane into its own window
+    fn toggle_mirror(&mut self, pane_id: PaneId) {
+        let Some(terminal_id) = self.current_workspace().terminal_id(pane_id) else {
+            self.toast = Some(Toast::new("Only terminal panes can be mirrored"));
+            return;
+        };
+        self.mirror = if self.mirror == Some(terminal_id) { None } else { Some(terminal_id) };
+    }
+
+    /// Read-only mirror window; it closes along with its source pane
+    fn show_mirror(&mut self, ctx: &Context) {
+        let Some(terminal_id) = self.mirror else {
+            return;
+        };
+        let Some(ws_idx) = self.workspaces.iter().position(|ws| ws.find_pane_by_terminal_id(terminal_id).is_some()) else {
+            self.mirror = None;
+            return;
+        };
+        let in_background = ws_idx != self.active_workspace;
+        let ws = &mut self.workspaces[ws_idx];
+        let title = ws.find_pane_by_terminal_id(terminal_id).map(|pane_id| ws.pane_label(pane_id)).unwrap_or_default();
+        let Some(terminal) = ws.get_terminal_mut(terminal_id) else {
+            return;
+        };
+        // Only visible panes are synced by their view
+        if in_background {
+            terminal.backend.sync();
+        }
+
+        let open = crate::ui::PaneMirror::new(title, terminal.backend.grid(), &self.cached_terminal_theme)
+            .font_size(self.config.ui.mirror_font_size)
+            .ambiguous_width(self.config.terminal.ambiguous_width)
+            .show(ctx);
+        if !open {
+            self.mirror = None;
         }
     }
 
     /// Handle smart paste: Try image first, then fall back to text
     fn handle_smart_paste(&mut self) {
-        match Clipboard::new() {
-            Ok(mut clipboard) => {
-                // Try to get image first
-                if let Ok(img_data) = clipboard.get_image() {
-                    log::info!("Pasting image from clipboard");
-
-                    // Generate unique filename with timestamp
-                    let timestamp = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis();
-
-                    // Use home directory for better Unicode support
-                    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/tmp"));
-                    let file_path = home.join(format!(".vibeterm_paste_{}.png", timestamp));
-                    let file_path_str = file_path.to_string_lossy().to_string();
-
-                    // Convert arboard ImageData to image crate format and save
-                    let img = image::RgbaImage::from_raw(
-                        img_data.width as u32,
-                        img_data.height as u32,
-                        img_data.bytes.into_owned(),
-                    );
+        let ws = self.current_workspace();
+        let Some(terminal_id) = ws.terminal_id(ws.focused_pane) else {
+            return;
+        };
+        let settings = self.config.terminal.paste_image.clone();
+        if !self.config.terminal.async_paste {
+            let payload = crate::clipboard_paste::read(&settings);
+            self.apply_paste(terminal_id, payload);
+            return;
+        }
 
-                    if let Some(img) = img {
-                        match img.save(&file_path) {
-                            Ok(_) => {
-                                log::info!("Image saved to {}", file_path_str);
-                                // Send [image: path] marker to the terminal
-                                if let Some(ws) = self.workspaces.get_mut(self.active_workspace) {
-                                    let focused = ws.focused_pane;
-                                    if let Some(content) = ws.get_content_mut(focused) {
-                                        if let TabContent::Terminal(terminal) = content {
-                                            let marker = format!("[image: {}]\n", file_path_str);
-                                            terminal.backend.process_command(
-                                                BackendCommand::Write(marker.into_bytes())
-                                            );
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                log::error!("Failed to save clipboard image: {}", e);
-                            }
-                        }
-                    } else {
-                        log::error!("Failed to convert clipboard image data to RgbaImage");
-                    }
-                    return; // Image handled, don't try text
-                }
+        let request = self.pastes.start(terminal_id, std::time::Instant::now());
+        let tx = self.paste_tx.clone();
+        let ctx = self.ctx.clone();
+        self.tokio_runtime.spawn_blocking(move || {
+            let payload = crate::clipboard_paste::read_with_timeout(
+                move || crate::clipboard_paste::read(&settings),
+                crate::clipboard_paste::FETCH_TIMEOUT,
+            );
+            let _ = tx.send(crate::clipboard_paste::PasteReady { request, payload });
+            ctx.request_repaint();
+        });
+        // Shows the spinner if the clipboard is slow
+        self.ctx.request_repaint_after(crate::clipboard_paste::SPINNER_DELAY);
+    }
 
-                // No image, try text
-                if let Ok(text) = clipboard.get_text() {
-                    log::info!("Pasting text from clipboard: {} chars", text.len());
-                    self.send_text_to_terminal(&text);
-                }
+    /// Type finished background pastes
+    fn process_paste_results(&mut self) {
+        while let Ok(ready) = self.paste_rx.try_recv() {
+            if let Some(terminal_id) = self.pastes.finish(ready.request) {
+                self.apply_paste(terminal_id, ready.payload);
             }
-            Err(e) => {
-                log::error!("Failed to access clipboard: {}", e);
+        }
+    }
+
+    /// Type a paste into terminal `terminal_id`, unless its pane has closed
+    fn apply_paste(&mut self, terminal_id: u64, payload: crate::clipboard_paste::PastePayload) {
+        use crate::clipboard_paste::PastePayload;
+        let (text, typed) = match payload {
+            PastePayload::Text(text) => (text, true),
+            PastePayload::Image(marker) => (marker, false),
+            PastePayload::Empty => return,
+            PastePayload::Failed(e) => {
+                log::warn!("{}", e);
+                self.toast = Some(Toast::new(e));
+                return;
             }
+        };
+        let Some(terminal) = self.workspaces.iter_mut().find_map(|ws| {
+            let pane_id = ws.find_pane_by_terminal_id(terminal_id)?;
+            match ws.get_content_mut(pane_id) {
+                Some(TabContent::Terminal(terminal)) => Some(terminal),
+                _ => None,
+            }
+        }) else {
+            log::info!("Dropped a paste: its terminal closed");
+            return;
+        };
+        log::info!("Pasting {} chars", text.len());
+        terminal.backend.process_command(BackendCommand::Write(text.into_bytes()));
+        if typed {
+            terminal.typed = true;
+            self.note_terminal_input();
         }
     }
 
@@ -829,220 +3016,3575 @@ impl VibeTermApp {
                     terminal.backend.process_command(
                         BackendCommand::Write(text.to_string().into_bytes())
                     );
+                    terminal.typed = true;
                 }
             }
         }
+        self.note_terminal_input();
     }
 
+    /// Send the focused pane's selection to the next or previous pane
+    ///
+    /// With `submit`, a newline follows the text (same as Shift+Enter).
+    fn send_selection_to_neighbor(&mut self, forward: bool, submit: bool) {
+        let ws = self.current_workspace();
+        let Some(text) = ws.selection_text(ws.focused_pane) else {
+            return;
+        };
+        if let Some(target) = ws.neighbor_pane(forward) {
+            self.send_text_to_pane(target, &text, submit);
+        }
+    }
 
-    /// Handle IME (Input Method Editor) events for Korean/Japanese/Chinese input
-    fn handle_ime_events(&mut self, ctx: &Context) {
-        // Early check: only clone events if there are any IME events to process
-        let has_ime_events = ctx.input(|i| i.events.iter().any(|e| matches!(e, Event::Ime(_))));
-        if !has_ime_events && !self.ime_composing {
-            return; // No IME events and not composing, skip processing
+    /// Open the pane picker to choose where the selection goes
+    fn open_send_selection_picker(&mut self) {
+        let ws = self.current_workspace();
+        if ws.pane_count() < 2 {
+            return;
         }
+        self.pane_pick = ws.selection_text(ws.focused_pane).map(PanePick::Send);
+    }
 
-        let events = ctx.input(|i| i.events.clone());
+    /// Open the pane picker to choose two terminals to diff
+    fn start_pane_diff(&mut self, scope: DiffScope) {
+        let ws = self.current_workspace();
+        let terminals = ws.pane_ids()
+            .into_iter()
+            .filter(|&pane_id| ws.terminal_id(pane_id).is_some())
+            .count();
+        if terminals < 2 {
+            self.toast = Some(Toast::new("Diff Panes needs two terminal panes in this tab"));
+            return;
+        }
+        self.pane_pick = Some(PanePick::Diff { scope, first: None });
+    }
 
-        for event in &events {
-            if let Event::Ime(ime_event) = event {
-                match ime_event {
-                    ImeEvent::Enabled => {
-                        // Don't set composing here - wait for actual preedit text
-                        // This prevents false positives that drop all text events
-                    }
-                    ImeEvent::Preedit(text) => {
-                        self.ime_composing = !text.is_empty();
-                    }
-                    ImeEvent::Commit(text) => {
-                        log::info!("IME Commit: '{}'", text);
-                        // Send committed text to terminal
-                        if let Some(ws) = self.workspaces.get_mut(self.active_workspace) {
-                            let focused = ws.focused_pane;
-                            if let Some(content) = ws.get_content_mut(focused) {
-                                if let TabContent::Terminal(terminal) = content {
-                                    terminal.backend.process_command(
-                                        BackendCommand::Write(text.clone().into_bytes())
-                                    );
-                                }
-                            }
-                        }
-                        self.ime_composing = false;
-                    }
-                    ImeEvent::Disabled => {
-                        self.ime_composing = false;
-                    }
+    /// Diff two terminal panes of the current workspace in a new tab
+    fn open_pane_diff(&mut self, left: PaneId, right: PaneId, scope: DiffScope) {
+        let ws = self.current_workspace();
+        let (Some(left), Some(right)) = (ws.terminal_id(left), ws.terminal_id(right)) else {
+            return;
+        };
+        let sidebar_root = ws.sidebar_root.clone();
+        let (Some(left), Some(right)) = (self.snapshot_terminal(left, scope), self.snapshot_terminal(right, scope)) else {
+            return;
+        };
+
+        let view = DiffView { left, right, scope, pending: None, diff: None };
+        let workspace = Workspace::with_content(format!("diff: {}", view.label()), TabContent::Diff(view), sidebar_root);
+        let pane_id = workspace.focused_pane;
+        let index = self.add_workspace(workspace, self.config.ui.new_tab_position);
+        self.compute_diff(index, pane_id);
+    }
+
+    /// Text of a terminal in any workspace, for one side of a diff
+    fn snapshot_terminal(&mut self, terminal_id: u64, scope: DiffScope) -> Option<DiffSide> {
+        let ws = self.workspaces.iter_mut().find(|ws| ws.find_pane_by_terminal_id(terminal_id).is_some())?;
+        let label = ws.find_pane_by_terminal_id(terminal_id).map(|pane_id| ws.pane_label(pane_id))?;
+        let terminal = ws.get_terminal_mut(terminal_id)?;
+        // Background tabs aren't synced by their view
+        terminal.backend.sync();
+        let text = crate::pane_diff::snapshot(terminal.backend.grid(), scope);
+        Some(DiffSide { terminal_id, label, text })
+    }
+
+    /// Diff a diff pane's snapshots in the background
+    fn compute_diff(&mut self, workspace_id: usize, pane_id: PaneId) {
+        let request = self.next_diff_request;
+        let Some(TabContent::Diff(view)) = self.workspaces[workspace_id].get_content_mut(pane_id) else {
+            return;
+        };
+        self.next_diff_request += 1;
+        view.pending = Some(request);
+        let (left, right) = (view.left.text.clone(), view.right.text.clone());
+        let tx = self.diff_tx.clone();
+        let ctx = self.ctx.clone();
+        self.tokio_runtime.spawn_blocking(move || {
+            let diff = crate::pane_diff::diff(&left, &right);
+            let _ = tx.send(DiffResult { request, diff });
+            ctx.request_repaint();
+        });
+    }
+
+    /// Re-snapshot or swap a diff pane of the current workspace
+    fn run_diff_command(&mut self, pane_id: PaneId, command: DiffCommand) {
+        let workspace_id = self.active_workspace;
+        let Some(TabContent::Diff(view)) = self.workspaces[workspace_id].get_content_mut(pane_id) else {
+            return;
+        };
+        match command {
+            DiffCommand::Swap => std::mem::swap(&mut view.left, &mut view.right),
+            DiffCommand::Rerun => {
+                let (left, right, scope) = (view.left.terminal_id, view.right.terminal_id, view.scope);
+                let (Some(left), Some(right)) = (self.snapshot_terminal(left, scope), self.snapshot_terminal(right, scope)) else {
+                    self.toast = Some(Toast::new("A compared pane was closed"));
+                    return;
+                };
+                if let Some(TabContent::Diff(view)) = self.workspaces[workspace_id].get_content_mut(pane_id) {
+                    view.left = left;
+                    view.right = right;
                 }
             }
         }
+        self.compute_diff(workspace_id, pane_id);
+    }
 
-        // If IME is composing, filter out Text events to prevent double input
-        if self.ime_composing {
-            ctx.input_mut(|i| {
-                i.events.retain(|e| !matches!(e, Event::Text(_)));
+    /// Show finished background diffs
+    fn process_diff_results(&mut self) {
+        while let Ok(result) = self.diff_rx.try_recv() {
+            let view = self.workspaces.iter_mut().find_map(|ws| {
+                ws.root.collect_contents_mut().into_iter().find_map(|(_, content)| match content {
+                    TabContent::Diff(view) if view.pending == Some(result.request) => Some(view),
+                    _ => None,
+                })
             });
+            // The pane may have been closed or re-run meanwhile
+            if let Some(view) = view {
+                view.pending = None;
+                view.diff = Some(result.diff);
+            }
         }
     }
 
-    /// Handle menu bar events
-    fn handle_menu_events(&mut self) {
-        while let Some(action) = menu::poll_menu_event() {
-            match action {
-                MenuAction::NewTab => self.create_new_tab(),
-                MenuAction::NewWindow => {
-                    // TODO: Open new window
-                    log::info!("New window requested");
-                }
-                MenuAction::CloseTab => self.close_current_pane(),
-                MenuAction::CloseWindow => {
-                    // Handled by system
-                }
-                MenuAction::SplitHorizontal => self.split_pane_horizontal(),
-                MenuAction::SplitVertical => self.split_pane_vertical(),
-                MenuAction::ToggleSidebar => self.sidebar_visible = !self.sidebar_visible,
-                MenuAction::Preferences => self.preferences_window.open(self.config.clone()),
-                MenuAction::About => {
-                    log::info!("About VibeTerm v{}", env!("CARGO_PKG_VERSION"));
-                }
-                MenuAction::Quit => {
-                    // Handled by system
-                }
+    /// Show or hide the git blame column of a file viewer pane
+    fn toggle_blame(&mut self, workspace_id: usize, pane_id: PaneId) {
+        let Some(TabContent::FileViewer { blame, .. }) = self.workspaces[workspace_id].get_content_mut(pane_id) else {
+            return;
+        };
+        // Dropping a running job cancels it
+        if blame.take().is_none() {
+            self.start_blame(workspace_id, pane_id);
+        }
+    }
+
+    /// Blame a file viewer pane's text in the background
+    fn start_blame(&mut self, workspace_id: usize, pane_id: PaneId) {
+        let max_lines = self.config.file_viewer.blame_max_lines;
+        let request = self.next_blame_request;
+        let Some(TabContent::FileViewer { path, content, state, blame, .. }) =
+            self.workspaces[workspace_id].get_content_mut(pane_id)
+        else {
+            return;
+        };
+
+        // Lines of a head-and-tail preview don't match the file's
+        if matches!(state, ViewerState::Loaded { skipped, .. } if *skipped > 0) {
+            *blame = Some(BlameView::Unavailable("No blame for a truncated file".to_string()));
+            return;
+        }
+        if content.lines().count() > max_lines {
+            *blame = Some(BlameView::Unavailable(format!("No blame for files over {} lines", max_lines)));
+            return;
+        }
+
+        self.next_blame_request += 1;
+        let job = crate::blame::BlameJob::new(request);
+        let cancel = job.cancel_flag();
+        *blame = Some(BlameView::Loading(job));
+        let (path, content) = (path.clone(), content.clone());
+        let tx = self.blame_tx.clone();
+        let ctx = self.ctx.clone();
+        self.tokio_runtime.spawn_blocking(move || {
+            let result = match crate::blame::blame(&path, &content, &cancel) {
+                Ok(Some(runs)) => Ok(runs),
+                // The pane closed or blame was turned off
+                Ok(None) => return,
+                Err(e) => Err(e),
+            };
+            let _ = tx.send(BlameResult { request, result });
+            ctx.request_repaint();
+        });
+    }
+
+    /// Show finished background blames
+    fn process_blame_results(&mut self) {
+        while let Ok(result) = self.blame_rx.try_recv() {
+            let blame = self.workspaces.iter_mut().find_map(|ws| {
+                ws.root.collect_contents_mut().into_iter().find_map(|(_, content)| match content {
+                    TabContent::FileViewer { blame: Some(blame @ BlameView::Loading(_)), .. } => {
+                        matches!(blame, BlameView::Loading(job) if job.request == result.request).then_some(blame)
+                    }
+                    _ => None,
+                })
+            });
+            // The pane may have been closed, reloaded or toggled meanwhile
+            if let Some(blame) = blame {
+                *blame = match result.result {
+                    Ok(runs) => BlameView::Ready(runs),
+                    Err(e) => BlameView::Unavailable(e),
+                };
+            }
+        }
+    }
+
+    /// Track the sidebar entry under the pointer and, once it has rested
+    /// on an image for [`crate::thumbnails::HOVER_DWELL`], load its thumbnail
+    fn update_thumbnail_hover(&mut self, hovered: Option<PathBuf>) {
+        let Some(path) = hovered.filter(|path| crate::thumbnails::is_image(path)) else {
+            self.thumbnail_hover = None;
+            return;
+        };
+        let now = std::time::Instant::now();
+        let hover = match &mut self.thumbnail_hover {
+            Some(hover) if hover.path == path => hover,
+            _ => self.thumbnail_hover.insert(ThumbnailHover { path, since: now, key: None }),
+        };
+        if hover.key.is_some() {
+            return;
+        }
+        let waited = now.duration_since(hover.since);
+        if waited < crate::thumbnails::HOVER_DWELL {
+            self.ctx.request_repaint_after(crate::thumbnails::HOVER_DWELL - waited);
+            return;
+        }
+
+        // Watcher events name canonical paths
+        let key = hover.path.canonicalize().unwrap_or_else(|_| hover.path.clone());
+        hover.key = Some(key.clone());
+        let modified = std::fs::metadata(&key).and_then(|meta| meta.modified()).ok();
+        if !self.thumbnails.request(&key, modified) {
+            return;
+        }
+        let tx = self.thumbnail_tx.clone();
+        let ctx = self.ctx.clone();
+        self.tokio_runtime.spawn_blocking(move || {
+            let result = crate::thumbnails::decode(&key);
+            let _ = tx.send(ThumbnailResult { path: key, modified, result });
+            ctx.request_repaint();
+        });
+    }
+
+    /// Turn decoded thumbnails into textures
+    fn process_thumbnail_results(&mut self) {
+        while let Ok(decoded) = self.thumbnail_rx.try_recv() {
+            let result = decoded.result.map(|image| {
+                let bytes = image.image.pixels.len() * 4;
+                crate::thumbnails::Thumbnail {
+                    texture: self.ctx.load_texture(
+                        format!("thumbnail:{}", decoded.path.display()),
+                        image.image,
+                        egui::TextureOptions::LINEAR,
+                    ),
+                    width: image.width,
+                    height: image.height,
+                    file_size: image.file_size,
+                    bytes,
+                }
+            });
+            self.thumbnails.insert(&decoded.path, decoded.modified, result);
+        }
+    }
+
+    /// Write text to a pane in the current workspace
+    fn send_text_to_pane(&mut self, target: PaneId, text: &str, submit: bool) {
+        let ws = self.current_workspace_mut();
+        if ws.write_to_pane(target, text) && submit {
+            ws.write_to_pane(target, "\n");
+        }
+        self.note_terminal_input();
+    }
+
+    /// What gets keystrokes, from the surfaces open right now
+    fn keyboard_owner(&self) -> KeyboardOwner {
+        let ws = self.current_workspace();
+        KeyboardOwner::resolve(KeyboardSurfaces {
+            modal: self.note_editing
+                || self.pane_pick.is_some()
+                || self.run_all_dialog.is_visible()
+                || self.import_dialog.is_visible()
+                || self.font_diagnostics_dialog.is_visible()
+                || self.startup_report_dialog.is_visible()
+                || self.consent_prompt.is_some()
+                || !self.dir_hook_prompts.is_empty()
+                || self.quit_prompt.is_some()
+                || self.file_viewer_prompt.is_some()
+                || self.mark_prompt.is_some()
+                || self.mark_list.is_visible()
+                || self.delete_confirm.is_some(),
+            palette: self.command_palette.is_visible()
+                || self.snippet_palette.is_visible()
+                || self.clipboard_history_palette.is_visible()
+                || self.history_peek.is_visible()
+                || self.ssh_palette.is_visible(),
+            // In its own window it gets its own keystrokes
+            preferences: self.preferences_window.is_visible() && self.ctx.embed_viewports(),
+            sidebar_focused: self.sidebar_focused,
+            search_bar: self.search_panel.is_visible(),
+            sidebar_filter: ws.sidebar_filter.is_some(),
+        })
+    }
+
+    /// Does the focused terminal receive keyboard input?
+    fn terminal_has_keyboard_focus(&self) -> bool {
+        self.keyboard_owner() == KeyboardOwner::Terminal
+    }
+
+    /// Make a file viewer pane follow the sidebar selection, or stop it
+    ///
+    /// One pane per workspace follows; enabling another moves it there.
+    fn toggle_follow_sidebar(&mut self, pane_id: PaneId) {
+        let ws = &mut self.workspaces[self.active_workspace];
+        if !matches!(ws.get_content(pane_id), Some(TabContent::FileViewer { .. })) {
+            self.toast = Some(Toast::new("Follow Sidebar Selection works in file viewer panes"));
+            return;
+        }
+        if ws.follow_pane == Some(pane_id) {
+            ws.follow_pane = None;
+            ws.follow_pending = None;
+        } else {
+            ws.follow_pane = Some(pane_id);
+        }
+    }
+
+    /// Queue the sidebar's selected file for the following pane
+    fn queue_follow_selection(&mut self) {
+        let ws = &mut self.workspaces[self.active_workspace];
+        if ws.follow_pane.is_none() {
+            return;
+        }
+        if let Some(path) = ws.selected_sidebar_file() {
+            ws.follow_pending = Some((path, std::time::Instant::now() + FOLLOW_SELECTION_DELAY));
+            self.ctx.request_repaint_after(FOLLOW_SELECTION_DELAY);
+        }
+    }
+
+    /// Load the queued selection into the following pane once it's due
+    fn process_follow_selection(&mut self) {
+        let workspace_id = self.active_workspace;
+        let ws = &mut self.workspaces[workspace_id];
+        if ws.follow_pending.as_ref().is_none_or(|(_, due)| std::time::Instant::now() < *due) {
+            return;
+        }
+        let (Some((path, _)), Some(pane_id)) = (ws.follow_pending.take(), ws.follow_pane) else {
+            return;
+        };
+        match ws.get_content(pane_id) {
+            Some(TabContent::FileViewer { path: shown, .. }) if *shown == path => return,
+            Some(TabContent::FileViewer { .. }) => {}
+            // Closed or moved to another tab
+            _ => {
+                ws.follow_pane = None;
+                return;
+            }
+        }
+        let _ = ws.root.replace_content(pane_id, TabContent::file_viewer(path));
+        self.load_file_view(workspace_id, pane_id, LoadMode::Preview);
+    }
+
+    /// Replace a file viewer or empty pane with a terminal
+    ///
+    /// A file viewer's terminal starts in the file's directory, an empty
+    /// pane's in the sidebar root.
+    fn convert_to_terminal(&mut self, pane_id: PaneId) {
+        let ws = &self.workspaces[self.active_workspace];
+        let dir = match ws.get_content(pane_id) {
+            Some(TabContent::FileViewer { path, .. }) => path.parent()
+                .filter(|dir| dir.is_dir())
+                .map(|dir| dir.to_path_buf())
+                .unwrap_or_else(|| ws.sidebar_root.clone()),
+            Some(TabContent::Empty) => ws.sidebar_root.clone(),
+            _ => return,
+        };
+
+        let id = self.next_terminal_id;
+        let backend = match create_terminal_backend(id, self.pty_sender.clone(), Some(dir.clone())) {
+            Ok(backend) => backend,
+            Err(e) => {
+                log::warn!("Failed to create terminal: {}", e);
+                self.toast = Some(Toast::new(format!("Failed to create terminal: {}", e)));
+                return;
+            }
+        };
+        self.next_terminal_id += 1;
+
+        let project_root = crate::project::detect_project_root(&dir);
+        let terminal = TerminalInstance::new(backend, dir, project_root);
+        let ws = &mut self.workspaces[self.active_workspace];
+        if ws.root.replace_content(pane_id, TabContent::Terminal(terminal)).is_err() {
+            return;
+        }
+        if ws.follow_pane == Some(pane_id) {
+            ws.follow_pane = None;
+        }
+        log::info!("Pane {} converted to terminal {}", pane_id.0, id);
+
+        self.discover_shell_pid(id);
+        if self.current_workspace().focused_pane == pane_id {
+            self.sync_sidebar_root(pane_id);
+        }
+    }
+
+    /// Replace a terminal pane with a file viewer, shutting its shell down
+    fn convert_to_file_viewer(&mut self, pane_id: PaneId, path: PathBuf) {
+        if !path.is_file() {
+            self.toast = Some(Toast::new(format!("Not a file: {}", path.display())));
+            return;
+        }
+
+        let ws = &mut self.workspaces[self.active_workspace];
+        if !matches!(ws.get_content(pane_id), Some(TabContent::Terminal(_))) {
+            return;
+        }
+        if let Ok(TabContent::Terminal(mut terminal)) = ws.root.replace_content(pane_id, TabContent::file_viewer(path)) {
+            if let Some(tracker) = terminal.pty_tracker.take() {
+                crate::pty_tracker::release_shell_pid(&self.claimed_pids, tracker.pid());
+            }
+            // Dropping the backend shuts the PTY down; its Exit event no
+            // longer matches a pane
+            log::info!("Terminal {} in pane {} converted to file viewer", terminal.id, pane_id.0);
+            self.load_file_view(self.active_workspace, pane_id, LoadMode::Preview);
+        }
+    }
+
+    /// Convert a terminal pane to the sidebar's selected file, or ask for one
+    fn request_file_viewer_conversion(&mut self, pane_id: PaneId) {
+        let ws = self.current_workspace();
+        let Some(TabContent::Terminal(terminal)) = ws.get_content(pane_id) else {
+            return;
+        };
+        match ws.selected_sidebar_file() {
+            Some(path) => self.convert_to_file_viewer(pane_id, path),
+            None => {
+                let dir = format!("{}/", terminal.current_dir.display());
+                self.file_viewer_prompt = Some((pane_id, dir));
+            }
+        }
+    }
+
+    /// Right-click menu for a pane
+    fn show_pane_menu(&mut self, ctx: &Context) {
+        let Some((pane_id, pos)) = self.pane_menu else {
+            return;
+        };
+        let (is_terminal, can_convert, can_follow) = match self.current_workspace().get_content(pane_id) {
+            Some(TabContent::Terminal(_)) => (true, false, false),
+            Some(TabContent::FileViewer { .. }) => (false, true, true),
+            Some(TabContent::Empty) => (false, true, false),
+            Some(TabContent::Diff(_) | TabContent::Stats(_) | TabContent::ColorTest(_)) => (false, false, false),
+            None => {
+                self.pane_menu = None;
+                return;
+            }
+        };
+
+        let mut chosen = false;
+        let area = egui::Area::new(egui::Id::new("pane_menu"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(pos)
+            .show(ctx, |ui| {
+                Frame::menu(ui.style())
+                    .fill(self.theme.surface)
+                    .stroke(egui::Stroke::new(1.0, self.theme.border))
+                    .show(ui, |ui| {
+                        if is_terminal {
+                            if let Some(line) = self.pane_menu_line {
+                                if ui.button("Copy Logical Line").on_hover_text("Copy the whole line under the pointer, without its wraps").clicked() {
+                                    chosen = true;
+                                    self.copy_logical_line(pane_id, line);
+                                }
+                            }
+                            if ui.button("Convert to File Viewer…").clicked() {
+                                chosen = true;
+                                self.request_file_viewer_conversion(pane_id);
+                            }
+                            let mirrored = self.mirror.is_some() && self.mirror == self.current_workspace().terminal_id(pane_id);
+                            let label = if mirrored { "✓ Mirror to New Window" } else { "Mirror to New Window" };
+                            if ui.button(label).clicked() {
+                                chosen = true;
+                                self.toggle_mirror(pane_id);
+                            }
+                        }
+                        if can_convert && ui.button("Convert to Terminal Here").clicked() {
+                            chosen = true;
+                            self.convert_to_terminal(pane_id);
+                        }
+                        if can_follow {
+                            let following = self.current_workspace().follow_pane == Some(pane_id);
+                            let label = if following { "✓ Follow Sidebar Selection" } else { "Follow Sidebar Selection" };
+                            if ui.button(label).clicked() {
+                                chosen = true;
+                                self.toggle_follow_sidebar(pane_id);
+                            }
+                        }
+                        if ui.button("Move to New Tab").clicked() {
+                            chosen = true;
+                            self.move_pane_to_new_tab(pane_id, self.active_workspace + 1);
+                        }
+
+                        ui.separator();
+                        let current = self.current_workspace().pane_tint(pane_id);
+                        let swatches = [
+                            ("Red", self.theme.red),
+                            ("Yellow", self.theme.yellow),
+                            ("Green", self.theme.green),
+                            ("Cyan", self.theme.cyan),
+                            ("Blue", self.theme.blue),
+                            ("Magenta", self.theme.magenta),
+                        ];
+                        ui.horizontal(|ui| {
+                            ui.label("Tint");
+                            for (name, color) in swatches {
+                                let (rect, response) = ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::click());
+                                ui.painter().rect_filled(rect, 3.0, color);
+                                if current == Some(color) {
+                                    ui.painter().rect_stroke(rect, 3.0, egui::Stroke::new(1.5, self.theme.text), egui::StrokeKind::Outside);
+                                }
+                                if response.on_hover_text(name).clicked() {
+                                    chosen = true;
+                                    self.current_workspace_mut().set_pane_tint(pane_id, Some(color));
+                                }
+                            }
+                            if current.is_some() && ui.small_button("Clear").clicked() {
+                                chosen = true;
+                                self.current_workspace_mut().set_pane_tint(pane_id, None);
+                            }
+                        });
+                    });
+            });
+
+        let clicked_outside = ctx.input(|i| {
+            i.key_pressed(Key::Escape)
+                || (i.pointer.button_clicked(egui::PointerButton::Primary)
+                    && i.pointer.interact_pos().is_some_and(|p| !area.response.rect.contains(p)))
+        });
+        if chosen || clicked_outside {
+            self.pane_menu = None;
+        }
+    }
+
+    /// Ask for the file a terminal pane should show instead
+    fn show_file_viewer_prompt(&mut self, ctx: &Context) {
+        let Some((pane_id, input)) = &mut self.file_viewer_prompt else {
+            return;
+        };
+        let pane_id = *pane_id;
+
+        let mut submit = false;
+        let mut close = false;
+        egui::Window::new("Convert to File Viewer")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 100.0))
+            .fixed_size(egui::vec2(420.0, 0.0))
+            .frame(Frame::window(&ctx.style())
+                .fill(self.theme.surface)
+                .stroke(egui::Stroke::new(1.0, self.theme.border)))
+            .show(ctx, |ui| {
+                let response = ui.add(egui::TextEdit::singleline(input)
+                    .font(theme::ui_font(13.0))
+                    .desired_width(f32::INFINITY)
+                    .hint_text("File path"));
+                response.request_focus();
+                if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                    submit = true;
+                }
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    submit |= ui.button("Open").clicked();
+                    close |= ui.button("Cancel").clicked();
+                });
+                close |= ui.input(|i| i.key_pressed(Key::Escape));
+            });
+
+        if submit {
+            if let Some((_, input)) = self.file_viewer_prompt.take() {
+                // Relative paths are relative to the terminal's directory
+                let path = match self.current_workspace().get_content(pane_id) {
+                    Some(TabContent::Terminal(terminal)) => terminal.current_dir.join(input.trim()),
+                    _ => PathBuf::from(input.trim()),
+                };
+                self.convert_to_file_viewer(pane_id, path);
+            }
+        } else if close {
+            self.file_viewer_prompt = None;
+        }
+    }
+
+    /// Ask for the label of a new scrollback mark
+    fn show_mark_prompt(&mut self, ctx: &Context) {
+        let Some(prompt) = &mut self.mark_prompt else {
+            return;
+        };
+
+        let mut submit = false;
+        let mut close = false;
+        egui::Window::new("Add Mark")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 100.0))
+            .fixed_size(egui::vec2(420.0, 0.0))
+            .frame(Frame::window(&ctx.style())
+                .fill(self.theme.surface)
+                .stroke(egui::Stroke::new(1.0, self.theme.border)))
+            .show(ctx, |ui| {
+                if !prompt.preview.is_empty() {
+                    ui.label(egui::RichText::new(&prompt.preview)
+                        .font(theme::ui_font(11.0))
+                        .color(self.theme.text_dim));
+                    ui.add_space(4.0);
+                }
+                let response = ui.add(egui::TextEdit::singleline(&mut prompt.input)
+                    .font(theme::ui_font(13.0))
+                    .desired_width(f32::INFINITY)
+                    .hint_text("Label (blank for the time)"));
+                // Before taking focus back, which would hide the loss
+                if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                    submit = true;
+                }
+                response.request_focus();
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    submit |= ui.button("Add").clicked();
+                    close |= ui.button("Cancel").clicked();
+                });
+                close |= ui.input(|i| i.key_pressed(Key::Escape));
+            });
+
+        if submit {
+            if let Some(prompt) = self.mark_prompt.take() {
+                self.add_mark(prompt);
+            }
+        } else if close {
+            self.mark_prompt = None;
+        }
+    }
+
+    /// Ask before deleting a sidebar item for good
+    ///
+    /// Delete is a dangerous action like those in the command palette: it
+    /// runs on a second click or Enter, or Enter held down.
+    fn show_delete_confirm(&mut self, ctx: &Context) {
+        let Some(path) = self.delete_confirm.clone() else {
+            return;
+        };
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let what = if path.is_dir() { "folder" } else { "file" };
+        let now = std::time::Instant::now();
+        let armed = self.delete_confirmation.armed(now).is_some();
+        let hold = self.delete_confirmation.hold_progress(now);
+
+        let mut clicked = false;
+        let mut entered = false;
+        let mut close = false;
+        egui::Window::new("Delete Permanently")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 100.0))
+            .fixed_size(egui::vec2(420.0, 0.0))
+            .frame(Frame::window(&ctx.style())
+                .fill(self.theme.surface)
+                .stroke(egui::Stroke::new(1.0, self.theme.border)))
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new(format!("Permanently delete the {} \"{}\"?", what, name))
+                    .font(theme::ui_font(13.0))
+                    .color(self.theme.text));
+                ui.label(egui::RichText::new("It won't go to the trash, and this can't be undone.")
+                    .font(theme::ui_font(11.0))
+                    .color(self.theme.text_dim));
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    let delete = egui::Button::new(egui::RichText::new(format!("Delete {}", name)).color(self.theme.red));
+                    let delete = ui.add(delete);
+                    clicked |= delete.clicked();
+                    // Fills up while Enter is held
+                    if let Some(progress) = hold {
+                        let rect = delete.rect;
+                        let right = rect.left() + rect.width() * progress;
+                        ui.painter().hline(rect.left()..=right, rect.bottom() + 1.0, egui::Stroke::new(2.0, self.theme.red));
+                    }
+                    close |= ui.button("Cancel").clicked();
+                    if armed {
+                        ui.label(egui::RichText::new("Press again to confirm")
+                            .font(theme::ui_font(10.0))
+                            .color(self.theme.red));
+                    }
+                });
+                entered |= ui.input(|i| i.key_pressed(Key::Enter));
+                close |= ui.input(|i| i.key_pressed(Key::Escape));
+            });
+
+        if close {
+            self.delete_confirm = None;
+            self.delete_confirmation.reset();
+            return;
+        }
+        let confirmed = (clicked && self.delete_confirmation.click(path.clone(), now))
+            || (entered && self.delete_confirmation.press(path.clone(), now))
+            || self.delete_confirmation.hold(ctx.input(|i| i.key_down(Key::Enter)), now);
+        if !confirmed {
+            if self.delete_confirmation.armed(now).is_some() {
+                ctx.request_repaint_after(std::time::Duration::from_millis(50));
+            }
+            return;
+        }
+        self.delete_confirm = None;
+        let message = match crate::trash::delete_permanently(&path) {
+            Ok(()) => format!("Deleted {} permanently", name),
+            Err(e) => {
+                log::warn!("{}", e);
+                e
+            }
+        };
+        self.toast = Some(Toast::new(message));
+        self.refresh_after_file_removal();
+    }
+
+    /// Move sidebar items to the trash, with one toast to undo them all
+    fn trash_paths(&mut self, paths: Vec<PathBuf>) {
+        let trash = crate::trash::Trash::user();
+        let mut trashed = Vec::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            match trash.remove(&path) {
+                Ok(item) => trashed.push(item),
+                Err(e) => {
+                    log::warn!("{}", e);
+                    errors.push(e);
+                }
+            }
+        }
+        if !trashed.is_empty() || !errors.is_empty() {
+            self.refresh_after_file_removal();
+        }
+
+        let deleted = match trashed.as_slice() {
+            [] => None,
+            [item] => Some(format!("Deleted {}", item.name())),
+            items => Some(format!("Deleted {} items", items.len())),
+        };
+        self.toast = match (deleted, errors.first()) {
+            (Some(message), None) => Some(Toast::with_action(message, ToastAction::UndoDelete(trashed))),
+            (Some(message), Some(e)) => Some(Toast::with_action(
+                format!("{} ({} failed: {})", message, errors.len(), e),
+                ToastAction::UndoDelete(trashed),
+            )),
+            (None, Some(e)) => Some(Toast::new(e.clone())),
+            (None, None) => None,
+        };
+    }
+
+    /// Put trashed items back, from the undo toast
+    fn restore_trashed(&mut self, items: Vec<crate::trash::Trashed>) {
+        let mut restored = 0;
+        let mut errors = Vec::new();
+        for item in &items {
+            match item.restore() {
+                Ok(()) => restored += 1,
+                Err(e) => {
+                    log::warn!("{}", e);
+                    errors.push(e);
+                }
+            }
+        }
+        self.refresh_after_file_removal();
+        self.toast = Some(Toast::new(match (errors.first(), items.as_slice()) {
+            (Some(e), _) => e.clone(),
+            (None, [item]) => format!("Restored {}", item.name()),
+            (None, _) => format!("Restored {} items", restored),
+        }));
+    }
+
+    /// Reload the sidebar and git status after files were removed or restored
+    fn refresh_after_file_removal(&mut self) {
+        let root = self.current_workspace().sidebar_root.clone();
+        self.load_directory_async(self.active_workspace, root);
+        if self.context_manager.is_git_available() {
+            self.context_manager.refresh_git_status();
+        }
+    }
+
+    /// Pin the focused terminal's selection into a floating note
+    fn pin_selected_output(&mut self) {
+        let id = self.next_note_id;
+        let ws = self.current_workspace_mut();
+        let Some(text) = ws.selection_text(ws.focused_pane) else {
+            return;
+        };
+        if ws.notes.len() >= crate::ui::MAX_NOTES_PER_WORKSPACE {
+            log::warn!("Output note limit ({}) reached", crate::ui::MAX_NOTES_PER_WORKSPACE);
+            return;
+        }
+
+        ws.notes.push(OutputNote::new(id, text));
+        self.next_note_id += 1;
+    }
+
+    /// Show the active workspace's output notes above the panes
+    fn show_output_notes(&mut self, ctx: &Context, bounds: egui::Rect) {
+        let workspace = self.active_workspace;
+        let theme = &self.theme;
+        let mut editing = false;
+
+        self.workspaces[workspace].notes.retain_mut(|note| {
+            let response = OutputNoteWindow::new(note, workspace, bounds, theme).show(ctx);
+            editing |= response.editing;
+            !response.closed
+        });
+
+        self.note_editing = editing;
+    }
+
+    /// Low-latency repaint path after writing to a PTY
+    ///
+    /// Repaints right away and once more shortly after, so the echo shows
+    /// up without waiting for the idle repaint timer. egui_term already
+    /// requests a repaint on every PTY wakeup; this covers the echo racing
+    /// the current frame.
+    fn note_terminal_input(&mut self) {
+        let now = std::time::Instant::now();
+        self.ctx.request_repaint();
+        self.echo_repaint_at = Some(now + crate::latency::ECHO_REPAINT_DELAY);
+
+        if self.config.advanced.show_latency_overlay {
+            if let Some(fingerprint) = self.focused_terminal_fingerprint() {
+                self.latency_probe.start(now, fingerprint);
+            }
+        }
+    }
+
+    /// Grid fingerprint of the focused terminal (for latency measurement)
+    fn focused_terminal_fingerprint(&self) -> Option<u64> {
+        let ws = self.current_workspace();
+        match ws.get_content(ws.focused_pane)? {
+            TabContent::Terminal(terminal) => Some(crate::latency::grid_fingerprint(terminal.backend.grid())),
+            _ => None,
+        }
+    }
+
+    /// Draw the input latency overlay in the top-right corner
+    fn show_latency_overlay(&self, ui: &egui::Ui) {
+        let format_ms = |d: Option<std::time::Duration>| match d {
+            Some(d) => format!("{:.1}", d.as_secs_f64() * 1000.0),
+            None => "--".to_string(),
+        };
+        let mut text = format!(
+            "key→glyph {} ms (avg {} ms)",
+            format_ms(self.latency_probe.last()),
+            format_ms(self.latency_probe.average()),
+        );
+        let ws = self.current_workspace();
+        if let Some(TabContent::Terminal(terminal)) = ws.get_content(ws.focused_pane) {
+            let rate = self.throughput.rate(terminal.id, std::time::Instant::now());
+            text.push_str(&format!(" · output {}", crate::throughput::format_rate(rate)));
+        }
+        if self.startup.is_complete() {
+            text.push_str(&format!(" · startup {} ms", self.startup.total().as_millis()));
+        }
+
+        let anchor = ui.max_rect().right_top() + egui::vec2(-8.0, 8.0);
+        let painter = ui.painter();
+        let galley = painter.layout_no_wrap(text, theme::ui_font(10.0), self.theme.text_dim);
+        let rect = egui::Rect::from_min_size(anchor - egui::vec2(galley.size().x, 0.0), galley.size())
+            .expand(4.0);
+        painter.rect_filled(rect, 2.0, self.theme.surface);
+        painter.galley(rect.min + egui::vec2(4.0, 4.0), galley, self.theme.text_dim);
+    }
+
+    /// Panes that "Run in All Panes" can target, across all workspaces
+    fn run_targets(&self) -> Vec<crate::run_all::RunTarget> {
+        let mut targets = Vec::new();
+        for (idx, ws) in self.workspaces.iter().enumerate() {
+            collect_run_targets(&ws.root, idx, &mut targets);
+        }
+        targets
+    }
+
+    /// Queue a command for every selected terminal
+    fn start_run_all(&mut self, request: RunAllRequest) {
+        for label in &request.skipped {
+            log::info!("Run in all panes: skipping read-only {}", label);
+        }
+        if self.run_all_job.is_some() {
+            log::warn!("Run in all panes: replacing unfinished run");
+        }
+
+        self.run_all_job = Some(RunAllJob {
+            command: request.command,
+            pending: request.terminals.into(),
+            next_at: std::time::Instant::now(),
+            sent: 0,
+            closed: 0,
+            skipped: request.skipped.len(),
+        });
+    }
+
+    /// Send the queued command to the next terminal once its stagger has passed
+    fn process_run_all_job(&mut self, ctx: &Context) {
+        let Some(job) = &mut self.run_all_job else {
+            return;
+        };
+
+        let now = std::time::Instant::now();
+        if now < job.next_at {
+            ctx.request_repaint_after(job.next_at - now);
+            return;
+        }
+
+        if let Some((terminal_id, label)) = job.pending.pop_front() {
+            let terminal = self.workspaces
+                .iter_mut()
+                .find_map(|ws| ws.get_terminal_mut(terminal_id));
+            match terminal {
+                Some(terminal) => {
+                    terminal.backend.process_command(
+                        BackendCommand::Write(format!("{}\n", job.command).into_bytes())
+                    );
+                    log::info!("Run in all panes: sent to {}", label);
+                    job.sent += 1;
+                }
+                None => {
+                    log::warn!("Run in all panes: {} closed before sending", label);
+                    job.closed += 1;
+                }
+            }
+
+            let stagger = std::time::Duration::from_millis(self.config.ui.run_all_stagger_ms);
+            job.next_at = now + stagger;
+            if !job.pending.is_empty() {
+                ctx.request_repaint_after(stagger);
+                return;
+            }
+        }
+
+        let mut summary = format!("Ran in {} terminal(s)", job.sent);
+        if job.skipped > 0 {
+            summary.push_str(&format!(", skipped {} read-only", job.skipped));
+        }
+        if job.closed > 0 {
+            summary.push_str(&format!(", {} closed", job.closed));
+        }
+        log::info!("Run in all panes: {}", summary);
+        self.run_all_job = None;
+        self.toast = Some(Toast::new(summary));
+    }
+
+    /// Show the current toast above the status bar until it expires
+    fn show_toast(&mut self, ctx: &Context) {
+        let Some(toast) = &self.toast else {
+            return;
+        };
+        let duration = toast.action.as_ref().map_or(TOAST_DURATION, ToastAction::duration);
+        let Some(remaining) = duration.checked_sub(toast.posted.elapsed()) else {
+            self.toast = None;
+            return;
+        };
+
+        let mut clicked = false;
+        egui::Area::new(egui::Id::new("toast"))
+            .order(egui::Order::Tooltip)
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -(theme::ui_scaled(self.config.ui.status_bar_height) + 12.0)))
+            .interactable(toast.action.is_some())
+            .show(ctx, |ui| {
+                Frame::NONE
+                    .fill(self.theme.surface)
+                    .stroke(egui::Stroke::new(1.0, self.theme.border))
+                    .inner_margin(egui::Margin::symmetric(12, 6))
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(toast.message.as_str())
+                                .font(theme::ui_font(11.0))
+                                .color(self.theme.text));
+                            if let Some(action) = &toast.action {
+                                clicked = ui.small_button(action.label()).clicked();
+                            }
+                        });
+                    });
+            });
+        ctx.request_repaint_after(remaining);
+
+        if clicked {
+            match self.toast.take().and_then(|mut t| t.action.take()) {
+                Some(ToastAction::OpenProjectLayout(root)) => self.open_project_layout(root),
+                Some(ToastAction::UndoDelete(items)) => self.restore_trashed(items),
+                None => {}
+            }
+        }
+    }
+
+    /// Offer a project's layout file once per root
+    fn offer_project_layout(&mut self, root: PathBuf) {
+        if !crate::project::has_project_layout(&root) || !self.offered_layouts.insert(root.clone()) {
+            return;
+        }
+        let name = root.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        self.toast = Some(Toast::with_action(
+            format!("{} has a {}", name, crate::project::PROJECT_LAYOUT_FILE),
+            ToastAction::OpenProjectLayout(root),
+        ));
+    }
+
+    /// Open a project's layout file in a new tab
+    ///
+    /// Layouts with commands ask for consent the first time (and again
+    /// whenever the commands change).
+    fn open_project_layout(&mut self, root: PathBuf) {
+        let layout = match crate::project::load_project_layout(&root) {
+            Ok(layout) => layout,
+            Err(e) => {
+                log::warn!("Project layout {}: {}", root.display(), e);
+                self.toast = Some(Toast::new(e));
+                return;
+            }
+        };
+        for warning in &layout.warnings {
+            log::warn!("{} in {}: {}", crate::project::PROJECT_LAYOUT_FILE, root.display(), warning);
+        }
+
+        if self.project_consent.needs_prompt(&layout) {
+            self.consent_prompt = Some(layout);
+        } else {
+            self.build_project_workspace(layout, true);
+        }
+    }
+
+    /// Create the tab for a project layout, optionally running its commands
+    fn build_project_workspace(&mut self, layout: crate::project::ProjectLayout, run_commands: bool) {
+        let first_id = self.next_terminal_id;
+        let workspace = match Workspace::from_project(&layout, first_id, self.pty_sender.clone()) {
+            Ok(workspace) => workspace,
+            Err(e) => {
+                log::warn!("Failed to open project layout: {}", e);
+                self.toast = Some(Toast::new(format!("Failed to open project layout: {}", e)));
+                return;
+            }
+        };
+        self.next_terminal_id += layout.panes.len() as u64;
+        let ws_idx = self.add_workspace(workspace, self.config.ui.new_tab_position);
+
+        for (index, pane) in layout.panes.iter().enumerate() {
+            let terminal_id = first_id + index as u64;
+            self.discover_shell_pid(terminal_id);
+
+            // The PTY buffers the line until the shell reads it
+            if let (true, Some(command)) = (run_commands, &pane.command) {
+                if let Some(terminal) = self.workspaces[ws_idx].get_terminal_mut(terminal_id) {
+                    terminal.backend.process_command(BackendCommand::Write(format!("{}\n", command).into_bytes()));
+                }
+            }
+        }
+
+        self.context_manager.set_active_directory(&layout.root);
+        self.load_directory_async(ws_idx, layout.root);
+    }
+
+    /// Ask before running a project's layout commands
+    fn show_project_consent(&mut self, ctx: &Context) {
+        let Some(layout) = &self.consent_prompt else {
+            return;
+        };
+
+        let mut choice = None;
+        let mut cancel = false;
+        egui::Window::new("Run Project Commands?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .frame(Frame::window(&ctx.style())
+                .fill(self.theme.surface)
+                .stroke(egui::Stroke::new(1.0, self.theme.border)))
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new(format!(
+                    "{} in {} wants to run:",
+                    crate::project::PROJECT_LAYOUT_FILE,
+                    layout.root.display()
+                ))
+                    .font(theme::ui_font(12.0))
+                    .color(self.theme.text));
+                ui.add_space(4.0);
+                for command in layout.commands() {
+                    ui.label(egui::RichText::new(format!("❯ {}", command))
+                        .font(theme::ui_font(12.0))
+                        .color(self.theme.yellow));
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Allow and Open").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Open Without Commands").clicked() {
+                        choice = Some(false);
+                    }
+                    if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if cancel {
+            self.consent_prompt = None;
+        } else if let Some(run_commands) = choice {
+            let Some(layout) = self.consent_prompt.take() else {
+                return;
+            };
+            if run_commands {
+                self.project_consent.grant(&layout);
+                if let Err(e) = self.project_consent.save() {
+                    log::warn!("{}", e);
+                }
+            }
+            self.build_project_workspace(layout, run_commands);
+        }
+    }
+
+    /// Route this frame's input events in one ordered pass
+    ///
+    /// IME state is updated first, shortcuts then see the untouched queue,
+    /// and app-handled writes (IME commits, Shift+Enter newlines) happen in
+    /// event order before consumed events are removed in a single retain.
+    fn process_input(&mut self, ctx: &Context) {
+        let events = ctx.input(|i| i.events.clone());
+        let ws = self.current_workspace();
+        let alt_screen = ws.is_alt_screen(ws.focused_pane);
+        let mut actions = self.input_router.route(&events, self.terminal_has_keyboard_focus(), alt_screen);
+
+        let swallowed = self.handle_shortcuts(ctx);
+        for (event, action) in events.iter().zip(actions.iter_mut()) {
+            let shortcut = matches!(event, Event::Key { key, .. } if Some(*key) == swallowed);
+            if shortcut || ui_scale_step(event).is_some() {
+                *action = InputAction::Drop;
+            }
+        }
+        self.start_terminal_on_keypress(&events);
+
+        // Keys the terminal view writes to the PTY, and End while scrolled back
+        if self.terminal_has_keyboard_focus() {
+            let ws = self.current_workspace_mut();
+            if let Some(TabContent::Terminal(terminal)) = ws.root.get_content_mut(ws.focused_pane) {
+                let detached = terminal.follow.unseen().is_some() && !alt_screen;
+                for (event, action) in events.iter().zip(actions.iter_mut()) {
+                    if !action.keeps_event() {
+                        continue;
+                    }
+                    match event {
+                        Event::Key { key: Key::End, pressed: true, modifiers, .. } if detached && modifiers.is_none() => {
+                            *action = InputAction::ScrollToBottom;
+                        }
+                        Event::Key { pressed: true, modifiers, .. } if !modifiers.command => terminal.typed = true,
+                        Event::Text(_) | Event::Paste(_) => terminal.typed = true,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        // Enter at the shell starts a command; its diagnostics replace the last ones
+        let submitted = events.iter().zip(&actions).any(|(event, action)| {
+            action.keeps_event() && matches!(
+                event,
+                Event::Key { key: Key::Enter, pressed: true, modifiers, .. } if modifiers.is_none()
+            )
+        });
+        if submitted && self.terminal_has_keyboard_focus() && !alt_screen {
+            self.record_submitted_command();
+            let ws = self.current_workspace_mut();
+            if let Some(TabContent::Terminal(terminal)) = ws.root.get_content_mut(ws.focused_pane) {
+                terminal.diagnostics.command_started(terminal.backend.grid());
+            }
+        }
+
+        for action in &actions {
+            match action {
+                InputAction::CommitText(text) => self.send_text_to_terminal(text),
+                InputAction::InsertNewline => self.send_text_to_terminal("\n"),
+                InputAction::ScrollPages(pages) => {
+                    let ws = self.current_workspace_mut();
+                    if let Some(TabContent::Terminal(terminal)) = ws.root.get_content_mut(ws.focused_pane) {
+                        terminal.scroll_pages(*pages);
+                    }
+                }
+                InputAction::ScrollToBottom => {
+                    let ws = self.current_workspace_mut();
+                    if let Some(TabContent::Terminal(terminal)) = ws.root.get_content_mut(ws.focused_pane) {
+                        terminal.scroll_to_line(0);
+                    }
+                }
+                InputAction::Pass | InputAction::Drop => {}
+            }
+        }
+
+        if actions.iter().all(InputAction::keeps_event) {
+            return;
+        }
+        ctx.input_mut(|i| {
+            let mut actions = actions.iter();
+            i.events.retain(|_| actions.next().is_none_or(InputAction::keeps_event));
+        });
+    }
+
+    /// Turn a focused empty pane into a terminal on its first keypress
+    ///
+    /// The events stay queued, so the new terminal's view gets them this frame.
+    fn start_terminal_on_keypress(&mut self, events: &[Event]) {
+        let ws = self.current_workspace();
+        let pane_id = ws.focused_pane;
+        if !matches!(ws.get_content(pane_id), Some(TabContent::Empty)) || !self.terminal_has_keyboard_focus() {
+            return;
+        }
+        let typed = events.iter().any(|event| match event {
+            Event::Text(_) | Event::Paste(_) | Event::Ime(ImeEvent::Commit(_)) => true,
+            Event::Key { pressed: true, modifiers, .. } => !modifiers.command && !modifiers.ctrl,
+            _ => false,
+        });
+        if typed {
+            self.convert_to_terminal(pane_id);
+        }
+    }
+
+    /// Handle native menu bar events
+    fn handle_menu_events(&mut self, ctx: &Context) {
+        while let Some(action) = menu::poll_menu_event() {
+            self.handle_menu_action(action, ctx);
+        }
+    }
+
+    /// Run an action picked from the native or in-window menu bar
+    fn handle_menu_action(&mut self, action: MenuAction, ctx: &Context) {
+        match action {
+            MenuAction::NewTab => self.create_new_tab(),
+            MenuAction::NewWindow => {
+                // TODO: Open new window
+                log::info!("New window requested");
+            }
+            MenuAction::CloseTab => self.close_current_pane(),
+            MenuAction::CloseWindow | MenuAction::Quit => {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            MenuAction::SplitHorizontal => self.split_pane_horizontal(),
+            MenuAction::SplitVertical => self.split_pane_vertical(),
+            MenuAction::ToggleSidebar => self.toggle_sidebar(),
+            MenuAction::Preferences => self.preferences_window.open(self.config.clone()),
+            MenuAction::OpenConfigFile => self.open_config_file(),
+            MenuAction::RevealConfigFolder => self.reveal_config_folder(),
+            MenuAction::EditConfig => self.edit_config(),
+            MenuAction::About => {
+                log::info!("About VibeTerm v{}", env!("CARGO_PKG_VERSION"));
+            }
+        }
+    }
+
+    /// Handle requests from later launches of VibeTerm
+    fn process_ipc_commands(&mut self, ctx: &Context) {
+        let Some(server) = &self.ipc_server else {
+            return;
+        };
+        let requests: Vec<_> = std::iter::from_fn(|| server.try_recv()).collect();
+        for request in requests {
+            match request.command.clone() {
+                crate::ipc::IpcCommand::NewTab { dir } => {
+                    let dir = dir.filter(|dir| dir.is_dir()).unwrap_or_else(launch_dir);
+                    self.create_new_tab_in(dir, self.config.ui.new_tab_position);
+                }
+                crate::ipc::IpcCommand::OpenFile { path } => self.open_path(path),
+                crate::ipc::IpcCommand::FocusWindow => {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                }
+                crate::ipc::IpcCommand::Context => {
+                    let snapshot = crate::context::ContextSnapshot::build(&self.context_manager);
+                    match serde_json::to_string(&snapshot) {
+                        Ok(json) => request.respond(json),
+                        Err(e) => request.respond(crate::ipc::error_response(&e.to_string())),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Type the context snapshot into the focused terminal at the cursor
+    ///
+    /// As the quoted path of a temp file, or with `heredoc` the whole
+    /// document assigned to `$VIBETERM_CONTEXT` in one bracketed paste (the
+    /// path again if the shell hasn't turned bracketed paste on).
+    fn insert_context(&mut self, heredoc: bool) {
+        let focused = self.current_workspace().focused_pane;
+        let bracketed = match self.current_workspace().get_content(focused) {
+            Some(TabContent::Terminal(terminal)) => terminal.backend.mode().contains(TerminalMode::BRACKETED_PASTE),
+            _ => {
+                self.toast = Some(Toast::new("Focus a terminal to insert the context"));
+                return;
+            }
+        };
+
+        let snapshot = crate::context::ContextSnapshot::build(&self.context_manager);
+        // Without bracketed paste the shell would run the here-doc line by
+        // line as it's typed; the file path is one safe word
+        let text = if heredoc && bracketed {
+            crate::context::snapshot::bracketed_paste(
+                &crate::context::snapshot::heredoc("VIBETERM_CONTEXT", &snapshot.to_markdown()),
+            )
+        } else {
+            if heredoc {
+                self.toast = Some(Toast::new("This shell doesn't take bracketed paste: inserted the context file instead"));
+            }
+            let dir = Config::cache_dir();
+            let now = std::time::SystemTime::now();
+            let ttl = std::time::Duration::from_secs(self.config.context.snapshot_ttl_mins * 60);
+            crate::context::snapshot::clean_up(&dir, ttl, now);
+            match crate::context::snapshot::write_temp(&dir, &snapshot, now) {
+                Ok(path) => crate::ui::shell_quote(&path),
+                Err(e) => {
+                    log::warn!("{}", e);
+                    self.toast = Some(Toast::new(e));
+                    return;
+                }
+            }
+        };
+        self.current_workspace_mut().write_to_pane(focused, &text);
+        if snapshot.files.is_empty() {
+            self.toast = Some(Toast::new("No pinned files: the context only names the project"));
+        }
+    }
+
+    /// Open the paths the OS sent (Finder, the Dock)
+    ///
+    /// Past `ui.dormant_tab_batch` folders, all but the last open dormant.
+    fn process_open_requests(&mut self) {
+        self.open_requests.extend(crate::open_events::take());
+        let paths = std::mem::take(&mut self.open_requests);
+        let folders = paths.iter().filter(|path| path.is_dir()).count();
+        let mut dormant = crate::dormant::dormant_in_batch(folders, self.config.ui.dormant_tab_batch);
+        for path in paths {
+            if dormant > 0 && path.is_dir() {
+                dormant -= 1;
+                self.open_dormant_tab(path);
+            } else {
+                self.open_path(path);
+            }
+        }
+    }
+
+    /// Add a tab for `dir`, its shell started when it's first shown
+    ///
+    /// Its file tree loads right away, so the sidebar is ready before the shell.
+    fn open_dormant_tab(&mut self, dir: PathBuf) {
+        let name = format!("shell-{}", self.workspaces.len() + 1);
+        let now = std::time::Instant::now();
+        let index = self.add_workspace(Workspace::dormant(name, dir, now), self.config.ui.new_tab_position);
+        self.dormant_wake_at = Some(now + std::time::Duration::from_millis(self.config.ui.dormant_wake_delay_ms));
+        let root = self.workspaces[index].sidebar_root.clone();
+        self.load_directory_async(index, root);
+    }
+
+    /// Start the terminals of a dormant tab, and load its files
+    fn wake_workspace(&mut self, index: usize) {
+        use crate::dormant::DormantPane;
+
+        let Some(layout) = self.workspaces.get_mut(index).and_then(|ws| ws.dormant.take()) else {
+            return;
+        };
+
+        // Pane IDs are the layout's pane indices
+        let mut terminal_ids = Vec::new();
+        let mut files = Vec::new();
+        let mut contents = Vec::with_capacity(layout.panes.len());
+        for (pane_index, pane) in layout.panes.into_iter().enumerate() {
+            let content = match pane {
+                DormantPane::Terminal { dir, name } => {
+                    let id = self.next_terminal_id;
+                    self.next_terminal_id += 1;
+                    match create_terminal_backend(id, self.pty_sender.clone(), Some(dir.clone())) {
+                        Ok(backend) => {
+                            let project_root = crate::project::detect_project_root(&dir);
+                            let mut terminal = TerminalInstance::new(backend, dir, project_root);
+                            terminal.name = name;
+                            terminal_ids.push(id);
+                            TabContent::Terminal(terminal)
+                        }
+                        Err(e) => {
+                            // The pane stays, empty, and starts a shell on a keypress
+                            log::warn!("Failed to start terminal in {}: {}", dir.display(), e);
+                            self.toast = Some(Toast::new(format!("Cannot start a shell in {}", dir.display())));
+                            TabContent::Empty
+                        }
+                    }
+                }
+                DormantPane::File(path) => {
+                    files.push(PaneId(pane_index as u64));
+                    TabContent::file_viewer(path)
+                }
+                DormantPane::Empty => TabContent::Empty,
+            };
+            contents.push(Some(content));
+        }
+
+        let ws = &mut self.workspaces[index];
+        ws.root = build_project_node(&layout.spec, &mut contents);
+        ws.focused_pane = PaneId(layout.focused as u64);
+        ws.next_pane_id = contents.len() as u64;
+
+        for id in terminal_ids {
+            self.discover_shell_pid(id);
+        }
+        for pane_id in files {
+            self.load_file_view(index, pane_id, LoadMode::Preview);
+        }
+    }
+
+    /// Once the app has been idle a while, start the newest dormant tabs
+    fn wake_idle_dormant_tabs(&mut self, ctx: &Context) {
+        let Some(at) = self.dormant_wake_at else {
+            return;
+        };
+        let now = std::time::Instant::now();
+        let delay = std::time::Duration::from_millis(self.config.ui.dormant_wake_delay_ms);
+        if ctx.input(|i| !i.events.is_empty() || i.pointer.any_down()) {
+            self.dormant_wake_at = Some(now + delay);
+            ctx.request_repaint_after(delay);
+            return;
+        }
+        if now < at {
+            ctx.request_repaint_after(at - now);
+            return;
+        }
+
+        self.dormant_wake_at = None;
+        let dormant: Vec<(usize, std::time::Instant)> = self.workspaces
+            .iter()
+            .enumerate()
+            .filter_map(|(index, ws)| ws.dormant.as_ref().map(|layout| (index, layout.since)))
+            .collect();
+        for index in crate::dormant::idle_wake_order(&dormant, self.config.ui.dormant_wake_count) {
+            self.wake_workspace(index);
+        }
+    }
+
+    /// Open a directory as a terminal tab rooted there, or a file in a file viewer tab
+    fn open_path(&mut self, path: PathBuf) {
+        if path.is_dir() {
+            self.create_new_tab_in(path, self.config.ui.new_tab_position);
+        } else if path.is_file() {
+            self.create_file_tab(path);
+        } else {
+            log::warn!("Cannot open {}: not found", path.display());
+            self.toast = Some(Toast::new(format!("Cannot open {}: not found", path.display())));
+        }
+    }
+
+    /// Process PTY events
+    fn process_pty_events(&mut self, ctx: &Context) {
+        while let Ok((terminal_id, event)) = self.pty_receiver.try_recv() {
+            match event {
+                PtyEvent::ChildExit(code) => {
+                    let terminal = self.workspaces
+                        .iter_mut()
+                        .find_map(|ws| ws.get_terminal_mut(terminal_id));
+                    if let Some(terminal) = terminal {
+                        terminal.exit_code = Some(code);
+                    }
+                }
+                PtyEvent::Exit => {
+                    log::info!("Terminal {} exited", terminal_id);
+                    // Find and remove the terminal
+                    let mut quick_exits = None;
+                    for workspace in &mut self.workspaces {
+                        if let Some(terminal) = workspace.get_terminal_mut(terminal_id) {
+                            if let Some(tracker) = terminal.pty_tracker.take() {
+                                crate::pty_tracker::release_shell_pid(&self.claimed_pids, tracker.pid());
+                            }
+                            if let Some(writer) = &self.session_log_writer {
+                                terminal.stop_logging(writer, self.config.terminal.session_logging.strip_ansi);
+                            }
+                            // A clean exit was asked for; only a failure or a signal
+                            // (no status) counts
+                            let failed = terminal.exit_code != Some(0);
+                            if failed && terminal.spawned_at.elapsed() < QUICK_EXIT_WINDOW {
+                                quick_exits = Some(terminal.quick_exits + 1);
+                            }
+                        }
+                        if let Some(pane_id) = workspace.find_pane_by_terminal_id(terminal_id) {
+                            // A shell that died on startup keeps its pane for the restart
+                            if workspace.pane_count() > 1 && quick_exits.is_none() {
+                                workspace.close_pane(pane_id);
+                            }
+                            break;
+                        }
+                    }
+                    if let Some(quick_exits) = quick_exits {
+                        self.handle_quick_exit(terminal_id, quick_exits);
+                    }
+                }
+                PtyEvent::Wakeup => {
+                    let terminal = self.workspaces
+                        .iter_mut()
+                        .find_map(|ws| ws.get_terminal_mut(terminal_id));
+                    if let Some(terminal) = terminal {
+                        terminal.output_pending = true;
+                        terminal.diagnostics_pending = true;
+                        terminal.throughput_pending = true;
+                        terminal.marks_pending = true;
+                        terminal.follow_pending = true;
+                        terminal.last_output = Some(std::time::Instant::now());
+                        if let Some(report) = terminal.backend.take_progress() {
+                            terminal.progress = crate::progress::ProgressStatus::apply(report, std::time::Instant::now());
+                        }
+                    }
+                }
+                PtyEvent::PtyWrite(text) => {
+                    // The backend's reply to a query the program sent
+                    let reply = crate::device_attributes::reply(&text, self.config.terminal.term_id);
+                    let terminal = self.workspaces
+                        .iter_mut()
+                        .find_map(|ws| ws.get_terminal_mut(terminal_id));
+                    if let Some(terminal) = terminal {
+                        terminal.backend.process_command(BackendCommand::Write(reply.into_bytes()));
+                    }
+                }
+                PtyEvent::Bell => {
+                    // No sound; a bell in the background asks for attention
+                    let focused = ctx.input(|i| i.viewport().focused.unwrap_or(true));
+                    if self.notifier.notify(crate::notifier::Notice::Bell) && !focused {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(
+                            egui::UserAttentionType::Informational,
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Drop progress that stopped updating, and show what's left on the
+    /// Dock icon
+    fn process_progress(&mut self, ctx: &Context) {
+        let now = std::time::Instant::now();
+        let mut jobs = Vec::new();
+        let mut next_frame: Option<std::time::Duration> = None;
+        for ws in &mut self.workspaces {
+            for (_, content) in ws.root.collect_contents_mut() {
+                let TabContent::Terminal(terminal) = content else {
+                    continue;
+                };
+                if terminal.progress.is_some_and(|status| status.is_expired(now)) {
+                    terminal.progress = None;
+                }
+                let Some(status) = terminal.progress else {
+                    continue;
+                };
+                jobs.push(status.progress);
+                let wait = if status.progress.state == crate::progress::ProgressState::Indeterminate {
+                    PROGRESS_PULSE_FRAME
+                } else {
+                    (status.updated + crate::progress::PROGRESS_TIMEOUT).saturating_duration_since(now)
+                };
+                next_frame = Some(next_frame.map_or(wait, |next| next.min(wait)));
+            }
+        }
+        if let Some(wait) = next_frame {
+            ctx.request_repaint_after(wait);
+        }
+
+        let dock = crate::progress::combine(jobs);
+        if dock != self.dock_progress {
+            self.dock_progress = dock;
+            if !crate::terminal_backend::headless() {
+                crate::progress::show_in_dock(dock);
+            }
+        }
+    }
+
+    /// Restart a shell that exited right after starting, or give up after a few in a row
+    fn handle_quick_exit(&mut self, terminal_id: u64, quick_exits: u32) {
+        let found = self.workspaces.iter().enumerate().find_map(|(index, ws)| {
+            ws.find_pane_by_terminal_id(terminal_id).map(|pane_id| (index, pane_id))
+        });
+        let Some((ws_index, pane_id)) = found else {
+            return;
+        };
+        let Some(terminal) = self.workspaces[ws_index].get_terminal_mut(terminal_id) else {
+            return;
+        };
+        terminal.quick_exits = quick_exits;
+        if quick_exits > MAX_SHELL_RESTARTS {
+            log::warn!("Shell {} exited right after starting {} times; not restarting", terminal.shell, quick_exits);
+            terminal.failure = Some(ShellFailure {
+                shell: terminal.shell.clone(),
+                exit_code: terminal.exit_code,
+            });
+            return;
+        }
+        log::warn!("Shell {} exited right after starting; restarting", terminal.shell);
+        self.restart_shell(ws_index, pane_id);
+    }
+
+    /// Replace a terminal pane's shell with a new one in the same directory
+    fn restart_shell(&mut self, ws_index: usize, pane_id: PaneId) {
+        let Some(TabContent::Terminal(old)) = self.workspaces[ws_index].get_content(pane_id) else {
+            return;
+        };
+        let dir = old.current_dir.clone();
+        let project_root = old.project_root.clone();
+        let name = old.name.clone();
+        let quick_exits = old.quick_exits;
+
+        let id = self.next_terminal_id;
+        let backend = match create_terminal_backend(id, self.pty_sender.clone(), Some(dir.clone())) {
+            Ok(backend) => backend,
+            Err(e) => {
+                log::warn!("Failed to restart terminal: {}", e);
+                self.toast = Some(Toast::new(format!("Failed to create terminal: {}", e)));
+                return;
+            }
+        };
+        self.next_terminal_id += 1;
+
+        let mut terminal = TerminalInstance::new(backend, dir, project_root);
+        terminal.name = name;
+        terminal.quick_exits = quick_exits;
+        if self.workspaces[ws_index].root.replace_content(pane_id, TabContent::Terminal(terminal)).is_ok() {
+            self.discover_shell_pid(id);
+        }
+    }
+
+    /// Start, stop and feed per-pane session logs
+    ///
+    /// New output is captured at most every `LOG_CAPTURE_INTERVAL`. A log
+    /// that fails to write is turned off for its pane with a toast.
+    fn process_session_logs(&mut self, ctx: &Context) {
+        let settings = &self.config.terminal.session_logging;
+
+        let errors = self.session_log_writer
+            .as_ref()
+            .map(|writer| writer.poll_errors())
+            .unwrap_or_default();
+        for (terminal_id, error) in errors {
+            log::warn!("Session log for terminal {} stopped: {}", terminal_id, error);
+            let terminal = self.workspaces
+                .iter_mut()
+                .find_map(|ws| ws.get_terminal_mut(terminal_id));
+            if let Some(terminal) = terminal {
+                terminal.session_log = None;
+                terminal.logging_override = Some(false);
+            }
+            self.logging_terminals.remove(&terminal_id);
+            self.toast = Some(Toast::new(format!("Logging stopped: {}", error)));
+        }
+
+        let due = self.last_log_capture.elapsed() >= LOG_CAPTURE_INTERVAL;
+        if due {
+            self.last_log_capture = std::time::Instant::now();
+        }
+
+        let mut logging = std::collections::HashSet::new();
+        let mut pending = false;
+        for ws in &mut self.workspaces {
+            let ws_name = ws.name.clone();
+            for (pane_id, content) in ws.root.collect_contents_mut() {
+                let TabContent::Terminal(terminal) = content else {
+                    continue;
+                };
+                let wanted = terminal.logging_override.unwrap_or(settings.enabled);
+
+                if wanted && terminal.session_log.is_none() {
+                    let writer = self.session_log_writer.get_or_insert_with(SessionLogWriter::spawn);
+                    writer.open(
+                        terminal.id,
+                        crate::session_log::expand_home(&settings.directory),
+                        crate::session_log::log_name(&ws_name, pane_id.0),
+                    );
+                    terminal.backend.sync();
+                    terminal.session_log = Some(LineCapture::new(terminal.backend.grid()));
+                    log::info!("Session logging started for terminal {}", terminal.id);
+                } else if let (false, Some(writer)) = (wanted, &self.session_log_writer) {
+                    if terminal.session_log.is_some() {
+                        terminal.stop_logging(writer, settings.strip_ansi);
+                        log::info!("Session logging stopped for terminal {}", terminal.id);
+                    }
+                } else if let (true, Some(writer)) = (terminal.output_pending && due, &self.session_log_writer) {
+                    terminal.capture_output(writer, settings.strip_ansi);
+                }
+
+                if terminal.session_log.is_some() {
+                    logging.insert(terminal.id);
+                    pending |= terminal.output_pending;
+                }
+            }
+        }
+
+        // Close the files of terminals that went away with their pane or tab
+        if let Some(writer) = &self.session_log_writer {
+            for terminal_id in self.logging_terminals.difference(&logging) {
+                writer.close(*terminal_id);
+            }
+        }
+        self.logging_terminals = logging;
+
+        if pending {
+            ctx.request_repaint_after(LOG_CAPTURE_INTERVAL);
+        }
+    }
+
+    /// Move the scrollback marks of all panes along with their new output
+    fn process_scrollback_marks(&mut self) {
+        for ws in &mut self.workspaces {
+            for (_, content) in ws.root.collect_contents_mut() {
+                if let TabContent::Terminal(terminal) = content {
+                    if terminal.marks_pending && terminal.marks_capture.is_some() {
+                        terminal.backend.sync();
+                        terminal.sync_marks();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Look for compiler diagnostics in new output of the active tab
+    ///
+    /// Its panes are all on screen, so their last rendered grid is current.
+    /// The output is copied here and scanned on the tokio runtime.
+    fn process_diagnostics(&mut self, ctx: &Context) {
+        while let Ok((terminal_id, command, items)) = self.diagnostics_rx.try_recv() {
+            if let Some(terminal) = self.workspaces.iter_mut().find_map(|ws| ws.get_terminal_mut(terminal_id)) {
+                terminal.diagnostics.finish_scan(command, items);
+            }
+        }
+
+        let due = self.last_diagnostics_scan.elapsed() >= DIAGNOSTICS_SCAN_INTERVAL;
+        if due {
+            self.last_diagnostics_scan = std::time::Instant::now();
+        }
+
+        let mut waiting = false;
+        let ws = &mut self.workspaces[self.active_workspace];
+        for (_, content) in ws.root.collect_contents_mut() {
+            let TabContent::Terminal(terminal) = content else {
+                continue;
+            };
+            if !terminal.diagnostics_pending || terminal.is_alt_screen() {
+                continue;
+            }
+            if !due {
+                waiting = true;
+                continue;
+            }
+            let Some(scan) = terminal.diagnostics.begin_scan(terminal.backend.grid()) else {
+                // Tried again once the running scan is back
+                waiting = true;
+                continue;
+            };
+            terminal.diagnostics_pending = false;
+
+            let terminal_id = terminal.id;
+            let cwd = terminal.current_dir.clone();
+            let tx = self.diagnostics_tx.clone();
+            let ctx = ctx.clone();
+            self.tokio_runtime.spawn_blocking(move || {
+                let items = crate::diagnostics::find_diagnostics(&scan.output, &cwd);
+                let _ = tx.send((terminal_id, scan.command, items));
+                ctx.request_repaint();
+            });
+        }
+
+        if waiting {
+            ctx.request_repaint_after(DIAGNOSTICS_SCAN_INTERVAL);
+        }
+    }
+
+    /// Open the focused terminal's next diagnostic, cycling through them
+    fn jump_to_next_diagnostic(&mut self) {
+        let ws = self.current_workspace_mut();
+        let terminal_pane = ws.focused_pane;
+        let Some(TabContent::Terminal(terminal)) = ws.get_content_mut(terminal_pane) else {
+            return;
+        };
+        let Some(diagnostic) = terminal.diagnostics.next_diagnostic() else {
+            self.toast = Some(Toast::new("No diagnostics in the last command's output"));
+            return;
+        };
+
+        if self.config.diagnostics.open_in_editor {
+            self.open_in_editor(&diagnostic.path, diagnostic.line, diagnostic.column.unwrap_or(1));
+        } else {
+            self.show_diagnostic_in_viewer(terminal_pane, diagnostic);
+        }
+    }
+
+    /// Shell prompt pattern shared by Copy Last Output and command history
+    fn prompt_pattern(&self) -> Option<regex::Regex> {
+        let pattern = &self.config.terminal.copy_last_output.prompt_pattern;
+        match regex::Regex::new(pattern) {
+            Ok(prompt) => Some(prompt),
+            Err(e) => {
+                log::warn!("Invalid prompt pattern {:?}: {}", pattern, e);
+                None
+            }
+        }
+    }
+
+    /// Record the command on the focused terminal's cursor line as submitted
+    fn record_submitted_command(&mut self) {
+        let Some(prompt) = self.prompt_pattern() else {
+            return;
+        };
+        let ws = self.current_workspace();
+        let Some(TabContent::Terminal(terminal)) = ws.get_content(ws.focused_pane) else {
+            return;
+        };
+        let line = crate::command_history::cursor_line(terminal.backend.grid());
+        if let Some(command) = crate::command_history::command_on_line(&line, &prompt) {
+            self.record_command(command);
+        }
+    }
+
+    /// Record a command as just submitted in the focused terminal
+    fn record_command(&mut self, command: String) {
+        let ws = self.current_workspace();
+        let pane_id = ws.focused_pane;
+        let Some(TabContent::Terminal(terminal)) = ws.get_content(pane_id) else {
+            return;
+        };
+        let entry = crate::command_history::CommandEntry {
+            command,
+            terminal_id: terminal.id,
+            source: ws.pane_label(pane_id),
+            at: Some(std::time::SystemTime::now()),
+        };
+        self.command_history.record(entry);
+        if let Some(stats) = &mut self.stats {
+            stats.record(crate::stats::StatEvent::Command { at: crate::stats::unix_now() });
+        }
+    }
+
+    /// Feed the usage stats: the focused project, and tabs and panes opened
+    fn update_stats(&mut self, ctx: &Context) {
+        let Some(stats) = &mut self.stats else {
+            return;
+        };
+        let now = std::time::Instant::now();
+        let (focused, has_input) = ctx.input(|i| (i.focused, !i.events.is_empty()));
+        if has_input {
+            stats.note_input(now);
+        }
+        let ws = &self.workspaces[self.active_workspace];
+        let project = match ws.get_content(ws.focused_pane) {
+            Some(TabContent::Terminal(terminal)) => terminal.project_root.as_deref(),
+            _ => None,
+        };
+        stats.focus(focused.then(|| project.unwrap_or(&ws.sidebar_root)), now);
+        let panes = self.workspaces.iter().map(Workspace::pane_count).sum();
+        stats.count_open(self.workspaces.len(), panes);
+        stats.tick(now);
+    }
+
+    /// Open a Usage Stats tab
+    fn open_usage_stats(&mut self) {
+        let sidebar_root = self.current_workspace().sidebar_root.clone();
+        let workspace = Workspace::with_content("usage stats".to_string(), TabContent::Stats(None), sidebar_root);
+        self.add_workspace(workspace, self.config.ui.new_tab_position);
+        self.summarize_stats();
+    }
+
+    /// Write out recorded stats and summarize the file in the background
+    fn summarize_stats(&mut self) {
+        if let Some(stats) = &mut self.stats {
+            stats.flush(std::time::Instant::now());
+        }
+        let tx = self.stats_tx.clone();
+        let ctx = self.ctx.clone();
+        self.tokio_runtime.spawn_blocking(move || {
+            let _ = tx.send(crate::stats::summarize_file(&crate::stats::StatsRecorder::stats_path()));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Show a background stats summary in every Usage Stats pane
+    fn process_stats_results(&mut self) {
+        while let Ok(summary) = self.stats_rx.try_recv() {
+            for ws in &mut self.workspaces {
+                for (_, content) in ws.root.collect_contents_mut() {
+                    if let TabContent::Stats(shown) = content {
+                        *shown = Some(summary.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Act on a button of a Usage Stats pane
+    fn handle_stats_command(&mut self, command: crate::ui::StatsCommand) {
+        match command {
+            crate::ui::StatsCommand::Refresh => {}
+            crate::ui::StatsCommand::DeleteAll => {
+                let deleted = match &mut self.stats {
+                    Some(stats) => stats.delete_all(),
+                    None => crate::stats::delete_file(&crate::stats::StatsRecorder::stats_path()),
+                };
+                self.toast = Some(match deleted {
+                    Ok(()) => Toast::new("Deleted all usage stats"),
+                    Err(e) => {
+                        log::warn!("{}", e);
+                        Toast::new(e)
+                    }
+                });
+            }
+        }
+        self.summarize_stats();
+    }
+
+    /// Open a Terminal Color Test tab, drawn with the current terminal theme
+    fn open_color_test(&mut self) {
+        let sidebar_root = self.current_workspace().sidebar_root.clone();
+        let content = TabContent::ColorTest(crate::color_test::preview());
+        let workspace = Workspace::with_content("color test".to_string(), content, sidebar_root);
+        self.add_workspace(workspace, self.config.ui.new_tab_position);
+    }
+
+    /// Type the color test script into a terminal of the tab, splitting one off if there is none
+    fn write_color_test_script(&mut self, pane_id: PaneId) {
+        let ws = self.current_workspace();
+        let terminal_pane = ws.pane_ids()
+            .into_iter()
+            .find(|&id| matches!(ws.get_content(id), Some(TabContent::Terminal(_))));
+        let terminal_pane = match terminal_pane {
+            Some(id) => id,
+            None => {
+                self.current_workspace_mut().focused_pane = pane_id;
+                self.split_pane_vertical();
+                self.current_workspace().focused_pane
+            }
+        };
+
+        let ws = self.current_workspace_mut();
+        if let Some(TabContent::Terminal(terminal)) = ws.get_content_mut(terminal_pane) {
+            terminal.backend.process_command(BackendCommand::Write(crate::color_test::script().into_bytes()));
+            ws.focused_pane = terminal_pane;
+        }
+    }
+
+    /// Open history peek with the commands of every terminal in every tab
+    ///
+    /// Only from a terminal at its shell: fullscreen apps keep the key.
+    fn open_history_peek(&mut self) {
+        let ws = self.current_workspace();
+        if !matches!(ws.get_content(ws.focused_pane), Some(TabContent::Terminal(_))) || ws.is_alt_screen(ws.focused_pane) {
+            return;
+        }
+
+        let mut scrollback = Vec::new();
+        if let Some(prompt) = self.prompt_pattern() {
+            for ws in &self.workspaces {
+                for pane_id in ws.pane_ids() {
+                    let Some(TabContent::Terminal(terminal)) = ws.get_content(pane_id) else {
+                        continue;
+                    };
+                    let lines = crate::last_output::grid_lines(terminal.backend.grid());
+                    let source = ws.pane_label(pane_id);
+                    for command in crate::command_history::scan(&lines, &prompt).into_iter().rev() {
+                        scrollback.push(crate::command_history::CommandEntry {
+                            command,
+                            terminal_id: terminal.id,
+                            source: source.clone(),
+                            at: None,
+                        });
+                    }
+                }
+            }
+        }
+        self.history_peek.open(self.command_history.with_scrollback(scrollback));
+    }
+
+    /// List the SSH hosts, recently used first
+    fn open_ssh_palette(&mut self) {
+        let path = crate::ssh_config::user_config_path();
+        let mut hosts = crate::ssh_config::load(path.as_deref(), &self.config.ssh_hosts);
+        crate::ssh_config::RecentHosts::load().sort(&mut hosts);
+        self.ssh_palette.open(hosts);
+    }
+
+    /// Run `ssh` to `host` in a new tab, or in a split of the focused pane
+    fn connect_to_host(&mut self, host: &crate::ssh_config::SshHost, split: bool) {
+        let id = self.next_terminal_id;
+        if split {
+            self.split_pane_horizontal();
+        } else {
+            self.create_new_tab();
+        }
+
+        let ws = self.current_workspace_mut();
+        let Some(terminal) = ws.get_terminal_mut(id) else {
+            return;
+        };
+        terminal.name = Some(host.name.clone());
+        terminal.backend.process_command(BackendCommand::Write(format!("{}\n", host.command()).into_bytes()));
+        if !split {
+            ws.name = host.name.clone();
+        }
+        if let Some(tint) = host.tint {
+            let pane_id = ws.focused_pane;
+            ws.set_pane_tint(pane_id, Some(tint));
+        }
+    }
+
+    /// Ask for a label for a mark on the focused terminal's current line
+    fn open_mark_prompt(&mut self) {
+        let ws = &mut self.workspaces[self.active_workspace];
+        let Some(TabContent::Terminal(terminal)) = ws.root.get_content_mut(ws.focused_pane) else {
+            return;
+        };
+        terminal.backend.sync();
+        if terminal.is_alt_screen() {
+            self.toast = Some(Toast::new("Marks need the scrollback; leave the fullscreen app first"));
+            return;
+        }
+        terminal.sync_marks();
+        let line = terminal.mark_line();
+        let text = crate::session_log::row_text(terminal.backend.grid(), Line(line), true);
+        self.mark_prompt = Some(MarkPrompt {
+            terminal_id: terminal.id,
+            position: terminal.marks.position(line),
+            preview: text.trim().chars().take(MARK_PREVIEW_CHARS).collect(),
+            input: String::new(),
+        });
+    }
+
+    /// Add the prompt's mark, labelled with the time if left blank
+    fn add_mark(&mut self, prompt: MarkPrompt) {
+        let label = match prompt.input.trim() {
+            "" => {
+                let minutes = crate::session_log::local_minutes();
+                format!("{:02}:{:02}", minutes / 60, minutes % 60)
+            }
+            label => label.to_string(),
+        };
+        let terminal = self.workspaces
+            .iter_mut()
+            .find_map(|ws| ws.get_terminal_mut(prompt.terminal_id));
+        // The pane closed while the prompt was open
+        let Some(terminal) = terminal else {
+            return;
+        };
+        terminal.backend.sync();
+        terminal.sync_marks();
+        terminal.marks.add(label.clone(), prompt.position, prompt.preview, std::time::SystemTime::now());
+        self.toast = Some(Toast::new(format!("Marked \"{}\"", label)));
+    }
+
+    /// List the focused terminal's marks to jump to
+    fn open_mark_list(&mut self) {
+        let ws = &mut self.workspaces[self.active_workspace];
+        let Some(TabContent::Terminal(terminal)) = ws.root.get_content_mut(ws.focused_pane) else {
+            return;
+        };
+        terminal.backend.sync();
+        if terminal.marks_capture.is_some() {
+            terminal.sync_marks();
+        }
+        self.mark_list.open(terminal.marks.marks().to_vec());
+    }
+
+    /// Scroll the focused terminal to its mark at `idx`
+    fn jump_to_mark(&mut self, idx: usize) {
+        let ws = &mut self.workspaces[self.active_workspace];
+        let Some(TabContent::Terminal(terminal)) = ws.root.get_content_mut(ws.focused_pane) else {
+            return;
+        };
+        if terminal.is_alt_screen() {
+            self.toast = Some(Toast::new("Leave the fullscreen app to jump to a mark"));
+            return;
+        }
+        match terminal.marks.grid_line(idx) {
+            Some(line) => terminal.scroll_to_line(line),
+            None => {
+                if let Some(mark) = terminal.marks.marks().get(idx) {
+                    self.toast = Some(Toast::new(format!("\"{}\" scrolled out of the history", mark.label)));
+                }
+            }
+        }
+    }
+
+    /// Scroll the focused terminal to the mark above or below the top of its view
+    fn step_mark(&mut self, forward: bool) {
+        let ws = &mut self.workspaces[self.active_workspace];
+        let Some(TabContent::Terminal(terminal)) = ws.root.get_content_mut(ws.focused_pane) else {
+            return;
+        };
+        if terminal.is_alt_screen() {
+            return;
+        }
+        let top = terminal.top_line();
+        let line = if forward { terminal.marks.next(top) } else { terminal.marks.previous(top) };
+        match line {
+            Some(line) => terminal.scroll_to_line(line),
+            None if terminal.marks.is_empty() => self.toast = Some(Toast::new("No marks in this pane")),
+            None => self.toast = Some(Toast::new(if forward { "No later mark" } else { "No earlier mark" })),
+        }
+    }
+
+    /// Copy the focused terminal's last command output to the clipboard
+    fn copy_last_output(&mut self) {
+        let ws = self.current_workspace();
+        let Some(TabContent::Terminal(terminal)) = ws.get_content(ws.focused_pane) else {
+            self.toast = Some(Toast::new("Copy Last Output works in terminal panes"));
+            return;
+        };
+        if terminal.is_alt_screen() {
+            self.toast = Some(Toast::new("No command output while a fullscreen app is running"));
+            return;
+        }
+
+        let settings = &self.config.terminal.copy_last_output;
+        let prompt = self.prompt_pattern();
+        let lines = crate::last_output::grid_lines(terminal.backend.grid());
+        let output = crate::last_output::extract(&lines, prompt.as_ref(), settings.fallback_lines);
+        if output.text.is_empty() {
+            self.toast = Some(Toast::new("The last command printed nothing"));
+            return;
+        }
+
+        let copied = self.copy_text(output.text, "Copy Last Output".to_string());
+        self.toast = Some(Toast::new(match copied {
+            Err(e) => {
+                log::warn!("Failed to copy output: {}", e);
+                format!("Failed to copy output: {}", e)
+            }
+            Ok(()) if output.fallback => format!(
+                "Copied the last {} lines (no prompts found)",
+                output.line_count
+            ),
+            Ok(()) => format!("Copied {} lines of output", output.line_count),
+        }));
+    }
+
+    /// Copy the line of a terminal's grid `line` is part of, joining its wraps
+    fn copy_logical_line(&mut self, pane_id: PaneId, line: i32) {
+        let Some(TabContent::Terminal(terminal)) = self.current_workspace().get_content(pane_id) else {
+            return;
+        };
+        let grid = terminal.backend.grid();
+        let rows = -(grid.history_size() as i32)..grid.screen_lines() as i32;
+        if !rows.contains(&line) {
+            return;
+        }
+        let text = crate::long_lines::logical_line(grid, Line(line));
+        if text.is_empty() {
+            self.toast = Some(Toast::new("The line is empty"));
+            return;
+        }
+        let columns = text.chars().count();
+        self.toast = Some(Toast::new(match self.copy_text(text, "Copy Logical Line".to_string()) {
+            Ok(()) => format!("Copied a {}-column line", columns),
+            Err(e) => {
+                log::warn!("Failed to copy line: {}", e);
+                format!("Failed to copy line: {}", e)
+            }
+        }));
+    }
+
+    /// Put text on the system clipboard and record it in the history
+    fn copy_text(&mut self, text: String, source: String) -> Result<(), arboard::Error> {
+        Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text.clone()))?;
+        self.clipboard_history.push(text, source, std::time::SystemTime::now());
+        Ok(())
+    }
+
+    /// Record text copied through egui this frame (terminal and file viewer
+    /// selections, copy path actions) as coming from the focused pane
+    fn record_copies(&mut self, ctx: &Context) {
+        let copied: Vec<String> = ctx.output(|o| {
+            o.commands
+                .iter()
+                .filter_map(|command| match command {
+                    egui::OutputCommand::CopyText(text) => Some(text.clone()),
+                    _ => None,
+                })
+                .collect()
+        });
+        if copied.is_empty() {
+            return;
+        }
+        let ws = self.current_workspace();
+        let source = ws.pane_label(ws.focused_pane);
+        for text in copied {
+            self.clipboard_history.push(text, source.clone(), std::time::SystemTime::now());
+        }
+    }
+
+    /// Run the configured editor command for a file position
+    fn open_in_editor(&mut self, path: &Path, line: u32, column: u32) {
+        let file = path.to_string_lossy();
+        let line = line.to_string();
+        let column = column.to_string();
+        // Substitute per argument so paths with spaces stay one argument
+        let args: Vec<String> = self.config.diagnostics.editor_command
+            .split_whitespace()
+            .map(|arg| arg.replace("{file}", &file).replace("{line}", &line).replace("{column}", &column))
+            .collect();
+        let Some((program, args)) = args.split_first() else {
+            self.toast = Some(Toast::new("No editor command configured"));
+            return;
+        };
+
+        if let Err(e) = std::process::Command::new(program).args(args).spawn() {
+            log::warn!("Failed to run editor {}: {}", program, e);
+            self.toast = Some(Toast::new(format!("Failed to run {}: {}", program, e)));
+        }
+    }
+
+    /// Show a diagnostic in the tab's file viewer, splitting one off the
+    /// terminal if there is none; the terminal keeps focus
+    fn show_diagnostic_in_viewer(&mut self, terminal_pane: PaneId, diagnostic: Diagnostic) {
+        let workspace_id = self.active_workspace;
+        let ws = &mut self.workspaces[workspace_id];
+        let viewer = ws.root.collect_contents_mut().into_iter().find_map(|(pane_id, content)| match content {
+            TabContent::FileViewer { path, goto_line, .. } => Some((pane_id, path, goto_line)),
+            TabContent::Terminal(_) | TabContent::Empty | TabContent::Diff(_) | TabContent::Stats(_) | TabContent::ColorTest(_) => None,
+        });
+
+        let pane_id = match viewer {
+            Some((_, path, goto_line)) if *path == diagnostic.path => {
+                *goto_line = Some(diagnostic.line);
+                return;
+            }
+            Some((pane_id, _, _)) => {
+                let _ = ws.root.replace_content(pane_id, TabContent::file_viewer(diagnostic.path));
+                pane_id
+            }
+            None => {
+                let pane_id = PaneId(ws.next_pane_id);
+                ws.next_pane_id += 1;
+                let old_root = std::mem::replace(&mut ws.root, placeholder_root());
+                let content = TabContent::file_viewer(diagnostic.path);
+                let (new_root, _) = split_node(old_root, terminal_pane, SplitDirection::Horizontal, pane_id, Some(content));
+                ws.root = new_root;
+                pane_id
+            }
+        };
+
+        if let Some(TabContent::FileViewer { goto_line, .. }) = ws.get_content_mut(pane_id) {
+            *goto_line = Some(diagnostic.line);
+        }
+        self.load_file_view(workspace_id, pane_id, LoadMode::Preview);
+    }
+
+    /// Find the shell process of a new terminal in the background
+    ///
+    /// The shell may not be up yet when the backend returns, so this retries
+    /// for up to `SHELL_DISCOVERY_TIMEOUT`. The shell is the child whose
+    /// environment carries `terminal_id`, so panes created in quick
+    /// succession each get their own.
+    fn discover_shell_pid(&self, terminal_id: u64) {
+        use crate::pty_tracker::{claim_shell_pid, SHELL_DISCOVERY_RETRY, SHELL_DISCOVERY_TIMEOUT};
+
+        // Mock terminals have no shell to find
+        if crate::terminal_backend::headless() {
+            return;
+        }
+
+        let claimed = self.claimed_pids.clone();
+        let tx = self.shell_pid_tx.clone();
+
+        self.tokio_runtime.spawn(async move {
+            let start = std::time::Instant::now();
+
+            loop {
+                let claimed = claimed.clone();
+                let found = tokio::task::spawn_blocking(move || claim_shell_pid(&claimed, terminal_id))
+                    .await
+                    .ok()
+                    .flatten();
+
+                if let Some(pid) = found {
+                    let _ = tx.send((terminal_id, pid));
+                    return;
+                }
+
+                if start.elapsed() >= SHELL_DISCOVERY_TIMEOUT {
+                    log::warn!("Shell process for terminal {} not found after {:?}", terminal_id, SHELL_DISCOVERY_TIMEOUT);
+                    return;
+                }
+
+                tokio::time::sleep(SHELL_DISCOVERY_RETRY).await;
+            }
+        });
+    }
+
+    /// Attach PTY trackers for discovered shell processes
+    fn process_shell_pid_results(&mut self) {
+        while let Ok((terminal_id, pid)) = self.shell_pid_rx.try_recv() {
+            let terminal = self.workspaces
+                .iter_mut()
+                .find_map(|ws| ws.get_terminal_mut(terminal_id));
+
+            let tracker = terminal.and_then(|terminal| {
+                let tracker = crate::pty_tracker::PtyTracker::new(pid)?;
+                log::debug!("Terminal {} tracking shell PID {}", terminal_id, pid);
+                terminal.current_dir = tracker.current_dir().clone();
+                terminal.project_root = crate::project::detect_project_root(&terminal.current_dir);
+                terminal.pty_tracker = Some(tracker);
+                Some(())
+            });
+
+            if tracker.is_none() {
+                // Terminal closed meanwhile or process already gone
+                crate::pty_tracker::release_shell_pid(&self.claimed_pids, pid);
+            }
+        }
+    }
+
+    /// Poll PTY trackers and update terminal CWDs
+    ///
+    /// This is called every frame. PTY trackers internally manage their polling
+    /// interval (500ms for focused, 2s for unfocused).
+    ///
+    /// Can be disabled via `config.ui.enable_cwd_polling` for users with
+    /// performance concerns.
+    fn poll_pty_trackers(&mut self) {
+        // Skip polling if disabled in config
+        if !self.config.ui.enable_cwd_polling {
+            return;
+        }
+
+        use std::time::Duration;
+
+        let focused_workspace = self.active_workspace;
+        let detect_elevation = self.config.ui.highlight_root_panes;
+        let follow_cd = self.config.ui.sidebar_follows_cd;
+        let mut new_project_roots = Vec::new();
+        let mut entered_dirs = Vec::new();
+
+        for (ws_idx, workspace) in self.workspaces.iter_mut().enumerate() {
+            let focused_pane = workspace.focused_pane;
+            let is_active_workspace = ws_idx == focused_workspace;
+
+            // Collect mutable references to terminal contents
+            let contents = workspace.root.collect_contents_mut();
+
+            for (pane_id, content) in contents {
+                if let TabContent::Terminal(terminal) = content {
+                    if let Some(ref mut tracker) = terminal.pty_tracker {
+                        // Set poll interval based on focus state
+                        // Focused pane in active workspace: 500ms
+                        // Unfocused or inactive workspace: 2s
+                        let interval = if is_active_workspace && pane_id == focused_pane {
+                            Duration::from_millis(500)
+                        } else {
+                            Duration::from_secs(2)
+                        };
+                        tracker.set_interval(interval);
+                        tracker.set_detect_elevation(detect_elevation);
+
+                        let changes = tracker.poll();
+                        if changes.elevated {
+                            log::info!("Terminal {} root: {}", terminal.id, tracker.is_elevated());
+                        }
+
+                        // Update CWD if changed
+                        if changes.cwd {
+                            let new_dir = tracker.current_dir().clone();
+                            log::debug!(
+                                "Terminal {} CWD changed: {:?} -> {:?}",
+                                terminal.id,
+                                terminal.current_dir,
+                                new_dir
+                            );
+                            terminal.current_dir = new_dir.clone();
+                            terminal.cwd_changed_at = Some(std::time::Instant::now());
+                            entered_dirs.push((terminal.id, new_dir.clone()));
+                            let project_root = crate::project::detect_project_root(&new_dir);
+                            if project_root != terminal.project_root {
+                                new_project_roots.extend(project_root.clone());
+                            }
+                            terminal.project_root = project_root;
+
+                            // The sidebar follows the focused pane once it settles
+                            if follow_cd && pane_id == focused_pane {
+                                let target = terminal.project_root.as_ref().unwrap_or(&terminal.current_dir).clone();
+                                workspace.root_governor.request(pane_id, target, terminal.cwd_changed_at);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for root in new_project_roots {
+            self.offer_project_layout(root);
+        }
+        for (terminal_id, dir) in entered_dirs {
+            self.run_dir_hooks(terminal_id, &dir);
+        }
+    }
+
+    /// Fire the directory hooks matching the directory a terminal entered
+    fn run_dir_hooks(&mut self, terminal_id: u64, dir: &Path) {
+        if self.config.terminal.dir_hooks.is_empty() {
+            return;
+        }
+        let live = self.workspaces
+            .iter()
+            .flat_map(|ws| ws.pane_ids().into_iter().filter_map(|pane_id| ws.terminal_id(pane_id)))
+            .collect();
+        self.dir_hook_tracker.retain_terminals(&live);
+
+        for index in self.dir_hook_tracker.enter(terminal_id, dir, &self.config.terminal.dir_hooks) {
+            let hook = self.config.terminal.dir_hooks[index].clone();
+            match &hook.action {
+                crate::config::DirHookAction::Banner { text } => {
+                    let terminal = self.workspaces.iter_mut().find_map(|ws| ws.get_terminal_mut(terminal_id));
+                    if let Some(terminal) = terminal {
+                        terminal.dir_banner = Some(text.clone());
+                    }
+                }
+                crate::config::DirHookAction::Command { .. } => {
+                    if self.dir_hook_consent.is_granted(&hook) {
+                        self.type_dir_hook(terminal_id, &hook);
+                    } else {
+                        self.dir_hook_prompts.push_back((terminal_id, hook));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Type a directory hook's command into a terminal
+    fn type_dir_hook(&mut self, terminal_id: u64, hook: &crate::config::DirHook) {
+        let Some(text) = crate::dir_hooks::typed_text(hook) else {
+            return;
+        };
+        let terminal = self.workspaces.iter_mut().find_map(|ws| ws.get_terminal_mut(terminal_id));
+        if let Some(terminal) = terminal {
+            terminal.backend.process_command(BackendCommand::Write(text.into_bytes()));
+        }
+    }
+
+    /// What quitting now would interrupt
+    fn quit_summary(&self) -> crate::quit::QuitSummary {
+        let mut panes = Vec::new();
+        for ws in &self.workspaces {
+            for pane_id in ws.pane_ids() {
+                if let Some(TabContent::Terminal(terminal)) = ws.get_content(pane_id) {
+                    panes.push(crate::quit::PaneActivity {
+                        workspace: ws.name.clone(),
+                        pane: ws.pane_label(pane_id),
+                        running_job: terminal.pty_tracker.as_ref().is_some_and(|tracker| tracker.has_foreground_job()),
+                        logging: terminal.session_log.is_some(),
+                    });
+                }
+            }
+        }
+        let work = crate::quit::BackgroundWork {
+            dir_scans: self.loading_dirs.len(),
+            git_fetch: self.context_manager.fetch_status().is_some_and(|status| status.in_flight),
+            project_search: self.search_panel.is_running(),
+        };
+        crate::quit::QuitSummary::collect(&panes, work)
+    }
+
+    /// Hold the window open for the quit dialog when quitting would interrupt something
+    fn handle_close_request(&mut self, ctx: &Context) {
+        if self.quit_confirmed || !ctx.input(|i| i.viewport().close_requested()) {
+            return;
+        }
+        let summary = self.quit_summary();
+        if crate::quit::should_confirm(self.config.ui.confirm_quit, &summary) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.quit_prompt = Some(summary);
+        }
+    }
+
+    /// Dialog listing what quitting would interrupt
+    fn show_quit_prompt(&mut self, ctx: &Context) {
+        let Some(summary) = &self.quit_prompt else {
+            return;
+        };
+
+        let mut confirm_quit = self.config.ui.confirm_quit;
+        let mut choice = None;
+        egui::Window::new("Quit VibeTerm?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .frame(Frame::window(&ctx.style())
+                .fill(self.theme.surface)
+                .stroke(egui::Stroke::new(1.0, self.theme.border)))
+            .show(ctx, |ui| {
+                let section = |ui: &mut egui::Ui, title: &str, lines: Vec<String>| {
+                    if lines.is_empty() {
+                        return;
+                    }
+                    ui.label(egui::RichText::new(title).font(theme::ui_font(12.0)).color(self.theme.text));
+                    for line in lines {
+                        ui.label(egui::RichText::new(format!("  {}", line))
+                            .font(theme::ui_font(12.0))
+                            .color(self.theme.yellow));
+                    }
+                    ui.add_space(4.0);
+                };
+                section(ui, "Running jobs will be stopped:", summary.running
+                    .iter()
+                    .map(|(workspace, count)| match count {
+                        1 => workspace.clone(),
+                        n => format!("{} ({} panes)", workspace, n),
+                    })
+                    .collect());
+                section(ui, "Session logs will be closed:", summary.logging.clone());
+                section(ui, "Unfinished background work:", summary.tasks.clone());
+
+                ui.checkbox(&mut confirm_quit, egui::RichText::new("Ask before quitting").font(theme::ui_font(12.0)));
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Quit").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                        choice = Some(false);
+                    }
+                });
+            });
+
+        if confirm_quit != self.config.ui.confirm_quit {
+            self.config.edit(std::time::Instant::now(), |c| c.ui.confirm_quit = confirm_quit);
+        }
+        match choice {
+            Some(true) => {
+                self.quit_prompt = None;
+                self.quit_confirmed = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            Some(false) => self.quit_prompt = None,
+            None => {}
+        }
+    }
+
+    /// Finish session logs, stop the watcher and hang up the shells
+    fn shut_down(&mut self) {
+        if let Some(writer) = self.session_log_writer.take() {
+            let strip_ansi = self.config.terminal.session_logging.strip_ansi;
+            for ws in &mut self.workspaces {
+                for (_, content) in ws.root.collect_contents_mut() {
+                    if let TabContent::Terminal(terminal) = content {
+                        terminal.stop_logging(&writer, strip_ansi);
+                    }
+                }
+            }
+            if !writer.finish(crate::quit::LOG_FLUSH_WAIT) {
+                log::warn!("Session logs were still being written at exit");
+            }
+        }
+
+        self.context_manager.stop_watcher();
+
+        let pids: Vec<u32> = self.workspaces
+            .iter()
+            .flat_map(|ws| ws.pane_ids().into_iter().filter_map(|pane_id| match ws.get_content(pane_id) {
+                Some(TabContent::Terminal(terminal)) => terminal.pty_tracker.as_ref().map(|tracker| tracker.pid()),
+                _ => None,
+            }))
+            .collect();
+        let remaining = crate::quit::hang_up(&pids, crate::quit::HANGUP_WAIT);
+        if remaining > 0 {
+            log::info!("{} shell(s) still running at exit; closing their terminals ends them", remaining);
+        }
+    }
+
+    /// Ask before a directory hook types its command for the first time
+    fn show_dir_hook_consent(&mut self, ctx: &Context) {
+        let Some((_, hook)) = self.dir_hook_prompts.front() else {
+            return;
+        };
+
+        let mut choice = None;
+        egui::Window::new("Run Directory Hook?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .frame(Frame::window(&ctx.style())
+                .fill(self.theme.surface)
+                .stroke(egui::Stroke::new(1.0, self.theme.border)))
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new(format!("The directory hook for \"{}\" wants to type:", hook.matches.trim()))
+                    .font(theme::ui_font(12.0))
+                    .color(self.theme.text));
+                ui.add_space(4.0);
+                let text = crate::dir_hooks::typed_text(hook).unwrap_or_default();
+                ui.label(egui::RichText::new(format!("❯ {}", text.trim_end()))
+                    .font(theme::ui_font(12.0))
+                    .color(self.theme.yellow));
+                if text.ends_with('\n') {
+                    ui.label(egui::RichText::new("and press Enter")
+                        .font(theme::ui_font(11.0))
+                        .color(self.theme.text_dim));
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Allow").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Skip").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                        choice = Some(false);
+                    }
+                });
+            });
+
+        let Some(allow) = choice else {
+            return;
+        };
+        let Some((terminal_id, hook)) = self.dir_hook_prompts.pop_front() else {
+            return;
+        };
+        if allow {
+            self.dir_hook_consent.grant(&hook);
+            if let Err(e) = self.dir_hook_consent.save() {
+                log::warn!("{}", e);
+            }
+            self.type_dir_hook(terminal_id, &hook);
+        }
+
+        // Queued hooks allowed along with this one don't ask again
+        let (granted, waiting) = std::mem::take(&mut self.dir_hook_prompts)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(_, hook)| self.dir_hook_consent.is_granted(hook));
+        self.dir_hook_prompts = waiting.into();
+        for (terminal_id, hook) in granted {
+            self.type_dir_hook(terminal_id, &hook);
+        }
+    }
+
+    /// Switch the sidebar root to a pane's project root (or CWD)
+    ///
+    /// Goes through the workspace's `RootSwitchGovernor`, so the switch may
+    /// wait for the pane's directory to settle or for a fullscreen app to exit.
+    fn sync_sidebar_root(&mut self, pane_id: PaneId) {
+        let ws = &mut self.workspaces[self.active_workspace];
+        let Some(TabContent::Terminal(terminal)) = ws.root.get_content(pane_id) else {
+            return;
+        };
+        let new_root = terminal.project_root.as_ref().unwrap_or(&terminal.current_dir).clone();
+        let changed_at = terminal.cwd_changed_at;
+        ws.root_governor.request(pane_id, new_root, changed_at);
+        self.apply_sidebar_root_switch();
+    }
+
+    /// Palette commands that don't apply right now: the pause/resume pair
+    /// shows only the one matching the subsystem's state
+    fn hidden_palette_commands(&self) -> Vec<&'static str> {
+        let mut hidden = Vec::new();
+        let watcher = self.context_manager.watcher_state();
+        if !watcher.enabled || watcher.paused {
+            hidden.push("pause_file_watcher");
+        }
+        if !watcher.enabled || !watcher.paused {
+            hidden.push("resume_file_watcher");
+        }
+        let git_enabled = self.context_manager.is_git_enabled();
+        if !git_enabled || self.context_manager.is_git_paused() {
+            hidden.push("pause_git_status");
+        }
+        if !git_enabled || !self.context_manager.is_git_paused() {
+            hidden.push("resume_git_status");
+        }
+        let ws = self.current_workspace();
+        if !matches!(ws.get_content(ws.focused_pane), Some(TabContent::FileViewer { .. })) {
+            hidden.push("toggle_follow_sidebar");
+        }
+        hidden
+    }
+
+    /// Palette commands greyed out because something they need is missing
+    fn disabled_palette_commands(&self) -> Vec<(&'static str, String)> {
+        let mut disabled = Vec::new();
+        if let Some(reason) = self.capabilities.unavailable_reason(crate::capabilities::Capability::FileWatcher) {
+            disabled.push(("pause_file_watcher", reason.clone()));
+            disabled.push(("resume_file_watcher", reason));
+        }
+        disabled
+    }
+
+    /// Point the active workspace's sidebar at `new_root`, remembering the old one
+    fn set_sidebar_root(&mut self, new_root: PathBuf) {
+        let ws = &mut self.workspaces[self.active_workspace];
+        if new_root != ws.sidebar_root {
+            ws.root_history.visit(ws.sidebar_root.clone());
+            self.switch_sidebar_root(new_root);
+        }
+    }
+
+    /// Go back (or forward) through the active workspace's sidebar roots
+    ///
+    /// Roots deleted since are skipped, with a toast.
+    fn navigate_sidebar_root(&mut self, forward: bool) {
+        let ws = &mut self.workspaces[self.active_workspace];
+        let current = ws.sidebar_root.clone();
+        let exists = |dir: &PathBuf| dir.is_dir();
+        let step = if forward {
+            ws.root_history.forward(current, exists)
+        } else {
+            ws.root_history.back(current, exists)
+        };
+        match step.skipped.as_slice() {
+            [] => {}
+            [dir] => self.toast = Some(Toast::new(format!("Skipped {}: it no longer exists", dir_label(dir)))),
+            skipped => self.toast = Some(Toast::new(format!("Skipped {} folders that no longer exist", skipped.len()))),
+        }
+        if let Some(target) = step.target {
+            self.switch_sidebar_root(target);
+        }
+    }
+
+    /// Reload the active workspace's sidebar at `new_root`, leaving its history alone
+    fn switch_sidebar_root(&mut self, new_root: PathBuf) {
+        let ws = &mut self.workspaces[self.active_workspace];
+
+        // Only reload if root changed
+        if new_root != ws.sidebar_root {
+            ws.sidebar_root = new_root.clone();
+            ws.sidebar_filter = None;
+
+            // Update context manager with new directory
+            self.context_manager.set_active_directory(&new_root);
+
+            self.load_directory_async(self.active_workspace, new_root.clone());
+            self.offer_project_layout(new_root);
+        }
+    }
+
+    /// Type a `cd` into the focused terminal (from the breadcrumb bar)
+    fn cd_focused_terminal(&mut self, path: &Path) {
+        let ws = self.current_workspace();
+        let focused = ws.focused_pane;
+        if ws.is_alt_screen(focused) {
+            self.toast = Some(Toast::new("Can't cd while a full-screen program is running"));
+            return;
+        }
+        if self.current_workspace_mut().write_to_pane(focused, &cd_command(path)) {
+            self.note_terminal_input();
+        }
+    }
+
+    /// Apply a queued sidebar root switch once the governor allows it
+    ///
+    /// Returns when the queued switch is due, for scheduling a repaint.
+    fn apply_sidebar_root_switch(&mut self) -> Option<std::time::Instant> {
+        let now = std::time::Instant::now();
+        let ws = &mut self.workspaces[self.active_workspace];
+        ws.root_governor.set_timing(
+            std::time::Duration::from_millis(self.config.ui.root_switch_dwell_ms),
+            std::time::Duration::from_millis(self.config.ui.root_switch_interval_ms),
+        );
+        let focused = ws.focused_pane;
+        let blocked = ws.is_alt_screen(focused);
+        if let Some(new_root) = ws.root_governor.poll(focused, &ws.sidebar_root, blocked, now) {
+            self.set_sidebar_root(new_root);
+        }
+        self.current_workspace().root_governor.next_due(now)
+    }
+
+    /// Process async directory loading results
+    fn process_dir_load_results(&mut self) {
+        while let Ok(result) = self.dir_load_rx.try_recv() {
+            let index = self.workspace_index(result.workspace_id);
+            if result.for_filter {
+                if let Some(index) = index {
+                    self.apply_filter_scan(index, result.entries);
+                }
+                continue;
+            }
+            self.loading_dirs.remove(&result.workspace_id);
+            if let Some(ws) = index.map(|index| &mut self.workspaces[index]) {
+                // Update context manager with new directory for git status
+                self.context_manager.set_active_directory(&ws.sidebar_root);
+
+                ws.sidebar_entries = restore_sidebar_tree(ws, result);
+                ws.git_sync.invalidate();
+                let tree_state_dirty = ws.tree_state_dirty;
+
+                if tree_state_dirty {
+                    self.schedule_tree_state_save();
+                }
+            }
+        }
+    }
+
+    /// Read a file viewer pane's file, in the background unless it's small
+    fn load_file_view(&mut self, workspace_id: usize, pane_id: PaneId, mode: LoadMode) {
+        let limits = LoadLimits::new(&self.config.file_viewer);
+        let content = self.workspaces
+            .get_mut(workspace_id)
+            .and_then(|ws| ws.get_content_mut(pane_id));
+        let Some(TabContent::FileViewer { path, content, expanded, state, markdown, blame, .. }) = content else {
+            return;
+        };
+        // Reloads keep the pane's current view
+        if markdown.is_none() && crate::ui::is_markdown(path) {
+            *markdown = Some(MarkdownPane {
+                mode: self.config.file_viewer.markdown_mode(path),
+                document: None,
+            });
+        }
+
+        // Metadata is cheap even for huge files; reading them is not
+        let size = std::fs::metadata(&*path).map(|m| m.len()).unwrap_or(0);
+        if crate::file_loader::load_tier(size, mode, &limits) == LoadTier::Inline {
+            let result = crate::file_loader::read_file(path, mode, &limits);
+            apply_file_load(content, expanded, state, markdown, blame, mode, result);
+            return;
+        }
+
+        *state = ViewerState::Loading(mode);
+        let path = path.clone();
+        let workspace_id = self.workspaces[workspace_id].id;
+        let tx = self.file_load_tx.clone();
+        self.tokio_runtime.spawn(async move {
+            let read_path = path.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                crate::file_loader::read_file(&read_path, mode, &limits)
+            }).await;
+
+            if let Ok(result) = result {
+                let _ = tx.send(FileLoadResult {
+                    workspace_id,
+                    pane_id,
+                    path,
+                    mode,
+                    result,
+                });
+            }
+        });
+    }
+
+    /// Switch to a new configuration without restarting
+    fn apply_config(&mut self, ctx: &Context, new_config: Config) {
+        if new_config.context != self.config.context {
+            self.context_manager.reconfigure(new_config.context.clone());
+        }
+        self.set_theme(ctx, &new_config);
+        self.theme_preview = false;
+        if !new_config.font.same_faces(&self.config.font) {
+            // Turned on after startup: read right away, so the next frame has it
+            if new_config.font.cjk_fallback && self.cjk_font.is_none() {
+                self.cjk_font = crate::theme::read_cjk_font(crate::theme::CJK_FONT_PATHS);
+                self.cjk_loader = None;
+            }
+            crate::theme::configure_fonts(ctx, &new_config.font, self.cjk_font.as_deref());
+        }
+        self.clipboard_history.configure(&new_config.terminal.clipboard_history);
+        if new_config.terminal.shell != self.config.terminal.shell {
+            let shell = crate::shell_path::configure(&new_config.terminal.shell);
+            if let Some(toast) = shell_replaced_toast(&shell) {
+                self.toast = Some(toast);
+            }
+        }
+        self.notifier.configure(&new_config.ui.dnd_schedule);
+        if new_config.advanced.local_stats != self.stats.is_some() {
+            if let Some(stats) = &mut self.stats {
+                stats.flush(std::time::Instant::now());
+            }
+            self.stats = new_config.advanced.local_stats
+                .then(|| crate::stats::StatsRecorder::new(crate::stats::StatsRecorder::stats_path()));
+        }
+        if (new_config.ui.sidebar_width - self.config.ui.sidebar_width).abs() > 0.5 {
+            self.sidebar_width_changed = true;
+        }
+        self.config.update(new_config, std::time::Instant::now());
+    }
+
+    /// Switch the app and terminal colors to `config`'s theme
+    ///
+    /// Everything changes in one frame, so nothing flickers between themes.
+    fn set_theme(&mut self, ctx: &Context, config: &Config) {
+        self.theme = RuntimeTheme::from(&config.theme);
+        self.cached_terminal_theme = Arc::new(theme::get_terminal_theme(config));
+        self.cached_dim_terminal_theme = Arc::new(theme::get_dim_terminal_theme(config));
+        crate::theme::apply_theme(ctx, &self.theme);
+    }
+
+    /// Show a theme preset from Preferences, or go back to the config's theme
+    fn preview_theme(&mut self, ctx: &Context, preview: crate::ui::ThemePreview) {
+        let mut config = Config::clone(&self.config);
+        match preview {
+            crate::ui::ThemePreview::Show(theme) => {
+                config.theme = *theme;
+                self.theme_preview = true;
+            }
+            crate::ui::ThemePreview::End if self.theme_preview => self.theme_preview = false,
+            crate::ui::ThemePreview::End => return,
+        }
+        self.set_theme(ctx, &config);
+    }
+
+    /// Write pending config changes in the background once they are due
+    ///
+    /// Headless runs drop them: tests must never touch the user's config.toml.
+    fn save_config_if_due(&mut self, ctx: &Context) {
+        let now = std::time::Instant::now();
+        if let Some((path, contents)) = self.config.take_due(now) {
+            if crate::terminal_backend::headless() {
+                return;
+            }
+            self.write_config(path, contents, false);
+        } else if let Some(due) = self.config.save_due() {
+            ctx.request_repaint_after(due.saturating_duration_since(now));
+        }
+    }
+
+    /// Write pending config changes right away
+    fn save_config_now(&mut self) {
+        if let Some((path, contents)) = self.config.take_pending() {
+            if crate::terminal_backend::headless() {
+                return;
+            }
+            self.write_config(path, contents, true);
+        }
+    }
+
+    /// Write config.toml, in the background unless `now`
+    fn write_config(&mut self, path: PathBuf, contents: String, now: bool) {
+        self.config_generation += 1;
+        let generation = self.config_generation;
+        let written = Arc::clone(&self.config_written);
+        let save = move || {
+            // Held through the write, so saves never share the temporary file
+            let mut written = written.lock().unwrap_or_else(|e| e.into_inner());
+            if *written > generation {
+                return;
+            }
+            match crate::config_store::write_atomic(&path, &contents) {
+                Ok(()) => *written = generation,
+                Err(e) => log::error!("Failed to save config: {}", e),
+            }
+        };
+        if now {
+            save();
+        } else {
+            self.tokio_runtime.spawn_blocking(save);
+        }
+    }
+
+    /// Write the in-memory config if config.toml doesn't exist yet
+    fn ensure_config_file(&mut self) -> bool {
+        if Config::config_path().exists() {
+            return true;
+        }
+        match self.config.save() {
+            Ok(()) => {
+                self.config_modified = Config::modified();
+                true
+            }
+            Err(e) => {
+                log::warn!("Failed to create config file: {}", e);
+                self.toast = Some(Toast::new(e));
+                false
+            }
+        }
+    }
+
+    /// Show config.toml in a file viewer tab
+    fn open_config_file(&mut self) {
+        if self.ensure_config_file() {
+            self.create_file_tab(Config::config_path());
+        }
+    }
+
+    /// Open the config folder in the system file manager
+    fn reveal_config_folder(&mut self) {
+        let dir = Config::config_dir();
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            self.toast = Some(Toast::new(format!("Failed to create {}: {}", dir.display(), e)));
+            return;
+        }
+        if let Err(e) = std::process::Command::new(SYSTEM_OPEN).arg(&dir).spawn() {
+            log::warn!("Failed to run {}: {}", SYSTEM_OPEN, e);
+            self.toast = Some(Toast::new(format!("Failed to run {}: {}", SYSTEM_OPEN, e)));
+        }
+    }
+
+    /// Edit config.toml with the external editor command if enabled,
+    /// otherwise with `$EDITOR` in the focused terminal
+    fn edit_config(&mut self) {
+        if !self.ensure_config_file() {
+            return;
+        }
+        let path = Config::config_path();
+        if self.config.diagnostics.open_in_editor {
+            self.open_in_editor(&path, 1, 1);
+            return;
+        }
+        let ws = self.current_workspace();
+        if !matches!(ws.get_content(ws.focused_pane), Some(TabContent::Terminal(_))) {
+            self.toast = Some(Toast::new("Edit Config needs a focused terminal"));
+            return;
+        }
+        let command = format!("${{EDITOR:-vi}} {}\n", crate::ui::shell_quote(&path));
+        self.send_text_to_terminal(&command);
+    }
+
+    /// Apply outside edits of config.toml and refresh viewers showing it
+    ///
+    /// A file that fails to parse is reported and the current config kept.
+    fn check_config_file(&mut self, ctx: &Context) {
+        let now = std::time::Instant::now();
+        if now < self.config_check_at {
+            return;
+        }
+        self.config_check_at = now + CONFIG_CHECK_INTERVAL;
+
+        let modified = Config::modified();
+        if modified.is_none() || modified == self.config_modified {
+            return;
+        }
+        self.config_modified = modified;
+
+        let path = Config::config_path();
+        match Config::read(&path) {
+            // Our own saves come back unchanged
+            Ok(config) if config.same_file_contents(&self.config) => {}
+            Ok(config) => {
+                log::info!("Config file changed, reloading");
+                self.apply_config(ctx, config);
+                // The file wins over changes not saved yet
+                self.config.mark_saved();
+                self.toast = Some(Toast::new("Reloaded config.toml"));
+            }
+            Err(e) => {
+                log::warn!("{}", e);
+                self.toast = Some(Toast::new(format!("config.toml: {}", e)));
+            }
+        }
+        if let Ok(canonical) = path.canonicalize() {
+            self.reload_changed_file_views(&canonical);
+        }
+    }
+
+    /// Add the CJK fallback font once the startup read finishes
+    fn process_cjk_font(&mut self, ctx: &Context) {
+        let Some(loader) = &self.cjk_loader else { return };
+        match loader.poll() {
+            crate::theme::CjkFontLoad::Pending => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(50));
+                return;
+            }
+            crate::theme::CjkFontLoad::Found(font_data) => {
+                crate::theme::configure_fonts(ctx, &self.config.font, Some(&font_data));
+                self.cjk_font = Some(font_data);
+            }
+            crate::theme::CjkFontLoad::Missing => {
+                log::info!("No CJK fallback font found, using the built-in fonts");
+            }
+        }
+        self.cjk_loader = None;
+    }
+
+    /// Check the terminal font's glyph coverage and show the report
+    fn run_font_diagnostics(&mut self, ctx: &Context) {
+        let definitions = ctx.fonts(|fonts| fonts.lock().fonts.definitions().clone());
+        let reports = crate::font_diagnostics::diagnose(&definitions, &egui::FontFamily::Monospace);
+        let remedies = crate::font_diagnostics::remedies(&reports, &self.config.font);
+        self.font_diagnostics_dialog.open(reports, remedies);
+    }
+
+    fn apply_font_remedy(&mut self, ctx: &Context, remedy: crate::font_diagnostics::Remedy) {
+        match remedy {
+            crate::font_diagnostics::Remedy::EnableCjkFallback => {
+                let mut config = self.config.clone();
+                config.font.cjk_fallback = true;
+                self.apply_config(ctx, config);
+                // New fonts are in use from the next frame
+                self.font_diagnostics_dialog.rerun_next_frame();
+            }
+            crate::font_diagnostics::Remedy::SetFallbackFont => {
+                self.preferences_window.open(self.config.clone());
             }
         }
     }
 
-    /// Process PTY events
-    fn process_pty_events(&mut self) {
-        while let Ok((terminal_id, event)) = self.pty_receiver.try_recv() {
-            match event {
-                PtyEvent::Exit => {
-                    log::info!("Terminal {} exited", terminal_id);
-                    // Find and remove the terminal
-                    for workspace in &mut self.workspaces {
-                        if let Some(pane_id) = workspace.find_pane_by_terminal_id(terminal_id) {
-                            if workspace.pane_count() > 1 {
-                                workspace.close_pane(pane_id);
-                            }
-                            break;
-                        }
-                    }
-                }
-                _ => {}
+    /// Copy the config files into a new bundle folder at `dest`
+    fn export_settings_bundle(&mut self, dest: &str) {
+        let home = dirs::home_dir().unwrap_or_default();
+        let dest = crate::settings_bundle::expand_home(dest.trim(), &home);
+        self.save_config_now();
+        self.save_tree_states(true);
+        let tree_state_dir = crate::tree_state::state_dir();
+        let message = match crate::settings_bundle::export(&Config::config_dir(), &tree_state_dir, &dest, &home) {
+            Ok(manifest) => format!("Exported {} settings file(s) to {}", manifest.components.len(), dest.display()),
+            Err(e) => {
+                log::warn!("Settings export failed: {}", e);
+                format!("Export failed: {}", e)
             }
-        }
+        };
+        self.toast = Some(Toast::new(message));
     }
 
-    /// Poll PTY trackers and update terminal CWDs
-    ///
-    /// This is called every frame. PTY trackers internally manage their polling
-    /// interval (500ms for focused, 2s for unfocused).
-    ///
-    /// Can be disabled via `config.ui.enable_cwd_polling` for users with
-    /// performance concerns.
-    fn poll_pty_trackers(&mut self) {
-        // Skip polling if disabled in config
-        if !self.config.ui.enable_cwd_polling {
-            return;
+    /// Validate a bundle and ask which parts of it to import
+    fn open_settings_bundle(&mut self, bundle: &str) {
+        let home = dirs::home_dir().unwrap_or_default();
+        let bundle = crate::settings_bundle::expand_home(bundle.trim(), &home);
+        match crate::settings_bundle::read_manifest(&bundle) {
+            Ok(manifest) => {
+                let plan = crate::settings_bundle::import_plan(&manifest, &Config::config_dir(), &crate::tree_state::state_dir());
+                self.import_dialog.open(bundle, manifest, plan);
+            }
+            Err(e) => self.toast = Some(Toast::new(e)),
         }
+    }
 
-        use std::time::Duration;
-
-        let focused_workspace = self.active_workspace;
-
-        for (ws_idx, workspace) in self.workspaces.iter_mut().enumerate() {
-            let focused_pane = workspace.focused_pane;
-            let is_active_workspace = ws_idx == focused_workspace;
+    /// Import the confirmed parts of a bundle and reload them
+    fn import_settings_bundle(&mut self, ctx: &Context, request: crate::ui::ImportRequest) {
+        use crate::settings_bundle::Component;
+
+        let home = dirs::home_dir().unwrap_or_default();
+        // The backup should hold the config as it is now
+        self.save_config_now();
+        let result = crate::settings_bundle::apply_import(
+            &request.bundle,
+            &request.manifest,
+            &Config::config_dir(),
+            &crate::tree_state::state_dir(),
+            &request.components,
+            &home,
+        );
+        let backup = match result {
+            Ok(backup) => backup,
+            Err(e) => {
+                log::warn!("Settings import failed: {}", e);
+                self.toast = Some(Toast::new(format!("Import failed: {}", e)));
+                return;
+            }
+        };
 
-            // Collect mutable references to terminal contents
-            let contents = workspace.root.collect_contents_mut();
+        if request.components.contains(&Component::Config) {
+            self.apply_config(ctx, Config::load());
+            self.config.mark_saved();
+        }
+        if request.components.contains(&Component::TrustedProjects) {
+            self.project_consent = crate::project::ProjectConsent::load();
+        }
+        // Snippets are read from disk whenever the snippet palette opens
+        self.toast = Some(Toast::new(format!(
+            "Imported {} settings file(s); previous files saved in {}",
+            request.components.len(),
+            backup.display()
+        )));
+    }
 
-            for (pane_id, content) in contents {
-                if let TabContent::Terminal(terminal) = content {
-                    if let Some(ref mut tracker) = terminal.pty_tracker {
-                        // Set poll interval based on focus state
-                        // Focused pane in active workspace: 500ms
-                        // Unfocused or inactive workspace: 2s
-                        let interval = if is_active_workspace && pane_id == focused_pane {
-                            Duration::from_millis(500)
-                        } else {
-                            Duration::from_secs(2)
-                        };
-                        tracker.set_interval(interval);
+    /// Follow a link clicked in a rendered Markdown file
+    fn open_markdown_link(&mut self, markdown_path: &Path, link: &str) {
+        let base_dir = markdown_path.parent().unwrap_or(Path::new("."));
+        match crate::ui::link_target(link, base_dir) {
+            crate::ui::LinkTarget::External(url) => self.ctx.open_url(egui::OpenUrl::new_tab(url)),
+            crate::ui::LinkTarget::File(path) if path.is_file() => self.create_file_tab(path),
+            crate::ui::LinkTarget::File(path) => {
+                self.toast = Some(Toast::new(format!("Not found: {}", path.display())));
+            }
+            crate::ui::LinkTarget::Anchor => {}
+        }
+    }
 
-                        // Poll and update CWD if changed
-                        if tracker.poll() {
-                            let new_dir = tracker.current_dir().clone();
-                            log::debug!(
-                                "Terminal {} CWD changed: {:?} -> {:?}",
-                                terminal.id,
-                                terminal.current_dir,
-                                new_dir
-                            );
-                            terminal.current_dir = new_dir.clone();
-                            terminal.project_root = crate::project::detect_project_root(&new_dir);
-                        }
+    /// Reload file viewers showing `changed` (a canonical path), keeping their mode
+    fn reload_changed_file_views(&mut self, changed: &Path) {
+        let mut reloads = Vec::new();
+        for (workspace_id, ws) in self.workspaces.iter_mut().enumerate() {
+            for (pane_id, content) in ws.root.collect_contents_mut() {
+                if let TabContent::FileViewer { path, state: ViewerState::Loaded { mode, .. }, .. } = content {
+                    if path.canonicalize().is_ok_and(|path| path == changed) {
+                        reloads.push((workspace_id, pane_id, *mode));
                     }
                 }
             }
         }
+        for (workspace_id, pane_id, mode) in reloads {
+            self.load_file_view(workspace_id, pane_id, mode);
+        }
     }
 
-    /// Process async directory loading results
-    fn process_dir_load_results(&mut self) {
-        while let Ok(result) = self.dir_load_rx.try_recv() {
-            if let Some(ws) = self.workspaces.get_mut(result.workspace_id) {
-                ws.sidebar_entries = result.entries;
-                self.loading_dirs.remove(&result.workspace_id);
-
-                // Update context manager with new directory for git status
-                let _ = self.context_manager.set_active_directory(&ws.sidebar_root);
-
-                // Update git status for all entries
-                self.update_sidebar_git_status();
+    /// Apply finished background file reads
+    fn process_file_load_results(&mut self) {
+        while let Ok(load) = self.file_load_rx.try_recv() {
+            let content = self.workspaces
+                .iter_mut()
+                .find(|ws| ws.id == load.workspace_id)
+                .and_then(|ws| ws.get_content_mut(load.pane_id));
+            // The pane may have been closed, converted or reloaded meanwhile
+            match content {
+                Some(TabContent::FileViewer { path, content, expanded, state, markdown, blame, .. })
+                    if *path == load.path && *state == ViewerState::Loading(load.mode) =>
+                {
+                    apply_file_load(content, expanded, state, markdown, blame, load.mode, load.result);
+                }
+                _ => log::debug!("Dropping stale file load for {}", load.path.display()),
             }
         }
     }
 
     /// Start async directory loading
     fn load_directory_async(&mut self, workspace_id: usize, path: PathBuf) {
+        let Some(ws) = self.workspaces.get(workspace_id) else {
+            return;
+        };
+        let (workspace_id, visibility) = (ws.id, ws.tree_visibility);
+        // Expand All opens everything once the scan is in; a reload of the
+        // tracked root keeps what's open now
+        let restore = !ws.expand_all_pending;
+        let reopen = (restore && ws.tracked_tree_root.as_ref() == Some(&path))
+            .then(|| TreeState::capture(&path, &ws.sidebar_entries, ws.selected_sidebar_entry, std::time::SystemTime::now()));
         self.loading_dirs.insert(workspace_id, true);
+        let options = ScanOptions::new(&path, visibility, &self.config.ui);
 
         let tx = self.dir_load_tx.clone();
         let runtime = self.tokio_runtime.clone();
 
         runtime.spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                let entries = scan_directory(&path, 10, 1000, &options);
+                let mut result = DirLoadResult {
+                    workspace_id,
+                    entries,
+                    selected: None,
+                    project_root: false,
+                    for_filter: false,
+                };
+                if restore {
+                    load_sidebar_tree(&path, reopen, &options, &mut result);
+                }
+                result
+            }).await;
+
+            if let Ok(result) = result {
+                let _ = tx.send(result);
+            }
+        });
+    }
+
+    /// Start a deep scan of the sidebar root for filtering
+    ///
+    /// Unlike the tree, the filter needs children of collapsed directories,
+    /// so this scans down to the configured depth and file limits.
+    fn scan_filter_source_async(&mut self, workspace_id: usize) {
+        let Some(ws) = self.workspaces.get_mut(workspace_id) else {
+            return;
+        };
+        let Some(filter) = ws.sidebar_filter.as_mut() else {
+            return;
+        };
+        filter.scan_started = true;
+        filter.scanning = true;
+
+        let workspace_id = ws.id;
+        let path = ws.sidebar_root.clone();
+        let options = ScanOptions::new(&path, ws.tree_visibility, &self.config.ui);
+        let max_depth = self.config.ui.max_depth;
+        let max_files = self.config.ui.max_files;
+        let tx = self.dir_load_tx.clone();
+
+        self.tokio_runtime.spawn(async move {
             let entries = tokio::task::spawn_blocking(move || {
-                scan_directory(&path, 10, 1000)
+                scan_directory(&path, max_depth, max_files, &options)
             }).await;
 
             if let Ok(entries) = entries {
                 let _ = tx.send(DirLoadResult {
                     workspace_id,
                     entries,
+                    selected: None,
+                    project_root: false,
+                    for_filter: true,
                 });
             }
         });
     }
 
+    /// Replace the filter source with a finished deep scan
+    fn apply_filter_scan(&mut self, workspace_id: usize, mut entries: Vec<FileEntry>) {
+        let Some(filter) = self.workspaces
+            .get_mut(workspace_id)
+            .and_then(|ws| ws.sidebar_filter.as_mut())
+        else {
+            // Filter was closed while scanning
+            return;
+        };
+
+        for entry in &mut entries {
+            entry.git_status = Some(self.context_manager.get_git_status(&entry.path));
+            entry.is_pinned = self.context_manager.is_pinned(&entry.path);
+        }
+
+        filter.source = entries;
+        filter.scanning = false;
+        filter.refresh();
+    }
+
+    /// Auto-hide strip and the sidebar sliding out over the panes
+    fn show_sidebar_overlay(&mut self, ctx: &Context) {
+        if self.presentation.is_active() {
+            return;
+        }
+        let now = std::time::Instant::now();
+        let area = ctx.available_rect();
+        let width = self.config.ui.sidebar_width;
+        let strip = (self.config.ui.sidebar_mode == SidebarMode::AutoHide)
+            .then(|| egui::Rect::from_min_size(area.min, egui::vec2(crate::sidebar_reveal::STRIP_WIDTH, area.height())));
+        let progress = self.sidebar_reveal.progress(now);
+        let panel = egui::Rect::from_min_size(
+            egui::pos2(area.left() - width * (1.0 - progress), area.top()),
+            egui::vec2(width, area.height()),
+        );
+
+        let pointer = ctx.input(|i| i.pointer.hover_pos());
+        let over = pointer.is_some_and(|pos| {
+            strip.is_some_and(|strip| strip.contains(pos)) || (progress > 0.0 && panel.contains(pos))
+        });
+        self.sidebar_reveal.update(over, self.sidebar_focused, now);
+        if let Some(after) = self.sidebar_reveal.repaint_after(now) {
+            ctx.request_repaint_after(after);
+        }
+
+        if let Some(strip) = strip.filter(|_| progress < 1.0) {
+            let color = if over { self.theme.primary } else { self.theme.border };
+            egui::Area::new(egui::Id::new("sidebar_strip"))
+                .order(egui::Order::Middle)
+                .fixed_pos(strip.min)
+                .show(ctx, |ui| {
+                    let (rect, _) = ui.allocate_exact_size(strip.size(), egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 0.0, color.gamma_multiply(0.6));
+                });
+        }
+        if progress <= 0.0 {
+            return;
+        }
+        egui::Area::new(egui::Id::new("sidebar_overlay"))
+            .order(egui::Order::Middle)
+            .fixed_pos(panel.min)
+            .constrain(false)
+            .show(ctx, |ui| {
+                ui.set_clip_rect(panel.intersect(area));
+                ui.set_min_size(panel.size());
+                ui.set_max_size(panel.size());
+                ui.painter().rect_filled(panel, 0.0, self.theme.surface);
+                self.sidebar_ui(ui);
+                ui.painter().vline(panel.right(), panel.y_range(), egui::Stroke::new(1.0, self.theme.border));
+            });
+    }
+
+    /// File tree (or project search) inside the sidebar panel or overlay
+    fn sidebar_ui(&mut self, ui: &mut egui::Ui) {
+        // Clicking into the sidebar gives it keyboard focus
+        let pressed_inside = ui.input(|i| {
+            i.pointer.button_pressed(egui::PointerButton::Primary)
+                && i.pointer.interact_pos().is_some_and(|pos| ui.max_rect().contains(pos))
+        });
+        if pressed_inside {
+            self.sidebar_focused = true;
+        }
+
+        if self.search_panel.is_visible() {
+            let response = self.search_panel.show(ui, &self.theme);
+            if let Some((path, line)) = response.open {
+                self.open_search_result(path, line);
+            }
+            if response.close {
+                self.close_project_search();
+            }
+            return;
+        }
+
+        let ws = &mut self.workspaces[self.active_workspace];
+
+        // Focus request is one-shot
+        let focus_filter = ws.sidebar_filter
+            .as_mut()
+            .is_some_and(|f| std::mem::take(&mut f.focus_pending));
+        let ws = &self.workspaces[self.active_workspace];
+
+        // Collect pane info from layout tree
+        let panes_info: Vec<(PaneId, PathBuf)> = {
+            let mut info = Vec::new();
+            collect_pane_info(&ws.root, &mut info);
+            info
+        };
+
+        let root_name = ws.sidebar_root
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("/")
+            .to_string();
+
+        let loading = self.loading_dirs.get(&self.current_workspace().id).copied().unwrap_or(false);
+
+        let repo_status = self.context_manager.repo_status();
+        let show_git_status = self.config.context.enable_git_status &&
+                              self.context_manager.is_git_available();
+
+        // While filtering, the filtered view replaces the tree
+        let (entries, selected, filter_view) = match &ws.sidebar_filter {
+            Some(filter) => (
+                &filter.visible,
+                filter.selected,
+                Some(FilterView {
+                    query: &filter.query,
+                    highlights: &filter.highlights,
+                    scanning: filter.scanning,
+                    request_focus: focus_filter,
+                }),
+            ),
+            None => (&ws.sidebar_entries, ws.selected_sidebar_entry, None),
+        };
+        let filtering = filter_view.is_some();
+
+        let sidebar = Sidebar::new(
+            entries,
+            selected,
+            &root_name,
+            &self.theme,
+            &panes_info,
+            Some(ws.focused_pane),
+            loading && !filtering,
+            repo_status,
+            show_git_status,
+        )
+        .with_filter(filter_view)
+        .with_icon_style(crate::ui::resolve_style(self.config.ui.file_icons, self.nerd_glyphs.unwrap_or(false)))
+        .with_visibility(ws.tree_visibility)
+        .history(ws.root_history.can_go_back(), ws.root_history.can_go_forward())
+        .preview(self.thumbnail_hover.as_ref().and_then(|hover| {
+            let slot = self.thumbnails.peek(hover.key.as_ref()?)?;
+            Some((entries.iter().position(|entry| entry.path == hover.path)?, slot))
+        }));
+        let response = sidebar.show(ui);
+        let hovered_path = response.hovered.and_then(|idx| entries.get(idx)).map(|entry| entry.path.clone());
+        self.update_thumbnail_hover(hovered_path);
+
+        if response.visibility_mode_changed {
+            self.cycle_tree_visibility();
+        }
+        if response.back || response.forward {
+            self.navigate_sidebar_root(response.forward);
+        }
+
+        if filtering {
+            self.handle_filtered_sidebar_response(&response);
+        } else {
+            if let Some(idx) = response.selected {
+                self.workspaces[self.active_workspace].selected_sidebar_entry = Some(idx);
+                self.mark_tree_state_dirty();
+                self.queue_follow_selection();
+            }
+            if let Some(idx) = response.toggled_dir {
+                self.toggle_directory(idx);
+            }
+            // Double-click file opens where `ui.sidebar_open_action` says
+            if let Some(idx) = response.opened_file {
+                let ws = &self.workspaces[self.active_workspace];
+                if let Some(entry) = ws.sidebar_entries.get(idx) {
+                    if !entry.is_dir {
+                        let modifiers = self.ctx.input(|i| i.modifiers);
+                        self.open_sidebar_file(entry.path.clone(), modifiers);
+                    }
+                }
+            }
+            // Handle pin toggle
+            if let Some(idx) = response.toggle_pin {
+                let ws = &self.workspaces[self.active_workspace];
+                if let Some(entry) = ws.sidebar_entries.get(idx) {
+                    self.context_manager.toggle_pin(entry.path.clone());
+                }
+            }
+        }
+        let ws = &self.workspaces[self.active_workspace];
+        let entry_path = |idx: usize| ws.sidebar_entry(idx).map(|entry| entry.path.clone());
+        let trash = response.trash.and_then(entry_path);
+        let delete = response.delete_permanently.and_then(entry_path);
+        if let Some(path) = trash {
+            self.trash_paths(vec![path]);
+        }
+        if delete.is_some() {
+            self.delete_confirm = delete;
+            self.delete_confirmation.reset();
+        }
+        if response.toggle_filter {
+            self.toggle_sidebar_filter();
+        }
+        // Handle collapse/expand all
+        if response.collapse_all {
+            self.collapse_all_directories();
+        }
+        if response.expand_all {
+            self.expand_all_directories();
+        }
+        // Handle pane click - focus that pane and maybe reload sidebar
+        if let Some(clicked_pane) = response.pane_clicked {
+            self.workspaces[self.active_workspace].focused_pane = clicked_pane;
+            self.sync_sidebar_root(clicked_pane);
+        }
+    }
+
+    /// Show or hide the sidebar filter box
+    fn toggle_sidebar_filter(&mut self) {
+        let ws = &mut self.workspaces[self.active_workspace];
+        if ws.sidebar_filter.is_some() {
+            ws.sidebar_filter = None;
+        } else {
+            ws.sidebar_filter = Some(SidebarFilter::new(ws.sidebar_entries.clone()));
+            self.sidebar_focused = true;
+        }
+    }
+
+    /// Update the filter query, starting the deep scan on first input
+    fn set_sidebar_filter_query(&mut self, query: String) {
+        let workspace_id = self.active_workspace;
+        let Some(filter) = self.workspaces[workspace_id].sidebar_filter.as_mut() else {
+            return;
+        };
+
+        filter.query = query;
+        filter.refresh();
+
+        if !filter.scan_started && !filter.query.trim().is_empty() {
+            self.scan_filter_source_async(workspace_id);
+        }
+    }
+
+    /// Process update check results
+    fn process_update_check(&mut self) {
+        while let Ok(version) = self.update_rx.try_recv() {
+            self.notifier.notify(crate::notifier::Notice::Update);
+            self.available_update = Some(version);
+        }
+    }
+
+    /// Follow the Do Not Disturb schedule; summarize what it held back when it ends
+    fn update_dnd(&mut self, ctx: &Context) {
+        if let Some(summary) = self.notifier.update(crate::session_log::local_minutes()) {
+            self.toast = Some(Toast::new(summary));
+        }
+        // Quiet hours start and end without any input
+        if self.notifier.has_schedule() {
+            ctx.request_repaint_after(std::time::Duration::from_secs(60));
+        }
+    }
+
     /// Process context manager events
     fn process_context_events(&mut self) {
         use crate::context::ContextEvent;
 
+        if self.context_manager.is_git_available() {
+            let paths = self.git_paths_of_interest();
+            self.context_manager.set_paths_of_interest(paths);
+        }
         let events = self.context_manager.poll();
 
         for event in events {
             match event {
-                ContextEvent::FileSystemChanged { affected_dir, .. } => {
+                ContextEvent::FileSystemChanged { path, affected_dir } => {
+                    self.reload_changed_file_views(&path);
+                    self.thumbnails.invalidate(&path);
+                    let ws = &self.workspaces[self.active_workspace];
+                    if path.file_name().is_some_and(|n| n == crate::project::PROJECT_LAYOUT_FILE) {
+                        let root = ws.sidebar_root.clone();
+                        self.offer_project_layout(root);
+                    }
                     let ws = &self.workspaces[self.active_workspace];
-                    if affected_dir.starts_with(&ws.sidebar_root) ||
-                       ws.sidebar_root.starts_with(&affected_dir) {
+                    // Watcher paths are canonical (e.g. /private/var on macOS)
+                    let canonical_root = ws.sidebar_root
+                        .canonicalize()
+                        .unwrap_or_else(|_| ws.sidebar_root.clone());
+                    if affected_dir.starts_with(&canonical_root) ||
+                       canonical_root.starts_with(&affected_dir) {
                         let root = ws.sidebar_root.clone();
                         self.load_directory_async(self.active_workspace, root);
                     }
                 }
                 ContextEvent::GitStatusUpdated => {
-                    self.update_sidebar_git_status();
+                    // Applied by update_sidebar_git_status once the cache
+                    // generation changes
                 }
                 ContextEvent::FilePinned(path) => {
                     log::info!("File pinned: {:?}", path);
@@ -1059,10 +6601,125 @@ impl VibeTermApp {
         }
     }
 
+    /// Record finished background fetches and start the next one when due
+    fn process_git_fetches(&mut self) {
+        while let Ok((root, result)) = self.git_fetch_rx.try_recv() {
+            self.context_manager.fetch_finished(&root, result);
+        }
+        if let Some(root) = self.context_manager.take_due_fetch() {
+            self.spawn_git_fetch(root);
+        }
+    }
+
+    /// Run `git fetch` for `root` off the UI thread
+    fn spawn_git_fetch(&self, root: PathBuf) {
+        let tx = self.git_fetch_tx.clone();
+        self.tokio_runtime.spawn(async move {
+            let fetch_root = root.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                crate::context::fetch::run_fetch(&fetch_root)
+            }).await;
+
+            let result = result.unwrap_or_else(|e| Err(format!("Fetch task failed: {}", e)));
+            let _ = tx.send((root, result));
+        });
+    }
+
+    /// Open the project search in the sidebar, on the sidebar root
+    fn open_project_search(&mut self) {
+        if self.config.ui.sidebar_mode != SidebarMode::Pinned {
+            self.sidebar_reveal.open(std::time::Instant::now());
+        } else if !self.sidebar_visible {
+            self.search_reveals_sidebar = true;
+        }
+        if !self.sidebar_shown() {
+            self.search_reveals_sidebar = false;
+            self.toast = Some(Toast::new("The sidebar is hidden; widen the window to search"));
+            return;
+        }
+        let root = self.current_workspace().sidebar_root.clone();
+        self.search_panel.open(root, std::time::Instant::now());
+        self.sidebar_focused = true;
+    }
+
+    fn close_project_search(&mut self) {
+        self.search_panel.close();
+        self.search_reveals_sidebar = false;
+        self.search_cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Collect project search results and start a search once edits settle
+    fn process_project_search(&mut self) {
+        while let Ok((id, message)) = self.search_rx.try_recv() {
+            if id == self.search_id {
+                self.search_panel.push(message);
+            }
+        }
+
+        let now = std::time::Instant::now();
+        if let Some(delay) = self.search_panel.pending_delay(now) {
+            self.ctx.request_repaint_after(delay);
+        }
+        let Some(options) = self.search_panel.take_due_search(now) else {
+            return;
+        };
+
+        // Only the latest search keeps running
+        self.search_cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+        self.search_cancel = Arc::default();
+        self.search_id += 1;
+
+        let request = crate::project_search::SearchRequest {
+            root: self.search_panel.root().to_path_buf(),
+            options,
+            ignore_patterns: self.config.ui.file_tree_ignore_patterns.clone(),
+            max_results: self.config.search.max_results,
+            use_ripgrep: self.config.search.use_ripgrep,
+        };
+        let (id, cancel, tx, ctx) = (self.search_id, self.search_cancel.clone(), self.search_tx.clone(), self.ctx.clone());
+        self.tokio_runtime.spawn_blocking(move || {
+            crate::project_search::run(&request, &cancel, &mut |message| {
+                let _ = tx.send((id, message));
+                ctx.request_repaint();
+            });
+        });
+    }
+
+    /// Open a search result in a file viewer, at its line if given
+    fn open_search_result(&mut self, path: PathBuf, line: Option<u32>) {
+        self.create_file_tab(path);
+        let ws = self.current_workspace_mut();
+        let pane_id = ws.focused_pane;
+        if let Some(TabContent::FileViewer { goto_line, .. }) = ws.get_content_mut(pane_id) {
+            *goto_line = line;
+        }
+    }
+
+    /// Paths whose git status is on screen: expanded sidebar folders, files
+    /// at the sidebar root and open file viewers
+    fn git_paths_of_interest(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.current_workspace().sidebar_entries
+            .iter()
+            .filter(|entry| if entry.is_dir { entry.is_expanded } else { entry.depth == 0 })
+            .map(|entry| entry.path.clone())
+            .collect();
+        for ws in &self.workspaces {
+            for pane_id in ws.pane_ids() {
+                if let Some(TabContent::FileViewer { path, .. }) = ws.get_content(pane_id) {
+                    paths.push(path.clone());
+                }
+            }
+        }
+        paths
+    }
+
+    /// Give the active sidebar tree the current git statuses, a chunk per
+    /// frame on large trees
     fn update_sidebar_git_status(&mut self) {
         let ws = &mut self.workspaces[self.active_workspace];
-        for entry in &mut ws.sidebar_entries {
-            entry.git_status = Some(self.context_manager.get_git_status(&entry.path));
+        let git_cache = self.context_manager.git_cache();
+        if !ws.git_sync.step(&mut ws.sidebar_entries, git_cache, GIT_STATUS_CHUNK) {
+            self.ctx.request_repaint();
         }
     }
 
@@ -1076,16 +6733,16 @@ impl VibeTermApp {
     /// Toggle directory expansion
     fn toggle_directory(&mut self, idx: usize) {
         let ws = &mut self.workspaces[self.active_workspace];
+        let options = ScanOptions::new(&ws.sidebar_root, ws.tree_visibility, &self.config.ui);
+        let git_cache = self.context_manager.git_cache();
         if let Some(entry) = ws.sidebar_entries.get_mut(idx) {
             if entry.is_dir {
                 entry.is_expanded = !entry.is_expanded;
 
                 if entry.is_expanded {
-                    let children = load_directory_entries(&entry.path, entry.depth + 1);
+                    let children = list_directory(&entry.path, entry.depth + 1, &options, &|path| git_cache.is_ignored(path));
                     let insert_pos = idx + 1;
-                    for (i, child) in children.into_iter().enumerate() {
-                        ws.sidebar_entries.insert(insert_pos + i, child);
-                    }
+                    ws.sidebar_entries.splice(insert_pos..insert_pos, children);
                 } else {
                     let depth = entry.depth;
                     let mut remove_count = 0;
@@ -1096,10 +6753,53 @@ impl VibeTermApp {
                             break;
                         }
                     }
-                    for _ in 0..remove_count {
-                        ws.sidebar_entries.remove(idx + 1);
-                    }
+                    ws.sidebar_entries.drain(idx + 1..idx + 1 + remove_count);
                 }
+
+                // Sibling and descendant connectors change with the tree's shape
+                crate::ui::update_tree_flags(&mut ws.sidebar_entries);
+                ws.git_sync.invalidate();
+                self.mark_tree_state_dirty();
+            }
+        }
+    }
+
+    /// Note an expansion or selection change in the active sidebar tree
+    fn mark_tree_state_dirty(&mut self) {
+        let ws = &mut self.workspaces[self.active_workspace];
+        if ws.tracked_tree_root.is_some() {
+            ws.tree_state_dirty = true;
+            self.schedule_tree_state_save();
+        }
+    }
+
+    fn schedule_tree_state_save(&mut self) {
+        if self.tree_state_save_at.is_none() {
+            self.tree_state_save_at = Some(std::time::Instant::now() + TREE_STATE_SAVE_DELAY);
+            self.ctx.request_repaint_after(TREE_STATE_SAVE_DELAY);
+        }
+    }
+
+    /// Write sidebar tree states of tracked project roots
+    ///
+    /// Only dirty ones once the save delay has passed, unless `all` (on exit).
+    fn save_tree_states(&mut self, all: bool) {
+        if !all && self.tree_state_save_at.is_none_or(|at| std::time::Instant::now() < at) {
+            return;
+        }
+        self.tree_state_save_at = None;
+
+        let dir = crate::tree_state::state_dir();
+        let now = std::time::SystemTime::now();
+        for ws in &mut self.workspaces {
+            let dirty = std::mem::take(&mut ws.tree_state_dirty);
+            // Until its load finishes, the tree still shows the previous root
+            let Some(root) = ws.tracked_tree_root.as_ref().filter(|_| dirty || all) else {
+                continue;
+            };
+            let state = TreeState::capture(root, &ws.sidebar_entries, ws.selected_sidebar_entry, now);
+            if let Err(e) = state.save(&dir) {
+                log::warn!("{}", e);
             }
         }
     }
@@ -1117,6 +6817,9 @@ impl VibeTermApp {
 
         // Remove all child entries (depth > 0)
         ws.sidebar_entries.retain(|entry| entry.depth == 0);
+        crate::ui::update_tree_flags(&mut ws.sidebar_entries);
+        ws.git_sync.invalidate();
+        self.mark_tree_state_dirty();
     }
 
     /// Expand all directories in sidebar
@@ -1131,65 +6834,152 @@ impl VibeTermApp {
         }
 
         // Reload directory to show all children
+        ws.expand_all_pending = true;
         let root = ws.sidebar_root.clone();
         self.load_directory_async(self.active_workspace, root);
     }
 
-    /// Compute drop zones for all panes except the source pane
-    fn compute_drop_zones(&self, layout: &ComputedLayout, source_id: PaneId) -> Vec<DropZoneInfo> {
-        let mut zones = Vec::new();
-        let edge_ratio = 0.25;
+    /// Show the next set of entries in the sidebar (Normal, Show Hidden, Show All)
+    fn cycle_tree_visibility(&mut self) {
+        let workspace_id = self.active_workspace;
+        let ws = &mut self.workspaces[workspace_id];
+        ws.tree_visibility = ws.tree_visibility.next();
+        let root = ws.sidebar_root.clone();
+        let filtering = ws.sidebar_filter.is_some();
+        self.load_directory_async(workspace_id, root);
+        if filtering {
+            self.scan_filter_source_async(workspace_id);
+        }
+    }
+
+    /// Handle sidebar interaction while the filter is active
+    fn handle_filtered_sidebar_response(&mut self, response: &SidebarResponse) {
+        if let Some(query) = response.filter_changed.clone() {
+            self.set_sidebar_filter_query(query);
+        }
+
+        let ws = &mut self.workspaces[self.active_workspace];
+        let Some(filter) = ws.sidebar_filter.as_mut() else {
+            return;
+        };
+
+        if let Some(idx) = response.selected {
+            filter.selected = Some(idx);
+        }
+        let follow = response.selected.is_some() && ws.follow_pane.is_some();
+
+        // Enter opens the first match, double-click opens the clicked file
+        let open_path = if response.filter_submitted {
+            filter.first_file_match().map(|entry| entry.path.clone())
+        } else {
+            response.opened_file
+                .and_then(|idx| filter.visible.get(idx))
+                .filter(|entry| !entry.is_dir)
+                .map(|entry| entry.path.clone())
+        };
+
+        if response.filter_cleared {
+            ws.sidebar_filter = None;
+        }
+
+        if follow {
+            self.queue_follow_selection();
+        }
+        if let Some(path) = open_path {
+            let modifiers = self.ctx.input(|i| i.modifiers);
+            self.open_sidebar_file(path, modifiers);
+        }
+    }
 
-        for (pane_id, rect) in &layout.pane_rects {
-            if *pane_id == source_id {
-                continue; // Skip source pane
+    /// Draw pane number badges and act on the picked pane
+    ///
+    /// A number key or click picks that pane; Esc cancels. Panes that can't
+    /// be picked are dimmed.
+    fn show_pane_picker(&mut self, ui: &mut egui::Ui, layout: &ComputedLayout, focused_pane: PaneId) {
+        let ws = self.current_workspace();
+        let pane_ids = ws.pane_ids();
+        let pickable = |pane_id: PaneId| match &self.pane_pick {
+            Some(PanePick::Send(_)) => pane_id != focused_pane,
+            Some(PanePick::Diff { first, .. }) => {
+                *first != Some(pane_id) && matches!(ws.get_content(pane_id), Some(TabContent::Terminal(_)))
             }
+            None => false,
+        };
+        let mut target = None;
+
+        let (clicked_pos, pressed_number, cancelled) = ui.input(|i| {
+            let clicked = i.pointer.button_clicked(egui::PointerButton::Primary)
+                .then(|| i.pointer.interact_pos())
+                .flatten();
+            let number = [
+                Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5,
+                Key::Num6, Key::Num7, Key::Num8, Key::Num9,
+            ]
+            .iter()
+            .position(|key| i.key_pressed(*key));
+            (clicked, number, i.key_pressed(Key::Escape))
+        });
 
-            let w = rect.width();
-            let h = rect.height();
+        for (idx, pane_id) in pane_ids.iter().enumerate() {
+            let Some(&rect) = layout.pane_rects.get(pane_id) else {
+                continue;
+            };
+            if !pickable(*pane_id) {
+                ui.painter().rect_filled(rect, 0.0, egui::Color32::from_black_alpha(120));
+                continue;
+            }
 
-            // Top zone (25% of height from top)
-            zones.push(DropZoneInfo {
-                zone: DropZone::Top(*pane_id),
-                rect: egui::Rect::from_min_size(rect.min, egui::vec2(w, h * edge_ratio)),
-                highlight_rect: egui::Rect::from_min_size(rect.min, egui::vec2(w, h * 0.5)),
-            });
+            // Pane number badge
+            let center = rect.center();
+            ui.painter().circle_filled(center, 18.0, self.theme.primary);
+            ui.painter().text(
+                center,
+                egui::Align2::CENTER_CENTER,
+                format!("{}", idx + 1),
+                theme::ui_font(18.0),
+                self.theme.background,
+            );
 
-            // Bottom zone (25% of height from bottom)
-            zones.push(DropZoneInfo {
-                zone: DropZone::Bottom(*pane_id),
-                rect: egui::Rect::from_min_size(
-                    egui::pos2(rect.min.x, rect.max.y - h * edge_ratio),
-                    egui::vec2(w, h * edge_ratio),
-                ),
-                highlight_rect: egui::Rect::from_min_size(
-                    egui::pos2(rect.min.x, rect.min.y + h * 0.5),
-                    egui::vec2(w, h * 0.5),
-                ),
-            });
+            if pressed_number == Some(idx) || clicked_pos.is_some_and(|pos| rect.contains(pos)) {
+                target = Some(*pane_id);
+            }
+        }
 
-            // Left zone (25% of width from left)
-            zones.push(DropZoneInfo {
-                zone: DropZone::Left(*pane_id),
-                rect: egui::Rect::from_min_size(rect.min, egui::vec2(w * edge_ratio, h)),
-                highlight_rect: egui::Rect::from_min_size(rect.min, egui::vec2(w * 0.5, h)),
-            });
+        if let Some(target) = target {
+            match self.pane_pick.take() {
+                Some(PanePick::Send(text)) => self.send_text_to_pane(target, &text, false),
+                Some(PanePick::Diff { scope, first: None }) => {
+                    self.pane_pick = Some(PanePick::Diff { scope, first: Some(target) });
+                }
+                Some(PanePick::Diff { scope, first: Some(first) }) => self.open_pane_diff(first, target, scope),
+                None => {}
+            }
+        } else if cancelled || clicked_pos.is_some() {
+            // Esc or click outside any other pane
+            self.pane_pick = None;
+        }
+    }
 
-            // Right zone (25% of width from right)
-            zones.push(DropZoneInfo {
-                zone: DropZone::Right(*pane_id),
-                rect: egui::Rect::from_min_size(
-                    egui::pos2(rect.max.x - w * edge_ratio, rect.min.y),
-                    egui::vec2(w * edge_ratio, h),
-                ),
-                highlight_rect: egui::Rect::from_min_size(
-                    egui::pos2(rect.min.x + w * 0.5, rect.min.y),
-                    egui::vec2(w * 0.5, h),
-                ),
-            });
+    /// Vertical line where a dragged tab (or pane) would be inserted
+    fn paint_tab_drop_indicator(&self, painter: &egui::Painter, drop_index: usize, tab_rects: &[(usize, egui::Rect)]) {
+        let edge = if drop_index == 0 {
+            tab_rects.first().map(|(_, rect)| (rect.left(), rect))
+        } else {
+            tab_rects.get(drop_index - 1).map(|(_, rect)| (rect.right(), rect))
+        };
+        if let Some((x, rect)) = edge {
+            painter.line_segment(
+                [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                egui::Stroke::new(3.0, self.theme.primary),
+            );
         }
+    }
 
-        zones
+    /// Insertion index for a pane dropped on the tab bar at `pos`, if it is there
+    fn pane_drop_tab_index(&self, pos: egui::Pos2) -> Option<usize> {
+        self.tab_bar_rect
+            .contains(pos)
+            .then(|| self.find_tab_drop_zone(pos, &self.tab_rects).unwrap_or(self.tab_rects.len()))
     }
 
     /// Find drop zone for tab at cursor position
@@ -1212,57 +7002,81 @@ impl VibeTermApp {
 
     /// Execute a pane drop operation
     fn execute_pane_drop(&mut self, source_id: PaneId, zone: DropZone) {
+        let max_depth = self.config.ui.max_split_depth;
         let ws = &mut self.workspaces[self.active_workspace];
+        if crate::layout::depth_after_drop(&ws.root, source_id, zone).is_some_and(|depth| depth > max_depth) {
+            self.toast = Some(Toast::new(format!("Splits can nest at most {} levels deep", max_depth)));
+            return;
+        }
+        let old_root = std::mem::replace(&mut ws.root, placeholder_root());
 
-        // Create a placeholder to swap with
-        let placeholder = LayoutNode::Leaf {
-            id: PaneId(u64::MAX),
-            content: TabContent::FileViewer {
-                path: std::path::PathBuf::new(),
-                content: String::new(),
-                scroll_offset: 0.0,
-            },
-        };
-
-        // Step 1: Extract source pane from tree
-        let old_root = std::mem::replace(&mut ws.root, placeholder);
-
-        if let Some((tree_without_source, extracted_content)) = crate::layout::extract_pane(old_root, source_id) {
-            // Step 2: Determine target and direction from zone
-            let (target_id, direction, before) = match zone {
-                DropZone::Top(id) => (id, SplitDirection::Vertical, true),
-                DropZone::Bottom(id) => (id, SplitDirection::Vertical, false),
-                DropZone::Left(id) => (id, SplitDirection::Horizontal, true),
-                DropZone::Right(id) => (id, SplitDirection::Horizontal, false),
-            };
-
-            // Step 3: Insert at new location (keeping same PaneId for PTY connection)
-            ws.root = crate::layout::insert_adjacent(
-                tree_without_source,
-                target_id,
-                source_id,
-                extracted_content,
-                direction,
-                before,
-            );
-
-            // Keep focus on the moved pane
-            ws.focused_pane = source_id;
-        } else {
-            // Extraction failed (single pane?), restore original
-            // This shouldn't happen if drop zones are computed correctly
-            log::warn!("Failed to extract pane {} for drop", source_id.0);
+        match crate::layout::drop_pane(old_root, source_id, zone) {
+            Ok(new_root) => {
+                ws.root = new_root;
+                // Keep focus on the moved pane
+                ws.focused_pane = source_id;
+            }
+            Err(unchanged) => {
+                // Only pane, or a zone of the pane itself
+                log::warn!("Failed to move pane {} for drop", source_id.0);
+                ws.root = unchanged;
+            }
         }
     }
 
+    /// Faint line across a divider's split and its side percentages (or a locked side's px)
+    fn paint_divider_preview(&self, ui: &egui::Ui, divider: &crate::layout::DividerInfo, size: SplitSize) {
+        // Panes are painted after the dividers, so draw above them
+        let painter = ui.ctx().layer_painter(egui::LayerId::new(egui::Order::Tooltip, egui::Id::new("divider_preview")));
+        let center = divider.rect.center();
+        let preview = egui::Stroke::new(1.0, self.theme.primary.gamma_multiply(0.35));
+        let parent = divider.parent_rect;
+        match divider.direction {
+            SplitDirection::Horizontal => {
+                painter.vline(center.x, parent.y_range(), preview);
+            }
+            SplitDirection::Vertical => {
+                painter.hline(parent.x_range(), center.y, preview);
+            }
+        }
+
+        let available = crate::layout::available_length(parent, divider.direction, DIVIDER_WIDTH);
+        let (first, second) = crate::layout::split_percentages(size.ratio(available));
+        let (first, second) = match size {
+            SplitSize::Ratio(_) => (format!("{}%", first), format!("{}%", second)),
+            SplitSize::FixedFirst(_) => (format!("{:.0}px", size.first_length(available)), "auto".to_string()),
+            SplitSize::FixedSecond(_) => ("auto".to_string(), format!("{:.0}px", available - size.first_length(available))),
+        };
+        let separator = match divider.direction {
+            SplitDirection::Horizontal => "│",
+            SplitDirection::Vertical => "/",
+        };
+        let label = format!("{} {} {}", first, separator, second);
+        let anchor = ui.input(|i| i.pointer.latest_pos()).unwrap_or(center) + egui::vec2(12.0, 12.0);
+        let galley = painter.layout_no_wrap(label, theme::ui_font(11.0), self.theme.text);
+        let rect = egui::Rect::from_min_size(anchor, galley.size()).expand(4.0);
+        painter.rect_filled(rect, 3.0, self.theme.surface);
+        painter.rect_stroke(rect, 3.0, egui::Stroke::new(1.0, self.theme.border), egui::StrokeKind::Inside);
+        painter.galley(anchor, galley, self.theme.text);
+    }
 
     /// Render panes using the binary split tree layout
     fn render_panes(&mut self, ui: &mut egui::Ui) {
-        let terminal_theme = self.cached_terminal_theme.clone();
+        // Tab switches this frame are done; the tab leaving keeps its scroll positions
+        let active = self.active_workspace;
+        self.wake_workspace(active);
+        for (index, ws) in self.workspaces.iter_mut().enumerate() {
+            ws.set_shown(index == active);
+        }
+        let restore_scroll = std::mem::take(&mut self.workspaces[active].restore_scroll);
+
+        let terminal_theme = Arc::clone(&self.cached_terminal_theme);
+        let dim_terminal_theme = Arc::clone(&self.cached_dim_terminal_theme);
         let focused_pane = self.current_workspace().focused_pane;
 
         // Compute layout for all panes
         let available_rect = ui.available_rect_before_wrap();
+        self.pane_area = available_rect;
         let mut layout = ComputedLayout::new();
         let mut path = Vec::new();
         self.workspaces[self.active_workspace]
@@ -1270,15 +7084,27 @@ impl VibeTermApp {
             .compute_layout(available_rect, DIVIDER_WIDTH, &mut path, &mut layout);
 
         // Batch input state reads for efficiency
-        let (clicked_primary, button_pressed, pointer_pos, pointer_released) = ui.input(|i| (
+        let (clicked_primary, clicked_secondary, button_pressed, pointer_pos, pointer_released) = ui.input(|i| (
             i.pointer.button_clicked(egui::PointerButton::Primary),
+            i.pointer.button_clicked(egui::PointerButton::Secondary),
             i.pointer.button_pressed(egui::PointerButton::Primary),
             i.pointer.latest_pos(),
             i.pointer.any_released(),
         ));
 
-        if clicked_primary {
+        // Ignore clicks on floating windows (notes, palette) above the panes
+        let over_window = pointer_pos.is_some_and(|pos| {
+            ui.ctx().layer_id_at(pos).is_some_and(|layer| layer.order != egui::Order::Background)
+        });
+        let clicked_primary = clicked_primary && !over_window;
+        let clicked_secondary = clicked_secondary && !over_window;
+        let button_pressed = button_pressed && !over_window;
+
+        if clicked_primary && self.pane_pick.is_none() {
             if let Some(pos) = pointer_pos {
+                if available_rect.contains(pos) {
+                    self.sidebar_focused = false;
+                }
                 for (pane_id, rect) in &layout.pane_rects {
                     if rect.contains(pos) && *pane_id != focused_pane {
                         self.workspaces[self.active_workspace].focused_pane = *pane_id;
@@ -1289,20 +7115,58 @@ impl VibeTermApp {
             }
         }
 
+        // Right-click opens the pane menu (and focuses the pane)
+        if clicked_secondary && self.pane_pick.is_none() {
+            if let Some(pos) = pointer_pos {
+                if let Some((pane_id, rect)) = layout.pane_rects.iter().find(|(_, rect)| rect.contains(pos)) {
+                    let ws = &mut self.workspaces[self.active_workspace];
+                    ws.focused_pane = *pane_id;
+                    self.pane_menu = Some((*pane_id, pos));
+                    self.pane_menu_line = match ws.get_content(*pane_id) {
+                        Some(TabContent::Terminal(terminal)) => {
+                            let cell_height = terminal.backend.cell_height().max(1) as f32;
+                            let row = ((pos.y - rect.shrink(2.0).top()) / cell_height).floor() as i32;
+                            Some(row - terminal.backend.grid().display_offset() as i32)
+                        }
+                        _ => None,
+                    };
+                }
+            }
+        }
+        let picking = self.pane_pick.is_some();
+        let has_input = ui.input(|i| !i.events.is_empty());
+        let firehose = &self.config.terminal.firehose;
+        let firehose_thresholds = (firehose.enter_kbps as f64 * 1024.0, firehose.exit_kbps as f64 * 1024.0);
+        self.throughput.prune(std::time::Instant::now());
+        let resize_debounce = std::time::Duration::from_millis(self.config.ui.resize_debounce_ms);
+        let follow_triggers = crate::pane_view::FollowTriggers {
+            output: self.config.terminal.scroll_on_output,
+            keystroke: self.config.terminal.scroll_on_keystroke,
+        };
+        let terminal_focus = self.terminal_has_keyboard_focus();
+        let presenting = self.presentation.is_active();
+        let highlight_root = self.config.ui.highlight_root_panes;
+        let wrap_markers = self.config.terminal.show_wrap_markers;
+        let wrap_marker_columns = self.config.terminal.wrap_marker_columns;
+        let terminal_font = TerminalFont::new(FontSettings {
+            font_type: egui::FontId::monospace(
+                self.presentation.font_size(self.config.font.terminal_size, self.config.ui.presentation_font_bump),
+            ),
+        });
+
         // Handle pane drag-and-drop
         // Start potential drag on button press (not click release)
+        // A press in a divider's grab margin resizes instead
         if button_pressed && self.dragging_pane.is_none() && self.dragging_divider.is_none() {
             if let Some(pos) = pointer_pos {
-                for (pane_id, rect) in &layout.pane_rects {
-                    if rect.contains(pos) {
-                        self.dragging_pane = Some(PaneDragState {
-                            source_pane_id: *pane_id,
-                            start_pos: pos,
-                            current_pos: pos,
-                            drag_active: false,
-                        });
-                        break;
-                    }
+                let owner = crate::layout::press_owner(pos, &layout, crate::layout::DIVIDER_GRAB_MARGIN);
+                if let crate::layout::PressOwner::Pane(pane_id) = owner {
+                    self.dragging_pane = Some(PaneDragState {
+                        source_pane_id: pane_id,
+                        start_pos: pos,
+                        current_pos: pos,
+                        drag_active: false,
+                    });
                 }
             }
         }
@@ -1330,8 +7194,10 @@ impl VibeTermApp {
         // Handle drop on button release (separate block to avoid borrow issues)
         if pointer_released {
             if let Some(drag_state) = self.dragging_pane.take() {
-                if drag_state.drag_active {
-                    let drop_zones = self.compute_drop_zones(&layout, drag_state.source_pane_id);
+                if let Some(index) = self.pane_drop_tab_index(drag_state.current_pos).filter(|_| drag_state.drag_active) {
+                    self.move_pane_to_new_tab(drag_state.source_pane_id, index);
+                } else if drag_state.drag_active {
+                    let drop_zones = crate::layout::compute_drop_zones(&layout, drag_state.source_pane_id);
                     if let Some(zone_info) = drop_zones.iter().find(|z| z.rect.contains(drag_state.current_pos)) {
                         self.execute_pane_drop(drag_state.source_pane_id, zone_info.zone);
                     }
@@ -1342,42 +7208,31 @@ impl VibeTermApp {
 
         // Handle divider dragging
         let mut needs_recompute = false;
-        if let Some((_, divider_idx)) = self.dragging_divider {
-            if ui.input(|i| i.pointer.any_released()) {
+        let mut divider_drag_ended = false;
+        if let Some(drag) = self.dragging_divider.clone() {
+            let (released, cancel_key, alt) = ui.input_mut(|i| (
+                i.pointer.any_released(),
+                i.consume_key(egui::Modifiers::NONE, Key::Escape),
+                i.modifiers.alt,
+            ));
+            let root = &mut self.workspaces[drag.workspace].root;
+            let window = ui.ctx().screen_rect();
+
+            if cancel_key || (released && crate::layout::released_outside(pointer_pos, window)) {
+                // Esc, or letting go outside the window, puts the split back
+                drag.restore(root);
                 self.dragging_divider = None;
-            } else if let Some(pos) = pointer_pos {
-                // Get the divider info
-                if let Some(divider) = layout.dividers.get(divider_idx) {
-                    // Get the split node at this path and update its ratio
-                    if let Some(split_node) = self.workspaces[self.active_workspace]
-                        .root
-                        .get_split_at_path_mut(&divider.path)
-                    {
-                        if let LayoutNode::Split { direction, ratio, .. } = split_node {
-                            let parent_rect = if divider.path.is_empty() {
-                                available_rect
-                            } else {
-                                // For nested splits, we need the parent rect
-                                // For now, use available_rect as approximation
-                                available_rect
-                            };
-
-                            let new_ratio = match direction {
-                                SplitDirection::Horizontal => {
-                                    let relative_x = pos.x - parent_rect.left();
-                                    (relative_x / (parent_rect.width() - DIVIDER_WIDTH))
-                                        .clamp(crate::layout::MIN_SPLIT_RATIO, crate::layout::MAX_SPLIT_RATIO)
-                                }
-                                SplitDirection::Vertical => {
-                                    let relative_y = pos.y - parent_rect.top();
-                                    (relative_y / (parent_rect.height() - DIVIDER_WIDTH))
-                                        .clamp(crate::layout::MIN_SPLIT_RATIO, crate::layout::MAX_SPLIT_RATIO)
-                                }
-                            };
-                            *ratio = new_ratio;
-                            needs_recompute = true;
-                        }
-                    }
+                ui.ctx().stop_dragging();
+                needs_recompute = true;
+                divider_drag_ended = true;
+            } else if released {
+                self.dragging_divider = None;
+                divider_drag_ended = true;
+            } else if let (Some(pos), Some(divider)) = (pointer_pos, layout.dividers.get(drag.divider)) {
+                let new_ratio = crate::layout::drag_ratio(pos, divider.parent_rect, divider.direction, alt);
+                let available = crate::layout::available_length(divider.parent_rect, divider.direction, DIVIDER_WIDTH);
+                if let Some(size) = root.size_at_path(&drag.path) {
+                    needs_recompute = root.set_size_at_path(&drag.path, size.with_ratio(new_ratio, available));
                 }
             }
         }
@@ -1392,30 +7247,23 @@ impl VibeTermApp {
         }
 
         let focused_pane = self.current_workspace().focused_pane;
-
-        // Render dividers first (background layer)
-        for (idx, divider) in layout.dividers.iter().enumerate() {
-            let divider_response = ui.allocate_rect(divider.rect, egui::Sense::click_and_drag());
-
-            if divider_response.drag_started() {
-                self.dragging_divider = Some((self.active_workspace, idx));
-            }
-
-            let divider_color = if divider_response.dragged() || divider_response.hovered() {
-                self.theme.primary
-            } else {
-                self.theme.border
-            };
-            ui.painter().rect_filled(divider.rect, 0.0, divider_color);
-
-            if divider_response.hovered() || divider_response.dragged() {
-                let cursor = match divider.direction {
-                    SplitDirection::Horizontal => egui::CursorIcon::ResizeHorizontal,
-                    SplitDirection::Vertical => egui::CursorIcon::ResizeVertical,
-                };
-                ui.ctx().set_cursor_icon(cursor);
-            }
-        }
+        let follow_pane = self.current_workspace().follow_pane;
+        let pane_meta = self.current_workspace().pane_meta.clone();
+
+        // File viewer banner choice, applied after rendering
+        let mut file_reload = None;
+        let mut markdown_toggle = None;
+        let mut markdown_link = None;
+        let mut wrap_toggle = None;
+        let mut diff_command = None;
+        let mut empty_drop = None;
+        let mut shell_failure_action = None;
+        let mut blame_toggle = None;
+        let mut blame_restart = None;
+        let mut copied_hash = false;
+        let mut ime_rect = None;
+        let mut stats_command = None;
+        let mut color_test_script = None;
 
         // Render panes - O(n) single traversal instead of O(n²)
         // Collect all pane contents in one traversal, then render each
@@ -1428,10 +7276,25 @@ impl VibeTermApp {
             let Some(&rect) = layout.pane_rects.get(&pane_id) else {
                 continue;
             };
+            // Too small to draw into (and a terminal would get a zero-size PTY)
+            if !crate::layout::is_renderable(rect) {
+                ui.painter().rect_filled(rect, 0.0, self.theme.border);
+                continue;
+            }
             let is_focused = pane_id == focused_pane;
+            let tint = pane_meta.get(&pane_id).and_then(|meta| meta.tint);
+
+            let elevated = highlight_root && matches!(content, TabContent::Terminal(terminal) if terminal.is_elevated());
 
-            // Focus border
-            if is_focused {
+            // Focus border (root panes stay red either way)
+            if elevated {
+                ui.painter().rect_stroke(
+                    rect,
+                    0.0,
+                    egui::Stroke::new(2.0, self.theme.red),
+                    egui::StrokeKind::Inside,
+                );
+            } else if is_focused {
                 ui.painter().rect_stroke(
                     rect,
                     0.0,
@@ -1442,7 +7305,7 @@ impl VibeTermApp {
                 ui.painter().rect_stroke(
                     rect,
                     0.0,
-                    egui::Stroke::new(1.0, self.theme.border),
+                    egui::Stroke::new(1.0, tint.unwrap_or(self.theme.border)),
                     egui::StrokeKind::Inside,
                 );
             }
@@ -1450,45 +7313,580 @@ impl VibeTermApp {
             // Render pane content
             let inner_rect = rect.shrink(2.0);
             match content {
+                TabContent::Terminal(TerminalInstance { failure: Some(failure), .. }) => {
+                    ui.painter().rect_filled(inner_rect, 0.0, self.theme.background);
+                    ui.allocate_new_ui(
+                        egui::UiBuilder::new().max_rect(inner_rect.shrink(24.0)),
+                        |ui| {
+                            if let Some(action) = show_shell_failure(ui, failure, &self.theme) {
+                                shell_failure_action = Some((pane_id, action));
+                            }
+                        },
+                    );
+                }
                 TabContent::Terminal(terminal) => {
+                    // Hold back PTY resizes until the size settles
+                    let (size, recheck) = terminal.debounced_size(inner_rect.size(), resize_debounce, divider_drag_ended);
+                    if let Some(delay) = recheck {
+                        ui.ctx().request_repaint_after(delay);
+                    }
+
+                    // A firehose pane replays its last frame between redraws
+                    let firehose = self.throughput.is_firehose(terminal.id);
+                    let replay = firehose && !has_input && terminal.firehose_frame.as_ref().is_some_and(|frame| {
+                        frame.rect == inner_rect && frame.drawn_at.elapsed() < crate::throughput::FIREHOSE_FRAME_INTERVAL
+                    });
+                    if !firehose {
+                        terminal.firehose_frame = None;
+                    }
+
                     ui.allocate_new_ui(
                         egui::UiBuilder::new().max_rect(inner_rect),
                         |ui| {
-                            TerminalView::new(ui, &mut terminal.backend)
-                                .set_theme(terminal_theme.clone())
-                                .set_focus(is_focused)
-                                .set_size(inner_rect.size())
-                                .ui(ui);
+                            ui.set_clip_rect(inner_rect.intersect(ui.clip_rect()));
+                            if let (true, Some(frame)) = (replay, &terminal.firehose_frame) {
+                                // Same allocation as the view, so it keeps its focus
+                                ui.allocate_exact_size(size, egui::Sense::click());
+                                ui.ctx().graphics_mut(|graphics| {
+                                    let list = graphics.entry(ui.layer_id());
+                                    for egui::epaint::ClippedShape { clip_rect, shape } in &frame.shapes {
+                                        list.add(*clip_rect, shape.clone());
+                                    }
+                                });
+                                return;
+                            }
+
+                            // Focus is settled above, so the theme matches the focus border
+                            let theme = if is_focused { &terminal_theme } else { &dim_terminal_theme };
+                            let first_shape = ui.ctx().graphics(|graphics| {
+                                graphics.get(ui.layer_id()).map_or(0, |list| list.next_idx().0)
+                            });
+                            // Mock backends (headless runs) have nothing to draw
+                            if let Some(backend) = terminal.backend.as_backend_mut() {
+                                TerminalView::new(ui, backend)
+                                    .set_theme(egui_term::TerminalTheme::clone(theme))
+                                    .set_focus(is_focused && terminal_focus)
+                                    .set_font(terminal_font.clone())
+                                    .set_size(size)
+                                    .ui(ui);
+                            }
+
+                            let now = std::time::Instant::now();
+                            self.throughput.record(terminal.id, terminal.measure_output(), now);
+                            let (enter, exit) = firehose_thresholds;
+                            if self.throughput.update_firehose(terminal.id, now, enter, exit) {
+                                let shapes = ui.ctx().graphics(|graphics| {
+                                    graphics.get(ui.layer_id())
+                                        .map(|list| list.all_entries().skip(first_shape).cloned().collect())
+                                        .unwrap_or_default()
+                                });
+                                terminal.firehose_frame = Some(PaneFrame { rect: inner_rect, drawn_at: now, shapes });
+                            }
                         },
                     );
+                    if replay {
+                        ui.ctx().request_repaint_after(crate::throughput::FIREHOSE_FRAME_INTERVAL);
+                    } else {
+                        terminal.update_follow(follow_triggers);
+                    }
+
+                    // Output printed below a scrolled-back view; a click goes back down
+                    if let Some(unseen @ 1..) = terminal.follow.unseen() {
+                        let label = if unseen == 1 { "↓ 1 line below".to_string() } else { format!("↓ {} lines below", unseen) };
+                        let pill = ui.painter().layout_no_wrap(label, theme::ui_font(11.0), self.theme.background);
+                        let pill_rect = egui::Rect::from_center_size(
+                            egui::pos2(inner_rect.center().x, inner_rect.bottom() - 16.0),
+                            pill.size() + egui::vec2(16.0, 6.0),
+                        );
+                        let pill_response = ui.interact(pill_rect, ui.id().with(("follow_pill", pane_id)), egui::Sense::click())
+                            .on_hover_cursor(egui::CursorIcon::PointingHand);
+                        let fill = if pill_response.hovered() { self.theme.secondary } else { self.theme.primary };
+                        ui.painter().rect_filled(pill_rect, pill_rect.height() / 2.0, fill);
+                        ui.painter().galley(pill_rect.min + egui::vec2(8.0, 3.0), pill, self.theme.background);
+                        if pill_response.clicked() {
+                            terminal.scroll_to_line(0);
+                        }
+                    }
+
+                    // The OS puts IME candidates by the cursor of the terminal being typed in
+                    if is_focused && terminal_focus {
+                        let grid = terminal.backend.grid();
+                        let cursor = (
+                            grid.cursor.point.line.0 + grid.display_offset() as i32,
+                            grid.cursor.point.column.0,
+                        );
+                        let cell = egui::vec2(terminal.backend.cell_width() as f32, terminal.backend.cell_height() as f32);
+                        ime_rect = Some(crate::input::ime_cursor_rect(inner_rect, cell, cursor));
+                    }
+
+                    let mut name_right = inner_rect.right_top() + egui::vec2(-6.0, 4.0);
+                    if elevated {
+                        let badge = ui.painter().layout_no_wrap("root".to_string(), theme::ui_font(10.0), self.theme.background);
+                        let badge_rect = egui::Rect::from_min_size(
+                            name_right - egui::vec2(badge.size().x + 8.0, 0.0),
+                            badge.size() + egui::vec2(8.0, 2.0),
+                        );
+                        ui.painter().rect_filled(badge_rect, 3.0, self.theme.red);
+                        ui.painter().galley(badge_rect.min + egui::vec2(4.0, 1.0), badge, self.theme.background);
+                        name_right.x = badge_rect.left() - 6.0;
+                    }
+
+                    if let Some(name) = &terminal.name {
+                        ui.painter().text(
+                            name_right,
+                            egui::Align2::RIGHT_TOP,
+                            name,
+                            theme::ui_font(10.0),
+                            self.theme.text_dim,
+                        );
+                    }
+
+                    if !terminal.diagnostics.is_empty() {
+                        let count = terminal.diagnostics.len();
+                        ui.painter().text(
+                            inner_rect.left_top() + egui::vec2(6.0, 4.0),
+                            egui::Align2::LEFT_TOP,
+                            format!("⚠ {}", count),
+                            theme::ui_font(10.0),
+                            self.theme.yellow,
+                        );
+                    }
+
+                    if terminal.session_log.is_some() && !presenting {
+                        ui.painter().text(
+                            inner_rect.left_bottom() + egui::vec2(6.0, -4.0),
+                            egui::Align2::LEFT_BOTTOM,
+                            "● REC",
+                            theme::ui_font(10.0),
+                            self.theme.red,
+                        );
+                    }
+
+                    // A paste still waiting for a slow clipboard owner
+                    if self.pastes.is_slow(terminal.id, std::time::Instant::now()) {
+                        let label = ui.painter().text(
+                            inner_rect.right_bottom() + egui::vec2(-6.0, -4.0),
+                            egui::Align2::RIGHT_BOTTOM,
+                            "pasting…",
+                            theme::ui_font(10.0),
+                            self.theme.text_dim,
+                        );
+                        let spinner_rect = egui::Rect::from_center_size(label.left_center() - egui::vec2(10.0, 0.0), egui::vec2(10.0, 10.0));
+                        egui::Spinner::new().color(self.theme.text_dim).paint_at(ui, spinner_rect);
+                    }
+
+                    // Directory hook banner, dismissed with its ✕
+                    if let Some(text) = &terminal.dir_banner {
+                        let banner_rect = egui::Rect::from_min_size(inner_rect.min, egui::vec2(inner_rect.width(), 20.0));
+                        ui.painter().rect_filled(banner_rect, 0.0, self.theme.surface_light);
+                        ui.painter().text(
+                            banner_rect.left_center() + egui::vec2(8.0, 0.0),
+                            egui::Align2::LEFT_CENTER,
+                            text,
+                            theme::ui_font(11.0),
+                            self.theme.text,
+                        );
+                        let close_rect = egui::Rect::from_center_size(banner_rect.right_center() - egui::vec2(12.0, 0.0), egui::vec2(16.0, 16.0));
+                        let close = ui.put(close_rect, egui::Button::new(egui::RichText::new("✕").font(theme::ui_font(10.0))).frame(false));
+                        if close.clicked() {
+                            terminal.dir_banner = None;
+                        }
+                    }
+
+                    // Wrap markers on the rows long lines continue on
+                    if wrap_markers && !terminal.is_alt_screen() {
+                        let cell_height = terminal.backend.cell_height().max(1) as f32;
+                        for row in crate::long_lines::wrap_marker_rows(terminal.backend.grid(), wrap_marker_columns) {
+                            let top = inner_rect.left_top() + egui::vec2(0.0, row as f32 * cell_height);
+                            ui.painter().rect_filled(
+                                egui::Rect::from_min_size(top + egui::vec2(0.0, 2.0), egui::vec2(WRAP_MARKER_WIDTH, cell_height - 4.0)),
+                                0.0,
+                                self.theme.text_dim.gamma_multiply(0.6),
+                            );
+                        }
+                    }
+
+                    // Tick in the left gutter of marked lines in view
+                    if !terminal.marks.is_empty() && !terminal.is_alt_screen() {
+                        let grid = terminal.backend.grid();
+                        let cell_height = terminal.backend.cell_height().max(1) as f32;
+                        let rows = 0..grid.screen_lines() as i32;
+                        for row in terminal.marks.grid_lines().map(|line| line + grid.display_offset() as i32) {
+                            if rows.contains(&row) {
+                                let top = inner_rect.left_top() + egui::vec2(0.0, row as f32 * cell_height);
+                                ui.painter().rect_filled(
+                                    egui::Rect::from_min_size(top, egui::vec2(MARK_TICK_WIDTH, cell_height)),
+                                    0.0,
+                                    self.theme.primary,
+                                );
+                            }
+                        }
+                    }
+
+                    // Footer tag while a fullscreen app owns the pane
+                    if terminal.is_alt_screen() && !presenting {
+                        ui.painter().text(
+                            inner_rect.right_bottom() - egui::vec2(6.0, 4.0),
+                            egui::Align2::RIGHT_BOTTOM,
+                            "(app)",
+                            theme::ui_font(10.0),
+                            self.theme.text_dim,
+                        );
+                    } else if !presenting {
+                        paint_throughput_footer(ui, &self.throughput, terminal.id, inner_rect, &self.theme);
+                    }
+                    if let (Some(status), false) = (terminal.progress, presenting) {
+                        paint_progress_footer(ui, status.progress, inner_rect, &self.theme);
+                    }
                 }
-                TabContent::FileViewer { content: file_content, .. } => {
+                TabContent::FileViewer { content: file_content, expanded, scroll_offset, state, path, goto_line, markdown, wrap, blame } => {
                     ui.painter().rect_filled(inner_rect, 0.0, self.theme.background);
                     ui.allocate_new_ui(
                         egui::UiBuilder::new().max_rect(inner_rect),
                         |ui| {
-                            egui::ScrollArea::vertical()
+                            if let ViewerState::Loading(_) = state {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label(egui::RichText::new(format!("Loading {}…", path.display()))
+                                        .font(theme::ui_font(12.0))
+                                        .color(self.theme.text_dim));
+                                });
+                                return;
+                            }
+                            if let Some(mode) = show_file_notice(ui, *state, &self.theme) {
+                                file_reload = Some((pane_id, mode));
+                            }
+                            let wrap = wrap.unwrap_or(self.config.file_viewer.wrap);
+                            let rendered = matches!(markdown, Some(MarkdownPane { mode: MarkdownMode::Rendered, .. }));
+                            ui.horizontal(|ui| {
+                                if let Some(markdown) = markdown.as_ref() {
+                                    if let Some(mode) = show_markdown_toggle(ui, markdown.mode, &self.theme) {
+                                        markdown_toggle = Some((pane_id, path.clone(), mode));
+                                    }
+                                }
+                                if !rendered && show_wrap_toggle(ui, wrap, &self.theme) {
+                                    wrap_toggle = Some((pane_id, !wrap));
+                                }
+                                if !rendered && show_blame_toggle(ui, blame.as_ref(), &self.theme) {
+                                    blame_toggle = Some(pane_id);
+                                }
+                            });
+                            if matches!(blame, Some(BlameView::Stale)) {
+                                blame_restart = Some(pane_id);
+                            }
+
+                            if let Some(MarkdownPane { mode: MarkdownMode::Rendered, document }) = markdown {
+                                let document = document
+                                    .get_or_insert_with(|| crate::ui::MarkdownDocument::parse(file_content));
+                                let scroll = egui::ScrollArea::vertical();
+                                let scroll = if restore_scroll { scroll.scroll_offset(*scroll_offset) } else { scroll };
+                                *scroll_offset = scroll
+                                    .id_salt(format!("markdown_scroll_{}", pane_id.0))
+                                    .show(ui, |ui| {
+                                        egui::Frame::NONE
+                                            .inner_margin(egui::Margin::symmetric(12, 8))
+                                            .show(ui, |ui| {
+                                                if let Some(link) = document.show(ui, &self.theme) {
+                                                    markdown_link = Some((path.clone(), link));
+                                                }
+                                            });
+                                    })
+                                    .state
+                                    .offset;
+                                return;
+                            }
+
+                            // Unwrapped lines scroll sideways instead
+                            let scroll = if wrap { egui::ScrollArea::vertical() } else { egui::ScrollArea::both() };
+                            let scroll = if restore_scroll { scroll.scroll_offset(*scroll_offset) } else { scroll };
+                            let tab_width = self.config.file_viewer.tab_width;
+                            if expanded.as_ref().is_none_or(|(width, _)| *width != tab_width) {
+                                let text = match crate::file_loader::expand_tabs(file_content, tab_width) {
+                                    std::borrow::Cow::Owned(text) => Some(text),
+                                    std::borrow::Cow::Borrowed(_) => None,
+                                };
+                                *expanded = Some((tab_width, text));
+                            }
+                            let text = expanded.as_ref().and_then(|(_, text)| text.as_deref()).unwrap_or(file_content);
+                            let runs = match blame {
+                                Some(BlameView::Ready(runs)) => Some(runs),
+                                _ => None,
+                            };
+                            *scroll_offset = scroll
                                 .id_salt(format!("file_scroll_{}", pane_id.0))
                                 .show(ui, |ui| {
-                                    ui.add(egui::Label::new(
-                                        egui::RichText::new(file_content.as_str())
-                                            .font(theme::mono_font(12.0))
+                                    let label = egui::Label::new(
+                                        egui::RichText::new(text)
+                                            .font(theme::ui_font(12.0))
                                             .color(self.theme.text)
-                                    ).wrap());
-                                });
+                                    );
+                                    let label = if wrap { label.wrap() } else { label.extend() };
+                                    let show_text = |ui: &mut egui::Ui| {
+                                        let (pos, galley, response) = label.layout_in_ui(ui);
+
+                                        // Paragraphs are the file's lines, however they wrap
+                                        if let Some(line) = goto_line.take() {
+                                            let cursor = egui::epaint::text::cursor::PCursor {
+                                                paragraph: line.saturating_sub(1) as usize,
+                                                offset: 0,
+                                                prefer_next_row: false,
+                                            };
+                                            let rect = galley.pos_from_pcursor(cursor).translate(pos.to_vec2());
+                                            ui.scroll_to_rect(rect, Some(egui::Align::Center));
+                                        }
+                                        // Painted with the selection, so it can be copied like any label
+                                        egui::text_selection::LabelSelectionState::label_text_selection(
+                                            ui,
+                                            &response,
+                                            pos,
+                                            galley.clone(),
+                                            self.theme.text,
+                                            egui::Stroke::NONE,
+                                        );
+                                        (pos, galley)
+                                    };
+                                    let Some(runs) = runs else {
+                                        show_text(ui);
+                                        return;
+                                    };
+                                    // Blame labels take a column left of the text
+                                    ui.horizontal_top(|ui| {
+                                        let gutter_left = ui.cursor().left();
+                                        ui.add_space(theme::ui_scaled(BLAME_GUTTER_WIDTH));
+                                        let (pos, galley) = show_text(ui);
+                                        if let Some(hash) = show_blame_gutter(ui, runs, gutter_left, pos, &galley, &self.theme) {
+                                            ui.ctx().copy_text(hash);
+                                            copied_hash = true;
+                                        }
+                                    });
+                                })
+                                .state
+                                .offset;
+                        },
+                    );
+
+                    if follow_pane == Some(pane_id) {
+                        // Bottom corner, clear of the banners and the scroll bar
+                        let badge = ui.painter().layout_no_wrap("following".to_string(), theme::ui_font(10.0), self.theme.background);
+                        let badge_rect = egui::Rect::from_min_size(
+                            inner_rect.right_bottom() - egui::vec2(badge.size().x + 8.0 + 12.0, badge.size().y + 2.0 + 4.0),
+                            badge.size() + egui::vec2(8.0, 2.0),
+                        );
+                        ui.painter().rect_filled(badge_rect, 3.0, self.theme.primary);
+                        ui.painter().galley(badge_rect.min + egui::vec2(4.0, 1.0), badge, self.theme.background);
+                    }
+                }
+                TabContent::Diff(view) => {
+                    ui.painter().rect_filled(inner_rect, 0.0, self.theme.background);
+                    ui.allocate_new_ui(
+                        egui::UiBuilder::new().max_rect(inner_rect.shrink2(egui::vec2(6.0, 2.0))),
+                        |ui| {
+                            if let Some(command) = show_diff_view(ui, view, pane_id, &self.theme) {
+                                diff_command = Some((pane_id, command));
+                            }
+                        },
+                    );
+                }
+                TabContent::Stats(summary) => {
+                    ui.painter().rect_filled(inner_rect, 0.0, self.theme.background);
+                    ui.allocate_new_ui(
+                        egui::UiBuilder::new().max_rect(inner_rect.shrink2(egui::vec2(12.0, 8.0))),
+                        |ui| {
+                            if let Some(command) = crate::ui::show_stats(ui, summary.as_ref(), self.stats.is_some(), &self.theme) {
+                                stats_command = Some(command);
+                            }
+                        },
+                    );
+                }
+                TabContent::ColorTest(sections) => {
+                    ui.painter().rect_filled(inner_rect, 0.0, self.theme.background);
+                    ui.allocate_new_ui(
+                        egui::UiBuilder::new().max_rect(inner_rect.shrink2(egui::vec2(12.0, 8.0))),
+                        |ui| {
+                            let font_size = self.config.font.terminal_size;
+                            let command = crate::ui::show_color_test(ui, sections, &self.cached_terminal_theme, font_size, &self.theme);
+                            if command == Some(crate::ui::ColorTestCommand::WriteScript) {
+                                color_test_script = Some(pane_id);
+                            }
                         },
                     );
                 }
+                TabContent::Empty => {
+                    ui.painter().rect_filled(inner_rect, 0.0, self.theme.background);
+                    ui.painter().text(
+                        inner_rect.center(),
+                        egui::Align2::CENTER_CENTER,
+                        "No content — type to open a shell here or drop a file",
+                        theme::ui_font(12.0),
+                        self.theme.text_dim,
+                    );
+
+                    // A file dropped from the OS opens in this pane
+                    let dropped = ui.input(|i| {
+                        let over = i.pointer.hover_pos().is_some_and(|pos| inner_rect.contains(pos));
+                        let path = i.raw.dropped_files.iter().find_map(|file| file.path.clone());
+                        path.filter(|_| over)
+                    });
+                    if let Some(path) = dropped {
+                        empty_drop = Some((pane_id, path));
+                    }
+                }
+            }
+
+            // Tint wash over the content
+            if let Some(tint) = tint {
+                ui.painter().rect_filled(inner_rect, 0.0, tint.gamma_multiply(PANE_TINT_OPACITY));
+            }
+        }
+
+        // Dividers go after pane content: of overlapping widgets in a layer the
+        // last one gets the pointer, and the grab margin overlaps the panes
+        let pointer_down = ui.input(|i| i.pointer.any_down());
+        let mut divider_size_change = None;
+        for (idx, divider) in layout.dividers.iter().enumerate() {
+            let hit_rect = crate::layout::divider_hit_rect(divider.rect, divider.direction, crate::layout::DIVIDER_GRAB_MARGIN);
+            let divider_response = ui.interact(hit_rect, ui.id().with(("pane_divider", idx)), egui::Sense::click_and_drag());
+            let root = &self.workspaces[self.active_workspace].root;
+
+            if divider_response.drag_started() {
+                if let Some(original_size) = root.size_at_path(&divider.path) {
+                    self.dragging_divider = Some(DividerDrag {
+                        workspace: self.active_workspace,
+                        divider: idx,
+                        path: divider.path.clone(),
+                        original_size,
+                    });
+                }
+            }
+
+            // Right-click: keep one side at its current px size when the window resizes
+            let size = root.size_at_path(&divider.path).unwrap_or_default();
+            let available = crate::layout::available_length(divider.parent_rect, divider.direction, DIVIDER_WIDTH);
+            divider_response.context_menu(|ui| {
+                let (first, second, dimension) = match divider.direction {
+                    SplitDirection::Horizontal => ("Left", "Right", "Width"),
+                    SplitDirection::Vertical => ("Top", "Bottom", "Height"),
+                };
+                if size.is_fixed() {
+                    if ui.button("Unlock Pane Size").clicked() {
+                        divider_size_change = Some((divider.path.clone(), size.unlocked(available)));
+                        ui.close_menu();
+                    }
+                } else {
+                    for (second_side, side) in [(false, first), (true, second)] {
+                        if ui.button(format!("Lock {} Pane {}", side, dimension)).clicked() {
+                            divider_size_change = Some((divider.path.clone(), size.locked(second_side, available)));
+                            ui.close_menu();
+                        }
+                    }
+                }
+            });
+
+            // A cancelled drag is still "dragged" for egui until the button
+            // goes up; only our own drag state counts as active
+            let dragging = self.dragging_divider.as_ref().is_some_and(|drag| drag.divider == idx);
+            let hovering = divider_response.hovered() && !pointer_down;
+
+            let divider_color = if dragging || hovering {
+                self.theme.primary
+            } else {
+                self.theme.border
+            };
+            ui.painter().rect_filled(divider.rect, 0.0, divider_color);
+
+            if dragging || hovering {
+                let cursor = match divider.direction {
+                    SplitDirection::Horizontal => egui::CursorIcon::ResizeHorizontal,
+                    SplitDirection::Vertical => egui::CursorIcon::ResizeVertical,
+                };
+                ui.ctx().set_cursor_icon(cursor);
+                self.paint_divider_preview(ui, divider, size);
+            }
+        }
+        // Takes effect next frame, like a drag's first step
+        if let Some((path, size)) = divider_size_change {
+            self.workspaces[self.active_workspace].root.set_size_at_path(&path, size);
+        }
+
+        if let Some((pane_id, mode)) = file_reload {
+            self.load_file_view(self.active_workspace, pane_id, mode);
+        }
+        if let Some((pane_id, path, mode)) = markdown_toggle {
+            if let Some(TabContent::FileViewer { markdown: Some(markdown), .. }) =
+                self.current_workspace_mut().get_content_mut(pane_id)
+            {
+                markdown.mode = mode;
+            }
+            self.config.edit(std::time::Instant::now(), |config| config.file_viewer.set_markdown_mode(&path, mode));
+        }
+        if let Some((path, link)) = markdown_link {
+            self.open_markdown_link(&path, &link);
+        }
+        if let Some((pane_id, on)) = wrap_toggle {
+            if let Some(TabContent::FileViewer { wrap, .. }) = self.current_workspace_mut().get_content_mut(pane_id) {
+                *wrap = Some(on);
+            }
+        }
+        if let Some((pane_id, command)) = diff_command {
+            self.run_diff_command(pane_id, command);
+        }
+        if let Some(rect) = ime_rect {
+            // A focused text field placed its own already
+            ui.ctx().output_mut(|output| {
+                output.ime.get_or_insert(egui::output::IMEOutput {
+                    rect,
+                    cursor_rect: egui::Rect::from_min_size(rect.min, egui::vec2(1.0, rect.height())),
+                });
+            });
+        }
+        if let Some(command) = stats_command {
+            self.handle_stats_command(command);
+        }
+        if let Some(pane_id) = color_test_script {
+            self.write_color_test_script(pane_id);
+        }
+        if let Some(pane_id) = blame_toggle {
+            self.toggle_blame(self.active_workspace, pane_id);
+        }
+        if let Some(pane_id) = blame_restart {
+            self.start_blame(self.active_workspace, pane_id);
+        }
+        if copied_hash {
+            self.toast = Some(Toast::new("Copied commit hash"));
+        }
+        match shell_failure_action {
+            Some((_, ShellFailureAction::OpenPreferences)) => {
+                self.preferences_window.open_at(Config::clone(&self.config), crate::ui::PreferencesTab::Terminal);
+            }
+            Some((pane_id, ShellFailureAction::Retry)) => {
+                if let Some(TabContent::Terminal(terminal)) = self.current_workspace_mut().get_content_mut(pane_id) {
+                    terminal.quick_exits = 0;
+                }
+                self.restart_shell(self.active_workspace, pane_id);
+            }
+            None => {}
+        }
+        if let Some((pane_id, path)) = empty_drop {
+            if path.is_file() {
+                self.show_file_in_pane(self.active_workspace, pane_id, path);
             }
         }
 
+        // Pane picker for "Send Selection to..."
+        if picking {
+            self.show_pane_picker(ui, &layout, focused_pane);
+        }
+
         // Render drag feedback overlay
         if let Some(ref drag_state) = self.dragging_pane {
             if drag_state.drag_active {
-                let drop_zones = self.compute_drop_zones(&layout, drag_state.source_pane_id);
+                let drop_zones = crate::layout::compute_drop_zones(&layout, drag_state.source_pane_id);
 
-                // Find and highlight active zone
-                if let Some(zone_info) = drop_zones.iter().find(|z| z.rect.contains(drag_state.current_pos)) {
+                // Over the tab bar the pane becomes a tab of its own
+                if let Some(index) = self.pane_drop_tab_index(drag_state.current_pos) {
+                    let painter = ui.ctx().layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("pane_tab_drop")));
+                    self.paint_tab_drop_indicator(&painter, index, &self.tab_rects);
+                } else if let Some(zone_info) = drop_zones.iter().find(|z| z.rect.contains(drag_state.current_pos)) {
                     ui.painter().rect_filled(
                         zone_info.highlight_rect,
                         0.0,
@@ -1520,62 +7918,162 @@ impl VibeTermApp {
     }
 }
 
-impl eframe::App for VibeTermApp {
-    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+impl VibeTermApp {
+    /// One frame: input, background results and drawing
+    ///
+    /// Kept apart from `eframe::App::update` so tests can run it without a window.
+    fn run_frame(&mut self, ctx: &Context) {
+        theme::set_ui_scale(self.config.font.ui_scale);
+
         // Enable IME for Korean/Japanese/Chinese input
         ctx.send_viewport_cmd(egui::ViewportCommand::IMEAllowed(true));
 
+        // Fonts are only available once the first frame has started
+        if self.nerd_glyphs.is_none() {
+            let supported = crate::ui::nerd_glyphs_supported(ctx);
+            log::info!("Nerd Font file icons {}", if supported { "available" } else { "unavailable, using ASCII" });
+            self.nerd_glyphs = Some(supported);
+        }
+        self.current_workspace_mut().note_focused_terminal();
+        self.process_cjk_font(ctx);
+
         // Command palette toggle (Cmd+P or Ctrl+P)
         if ctx.input(|i| {
             i.key_pressed(Key::P) &&
-            (i.modifiers.command_only() || (i.modifiers.ctrl && !i.modifiers.alt && !i.modifiers.shift))
+            (crate::shortcuts::primary_only(i.modifiers) || (i.modifiers.ctrl && !i.modifiers.alt && !i.modifiers.shift))
         }) {
             self.command_palette.toggle();
         }
 
-        // Handle keyboard shortcuts
-        self.handle_shortcuts(ctx);
-
-        // Handle IME events (Korean/Japanese/Chinese input)
-        self.handle_ime_events(ctx);
+        // Keyboard shortcuts, IME (Korean/Japanese/Chinese input) and Shift+Enter
+        self.process_input(ctx);
 
         // Handle menu events
-        self.handle_menu_events();
+        self.handle_menu_events(ctx);
+        self.handle_close_request(ctx);
+
+        // Tabs and focus requested by later launches
+        self.process_ipc_commands(ctx);
+        self.process_open_requests();
+        self.wake_idle_dormant_tabs(ctx);
+
+        // Do Not Disturb applies to everything below
+        self.update_dnd(ctx);
+
+        // Local usage stats, when turned on
+        self.update_stats(ctx);
 
         // Process PTY events
-        self.process_pty_events();
+        self.process_pty_events(ctx);
+        self.process_progress(ctx);
+
+        // Feed session logs with new terminal output
+        self.process_session_logs(ctx);
+
+        // Keep scrollback marks on their lines
+        self.process_scrollback_marks();
+
+        // Collect compiler diagnostics from new output
+        self.process_diagnostics(ctx);
+
+        // Attach trackers for newly discovered shells
+        self.process_shell_pid_results();
 
         // Poll PTY trackers for CWD changes
         self.poll_pty_trackers();
 
+        // Sidebar root switches held back until directories settle
+        if let Some(due) = self.apply_sidebar_root_switch() {
+            let wait = due.saturating_duration_since(std::time::Instant::now());
+            // Zero means a fullscreen app holds it back; its exit repaints
+            if !wait.is_zero() {
+                ctx.request_repaint_after(wait);
+            }
+        }
+
         // Process async directory loading results
         self.process_dir_load_results();
+        self.save_tree_states(false);
+        self.save_session(false);
+        self.process_follow_selection();
+        self.process_file_load_results();
+        self.process_diff_results();
+        self.process_stats_results();
+        self.process_blame_results();
+        self.process_paste_results();
+        self.process_thumbnail_results();
 
         // Process context manager events
         self.process_context_events();
+        self.update_sidebar_git_status();
+        self.process_git_fetches();
+        self.process_project_search();
+
+        // Process update check results
+        self.process_update_check();
+
+        self.check_config_file(ctx);
+        self.save_config_if_due(ctx);
 
         // Show preferences window (spawns deferred viewport)
         let pref_response = self.preferences_window.show(ctx, &self.config, &self.theme);
 
+        if let Some(preview) = pref_response.theme_preview {
+            self.preview_theme(ctx, preview);
+        }
         if let Some(new_config) = pref_response.apply_config {
-            self.config = new_config.clone();
-            self.theme = RuntimeTheme::from(&new_config.theme);
-            self.cached_terminal_theme = theme::get_terminal_theme(&new_config);
-            crate::theme::apply_theme(ctx, &self.theme);
+            self.apply_config(ctx, new_config);
         }
 
         if pref_response.save_config {
-            if let Err(e) = self.config.save() {
-                log::error!("Failed to save config: {}", e);
-            }
+            self.save_config_now();
         }
 
         // Show command palette and execute commands
-        if let Some(command_id) = self.command_palette.show(ctx, &self.theme) {
-            match command_id {
+        self.command_palette.set_hidden(self.hidden_palette_commands());
+        self.command_palette.set_disabled(self.disabled_palette_commands());
+        self.command_palette.set_confirm_dangerous(self.config.ui.confirm_dangerous_commands);
+        let workspaces = &self.workspaces;
+        let palette_choices = |command: &'static str, _args: &[String]| match command {
+            "go_to_tab" => workspaces
+                .iter()
+                .enumerate()
+                .map(|(idx, ws)| tab_choice(idx, &ws.name))
+                .collect(),
+            "diff_panes" => DiffScope::ALL.iter().map(|scope| scope.label().to_string()).collect(),
+            _ => Vec::new(),
+        };
+        let mut palette_action = pref_response.palette_command
+            .and_then(|id| self.command_palette.start(id, &palette_choices));
+        if let Some(action) = self.command_palette.show(ctx, &self.theme, &palette_choices) {
+            palette_action = Some(action);
+        }
+        if let Some(action) = palette_action {
+            match action.command {
                 "new_tab" => {
                     self.create_new_tab();
                 }
+                "new_tab_at_end" => {
+                    self.create_new_tab_in(launch_dir(), NewTabPosition::End);
+                }
+                "new_tab_after_current" => {
+                    self.create_new_tab_in(launch_dir(), NewTabPosition::AfterCurrent);
+                }
+                "search_in_project" => {
+                    self.open_project_search();
+                }
+                "pause_file_watcher" | "resume_file_watcher" => {
+                    let enabled = action.command == "resume_file_watcher";
+                    self.context_manager.set_watcher_enabled(enabled);
+                    let message = if enabled { "File watcher resumed" } else { "File watcher paused" };
+                    self.toast = Some(Toast::new(message));
+                }
+                "pause_git_status" | "resume_git_status" => {
+                    let enabled = action.command == "resume_git_status";
+                    self.context_manager.set_git_enabled(enabled);
+                    let message = if enabled { "Git status resumed" } else { "Git status paused" };
+                    self.toast = Some(Toast::new(message));
+                }
                 "close_tab" => {
                     self.close_current_pane();
                 }
@@ -1585,15 +8083,146 @@ impl eframe::App for VibeTermApp {
                 "split_vertical" => {
                     self.split_pane_vertical();
                 }
+                "split_empty" => {
+                    self.split_pane_empty();
+                }
                 "close_pane" => {
                     self.close_current_pane();
                 }
+                "rename_tab" => {
+                    if let Some(name) = action.args.first() {
+                        self.current_workspace_mut().name = name.trim().to_string();
+                    }
+                }
+                "go_to_tab" => {
+                    let choice = action.args.first();
+                    if let Some(idx) = (0..self.workspaces.len())
+                        .find(|idx| choice == Some(&tab_choice(*idx, &self.workspaces[*idx].name)))
+                    {
+                        self.active_workspace = idx;
+                    }
+                }
+                "export_settings_bundle" => {
+                    if let Some(dest) = action.args.first() {
+                        self.export_settings_bundle(dest);
+                    }
+                }
+                "import_settings_bundle" => {
+                    if let Some(bundle) = action.args.first() {
+                        self.open_settings_bundle(bundle);
+                    }
+                }
                 "toggle_sidebar" => {
-                    self.sidebar_visible = !self.sidebar_visible;
+                    self.toggle_sidebar();
                 }
+                "toggle_presentation_mode" => self.toggle_presentation_mode(ctx),
                 "settings" => {
                     self.preferences_window.open(self.config.clone());
                 }
+                "send_selection_next" => {
+                    self.send_selection_to_neighbor(true, false);
+                }
+                "send_selection_prev" => {
+                    self.send_selection_to_neighbor(false, false);
+                }
+                "send_selection_to" => {
+                    self.open_send_selection_picker();
+                }
+                "diff_panes" => {
+                    let scope = action.args.first().and_then(|label| DiffScope::from_label(label));
+                    self.start_pane_diff(scope.unwrap_or(DiffScope::Screen));
+                }
+                "pin_output" => {
+                    self.pin_selected_output();
+                }
+                "run_in_all_panes" => {
+                    self.run_all_dialog.open();
+                }
+                "font_diagnostics" => {
+                    self.run_font_diagnostics(ctx);
+                }
+                "startup_report" => {
+                    self.startup_report_dialog.open();
+                }
+                "open_config_file" => {
+                    self.open_config_file();
+                }
+                "toggle_follow_sidebar" => {
+                    let pane_id = self.current_workspace().focused_pane;
+                    self.toggle_follow_sidebar(pane_id);
+                }
+                "reveal_config_folder" => {
+                    self.reveal_config_folder();
+                }
+                "edit_config" => {
+                    self.edit_config();
+                }
+                "convert_to_terminal" => {
+                    let pane_id = self.current_workspace().focused_pane;
+                    self.convert_to_terminal(pane_id);
+                }
+                "convert_to_file_viewer" => {
+                    let pane_id = self.current_workspace().focused_pane;
+                    self.request_file_viewer_conversion(pane_id);
+                }
+                "move_pane_left" => self.current_workspace_mut().move_focused_pane(PaneDirection::Left),
+                "move_pane_right" => self.current_workspace_mut().move_focused_pane(PaneDirection::Right),
+                "move_pane_up" => self.current_workspace_mut().move_focused_pane(PaneDirection::Up),
+                "move_pane_down" => self.current_workspace_mut().move_focused_pane(PaneDirection::Down),
+                "move_pane_to_new_tab" => {
+                    let pane_id = self.current_workspace().focused_pane;
+                    self.move_pane_to_new_tab(pane_id, self.active_workspace + 1);
+                }
+                "mirror_pane" => {
+                    let pane_id = self.current_workspace().focused_pane;
+                    self.toggle_mirror(pane_id);
+                }
+                "jump_to_next_diagnostic" => self.jump_to_next_diagnostic(),
+                "copy_last_output" => self.copy_last_output(),
+                "toggle_session_logging" => {
+                    let ws = self.current_workspace_mut();
+                    let pane_id = ws.focused_pane;
+                    if let Some(TabContent::Terminal(terminal)) = ws.get_content_mut(pane_id) {
+                        terminal.logging_override = Some(terminal.session_log.is_none());
+                    }
+                }
+                "open_project_layout" => {
+                    let root = self.current_workspace().sidebar_root.clone();
+                    self.open_project_layout(root);
+                }
+                "insert_snippet" => {
+                    self.snippet_palette.open(crate::snippets::load_snippets());
+                }
+                "clipboard_history" => {
+                    self.clipboard_history_palette.open(self.clipboard_history.entries().to_vec());
+                }
+                "history_peek" => self.open_history_peek(),
+                "connect_to_host" => self.open_ssh_palette(),
+                "usage_stats" => self.open_usage_stats(),
+                "color_test" => self.open_color_test(),
+                "insert_context" => self.insert_context(false),
+                "insert_context_heredoc" => self.insert_context(true),
+                "add_mark" => self.open_mark_prompt(),
+                "jump_to_mark" => self.open_mark_list(),
+                "previous_mark" => self.step_mark(false),
+                "next_mark" => self.step_mark(true),
+                "toggle_blame" => {
+                    let pane_id = self.current_workspace().focused_pane;
+                    if matches!(self.current_workspace().get_content(pane_id), Some(TabContent::FileViewer { .. })) {
+                        self.toggle_blame(self.active_workspace, pane_id);
+                    } else {
+                        self.toast = Some(Toast::new("Blame works in file viewer panes"));
+                    }
+                }
+                "toggle_dnd" => self.notifier.toggle(),
+                "ui_scale_up" => self.step_ui_scale(1),
+                "ui_scale_down" => self.step_ui_scale(-1),
+                "edit_snippets" => {
+                    match crate::snippets::ensure_snippets_file() {
+                        Ok(path) => self.create_file_tab(path),
+                        Err(e) => log::error!("Failed to open snippets: {}", e),
+                    }
+                }
                 "next_tab" => {
                     if self.active_workspace < self.workspaces.len() - 1 {
                         self.active_workspace += 1;
@@ -1608,6 +8237,80 @@ impl eframe::App for VibeTermApp {
             }
         }
 
+        // Show snippet palette and type the chosen command (without executing it)
+        if let Some(command) = self.snippet_palette.show(ctx, &self.theme) {
+            self.send_text_to_terminal(&command);
+        }
+
+        // Paste or re-copy an earlier copy
+        match self.clipboard_history_palette.show(ctx, &self.theme) {
+            Some(crate::ui::ClipboardHistoryAction::Paste(text)) => self.send_text_to_terminal(&text),
+            Some(crate::ui::ClipboardHistoryAction::Copy(text)) => {
+                ctx.copy_text(text);
+                self.toast = Some(Toast::new("Copied to clipboard"));
+            }
+            None => {}
+        }
+
+        // Type an earlier command into the focused terminal, or run it
+        match self.history_peek.show(ctx, &self.theme) {
+            Some(crate::ui::HistoryPeekAction::Insert(command)) => self.send_text_to_terminal(&command),
+            Some(crate::ui::HistoryPeekAction::Run(command)) => {
+                self.send_text_to_terminal(&format!("{}\n", command));
+                self.record_command(command);
+            }
+            None => {}
+        }
+
+        // Open an SSH connection and remember the host for next time
+        if let Some(action) = self.ssh_palette.show(ctx, &self.theme) {
+            let (host, split) = match action {
+                crate::ui::SshAction::NewTab(host) => (host, false),
+                crate::ui::SshAction::Split(host) => (host, true),
+            };
+            self.connect_to_host(&host, split);
+            let mut recent = crate::ssh_config::RecentHosts::load();
+            recent.touch(&host.name);
+            if let Err(e) = recent.save() {
+                log::warn!("{}", e);
+            }
+        }
+
+        if let Some(idx) = self.mark_list.show(ctx, &self.theme) {
+            self.jump_to_mark(idx);
+        }
+
+        // Run in All Panes dialog and the staggered sends it starts
+        if let Some(request) = self.import_dialog.show(ctx, &self.theme) {
+            self.import_settings_bundle(ctx, request);
+        }
+
+        match self.font_diagnostics_dialog.show(ctx, &self.theme) {
+            Some(crate::ui::FontDiagnosticsAction::Rerun) => self.run_font_diagnostics(ctx),
+            Some(crate::ui::FontDiagnosticsAction::Apply(remedy)) => self.apply_font_remedy(ctx, remedy),
+            None => {}
+        }
+        self.startup_report_dialog.show(ctx, &self.theme, &self.startup);
+
+        if self.run_all_dialog.is_visible() {
+            let targets = self.run_targets();
+            if let Some(request) = self.run_all_dialog.show(ctx, &self.theme, &targets, self.active_workspace) {
+                self.start_run_all(request);
+            }
+        }
+        self.process_run_all_job(ctx);
+
+        // Keystrokes headed for the focused terminal take the low-latency path
+        let typed_into_terminal = self.terminal_has_keyboard_focus() && ctx.input(|i| {
+            i.events.iter().any(|e| matches!(e,
+                Event::Text(_) | Event::Paste(_) | Event::Ime(ImeEvent::Commit(_)) |
+                Event::Key { pressed: true, .. }
+            ))
+        });
+        if typed_into_terminal {
+            self.note_terminal_input();
+        }
+
         // Dynamic repaint rate: immediate when user is typing, idle rate for cursor blink
         // Track if there's recent user input
         let has_recent_input = ctx.input(|i| !i.events.is_empty() || i.pointer.any_down());
@@ -1618,15 +8321,69 @@ impl eframe::App for VibeTermApp {
             ctx.request_repaint_after(std::time::Duration::from_millis(50)); // Idle rate for cursor blink
         }
 
+        // Follow-up repaint to pick up the echo of the last keystroke
+        if let Some(at) = self.echo_repaint_at {
+            let now = std::time::Instant::now();
+            if now >= at {
+                self.echo_repaint_at = None;
+            } else {
+                ctx.request_repaint_after(at - now);
+            }
+        }
+
+        // Shrink the chrome before laying out panels in a small window
+        self.responsive = self.responsive.update(ctx.screen_rect().size(), &self.config.ui);
+        if !self.sidebar_shown() {
+            self.sidebar_focused = false;
+        }
+
+        // In-window menu bar where there is no native one
+        let presenting = self.presentation.is_active();
+        if !crate::shortcuts::IS_MAC {
+            let menu_action = TopBottomPanel::top("menu_strip")
+                .show_animated(ctx, !presenting, menu::show_menu_strip)
+                .and_then(|response| response.inner);
+            if let Some(action) = menu_action {
+                self.handle_menu_action(action, ctx);
+            }
+        }
+
         // Tab bar (top)
+        self.tab_bar_rect = egui::Rect::NOTHING;
         TopBottomPanel::top("tab_bar")
-            .exact_height(theme::TAB_BAR_HEIGHT)
+            .exact_height(theme::ui_scaled(self.config.ui.tab_bar_height))
             .frame(Frame::NONE)
-            .show(ctx, |ui| {
+            .show_animated(ctx, !presenting, |ui| {
                 let tabs = self.get_tabs();
-                let tab_bar = TabBar::new(&tabs, self.active_workspace, &self.theme);
+                let activity_now = self.config.ui.show_activity_strip.then(std::time::Instant::now);
+                let (workspaces, theme) = (&self.workspaces, &self.theme);
+                let activity_details = |idx: usize, ui: &mut egui::Ui| {
+                    if let (Some(ws), Some(now)) = (workspaces.get(idx), activity_now) {
+                        ws.show_activity(ui, now, theme);
+                    }
+                };
+                let mut tab_bar = TabBar::new(&tabs, self.active_workspace, &self.theme)
+                    .compact(self.responsive.compact_tabs)
+                    .activity(activity_now)
+                    .muted(self.notifier.is_active())
+                    .height(theme::ui_scaled(self.config.ui.tab_bar_height));
+                if activity_now.is_some() {
+                    tab_bar = tab_bar.hover_details(&activity_details);
+                }
                 let response = tab_bar.show(ui);
 
+                // Keep the strips fading while any is still visible
+                if let Some(now) = activity_now {
+                    let fading = tabs.iter().filter_map(|tab| tab.last_output)
+                        .any(|at| now.saturating_duration_since(at) < crate::ui::ACTIVITY_FADED);
+                    if fading {
+                        ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+                    }
+                }
+                // Kept for panes dragged onto the tab bar
+                self.tab_bar_rect = ui.max_rect();
+                self.tab_rects = response.tab_rects.clone();
+
                 // Handle tab drag-and-drop
                 let pointer_pos = ui.input(|i| i.pointer.hover_pos());
                 let clicked_primary = ui.input(|i| i.pointer.button_clicked(egui::PointerButton::Primary));
@@ -1680,28 +8437,13 @@ impl eframe::App for VibeTermApp {
                     if drag_active {
                         if let Some(current_pos) = pointer_pos {
                             if let Some(drop_index) = self.find_tab_drop_zone(current_pos, &response.tab_rects) {
-                                // Reorder workspaces
-                                if source != drop_index {
-                                    let workspace = self.workspaces.remove(source);
-
-                                    // Adjust drop index if removing from before it
-                                    let adjusted_drop = if source < drop_index {
-                                        drop_index - 1
-                                    } else {
-                                        drop_index
-                                    };
-
-                                    self.workspaces.insert(adjusted_drop, workspace);
-
-                                    // Update active workspace index
-                                    if self.active_workspace == source {
-                                        self.active_workspace = adjusted_drop;
-                                    } else if source < self.active_workspace && self.active_workspace <= adjusted_drop {
-                                        self.active_workspace -= 1;
-                                    } else if source > self.active_workspace && self.active_workspace >= adjusted_drop {
-                                        self.active_workspace += 1;
-                                    }
-                                }
+                                // Adjust drop index if removing from before it
+                                let adjusted_drop = if source < drop_index {
+                                    drop_index - 1
+                                } else {
+                                    drop_index
+                                };
+                                self.move_tab(source, adjusted_drop);
                             }
                         }
                     }
@@ -1739,36 +8481,13 @@ impl eframe::App for VibeTermApp {
                             ghost_rect.center(),
                             egui::Align2::CENTER_CENTER,
                             ghost_text,
-                            egui::FontId::proportional(12.0),
+                            egui::FontId::proportional(theme::ui_scaled(12.0)),
                             self.theme.text,
                         );
 
                         // Drop zone indicator
                         if let Some(drop_index) = self.find_tab_drop_zone(drag_state.current_pos, &response.tab_rects) {
-                            // Find the position to draw indicator
-                            if drop_index > 0 && drop_index <= response.tab_rects.len() {
-                                if let Some((_, rect)) = response.tab_rects.get(drop_index.saturating_sub(1)) {
-                                    let x = rect.right();
-                                    let top = rect.top();
-                                    let bottom = rect.bottom();
-
-                                    ui.painter().line_segment(
-                                        [egui::pos2(x, top), egui::pos2(x, bottom)],
-                                        egui::Stroke::new(3.0, self.theme.primary),
-                                    );
-                                }
-                            } else if drop_index == 0 && !response.tab_rects.is_empty() {
-                                if let Some((_, rect)) = response.tab_rects.first() {
-                                    let x = rect.left();
-                                    let top = rect.top();
-                                    let bottom = rect.bottom();
-
-                                    ui.painter().line_segment(
-                                        [egui::pos2(x, top), egui::pos2(x, bottom)],
-                                        egui::Stroke::new(3.0, self.theme.primary),
-                                    );
-                                }
-                            }
+                            self.paint_tab_drop_indicator(ui.painter(), drop_index, &response.tab_rects);
                         }
                     }
                 }
@@ -1777,301 +8496,369 @@ impl eframe::App for VibeTermApp {
                     // Only switch tabs if not dragging
                     if self.dragging_tab.is_none() {
                         self.active_workspace = idx;
-                        // Reset focused pane to first pane when switching tabs
-                        let pane_ids = self.workspaces[idx].pane_ids();
-                        if let Some(first_id) = pane_ids.first() {
-                            self.workspaces[idx].focused_pane = *first_id;
-                        }
                     }
                 }
                 if let Some(idx) = response.closed_tab {
                     self.close_tab(idx);
                 }
+                if response.close_refused.is_some() {
+                    self.toast = Some(Toast::new("Pinned tabs close from their context menu"));
+                }
+                if let Some(idx) = response.toggled_pin {
+                    self.toggle_tab_pinned(idx);
+                }
                 if response.new_tab_requested {
                     self.create_new_tab();
                 }
             });
 
+        // Breadcrumb bar (focused terminal's directory)
+        TopBottomPanel::top("breadcrumb_bar")
+            .exact_height(theme::ui_scaled(theme::BREADCRUMB_BAR_HEIGHT))
+            .frame(Frame::NONE)
+            .show_animated(ctx, self.config.ui.show_breadcrumbs && !presenting, |ui| {
+                let ws = self.current_workspace();
+                let dir = match ws.get_content(ws.focused_pane) {
+                    Some(TabContent::Terminal(terminal)) => Some(terminal.current_dir.clone()),
+                    _ => None,
+                };
+                let response = BreadcrumbBar::new(dir.as_deref(), &self.theme)
+                    .cd_requires_modifier(self.config.terminal.breadcrumb_cd_requires_modifier)
+                    .ambiguous_width(self.config.terminal.ambiguous_width)
+                    .show(ui);
+
+                if let Some(path) = response.cd {
+                    self.cd_focused_terminal(&path);
+                }
+                if let Some(path) = response.set_sidebar_root {
+                    self.set_sidebar_root(path);
+                }
+                if let Some(path) = response.copy_path {
+                    ui.ctx().copy_text(path.display().to_string());
+                    self.toast = Some(Toast::new("Path copied"));
+                }
+            });
+
         // Status bar (bottom)
         TopBottomPanel::bottom("status_bar")
-            .exact_height(theme::STATUS_BAR_HEIGHT)
+            .exact_height(theme::ui_scaled(self.config.ui.status_bar_height))
             .frame(Frame::NONE)
-            .show(ctx, |ui| {
+            .show_animated(ctx, !presenting, |ui| {
                 let pane_count = self.current_workspace().pane_count();
                 let pane_ids = self.current_workspace().pane_ids();
                 let focused_pane = self.current_workspace().focused_pane;
                 let focused_idx = pane_ids.iter().position(|id| *id == focused_pane).unwrap_or(0);
-                StatusBar::new(pane_count, focused_idx, &self.theme).show(ui);
+                let context = ContextStatus {
+                    watcher: self.context_manager.watcher_state(),
+                    git_enabled: self.context_manager.is_git_enabled(),
+                    git_paused: self.context_manager.is_git_paused(),
+                    repo: self.context_manager.repo_status(),
+                    last_refresh: self.context_manager.last_refresh_info(),
+                    pinned_count: self.context_manager.pinned_count(),
+                    fetch: self.context_manager.fetch_status(),
+                };
+                let response = StatusBar::new(
+                    pane_count,
+                    focused_idx,
+                    &self.theme,
+                    // Held back until Do Not Disturb ends
+                    self.available_update.as_deref().filter(|_| !self.notifier.is_active()),
+                    context,
+                )
+                    .compact(self.responsive.compact_status)
+                    .segments(&self.config.ui.status_segments)
+                    .height(theme::ui_scaled(self.config.ui.status_bar_height))
+                    .dnd(self.notifier.is_active())
+                    .show(ui);
+
+                if response.dnd_clicked {
+                    self.notifier.toggle();
+                }
+
+                if response.update_clicked {
+                    ui.ctx().open_url(egui::OpenUrl::new_tab(crate::update_check::RELEASES_PAGE_URL));
+                }
+                if response.refresh_git {
+                    self.context_manager.refresh_git_status();
+                }
+                if response.fetch_now {
+                    if let Some(root) = self.context_manager.request_fetch() {
+                        self.spawn_git_fetch(root);
+                    }
+                }
+                if response.restart_watcher {
+                    self.context_manager.restart_watcher();
+                }
             });
 
         // Sidebar (left)
-        if self.sidebar_visible {
-            SidePanel::left("sidebar")
-                .exact_width(self.config.ui.sidebar_width)
-                .frame(Frame::NONE)
-                .resizable(true)
-                .show(ctx, |ui| {
-                    let ws = &self.workspaces[self.active_workspace];
-
-                    // Collect pane info from layout tree
-                    let panes_info: Vec<(PaneId, PathBuf)> = {
-                        let mut info = Vec::new();
-                        collect_pane_info(&ws.root, &mut info);
-                        info
-                    };
+        if self.config.ui.sidebar_mode != SidebarMode::Pinned {
+            self.show_sidebar_overlay(ctx);
+        } else if self.sidebar_shown() {
+            let width = self.config.ui.sidebar_width;
+            let panel = SidePanel::left("sidebar").frame(Frame::NONE).resizable(true);
+            // A width set in preferences replaces the dragged one
+            let panel = if std::mem::take(&mut self.sidebar_width_changed) {
+                panel.exact_width(width)
+            } else {
+                panel.default_width(width).width_range(SIDEBAR_WIDTH_RANGE)
+            };
+            let sidebar = panel.show(ctx, |ui| self.sidebar_ui(ui));
+
+            // Keep a dragged width once the drag ends; a narrow window
+            // squeezing the panel leaves the saved width alone
+            let resizing = ctx.is_being_dragged(egui::Id::new("sidebar").with("__resize"));
+            let dragged = sidebar.response.rect.width().round();
+            if std::mem::replace(&mut self.sidebar_resizing, resizing) && !resizing && (dragged - width).abs() >= 1.0 {
+                self.config.edit(std::time::Instant::now(), |config| config.ui.sidebar_width = dragged);
+            }
+        }
 
-                    let root_name = ws.sidebar_root
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("/")
-                        .to_string();
-
-                    let loading = self.loading_dirs.get(&self.active_workspace).copied().unwrap_or(false);
-
-                    let repo_status = self.context_manager.repo_status();
-                    let show_git_status = self.config.context.enable_git_status &&
-                                          self.context_manager.is_git_available();
-
-                    let sidebar = Sidebar::new(
-                        &ws.sidebar_entries,
-                        ws.selected_sidebar_entry,
-                        &root_name,
-                        &self.theme,
-                        &panes_info,
-                        Some(ws.focused_pane),
-                        loading,
-                        repo_status,
-                        show_git_status,
-                    );
-                    let response = sidebar.show(ui);
+        // Main content area (center)
+        let panes_response = CentralPanel::default()
+            .frame(Frame::NONE.fill(self.theme.background))
+            .show(ctx, |ui| {
+                self.render_panes(ui);
 
-                    if let Some(idx) = response.selected {
-                        self.workspaces[self.active_workspace].selected_sidebar_entry = Some(idx);
-                    }
-                    if let Some(idx) = response.toggled_dir {
-                        self.toggle_directory(idx);
-                    }
-                    // Double-click file opens in new tab
-                    if let Some(idx) = response.opened_file {
-                        let ws = &self.workspaces[self.active_workspace];
-                        if let Some(entry) = ws.sidebar_entries.get(idx) {
-                            if !entry.is_dir {
-                                self.create_file_tab(entry.path.clone());
-                            }
+                if self.config.advanced.show_latency_overlay {
+                    if self.latency_probe.is_pending() {
+                        if let Some(fingerprint) = self.focused_terminal_fingerprint() {
+                            self.latency_probe.observe(std::time::Instant::now(), fingerprint);
                         }
                     }
-                    // Handle pin toggle
-                    if let Some(idx) = response.toggle_pin {
-                        let ws = &self.workspaces[self.active_workspace];
-                        if let Some(entry) = ws.sidebar_entries.get(idx) {
-                            self.context_manager.toggle_pin(entry.path.clone());
-                        }
-                    }
-                    // Handle collapse/expand all
-                    if response.collapse_all {
-                        self.collapse_all_directories();
-                    }
-                    if response.expand_all {
-                        self.expand_all_directories();
-                    }
-                    // Handle pane click - focus that pane and maybe reload sidebar
-                    if let Some(clicked_pane) = response.pane_clicked {
-                        let ws = &mut self.workspaces[self.active_workspace];
-                        ws.focused_pane = clicked_pane;
-
-                        // Determine new sidebar root
-                        if let Some(content) = ws.root.get_content(clicked_pane) {
-                            if let TabContent::Terminal(terminal) = content {
-                                let new_root = terminal.project_root.as_ref().unwrap_or(&terminal.current_dir).clone();
+                    self.show_latency_overlay(ui);
+                }
+            });
 
-                                // Only reload if root changed
-                                if new_root != ws.sidebar_root {
-                                    ws.sidebar_root = new_root.clone();
+        // Pinned output notes float above the panes
+        self.show_output_notes(ctx, panes_response.response.rect);
+
+        self.show_pane_menu(ctx);
+        self.show_file_viewer_prompt(ctx);
+        self.show_mark_prompt(ctx);
+        self.show_delete_confirm(ctx);
+        self.show_project_consent(ctx);
+        self.show_dir_hook_consent(ctx);
+        self.show_quit_prompt(ctx);
+        self.show_toast(ctx);
+        self.show_presentation_hint(ctx);
+        self.show_mirror(ctx);
+
+        // After everything that may have copied text this frame
+        self.record_copies(ctx);
+
+        if !self.startup.is_complete() {
+            self.startup.lap("first frame");
+            log::info!("{}", self.startup.summary());
+        }
+    }
+}
 
-                                    // Update context manager with new directory
-                                    let _ = self.context_manager.set_active_directory(&new_root);
+impl eframe::App for VibeTermApp {
+    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+        self.run_frame(ctx);
+    }
 
-                                    self.load_directory_async(self.active_workspace, new_root);
-                                }
-                            }
-                        }
-                    }
-                });
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(stats) = &mut self.stats {
+            stats.flush(std::time::Instant::now());
         }
+        self.save_tree_states(true);
+        self.save_session(true);
+        self.save_config_now();
+        self.toast = None;
+        self.shut_down();
+    }
+}
 
-        // Main content area (center)
-        CentralPanel::default()
-            .frame(Frame::NONE.fill(self.theme.background))
-            .show(ctx, |ui| {
-                self.render_panes(ui);
-            });
+/// Entry for a tab in the "Go to Tab" list
+/// Key a bracket shortcut arrived as, if pressed: with Shift held the
+/// keyboard layout turns `[` into `{`
+fn bracket_pressed(i: &egui::InputState, bracket: Key, shifted: Key) -> Option<Key> {
+    [bracket, shifted].into_iter().find(|&key| i.key_pressed(key))
+}
+
+/// UI scale steps for a Cmd+Alt+Plus/Minus press
+fn ui_scale_step(event: &Event) -> Option<i32> {
+    let Event::Key { key, pressed: true, modifiers, .. } = event else {
+        return None;
+    };
+    if !modifiers.command || !modifiers.alt {
+        return None;
+    }
+    match key {
+        Key::Plus | Key::Equals => Some(1),
+        Key::Minus => Some(-1),
+        _ => None,
+    }
+}
+
+fn tab_choice(idx: usize, name: &str) -> String {
+    format!("{}  {}", idx + 1, name)
+}
+
+/// Stand-in for a layout taken out of a workspace to be rebuilt
+///
+/// Every rebuild puts a tree back, the old one if it fails, so this never
+/// stays in a workspace.
+fn placeholder_root() -> LayoutNode<TabContent> {
+    LayoutNode::Leaf {
+        id: PaneId(u64::MAX),
+        content: TabContent::Empty,
     }
 }
 
+/// Directory new tabs start in
+fn launch_dir() -> PathBuf {
+    std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"))
+}
+
+/// Error panel in place of a terminal whose shell keeps exiting
+fn show_shell_failure(ui: &mut egui::Ui, failure: &ShellFailure, colors: &RuntimeTheme) -> Option<ShellFailureAction> {
+    let mut action = None;
+    ui.label(egui::RichText::new("The shell exits right after starting")
+        .font(theme::ui_font(14.0))
+        .color(colors.red));
+    ui.add_space(6.0);
+    ui.label(egui::RichText::new(&failure.shell).font(theme::ui_font(12.0)).color(colors.text));
+    let status = match failure.exit_code {
+        Some(code) => format!("Exit status {}", code),
+        None => "No exit status".to_string(),
+    };
+    ui.label(egui::RichText::new(status).font(theme::ui_font(12.0)).color(colors.text_dim));
+    ui.label(egui::RichText::new(format!(
+        "It stopped {} times in a row, so it isn't restarted. Set another shell in Preferences.",
+        MAX_SHELL_RESTARTS + 1
+    ))
+        .font(theme::ui_font(12.0))
+        .color(colors.text_dim));
+    ui.add_space(8.0);
+    ui.horizontal(|ui| {
+        if ui.button("Terminal Preferences…").clicked() {
+            action = Some(ShellFailureAction::OpenPreferences);
+        }
+        if ui.button("Try Again").clicked() {
+            action = Some(ShellFailureAction::Retry);
+        }
+    });
+    action
+}
+
+/// Toast telling that the wanted shell can't run and what runs instead
+fn shell_replaced_toast(shell: &crate::shell_path::ShellChoice) -> Option<Toast> {
+    let replaced = shell.replaced.as_ref()?;
+    Some(Toast::new(format!("{} can't be run; new terminals use {}", replaced, shell.path)))
+}
+
 /// Create a new terminal backend
 fn create_terminal_backend(
     id: u64,
-    ctx: &Context,
     pty_sender: Sender<(u64, PtyEvent)>,
-) -> anyhow::Result<TerminalBackend> {
-    let shell = std::env::var("SHELL").unwrap_or_else(|_| {
-        if cfg!(target_os = "windows") {
-            "cmd.exe".to_string()
-        } else {
-            "/bin/bash".to_string()
-        }
-    });
+    working_directory: Option<PathBuf>,
+) -> anyhow::Result<Box<dyn TerminalBackendHandle>> {
+    if crate::terminal_backend::headless() {
+        return Ok(Box::new(MockBackend::new(id)));
+    }
 
+    let progress = ProgressFeed::new();
+    let tap = progress.clone();
     let settings = BackendSettings {
-        shell,
+        shell: crate::shell_path::shell(),
         args: vec![],
-        working_directory: std::env::current_dir().ok(),
+        working_directory,
+        output_tap: Some(OutputTap::new(move |output| tap.push(output))),
     };
 
-    let backend = TerminalBackend::new(id, ctx.clone(), pty_sender, settings)?;
-    Ok(backend)
+    // egui_term repaints the context it's given for every event; a detached
+    // one leaves waking the UI to the repaint gate
+    let backend = TerminalBackend::new(id, Context::default(), pty_sender, settings)?;
+    Ok(Box::new(PtyBackend::new(backend, progress)))
 }
 
-/// Find the most recently spawned shell process that is a child of the current process.
-///
-/// This is a heuristic approach since egui_term doesn't expose the child PID directly.
-/// We scan for shell processes that:
-/// 1. Have our process as an ancestor (parent or grandparent)
-/// 2. Were recently started
+/// Reopen the directories that were open in the project at `root`
 ///
-/// Returns None on unsupported platforms or if no matching process is found.
-/// Includes a 2-second timeout to prevent blocking on slow systems.
-#[cfg(target_os = "macos")]
-fn find_shell_pid() -> Option<u32> {
-    use libproc::processes::{pids_by_type, ProcFilter};
-    use std::time::{Duration, Instant};
-
-    let start = Instant::now();
-    let timeout = Duration::from_secs(2);
-
-    let our_pid = std::process::id();
-
-    // Get list of all processes
-    let pids = pids_by_type(ProcFilter::All).ok()?;
-
-    // Find shell processes whose parent is our process
-    // egui_term spawns the shell directly, so the shell's parent should be us
-    for &pid in &pids {
-        // Check timeout periodically to avoid blocking on slow systems
-        if start.elapsed() > timeout {
-            log::warn!("find_shell_pid timeout after {:?}", timeout);
-            return None;
+/// `reopen` is the current tree on a reload, otherwise the saved tree state
+/// is used. Other roots keep the scan as is. Runs with the scan, off the UI
+/// thread.
+fn load_sidebar_tree(root: &Path, reopen: Option<TreeState>, options: &ScanOptions, result: &mut DirLoadResult) {
+    let state = match reopen {
+        Some(state) => state,
+        None if crate::project::is_project_root(root) => {
+            TreeState::load(&crate::tree_state::state_dir(), root).unwrap_or_default()
         }
+        None => return,
+    };
+    let is_gitignored = crate::directory_scanner::gitignore_check(root);
+    let entries = std::mem::take(&mut result.entries);
+    (result.entries, result.selected) = state.restore(entries, |dir, depth| list_directory(dir, depth, options, &is_gitignored));
+    result.project_root = true;
+}
 
-        if let Some(ppid) = get_parent_pid(pid) {
-            if ppid == our_pid {
-                // Found a child process - this is likely our shell
-                return Some(pid);
-            }
+/// Sidebar entries for a finished load of `ws.sidebar_root`
+///
+/// Keeps track of project roots, whose open directories were restored with
+/// the scan; Expand All opens every directory instead.
+fn restore_sidebar_tree(ws: &mut Workspace, mut result: DirLoadResult) -> Vec<FileEntry> {
+    if std::mem::take(&mut ws.expand_all_pending) {
+        for entry in result.entries.iter_mut().filter(|entry| entry.is_dir) {
+            entry.is_expanded = true;
         }
+        ws.tree_state_dirty = ws.tracked_tree_root.is_some();
+        return result.entries;
     }
 
-    None
-}
-
-#[cfg(target_os = "macos")]
-fn get_parent_pid(pid: u32) -> Option<u32> {
-    use libproc::libproc::bsd_info::BSDInfo;
-    use libproc::libproc::proc_pid::pidinfo;
-
-    pidinfo::<BSDInfo>(pid as i32, 0)
-        .ok()
-        .map(|info| info.pbi_ppid)
-}
-
-#[cfg(target_os = "linux")]
-fn find_shell_pid() -> Option<u32> {
-    use std::fs;
-    use std::time::{Duration, Instant};
-
-    let start = Instant::now();
-    let timeout = Duration::from_secs(2);
-
-    let our_pid = std::process::id();
-
-    // Read /proc to find child processes
-    if let Ok(entries) = fs::read_dir("/proc") {
-        for entry in entries.flatten() {
-            // Check timeout periodically to avoid blocking on slow systems
-            if start.elapsed() > timeout {
-                log::warn!("find_shell_pid timeout after {:?}", timeout);
-                return None;
-            }
-
-            let name = entry.file_name();
-            if let Ok(pid) = name.to_string_lossy().parse::<u32>() {
-                // Read the stat file to get parent PID
-                let stat_path = format!("/proc/{}/stat", pid);
-                if let Ok(stat) = fs::read_to_string(&stat_path) {
-                    // Format: pid (comm) state ppid ...
-                    // Find the closing paren, then parse the ppid
-                    if let Some(close_paren) = stat.rfind(')') {
-                        let rest = &stat[close_paren + 2..]; // Skip ") "
-                        let fields: Vec<&str> = rest.split_whitespace().collect();
-                        if fields.len() >= 2 {
-                            if let Ok(ppid) = fields[1].parse::<u32>() {
-                                if ppid == our_pid {
-                                    return Some(pid);
-                                }
-                            }
-                        }
-                    }
-                }
+    let root = ws.sidebar_root.clone();
+    if ws.tracked_tree_root.as_ref() != Some(&root) {
+        // Switching roots: the old tree's changes go out first
+        if let Some(old_root) = ws.tracked_tree_root.take().filter(|_| std::mem::take(&mut ws.tree_state_dirty)) {
+            let state = TreeState::capture(&old_root, &ws.sidebar_entries, ws.selected_sidebar_entry, std::time::SystemTime::now());
+            if let Err(e) = state.save(&crate::tree_state::state_dir()) {
+                log::warn!("{}", e);
             }
         }
+        if !result.project_root {
+            return result.entries;
+        }
+        ws.tracked_tree_root = Some(root);
     }
 
-    None
+    ws.selected_sidebar_entry = result.selected;
+    result.entries
 }
 
-#[cfg(not(any(target_os = "macos", target_os = "linux")))]
-fn find_shell_pid() -> Option<u32> {
-    None
+/// Last component of `path`, for naming a tab
+fn dir_label(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
 }
 
-/// Load directory entries for sidebar
-fn load_directory_entries(path: &PathBuf, depth: usize) -> Vec<FileEntry> {
-    let mut entries = Vec::new();
-
-    if let Ok(read_dir) = std::fs::read_dir(path) {
-        let mut items: Vec<_> = read_dir.filter_map(|e| e.ok()).collect();
-
-        // Sort: directories first, then alphabetically
-        items.sort_by(|a, b| {
-            let a_is_dir = a.file_type().map(|t| t.is_dir()).unwrap_or(false);
-            let b_is_dir = b.file_type().map(|t| t.is_dir()).unwrap_or(false);
-
-            match (a_is_dir, b_is_dir) {
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                _ => a.file_name().cmp(&b.file_name()),
-            }
-        });
-
-        let total = items.len();
-        for (i, item) in items.into_iter().enumerate() {
-            let name = item.file_name().to_string_lossy().to_string();
-
-            // Skip hidden files
-            if name.starts_with('.') {
-                continue;
-            }
-
-            let is_dir = item.file_type().map(|t| t.is_dir()).unwrap_or(false);
-            let is_last = i == total - 1;
-
-            let mut entry = FileEntry::new(name, item.path(), is_dir, depth);
-            entry.is_last = is_last;
-            entries.push(entry);
+/// Collect "Run in All Panes" candidates from a layout tree
+fn collect_run_targets(node: &LayoutNode<TabContent>, workspace: usize, out: &mut Vec<crate::run_all::RunTarget>) {
+    match node {
+        LayoutNode::Leaf { id, content } => {
+            let (terminal_id, dir) = match content {
+                TabContent::Terminal(terminal) => (Some(terminal.id), terminal.current_dir.clone()),
+                TabContent::FileViewer { path, .. } => (None, path.clone()),
+                // Nothing to run in or show a directory for
+                TabContent::Empty | TabContent::Diff(_) | TabContent::Stats(_) | TabContent::ColorTest(_) => return,
+            };
+            out.push(crate::run_all::RunTarget { workspace, pane_id: *id, terminal_id, dir });
+        }
+        LayoutNode::Split { first, second, .. } => {
+            collect_run_targets(first, workspace, out);
+            collect_run_targets(second, workspace, out);
         }
     }
+}
 
-    entries
+/// Latest output of the terminals in a layout tree
+fn latest_output(node: &LayoutNode<TabContent>) -> Option<std::time::Instant> {
+    match node {
+        LayoutNode::Leaf { content: TabContent::Terminal(terminal), .. } => terminal.last_output,
+        LayoutNode::Leaf { .. } => None,
+        LayoutNode::Split { first, second, .. } => latest_output(first).max(latest_output(second)),
+    }
 }
 
 /// Collect pane info (id, current_dir) from layout tree
@@ -0,0 +1,1218 @@
+//! Headless App Harness
+//!
+//! Runs `VibeTermApp` frames in a bare `egui::Context` with mock terminals
+//! (see `terminal_backend`), so tests can feed it synthetic input and check
+//! the resulting workspaces and panes without a window or PTYs.
+
+use egui::{Event, Key, Modifiers, RawInput};
+
+use super::*;
+use crate::shortcuts::IS_MAC;
+
+/// Size of the fake window
+const SCREEN: egui::Vec2 = egui::vec2(1280.0, 800.0);
+
+/// The app plus the context it draws into
+struct Headless {
+    ctx: Context,
+    app: VibeTermApp,
+}
+
+impl Headless {
+    /// App with the default config, after its first frame
+    fn new() -> Self {
+        let ctx = Context::default();
+        let app = VibeTermApp::with_config(&ctx, Config::default(), None, StartupReport::start());
+        let mut headless = Self { ctx, app };
+        headless.run(Vec::new(), Modifiers::NONE);
+        headless
+    }
+
+    /// Run one frame with the given input
+    fn run(&mut self, events: Vec<Event>, modifiers: Modifiers) {
+        let input = RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, SCREEN)),
+            modifiers,
+            events,
+            ..Default::default()
+        };
+        let app = &mut self.app;
+        let _ = self.ctx.run(input, |ctx| app.run_frame(ctx));
+    }
+
+    /// Press `key` with the primary shortcut modifier (Cmd, or Ctrl off macOS)
+    fn shortcut(&mut self, key: Key) {
+        self.press(key, Modifiers::NONE);
+    }
+
+    /// Press `key` with the primary shortcut modifier and `extra`
+    fn press(&mut self, key: Key, extra: Modifiers) {
+        let modifiers = Modifiers {
+            mac_cmd: IS_MAC,
+            ctrl: !IS_MAC,
+            command: true,
+            ..Modifiers::NONE
+        } | extra;
+        for pressed in [true, false] {
+            let event = Event::Key {
+                key,
+                physical_key: Some(key),
+                pressed,
+                repeat: false,
+                modifiers,
+            };
+            self.run(vec![event], modifiers);
+        }
+    }
+
+    /// Deliver paths the way Finder's "Open With" and Dock drops arrive
+    fn open_from_os(&mut self, paths: &[&Path]) {
+        self.app.open_requests.extend(paths.iter().map(|path| path.to_path_buf()));
+        self.run(Vec::new(), Modifiers::NONE);
+    }
+
+    fn tab_names(&self) -> Vec<String> {
+        self.app.get_tabs().into_iter().map(|tab| tab.name).collect()
+    }
+
+    fn pane_count(&self) -> usize {
+        self.app.current_workspace().pane_count()
+    }
+}
+
+#[test]
+fn test_starts_with_one_terminal() {
+    let headless = Headless::new();
+    assert_eq!(headless.tab_names(), ["shell"]);
+    assert_eq!(headless.pane_count(), 1);
+    let ws = headless.app.current_workspace();
+    assert!(matches!(ws.get_content(ws.focused_pane), Some(TabContent::Terminal(_))));
+}
+
+#[test]
+fn test_new_tab_shortcut() {
+    let mut headless = Headless::new();
+    headless.shortcut(Key::T);
+    assert_eq!(headless.tab_names(), ["shell", "shell-2"]);
+    assert_eq!(headless.app.active_workspace, 1);
+    assert_eq!(headless.pane_count(), 1);
+}
+
+#[test]
+fn test_split_and_close_shortcuts() {
+    let mut headless = Headless::new();
+    headless.shortcut(Key::D);
+    assert_eq!(headless.pane_count(), 2);
+    let ws = headless.app.current_workspace();
+    assert_eq!(ws.focused_pane, PaneId(1));
+    assert!(matches!(ws.root, LayoutNode::Split { direction: SplitDirection::Horizontal, .. }));
+
+    headless.shortcut(Key::W);
+    assert_eq!(headless.pane_count(), 1);
+    assert_eq!(headless.tab_names(), ["shell"]);
+
+    // The last pane of the last tab stays
+    headless.shortcut(Key::W);
+    assert_eq!(headless.pane_count(), 1);
+    assert_eq!(headless.tab_names(), ["shell"]);
+}
+
+#[test]
+fn test_closing_last_pane_closes_tab() {
+    let mut headless = Headless::new();
+    headless.shortcut(Key::T);
+    headless.shortcut(Key::W);
+    assert_eq!(headless.tab_names(), ["shell"]);
+    assert_eq!(headless.app.active_workspace, 0);
+}
+
+#[test]
+fn test_mirror_closes_with_source_pane() {
+    let mut headless = Headless::new();
+    headless.shortcut(Key::D);
+    let source = headless.app.current_workspace().focused_pane;
+    headless.app.toggle_mirror(source);
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert_eq!(headless.app.mirror, headless.app.current_workspace().terminal_id(source));
+
+    // Toggling again stops it
+    headless.app.toggle_mirror(source);
+    assert_eq!(headless.app.mirror, None);
+
+    headless.app.toggle_mirror(source);
+    headless.shortcut(Key::W);
+    assert_eq!(headless.pane_count(), 1);
+    assert_eq!(headless.app.mirror, None);
+}
+
+/// The tree has no leftover placeholder leaf from a tree transformation
+fn assert_no_placeholder(ws: &Workspace) {
+    let ids = ws.pane_ids();
+    assert!(!ids.contains(&PaneId(u64::MAX)), "placeholder left in {:?}", ids);
+    assert!(ids.iter().all(|&id| !matches!(ws.get_content(id), Some(TabContent::Empty))));
+}
+
+#[test]
+fn test_failed_transformations_restore_tree() {
+    let mut headless = Headless::new();
+    let only = headless.app.current_workspace().focused_pane;
+
+    // The last pane can't close, nor be dropped next to itself
+    assert!(!headless.app.current_workspace_mut().close_pane(only));
+    headless.app.execute_pane_drop(only, DropZone::Left(only));
+    let ws = headless.app.current_workspace();
+    assert_eq!(ws.pane_ids(), [only]);
+    assert!(matches!(ws.get_content(only), Some(TabContent::Terminal(_))));
+    assert_no_placeholder(ws);
+
+    // Unknown panes leave a split tree alone too
+    headless.shortcut(Key::D);
+    assert!(!headless.app.current_workspace_mut().close_pane(PaneId(99)));
+    headless.app.execute_pane_drop(PaneId(99), DropZone::Right(only));
+    assert_eq!(headless.pane_count(), 2);
+    assert_no_placeholder(headless.app.current_workspace());
+}
+
+#[test]
+fn test_empty_pane_starts_terminal_on_keypress() {
+    let mut headless = Headless::new();
+    headless.app.split_pane_empty();
+    let ws = headless.app.current_workspace();
+    let empty = ws.focused_pane;
+    assert_eq!(ws.pane_count(), 2);
+    assert!(matches!(ws.get_content(empty), Some(TabContent::Empty)));
+
+    // Shortcuts leave it empty
+    headless.shortcut(Key::T);
+    headless.app.active_workspace = 0;
+    assert!(matches!(headless.app.current_workspace().get_content(empty), Some(TabContent::Empty)));
+
+    headless.run(vec![Event::Text("l".to_string())], Modifiers::NONE);
+    let ws = headless.app.current_workspace();
+    assert_eq!(ws.focused_pane, empty);
+    assert!(matches!(ws.get_content(empty), Some(TabContent::Terminal(_))));
+}
+
+#[test]
+fn test_file_opens_in_empty_pane() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("notes.txt");
+    std::fs::write(&path, "hello\n").unwrap();
+
+    let mut headless = Headless::new();
+    headless.app.split_pane_empty();
+    let empty = headless.app.current_workspace().focused_pane;
+    headless.app.open_file(path.clone());
+    assert_eq!(headless.tab_names(), ["shell"]);
+    match headless.app.current_workspace().get_content(empty) {
+        Some(TabContent::FileViewer { path: shown, content, .. }) => {
+            assert_eq!(shown, &path);
+            assert_eq!(content, "hello\n");
+        }
+        _ => panic!("file not shown in the empty pane"),
+    }
+
+    // With nothing empty it gets a tab of its own
+    headless.app.open_file(path);
+    assert_eq!(headless.tab_names().len(), 2);
+}
+
+#[test]
+fn test_theme_preview_reverts() {
+    let mut headless = Headless::new();
+    let original = headless.app.theme.clone();
+    let terminal_theme = Arc::clone(&headless.app.cached_terminal_theme);
+
+    let preset = crate::config::ThemeConfig {
+        background: "#000000".to_string(),
+        ..Default::default()
+    };
+    let ctx = headless.ctx.clone();
+    headless.app.preview_theme(&ctx, crate::ui::ThemePreview::Show(Box::new(preset)));
+    assert_eq!(headless.app.theme.background, egui::Color32::BLACK);
+    assert!(!Arc::ptr_eq(&terminal_theme, &headless.app.cached_terminal_theme));
+    assert_ne!(headless.app.config.theme.background, "#000000");
+
+    headless.app.preview_theme(&ctx, crate::ui::ThemePreview::End);
+    assert_eq!(headless.app.theme, original);
+
+    // Ending again is a no-op
+    let terminal_theme = Arc::clone(&headless.app.cached_terminal_theme);
+    headless.app.preview_theme(&ctx, crate::ui::ThemePreview::End);
+    assert!(Arc::ptr_eq(&terminal_theme, &headless.app.cached_terminal_theme));
+}
+
+#[test]
+fn test_pane_diff_opens_in_new_tab() {
+    let mut headless = Headless::new();
+    headless.shortcut(Key::D);
+    headless.app.open_pane_diff(PaneId(0), PaneId(1), DiffScope::Screen);
+    assert_eq!(headless.tab_names().len(), 2);
+    assert_eq!(headless.app.active_workspace, 1);
+
+    let pane = headless.app.current_workspace().focused_pane;
+    let diff_view = |app: &VibeTermApp| match app.current_workspace().get_content(pane) {
+        Some(TabContent::Diff(view)) => view.clone(),
+        _ => panic!("no diff pane"),
+    };
+    // Wait for the background diff
+    for _ in 0..200 {
+        headless.run(Vec::new(), Modifiers::NONE);
+        if diff_view(&headless.app).diff.is_some() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let view = diff_view(&headless.app);
+    assert_eq!(view.pending, None);
+    let diff = view.diff.expect("diff not finished");
+    assert_eq!((diff.added, diff.removed), (0, 0));
+
+    headless.app.run_diff_command(pane, DiffCommand::Swap);
+    let swapped = diff_view(&headless.app);
+    assert_eq!(swapped.left.terminal_id, view.right.terminal_id);
+    assert!(swapped.pending.is_some());
+}
+
+#[test]
+fn test_split_depth_is_limited() {
+    let mut headless = Headless::new();
+    headless.app.config.edit(std::time::Instant::now(), |config| config.ui.max_split_depth = 2);
+    headless.shortcut(Key::D);
+    headless.shortcut(Key::D);
+    assert_eq!(headless.pane_count(), 3);
+
+    // The focused pane is two levels deep already
+    headless.shortcut(Key::D);
+    assert_eq!(headless.pane_count(), 3);
+    assert!(headless.app.toast.is_some());
+
+    // h(0, h(1, 2)) -> h(h(0, 3), h(1, 2))
+    headless.app.current_workspace_mut().focused_pane = PaneId(0);
+    headless.shortcut(Key::D);
+    assert_eq!(headless.pane_count(), 4);
+
+    // Dropping 2 on top of 0 would nest it three levels deep
+    let before = headless.app.current_workspace().pane_ids();
+    headless.app.toast = None;
+    headless.app.execute_pane_drop(PaneId(2), DropZone::Top(PaneId(0)));
+    assert_eq!(headless.app.current_workspace().pane_ids(), before);
+    assert!(headless.app.toast.is_some());
+}
+
+#[test]
+fn test_history_peek_lists_commands_of_all_tabs() {
+    let mut headless = Headless::new();
+    headless.app.record_command("cargo test".to_string());
+    headless.shortcut(Key::T);
+    headless.app.record_command("git status".to_string());
+
+    // Ctrl+R off macOS is the shell's own reverse search
+    if !IS_MAC {
+        headless.shortcut(Key::R);
+        assert!(!headless.app.history_peek.is_visible());
+    }
+    let shift = if IS_MAC { Modifiers::NONE } else { Modifiers::SHIFT };
+    headless.press(Key::R, shift);
+    assert!(headless.app.history_peek.is_visible());
+    assert!(!headless.app.terminal_has_keyboard_focus());
+
+    // Enter types the newest command and closes the overlay, without
+    // reaching the terminal as well
+    let written_before = {
+        let ws = headless.app.current_workspace();
+        match ws.get_content(ws.focused_pane) {
+            Some(TabContent::Terminal(terminal)) => terminal.backend.written().len(),
+            _ => panic!("focused pane is not a terminal"),
+        }
+    };
+    let enter = Event::Key { key: Key::Enter, physical_key: None, pressed: true, repeat: false, modifiers: Modifiers::NONE };
+    headless.run(vec![enter], Modifiers::NONE);
+    assert!(!headless.app.history_peek.is_visible());
+    let ws = headless.app.current_workspace();
+    let Some(TabContent::Terminal(terminal)) = ws.get_content(ws.focused_pane) else {
+        panic!("focused pane is not a terminal");
+    };
+    assert_eq!(&terminal.backend.written()[written_before..], b"git status");
+    let terminal_ids: Vec<u64> = headless.app.command_history
+        .with_scrollback(Vec::new())
+        .iter()
+        .map(|entry| entry.terminal_id)
+        .collect();
+    assert_eq!(terminal_ids.len(), 2);
+    assert_ne!(terminal_ids[0], terminal_ids[1]);
+}
+
+#[test]
+fn test_bars_use_configured_heights() {
+    let mut headless = Headless::new();
+    headless.app.config.edit(std::time::Instant::now(), |config| {
+        config.ui.tab_bar_height = 36.0;
+        config.ui.status_bar_height = 26.0;
+    });
+    headless.run(Vec::new(), Modifiers::NONE);
+
+    let panel_height = |id: &str| {
+        egui::containers::panel::PanelState::load(&headless.ctx, egui::Id::new(id))
+            .map(|state| state.rect.height())
+    };
+    assert_eq!(panel_height("tab_bar"), Some(36.0));
+    assert_eq!(panel_height("status_bar"), Some(26.0));
+    assert_eq!(headless.app.tab_bar_rect.height(), 36.0);
+}
+
+#[test]
+fn test_dnd_counts_bells_and_summarizes() {
+    let mut headless = Headless::new();
+    headless.app.notifier.toggle();
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert!(headless.app.notifier.is_active());
+
+    for _ in 0..2 {
+        headless.app.pty_sender.send((0, PtyEvent::Bell)).unwrap();
+    }
+    headless.run(Vec::new(), Modifiers::NONE);
+
+    headless.app.notifier.toggle();
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert!(!headless.app.notifier.is_active());
+    let toast = headless.app.toast.as_ref().expect("no summary toast");
+    assert_eq!(toast.message, "Do Not Disturb off · held back 2 bells");
+}
+
+#[test]
+fn test_paths_from_the_os_open_as_tabs() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let project = temp.path().join("project");
+    std::fs::create_dir(&project).unwrap();
+    let notes = project.join("notes.md");
+    std::fs::write(&notes, "# Notes").unwrap();
+
+    let mut headless = Headless::new();
+    headless.open_from_os(&[&project, &notes]);
+    assert_eq!(headless.tab_names().len(), 3);
+
+    let folder_tab = &headless.app.workspaces[1];
+    assert_eq!(folder_tab.sidebar_root, project);
+    assert!(matches!(folder_tab.get_content(folder_tab.focused_pane), Some(TabContent::Terminal(_))));
+    let file_tab = headless.app.current_workspace();
+    assert!(matches!(
+        file_tab.get_content(file_tab.focused_pane),
+        Some(TabContent::FileViewer { path, .. }) if *path == notes
+    ));
+
+    headless.open_from_os(&[&temp.path().join("missing")]);
+    assert_eq!(headless.tab_names().len(), 3);
+    assert!(headless.app.toast.as_ref().is_some_and(|toast| toast.message.starts_with("Cannot open")));
+}
+
+#[test]
+fn test_marks_are_labelled_and_listed() {
+    let mut headless = Headless::new();
+    let modifiers = Modifiers {
+        mac_cmd: IS_MAC,
+        ctrl: !IS_MAC,
+        command: true,
+        shift: true,
+        ..Modifiers::NONE
+    };
+    let add_mark = Event::Key { key: Key::M, physical_key: Some(Key::M), pressed: true, repeat: false, modifiers };
+    headless.run(vec![add_mark], modifiers);
+    assert!(headless.app.mark_prompt.is_some());
+    assert!(!headless.app.terminal_has_keyboard_focus());
+
+    let enter = Event::Key { key: Key::Enter, physical_key: None, pressed: true, repeat: false, modifiers: Modifiers::NONE };
+    headless.run(vec![Event::Text("build".to_string())], Modifiers::NONE);
+    headless.run(vec![enter], Modifiers::NONE);
+    assert!(headless.app.mark_prompt.is_none());
+
+    let ws = headless.app.current_workspace();
+    let Some(TabContent::Terminal(terminal)) = ws.get_content(ws.focused_pane) else {
+        panic!("focused pane is not a terminal");
+    };
+    let labels: Vec<&str> = terminal.marks.marks().iter().map(|mark| mark.label.as_str()).collect();
+    assert_eq!(labels, ["build"]);
+    assert_eq!(terminal.marks.grid_line(0), Some(0));
+
+    // Shift turns the brackets into braces, as a real keyboard sends them
+    let toast = |headless: &Headless| headless.app.toast.as_ref().map(|toast| toast.message.clone());
+    for (key, physical_key, message) in [
+        (Key::CloseCurlyBracket, Key::CloseBracket, "No later mark"),
+        (Key::OpenCurlyBracket, Key::OpenBracket, "No earlier mark"),
+    ] {
+        headless.app.toast = None;
+        let step = Event::Key { key, physical_key: Some(physical_key), pressed: true, repeat: false, modifiers };
+        headless.run(vec![step], modifiers);
+        assert_eq!(toast(&headless).as_deref(), Some(message));
+    }
+
+    headless.app.open_mark_list();
+    assert!(headless.app.mark_list.is_visible());
+    assert!(!headless.app.terminal_has_keyboard_focus());
+}
+
+#[test]
+fn test_shell_exiting_on_startup_is_restarted_then_reported() {
+    let mut headless = Headless::new();
+    headless.shortcut(Key::D);
+    let focused_terminal = |headless: &Headless| {
+        let ws = headless.app.current_workspace();
+        match ws.get_content(ws.focused_pane) {
+            Some(TabContent::Terminal(terminal)) => (terminal.id, terminal.failure.is_some()),
+            _ => panic!("focused pane is not a terminal"),
+        }
+    };
+
+    // Two quick exits restart the shell in the same pane
+    for _ in 0..2 {
+        let (id, _) = focused_terminal(&headless);
+        headless.app.pty_sender.send((id, PtyEvent::Exit)).unwrap();
+        headless.run(Vec::new(), Modifiers::NONE);
+        let (restarted, failed) = focused_terminal(&headless);
+        assert_ne!(restarted, id);
+        assert!(!failed);
+        assert_eq!(headless.pane_count(), 2);
+    }
+
+    // The third gives up and keeps the exit status for the panel
+    let (id, _) = focused_terminal(&headless);
+    headless.app.pty_sender.send((id, PtyEvent::ChildExit(127))).unwrap();
+    headless.app.pty_sender.send((id, PtyEvent::Exit)).unwrap();
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert_eq!(focused_terminal(&headless), (id, true));
+    assert_eq!(headless.pane_count(), 2);
+    let ws = headless.app.current_workspace();
+    let Some(TabContent::Terminal(terminal)) = ws.get_content(ws.focused_pane) else {
+        unreachable!();
+    };
+    assert_eq!(terminal.failure.as_ref().and_then(|failure| failure.exit_code), Some(127));
+
+    // Typing `exit` right away closes the pane like any other time
+    headless.shortcut(Key::D);
+    let (id, _) = focused_terminal(&headless);
+    headless.app.pty_sender.send((id, PtyEvent::ChildExit(0))).unwrap();
+    headless.app.pty_sender.send((id, PtyEvent::Exit)).unwrap();
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert_eq!(headless.pane_count(), 2);
+    assert!(headless.app.current_workspace().find_pane_by_terminal_id(id).is_none());
+}
+
+#[test]
+fn test_blame_toggles_and_reports_why_it_cant_run() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("notes.txt");
+    std::fs::write(&path, "one\ntwo\nthree\n").unwrap();
+
+    let mut headless = Headless::new();
+    headless.app.open_file(path);
+    let pane = headless.app.current_workspace().focused_pane;
+    let blame = |headless: &Headless| match headless.app.current_workspace().get_content(pane) {
+        Some(TabContent::FileViewer { blame, .. }) => match blame {
+            None => "off".to_string(),
+            Some(BlameView::Loading(_) | BlameView::Stale) => "loading".to_string(),
+            Some(BlameView::Ready(_)) => "ready".to_string(),
+            Some(BlameView::Unavailable(reason)) => reason.clone(),
+        },
+        _ => panic!("file not shown"),
+    };
+
+    // Outside a repository the background blame fails
+    headless.app.toggle_blame(headless.app.active_workspace, pane);
+    for _ in 0..500 {
+        if blame(&headless) != "loading" {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        headless.run(Vec::new(), Modifiers::NONE);
+    }
+    assert_eq!(blame(&headless), "Not in a git repository");
+    headless.app.toggle_blame(headless.app.active_workspace, pane);
+    assert_eq!(blame(&headless), "off");
+
+    // Files over the cap aren't blamed at all
+    headless.app.config.edit(std::time::Instant::now(), |config| config.file_viewer.blame_max_lines = 2);
+    headless.app.toggle_blame(headless.app.active_workspace, pane);
+    assert_eq!(blame(&headless), "No blame for files over 2 lines");
+}
+
+#[test]
+fn test_auto_hide_sidebar_slides_over_panes() {
+    let mut headless = Headless::new();
+    let docked_left = headless.app.pane_area.left();
+    assert!(docked_left > 0.0);
+
+    headless.app.config.edit(std::time::Instant::now(), |config| config.ui.sidebar_mode = SidebarMode::AutoHide);
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert_eq!(headless.app.pane_area.left(), 0.0);
+    assert!(!headless.app.sidebar_shown());
+
+    // Hovering the strip brings it out without moving the panes
+    headless.run(vec![Event::PointerMoved(egui::pos2(2.0, 400.0))], Modifiers::NONE);
+    assert!(headless.app.sidebar_shown());
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert_eq!(headless.app.pane_area.left(), 0.0);
+
+    headless.shortcut(Key::B);
+    assert!(!headless.app.sidebar_shown());
+    headless.shortcut(Key::B);
+    assert!(headless.app.sidebar_shown());
+
+    // Opening a file puts it away
+    headless.app.open_file(PathBuf::from("/tmp/vibeterm-missing.txt"));
+    assert!(!headless.app.sidebar_shown());
+}
+
+#[test]
+fn test_project_search_shows_a_hidden_sidebar_until_closed() {
+    let mut headless = Headless::new();
+    headless.shortcut(Key::B);
+    assert!(!headless.app.sidebar_shown());
+
+    headless.press(Key::F, Modifiers::SHIFT);
+    assert!(headless.app.sidebar_shown());
+    assert!(!headless.app.sidebar_visible);
+
+    let escape = Event::Key { key: Key::Escape, physical_key: None, pressed: true, repeat: false, modifiers: Modifiers::NONE };
+    headless.run(vec![escape], Modifiers::NONE);
+    assert!(!headless.app.search_panel.is_visible());
+    assert!(!headless.app.sidebar_shown());
+}
+
+#[test]
+fn test_sidebar_keeps_the_width_it_was_dragged_to() {
+    let mut headless = Headless::new();
+
+    // Squeezed by the panel's limits, the saved width stays
+    headless.app.config.edit(std::time::Instant::now(), |config| config.ui.sidebar_width = 500.0);
+    headless.app.sidebar_width_changed = true;
+    for _ in 0..3 {
+        headless.run(Vec::new(), Modifiers::NONE);
+    }
+    assert!(headless.app.pane_area.left() <= 401.0);
+    assert_eq!(headless.app.config.ui.sidebar_width, 500.0);
+
+    // Dragging the edge saves where it was let go
+    let edge = headless.app.pane_area.left() - 1.0;
+    let button = |pos: egui::Pos2, pressed| Event::PointerButton {
+        pos,
+        button: egui::PointerButton::Primary,
+        pressed,
+        modifiers: Modifiers::NONE,
+    };
+    headless.run(vec![Event::PointerMoved(egui::pos2(edge, 400.0))], Modifiers::NONE);
+    headless.run(vec![button(egui::pos2(edge, 400.0), true)], Modifiers::NONE);
+    for x in [edge - 40.0, 300.0] {
+        headless.run(vec![Event::PointerMoved(egui::pos2(x, 400.0))], Modifiers::NONE);
+    }
+    headless.run(vec![button(egui::pos2(300.0, 400.0), false)], Modifiers::NONE);
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert_eq!(headless.app.config.ui.sidebar_width, 300.0);
+}
+
+#[test]
+fn test_tab_switch_keeps_focus_and_scroll_positions() {
+    let mut headless = Headless::new();
+    headless.shortcut(Key::D);
+    assert_eq!(headless.app.current_workspace().focused_pane, PaneId(1));
+    headless.shortcut(Key::T);
+    headless.shortcut(Key::T);
+
+    let offsets = |ws: &mut Workspace| -> Vec<Option<usize>> {
+        ws.root
+            .collect_contents_mut()
+            .into_iter()
+            .filter_map(|(_, content)| match content {
+                TabContent::Terminal(terminal) => Some(terminal.saved_offset),
+                _ => None,
+            })
+            .collect()
+    };
+    let bottom = Some(0);
+    assert_eq!(offsets(&mut headless.app.workspaces[0]), [bottom, bottom]);
+
+    // The first tab moves to the end; its views go with it, not with its old index
+    headless.app.move_tab(0, 2);
+    assert_eq!(headless.app.active_workspace, 1);
+    headless.shortcut(Key::Num3);
+    let ws = &mut headless.app.workspaces[2];
+    assert!(ws.shown);
+    assert_eq!(ws.focused_pane, PaneId(1));
+    assert_eq!(offsets(ws), [None, None]);
+    assert!(!headless.app.workspaces[1].shown);
+    assert_eq!(offsets(&mut headless.app.workspaces[1]), [bottom]);
+
+    // File viewers get their offset back on the first frame only
+    headless.app.workspaces[2].restore_scroll = true;
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert!(!headless.app.workspaces[2].restore_scroll);
+}
+
+#[test]
+fn test_connect_to_host_names_and_tints_the_terminal() {
+    let mut headless = Headless::new();
+    let tint = egui::Color32::from_rgb(0xE0, 0x6C, 0x75);
+    let host = crate::ssh_config::SshHost {
+        name: "bastion".to_string(),
+        hostname: Some("bastion.example.com".to_string()),
+        user: None,
+        port: None,
+        tint: Some(tint),
+        in_ssh_config: false,
+    };
+
+    headless.app.connect_to_host(&host, false);
+    assert_eq!(headless.tab_names(), ["shell", "bastion"]);
+    let ws = headless.app.current_workspace();
+    assert_eq!(ws.pane_label(ws.focused_pane), "bastion");
+    assert_eq!(ws.pane_tint(ws.focused_pane), Some(tint));
+
+    // A split keeps the tab's name
+    headless.app.connect_to_host(&crate::ssh_config::SshHost { tint: None, ..host }, true);
+    assert_eq!(headless.pane_count(), 2);
+    assert_eq!(headless.tab_names(), ["shell", "bastion"]);
+    let ws = headless.app.current_workspace();
+    assert_eq!(ws.pane_tint(ws.focused_pane), None);
+}
+
+#[test]
+fn test_pinned_tabs_stay_first() {
+    let mut headless = Headless::new();
+    headless.shortcut(Key::T);
+    headless.shortcut(Key::T);
+    for (ws, name) in headless.app.workspaces.iter_mut().zip(["a", "b", "c"]) {
+        ws.name = name.to_string();
+    }
+
+    // Pinning moves the tab to the end of the pinned group, and the active tab follows
+    headless.app.toggle_tab_pinned(2);
+    assert_eq!(headless.tab_names(), ["c", "a", "b"]);
+    assert_eq!(headless.app.active_workspace, 0);
+    headless.app.toggle_tab_pinned(2);
+    assert_eq!(headless.tab_names(), ["c", "b", "a"]);
+
+    // Cmd+number counts pinned tabs in the order they're drawn
+    headless.shortcut(Key::Num2);
+    assert_eq!(headless.app.current_workspace().name, "b");
+
+    // Neither kind crosses the group boundary
+    headless.app.move_tab(2, 0);
+    assert_eq!(headless.tab_names(), ["c", "b", "a"]);
+    headless.app.move_tab(0, 2);
+    assert_eq!(headless.tab_names(), ["b", "c", "a"]);
+
+    // Unpinning puts the tab right after the pinned group
+    headless.app.toggle_tab_pinned(0);
+    assert_eq!(headless.tab_names(), ["c", "b", "a"]);
+    assert_eq!(headless.app.pinned_tab_count(), 1);
+    assert_eq!(headless.app.current_workspace().name, "b");
+}
+
+#[test]
+fn test_closing_with_background_work_asks_first() {
+    let mut headless = Headless::new();
+    let close = |headless: &mut Headless| {
+        let mut input = RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, SCREEN)),
+            ..Default::default()
+        };
+        input.viewports.insert(egui::ViewportId::ROOT, egui::ViewportInfo {
+            events: vec![egui::ViewportEvent::Close],
+            ..Default::default()
+        });
+        let app = &mut headless.app;
+        let _ = headless.ctx.run(input, |ctx| app.run_frame(ctx));
+    };
+
+    // Nothing running once the startup directory scan is done: the window just closes
+    for _ in 0..200 {
+        if headless.app.loading_dirs.is_empty() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        headless.run(Vec::new(), Modifiers::NONE);
+    }
+    close(&mut headless);
+    assert!(headless.app.quit_prompt.is_none());
+
+    headless.app.loading_dirs.insert(headless.app.workspaces[0].id, true);
+    close(&mut headless);
+    let prompt = headless.app.quit_prompt.clone().expect("quit dialog");
+    assert_eq!(prompt.tasks, ["1 directory scan"]);
+
+    // Turned off in the config, quitting doesn't ask
+    headless.app.quit_prompt = None;
+    headless.app.config.edit(std::time::Instant::now(), |c| c.ui.confirm_quit = false);
+    close(&mut headless);
+    assert!(headless.app.quit_prompt.is_none());
+}
+
+#[test]
+fn test_color_test_tab_writes_script_to_a_split() {
+    let mut headless = Headless::new();
+    headless.app.open_color_test();
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert_eq!(headless.tab_names(), ["shell", "color test"]);
+    let color_pane = headless.app.current_workspace().focused_pane;
+
+    // The first press splits a terminal off below the preview, later ones reuse it
+    headless.app.write_color_test_script(color_pane);
+    assert_eq!(headless.pane_count(), 2);
+    let ws = headless.app.current_workspace();
+    let terminal_pane = ws.focused_pane;
+    assert!(matches!(ws.get_content(terminal_pane), Some(TabContent::Terminal(_))));
+
+    headless.app.write_color_test_script(color_pane);
+    assert_eq!(headless.pane_count(), 2);
+    assert_eq!(headless.app.current_workspace().focused_pane, terminal_pane);
+}
+
+#[test]
+fn test_folders_opened_in_bulk_start_dormant() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let folders: Vec<PathBuf> = (0..5)
+        .map(|i| {
+            let dir = temp.path().join(format!("project-{}", i));
+            std::fs::create_dir(&dir).unwrap();
+            std::fs::write(dir.join("README.md"), "").unwrap();
+            dir
+        })
+        .collect();
+    let is_terminal = |ws: &Workspace| matches!(ws.get_content(ws.focused_pane), Some(TabContent::Terminal(_)));
+
+    let mut headless = Headless::new();
+    headless.open_from_os(&folders.iter().map(PathBuf::as_path).collect::<Vec<_>>());
+    let dormant: Vec<bool> = headless.app.get_tabs().iter().map(|tab| tab.dormant).collect();
+    assert_eq!(dormant, [false, true, true, true, true, false]);
+    // The last folder is the tab shown, and it's running
+    assert_eq!(headless.app.active_workspace, 5);
+    assert!(is_terminal(headless.app.current_workspace()));
+    assert_eq!(headless.app.workspaces[2].sidebar_root, folders[1]);
+    // Their file trees load without waiting for the shell
+    let ws = &headless.app.workspaces[2];
+    assert!(headless.app.loading_dirs.contains_key(&ws.id) || !ws.sidebar_entries.is_empty());
+
+    // Switching to a dormant tab starts its shell before it's drawn
+    headless.shortcut(Key::Num2);
+    let ws = headless.app.current_workspace();
+    assert!(ws.dormant.is_none() && is_terminal(ws));
+    let Some(TabContent::Terminal(terminal)) = ws.get_content(ws.focused_pane) else {
+        unreachable!();
+    };
+    assert_eq!(terminal.current_dir, folders[0]);
+
+    // Idle, the newest ones start; the rest wait to be shown
+    headless.app.dormant_wake_at = Some(std::time::Instant::now());
+    headless.run(Vec::new(), Modifiers::NONE);
+    let dormant: Vec<bool> = headless.app.workspaces.iter().map(|ws| ws.dormant.is_some()).collect();
+    assert_eq!(dormant, [false, false, true, false, false, false]);
+    assert!(headless.app.dormant_wake_at.is_none());
+}
+
+#[test]
+fn test_new_tabs_can_open_after_the_current_one() {
+    let mut headless = Headless::new();
+    headless.shortcut(Key::T);
+    headless.shortcut(Key::T);
+    headless.app.config.edit(std::time::Instant::now(), |config| config.ui.new_tab_position = NewTabPosition::AfterCurrent);
+
+    headless.shortcut(Key::Num1);
+    headless.app.dragging_tab = Some(TabDragState {
+        source_index: 2,
+        start_pos: egui::Pos2::ZERO,
+        current_pos: egui::Pos2::ZERO,
+        drag_active: false,
+    });
+    headless.app.create_new_tab();
+    assert_eq!(headless.tab_names(), ["shell", "shell-4", "shell-2", "shell-3"]);
+    assert_eq!(headless.app.active_workspace, 1);
+    // A tab being dragged keeps pointing at the same tab
+    assert_eq!(headless.app.dragging_tab.as_ref().map(|drag| drag.source_index), Some(3));
+
+    // The palette command overrides the setting
+    headless.app.create_new_tab_in(launch_dir(), NewTabPosition::End);
+    assert_eq!(headless.app.active_workspace, 4);
+}
+
+#[test]
+fn test_background_loads_find_their_tab_after_an_insert() {
+    let temp = tempfile::tempdir().unwrap();
+    std::fs::write(temp.path().join("notes.md"), "# Notes").unwrap();
+    let mut headless = Headless::new();
+    headless.shortcut(Key::T);
+    headless.app.config.edit(std::time::Instant::now(), |config| config.ui.new_tab_position = NewTabPosition::AfterCurrent);
+
+    // The second tab's tree is scanning while a tab opens ahead of it
+    headless.shortcut(Key::Num1);
+    headless.app.workspaces[1].sidebar_root = temp.path().to_path_buf();
+    headless.app.load_directory_async(1, temp.path().to_path_buf());
+    let scanned = headless.app.workspaces[1].id;
+    headless.app.create_new_tab();
+
+    for _ in 0..200 {
+        headless.run(Vec::new(), Modifiers::NONE);
+        if !headless.app.loading_dirs.contains_key(&scanned) {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let listed = |ws: &Workspace| ws.sidebar_entries.iter().any(|entry| entry.name == "notes.md");
+    assert!(listed(&headless.app.workspaces[2]));
+    assert!(!listed(&headless.app.workspaces[1]));
+}
+
+#[test]
+fn test_project_tree_reloads_keep_open_directories() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = temp.path().canonicalize().unwrap();
+    std::fs::write(root.join("Cargo.toml"), "").unwrap();
+    std::fs::create_dir_all(root.join("src/bin")).unwrap();
+    let mut headless = Headless::new();
+    let scan = |headless: &mut Headless| {
+        for _ in 0..200 {
+            headless.run(Vec::new(), Modifiers::NONE);
+            if headless.app.loading_dirs.is_empty() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    };
+    scan(&mut headless);
+
+    headless.app.set_sidebar_root(root.clone());
+    scan(&mut headless);
+    assert_eq!(headless.app.current_workspace().tracked_tree_root, Some(root.clone()));
+    let src = headless.app.current_workspace().sidebar_entries.iter().position(|entry| entry.name == "src").unwrap();
+    headless.app.toggle_directory(src);
+
+    // The loader lists the open directories again along with the scan
+    headless.app.load_directory_async(headless.app.active_workspace, root.clone());
+    scan(&mut headless);
+    let entries = &headless.app.current_workspace().sidebar_entries;
+    let open: Vec<_> = entries.iter().filter(|entry| entry.is_expanded).map(|entry| entry.path.clone()).collect();
+    assert_eq!(open, [root.join("src")]);
+    assert!(entries.iter().any(|entry| entry.path == root.join("src/bin") && entry.depth == 1));
+}
+
+#[test]
+fn test_pastes_into_closed_terminals_are_dropped() {
+    use crate::clipboard_paste::{PastePayload, PasteReady};
+    let mut headless = Headless::new();
+    headless.shortcut(Key::D);
+    let ws = headless.app.current_workspace();
+    let closing = ws.terminal_id(ws.focused_pane).unwrap();
+
+    let now = std::time::Instant::now();
+    let failed = headless.app.pastes.start(closing, now);
+    let orphaned = headless.app.pastes.start(closing, now);
+    headless.shortcut(Key::W);
+    assert_eq!(headless.pane_count(), 1);
+
+    headless.app.paste_tx.send(PasteReady { request: orphaned, payload: PastePayload::Text("ls".to_string()) }).unwrap();
+    headless.app.paste_tx.send(PasteReady { request: failed, payload: PastePayload::Failed("No clipboard".to_string()) }).unwrap();
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert_eq!(headless.app.pastes.finish(orphaned), None);
+    assert!(!headless.app.pastes.is_slow(closing, now + crate::clipboard_paste::SPINNER_DELAY));
+    assert_eq!(headless.app.toast.as_ref().map(|toast| toast.message.as_str()), Some("No clipboard"));
+}
+
+#[test]
+fn test_sidebar_roots_go_back_and_forward() {
+    let dirs: Vec<_> = (0..3).map(|_| tempfile::tempdir().unwrap()).collect();
+    let [a, b, c] = [0, 1, 2].map(|n| dirs[n].path().to_path_buf());
+    let mut headless = Headless::new();
+    let start = headless.app.current_workspace().sidebar_root.clone();
+    for dir in [&a, &b, &c] {
+        headless.app.set_sidebar_root(dir.clone());
+    }
+    let root = |headless: &Headless| headless.app.current_workspace().sidebar_root.clone();
+
+    // Cmd+[ goes back while the sidebar has focus
+    headless.app.sidebar_focused = true;
+    headless.shortcut(Key::OpenBracket);
+    assert_eq!(root(&headless), b);
+    headless.app.navigate_sidebar_root(false);
+    assert_eq!(root(&headless), a);
+    headless.app.navigate_sidebar_root(true);
+    assert_eq!(root(&headless), b);
+
+    // Going back doesn't add to the history, and a deleted root is skipped
+    std::fs::remove_dir(&a).unwrap();
+    headless.app.navigate_sidebar_root(false);
+    assert_eq!(root(&headless), start);
+    let toast = headless.app.toast.as_ref().expect("no skip toast");
+    assert!(toast.message.ends_with("it no longer exists"), "{}", toast.message);
+    assert!(!headless.app.current_workspace().root_history.can_go_back());
+    headless.app.navigate_sidebar_root(true);
+    assert_eq!(root(&headless), b);
+    headless.app.navigate_sidebar_root(true);
+    assert_eq!(root(&headless), c);
+}
+
+#[test]
+fn test_session_restores_tabs_and_splits() {
+    let dir = tempfile::tempdir().unwrap();
+    let notes = dir.path().join("notes.md");
+    std::fs::write(&notes, "# notes").unwrap();
+
+    let mut headless = Headless::new();
+    headless.shortcut(Key::D);
+    headless.app.create_file_tab(notes.clone());
+    headless.app.create_file_tab(dir.path().join("deleted.txt"));
+    let red = egui::Color32::from_rgb(0xE0, 0x6C, 0x75);
+    headless.app.workspaces[0].set_pane_tint(PaneId(1), Some(red));
+    headless.app.workspaces[0].notes.push(OutputNote::new(0, "error: linker failed"));
+    headless.shortcut(Key::Num2);
+    let session = headless.app.capture_session();
+    assert_eq!(session.tabs.len(), 3);
+    assert_eq!(session.active, 1);
+
+    let mut restored = Headless::new();
+    restored.app.workspaces.clear();
+    restored.app.restore_session(session);
+    restored.run(Vec::new(), Modifiers::NONE);
+    assert_eq!(restored.tab_names(), ["shell", "notes.md", "deleted.txt"]);
+    assert_eq!(restored.app.active_workspace, 1);
+    let ws = &restored.app.workspaces[1];
+    assert!(matches!(ws.get_content(ws.focused_pane), Some(TabContent::FileViewer { path, .. }) if *path == notes));
+    // Only the active tab starts; the split's shells wait until it's shown
+    let dormant: Vec<bool> = restored.app.workspaces.iter().map(|ws| ws.dormant.is_some()).collect();
+    assert_eq!(dormant, [true, false, true]);
+    restored.shortcut(Key::Num1);
+    assert_eq!(restored.app.workspaces[0].pane_count(), 2);
+    assert!(restored.app.workspaces[0].dormant.is_none());
+    // Tints and notes come back with it
+    assert_eq!(restored.app.workspaces[0].pane_tint(PaneId(1)), Some(red));
+    assert_eq!(restored.app.workspaces[0].pane_tint(PaneId(0)), None);
+    let notes: Vec<&str> = restored.app.workspaces[0].notes.iter().map(|note| note.text.as_str()).collect();
+    assert_eq!(notes, ["error: linker failed"]);
+    // A file that's gone comes back as an empty pane
+    restored.shortcut(Key::Num3);
+    let ws = &restored.app.workspaces[2];
+    assert!(matches!(ws.get_content(ws.focused_pane), Some(TabContent::Empty)));
+}
+
+#[test]
+fn test_startup_phases_are_timed_through_the_first_frame() {
+    let mut headless = Headless::new();
+    let startup = &headless.app.startup;
+    assert!(startup.is_complete());
+    let names: Vec<&str> = startup.phases().iter().map(|phase| phase.name).collect();
+    // `main` and `new` time the phases before and after `with_config`
+    assert_eq!(names, ["theme and fonts", "context", "terminals", "background tasks", "first frame"]);
+    assert!(names.iter().all(|name| crate::startup::PHASES.contains(name)));
+
+    // Later frames don't add to it
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert_eq!(headless.app.startup.phases().len(), names.len());
+}
+
+#[test]
+fn test_typing_into_the_palette_never_reaches_the_terminal() {
+    let mut headless = Headless::new();
+    headless.shortcut(Key::D);
+    headless.shortcut(Key::P);
+    assert!(headless.app.command_palette.is_visible());
+    assert_eq!(headless.app.keyboard_owner(), KeyboardOwner::Palette);
+
+    headless.run(
+        vec![Event::Text("split".to_string()), Event::Ime(ImeEvent::Commit("한".to_string()))],
+        Modifiers::NONE,
+    );
+    // Neither closes a pane nor pastes while the palette is open
+    headless.shortcut(Key::W);
+    headless.shortcut(Key::V);
+    assert_eq!(headless.pane_count(), 2);
+
+    let written = |headless: &Headless| {
+        let ws = headless.app.current_workspace();
+        match ws.get_content(ws.focused_pane) {
+            Some(TabContent::Terminal(terminal)) => terminal.backend.written().to_vec(),
+            _ => panic!("focused pane is not a terminal"),
+        }
+    };
+    assert_eq!(written(&headless), b"");
+
+    // Once it's closed the terminal has the keyboard again
+    let escape = Event::Key { key: Key::Escape, physical_key: None, pressed: true, repeat: false, modifiers: Modifiers::NONE };
+    headless.run(vec![escape], Modifiers::NONE);
+    assert_eq!(headless.app.keyboard_owner(), KeyboardOwner::Terminal);
+    headless.run(vec![Event::Ime(ImeEvent::Commit("한".to_string()))], Modifiers::NONE);
+    assert_eq!(written(&headless), "한".as_bytes());
+}
+
+#[test]
+fn test_new_splits_and_tabs_start_in_the_focused_terminals_directory() {
+    let project = tempfile::tempdir().unwrap();
+    let notes = project.path().join("notes.md");
+    std::fs::write(&notes, "# Notes\n").unwrap();
+    let mut headless = Headless::new();
+    let cd = |headless: &mut Headless, dir: &Path| {
+        let ws = headless.app.current_workspace_mut();
+        let Some(TabContent::Terminal(terminal)) = ws.root.get_content_mut(ws.focused_pane) else {
+            panic!("focused pane is not a terminal");
+        };
+        terminal.current_dir = dir.to_path_buf();
+    };
+    let focused_dir = |headless: &Headless| {
+        headless.app.current_workspace().focused_terminal_dir().unwrap()
+    };
+
+    cd(&mut headless, project.path());
+    headless.shortcut(Key::D);
+    assert_eq!(focused_dir(&headless), project.path());
+    headless.shortcut(Key::T);
+    assert_eq!(focused_dir(&headless), project.path());
+
+    // A file viewer passes on the directory of the terminal focused before it
+    headless.app.current_workspace_mut().split_focused_with(
+        SplitDirection::Horizontal,
+        TabContent::file_viewer(notes),
+    );
+    headless.run(Vec::new(), Modifiers::NONE);
+    assert_eq!(headless.app.new_terminal_dir(), project.path());
+
+    // A deleted directory falls back to home
+    let gone = project.path().join("gone");
+    headless.app.current_workspace_mut().focus_prev();
+    cd(&mut headless, &gone);
+    assert_eq!(Some(headless.app.new_terminal_dir()), dirs::home_dir());
+
+    headless.app.config.edit(std::time::Instant::now(), |config| {
+        config.terminal.new_terminal_dir = NewTerminalDir::Launch;
+    });
+    assert_eq!(headless.app.new_terminal_dir(), launch_dir());
+}
+
+#[test]
+fn test_sidebar_files_open_where_configured() {
+    let dir = tempfile::tempdir().unwrap();
+    let [notes, todo] = ["notes.md", "todo.txt"].map(|name| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, "text\n").unwrap();
+        path
+    });
+    let viewed = |headless: &Headless| {
+        let ws = headless.app.current_workspace();
+        match ws.get_content(ws.focused_pane) {
+            Some(TabContent::FileViewer { path, .. }) => Some(path.clone()),
+            _ => None,
+        }
+    };
+    let mut headless = Headless::new();
+    let set_action = |headless: &mut Headless, action| {
+        headless.app.config.edit(std::time::Instant::now(), |config| config.ui.sidebar_open_action = action);
+    };
+
+    // The default opens a new tab
+    headless.app.open_sidebar_file(notes.clone(), Modifiers::NONE);
+    assert_eq!(headless.tab_names(), ["shell", "notes.md"]);
+    headless.app.close_tab(1);
+
+    set_action(&mut headless, SidebarOpenAction::SplitDown);
+    headless.app.open_sidebar_file(notes.clone(), Modifiers::NONE);
+    assert_eq!(headless.pane_count(), 2);
+    assert!(matches!(headless.app.current_workspace().root, LayoutNode::Split { direction: SplitDirection::Vertical, .. }));
+    assert_eq!(viewed(&headless), Some(notes.clone()));
+
+    // The viewer is reused, from any pane of the tab
+    set_action(&mut headless, SidebarOpenAction::ReplaceCurrentFileViewer);
+    headless.app.current_workspace_mut().focus_prev();
+    headless.app.open_sidebar_file(todo.clone(), Modifiers::NONE);
+    assert_eq!(headless.pane_count(), 2);
+    assert_eq!(viewed(&headless), Some(todo.clone()));
+
+    // Cmd splits anyway; Alt goes to the editor
+    headless.app.open_sidebar_file(notes.clone(), Modifiers::COMMAND);
+    assert_eq!(headless.pane_count(), 3);
+    assert_eq!(viewed(&headless), Some(notes.clone()));
+    headless.app.config.edit(std::time::Instant::now(), |config| config.diagnostics.editor_command.clear());
+    headless.app.open_sidebar_file(todo, Modifiers::ALT);
+    assert_eq!(headless.pane_count(), 3);
+    assert_eq!(headless.app.toast.as_ref().map(|toast| toast.message.as_str()), Some("No editor command configured"));
+}
+
+#[test]
+fn test_ui_scale_keys_leave_egui_zoom_alone() {
+    let mut headless = Headless::new();
+    headless.press(Key::Plus, Modifiers::ALT);
+    assert_eq!(headless.app.config.font.ui_scale, 1.1);
+    headless.press(Key::Minus, Modifiers::ALT);
+    headless.press(Key::Minus, Modifiers::ALT);
+    assert_eq!(headless.app.config.font.ui_scale, 0.9);
+    assert_eq!(headless.ctx.zoom_factor(), 1.0);
+}
+
+#[test]
+fn test_directory_hooks_queue_for_consent() {
+    let mut headless = Headless::new();
+    let dir = tempfile::tempdir().unwrap();
+    let hook = |command: &str| crate::config::DirHook {
+        matches: format!("{}/**", dir.path().display()),
+        action: crate::config::DirHookAction::Command { command: command.to_string() },
+        ..Default::default()
+    };
+    let hooks = vec![hook("echo vibeterm-queued-1"), hook("echo vibeterm-queued-2")];
+    headless.app.config.edit(std::time::Instant::now(), |config| config.terminal.dir_hooks = hooks);
+    let ws = headless.app.current_workspace();
+    let terminal_id = ws.terminal_id(ws.focused_pane).unwrap();
+
+    // Both fire at once; the second waits for the first to be answered
+    headless.app.run_dir_hooks(terminal_id, &dir.path().join("src"));
+    assert_eq!(headless.app.dir_hook_prompts.len(), 2);
+    headless.press(Key::Escape, Modifiers::NONE);
+    let waiting: Vec<_> = headless.app.dir_hook_prompts.iter().map(|(_, hook)| hook.action.clone()).collect();
+    assert_eq!(waiting, [crate::config::DirHookAction::Command { command: "echo vibeterm-queued-2".to_string() }]);
+    headless.press(Key::Escape, Modifiers::NONE);
+    assert!(headless.app.dir_hook_prompts.is_empty());
+}
+
+#[test]
+fn test_progress_shows_on_the_tab_until_it_goes_stale() {
+    use crate::progress::{Progress, ProgressState, ProgressStatus, PROGRESS_TIMEOUT};
+    let mut headless = Headless::new();
+    headless.shortcut(Key::D);
+    let now = std::time::Instant::now();
+    let progress = |state, percent| Progress { state, percent };
+    let ws = headless.app.current_workspace_mut();
+    for (pane_id, status) in ws.pane_ids().into_iter().zip([
+        ProgressStatus { progress: progress(ProgressState::Normal, Some(40)), updated: now },
+        ProgressStatus { progress: progress(ProgressState::Error, Some(90)), updated: now - PROGRESS_TIMEOUT },
+    ]) {
+        let Some(TabContent::Terminal(terminal)) = ws.get_content_mut(pane_id) else {
+            panic!("pane is not a terminal");
+        };
+        terminal.progress = Some(status);
+    }
+
+    // The error stopped updating, so the one still going is what's shown
+    headless.run(Vec::new(), Modifiers::NONE);
+    let expected = Some(progress(ProgressState::Normal, Some(40)));
+    assert_eq!(headless.app.get_tabs()[0].progress, expected);
+    assert_eq!(headless.app.dock_progress, expected);
+}
+
+#[test]
+fn test_permanent_delete_waits_for_a_second_press() {
+    let mut headless = Headless::new();
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("notes.txt");
+    std::fs::write(&file, "x").unwrap();
+    headless.app.delete_confirm = Some(file.clone());
+    let enter = |pressed| Event::Key {
+        key: Key::Enter,
+        physical_key: None,
+        pressed,
+        repeat: false,
+        modifiers: Modifiers::NONE,
+    };
+
+    // The first Enter only arms the Delete button
+    headless.run(vec![enter(true)], Modifiers::NONE);
+    headless.run(vec![enter(false)], Modifiers::NONE);
+    assert!(file.exists());
+    assert_eq!(headless.app.delete_confirm.as_ref(), Some(&file));
+
+    headless.run(vec![enter(true)], Modifiers::NONE);
+    assert!(!file.exists());
+    assert!(headless.app.delete_confirm.is_none());
+}
@@ -0,0 +1,209 @@
+//! Git Blame for File Viewers
+//!
+//! Blames a file as shown (uncommitted edits included) on a background
+//! thread and groups its lines into runs last touched by the same commit,
+//! one label per run in the viewer's gutter.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use git2::{Oid, Repository};
+
+/// Commit that last touched a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameCommit {
+    /// Full hash (`None` for lines not committed yet)
+    pub id: Option<String>,
+    pub author: String,
+    pub time: SystemTime,
+    pub summary: String,
+}
+
+impl BlameCommit {
+    /// Gutter label: short author and age
+    pub fn label(&self, now: SystemTime) -> String {
+        if self.id.is_none() {
+            return "uncommitted".to_string();
+        }
+        let author: String = self.author.split_whitespace().next().unwrap_or("").chars().take(10).collect();
+        format!("{} {}", author, short_age(self.time, now))
+    }
+
+    /// Hover text: hash and summary
+    pub fn details(&self) -> String {
+        match &self.id {
+            Some(id) => format!("{}\n{} · {}\n\nClick to copy the hash", self.summary, self.author, id),
+            None => "Not committed yet".to_string(),
+        }
+    }
+}
+
+/// Consecutive lines last touched by one commit (0-based, end exclusive)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameRun {
+    pub start: usize,
+    pub end: usize,
+    pub commit: Arc<BlameCommit>,
+}
+
+/// Merge blame hunks of the same commit that follow each other
+///
+/// git splits a commit's lines into several hunks when they came from
+/// different places; the gutter labels the whole stretch once.
+pub fn coalesce(hunks: impl IntoIterator<Item = BlameRun>) -> Vec<BlameRun> {
+    let mut runs: Vec<BlameRun> = Vec::new();
+    for hunk in hunks {
+        if hunk.start >= hunk.end {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if run.end == hunk.start && run.commit.id == hunk.commit.id => run.end = hunk.end,
+            _ => runs.push(hunk),
+        }
+    }
+    runs
+}
+
+/// Blame job of a file viewer pane; dropping it cancels the work
+#[derive(Debug)]
+pub struct BlameJob {
+    pub request: u64,
+    cancel: Arc<AtomicBool>,
+}
+
+impl BlameJob {
+    pub fn new(request: u64) -> Self {
+        Self { request, cancel: Arc::new(AtomicBool::new(false)) }
+    }
+
+    /// Flag the worker checks between steps
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancel)
+    }
+}
+
+impl Drop for BlameJob {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Blame `content`, the shown text of `path`, against HEAD
+///
+/// Returns `Ok(None)` if cancelled.
+pub fn blame(path: &Path, content: &str, cancel: &AtomicBool) -> Result<Option<Vec<BlameRun>>, String> {
+    let dir = path.parent().ok_or_else(|| format!("No directory for {}", path.display()))?;
+    let repo = Repository::discover(dir).map_err(|_| "Not in a git repository".to_string())?;
+    let workdir = repo.workdir().ok_or_else(|| "Bare repository".to_string())?;
+    let path = path.canonicalize().map_err(|e| e.to_string())?;
+    let workdir = workdir.canonicalize().map_err(|e| e.to_string())?;
+    let relative = path.strip_prefix(&workdir).map_err(|_| "File is outside the repository".to_string())?;
+
+    let committed = repo.blame_file(relative, None).map_err(|e| e.message().to_string())?;
+    if cancel.load(Ordering::Relaxed) {
+        return Ok(None);
+    }
+    let blame = committed.blame_buffer(content.as_bytes()).map_err(|e| e.message().to_string())?;
+
+    let mut commits: HashMap<Oid, Arc<BlameCommit>> = HashMap::new();
+    let mut hunks = Vec::with_capacity(blame.len());
+    for hunk in blame.iter() {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        let oid = hunk.final_commit_id();
+        let commit = commits.entry(oid).or_insert_with(|| Arc::new(describe(&repo, oid, &hunk))).clone();
+        let start = hunk.final_start_line().saturating_sub(1);
+        hunks.push(BlameRun { start, end: start + hunk.lines_in_hunk(), commit });
+    }
+    Ok(Some(coalesce(hunks)))
+}
+
+/// Commit details of a hunk
+fn describe(repo: &Repository, oid: Oid, hunk: &git2::BlameHunk) -> BlameCommit {
+    let signature = hunk.final_signature();
+    let author = signature.name().unwrap_or("unknown").to_string();
+    let seconds = signature.when().seconds().max(0) as u64;
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+    if oid.is_zero() {
+        return BlameCommit { id: None, author, time, summary: String::new() };
+    }
+    let summary = repo
+        .find_commit(oid)
+        .ok()
+        .and_then(|commit| commit.summary().map(str::to_string))
+        .unwrap_or_default();
+    BlameCommit { id: Some(oid.to_string()), author, time, summary }
+}
+
+/// "3d", "5mo" style age
+fn short_age(time: SystemTime, now: SystemTime) -> String {
+    let secs = now.duration_since(time).map_or(0, |d| d.as_secs());
+    match secs {
+        0..3600 => format!("{}m", secs / 60),
+        3600..86_400 => format!("{}h", secs / 3600),
+        86_400..2_592_000 => format!("{}d", secs / 86_400),
+        2_592_000..31_536_000 => format!("{}mo", secs / 2_592_000),
+        _ => format!("{}y", secs / 31_536_000),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(id: &str) -> Arc<BlameCommit> {
+        Arc::new(BlameCommit {
+            id: Some(id.to_string()),
+            author: "Ada Lovelace".to_string(),
+            time: SystemTime::UNIX_EPOCH,
+            summary: String::new(),
+        })
+    }
+
+    fn hunk(start: usize, end: usize, commit: &Arc<BlameCommit>) -> BlameRun {
+        BlameRun { start, end, commit: Arc::clone(commit) }
+    }
+
+    #[test]
+    fn test_adjacent_hunks_of_a_commit_merge() {
+        let (a, b) = (commit("aaa"), commit("bbb"));
+        let runs = coalesce([hunk(0, 3, &a), hunk(3, 5, &a), hunk(5, 6, &b), hunk(6, 9, &a)]);
+        let spans: Vec<(usize, usize, &str)> = runs
+            .iter()
+            .map(|run| (run.start, run.end, run.commit.id.as_deref().unwrap()))
+            .collect();
+        assert_eq!(spans, [(0, 5, "aaa"), (5, 6, "bbb"), (6, 9, "aaa")]);
+    }
+
+    #[test]
+    fn test_gaps_and_empty_hunks_dont_merge() {
+        let a = commit("aaa");
+        let runs = coalesce([hunk(0, 2, &a), hunk(2, 2, &a), hunk(4, 6, &a)]);
+        assert_eq!(runs.len(), 2);
+        assert_eq!((runs[1].start, runs[1].end), (4, 6));
+        assert!(coalesce(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_uncommitted_lines_merge_with_each_other() {
+        let uncommitted = Arc::new(BlameCommit {
+            id: None,
+            author: "Not Committed Yet".to_string(),
+            time: SystemTime::UNIX_EPOCH,
+            summary: String::new(),
+        });
+        let other = Arc::new(BlameCommit::clone(&uncommitted));
+        let runs = coalesce([hunk(0, 1, &uncommitted), hunk(1, 4, &other)]);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].commit.label(SystemTime::UNIX_EPOCH), "uncommitted");
+    }
+
+    #[test]
+    fn test_label_abbreviates_author_and_age() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(3 * 86_400);
+        assert_eq!(commit("aaa").label(now), "Ada 3d");
+    }
+}
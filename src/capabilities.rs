@@ -0,0 +1,152 @@
+//! Capabilities
+//!
+//! What works on this machine, probed once at startup. Settings and
+//! palette commands that need something missing are shown disabled, with
+//! the reason, instead of silently doing nothing.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Something a feature needs from the platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Reading a shell's working directory, for directory tracking
+    CwdTracking,
+    /// A system font with Korean/Japanese/Chinese glyphs
+    CjkFont,
+    /// File system notifications
+    FileWatcher,
+    /// Drawing through OpenGL on the GPU
+    HardwareAcceleration,
+}
+
+impl Capability {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Capability::CwdTracking => "CWD tracking",
+            Capability::CjkFont => "CJK fallback",
+            Capability::FileWatcher => "File watching",
+            Capability::HardwareAcceleration => "Hardware acceleration",
+        }
+    }
+}
+
+/// Why reading another process's directory fails on this platform
+#[cfg(target_os = "macos")]
+const CWD_DENIED: &str = "libproc access denied";
+#[cfg(target_os = "linux")]
+const CWD_DENIED: &str = "/proc is not readable";
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+const CWD_DENIED: &str = "not supported on this platform";
+
+/// Probe results
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    /// Operating system, as in `std::env::consts::OS`
+    pub platform: &'static str,
+    /// Missing capabilities and why
+    missing: HashMap<Capability, String>,
+}
+
+impl Capabilities {
+    /// Everything available, as assumed before probing and in tests
+    pub fn all() -> Self {
+        Self { platform: std::env::consts::OS, missing: HashMap::new() }
+    }
+
+    /// Probe this machine
+    ///
+    /// `watcher_error` is why the file watcher didn't start, if it didn't.
+    pub fn probe(watcher_error: Option<&str>, hardware_acceleration: bool) -> Self {
+        let mut capabilities = Self::all();
+        capabilities.note(Capability::CwdTracking, probe_cwd_tracking(crate::pty_tracker::get_process_cwd));
+        capabilities.note(Capability::CjkFont, probe_cjk_font(crate::theme::CJK_FONT_PATHS, Path::exists));
+        capabilities.note(Capability::FileWatcher, match watcher_error {
+            Some(e) => Err(e.to_string()),
+            None => Ok(()),
+        });
+        capabilities.note(Capability::HardwareAcceleration, if hardware_acceleration {
+            Ok(())
+        } else {
+            Err("no OpenGL context".to_string())
+        });
+        for (capability, reason) in &capabilities.missing {
+            log::info!("{} unavailable: {}", capability.label(), reason);
+        }
+        capabilities
+    }
+
+    /// Record the result of probing `capability`
+    pub fn note(&mut self, capability: Capability, result: Result<(), String>) {
+        match result {
+            Ok(()) => self.missing.remove(&capability),
+            Err(reason) => self.missing.insert(capability, reason),
+        };
+    }
+
+    pub fn is_available(&self, capability: Capability) -> bool {
+        !self.missing.contains_key(&capability)
+    }
+
+    /// "CWD tracking unavailable: libproc access denied", for tooltips
+    pub fn unavailable_reason(&self, capability: Capability) -> Option<String> {
+        self.missing
+            .get(&capability)
+            .map(|reason| format!("{} unavailable: {}", capability.label(), reason))
+    }
+}
+
+/// Can `cwd_of` read the directory of this very process?
+pub fn probe_cwd_tracking(cwd_of: impl Fn(u32) -> Option<PathBuf>) -> Result<(), String> {
+    match cwd_of(std::process::id()) {
+        Some(_) => Ok(()),
+        None => Err(CWD_DENIED.to_string()),
+    }
+}
+
+/// Is there a CJK font at one of `paths`?
+pub fn probe_cjk_font(paths: &[&str], exists: impl Fn(&Path) -> bool) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err("no known CJK font location on this platform".to_string());
+    }
+    if paths.iter().any(|path| exists(Path::new(path))) {
+        Ok(())
+    } else {
+        Err("no system CJK font found".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_probes_report_why() {
+        assert_eq!(probe_cwd_tracking(|_| Some(PathBuf::from("/"))), Ok(()));
+        assert_eq!(probe_cwd_tracking(|_| None), Err(CWD_DENIED.to_string()));
+
+        let paths = ["/fonts/a.ttc", "/fonts/b.ttc"];
+        assert_eq!(probe_cjk_font(&paths, |path| path == Path::new("/fonts/b.ttc")), Ok(()));
+        assert_eq!(probe_cjk_font(&paths, |_| false), Err("no system CJK font found".to_string()));
+        assert!(probe_cjk_font(&[], |_| true).is_err());
+    }
+
+    #[test]
+    fn test_missing_capabilities_give_a_reason() {
+        let mut capabilities = Capabilities::all();
+        assert!(capabilities.is_available(Capability::CwdTracking));
+        assert_eq!(capabilities.unavailable_reason(Capability::CwdTracking), None);
+
+        capabilities.note(Capability::CwdTracking, probe_cwd_tracking(|_| None));
+        assert!(!capabilities.is_available(Capability::CwdTracking));
+        assert_eq!(
+            capabilities.unavailable_reason(Capability::CwdTracking),
+            Some(format!("CWD tracking unavailable: {}", CWD_DENIED)),
+        );
+        assert!(capabilities.is_available(Capability::FileWatcher));
+
+        // A later probe that works clears it
+        capabilities.note(Capability::CwdTracking, Ok(()));
+        assert!(capabilities.is_available(Capability::CwdTracking));
+    }
+}
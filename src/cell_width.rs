@@ -0,0 +1,113 @@
+//! Terminal Cell Widths
+//!
+//! Grid columns taken by text, for VibeTerm's own column math. The grid
+//! comes from alacritty_terminal, which lays characters out with
+//! unicode-width's narrow widths and has no option for ambiguous ones, so
+//! `Narrow` matches it exactly; `Wide` counts East Asian ambiguous
+//! characters as two columns, as CJK locales and fonts draw them. Grids
+//! VibeTerm paints itself draw those over two cells where the program
+//! left room (see [`painted_columns`]).
+//!
+//! A variation selector never changes the width of the character before
+//! it, as in the grid: emoji that are wide by default stay two columns
+//! with U+FE0F, text-default symbols like ❤ stay one.
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::config::AmbiguousWidth;
+
+/// Columns taken by `c` (0 for combining marks, selectors and controls)
+pub fn char_width(c: char, ambiguous: AmbiguousWidth) -> usize {
+    let width = match ambiguous {
+        AmbiguousWidth::Narrow => c.width(),
+        AmbiguousWidth::Wide => c.width_cjk(),
+    };
+    width.unwrap_or(0)
+}
+
+/// Columns taken by `text`
+pub fn str_width(text: &str, ambiguous: AmbiguousWidth) -> usize {
+    text.chars().map(|c| char_width(c, ambiguous)).sum()
+}
+
+/// Grid cells to paint a cell holding `c` across
+///
+/// The grid puts an ambiguous character in one cell. A program counting it
+/// as two columns leaves the cell after it blank, so with `Wide` it's drawn
+/// over that one too; without the room it stays in its cell rather than
+/// pushing the rest of the row over. `wide_cell` is the grid's own double
+/// width (CJK, emoji).
+pub fn painted_columns(c: char, wide_cell: bool, next_blank: bool, ambiguous: AmbiguousWidth) -> usize {
+    if wide_cell || (next_blank && char_width(c, ambiguous) == 2) {
+        2
+    } else {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AmbiguousWidth::{Narrow, Wide};
+
+    #[test]
+    fn test_char_widths() {
+        // (character, narrow, wide)
+        let table = [
+            ('a', 1, 1),
+            ('─', 1, 2),
+            ('±', 1, 2),
+            ('§', 1, 2),
+            ('×', 1, 2),
+            ('①', 1, 2),
+            ('→', 1, 2),
+            ('…', 1, 2),
+            ('한', 2, 2),
+            ('日', 2, 2),
+            ('Ａ', 2, 2),
+            ('😀', 2, 2),
+            // Private use (Powerline, Nerd Font icons) is ambiguous too
+            ('\u{e0b0}', 1, 2),
+            ('\u{301}', 0, 0),
+            ('\u{FE0F}', 0, 0),
+            ('\u{200D}', 0, 0),
+            ('\t', 0, 0),
+        ];
+        for (c, narrow, wide) in table {
+            assert_eq!(char_width(c, Narrow), narrow, "{:?} narrow", c);
+            assert_eq!(char_width(c, Wide), wide, "{:?} wide", c);
+        }
+    }
+
+    #[test]
+    fn test_emoji_with_variation_selectors() {
+        for mode in [Narrow, Wide] {
+            assert_eq!(str_width("😀\u{FE0F}", mode), 2);
+            assert_eq!(str_width("🚀\u{FE0E}", mode), 2);
+            assert_eq!(str_width("\u{2764}\u{FE0F}", mode), 1);
+            assert_eq!(str_width("e\u{301}", mode), 1);
+        }
+    }
+
+    #[test]
+    fn test_str_width() {
+        assert_eq!(str_width("", Narrow), 0);
+        assert_eq!(str_width("src/한글", Narrow), 8);
+        assert_eq!(str_width("±5 ①", Narrow), 4);
+        assert_eq!(str_width("±5 ①", Wide), 6);
+    }
+
+    #[test]
+    fn test_painted_columns() {
+        // Ambiguous characters spread over a blank neighbour only in wide mode
+        assert_eq!(painted_columns('±', false, true, Narrow), 1);
+        assert_eq!(painted_columns('±', false, true, Wide), 2);
+        assert_eq!(painted_columns('±', false, false, Wide), 1);
+        assert_eq!(painted_columns('a', false, true, Wide), 1);
+        // The grid's wide cells are two columns either way
+        for mode in [Narrow, Wide] {
+            assert_eq!(painted_columns('한', true, false, mode), 2);
+            assert_eq!(painted_columns('😀', true, true, mode), 2);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Clipboard Paste
+//!
+//! Reading the clipboard blocks until its owner answers, which a remote
+//! desktop or some Electron apps take their time with. Cmd+V reads it on
+//! a blocking task instead, saves a pasted image there too, and hands the
+//! result back to the frame loop, which types it into the terminal the
+//! paste was started in if that is still open.
+
+use std::time::{Duration, Instant};
+use arboard::Clipboard;
+use crate::config::PasteImageConfig;
+
+/// How long the clipboard owner gets to answer
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Pastes slower than this show a spinner on their pane
+pub const SPINNER_DELAY: Duration = Duration::from_millis(150);
+
+/// What a paste types
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PastePayload {
+    Text(String),
+    /// Marker of a saved image, by default `[image: path]`
+    Image(String),
+    /// Nothing to paste
+    Empty,
+    Failed(String),
+}
+
+/// A paste whose clipboard read finished
+#[derive(Debug)]
+pub struct PasteReady {
+    pub request: u64,
+    pub payload: PastePayload,
+}
+
+/// A paste waiting for the clipboard
+#[derive(Debug)]
+struct Pending {
+    request: u64,
+    /// Terminal it goes to (pane IDs repeat across tabs; terminal IDs don't)
+    terminal_id: u64,
+    since: Instant,
+}
+
+/// Pastes waiting for the clipboard
+#[derive(Debug, Default)]
+pub struct PasteQueue {
+    next_request: u64,
+    pending: Vec<Pending>,
+}
+
+impl PasteQueue {
+    /// Start a paste into `terminal_id`; returns its request number
+    pub fn start(&mut self, terminal_id: u64, now: Instant) -> u64 {
+        let request = self.next_request;
+        self.next_request += 1;
+        self.pending.push(Pending { request, terminal_id, since: now });
+        request
+    }
+
+    /// Terminal a finished paste goes to; `None` if it isn't pending
+    pub fn finish(&mut self, request: u64) -> Option<u64> {
+        let index = self.pending.iter().position(|pending| pending.request == request)?;
+        Some(self.pending.remove(index).terminal_id)
+    }
+
+    /// Has a paste into `terminal_id` been waiting long enough to show it?
+    pub fn is_slow(&self, terminal_id: u64, now: Instant) -> bool {
+        self.pending
+            .iter()
+            .any(|pending| pending.terminal_id == terminal_id && now.duration_since(pending.since) >= SPINNER_DELAY)
+    }
+}
+
+/// Read the clipboard: an image, saved with `settings`, before text
+pub fn read(settings: &PasteImageConfig) -> PastePayload {
+    let mut clipboard = match Clipboard::new() {
+        Ok(clipboard) => clipboard,
+        Err(e) => return PastePayload::Failed(format!("Failed to access clipboard: {}", e)),
+    };
+    if let Ok(data) = clipboard.get_image() {
+        let Some(image) = image::RgbaImage::from_raw(data.width as u32, data.height as u32, data.bytes.into_owned()) else {
+            return PastePayload::Failed("Failed to convert clipboard image".to_string());
+        };
+        return match crate::paste_image::save(&image, settings) {
+            Ok(path) => {
+                log::info!("Image saved to {}", path.display());
+                PastePayload::Image(crate::paste_image::expand_marker(&settings.marker_template, &path))
+            }
+            Err(e) => PastePayload::Failed(e),
+        };
+    }
+    match clipboard.get_text() {
+        Ok(text) => PastePayload::Text(text),
+        Err(_) => PastePayload::Empty,
+    }
+}
+
+/// Run `read` on its own thread, giving up on it after `timeout`
+pub fn read_with_timeout(read: impl FnOnce() -> PastePayload + Send + 'static, timeout: Duration) -> PastePayload {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(read());
+    });
+    rx.recv_timeout(timeout).unwrap_or_else(|_| {
+        PastePayload::Failed(format!("The clipboard didn't answer within {} ms", timeout.as_millis()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finished_pastes_go_to_their_terminal() {
+        let start = Instant::now();
+        let mut queue = PasteQueue::default();
+        let first = queue.start(7, start);
+        let second = queue.start(9, start);
+        assert_ne!(first, second);
+
+        // Out of order, each to its own terminal, once
+        assert_eq!(queue.finish(second), Some(9));
+        assert_eq!(queue.finish(second), None);
+        assert_eq!(queue.finish(first), Some(7));
+        assert_eq!(queue.finish(42), None);
+    }
+
+    #[test]
+    fn test_spinner_waits_for_slow_pastes() {
+        let start = Instant::now();
+        let mut queue = PasteQueue::default();
+        let request = queue.start(7, start);
+        assert!(!queue.is_slow(7, start + Duration::from_millis(100)));
+        assert!(queue.is_slow(7, start + SPINNER_DELAY));
+        assert!(!queue.is_slow(8, start + SPINNER_DELAY));
+        queue.finish(request);
+        assert!(!queue.is_slow(7, start + SPINNER_DELAY));
+    }
+
+    #[test]
+    fn test_slow_clipboard_times_out() {
+        let fast = read_with_timeout(|| PastePayload::Text("hi".to_string()), Duration::from_secs(5));
+        assert_eq!(fast, PastePayload::Text("hi".to_string()));
+
+        let slow = read_with_timeout(
+            || {
+                std::thread::sleep(Duration::from_millis(500));
+                PastePayload::Empty
+            },
+            Duration::from_millis(20),
+        );
+        assert_eq!(slow, PastePayload::Failed("The clipboard didn't answer within 20 ms".to_string()));
+    }
+}
@@ -0,0 +1,212 @@
+//! Terminal Color Test
+//!
+//! What the Color Test tab shows: the 16 ANSI colors, the 256-color cube,
+//! a truecolor gradient, text attributes and box-drawing rows. The tab
+//! paints them in-process through the terminal theme; [`script`] prints
+//! the same thing with `printf` to check a real PTY.
+
+use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::vte::ansi::{Color, NamedColor, Rgb};
+
+/// Cells in the truecolor gradient
+pub const GRADIENT_WIDTH: usize = 72;
+
+/// Text shown on each foreground/background pair
+const SAMPLE: &str = " gYw ";
+
+/// Attribute samples and their SGR codes
+const ATTRIBUTES: [(&str, Flags, u8); 6] = [
+    ("bold", Flags::BOLD, 1),
+    ("dim", Flags::DIM, 2),
+    ("italic", Flags::ITALIC, 3),
+    ("underline", Flags::UNDERLINE, 4),
+    ("strikethrough", Flags::STRIKEOUT, 9),
+    ("inverse", Flags::INVERSE, 7),
+];
+
+/// Box-drawing rows, wide characters included, to check alignment
+const BOX_ROWS: [&str; 7] = [
+    "┌────┬────┐ ╔════╦════╗ ╭────╮",
+    "│ ab │ 中 │ ║ ab ║ 文 ║ │ ▀▄ │",
+    "├────┼────┤ ╠════╬════╣ │ █▌ │",
+    "│ ░▒ │ ▓█ │ ║ ┼┼ ║ ╳╳ ║ │ ⠿⣿ │",
+    "└────┴────┘ ╚════╩════╝ ╰────╯",
+    "▁▂▃▄▅▆▇█▇▆▅▄▃▂▁ ◢◣◤◥ ●○◆◇",
+    "0123456789012345678901234567890",
+];
+
+/// A run of text in one style
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub fg: Color,
+    pub bg: Color,
+    pub flags: Flags,
+}
+
+impl Span {
+    fn new(text: impl Into<String>, fg: Color, bg: Color) -> Self {
+        Self { text: text.into(), fg, bg, flags: Flags::empty() }
+    }
+
+    fn plain(text: impl Into<String>) -> Self {
+        Self::new(text, Color::Named(NamedColor::Foreground), Color::Named(NamedColor::Background))
+    }
+
+    fn background(text: impl Into<String>, bg: Color) -> Self {
+        Self::new(text, Color::Named(NamedColor::Foreground), bg)
+    }
+}
+
+/// A titled block of rows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub title: &'static str,
+    pub rows: Vec<Vec<Span>>,
+}
+
+/// Color of cell `i` of the truecolor gradient: red through green to blue
+pub fn gradient(i: usize) -> Rgb {
+    let i = i.min(GRADIENT_WIDTH) as i32;
+    let width = GRADIENT_WIDTH as i32;
+    let r = 255 - i * 255 / width;
+    let g = i * 510 / width;
+    let g = if g > 255 { 510 - g } else { g };
+    let b = i * 255 / width;
+    Rgb { r: r as u8, g: g as u8, b: b as u8 }
+}
+
+/// Everything the Color Test tab shows
+pub fn preview() -> Vec<Section> {
+    // Rows are backgrounds (default, then 40–47); columns foregrounds (default, 30–37, 90–97)
+    let backgrounds = std::iter::once((String::from("  "), Color::Named(NamedColor::Background)))
+        .chain((0..8).map(|i| (format!("{}", 40 + i), Color::Indexed(i))));
+    let ansi = backgrounds
+        .map(|(label, bg)| {
+            let mut row = vec![Span::plain(format!("{:>3} ", label))];
+            row.push(Span::new(SAMPLE, Color::Named(NamedColor::Foreground), bg));
+            row.extend((0..16).map(|i| Span::new(SAMPLE, Color::Indexed(i), bg)));
+            row
+        })
+        .collect();
+
+    let swatches = (0..2)
+        .map(|half| {
+            (0..8)
+                .map(|i| {
+                    let index = half * 8 + i;
+                    Span::background(format!(" {:>2}  ", index), Color::Indexed(index))
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut cube: Vec<Vec<Span>> = (0..6)
+        .map(|r| (0..36).map(|i| Span::background("  ", Color::Indexed(16 + r * 36 + i))).collect())
+        .collect();
+    cube.push((232..=255).map(|i| Span::background("   ", Color::Indexed(i))).collect());
+
+    let truecolor = vec![(0..GRADIENT_WIDTH)
+        .map(|i| Span::background(" ", Color::Spec(gradient(i))))
+        .collect()];
+
+    let mut attributes = vec![Span::plain("normal")];
+    for (name, flags, _) in ATTRIBUTES {
+        attributes.push(Span::plain(" "));
+        attributes.push(Span { flags, ..Span::plain(name) });
+    }
+
+    vec![
+        Section { title: "16 ANSI colors", rows: ansi },
+        Section { title: "Palette", rows: swatches },
+        Section { title: "256-color cube and grays", rows: cube },
+        Section { title: "Truecolor", rows: truecolor },
+        Section { title: "Attributes", rows: vec![attributes] },
+        Section { title: "Box drawing", rows: BOX_ROWS.iter().map(|row| vec![Span::plain(*row)]).collect() },
+    ]
+}
+
+/// Shell command printing the same preview with `printf`
+///
+/// The script only uses double quotes, so it runs under `sh -c '…'` from
+/// any shell the user has.
+pub fn script() -> String {
+    let attributes: String = ATTRIBUTES
+        .iter()
+        .map(|(name, _, code)| format!(" \\033[{}m{}\\033[0m", code, name))
+        .collect();
+    let boxes: String = BOX_ROWS.iter().map(|row| format!(" \"{}\"", row)).collect();
+    let width = GRADIENT_WIDTH;
+
+    let lines = [
+        "printf \"\\n16 ANSI colors\\n\"".to_string(),
+        format!(
+            "for bg in 49 40 41 42 43 44 45 46 47; do printf \"%3s \" $bg; \
+             for fg in 39 30 31 32 33 34 35 36 37 90 91 92 93 94 95 96 97; do \
+             printf \"\\033[%s;%sm{}\\033[0m\" $fg $bg; done; printf \"\\n\"; done",
+            SAMPLE
+        ),
+        "printf \"\\nPalette\\n\"".to_string(),
+        "i=0; while [ $i -lt 16 ]; do printf \"\\033[48;5;%dm %2d  \\033[0m\" $i $i; i=$((i+1)); \
+         [ $i -eq 8 ] && printf \"\\n\"; done; printf \"\\n\"".to_string(),
+        "printf \"\\n256-color cube and grays\\n\"".to_string(),
+        "r=0; while [ $r -lt 6 ]; do i=0; while [ $i -lt 36 ]; do \
+         printf \"\\033[48;5;%dm  \" $((16 + r*36 + i)); i=$((i+1)); done; \
+         printf \"\\033[0m\\n\"; r=$((r+1)); done".to_string(),
+        "i=232; while [ $i -le 255 ]; do printf \"\\033[48;5;%dm   \" $i; i=$((i+1)); done; printf \"\\033[0m\\n\"".to_string(),
+        "printf \"\\nTruecolor\\n\"".to_string(),
+        format!(
+            "i=0; while [ $i -lt {w} ]; do r=$((255 - i*255/{w})); g=$((i*510/{w})); \
+             [ $g -gt 255 ] && g=$((510 - g)); b=$((i*255/{w})); \
+             printf \"\\033[48;2;%d;%d;%dm \" $r $g $b; i=$((i+1)); done; printf \"\\033[0m\\n\"",
+            w = width
+        ),
+        "printf \"\\nAttributes\\n\"".to_string(),
+        format!("printf \"normal{}\\n\"", attributes),
+        "printf \"\\nBox drawing\\n\"".to_string(),
+        format!("printf \"%s\\n\"{}", boxes),
+    ];
+    format!("sh -c '{}'\n", lines.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_covers_every_palette_index() {
+        let sections = preview();
+        let mut indexed: Vec<u8> = sections
+            .iter()
+            .filter(|section| section.title != "16 ANSI colors")
+            .flat_map(|section| section.rows.iter().flatten())
+            .filter_map(|span| match span.bg {
+                Color::Indexed(index) => Some(index),
+                _ => None,
+            })
+            .collect();
+        indexed.sort_unstable();
+        assert_eq!(indexed, (0..=255).collect::<Vec<u8>>());
+
+        // Each ANSI row: label, default foreground, then the 16 colors
+        let ansi = &sections[0].rows;
+        assert_eq!(ansi.len(), 9);
+        assert!(ansi.iter().all(|row| row.len() == 18));
+    }
+
+    #[test]
+    fn test_gradient_runs_red_to_blue() {
+        assert_eq!(gradient(0), Rgb { r: 255, g: 0, b: 0 });
+        assert_eq!(gradient(GRADIENT_WIDTH / 2), Rgb { r: 128, g: 255, b: 127 });
+        assert_eq!(gradient(GRADIENT_WIDTH), Rgb { r: 0, g: 0, b: 255 });
+    }
+
+    #[test]
+    fn test_script_is_one_single_quoted_command() {
+        let script = script();
+        let body = script.strip_prefix("sh -c '").and_then(|rest| rest.strip_suffix("'\n")).unwrap();
+        assert!(!body.contains('\'') && !body.contains('\n'));
+        assert!(body.contains(&format!("-lt {}", GRADIENT_WIDTH)));
+        assert!(body.contains("\\033[9mstrikethrough"));
+    }
+}
@@ -0,0 +1,160 @@
+//! Command History
+//!
+//! Commands typed at a shell prompt in any pane, for the history peek
+//! overlay. A command is recorded when Enter submits it; prompts are
+//! recognized by the Copy Last Output prompt pattern. Commands that were
+//! only found in a pane's scrollback (run before VibeTerm was watching, or
+//! restored with the session) have no time. Session-only.
+
+use alacritty_terminal::grid::{Dimensions, Grid};
+use alacritty_terminal::index::Line;
+use alacritty_terminal::term::cell::{Cell, Flags};
+use regex::Regex;
+use std::time::SystemTime;
+
+/// Commands kept
+pub const MAX_COMMANDS: usize = 500;
+
+/// One submitted command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandEntry {
+    pub command: String,
+    /// Terminal it was typed in
+    pub terminal_id: u64,
+    /// Pane it was typed in
+    pub source: String,
+    /// When it was submitted; `None` when only found in scrollback
+    pub at: Option<SystemTime>,
+}
+
+/// Command typed after the prompt on `line`, if it has one
+pub fn command_on_line(line: &str, prompt: &Regex) -> Option<String> {
+    let found = prompt.find(line)?;
+    let command = line[found.end()..].trim();
+    (!command.is_empty()).then(|| command.to_string())
+}
+
+/// Commands on the prompt lines of `lines`, oldest first
+///
+/// The last line is the cursor's, where the next command is being typed.
+pub fn scan(lines: &[String], prompt: &Regex) -> Vec<String> {
+    lines[..lines.len().saturating_sub(1)]
+        .iter()
+        .filter_map(|line| command_on_line(line, prompt))
+        .collect()
+}
+
+/// Text of the cursor's line, including rows it wrapped from
+pub fn cursor_line(grid: &Grid<Cell>) -> String {
+    let top = -(grid.history_size() as i32);
+    let mut start = grid.cursor.point.line.0;
+    while start > top && grid[Line(start - 1)][grid.last_column()].flags.contains(Flags::WRAPLINE) {
+        start -= 1;
+    }
+    crate::session_log::text_since(grid, Line(start)).trim_end_matches('\n').to_string()
+}
+
+/// Submitted commands, newest first
+#[derive(Default)]
+pub struct CommandHistory {
+    entries: Vec<CommandEntry>,
+}
+
+impl CommandHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a command; running it again in the same terminal moves it to the top
+    pub fn record(&mut self, entry: CommandEntry) {
+        self.entries
+            .retain(|old| old.terminal_id != entry.terminal_id || old.command != entry.command);
+        self.entries.insert(0, entry);
+        self.entries.truncate(MAX_COMMANDS);
+    }
+
+    /// Recorded commands followed by those only found in scrollback
+    ///
+    /// `scrollback` holds each terminal's scanned commands, newest first;
+    /// ones already recorded for that terminal are left out.
+    pub fn with_scrollback(&self, scrollback: Vec<CommandEntry>) -> Vec<CommandEntry> {
+        let mut entries = self.entries.clone();
+        for entry in scrollback {
+            let known = entries
+                .iter()
+                .any(|old| old.terminal_id == entry.terminal_id && old.command == entry.command);
+            if !known {
+                entries.push(entry);
+            }
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::term::test::TermSize;
+    use alacritty_terminal::term::{Config, Term};
+    use alacritty_terminal::vte::ansi::Processor;
+
+    fn prompt() -> Regex {
+        Regex::new(&crate::config::CopyLastOutputConfig::default().prompt_pattern).unwrap()
+    }
+
+    fn entry(command: &str, terminal_id: u64, at: Option<SystemTime>) -> CommandEntry {
+        CommandEntry { command: command.to_string(), terminal_id, source: format!("pane {}", terminal_id), at }
+    }
+
+    #[test]
+    fn test_commands_on_prompt_lines() {
+        let prompt = prompt();
+        assert_eq!(command_on_line("user@host:~/p$ cargo test  ", &prompt).as_deref(), Some("cargo test"));
+        assert_eq!(command_on_line("~/p ❯ git status", &prompt).as_deref(), Some("git status"));
+        assert_eq!(command_on_line("~/p $", &prompt), None);
+        assert_eq!(command_on_line("error: boom", &prompt), None);
+
+        let lines: Vec<String> = ["~/p $ ls", "a.txt", "~/p $", "~/p $ make", "done", "~/p $ half-typ"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(scan(&lines, &prompt), ["ls", "make"]);
+        assert!(scan(&[], &prompt).is_empty());
+    }
+
+    #[test]
+    fn test_cursor_line_joins_wrapped_rows() {
+        let mut term = Term::new(Config::default(), &TermSize::new(10, 4), VoidListener);
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut term, b"out\r\n$ echo 0123456789");
+        assert_eq!(cursor_line(term.grid()), "$ echo 0123456789");
+    }
+
+    #[test]
+    fn test_record_moves_repeats_to_top() {
+        let mut history = CommandHistory::new();
+        let at = Some(SystemTime::UNIX_EPOCH);
+        history.record(entry("ls", 1, at));
+        history.record(entry("make", 1, at));
+        history.record(entry("ls", 2, at));
+        history.record(entry("ls", 1, at));
+        let commands: Vec<(&str, u64)> = history.entries.iter().map(|e| (e.command.as_str(), e.terminal_id)).collect();
+        assert_eq!(commands, [("ls", 1), ("ls", 2), ("make", 1)]);
+
+        for n in 0..MAX_COMMANDS + 10 {
+            history.record(entry(&format!("echo {}", n), 1, at));
+        }
+        assert_eq!(history.entries.len(), MAX_COMMANDS);
+        assert_eq!(history.entries[0].command, format!("echo {}", MAX_COMMANDS + 9));
+    }
+
+    #[test]
+    fn test_scrollback_fills_in_unrecorded_commands() {
+        let mut history = CommandHistory::new();
+        history.record(entry("make", 1, Some(SystemTime::UNIX_EPOCH)));
+        let merged = history.with_scrollback(vec![entry("make", 1, None), entry("ls", 1, None), entry("make", 2, None)]);
+        let commands: Vec<(&str, u64, bool)> = merged.iter().map(|e| (e.command.as_str(), e.terminal_id, e.at.is_some())).collect();
+        assert_eq!(commands, [("make", 1, true), ("ls", 1, false), ("make", 2, false)]);
+    }
+}
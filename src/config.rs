@@ -4,7 +4,9 @@
 
 use egui::Color32;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use crate::context::ContextConfig;
 
 /// Main configuration
@@ -14,7 +16,14 @@ pub struct Config {
     pub theme: ThemeConfig,
     pub font: FontConfig,
     pub ui: UiConfig,
+    pub terminal: TerminalConfig,
+    pub file_viewer: FileViewerConfig,
+    pub diagnostics: DiagnosticsConfig,
+    pub search: SearchConfig,
     pub context: ContextConfig,
+    pub advanced: AdvancedConfig,
+    /// Extra hosts for "Connect to Host…", next to those of `~/.ssh/config`
+    pub ssh_hosts: Vec<SshHostConfig>,
 }
 
 impl Default for Config {
@@ -23,7 +32,13 @@ impl Default for Config {
             theme: ThemeConfig::default(),
             font: FontConfig::default(),
             ui: UiConfig::default(),
+            terminal: TerminalConfig::default(),
+            file_viewer: FileViewerConfig::default(),
+            diagnostics: DiagnosticsConfig::default(),
+            search: SearchConfig::default(),
             context: ContextConfig::default(),
+            advanced: AdvancedConfig::default(),
+            ssh_hosts: Vec::new(),
         }
     }
 }
@@ -113,6 +128,13 @@ pub struct FontConfig {
     pub terminal_size: f32,
     /// Font size for UI
     pub ui_size: f32,
+    /// Search well-known system paths for a CJK font to fall back to
+    pub cjk_fallback: bool,
+    /// Font file searched after the built-in fonts (e.g. a Nerd Font),
+    /// empty for none
+    pub fallback_font: String,
+    /// Scale of sidebar, tab, status bar and pane label text (not the terminal)
+    pub ui_scale: f32,
 }
 
 impl Default for FontConfig {
@@ -120,10 +142,28 @@ impl Default for FontConfig {
         Self {
             terminal_size: 14.0,
             ui_size: 12.0,
+            cjk_fallback: true,
+            fallback_font: String::new(),
+            ui_scale: 1.0,
         }
     }
 }
 
+impl FontConfig {
+    /// UI scales Preferences and Cmd+Alt+Plus/Minus offer
+    pub const UI_SCALES: RangeInclusive<f32> = 0.8..=1.6;
+
+    /// Do both configs load the same font files?
+    pub fn same_faces(&self, other: &Self) -> bool {
+        self.cjk_fallback == other.cjk_fallback && self.fallback_font == other.fallback_font
+    }
+
+    /// Pull a hand-edited UI scale back into the range Preferences offers
+    pub fn clamp_ui_scale(&mut self) {
+        self.ui_scale = clamp_or(self.ui_scale, Self::UI_SCALES, Self::default().ui_scale);
+    }
+}
+
 /// UI layout configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -136,6 +176,8 @@ pub struct UiConfig {
     pub status_bar_height: f32,
     /// Show sidebar by default
     pub show_sidebar: bool,
+    /// Whether the sidebar takes its own space or slides out over the panes
+    pub sidebar_mode: SidebarMode,
     /// Enable CWD polling for terminal tracking (disable for performance)
     pub enable_cwd_polling: bool,
     /// Show hidden files in file tree
@@ -146,6 +188,225 @@ pub struct UiConfig {
     pub max_depth: usize,
     /// Patterns to ignore in file tree (e.g., ".git", "target")
     pub file_tree_ignore_patterns: Vec<String>,
+    /// Delay before a pane resize is sent to the PTY (milliseconds)
+    pub resize_debounce_ms: u64,
+    /// Delay between terminals when running a command in all panes (milliseconds)
+    pub run_all_stagger_ms: u64,
+    /// Icons shown for files in the file tree
+    pub file_icons: FileIconStyle,
+    /// How far unfocused terminals' colors fade toward the background (0 disables)
+    pub inactive_pane_desaturation: f32,
+    /// Launching again opens a tab in the running window instead of a new app
+    pub single_instance: bool,
+    /// Below this window width the sidebar is hidden (px)
+    pub sidebar_auto_hide_width: f32,
+    /// Below this window width tabs show only their numbers (px)
+    pub compact_tab_bar_width: f32,
+    /// Below this window width optional status bar segments are hidden (px)
+    pub compact_status_bar_width: f32,
+    /// Splits that would make a pane narrower than this are refused (px)
+    pub min_pane_width: f32,
+    /// Splits that would make a pane shorter than this are refused (px)
+    pub min_pane_height: f32,
+    /// Splits that would nest a pane deeper than this are refused
+    pub max_split_depth: usize,
+    /// Added to the terminal font size in presentation mode (pt)
+    pub presentation_font_bump: f32,
+    /// Presentation mode also makes the window fullscreen
+    pub presentation_fullscreen: bool,
+    /// Show the focused terminal's directory as a path bar above the panes
+    pub show_breadcrumbs: bool,
+    /// Red border and badge on panes running as root (needs directory tracking)
+    pub highlight_root_panes: bool,
+    /// Strip under each tab showing how recently its terminals printed output
+    pub show_activity_strip: bool,
+    /// Dangerous palette commands need a second Enter (or a held one)
+    pub confirm_dangerous_commands: bool,
+    /// Ask before quitting while jobs run, logs are open or background work is unfinished
+    pub confirm_quit: bool,
+    /// The sidebar follows the focused pane into directories it cds to
+    pub sidebar_follows_cd: bool,
+    /// A pane's directory must be unchanged this long before the sidebar follows it (milliseconds)
+    pub root_switch_dwell_ms: u64,
+    /// Minimum time between two sidebar root switches in a workspace (milliseconds)
+    pub root_switch_interval_ms: u64,
+    /// Font size of the pane mirror window (pt)
+    pub mirror_font_size: f32,
+    /// Daily Do Not Disturb hours, e.g. "22:00-08:00" (empty for none)
+    pub dnd_schedule: String,
+    /// Folders opened together past this many open as dormant tabs, started when shown (0 for never)
+    pub dormant_tab_batch: usize,
+    /// Dormant tabs started anyway once the app is idle, newest first
+    pub dormant_wake_count: usize,
+    /// How long the app must be idle before those start (milliseconds)
+    pub dormant_wake_delay_ms: u64,
+    /// Where new tabs open
+    pub new_tab_position: NewTabPosition,
+    /// Status bar segments in the order drawn; leave one out to hide it
+    pub status_segments: Vec<StatusSegment>,
+    /// Reopen the tabs and splits of the last run at launch
+    pub restore_session: bool,
+    /// What double-clicking a file in the sidebar does
+    pub sidebar_open_action: SidebarOpenAction,
+}
+
+/// Where a new tab is inserted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NewTabPosition {
+    /// After the last tab
+    #[default]
+    End,
+    /// Right after the active tab
+    AfterCurrent,
+}
+
+/// What double-clicking a sidebar file does (an empty focused pane shows
+/// it in place for the tab and split actions)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SidebarOpenAction {
+    #[default]
+    NewTab,
+    /// A file viewer split off to the right of the focused pane
+    SplitRight,
+    /// A file viewer split off below the focused pane
+    SplitDown,
+    /// `diagnostics.editor_command`
+    ExternalEditor,
+    /// Show it in the tab's file viewer, if it has one
+    ReplaceCurrentFileViewer,
+}
+
+impl SidebarOpenAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SidebarOpenAction::NewTab => "New tab",
+            SidebarOpenAction::SplitRight => "Split right",
+            SidebarOpenAction::SplitDown => "Split down",
+            SidebarOpenAction::ExternalEditor => "External editor",
+            SidebarOpenAction::ReplaceCurrentFileViewer => "Replace file viewer",
+        }
+    }
+
+    /// Action for a double-click with `modifiers` held: Alt forces the
+    /// external editor, Cmd (Ctrl off macOS) a split, which goes down only
+    /// if that's the default. Replacing falls back to a new tab in a tab
+    /// without a file viewer.
+    pub fn for_click(self, modifiers: egui::Modifiers, has_file_viewer: bool) -> Self {
+        if modifiers.alt {
+            return SidebarOpenAction::ExternalEditor;
+        }
+        if modifiers.command {
+            return match self {
+                SidebarOpenAction::SplitDown => SidebarOpenAction::SplitDown,
+                _ => SidebarOpenAction::SplitRight,
+            };
+        }
+        match self {
+            SidebarOpenAction::ReplaceCurrentFileViewer if !has_file_viewer => SidebarOpenAction::NewTab,
+            action => action,
+        }
+    }
+}
+
+/// A part of the status bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusSegment {
+    AppName,
+    /// Focused pane among the tab's panes
+    Panes,
+    /// Split/close/switch shortcut hints
+    Shortcuts,
+    /// Newer release badge, while one is available
+    Update,
+    Version,
+    /// Do Not Disturb bell
+    Dnd,
+    /// Watcher and git health dot
+    Context,
+}
+
+impl StatusSegment {
+    /// Every segment, in the default order
+    pub const ALL: [StatusSegment; 7] = [
+        StatusSegment::AppName,
+        StatusSegment::Panes,
+        StatusSegment::Shortcuts,
+        StatusSegment::Update,
+        StatusSegment::Version,
+        StatusSegment::Dnd,
+        StatusSegment::Context,
+    ];
+}
+
+/// File tree icon style
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileIconStyle {
+    /// Same marker for every file
+    Off,
+    /// Unicode/ASCII glyphs that render in any font
+    Ascii,
+    /// Nerd Font glyphs (falls back to Ascii if the font lacks them)
+    #[default]
+    Nerd,
+}
+
+impl UiConfig {
+    /// Tab bar heights Preferences offers (px)
+    pub const TAB_BAR_HEIGHTS: RangeInclusive<f32> = 24.0..=40.0;
+    /// Status bar heights Preferences offers (px)
+    pub const STATUS_BAR_HEIGHTS: RangeInclusive<f32> = 16.0..=32.0;
+
+    /// Pull hand-edited bar heights back into the ranges Preferences offers
+    pub fn clamp_bar_heights(&mut self) {
+        let defaults = Self::default();
+        self.tab_bar_height = clamp_or(self.tab_bar_height, Self::TAB_BAR_HEIGHTS, defaults.tab_bar_height);
+        self.status_bar_height = clamp_or(self.status_bar_height, Self::STATUS_BAR_HEIGHTS, defaults.status_bar_height);
+    }
+}
+
+/// `value` clamped to `range`, or `default` when it isn't a number
+fn clamp_or(value: f32, range: RangeInclusive<f32>, default: f32) -> f32 {
+    if value.is_nan() {
+        return default;
+    }
+    value.clamp(*range.start(), *range.end())
+}
+
+/// Where the sidebar is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SidebarMode {
+    /// Beside the panes, taking its own space
+    #[default]
+    Pinned,
+    /// A strip on the left edge; hovering it slides the sidebar out over the panes
+    AutoHide,
+    /// Only Cmd+B slides the sidebar out over the panes
+    Hidden,
+}
+
+impl SidebarMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SidebarMode::Pinned => "Pinned",
+            SidebarMode::AutoHide => "Auto-hide",
+            SidebarMode::Hidden => "Hidden",
+        }
+    }
+}
+
+impl FileIconStyle {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileIconStyle::Off => "Off",
+            FileIconStyle::Ascii => "ASCII",
+            FileIconStyle::Nerd => "Nerd Font",
+        }
+    }
 }
 
 impl Default for UiConfig {
@@ -155,6 +416,7 @@ impl Default for UiConfig {
             tab_bar_height: 28.0,
             status_bar_height: 20.0,
             show_sidebar: true,
+            sidebar_mode: SidebarMode::default(),
             enable_cwd_polling: true,
             show_hidden_files: false,
             max_files: 1000,
@@ -164,10 +426,460 @@ impl Default for UiConfig {
                 "target".to_string(),
                 "node_modules".to_string(),
             ],
+            resize_debounce_ms: 100,
+            run_all_stagger_ms: 50,
+            file_icons: FileIconStyle::default(),
+            inactive_pane_desaturation: 0.0,
+            single_instance: true,
+            sidebar_auto_hide_width: 700.0,
+            compact_tab_bar_width: 600.0,
+            compact_status_bar_width: 800.0,
+            min_pane_width: 160.0,
+            min_pane_height: 80.0,
+            max_split_depth: 6,
+            presentation_font_bump: 4.0,
+            presentation_fullscreen: true,
+            show_breadcrumbs: false,
+            highlight_root_panes: true,
+            show_activity_strip: true,
+            confirm_dangerous_commands: true,
+            confirm_quit: true,
+            sidebar_follows_cd: false,
+            root_switch_dwell_ms: 2000,
+            root_switch_interval_ms: 3000,
+            mirror_font_size: 20.0,
+            dnd_schedule: String::new(),
+            dormant_tab_batch: 3,
+            dormant_wake_count: 2,
+            dormant_wake_delay_ms: 5000,
+            new_tab_position: NewTabPosition::default(),
+            status_segments: StatusSegment::ALL.to_vec(),
+            restore_session: true,
+            sidebar_open_action: SidebarOpenAction::default(),
+        }
+    }
+}
+
+/// Terminal behavior configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerminalConfig {
+    /// Shell for new terminals (empty for `$SHELL`)
+    pub shell: String,
+    pub session_logging: SessionLoggingConfig,
+    pub copy_last_output: CopyLastOutputConfig,
+    pub clipboard_history: ClipboardHistoryConfig,
+    /// Path bar segments only `cd` when clicked with Cmd held
+    pub breadcrumb_cd_requires_modifier: bool,
+    /// Columns taken by East Asian ambiguous-width characters (±, §, ①…)
+    pub ambiguous_width: AmbiguousWidth,
+    /// Where and how smart paste saves clipboard images
+    pub paste_image: PasteImageConfig,
+    /// Read the clipboard off the UI thread for Cmd+V (off: read it in place, which can stall)
+    pub async_paste: bool,
+    /// When fast output gets throttled
+    pub firehose: FirehoseConfig,
+    /// Terminal type reported to device attribute (DA) queries
+    pub term_id: TermId,
+    /// New output scrolls a scrolled-back view to the bottom
+    pub scroll_on_output: bool,
+    /// Typing scrolls a scrolled-back view to the bottom
+    pub scroll_on_keystroke: bool,
+    /// Mark the rows a long line wrapped onto, in their left column
+    pub show_wrap_markers: bool,
+    /// Lines longer than this many columns get wrap markers
+    pub wrap_marker_columns: usize,
+    /// What to do when a terminal enters a matching directory
+    pub dir_hooks: Vec<DirHook>,
+    /// Where new splits and tabs start their shell
+    pub new_terminal_dir: NewTerminalDir,
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            shell: String::new(),
+            session_logging: SessionLoggingConfig::default(),
+            copy_last_output: CopyLastOutputConfig::default(),
+            clipboard_history: ClipboardHistoryConfig::default(),
+            breadcrumb_cd_requires_modifier: false,
+            ambiguous_width: AmbiguousWidth::default(),
+            paste_image: PasteImageConfig::default(),
+            async_paste: true,
+            firehose: FirehoseConfig::default(),
+            term_id: TermId::default(),
+            scroll_on_output: false,
+            scroll_on_keystroke: true,
+            show_wrap_markers: true,
+            wrap_marker_columns: 300,
+            dir_hooks: Vec::new(),
+            new_terminal_dir: NewTerminalDir::default(),
+        }
+    }
+}
+
+/// Throttling of terminals printing faster than anyone can read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FirehoseConfig {
+    /// Panes are redrawn at most 10 times a second above this rate (KB/s)
+    pub enter_kbps: u32,
+    /// Full-rate redraws resume below this rate (KB/s)
+    pub exit_kbps: u32,
+}
+
+impl Default for FirehoseConfig {
+    fn default() -> Self {
+        Self {
+            enter_kbps: 1024,
+            exit_kbps: 256,
+        }
+    }
+}
+
+/// Images saved by smart paste
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PasteImageConfig {
+    /// Where images are saved (`~` is expanded; empty for the system temp dir)
+    pub directory: PathBuf,
+    /// Encoding of saved images
+    pub format: PasteImageFormat,
+    /// File name without extension; `{timestamp}` (ms) and `{date}` are
+    /// expanded. A paste whose name is taken fails instead of overwriting.
+    pub filename_template: String,
+    /// Text typed into the terminal; `{path}` and `{quoted_path}` are expanded
+    pub marker_template: String,
+    /// Pasted images older than this are deleted at startup (0 keeps them)
+    pub cleanup_after_days: u32,
+}
+
+impl Default for PasteImageConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("~/Pictures/VibeTerm"),
+            format: PasteImageFormat::default(),
+            filename_template: "paste-{date}-{timestamp}".to_string(),
+            marker_template: "[image: {path}]\n".to_string(),
+            cleanup_after_days: 0,
+        }
+    }
+}
+
+/// Encoding of pasted images
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PasteImageFormat {
+    #[default]
+    Png,
+    /// Lossy; smaller for large screenshots
+    Jpeg { quality: u8 },
+    /// Lossless
+    Webp,
+}
+
+/// Action run when a terminal enters a matching directory (`[[terminal.dir_hooks]]`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirHook {
+    /// File the directory contains (`.nvmrc`), or a glob of its path
+    /// (`~/work/**`) when it has a `/`
+    pub matches: String,
+    pub action: DirHookAction,
+    /// Fire once per terminal and directory, not on every visit
+    pub once_per_dir: bool,
+    /// Press Enter after a command ending in a newline; otherwise it's only typed
+    pub auto_exec: bool,
+}
+
+/// What a directory hook does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DirHookAction {
+    /// Show a dismissible line at the top of the pane
+    Banner { text: String },
+    /// Type a command into the terminal (asks the first time)
+    Command { command: String },
+}
+
+impl Default for DirHookAction {
+    fn default() -> Self {
+        DirHookAction::Banner { text: String::new() }
+    }
+}
+
+/// Width of East Asian ambiguous-width characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AmbiguousWidth {
+    /// One column, as in Western locales
+    #[default]
+    Narrow,
+    /// Two columns, as in CJK locales
+    Wide,
+}
+
+/// Directory new splits and tabs start in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NewTerminalDir {
+    /// The focused terminal's, or the tab's last focused one from a file
+    /// viewer (home if it's been deleted)
+    #[default]
+    Inherit,
+    Home,
+    /// Where VibeTerm was started
+    Launch,
+}
+
+impl NewTerminalDir {
+    pub fn label(&self) -> &'static str {
+        match self {
+            NewTerminalDir::Inherit => "Focused pane's directory",
+            NewTerminalDir::Home => "Home directory",
+            NewTerminalDir::Launch => "Launch directory",
+        }
+    }
+}
+
+/// Terminal type reported to programs that ask (DA1/DA2 queries)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TermId {
+    /// VT220 with color, reported as a VT100-compatible emulator
+    #[default]
+    Xterm,
+    /// A plain VT220 with color
+    Vt220,
+    /// A VT420 with color
+    Vt420,
+}
+
+impl TermId {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TermId::Xterm => "xterm-like",
+            TermId::Vt220 => "VT220",
+            TermId::Vt420 => "VT420",
+        }
+    }
+
+    /// Which programs the profile suits
+    pub fn hint(&self) -> &'static str {
+        match self {
+            TermId::Xterm => "tmux, screen and most current programs",
+            TermId::Vt220 => "Old curses programs that only know DEC terminals up to the VT220",
+            TermId::Vt420 => "Programs that ask for a VT400-level terminal before using their full screen mode",
+        }
+    }
+}
+
+/// "Copy Last Output" command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CopyLastOutputConfig {
+    /// Key binding, e.g. `Cmd+Alt+C` (empty to disable)
+    pub shortcut: String,
+    /// Regex matching shell prompt lines
+    pub prompt_pattern: String,
+    /// Lines copied when no prompts are found
+    pub fallback_lines: usize,
+}
+
+impl Default for CopyLastOutputConfig {
+    fn default() -> Self {
+        Self {
+            shortcut: "Cmd+Alt+C".to_string(),
+            prompt_pattern: r"^(\S+\s?)?[$%#❯➜](\s|$)".to_string(),
+            fallback_lines: 50,
+        }
+    }
+}
+
+/// History of text copied inside VibeTerm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardHistoryConfig {
+    /// Keep the history across restarts
+    pub persist: bool,
+    /// Regex for secret-looking text that is never stored (empty to store everything)
+    pub secret_pattern: String,
+}
+
+impl Default for ClipboardHistoryConfig {
+    fn default() -> Self {
+        Self {
+            persist: false,
+            secret_pattern: r"AKIA[0-9A-Z]{16}|-----BEGIN [A-Z ]*PRIVATE KEY|gh[pousr]_[A-Za-z0-9]{36}|xox[abprs]-[A-Za-z0-9-]+".to_string(),
+        }
+    }
+}
+
+/// Automatic logging of terminal output to files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionLoggingConfig {
+    /// Log every new terminal (panes can still be toggled individually)
+    pub enabled: bool,
+    /// Where `<date>-<workspace>-<pane>.log` files go (`~` is expanded)
+    pub directory: PathBuf,
+    /// Write plain text instead of re-encoding colors as ANSI escapes
+    pub strip_ansi: bool,
+}
+
+impl Default for SessionLoggingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: PathBuf::from("~/.config/vibeterm/logs"),
+            strip_ansi: true,
+        }
+    }
+}
+
+/// File viewer limits for large files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileViewerConfig {
+    /// Files larger than this are loaded in the background (MB)
+    pub async_load_mb: u64,
+    /// Files larger than this only show their head and tail (MB)
+    pub truncate_mb: u64,
+    /// Size of the head and of the tail shown for truncated files (KB)
+    pub preview_kb: u64,
+    /// Last Markdown view picked, by file extension
+    pub markdown_modes: BTreeMap<String, MarkdownMode>,
+    /// Wrap long lines (panes can override it)
+    pub wrap: bool,
+    /// Columns between tab stops
+    pub tab_width: u8,
+    /// Git blame is only offered for files with at most this many lines
+    pub blame_max_lines: usize,
+}
+
+impl Default for FileViewerConfig {
+    fn default() -> Self {
+        Self {
+            async_load_mb: 5,
+            truncate_mb: 50,
+            preview_kb: 256,
+            markdown_modes: BTreeMap::new(),
+            wrap: true,
+            tab_width: 4,
+            blame_max_lines: 20_000,
         }
     }
 }
 
+impl FileViewerConfig {
+    /// View a Markdown file at `path` opens in
+    pub fn markdown_mode(&self, path: &Path) -> MarkdownMode {
+        path.extension()
+            .and_then(|ext| self.markdown_modes.get(&ext.to_string_lossy().to_lowercase()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Remember `mode` for files with the extension of `path`
+    pub fn set_markdown_mode(&mut self, path: &Path, mode: MarkdownMode) {
+        if let Some(ext) = path.extension() {
+            self.markdown_modes.insert(ext.to_string_lossy().to_lowercase(), mode);
+        }
+    }
+}
+
+/// How the file viewer shows Markdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkdownMode {
+    #[default]
+    Rendered,
+    Source,
+}
+
+/// Where "Jump to Next Diagnostic" opens files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiagnosticsConfig {
+    /// Open in an external editor instead of the file viewer
+    pub open_in_editor: bool,
+    /// Editor command; `{file}`, `{line}` and `{column}` are replaced
+    pub editor_command: String,
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> Self {
+        Self {
+            open_in_editor: false,
+            editor_command: "code --goto {file}:{line}:{column}".to_string(),
+        }
+    }
+}
+
+/// "Search in Project"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Matching lines shown before the search stops
+    pub max_results: usize,
+    /// Use ripgrep (`rg`) when it's installed
+    pub use_ripgrep: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            max_results: 2000,
+            use_ripgrep: true,
+        }
+    }
+}
+
+/// Advanced/miscellaneous configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdvancedConfig {
+    /// Check GitHub releases for a newer version (at most once per day)
+    pub check_for_updates: bool,
+    /// Show keypress → glyph latency overlay
+    pub show_latency_overlay: bool,
+    /// Copy PATH and friends from the login shell at startup
+    pub import_login_shell_env: bool,
+    /// Extra variables to copy from the login shell
+    pub login_shell_env_vars: Vec<String>,
+    /// Record usage stats (time per project, commands per day) on this machine
+    pub local_stats: bool,
+}
+
+impl Default for AdvancedConfig {
+    fn default() -> Self {
+        Self {
+            check_for_updates: false,
+            show_latency_overlay: false,
+            // Apps started from Finder miss the shell's PATH
+            import_login_shell_env: crate::shortcuts::IS_MAC,
+            login_shell_env_vars: Vec::new(),
+            local_stats: false,
+        }
+    }
+}
+
+/// A saved SSH host (`[[ssh_hosts]]`)
+///
+/// An entry named after a `~/.ssh/config` host only adds its tint.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SshHostConfig {
+    pub name: String,
+    /// Address to connect to; the name when empty
+    pub hostname: String,
+    pub user: String,
+    /// 0 = ssh's default
+    pub port: u16,
+    /// Pane tint as `#RRGGBB`, empty for none
+    pub tint: String,
+}
+
 impl Config {
     /// Get config directory path
     pub fn config_dir() -> PathBuf {
@@ -175,6 +887,18 @@ impl Config {
         PathBuf::from(home).join(".config/vibeterm")
     }
 
+    /// Get data directory path (state that isn't configuration)
+    pub fn data_dir() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home).join(".local/share/vibeterm")
+    }
+
+    /// Get cache directory path (files that may be deleted any time)
+    pub fn cache_dir() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home).join(".cache/vibeterm")
+    }
+
     /// Get config file path
     pub fn config_path() -> PathBuf {
         Self::config_dir().join("config.toml")
@@ -189,63 +913,116 @@ impl Config {
             return Self::default();
         }
 
-        match std::fs::read_to_string(&path) {
-            Ok(contents) => {
-                match toml::from_str::<Config>(&contents) {
-                    Ok(config) => {
-                        log::info!("Config loaded from {:?}", path);
-                        config
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to parse config: {}. Using defaults.", e);
-                        Self::default()
-                    }
-                }
+        match Self::read(&path) {
+            Ok(config) => {
+                log::info!("Config loaded from {:?}", path);
+                config
             }
             Err(e) => {
-                log::warn!("Failed to read config: {}. Using defaults.", e);
+                log::warn!("{}. Using defaults.", e);
                 Self::default()
             }
         }
     }
 
+    /// Read a config file, reporting errors instead of falling back to defaults
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config: {}", e))?;
+        let mut config: Self = toml::from_str(&contents).map_err(|e| format!("Failed to parse config: {}", e))?;
+        config.ui.clamp_bar_heights();
+        config.font.clamp_ui_scale();
+        Ok(config)
+    }
+
+    /// Modification time of the config file, `None` if it doesn't exist
+    pub fn modified() -> Option<std::time::SystemTime> {
+        std::fs::metadata(Self::config_path()).and_then(|meta| meta.modified()).ok()
+    }
+
+    /// Do both configs serialize to the same file?
+    pub fn same_file_contents(&self, other: &Self) -> bool {
+        toml::to_string(self).ok() == toml::to_string(other).ok()
+    }
+
     /// Save config to file
     pub fn save(&self) -> Result<(), String> {
         let path = Self::config_path();
-        let dir = Self::config_dir();
-
-        // Create directory
-        std::fs::create_dir_all(&dir)
-            .map_err(|e| format!("Failed to create config dir: {}", e))?;
 
         // Serialize to TOML
         let toml_string = toml::to_string_pretty(self)
             .map_err(|e| format!("Failed to serialize config: {}", e))?;
 
-        // Write to file
-        std::fs::write(&path, toml_string)
-            .map_err(|e| format!("Failed to write config: {}", e))?;
+        // Write through a temporary file so a crash can't truncate it
+        crate::config_store::write_atomic(&path, &toml_string)?;
 
         log::info!("Config saved to {:?}", path);
         Ok(())
     }
 }
 
-/// Parse hex color string to Color32
+/// Parse a `#RRGGBB` color, `#` optional
+///
+/// `#RRGGBBAA` is read too, with its alpha ignored, as themes have always
+/// been allowed to use it.
+///
+/// The one hex mapping: the UI, Preferences and the terminal palette all
+/// go through it, so a color looks the same everywhere it's used.
+pub fn hex_to_color32(hex: &str) -> Option<Color32> {
+    let hex = hex.trim();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if hex.len() != 6 && hex.len() != 8 {
+        return None;
+    }
+    if hex.len() == 8 {
+        u8::from_str_radix(hex.get(6..8)?, 16).ok()?;
+    }
+    let r = u8::from_str_radix(hex.get(0..2)?, 16).ok()?;
+    let g = u8::from_str_radix(hex.get(2..4)?, 16).ok()?;
+    let b = u8::from_str_radix(hex.get(4..6)?, 16).ok()?;
+    Some(Color32::from_rgb(r, g, b))
+}
+
+/// Format a color as `#RRGGBB`
+pub fn color32_to_hex(color: Color32) -> String {
+    format!("#{:02X}{:02X}{:02X}", color.r(), color.g(), color.b())
+}
+
+/// Parse hex color string to Color32, gray if it isn't one
 pub fn parse_hex_color(hex: &str) -> Color32 {
-    let hex = hex.trim_start_matches('#');
-    if hex.len() >= 6 {
-        let r = u8::from_str_radix(&hex[0..2], 16).unwrap_or(0);
-        let g = u8::from_str_radix(&hex[2..4], 16).unwrap_or(0);
-        let b = u8::from_str_radix(&hex[4..6], 16).unwrap_or(0);
-        Color32::from_rgb(r, g, b)
-    } else {
-        Color32::GRAY
+    hex_to_color32(hex).unwrap_or(Color32::GRAY)
+}
+
+/// Blend a hex color toward `target` in linear RGB
+///
+/// `factor` 0 keeps `color`, 1 gives `target`. Unparseable input is
+/// returned unchanged.
+pub fn blend_hex_color(color: &str, target: &str, factor: f32) -> String {
+    use egui::ecolor::{gamma_u8_from_linear_f32, linear_f32_from_gamma_u8};
+
+    fn channels(hex: &str) -> Option<[u8; 3]> {
+        let hex = hex.strip_prefix('#')?;
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        Some([channel(0)?, channel(2)?, channel(4)?])
     }
+
+    let (Some(from), Some(to)) = (channels(color), channels(target)) else {
+        return color.to_string();
+    };
+    let factor = factor.clamp(0.0, 1.0);
+    let [r, g, b] = std::array::from_fn(|i| {
+        let from = linear_f32_from_gamma_u8(from[i]);
+        let to = linear_f32_from_gamma_u8(to[i]);
+        gamma_u8_from_linear_f32(from + (to - from) * factor)
+    });
+    format!("#{:02X}{:02X}{:02X}", r, g, b)
 }
 
 /// Runtime theme colors (parsed from config)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub struct RuntimeTheme {
     pub background: Color32,
@@ -315,3 +1092,113 @@ impl From<&ThemeConfig> for RuntimeTheme {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_reports_errors() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        assert!(Config::read(&path).unwrap_err().starts_with("Failed to read config"));
+
+        std::fs::write(&path, "[font]\nterminal_size = \"big\"\n").unwrap();
+        assert!(Config::read(&path).unwrap_err().starts_with("Failed to parse config"));
+
+        std::fs::write(&path, "[font]\nterminal_size = 16.0\n").unwrap();
+        let config = Config::read(&path).unwrap();
+        assert_eq!(config.font.terminal_size, 16.0);
+        assert!(!config.same_file_contents(&Config::default()));
+        assert!(Config::default().same_file_contents(&Config::default()));
+    }
+
+    #[test]
+    fn test_bar_heights_are_clamped_on_read() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[ui]\ntab_bar_height = 500.0\nstatus_bar_height = 2.0\n").unwrap();
+        let config = Config::read(&path).unwrap();
+        assert_eq!(config.ui.tab_bar_height, 40.0);
+        assert_eq!(config.ui.status_bar_height, 16.0);
+
+        std::fs::write(&path, "[ui]\ntab_bar_height = 32.0\nstatus_bar_height = nan\n").unwrap();
+        let config = Config::read(&path).unwrap();
+        assert_eq!(config.ui.tab_bar_height, 32.0);
+        assert_eq!(config.ui.status_bar_height, UiConfig::default().status_bar_height);
+    }
+
+    #[test]
+    fn test_ui_scale_is_clamped_on_read() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[font]\nui_scale = 3.0\n").unwrap();
+        assert_eq!(Config::read(&path).unwrap().font.ui_scale, 1.6);
+        std::fs::write(&path, "[font]\nui_scale = 1.2\n").unwrap();
+        assert_eq!(Config::read(&path).unwrap().font.ui_scale, 1.2);
+    }
+
+    #[test]
+    fn test_blend_endpoints() {
+        assert_eq!(blend_hex_color("#E07A5F", "#2E1A16", 0.0), "#E07A5F");
+        assert_eq!(blend_hex_color("#E07A5F", "#2E1A16", 1.0), "#2E1A16");
+        assert_eq!(blend_hex_color("#E07A5F", "#2E1A16", 7.0), "#2E1A16");
+    }
+
+    #[test]
+    fn test_blend_is_linear_light() {
+        // Halfway between black and white in linear light is sRGB 0xBC, not 0x80
+        assert_eq!(blend_hex_color("#FFFFFF", "#000000", 0.5), "#BCBCBC");
+        assert_eq!(blend_hex_color("#000000", "#FFFFFF", 0.5), "#BCBCBC");
+        assert_eq!(blend_hex_color("#FF0000", "#0000FF", 0.5), "#BC00BC");
+    }
+
+    #[test]
+    fn test_sidebar_open_action_follows_modifiers() {
+        use egui::Modifiers;
+        use SidebarOpenAction::*;
+        let all = [NewTab, SplitRight, SplitDown, ExternalEditor, ReplaceCurrentFileViewer];
+
+        // Without modifiers the setting applies as is
+        for action in all {
+            assert_eq!(action.for_click(Modifiers::NONE, true), action);
+        }
+        assert_eq!(ReplaceCurrentFileViewer.for_click(Modifiers::NONE, false), NewTab);
+        assert_eq!(SplitDown.for_click(Modifiers::SHIFT, false), SplitDown);
+
+        // Cmd splits, keeping a downward default
+        for action in all {
+            let split = if action == SplitDown { SplitDown } else { SplitRight };
+            assert_eq!(action.for_click(Modifiers::COMMAND, false), split);
+        }
+
+        // Alt opens the editor, also with Cmd held
+        for action in all {
+            assert_eq!(action.for_click(Modifiers::ALT, true), ExternalEditor);
+            assert_eq!(action.for_click(Modifiers::ALT | Modifiers::COMMAND, false), ExternalEditor);
+        }
+
+        let config: UiConfig = toml::from_str("sidebar_open_action = \"replacecurrentfileviewer\"").unwrap();
+        assert_eq!(config.sidebar_open_action, ReplaceCurrentFileViewer);
+    }
+
+    #[test]
+    fn test_blend_keeps_unparseable_color() {
+        assert_eq!(blend_hex_color("red", "#000000", 0.5), "red");
+        assert_eq!(blend_hex_color("#FFF", "#000000", 0.5), "#FFF");
+        assert_eq!(blend_hex_color("#FFFFFF", "#00", 0.5), "#FFFFFF");
+    }
+
+    #[test]
+    fn test_hex_parsing_round_trips() {
+        let orange = Color32::from_rgb(0xE0, 0x7A, 0x5F);
+        assert_eq!(hex_to_color32("#E07A5F"), Some(orange));
+        assert_eq!(hex_to_color32(" e07a5f "), Some(orange));
+        assert_eq!(color32_to_hex(orange), "#E07A5F");
+        assert_eq!(hex_to_color32("#E07A5F80"), Some(orange));
+        for bad in ["", "#FFF", "#E07A5F0", "#E07A5FGG", "#GG0000", "#ÿÿÿ"] {
+            assert_eq!(hex_to_color32(bad), None, "{}", bad);
+        }
+        assert_eq!(parse_hex_color("red"), Color32::GRAY);
+    }
+}
@@ -0,0 +1,211 @@
+//! Config Store
+//!
+//! Owns the live `Config` and decides when it goes to disk. Changes mark
+//! their top-level sections (`[ui]`, `[font]`, ...) dirty and schedule a
+//! save a short while later, so a burst of Apply clicks or a sidebar drag
+//! writes config.toml once. Writes go to a temporary file that is renamed
+//! over config.toml, so a crash mid-write can't leave it half written.
+
+use std::collections::BTreeSet;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+
+/// Time from the last change to the save
+pub const SAVE_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// The live config and its unsaved changes
+pub struct ConfigStore {
+    config: Config,
+    path: PathBuf,
+    /// Sections changed since the last save
+    dirty: BTreeSet<String>,
+    /// When the pending save is due
+    save_at: Option<Instant>,
+}
+
+impl Deref for ConfigStore {
+    type Target = Config;
+
+    fn deref(&self) -> &Config {
+        &self.config
+    }
+}
+
+impl ConfigStore {
+    /// Store for `config`, saved to `path`
+    pub fn new(config: Config, path: PathBuf) -> Self {
+        Self {
+            config,
+            path,
+            dirty: BTreeSet::new(),
+            save_at: None,
+        }
+    }
+
+    /// Replace the config, scheduling a save if it changed
+    ///
+    /// Returns whether any section changed.
+    pub fn update(&mut self, config: Config, now: Instant) -> bool {
+        let changed = changed_sections(&self.config, &config);
+        self.config = config;
+        if changed.is_empty() {
+            return false;
+        }
+        self.dirty.extend(changed);
+        self.save_at = Some(now + SAVE_DEBOUNCE);
+        true
+    }
+
+    /// Change the config in place, scheduling a save if it changed
+    pub fn edit(&mut self, now: Instant, change: impl FnOnce(&mut Config)) -> bool {
+        let mut config = self.config.clone();
+        change(&mut config);
+        self.update(config, now)
+    }
+
+    /// Forget unsaved changes: the file on disk matches the config
+    pub fn mark_saved(&mut self) {
+        self.dirty.clear();
+        self.save_at = None;
+    }
+
+    /// Sections with unsaved changes
+    pub fn dirty_sections(&self) -> impl Iterator<Item = &str> {
+        self.dirty.iter().map(String::as_str)
+    }
+
+    /// When the pending save is due, if there is one
+    pub fn save_due(&self) -> Option<Instant> {
+        self.save_at
+    }
+
+    /// File contents to write if a save is due at `now`
+    ///
+    /// The changes count as saved once this returns them.
+    pub fn take_due(&mut self, now: Instant) -> Option<(PathBuf, String)> {
+        if self.save_at.is_none_or(|at| at > now) {
+            return None;
+        }
+        self.take_pending()
+    }
+
+    /// File contents to write for any unsaved changes, due or not
+    pub fn take_pending(&mut self) -> Option<(PathBuf, String)> {
+        if self.dirty.is_empty() {
+            return None;
+        }
+        match toml::to_string_pretty(&self.config) {
+            Ok(contents) => {
+                log::info!("Saving config ({} changed)", self.dirty_sections().collect::<Vec<_>>().join(", "));
+                self.mark_saved();
+                Some((self.path.clone(), contents))
+            }
+            Err(e) => {
+                log::error!("Failed to serialize config: {}", e);
+                self.mark_saved();
+                None
+            }
+        }
+    }
+}
+
+/// Top-level sections that differ between two configs
+fn changed_sections(old: &Config, new: &Config) -> BTreeSet<String> {
+    let (Ok(toml::Value::Table(old)), Ok(toml::Value::Table(new))) =
+        (toml::Value::try_from(old), toml::Value::try_from(new))
+    else {
+        // Unreachable in practice; treat it as a change so it gets saved
+        return BTreeSet::from(["config".to_string()]);
+    };
+    old.keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect()
+}
+
+/// Replace `path` with `contents` through a temporary file and a rename
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), String> {
+    let dir = path.parent().ok_or_else(|| format!("No parent directory for {}", path.display()))?;
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let temp = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+    let result = std::fs::write(&temp, contents)
+        .and_then(|()| std::fs::rename(&temp, path));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp);
+        return Err(format!("Failed to write {}: {}", path.display(), e));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> (ConfigStore, Instant) {
+        (ConfigStore::new(Config::default(), PathBuf::from("/tmp/config.toml")), Instant::now())
+    }
+
+    #[test]
+    fn test_dirty_sections_are_tracked() {
+        let (mut store, now) = store();
+        assert!(!store.update(Config::default(), now));
+        assert_eq!(store.save_due(), None);
+
+        assert!(store.edit(now, |config| config.ui.sidebar_width = 300.0));
+        assert!(store.edit(now, |config| config.font.terminal_size += 1.0));
+        assert!(!store.edit(now, |config| config.ui.sidebar_width = 300.0));
+        assert_eq!(store.dirty_sections().collect::<Vec<_>>(), ["font", "ui"]);
+        assert_eq!(store.ui.sidebar_width, 300.0);
+
+        store.mark_saved();
+        assert_eq!(store.dirty_sections().count(), 0);
+        assert_eq!(store.take_pending(), None);
+    }
+
+    #[test]
+    fn test_saves_are_debounced() {
+        let (mut store, now) = store();
+        store.edit(now, |config| config.ui.sidebar_width = 250.0);
+        let later = now + Duration::from_secs(1);
+        store.edit(later, |config| config.ui.sidebar_width = 260.0);
+
+        // Each change pushes the save back
+        assert_eq!(store.take_due(now + SAVE_DEBOUNCE), None);
+        assert_eq!(store.save_due(), Some(later + SAVE_DEBOUNCE));
+        let (path, contents) = store.take_due(later + SAVE_DEBOUNCE).unwrap();
+        assert_eq!(path, PathBuf::from("/tmp/config.toml"));
+        let saved: Config = toml::from_str(&contents).unwrap();
+        assert_eq!(saved.ui.sidebar_width, 260.0);
+
+        // Nothing left to save
+        assert_eq!(store.save_due(), None);
+        assert_eq!(store.take_due(later + SAVE_DEBOUNCE * 2), None);
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("nested/config.toml");
+        write_atomic(&path, "a = 1\n").unwrap();
+        write_atomic(&path, "a = 2\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a = 2\n");
+
+        // Only the file itself is left behind
+        let names: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["config.toml"]);
+
+        // A failed write leaves the old file alone
+        assert!(write_atomic(&path.join("config.toml"), "x").is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a = 2\n");
+    }
+}
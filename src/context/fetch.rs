@@ -0,0 +1,188 @@
+//! Background `git fetch` (opt-in)
+//!
+//! Keeps the remote-tracking refs behind ahead/behind counts fresh. The
+//! fetch shells out to the git binary with prompts disabled, so only
+//! credentials available without asking (ssh-agent, a credential helper)
+//! are used.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+
+/// Fetch progress for the active repository
+#[derive(Debug, Clone, Default)]
+pub struct FetchTracker {
+    /// Repository the fields below belong to
+    root: Option<PathBuf>,
+    /// When the last fetch (successful or not) started
+    last_attempt: Option<SystemTime>,
+    in_flight: bool,
+    last_error: Option<String>,
+}
+
+/// Fetch state shown in the status bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchStatus {
+    /// Last successful fetch, from anywhere (FETCH_HEAD's timestamp)
+    pub last_fetch: Option<SystemTime>,
+    /// Remote refs are older than the fetch interval
+    pub stale: bool,
+    pub in_flight: bool,
+    pub last_error: Option<String>,
+}
+
+impl FetchTracker {
+    /// Start a fetch of `root` if it's due; returns `true` if one started
+    pub fn start_if_due(&mut self, root: &Path, last_fetch: Option<SystemTime>, interval: Duration) -> bool {
+        self.switch_root(root);
+        let now = SystemTime::now();
+        if self.in_flight || !fetch_due(now, last_fetch, self.last_attempt, interval) {
+            return false;
+        }
+        self.start(now);
+        true
+    }
+
+    /// Start a fetch of `root` now unless one is running
+    pub fn start_now(&mut self, root: &Path) -> bool {
+        self.switch_root(root);
+        if self.in_flight {
+            return false;
+        }
+        self.start(SystemTime::now());
+        true
+    }
+
+    fn start(&mut self, now: SystemTime) {
+        self.in_flight = true;
+        self.last_attempt = Some(now);
+    }
+
+    /// Forget state that belonged to another repository
+    fn switch_root(&mut self, root: &Path) {
+        if self.root.as_deref() != Some(root) {
+            *self = Self {
+                root: Some(root.to_path_buf()),
+                ..Self::default()
+            };
+        }
+    }
+
+    /// Record the result of the fetch of `root`
+    pub fn finish(&mut self, root: &Path, result: Result<(), String>) {
+        if self.root.as_deref() != Some(root) {
+            return;
+        }
+        self.in_flight = false;
+        self.last_error = result.err();
+    }
+
+    pub fn status(&self, last_fetch: Option<SystemTime>, interval: Duration) -> FetchStatus {
+        FetchStatus {
+            last_fetch,
+            stale: is_stale(SystemTime::now(), last_fetch, interval),
+            in_flight: self.in_flight,
+            last_error: self.last_error.clone(),
+        }
+    }
+}
+
+/// Is a fetch due, given the last successful fetch and the last attempt?
+pub fn fetch_due(
+    now: SystemTime,
+    last_fetch: Option<SystemTime>,
+    last_attempt: Option<SystemTime>,
+    interval: Duration,
+) -> bool {
+    match last_fetch.max(last_attempt) {
+        Some(latest) => now.duration_since(latest).is_ok_and(|age| age >= interval),
+        None => true,
+    }
+}
+
+/// Are remote refs older than `interval`?
+pub fn is_stale(now: SystemTime, last_fetch: Option<SystemTime>, interval: Duration) -> bool {
+    match last_fetch {
+        Some(fetched) => now.duration_since(fetched).is_ok_and(|age| age > interval),
+        None => true,
+    }
+}
+
+/// Last time anything fetched into the repository with git dir `git_dir`
+pub fn last_fetch_time(git_dir: &Path) -> Option<SystemTime> {
+    std::fs::metadata(git_dir.join("FETCH_HEAD")).and_then(|m| m.modified()).ok()
+}
+
+/// `git fetch --no-tags origin` in `root` (blocking, never prompts)
+pub fn run_fetch(root: &Path) -> Result<(), String> {
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(root)
+        .args(["fetch", "--no-tags", "--quiet", "origin"])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null());
+    // Fail instead of asking for an ssh passphrase or host key
+    if std::env::var_os("GIT_SSH_COMMAND").is_none() {
+        command.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+    }
+
+    let output = command.output().map_err(|e| format!("Failed to run git: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(stderr.lines().last().unwrap_or("git fetch failed").trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN: Duration = Duration::from_secs(60);
+
+    fn at(mins: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + MIN * mins as u32
+    }
+
+    #[test]
+    fn test_fetch_due() {
+        let interval = MIN * 15;
+        assert!(fetch_due(at(100), None, None, interval));
+        assert!(!fetch_due(at(100), Some(at(90)), None, interval));
+        assert!(fetch_due(at(100), Some(at(85)), None, interval));
+        // A failed attempt also waits out the interval
+        assert!(!fetch_due(at(100), Some(at(10)), Some(at(95)), interval));
+        assert!(fetch_due(at(120), Some(at(10)), Some(at(95)), interval));
+    }
+
+    #[test]
+    fn test_stale() {
+        let interval = MIN * 15;
+        assert!(is_stale(at(100), None, interval));
+        assert!(!is_stale(at(100), Some(at(85)), interval));
+        assert!(is_stale(at(100), Some(at(84)), interval));
+    }
+
+    #[test]
+    fn test_tracker_runs_one_fetch_at_a_time() {
+        let root = Path::new("/repo");
+        let mut tracker = FetchTracker::default();
+        assert!(tracker.start_if_due(root, None, MIN));
+        assert!(!tracker.start_now(root));
+        assert!(tracker.status(None, MIN).in_flight);
+
+        tracker.finish(root, Err("Could not read from remote repository.".to_string()));
+        let status = tracker.status(None, MIN);
+        assert!(!status.in_flight && status.stale);
+        assert!(status.last_error.is_some());
+        // Just attempted, so not due again yet
+        assert!(!tracker.start_if_due(root, None, MIN));
+
+        // Another repository starts fresh
+        assert!(tracker.start_if_due(Path::new("/other"), None, MIN));
+        tracker.finish(root, Ok(()));
+        assert!(tracker.status(None, MIN).in_flight);
+    }
+}
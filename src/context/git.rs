@@ -5,7 +5,16 @@
 use git2::{Repository, StatusOptions, Status, StatusShow};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Source of [`GitStatusCache`] generations, shared so that a replaced
+/// cache never repeats one
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+fn next_generation() -> u64 {
+    NEXT_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
 
 /// Git status for a single file
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -99,6 +108,21 @@ pub struct RepoStatus {
     pub is_dirty: bool,
     pub ahead: usize,
     pub behind: usize,
+    /// When remote-tracking refs were last fetched, if ever
+    pub last_fetch: Option<SystemTime>,
+    /// Counts come from the last full pass, not the latest scoped refresh
+    pub approximate: bool,
+}
+
+/// Timing of the most recent git status refresh
+#[derive(Debug, Clone, Copy)]
+pub struct RefreshInfo {
+    /// When the refresh started
+    pub at: Instant,
+    /// How long `git status` took
+    pub duration: Duration,
+    /// Only the paths of interest were queried
+    pub scoped: bool,
 }
 
 /// Cache for git status
@@ -110,6 +134,21 @@ pub struct GitStatusCache {
     last_refresh: Instant,
     refresh_interval: Duration,
     dirty: bool,
+    last_refresh_info: Option<RefreshInfo>,
+    /// Full refreshes slower than this switch to scoped refreshes
+    scoped_threshold: Option<Duration>,
+    /// Interval of the full pass that keeps the summary counts current
+    full_refresh_interval: Duration,
+    last_full_refresh: Instant,
+    scoped: bool,
+    /// Paths last passed to `set_paths_of_interest`
+    requested_paths: Vec<PathBuf>,
+    /// Repo-relative paths the UI shows, queried in scoped mode
+    paths_of_interest: Vec<PathBuf>,
+    /// Changes whenever file statuses do
+    generation: u64,
+    /// Changes whenever the repository root does
+    root_generation: u64,
 }
 
 impl GitStatusCache {
@@ -122,6 +161,15 @@ impl GitStatusCache {
             last_refresh: Instant::now() - refresh_interval,
             refresh_interval,
             dirty: true,
+            last_refresh_info: None,
+            scoped_threshold: None,
+            full_refresh_interval: Duration::from_secs(120),
+            last_full_refresh: Instant::now(),
+            scoped: false,
+            requested_paths: Vec::new(),
+            paths_of_interest: Vec::new(),
+            generation: next_generation(),
+            root_generation: next_generation(),
         }
     }
 
@@ -137,12 +185,22 @@ impl GitStatusCache {
                     self.repo_root = Some(root);
                     self.repo = Some(repo);
                     self.dirty = true;
+                    self.scoped = false;
+                    self.requested_paths.clear();
+                    self.paths_of_interest.clear();
+                    self.file_statuses.clear();
+                    self.generation = next_generation();
+                    self.root_generation = next_generation();
                 }
             }
             Err(e) => {
                 if self.repo.is_some() {
                     log::debug!("No git repository at {:?}: {}", path, e);
                 }
+                if self.repo_root.is_some() {
+                    self.generation = next_generation();
+                    self.root_generation = next_generation();
+                }
                 self.repo = None;
                 self.repo_root = None;
                 self.file_statuses.clear();
@@ -151,6 +209,53 @@ impl GitStatusCache {
         }
     }
 
+    pub fn set_refresh_interval(&mut self, refresh_interval: Duration) {
+        self.refresh_interval = refresh_interval;
+    }
+
+    /// Switch to scoped refreshes once a full refresh takes at least
+    /// `threshold`; `None` always refreshes everything
+    pub fn set_scoped_threshold(&mut self, threshold: Option<Duration>) {
+        self.scoped_threshold = threshold;
+        if threshold.is_none() && self.scoped {
+            self.scoped = false;
+            self.dirty = true;
+        }
+    }
+
+    pub fn set_full_refresh_interval(&mut self, interval: Duration) {
+        self.full_refresh_interval = interval;
+    }
+
+    /// Paths shown in the UI: expanded directories and open files
+    ///
+    /// In scoped mode only these are queried, and a change takes effect on
+    /// the next refresh. Paths outside the repository are ignored.
+    pub fn set_paths_of_interest(&mut self, paths: Vec<PathBuf>) {
+        // Called every frame; skip the path resolution when nothing changed
+        if paths == self.requested_paths {
+            return;
+        }
+        self.requested_paths = paths;
+        let Some(root) = &self.repo_root else { return };
+        let mut relative: Vec<PathBuf> = self.requested_paths
+            .iter()
+            .filter_map(|path| match path.strip_prefix(root) {
+                Ok(relative) => Some(relative.to_path_buf()),
+                // Sidebar paths aren't canonical (e.g. /var vs /private/var)
+                Err(_) => path.canonicalize().ok()?.strip_prefix(root).ok().map(Path::to_path_buf),
+            })
+            .collect();
+        relative.sort();
+        relative.dedup();
+        if relative != self.paths_of_interest {
+            self.paths_of_interest = relative;
+            if self.scoped {
+                self.dirty = true;
+            }
+        }
+    }
+
     pub fn mark_dirty(&mut self) {
         self.dirty = true;
     }
@@ -168,18 +273,54 @@ impl GitStatusCache {
     }
 
     pub fn refresh(&mut self) {
-        let Some(repo) = &self.repo else { return };
-
-        self.file_statuses.clear();
+        if self.repo.is_none() {
+            return;
+        }
         self.dirty = false;
         self.last_refresh = Instant::now();
 
+        let full = !self.scoped || self.last_full_refresh.elapsed() >= self.full_refresh_interval;
+        let previous = std::mem::take(&mut self.file_statuses);
+        if full {
+            self.refresh_full();
+        } else {
+            self.refresh_scoped();
+        }
+        if self.file_statuses != previous {
+            self.generation = next_generation();
+        }
+
+        let duration = self.last_refresh.elapsed();
+        if full {
+            self.last_full_refresh = self.last_refresh;
+            let scoped = self.scoped_threshold.is_some_and(|threshold| duration >= threshold);
+            if scoped != self.scoped {
+                log::info!("Git status took {}ms, scoped refreshes {}", duration.as_millis(), if scoped { "on" } else { "off" });
+                self.scoped = scoped;
+            }
+        }
+        self.last_refresh_info = Some(RefreshInfo {
+            at: self.last_refresh,
+            duration,
+            scoped: !full,
+        });
+    }
+
+    fn status_options() -> StatusOptions {
         let mut opts = StatusOptions::new();
         opts.show(StatusShow::IndexAndWorkdir)
             .include_untracked(true)
             .recurse_untracked_dirs(true)
             .include_ignored(false)
             .exclude_submodules(true);
+        opts
+    }
+
+    /// Query every path and recount the summary
+    fn refresh_full(&mut self) {
+        let Some(repo) = &self.repo else { return };
+
+        let mut opts = Self::status_options();
 
         match repo.statuses(Some(&mut opts)) {
             Ok(statuses) => {
@@ -222,6 +363,8 @@ impl GitStatusCache {
                     is_dirty,
                     ahead,
                     behind,
+                    last_fetch: super::fetch::last_fetch_time(repo.path()),
+                    approximate: false,
                 });
             }
             Err(e) => {
@@ -230,6 +373,42 @@ impl GitStatusCache {
         }
     }
 
+    /// Query only the paths of interest; the counts keep their last full value
+    fn refresh_scoped(&mut self) {
+        let Some(repo) = &self.repo else { return };
+
+        if !self.paths_of_interest.is_empty() {
+            let mut opts = Self::status_options();
+            for path in &self.paths_of_interest {
+                opts.pathspec(path);
+            }
+            match repo.statuses(Some(&mut opts)) {
+                Ok(statuses) => {
+                    for entry in statuses.iter() {
+                        if let Some(path) = entry.path() {
+                            let status = FileGitStatus::from_git2_status(entry.status());
+                            self.file_statuses.insert(PathBuf::from(path), status);
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Failed to get git status: {}", e);
+                }
+            }
+        }
+
+        let branch = Self::get_branch_name(repo);
+        let (ahead, behind) = Self::get_ahead_behind(repo);
+        let last_fetch = super::fetch::last_fetch_time(repo.path());
+        if let Some(status) = &mut self.repo_status {
+            status.branch = branch;
+            status.ahead = ahead;
+            status.behind = behind;
+            status.last_fetch = last_fetch;
+            status.approximate = true;
+        }
+    }
+
     fn get_branch_name(repo: &Repository) -> String {
         match repo.head() {
             Ok(head) => {
@@ -287,6 +466,30 @@ impl GitStatusCache {
             .unwrap_or(FileGitStatus::Clean)
     }
 
+    /// Changes whenever any file status may have; unchanged refreshes keep it
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Changes whenever the repository root does, invalidating paths from
+    /// [`Self::relative_path`]
+    pub fn root_generation(&self) -> u64 {
+        self.root_generation
+    }
+
+    /// `path` relative to the repository root, if it's inside
+    pub fn relative_path(&self, path: &Path) -> Option<PathBuf> {
+        path.strip_prefix(self.repo_root.as_ref()?).ok().map(Path::to_path_buf)
+    }
+
+    /// Is `path` (absolute) ignored by the repository's .gitignore files?
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let (Some(repo), Some(relative)) = (&self.repo, self.relative_path(path)) else {
+            return false;
+        };
+        repo.is_path_ignored(relative).unwrap_or(false)
+    }
+
     pub fn get_status_for_absolute(&self, path: &Path) -> FileGitStatus {
         if let Some(root) = &self.repo_root {
             if let Ok(relative) = path.strip_prefix(root) {
@@ -307,6 +510,10 @@ impl GitStatusCache {
     pub fn repo_root(&self) -> Option<&Path> {
         self.repo_root.as_deref()
     }
+
+    pub fn last_refresh_info(&self) -> Option<RefreshInfo> {
+        self.last_refresh_info
+    }
 }
 
 impl Default for GitStatusCache {
@@ -334,6 +541,83 @@ mod tests {
         let cache = GitStatusCache::new(Duration::from_secs(5));
         assert!(!cache.is_in_repo());
         assert!(cache.repo_status().is_none());
+        assert!(cache.last_refresh_info().is_none());
+    }
+
+    /// Repo with untracked `a/one.txt` and `b/two.txt`, in scoped mode
+    fn scoped_repo() -> (tempfile::TempDir, GitStatusCache) {
+        let dir = tempfile::TempDir::new().unwrap();
+        Repository::init(dir.path()).unwrap();
+        for file in ["a/one.txt", "b/two.txt"] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "x").unwrap();
+        }
+        let mut cache = GitStatusCache::new(Duration::from_secs(5));
+        cache.set_scoped_threshold(Some(Duration::ZERO));
+        cache.set_root(dir.path());
+        cache.refresh();
+        assert!(cache.scoped);
+        (dir, cache)
+    }
+
+    #[test]
+    fn test_scoped_refresh_only_reports_paths_of_interest() {
+        let (dir, mut cache) = scoped_repo();
+        cache.set_paths_of_interest(vec![dir.path().join("a")]);
+        assert!(cache.needs_refresh());
+        cache.refresh();
+
+        assert!(cache.last_refresh_info().unwrap().scoped);
+        assert_eq!(cache.get_file_status(Path::new("a/one.txt")), FileGitStatus::Untracked);
+        assert_eq!(cache.get_file_status(Path::new("b/two.txt")), FileGitStatus::Clean);
+        // Counts still come from the full pass
+        let status = cache.repo_status().unwrap();
+        assert!(status.approximate);
+        assert_eq!(status.untracked_count, 2);
+
+        // Expanding `b` picks it up on the next refresh
+        cache.set_paths_of_interest(vec![dir.path().join("b"), dir.path().join("a")]);
+        assert!(cache.refresh_if_needed());
+        assert_eq!(cache.get_file_status(Path::new("b/two.txt")), FileGitStatus::Untracked);
+
+        // Same set again doesn't force another refresh
+        cache.set_paths_of_interest(vec![dir.path().join("a"), dir.path().join("b")]);
+        assert!(!cache.needs_refresh());
+    }
+
+    #[test]
+    fn test_periodic_full_pass_and_leaving_scoped_mode() {
+        let (_dir, mut cache) = scoped_repo();
+        cache.set_full_refresh_interval(Duration::ZERO);
+        cache.refresh();
+        assert!(!cache.last_refresh_info().unwrap().scoped);
+        assert!(!cache.repo_status().unwrap().approximate);
+        assert_eq!(cache.get_file_status(Path::new("b/two.txt")), FileGitStatus::Untracked);
+
+        cache.set_scoped_threshold(None);
+        assert!(!cache.scoped);
+        assert!(cache.needs_refresh());
+    }
+
+    #[test]
+    fn test_generation_only_changes_with_statuses() {
+        let (dir, mut cache) = scoped_repo();
+        cache.set_scoped_threshold(None);
+        cache.refresh();
+        let (generation, root_generation) = (cache.generation(), cache.root_generation());
+
+        cache.refresh();
+        assert_eq!(cache.generation(), generation);
+
+        std::fs::write(dir.path().join("three.txt"), "x").unwrap();
+        cache.refresh();
+        assert_ne!(cache.generation(), generation);
+        assert_eq!(cache.root_generation(), root_generation);
+
+        // A replacement cache never reuses a generation
+        let fresh = GitStatusCache::new(Duration::from_secs(5));
+        assert!(fresh.generation() > cache.generation());
     }
 
     #[test]
@@ -2,56 +2,181 @@
 
 use std::collections::VecDeque;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use super::events::ContextEvent;
-use super::git::{FileGitStatus, GitStatusCache, RepoStatus};
+use super::fetch::{FetchStatus, FetchTracker};
+use super::git::{FileGitStatus, GitStatusCache, RefreshInfo, RepoStatus};
 use super::pinned::{PinReason, PinnedFile, PinnedFiles};
 use super::ContextConfig;
 use crate::watcher::{FileWatcherService, WatcherConfig, WatcherEvent};
 
+/// File watcher health
+#[derive(Debug, Clone, Default)]
+pub struct WatcherState {
+    /// Watching is enabled in the config
+    pub enabled: bool,
+    /// Paused for this session from the command palette
+    pub paused: bool,
+    /// The watcher service is running
+    pub active: bool,
+    /// Number of watched directories
+    pub watched_paths: usize,
+    /// Most recent watcher error
+    pub last_error: Option<String>,
+}
+
 pub struct ContextManager {
     watcher: Option<FileWatcherService>,
+    last_watcher_error: Option<String>,
     git_cache: GitStatusCache,
+    fetch: FetchTracker,
     pinned: PinnedFiles,
     events: VecDeque<ContextEvent>,
     config: ContextConfig,
     active_dir: Option<PathBuf>,
+    /// Session-only pauses on top of the config (never saved)
+    watcher_paused: bool,
+    git_paused: bool,
 }
 
 impl ContextManager {
     pub fn new(config: ContextConfig) -> Self {
-        let watcher = if config.enable_file_watcher {
-            let watcher_config = WatcherConfig {
-                debounce: Duration::from_millis(config.watcher_debounce_ms),
-                ignore_patterns: vec![],
-                max_buffer_size: 100,
-            };
-            match FileWatcherService::new(watcher_config) {
-                Ok(w) => {
-                    log::info!("File watcher service initialized");
-                    Some(w)
-                }
-                Err(e) => {
-                    log::warn!("Failed to create file watcher: {}", e);
-                    None
-                }
-            }
-        } else {
-            None
-        };
-
-        let git_cache = GitStatusCache::new(Duration::from_secs(config.git_refresh_interval_secs));
+        let git_cache = new_git_cache(&config);
         let pinned = PinnedFiles::new(config.max_pinned_files);
 
-        Self {
-            watcher,
+        let mut manager = Self {
+            watcher: None,
+            last_watcher_error: None,
             git_cache,
+            fetch: FetchTracker::default(),
             pinned,
             events: VecDeque::new(),
             config,
             active_dir: None,
+            watcher_paused: false,
+            git_paused: false,
+        };
+        manager.start_watcher();
+        manager
+    }
+
+    /// File watching is enabled and not paused
+    fn watcher_on(&self) -> bool {
+        self.config.enable_file_watcher && !self.watcher_paused
+    }
+
+    /// Git status is enabled and not paused
+    fn git_on(&self) -> bool {
+        self.config.enable_git_status && !self.git_paused
+    }
+
+    /// Create the file watcher service if enabled
+    fn start_watcher(&mut self) {
+        if !self.watcher_on() {
+            return;
+        }
+
+        let watcher_config = WatcherConfig {
+            debounce: Duration::from_millis(self.config.watcher_debounce_ms),
+            ignore_patterns: vec![],
+            max_buffer_size: 100,
+        };
+        match FileWatcherService::new(watcher_config) {
+            Ok(w) => {
+                log::info!("File watcher service initialized");
+                self.watcher = Some(w);
+            }
+            Err(e) => {
+                log::warn!("Failed to create file watcher: {}", e);
+                self.last_watcher_error = Some(e);
+            }
+        }
+    }
+
+    /// Drop and recreate the file watcher, re-watching the active directory
+    pub fn restart_watcher(&mut self) {
+        self.watcher = None;
+        self.last_watcher_error = None;
+        self.start_watcher();
+
+        if let (Some(watcher), Some(dir)) = (&mut self.watcher, &self.active_dir) {
+            if let Err(e) = watcher.watch(dir) {
+                log::warn!("Failed to watch directory {:?}: {}", dir, e);
+                self.last_watcher_error = Some(e);
+            }
+        }
+    }
+
+    /// Apply new settings, keeping pins (up to the new limit) and the
+    /// active directory
+    pub fn reconfigure(&mut self, config: ContextConfig) {
+        let git_was_on = self.git_on();
+        let old = std::mem::replace(&mut self.config, config);
+
+        if self.git_on() != git_was_on {
+            self.reset_git_cache();
+        } else {
+            self.git_cache.set_refresh_interval(Duration::from_secs(self.config.git_refresh_interval_secs));
+            self.git_cache.set_scoped_threshold(self.config.git_scoped_threshold());
+            self.git_cache.set_full_refresh_interval(Duration::from_secs(self.config.git_full_refresh_interval_secs));
         }
+
+        for path in self.pinned.set_max_files(self.config.max_pinned_files) {
+            self.events.push_back(ContextEvent::FileUnpinned(path));
+        }
+
+        if self.config.enable_file_watcher != old.enable_file_watcher
+            || self.config.watcher_debounce_ms != old.watcher_debounce_ms
+        {
+            self.restart_watcher();
+        }
+
+        log::info!("Context settings applied: {:?}", self.config);
+    }
+
+    /// Start from a clean git cache so stale statuses vanish, re-rooted at
+    /// the active directory if git status is on
+    fn reset_git_cache(&mut self) {
+        self.git_cache = new_git_cache(&self.config);
+        if let (true, Some(dir)) = (self.git_on(), &self.active_dir) {
+            self.git_cache.set_root(dir);
+        }
+        self.events.push_back(ContextEvent::GitStatusUpdated);
+    }
+
+    /// Stop watching for good (quitting)
+    pub fn stop_watcher(&mut self) {
+        self.watcher_paused = true;
+        self.watcher = None;
+    }
+
+    /// Pause or resume the file watcher for this session
+    ///
+    /// Resuming watches the active directory again. Has no effect while
+    /// watching is disabled in the config.
+    pub fn set_watcher_enabled(&mut self, enabled: bool) {
+        let was_on = self.watcher_on();
+        self.watcher_paused = !enabled;
+        if self.watcher_on() != was_on {
+            self.restart_watcher();
+        }
+    }
+
+    /// Pause or resume git status for this session
+    ///
+    /// Resuming re-reads the repository of the active directory. Has no
+    /// effect while git status is disabled in the config.
+    pub fn set_git_enabled(&mut self, enabled: bool) {
+        let was_on = self.git_on();
+        self.git_paused = !enabled;
+        if self.git_on() != was_on {
+            self.reset_git_cache();
+        }
+    }
+
+    pub fn is_git_paused(&self) -> bool {
+        self.git_paused
     }
 
     pub fn set_active_directory(&mut self, path: &Path) {
@@ -64,10 +189,11 @@ impl ContextManager {
         if let Some(watcher) = &mut self.watcher {
             if let Err(e) = watcher.watch(&canonical) {
                 log::warn!("Failed to watch directory {:?}: {}", canonical, e);
+                self.last_watcher_error = Some(e);
             }
         }
 
-        if self.config.enable_git_status {
+        if self.git_on() {
             self.git_cache.set_root(&canonical);
         }
 
@@ -75,47 +201,39 @@ impl ContextManager {
     }
 
     pub fn poll(&mut self) -> Vec<ContextEvent> {
+        self.poll_at(Instant::now())
+    }
+
+    /// `poll` as if the time were `now`, for the watcher's debouncing
+    pub fn poll_at(&mut self, now: Instant) -> Vec<ContextEvent> {
         let mut result = Vec::new();
 
         if let Some(watcher) = &mut self.watcher {
-            for event in watcher.poll() {
+            for event in watcher.poll(now) {
+                let affected_dir = event.affected_dir();
                 match event {
                     WatcherEvent::Created(path)
                     | WatcherEvent::Modified(path)
                     | WatcherEvent::Deleted(path)
-                    | WatcherEvent::Changed(path) => {
-                        let affected_dir = path
-                            .parent()
-                            .map(|p| p.to_path_buf())
-                            .unwrap_or_else(|| path.clone());
+                    | WatcherEvent::Changed(path)
+                    | WatcherEvent::Renamed(_, path) => {
+                        let affected_dir = affected_dir.unwrap_or_else(|| path.clone());
 
                         result.push(ContextEvent::FileSystemChanged {
-                            path: path.clone(),
-                            affected_dir,
-                        });
-
-                        self.git_cache.mark_dirty();
-                    }
-                    WatcherEvent::Renamed(_, new_path) => {
-                        let affected_dir = new_path
-                            .parent()
-                            .map(|p| p.to_path_buf())
-                            .unwrap_or_else(|| new_path.clone());
-
-                        result.push(ContextEvent::FileSystemChanged {
-                            path: new_path,
+                            path,
                             affected_dir,
                         });
                         self.git_cache.mark_dirty();
                     }
                     WatcherEvent::Error(e) => {
+                        self.last_watcher_error = Some(e.clone());
                         result.push(ContextEvent::Error(e));
                     }
                 }
             }
         }
 
-        if self.config.enable_git_status && self.git_cache.refresh_if_needed() {
+        if self.git_on() && self.git_cache.refresh_if_needed() {
             result.push(ContextEvent::GitStatusUpdated);
         }
 
@@ -153,20 +271,95 @@ impl ContextManager {
         self.pinned.iter()
     }
 
+    pub fn pinned_count(&self) -> usize {
+        self.pinned.len()
+    }
+
     pub fn get_git_status(&self, path: &Path) -> FileGitStatus {
         self.git_cache.get_status_for_absolute(path)
     }
 
+    /// Statuses for bulk lookups such as the sidebar tree
+    pub fn git_cache(&self) -> &GitStatusCache {
+        &self.git_cache
+    }
+
     pub fn repo_status(&self) -> Option<&RepoStatus> {
         self.git_cache.repo_status()
     }
 
+    /// Directories expanded in the sidebar and open files; on huge repos
+    /// only these get statuses between full passes
+    pub fn set_paths_of_interest(&mut self, paths: Vec<PathBuf>) {
+        self.git_cache.set_paths_of_interest(paths);
+    }
+
     pub fn refresh_git_status(&mut self) {
         self.git_cache.refresh();
     }
 
+    /// Background fetch interval, if enabled
+    fn fetch_interval(&self) -> Option<Duration> {
+        match self.config.git_auto_fetch_interval_mins {
+            0 => None,
+            _ if !self.git_on() => None,
+            mins => Some(Duration::from_secs(mins * 60)),
+        }
+    }
+
+    /// Root of the active repo if its background fetch is due; the caller
+    /// runs the fetch and reports back with [`Self::fetch_finished`]
+    pub fn take_due_fetch(&mut self) -> Option<PathBuf> {
+        let interval = self.fetch_interval()?;
+        let status = self.git_cache.repo_status()?;
+        self.fetch
+            .start_if_due(&status.root, status.last_fetch, interval)
+            .then(|| status.root.clone())
+    }
+
+    /// Root of the active repo to fetch right away, unless one is running
+    pub fn request_fetch(&mut self) -> Option<PathBuf> {
+        self.fetch_interval()?;
+        let root = self.git_cache.repo_status()?.root.clone();
+        self.fetch.start_now(&root).then_some(root)
+    }
+
+    pub fn fetch_finished(&mut self, root: &Path, result: Result<(), String>) {
+        match &result {
+            // New remote refs change ahead/behind
+            Ok(()) => self.git_cache.mark_dirty(),
+            Err(e) => log::info!("Background fetch in {:?} failed: {}", root, e),
+        }
+        self.fetch.finish(root, result);
+    }
+
+    /// Fetch freshness of the active repo, if background fetch is enabled
+    pub fn fetch_status(&self) -> Option<FetchStatus> {
+        let interval = self.fetch_interval()?;
+        let status = self.git_cache.repo_status()?;
+        Some(self.fetch.status(status.last_fetch, interval))
+    }
+
     pub fn is_git_available(&self) -> bool {
-        self.config.enable_git_status && self.git_cache.is_in_repo()
+        self.git_on() && self.git_cache.is_in_repo()
+    }
+
+    pub fn is_git_enabled(&self) -> bool {
+        self.config.enable_git_status
+    }
+
+    pub fn last_refresh_info(&self) -> Option<RefreshInfo> {
+        self.git_cache.last_refresh_info()
+    }
+
+    pub fn watcher_state(&self) -> WatcherState {
+        WatcherState {
+            enabled: self.config.enable_file_watcher,
+            paused: self.watcher_paused,
+            active: self.watcher.as_ref().is_some_and(|w| w.is_active()),
+            watched_paths: self.watcher.as_ref().map_or(0, |w| w.watched_paths().len()),
+            last_error: self.last_watcher_error.clone(),
+        }
     }
 
     pub fn active_directory(&self) -> Option<&Path> {
@@ -178,4 +371,152 @@ impl Default for ContextManager {
     fn default() -> Self {
         Self::new(ContextConfig::default())
     }
-}
\ No newline at end of file
+}
+
+/// Git cache set up from the refresh settings in `config`
+fn new_git_cache(config: &ContextConfig) -> GitStatusCache {
+    let mut cache = GitStatusCache::new(Duration::from_secs(config.git_refresh_interval_secs));
+    cache.set_scoped_threshold(config.git_scoped_threshold());
+    cache.set_full_refresh_interval(Duration::from_secs(config.git_full_refresh_interval_secs));
+    cache
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(enable_file_watcher: bool) -> ContextConfig {
+        ContextConfig {
+            enable_file_watcher,
+            enable_git_status: false,
+            ..ContextConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_watcher_state_disabled() {
+        let mut manager = ContextManager::new(config(false));
+        manager.restart_watcher();
+
+        let state = manager.watcher_state();
+        assert!(!state.enabled);
+        assert!(!state.active);
+        assert_eq!(state.watched_paths, 0);
+        assert!(state.last_error.is_none());
+    }
+
+    #[test]
+    fn test_reconfigure_keeps_pins_and_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let files: Vec<_> = (0..3).map(|i| dir.path().join(format!("f{}.txt", i))).collect();
+        for f in &files {
+            std::fs::write(f, "test").unwrap();
+        }
+
+        let mut manager = ContextManager::new(config(false));
+        manager.set_active_directory(dir.path());
+        for f in &files {
+            manager.pin_file(f.clone());
+        }
+        manager.poll();
+
+        manager.reconfigure(ContextConfig {
+            max_pinned_files: 2,
+            ..config(true)
+        });
+
+        assert_eq!(manager.pinned_count(), 2);
+        assert!(!manager.is_pinned(&files[0]));
+        assert!(manager.is_pinned(&files[2]));
+        assert!(manager.active_directory().is_some());
+
+        let state = manager.watcher_state();
+        assert!(state.enabled);
+        assert!(state.active);
+        assert_eq!(state.watched_paths, 1);
+
+        let events = manager.poll();
+        assert!(events.iter().any(|e| matches!(e, ContextEvent::FileUnpinned(p) if p.ends_with("f0.txt"))));
+
+        manager.reconfigure(config(false));
+        assert!(!manager.watcher_state().active);
+        assert_eq!(manager.pinned_count(), 2);
+    }
+
+    #[test]
+    fn test_restart_watcher_rewatches_active_dir() {
+        let dir = tempfile::TempDir::new().unwrap();
+
+        let mut manager = ContextManager::new(config(true));
+        manager.set_active_directory(dir.path());
+        manager.restart_watcher();
+
+        let state = manager.watcher_state();
+        assert!(state.active);
+        assert_eq!(state.watched_paths, 1);
+        assert!(state.last_error.is_none());
+    }
+
+    #[test]
+    fn test_pausing_watcher_stops_events_and_resume_rewatches() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut manager = ContextManager::new(config(true));
+        manager.set_active_directory(dir.path());
+        manager.pin_file(dir.path().join("keep.txt"));
+
+        manager.set_watcher_enabled(false);
+        let state = manager.watcher_state();
+        assert!(state.enabled && state.paused);
+        assert!(!state.active);
+        assert_eq!(state.watched_paths, 0);
+
+        // Long past the debounce, so a running watcher would have flushed
+        std::fs::write(dir.path().join("new.txt"), "x").unwrap();
+        let later = Instant::now() + Duration::from_secs(60);
+        assert!(!manager.poll_at(later).iter().any(|e| matches!(e, ContextEvent::FileSystemChanged { .. })));
+
+        manager.set_watcher_enabled(true);
+        let state = manager.watcher_state();
+        assert!(state.active && !state.paused);
+        assert_eq!(state.watched_paths, 1);
+        assert!(manager.is_pinned(&dir.path().join("keep.txt")));
+    }
+
+    #[test]
+    fn test_pausing_git_clears_status_and_resume_rereads_repo() {
+        let dir = tempfile::TempDir::new().unwrap();
+        git2::Repository::init(dir.path()).unwrap();
+        let mut manager = ContextManager::new(ContextConfig {
+            enable_git_status: true,
+            ..config(false)
+        });
+        manager.set_active_directory(dir.path());
+        manager.poll();
+        assert!(manager.is_git_available());
+
+        manager.set_git_enabled(false);
+        assert!(manager.is_git_paused());
+        assert!(!manager.is_git_available());
+        // One update so the sidebar drops its statuses, then silence
+        assert_eq!(manager.poll().iter().filter(|e| matches!(e, ContextEvent::GitStatusUpdated)).count(), 1);
+        manager.refresh_git_status();
+        assert!(manager.poll().is_empty());
+
+        manager.set_git_enabled(true);
+        assert!(manager.is_git_available());
+        manager.poll();
+        let root = manager.repo_status().unwrap().root.canonicalize().unwrap();
+        assert_eq!(root, dir.path().canonicalize().unwrap());
+    }
+
+    #[test]
+    fn test_resume_does_not_override_config() {
+        let mut manager = ContextManager::new(config(false));
+        manager.set_watcher_enabled(false);
+        manager.set_watcher_enabled(true);
+        assert!(!manager.watcher_state().active);
+
+        manager.set_git_enabled(true);
+        assert!(manager.poll().is_empty());
+    }
+}
@@ -18,6 +18,7 @@
 //! - [`PinnedFiles`](pinned::PinnedFiles) - File pinning with LRU eviction (max 50 files)
 //! - [`FileWatcherService`](crate::watcher::FileWatcherService) - File system event monitoring
 //! - [`ContextEvent`](events::ContextEvent) - Event type for UI updates
+//! - [`ContextSnapshot`](snapshot::ContextSnapshot) - Pinned files as one document for CLI tools
 //!
 //! ## Usage Example
 //!
@@ -50,29 +51,53 @@
 //!     max_pinned_files: 50,           // Maximum pinned files
 //!     enable_file_watcher: true,      // Enable file system watching
 //!     enable_git_status: true,        // Enable git integration
+//!     git_auto_fetch_interval_mins: 0, // Background `git fetch` (0 = off)
+//!     git_scoped_threshold_ms: 1500,   // Scope refreshes when status is slower (0 = off)
+//!     git_full_refresh_interval_secs: 120, // Full pass for counts while scoped
+//!     snapshot_ttl_mins: 60,           // Keep snapshot temp files an hour
 //! };
 //! ```
 
 use std::time::Duration;
 
 pub mod events;
+pub mod fetch;
 pub mod git;
 pub mod manager;
 pub mod pinned;
+pub mod snapshot;
 
 pub use events::ContextEvent;
-pub use git::{FileGitStatus, GitStatusCache, RepoStatus};
-pub use manager::ContextManager;
+pub use fetch::FetchStatus;
+pub use git::{FileGitStatus, GitStatusCache, RefreshInfo, RepoStatus};
+pub use manager::{ContextManager, WatcherState};
 pub use pinned::{PinReason, PinnedFile, PinnedFiles};
+pub use snapshot::ContextSnapshot;
 
 /// Configuration for context system behavior
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct ContextConfig {
     pub watcher_debounce_ms: u64,
     pub git_refresh_interval_secs: u64,
     pub max_pinned_files: usize,
     pub enable_file_watcher: bool,
     pub enable_git_status: bool,
+    /// Minutes between background fetches of the active repo (0 = off)
+    pub git_auto_fetch_interval_mins: u64,
+    /// Full `git status` runs at least this slow switch to refreshing only
+    /// the paths shown in the UI (0 = off)
+    pub git_scoped_threshold_ms: u64,
+    /// Seconds between full passes for the summary counts while scoped
+    pub git_full_refresh_interval_secs: u64,
+    /// Minutes snapshot files written for terminals are kept
+    pub snapshot_ttl_mins: u64,
+}
+
+impl ContextConfig {
+    pub fn git_scoped_threshold(&self) -> Option<Duration> {
+        (self.git_scoped_threshold_ms > 0).then(|| Duration::from_millis(self.git_scoped_threshold_ms))
+    }
 }
 
 impl Default for ContextConfig {
@@ -83,6 +108,10 @@ impl Default for ContextConfig {
             max_pinned_files: 50,
             enable_file_watcher: true,
             enable_git_status: true,
+            git_auto_fetch_interval_mins: 0,
+            git_scoped_threshold_ms: 1500,
+            git_full_refresh_interval_secs: 120,
+            snapshot_ttl_mins: 60,
         }
     }
 }
@@ -168,6 +168,23 @@ impl PinnedFiles {
         }
     }
 
+    /// Change the capacity, evicting least recently used files over it
+    ///
+    /// Returns the evicted paths, oldest first.
+    pub fn set_max_files(&mut self, max_files: usize) -> Vec<PathBuf> {
+        self.max_files = max_files.max(1);
+
+        let mut evicted = Vec::new();
+        while self.files.len() > self.max_files {
+            let Some(oldest) = self.lru_order.pop_front() else {
+                break;
+            };
+            self.files.remove(&oldest);
+            evicted.push(oldest);
+        }
+        evicted
+    }
+
     fn evict_oldest(&mut self) {
         if let Some(oldest) = self.lru_order.pop_front() {
             self.files.remove(&oldest);
@@ -243,4 +260,30 @@ mod tests {
         assert!(!pinned.is_pinned(&file1)); // Evicted
         assert!(pinned.is_pinned(&file3));
     }
+
+    #[test]
+    fn test_shrinking_evicts_least_recent() {
+        let temp = TempDir::new().unwrap();
+        let files: Vec<_> = (0..4).map(|i| temp.path().join(format!("f{}.txt", i))).collect();
+        for f in &files {
+            fs::write(f, "test").unwrap();
+        }
+
+        let mut pinned = PinnedFiles::new(10);
+        for f in &files {
+            pinned.pin(f.clone(), PinReason::Manual);
+        }
+        pinned.touch(&files[0]);
+
+        let evicted = pinned.set_max_files(2);
+        assert_eq!(evicted.len(), 2);
+        assert!(evicted[0].ends_with("f1.txt"));
+        assert!(evicted[1].ends_with("f2.txt"));
+        assert!(pinned.is_pinned(&files[0]));
+        assert!(pinned.is_pinned(&files[3]));
+
+        // Growing keeps everything
+        assert!(pinned.set_max_files(50).is_empty());
+        assert_eq!(pinned.len(), 2);
+    }
 }
@@ -0,0 +1,230 @@
+//! Context Snapshot
+//!
+//! The pinned files of the active project, with their contents, as one
+//! document for CLI tools (aider, llm, sgpt): typed into a terminal as a
+//! temp file path or a here-doc, or sent as JSON over the IPC socket.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+
+use super::ContextManager;
+
+/// Bytes of each file included; longer files are cut
+pub const MAX_FILE_BYTES: usize = 64 * 1024;
+
+/// Snapshot files are named `context-<unix seconds>.md`
+const TEMP_PREFIX: &str = "context-";
+
+/// One pinned file in a snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SnapshotFile {
+    pub path: PathBuf,
+    /// Why it's pinned ("pinned", "edited", "mentioned")
+    pub reason: String,
+    /// UTF-8 contents, `None` for binary or unreadable files
+    pub content: Option<String>,
+    /// The contents stop at `MAX_FILE_BYTES`
+    pub truncated: bool,
+}
+
+/// The active project's context
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ContextSnapshot {
+    pub root: Option<PathBuf>,
+    pub branch: Option<String>,
+    pub files: Vec<SnapshotFile>,
+}
+
+impl ContextSnapshot {
+    /// Read the pinned files of `manager`
+    pub fn build(manager: &ContextManager) -> Self {
+        let repo = manager.repo_status();
+        Self {
+            root: repo.map(|repo| repo.root.clone()).or_else(|| manager.active_directory().map(Path::to_path_buf)),
+            branch: repo.map(|repo| repo.branch.clone()),
+            files: manager.pinned_files().map(|file| read_file(&file.path, file.reason.display())).collect(),
+        }
+    }
+
+    /// Markdown with a fenced block per file
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Project context\n\n");
+        if let Some(root) = &self.root {
+            out.push_str(&format!("Root: `{}`\n", root.display()));
+        }
+        if let Some(branch) = &self.branch {
+            out.push_str(&format!("Branch: `{}`\n", branch));
+        }
+        if self.files.is_empty() {
+            out.push_str("\nNo pinned files.\n");
+        }
+
+        for file in &self.files {
+            let name = self.root
+                .as_deref()
+                .and_then(|root| file.path.strip_prefix(root).ok())
+                .unwrap_or(&file.path);
+            out.push_str(&format!("\n## {} ({})\n\n", name.display(), file.reason));
+            let Some(content) = &file.content else {
+                out.push_str("_Binary or unreadable; not included._\n");
+                continue;
+            };
+            let fence = fence_for(content);
+            let language = file.path.extension().map(|ext| ext.to_string_lossy()).unwrap_or_default();
+            out.push_str(&format!("{}{}\n{}", fence, language, content));
+            if !content.ends_with('\n') {
+                out.push('\n');
+            }
+            if file.truncated {
+                out.push_str("… (truncated)\n");
+            }
+            out.push_str(&fence);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Read `path` for a snapshot, cut at `MAX_FILE_BYTES`
+fn read_file(path: &Path, reason: &str) -> SnapshotFile {
+    let bytes = std::fs::read(path).ok();
+    let truncated = bytes.as_ref().is_some_and(|bytes| bytes.len() > MAX_FILE_BYTES);
+    let content = bytes.and_then(|bytes| {
+        let kept = &bytes[..bytes.len().min(MAX_FILE_BYTES)];
+        match std::str::from_utf8(kept) {
+            Ok(text) => Some(text.to_string()),
+            // Only the cut split a character
+            Err(e) if truncated && e.error_len().is_none() => {
+                Some(String::from_utf8_lossy(&kept[..e.valid_up_to()]).into_owned())
+            }
+            Err(_) => None,
+        }
+    });
+    SnapshotFile { path: path.to_path_buf(), reason: reason.to_string(), content, truncated }
+}
+
+/// A backtick fence longer than any run of backticks in `content`
+fn fence_for(content: &str) -> String {
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// Shell text assigning `content` to `variable` through a quoted here-doc
+///
+/// Quoting the delimiter turns off expansion, so only a line equal to it
+/// could end the document early; a numbered delimiter avoids that.
+pub fn heredoc(variable: &str, content: &str) -> String {
+    let mut delimiter = String::from("VIBETERM_CONTEXT");
+    let mut n = 0;
+    while content.lines().any(|line| line == delimiter) {
+        n += 1;
+        delimiter = format!("VIBETERM_CONTEXT_{}", n);
+    }
+    let newline = if content.ends_with('\n') || content.is_empty() { "" } else { "\n" };
+    format!("{}=$(cat <<'{}'\n{}{}{}\n)\n", variable, delimiter, content, newline, delimiter)
+}
+
+/// `text` as one bracketed paste, so the shell edits it as a whole instead
+/// of running each line as it arrives
+///
+/// An end marker inside `text` would end the paste early, so it's dropped.
+pub fn bracketed_paste(text: &str) -> String {
+    format!("\x1b[200~{}\x1b[201~", text.replace("\x1b[201~", ""))
+}
+
+/// Write `snapshot` to a new `context-<ts>.md` in `dir`
+pub fn write_temp(dir: &Path, snapshot: &ContextSnapshot, now: SystemTime) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let seconds = now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let mut path = dir.join(format!("{}{}.md", TEMP_PREFIX, seconds));
+    let mut n = 1;
+    while path.exists() {
+        path = dir.join(format!("{}{}-{}.md", TEMP_PREFIX, seconds, n));
+        n += 1;
+    }
+    std::fs::write(&path, snapshot.to_markdown())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Delete snapshot files in `dir` older than `ttl`; returns how many
+pub fn clean_up(dir: &Path, ttl: Duration, now: SystemTime) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with(TEMP_PREFIX) && name.ends_with(".md")
+        })
+        .filter(|entry| {
+            entry.metadata()
+                .and_then(|meta| meta.modified())
+                .is_ok_and(|modified| now.duration_since(modified).is_ok_and(|age| age > ttl))
+        })
+        .filter(|entry| std::fs::remove_file(entry.path()).is_ok())
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heredoc_keeps_content_literal() {
+        let content = "echo $HOME `date` \"quoted\" 'single'\n\\n\n";
+        let typed = heredoc("CONTEXT", content);
+        assert_eq!(typed, format!("CONTEXT=$(cat <<'VIBETERM_CONTEXT'\n{}VIBETERM_CONTEXT\n)\n", content));
+
+        // A line that would end the document picks another delimiter
+        let tricky = "a\nVIBETERM_CONTEXT\nVIBETERM_CONTEXT_1\nb";
+        let typed = heredoc("CONTEXT", tricky);
+        assert!(typed.starts_with("CONTEXT=$(cat <<'VIBETERM_CONTEXT_2'\n"));
+        assert!(typed.ends_with("\nb\nVIBETERM_CONTEXT_2\n)\n"));
+    }
+
+    #[test]
+    fn test_bracketed_paste_cannot_be_ended_early() {
+        assert_eq!(bracketed_paste("a\nb\n"), "\x1b[200~a\nb\n\x1b[201~");
+        assert_eq!(bracketed_paste("a\x1b[201~rm -rf ~\n"), "\x1b[200~arm -rf ~\n\x1b[201~");
+    }
+
+    #[test]
+    fn test_markdown_fences_outlast_content() {
+        let snapshot = ContextSnapshot {
+            root: Some(PathBuf::from("/src/app")),
+            branch: Some("main".to_string()),
+            files: vec![SnapshotFile {
+                path: PathBuf::from("/src/app/README.md"),
+                reason: "pinned".to_string(),
+                content: Some("```sh\nmake\n```".to_string()),
+                truncated: false,
+            }],
+        };
+        let markdown = snapshot.to_markdown();
+        assert!(markdown.contains("Branch: `main`"));
+        assert!(markdown.contains("## README.md (pinned)\n\n````md\n```sh\nmake\n```\n````\n"));
+    }
+
+    #[test]
+    fn test_temp_files_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let first = write_temp(dir.path(), &ContextSnapshot::default(), now).unwrap();
+        let second = write_temp(dir.path(), &ContextSnapshot::default(), now).unwrap();
+        assert_ne!(first, second);
+        std::fs::write(dir.path().join("notes.md"), "keep").unwrap();
+
+        assert_eq!(clean_up(dir.path(), Duration::from_secs(60), now), 0);
+        assert_eq!(clean_up(dir.path(), Duration::from_secs(60), now + Duration::from_secs(120)), 2);
+        assert!(!first.exists());
+        assert!(dir.path().join("notes.md").exists());
+    }
+}
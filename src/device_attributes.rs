@@ -0,0 +1,81 @@
+//! Device Attribute Replies
+//!
+//! alacritty_terminal answers primary (`CSI c`) and secondary (`CSI > c`)
+//! device attribute queries itself and hands the reply to the app as a
+//! `PtyWrite` event, like every other reply it makes (cursor position,
+//! text area size...). The DA replies are swapped for the configured
+//! [`TermId`] profile; the others go back to the program unchanged. Only
+//! features VibeTerm draws are advertised: level 2 or 4 and ANSI color (22).
+
+use crate::config::TermId;
+
+/// alacritty_terminal's primary DA reply (VT102)
+const BACKEND_PRIMARY: &str = "\x1b[?6c";
+
+/// Reply to send back to the terminal's program for a reply the backend made
+pub fn reply(text: &str, term_id: TermId) -> String {
+    if text == BACKEND_PRIMARY {
+        primary(term_id).to_string()
+    } else if text.starts_with("\x1b[>") && text.ends_with('c') {
+        secondary(term_id, env!("CARGO_PKG_VERSION"))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Primary DA reply: conformance level and features
+pub fn primary(term_id: TermId) -> &'static str {
+    match term_id {
+        TermId::Xterm | TermId::Vt220 => "\x1b[?62;22c",
+        TermId::Vt420 => "\x1b[?64;22c",
+    }
+}
+
+/// Secondary DA reply: terminal type, VibeTerm's version and ROM cartridge (always 0)
+pub fn secondary(term_id: TermId, version: &str) -> String {
+    let kind = match term_id {
+        TermId::Xterm => 0,
+        TermId::Vt220 => 1,
+        TermId::Vt420 => 41,
+    };
+    format!("\x1b[>{};{};0c", kind, version_number(version))
+}
+
+/// `x.y.z` as the number xterm-style terminals report (x * 10000 + y * 100 + z)
+fn version_number(version: &str) -> u32 {
+    version
+        .split(['.', '-'])
+        .take(3)
+        .map(|part| part.parse::<u32>().unwrap_or(0))
+        .chain(std::iter::repeat(0))
+        .take(3)
+        .fold(0, |number, part| number * 100 + part.min(99))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_format_their_replies() {
+        assert_eq!(primary(TermId::Xterm), "\x1b[?62;22c");
+        assert_eq!(primary(TermId::Vt220), "\x1b[?62;22c");
+        assert_eq!(primary(TermId::Vt420), "\x1b[?64;22c");
+
+        assert_eq!(secondary(TermId::Xterm, "0.12.3"), "\x1b[>0;1203;0c");
+        assert_eq!(secondary(TermId::Vt220, "1.2.0"), "\x1b[>1;10200;0c");
+        assert_eq!(secondary(TermId::Vt420, "0.4.1-beta"), "\x1b[>41;401;0c");
+        assert_eq!(version_number("2"), 20000);
+    }
+
+    #[test]
+    fn test_only_device_attribute_replies_are_replaced() {
+        assert_eq!(reply("\x1b[?6c", TermId::Vt420), "\x1b[?64;22c");
+        assert!(reply("\x1b[>0;2501;1c", TermId::Vt220).starts_with("\x1b[>1;"));
+
+        // Cursor position and text area size reports are the program's answers
+        assert_eq!(reply("\x1b[12;40R", TermId::Vt220), "\x1b[12;40R");
+        assert_eq!(reply("\x1b[8;24;80t", TermId::Xterm), "\x1b[8;24;80t");
+        assert_eq!(reply("\x1b[?0u", TermId::Xterm), "\x1b[?0u");
+    }
+}
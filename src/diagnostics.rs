@@ -0,0 +1,344 @@
+//! Compiler Diagnostics in Terminal Output
+//!
+//! Finds `path:line[:col]` locations printed by compilers and test runners
+//! (rustc, gcc/clang, tsc, pytest and Python tracebacks) in a terminal's
+//! output since its last command, so they can be visited one by one.
+//!
+//! A scan copies the output on the UI thread; parsing it and checking which
+//! files exist happen in the background (`find_diagnostics`).
+
+use alacritty_terminal::grid::{Dimensions, Grid};
+use alacritty_terminal::index::Line;
+use alacritty_terminal::term::cell::Cell;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::session_log::LineCapture;
+
+/// Most diagnostics kept per pane
+const MAX_DIAGNOSTICS: usize = 200;
+
+/// Most output lines scanned per pane
+const MAX_SCAN_LINES: i32 = 5000;
+
+/// A source location printed in terminal output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// As printed (relative to the terminal's directory) until resolved
+    pub path: PathBuf,
+    pub line: u32,
+    pub column: Option<u32>,
+}
+
+/// Characters allowed in a path, with an optional Windows drive prefix
+const PATH: &str = r"(?:[A-Za-z]:[\\/])?[\w.\-+@~/\\]+";
+
+/// `path:line[:col]` (rustc, gcc, pytest, tsc --pretty)
+fn colon_format() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(&format!(r#"(?:^|[\s"'(\[<=])({PATH}):(\d+)(?::(\d+))?"#)).unwrap())
+}
+
+/// `path(line,col)` (tsc, MSBuild)
+fn paren_format() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(&format!(r#"(?:^|[\s"'\[<=])({PATH})\((\d+),(\d+)\)"#)).unwrap())
+}
+
+/// `File "path", line N` (Python tracebacks)
+fn python_format() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"File "([^"]+)", line (\d+)"#).unwrap())
+}
+
+/// Source locations in one line of output
+pub fn parse_line(text: &str) -> Vec<Diagnostic> {
+    let mut found = Vec::new();
+    for re in [paren_format(), colon_format(), python_format()] {
+        for caps in re.captures_iter(text) {
+            let path = &caps[1];
+            // Times like 12:30:45 aren't paths
+            if !path.chars().any(char::is_alphabetic) {
+                continue;
+            }
+            let Ok(line) = caps[2].parse() else {
+                continue;
+            };
+            let column = caps.get(3).and_then(|m| m.as_str().parse().ok());
+            let diagnostic = Diagnostic { path: PathBuf::from(path), line, column };
+            if !found.contains(&diagnostic) {
+                found.push(diagnostic);
+            }
+        }
+    }
+    found
+}
+
+/// Absolute path of a printed location, relative to `cwd`
+fn resolve(path: &Path, cwd: &Path) -> PathBuf {
+    // Windows-style separators from cross-platform tools
+    #[cfg(not(windows))]
+    let path = PathBuf::from(path.to_string_lossy().replace('\\', "/"));
+    #[cfg(windows)]
+    let path = path.to_path_buf();
+
+    if path.is_absolute() {
+        path
+    } else {
+        cwd.join(path)
+    }
+}
+
+/// Locations of existing files in `output`, relative to `cwd` (blocking)
+pub fn find_diagnostics(output: &str, cwd: &Path) -> Vec<Diagnostic> {
+    let mut items: Vec<Diagnostic> = Vec::new();
+    for text in output.lines() {
+        for mut diagnostic in parse_line(text) {
+            diagnostic.path = resolve(&diagnostic.path, cwd);
+            if !items.contains(&diagnostic) && diagnostic.path.is_file() {
+                items.push(diagnostic);
+                if items.len() == MAX_DIAGNOSTICS {
+                    return items;
+                }
+            }
+        }
+    }
+    items
+}
+
+/// Output copied for a background scan
+#[derive(Debug)]
+pub struct ScanRequest {
+    /// Which command the output belongs to
+    pub command: u64,
+    pub output: String,
+}
+
+/// Diagnostics in one terminal's output since its last command started
+#[derive(Debug)]
+pub struct PaneDiagnostics {
+    items: Vec<Diagnostic>,
+    /// Index of the next diagnostic to visit
+    next: usize,
+    /// First grid line of the command's output (negative is history)
+    start: i32,
+    /// Follows rows scrolling into the history to keep `start` in place
+    rows: LineCapture,
+    /// Counts commands, so a scan finishing after the next one started is dropped
+    command: u64,
+    /// A background scan hasn't reported back yet
+    scanning: bool,
+}
+
+impl Default for PaneDiagnostics {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            next: 0,
+            // All output until the first command
+            start: i32::MIN,
+            rows: LineCapture::default(),
+            command: 0,
+            scanning: false,
+        }
+    }
+}
+
+impl PaneDiagnostics {
+    /// A command was submitted on the cursor line; older output is dropped
+    pub fn command_started(&mut self, grid: &Grid<Cell>) {
+        self.items.clear();
+        self.next = 0;
+        self.start = grid.cursor.point.line.0 + 1;
+        self.rows = LineCapture::new(grid);
+        self.command += 1;
+        self.scanning = false;
+    }
+
+    /// Copy the command's output for a background scan; `None` while the
+    /// previous one is still running
+    pub fn begin_scan(&mut self, grid: &Grid<Cell>) -> Option<ScanRequest> {
+        let top = -(grid.history_size() as i32);
+        self.start = match self.rows.advance(grid) {
+            Some(rows) => self.start.saturating_sub(rows as i32),
+            None => top,
+        };
+        if self.scanning {
+            return None;
+        }
+        self.scanning = true;
+
+        let cursor = grid.cursor.point.line.0;
+        let from = self.start.max(top).max(cursor - MAX_SCAN_LINES);
+        Some(ScanRequest { command: self.command, output: crate::session_log::text_since(grid, Line(from)) })
+    }
+
+    /// Take the result of a background scan of `command`'s output
+    pub fn finish_scan(&mut self, command: u64, items: Vec<Diagnostic>) {
+        if command != self.command {
+            return;
+        }
+        self.scanning = false;
+        if self.next > items.len() {
+            self.next = 0;
+        }
+        self.items = items;
+    }
+
+    /// Next diagnostic, wrapping around to the first
+    pub fn next_diagnostic(&mut self) -> Option<Diagnostic> {
+        if self.items.is_empty() {
+            return None;
+        }
+        if self.next >= self.items.len() {
+            self.next = 0;
+        }
+        let diagnostic = self.items[self.next].clone();
+        self.next += 1;
+        Some(diagnostic)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alacritty_terminal::index::Column;
+    use tempfile::TempDir;
+
+    fn location(path: &str, line: u32, column: Option<u32>) -> Diagnostic {
+        Diagnostic { path: PathBuf::from(path), line, column }
+    }
+
+    #[test]
+    fn test_rustc_format() {
+        assert_eq!(parse_line("  --> src/app.rs:421:17"), vec![location("src/app.rs", 421, Some(17))]);
+        assert_eq!(parse_line("   ::: src/layout.rs:12:5"), vec![location("src/layout.rs", 12, Some(5))]);
+        assert_eq!(
+            parse_line("thread 'main' panicked at src/main.rs:3:5:"),
+            vec![location("src/main.rs", 3, Some(5))],
+        );
+    }
+
+    #[test]
+    fn test_gcc_format() {
+        assert_eq!(
+            parse_line("src/main.c:12:5: error: expected ';' before '}' token"),
+            vec![location("src/main.c", 12, Some(5))],
+        );
+        assert_eq!(parse_line("main.c:7: warning: unused"), vec![location("main.c", 7, None)]);
+        assert_eq!(
+            parse_line("In file included from /usr/include/stdio.h:27,"),
+            vec![location("/usr/include/stdio.h", 27, None)],
+        );
+    }
+
+    #[test]
+    fn test_tsc_formats() {
+        assert_eq!(
+            parse_line("src/index.ts(12,5): error TS2322: Type 'string' is not assignable"),
+            vec![location("src/index.ts", 12, Some(5))],
+        );
+        assert_eq!(
+            parse_line("src/index.ts:12:5 - error TS2322: Type 'string' is not assignable"),
+            vec![location("src/index.ts", 12, Some(5))],
+        );
+    }
+
+    #[test]
+    fn test_pytest_formats() {
+        assert_eq!(
+            parse_line("tests/test_api.py:42: AssertionError"),
+            vec![location("tests/test_api.py", 42, None)],
+        );
+        assert_eq!(
+            parse_line(r#"  File "/home/me/app/main.py", line 8, in <module>"#),
+            vec![location("/home/me/app/main.py", 8, None)],
+        );
+        assert_eq!(
+            parse_line("FAILED tests/test_api.py::test_get - assert 1 == 2"),
+            vec![],
+        );
+    }
+
+    #[test]
+    fn test_windows_paths() {
+        assert_eq!(
+            parse_line(r"C:\work\app\src\main.rs:10:5: error"),
+            vec![location(r"C:\work\app\src\main.rs", 10, Some(5))],
+        );
+        assert_eq!(
+            parse_line(r"  --> src\lib.rs:3:1"),
+            vec![location(r"src\lib.rs", 3, Some(1))],
+        );
+        assert_eq!(
+            parse_line(r"D:/proj/index.ts(4,2): error TS1005"),
+            vec![location("D:/proj/index.ts", 4, Some(2))],
+        );
+    }
+
+    #[test]
+    fn test_non_locations_are_ignored() {
+        assert!(parse_line("Finished at 12:30:45").is_empty());
+        assert!(parse_line("listening on http://localhost:8080").is_empty());
+        assert!(parse_line("error: could not compile `vibeterm`").is_empty());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_resolve_relative_and_backslash_paths() {
+        let cwd = Path::new("/work/app");
+        assert_eq!(resolve(Path::new("src/main.rs"), cwd), PathBuf::from("/work/app/src/main.rs"));
+        assert_eq!(resolve(Path::new(r"src\main.rs"), cwd), PathBuf::from("/work/app/src/main.rs"));
+        assert_eq!(resolve(Path::new("/etc/hosts"), cwd), PathBuf::from("/etc/hosts"));
+    }
+
+    #[test]
+    fn test_scan_keeps_existing_files_and_cycles() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir(temp.path().join("src")).unwrap();
+        std::fs::write(temp.path().join("src/a.rs"), "").unwrap();
+        std::fs::write(temp.path().join("src/b.rs"), "").unwrap();
+
+        let mut grid: Grid<Cell> = Grid::new(4, 40, 10);
+        let lines = ["$ cargo build", " --> src/a.rs:1:2", " --> src/gone.rs:3:4", " --> src/b.rs:5:6"];
+        for (line, text) in lines.iter().enumerate() {
+            for (col, c) in text.chars().enumerate() {
+                grid[Line(line as i32)][Column(col)].c = c;
+            }
+        }
+        grid.cursor.point.line = Line(3);
+
+        let mut diagnostics = PaneDiagnostics::default();
+        let scan = diagnostics.begin_scan(&grid).unwrap();
+        assert!(diagnostics.begin_scan(&grid).is_none());
+        diagnostics.finish_scan(scan.command, find_diagnostics(&scan.output, temp.path()));
+        assert_eq!(diagnostics.len(), 2);
+
+        let first = diagnostics.next_diagnostic().unwrap();
+        assert_eq!(first.path, temp.path().join("src/a.rs"));
+        assert_eq!((first.line, first.column), (1, Some(2)));
+        assert_eq!(diagnostics.next_diagnostic().unwrap().line, 5);
+        assert_eq!(diagnostics.next_diagnostic().unwrap().line, 1);
+
+        // A scan of the previous command finishing late is dropped
+        let late = diagnostics.begin_scan(&grid).unwrap();
+        diagnostics.command_started(&grid);
+        diagnostics.finish_scan(late.command, find_diagnostics(&late.output, temp.path()));
+        assert!(diagnostics.is_empty());
+
+        // Output before the command's own line is forgotten
+        let scan = diagnostics.begin_scan(&grid).unwrap();
+        diagnostics.finish_scan(scan.command, find_diagnostics(&scan.output, temp.path()));
+        assert!(diagnostics.is_empty());
+        assert!(diagnostics.next_diagnostic().is_none());
+    }
+}
@@ -0,0 +1,194 @@
+//! Directory Hooks
+//!
+//! `terminal.dir_hooks` react to a terminal's tracked directory changing:
+//! a banner over the pane, or a command typed into it (`nvm use` when a
+//! `.nvmrc` is there). Commands only run after the user allowed that hook
+//! once; the consent is kept like project layout consent.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::config::{Config, DirHook, DirHookAction};
+
+/// Does `hook` apply to `dir`?
+pub fn matches(hook: &DirHook, dir: &Path) -> bool {
+    let pattern = hook.matches.trim();
+    if pattern.contains('/') || pattern.starts_with('~') {
+        crate::run_all::glob_regex(pattern).is_some_and(|re| re.is_match(&dir.to_string_lossy()))
+    } else {
+        !pattern.is_empty() && dir.join(pattern).exists()
+    }
+}
+
+/// Text typed for a command hook: a trailing newline only runs it with `auto_exec`
+pub fn typed_text(hook: &DirHook) -> Option<String> {
+    let DirHookAction::Command { command } = &hook.action else {
+        return None;
+    };
+    let text = if hook.auto_exec {
+        command.clone()
+    } else {
+        command.trim_end_matches(['\r', '\n']).to_string()
+    };
+    (!text.trim().is_empty()).then_some(text)
+}
+
+/// Which hooks already fired for a terminal and directory
+#[derive(Debug, Default)]
+pub struct DirHookTracker {
+    /// (terminal ID, hook index, directory) of `once_per_dir` hooks that fired
+    fired: HashSet<(u64, usize, PathBuf)>,
+}
+
+impl DirHookTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Indices of the hooks to fire as terminal `terminal_id` enters `dir`
+    pub fn enter(&mut self, terminal_id: u64, dir: &Path, hooks: &[DirHook]) -> Vec<usize> {
+        self.enter_with(terminal_id, dir, hooks, matches)
+    }
+
+    fn enter_with(
+        &mut self,
+        terminal_id: u64,
+        dir: &Path,
+        hooks: &[DirHook],
+        matches: impl Fn(&DirHook, &Path) -> bool,
+    ) -> Vec<usize> {
+        hooks
+            .iter()
+            .enumerate()
+            .filter(|(_, hook)| matches(hook, dir))
+            .filter(|&(index, hook)| {
+                !hook.once_per_dir || self.fired.insert((terminal_id, index, dir.to_path_buf()))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Drop the state of terminals not in `live`
+    pub fn retain_terminals(&mut self, live: &HashSet<u64>) {
+        self.fired.retain(|(terminal_id, _, _)| live.contains(terminal_id));
+    }
+}
+
+/// Command hooks the user allowed to type into terminals
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirHookConsent {
+    /// [`consent_key`] values
+    pub granted: Vec<String>,
+}
+
+/// What the user agrees to: the hook's match and exactly what it types
+pub fn consent_key(hook: &DirHook) -> String {
+    format!("{}\u{1f}{}", hook.matches.trim(), typed_text(hook).unwrap_or_default())
+}
+
+impl DirHookConsent {
+    /// Get consent file path
+    pub fn state_path() -> PathBuf {
+        Config::config_dir().join("dir_hook_consent.toml")
+    }
+
+    /// Load consent from disk, falling back to none granted
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::state_path())
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save consent to disk
+    pub fn save(&self) -> Result<(), String> {
+        std::fs::create_dir_all(Config::config_dir())
+            .map_err(|e| format!("Failed to create config dir: {}", e))?;
+
+        let toml_string = toml::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize directory hook consent: {}", e))?;
+
+        std::fs::write(Self::state_path(), toml_string)
+            .map_err(|e| format!("Failed to write directory hook consent: {}", e))
+    }
+
+    pub fn is_granted(&self, hook: &DirHook) -> bool {
+        self.granted.contains(&consent_key(hook))
+    }
+
+    /// Remember that the user allowed this hook's command
+    pub fn grant(&mut self, hook: &DirHook) {
+        let key = consent_key(hook);
+        if !self.granted.contains(&key) {
+            self.granted.push(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(matches: &str, once_per_dir: bool) -> DirHook {
+        DirHook {
+            matches: matches.to_string(),
+            action: DirHookAction::Command { command: "nvm use\n".to_string() },
+            once_per_dir,
+            auto_exec: false,
+        }
+    }
+
+    #[test]
+    fn test_marker_file_and_glob_matching() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".nvmrc"), "20\n").unwrap();
+
+        assert!(matches(&hook(".nvmrc", false), dir.path()));
+        assert!(!matches(&hook(".python-version", false), dir.path()));
+        assert!(!matches(&hook("  ", false), dir.path()));
+
+        assert!(matches(&hook("/srv/**", false), Path::new("/srv/api/src")));
+        assert!(matches(&hook("/srv/*", false), Path::new("/srv/api")));
+        assert!(!matches(&hook("/srv/*", false), Path::new("/srv/api/src")));
+    }
+
+    #[test]
+    fn test_once_per_dir_bookkeeping() {
+        let hooks = [hook("/srv/**", true), hook("/srv/**", false), hook("/home/**", true)];
+        let matches_srv = |hook: &DirHook, dir: &Path| hook.matches.starts_with("/srv") && dir.starts_with("/srv");
+        let mut tracker = DirHookTracker::new();
+        let api = Path::new("/srv/api");
+        let web = Path::new("/srv/web");
+
+        assert_eq!(tracker.enter_with(1, api, &hooks, matches_srv), [0, 1]);
+        // Coming back only fires the hook that isn't once per directory
+        assert_eq!(tracker.enter_with(1, api, &hooks, matches_srv), [1]);
+        // Another directory, or another terminal, is a first visit
+        assert_eq!(tracker.enter_with(1, web, &hooks, matches_srv), [0, 1]);
+        assert_eq!(tracker.enter_with(2, api, &hooks, matches_srv), [0, 1]);
+
+        // A closed terminal's state is dropped
+        tracker.retain_terminals(&HashSet::from([2]));
+        assert_eq!(tracker.enter_with(1, api, &hooks, matches_srv), [0, 1]);
+        assert_eq!(tracker.enter_with(2, api, &hooks, matches_srv), [1]);
+    }
+
+    #[test]
+    fn test_commands_only_run_with_auto_exec() {
+        let mut typed = hook(".nvmrc", false);
+        assert_eq!(typed_text(&typed).as_deref(), Some("nvm use"));
+        typed.auto_exec = true;
+        assert_eq!(typed_text(&typed).as_deref(), Some("nvm use\n"));
+
+        let banner = DirHook { action: DirHookAction::Banner { text: "Node".into() }, ..typed.clone() };
+        assert_eq!(typed_text(&banner), None);
+
+        // Consent covers exactly what gets typed
+        let mut consent = DirHookConsent::default();
+        consent.grant(&typed);
+        assert!(consent.is_granted(&typed));
+        typed.auto_exec = false;
+        assert!(!consent.is_granted(&typed));
+    }
+}
@@ -1,10 +1,98 @@
 //! Directory scanning utilities for sidebar file tree
 //!
 //! Provides recursive directory scanning with configurable limits for
-//! depth and file count to prevent excessive resource usage.
+//! depth and file count to prevent excessive resource usage. Which
+//! entries are listed (dotfiles, ignore patterns, `.gitignore`d paths)
+//! follows the workspace's [`TreeVisibility`].
+
+use std::path::{Path, PathBuf};
+use crate::config::UiConfig;
+use crate::project_search::{repo_of, PathGlob};
+use crate::ui::{update_tree_flags, FileEntry};
+
+/// Which entries the sidebar lists, toggled from its header per workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TreeVisibility {
+    /// Dotfiles (unless `show_hidden_files`) and ignored entries are left out
+    #[default]
+    Normal,
+    /// Dotfiles are listed too
+    ShowHidden,
+    /// Everything, with ignored entries marked
+    ShowAll,
+}
 
-use std::path::PathBuf;
-use crate::ui::FileEntry;
+impl TreeVisibility {
+    /// Mode after this one in the header toggle's cycle
+    pub fn next(self) -> Self {
+        match self {
+            TreeVisibility::Normal => TreeVisibility::ShowHidden,
+            TreeVisibility::ShowHidden => TreeVisibility::ShowAll,
+            TreeVisibility::ShowAll => TreeVisibility::Normal,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TreeVisibility::Normal => "Normal",
+            TreeVisibility::ShowHidden => "Show Hidden",
+            TreeVisibility::ShowAll => "Show All",
+        }
+    }
+}
+
+/// What a scan lists
+///
+/// Ignored entries match an ignore pattern (themselves or a parent
+/// directory, relative to `root`) or are `.gitignore`d.
+#[derive(Clone)]
+pub struct ScanOptions {
+    root: PathBuf,
+    show_hidden: bool,
+    show_ignored: bool,
+    ignore_patterns: Vec<PathGlob>,
+}
+
+impl ScanOptions {
+    pub fn new(root: &Path, visibility: TreeVisibility, config: &UiConfig) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            show_hidden: config.show_hidden_files || visibility != TreeVisibility::Normal,
+            show_ignored: visibility == TreeVisibility::ShowAll,
+            ignore_patterns: config.file_tree_ignore_patterns.iter().filter_map(|glob| PathGlob::new(glob)).collect(),
+        }
+    }
+
+    /// Whether to list `path`, and if so whether it's shown as ignored
+    fn classify(&self, path: &Path, is_gitignored: &dyn Fn(&Path) -> bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let hidden = relative.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden && !self.show_hidden {
+            return None;
+        }
+        let ignored = relative
+            .ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .any(|ancestor| self.ignore_patterns.iter().any(|glob| glob.matches(ancestor)))
+            || is_gitignored(path);
+        (!ignored || self.show_ignored).then_some(ignored)
+    }
+}
+
+/// `.gitignore` check for paths under `root`, through its repository
+pub fn gitignore_check(root: &Path) -> impl Fn(&Path) -> bool {
+    let repo = repo_of(root);
+    let root = root.to_path_buf();
+    move |path: &Path| {
+        let Some((repo, root_in_repo)) = &repo else {
+            return false;
+        };
+        let Ok(relative) = path.strip_prefix(&root) else {
+            return false;
+        };
+        repo.is_path_ignored(root_in_repo.join(relative)).unwrap_or(false)
+    }
+}
 
 /// Scan directory recursively with limits (for async loading)
 ///
@@ -12,24 +100,32 @@ use crate::ui::FileEntry;
 /// * `root` - The root directory to scan
 /// * `max_depth` - Maximum recursion depth (0 = root only)
 /// * `max_files` - Maximum total files to include
+/// * `options` - Which entries to list
 ///
 /// # Returns
 /// A vector of `FileEntry` items representing the directory tree
-pub fn scan_directory(root: &PathBuf, max_depth: usize, max_files: usize) -> Vec<FileEntry> {
+pub fn scan_directory(root: &PathBuf, max_depth: usize, max_files: usize, options: &ScanOptions) -> Vec<FileEntry> {
     use std::fs;
 
     let mut entries = Vec::new();
     let mut file_count = 0;
+    let is_gitignored = gitignore_check(root);
+
+    struct Scan<'a> {
+        max_depth: usize,
+        max_files: usize,
+        options: &'a ScanOptions,
+        is_gitignored: &'a dyn Fn(&Path) -> bool,
+    }
 
     fn scan_recursive(
         path: &PathBuf,
         depth: usize,
-        max_depth: usize,
+        scan: &Scan,
         entries: &mut Vec<FileEntry>,
         file_count: &mut usize,
-        max_files: usize,
     ) -> bool {
-        if depth >= max_depth || *file_count >= max_files {
+        if depth >= scan.max_depth || *file_count >= scan.max_files {
             return false;
         }
 
@@ -40,8 +136,8 @@ pub fn scan_directory(root: &PathBuf, max_depth: usize, max_files: usize) -> Vec
         let mut items: Vec<_> = dir_entries.filter_map(|e| e.ok()).collect();
         items.sort_by_key(|e| e.path());
 
-        for (idx, entry) in items.iter().enumerate() {
-            if *file_count >= max_files {
+        for entry in &items {
+            if *file_count >= scan.max_files {
                 return false;
             }
 
@@ -52,28 +148,19 @@ pub fn scan_directory(root: &PathBuf, max_depth: usize, max_files: usize) -> Vec
                 .unwrap_or("?")
                 .to_string();
 
-            // Skip hidden files (starting with .)
-            if name.starts_with('.') {
+            // Skip hidden and ignored entries, depending on the mode
+            let Some(is_ignored) = scan.options.classify(&path, scan.is_gitignored) else {
                 continue;
-            }
+            };
 
-            let is_last = idx == items.len() - 1;
-
-            entries.push(FileEntry {
-                name,
-                path,
-                is_dir,
-                is_expanded: false,
-                depth,
-                is_last,
-                git_status: None,
-                is_pinned: false,
-            });
+            let mut file_entry = FileEntry::new(name, path, is_dir, depth);
+            file_entry.is_ignored = is_ignored;
+            entries.push(file_entry);
 
             *file_count += 1;
 
             if is_dir {
-                if !scan_recursive(&entry.path(), depth + 1, max_depth, entries, file_count, max_files) {
+                if !scan_recursive(&entry.path(), depth + 1, scan, entries, file_count) {
                     return false;
                 }
             }
@@ -82,7 +169,55 @@ pub fn scan_directory(root: &PathBuf, max_depth: usize, max_files: usize) -> Vec
         true
     }
 
-    scan_recursive(root, 0, max_depth, &mut entries, &mut file_count, max_files);
+    let scan = Scan { max_depth, max_files, options, is_gitignored: &is_gitignored };
+    scan_recursive(root, 0, &scan, &mut entries, &mut file_count);
+    // Hidden entries and the file limit change which sibling is last
+    update_tree_flags(&mut entries);
+    entries
+}
+
+/// List one directory for the sidebar: directories first, then files
+///
+/// `is_gitignored` answers for paths in the directory; the sidebar passes
+/// [`crate::context::GitStatusCache::is_ignored`].
+pub fn list_directory(
+    path: &Path,
+    depth: usize,
+    options: &ScanOptions,
+    is_gitignored: &dyn Fn(&Path) -> bool,
+) -> Vec<FileEntry> {
+    let mut entries = Vec::new();
+
+    if let Ok(read_dir) = std::fs::read_dir(path) {
+        let mut items: Vec<_> = read_dir.filter_map(|e| e.ok()).collect();
+
+        // Sort: directories first, then alphabetically
+        items.sort_by(|a, b| {
+            let a_is_dir = a.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            let b_is_dir = b.file_type().map(|t| t.is_dir()).unwrap_or(false);
+
+            match (a_is_dir, b_is_dir) {
+                (true, false) => std::cmp::Ordering::Less,
+                (false, true) => std::cmp::Ordering::Greater,
+                _ => a.file_name().cmp(&b.file_name()),
+            }
+        });
+
+        for item in items {
+            let Some(is_ignored) = options.classify(&item.path(), is_gitignored) else {
+                continue;
+            };
+
+            let name = item.file_name().to_string_lossy().to_string();
+            let is_dir = item.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            let mut entry = FileEntry::new(name, item.path(), is_dir, depth);
+            entry.is_ignored = is_ignored;
+            entries.push(entry);
+        }
+    }
+
+    // Skipped entries don't count as siblings
+    update_tree_flags(&mut entries);
     entries
 }
 
@@ -92,6 +227,14 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
 
+    fn options(root: &Path, visibility: TreeVisibility) -> ScanOptions {
+        ScanOptions::new(root, visibility, &UiConfig::default())
+    }
+
+    fn normal(root: &Path) -> ScanOptions {
+        options(root, TreeVisibility::Normal)
+    }
+
     /// Create a test directory structure for testing
     fn create_test_tree() -> TempDir {
         let temp = TempDir::new().unwrap();
@@ -128,7 +271,7 @@ mod tests {
     #[test]
     fn test_scan_empty_directory() {
         let temp = TempDir::new().unwrap();
-        let entries = scan_directory(&temp.path().to_path_buf(), 10, 1000);
+        let entries = scan_directory(&temp.path().to_path_buf(), 10, 1000, &normal(temp.path()));
         assert!(entries.is_empty());
     }
 
@@ -138,7 +281,7 @@ mod tests {
         let root = temp.path().to_path_buf();
 
         // Depth 1: should only get root level items
-        let entries = scan_directory(&root, 1, 1000);
+        let entries = scan_directory(&root, 1, 1000, &normal(&root));
 
         // Should have root-level items only (dir1, dir2, file1.txt)
         // Hidden dir should be excluded
@@ -146,7 +289,7 @@ mod tests {
         assert!(depths.iter().all(|&d| d == 0), "All entries should be at depth 0");
 
         // Depth 2: should include one level of children
-        let entries = scan_directory(&root, 2, 1000);
+        let entries = scan_directory(&root, 2, 1000, &normal(&root));
         let max_depth = entries.iter().map(|e| e.depth).max().unwrap_or(0);
         assert!(max_depth <= 1, "Max depth should be 1 with max_depth=2");
     }
@@ -157,11 +300,11 @@ mod tests {
         let root = temp.path().to_path_buf();
 
         // Limit to 2 files
-        let entries = scan_directory(&root, 10, 2);
+        let entries = scan_directory(&root, 10, 2, &normal(&root));
         assert!(entries.len() <= 2, "Should have at most 2 entries");
 
         // Limit to 100 files (should get everything except hidden)
-        let entries = scan_directory(&root, 10, 100);
+        let entries = scan_directory(&root, 10, 100, &normal(&root));
         assert!(entries.len() >= 4, "Should have at least 4 visible entries");
     }
 
@@ -170,7 +313,7 @@ mod tests {
         let temp = create_test_tree();
         let root = temp.path().to_path_buf();
 
-        let entries = scan_directory(&root, 10, 1000);
+        let entries = scan_directory(&root, 10, 1000, &normal(&root));
 
         // No hidden directories or files should be present
         let has_hidden = entries.iter().any(|e| e.name.starts_with('.'));
@@ -182,7 +325,7 @@ mod tests {
         let temp = create_test_tree();
         let root = temp.path().to_path_buf();
 
-        let entries = scan_directory(&root, 10, 1000);
+        let entries = scan_directory(&root, 10, 1000, &normal(&root));
 
         // Check that directories are correctly marked
         let dir_names: Vec<&str> = entries
@@ -198,7 +341,87 @@ mod tests {
     #[test]
     fn test_nonexistent_directory() {
         let path = PathBuf::from("/nonexistent/path/that/does/not/exist");
-        let entries = scan_directory(&path, 10, 1000);
+        let entries = scan_directory(&path, 10, 1000, &normal(&path));
         assert!(entries.is_empty(), "Nonexistent path should return empty");
     }
+
+    #[test]
+    fn test_last_flags_match_truncated_tree() {
+        let temp = create_test_tree();
+        let root = temp.path().to_path_buf();
+
+        // The file limit cuts the tree after dir1/file2.txt
+        let entries = scan_directory(&root, 10, 2, &normal(&root));
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["dir1", "file2.txt"]);
+        assert!(entries.iter().all(|e| e.is_last));
+    }
+
+    /// Repository with a .gitignore, a default ignore pattern and dotfiles
+    fn create_repo_tree() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        git2::Repository::init(root).unwrap();
+        fs::write(root.join(".gitignore"), "generated/\n*.tmp\n").unwrap();
+        fs::write(root.join(".env"), "SECRET=1").unwrap();
+        fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("scratch.tmp"), "").unwrap();
+        fs::create_dir_all(root.join("generated/out")).unwrap();
+        fs::write(root.join("generated/out/a.rs"), "").unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join("target/debug/app"), "").unwrap();
+        temp
+    }
+
+    /// Paths relative to `root`, with `!` after ignored ones
+    fn listed(root: &Path, entries: &[FileEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .map(|entry| {
+                let relative = entry.path.strip_prefix(root).unwrap().to_string_lossy().to_string();
+                if entry.is_ignored { format!("{}!", relative) } else { relative }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_scan_modes() {
+        let temp = create_repo_tree();
+        let root = temp.path().to_path_buf();
+        let scan = |visibility| listed(&root, &scan_directory(&root, 10, 1000, &options(&root, visibility)));
+
+        assert_eq!(scan(TreeVisibility::Normal), ["main.rs"]);
+        assert_eq!(scan(TreeVisibility::ShowHidden), [".env", ".gitignore", "main.rs"]);
+
+        let all = scan(TreeVisibility::ShowAll);
+        for expected in [".env", ".gitignore", "main.rs", "scratch.tmp!", "generated!", "generated/out/a.rs!", "target!", "target/debug/app!"] {
+            assert!(all.iter().any(|path| path == expected), "{} missing from {:?}", expected, all);
+        }
+        // The repository itself matches the default ".git" pattern
+        assert!(all.iter().any(|path| path == ".git!"));
+    }
+
+    #[test]
+    fn test_show_hidden_files_config_applies_in_normal_mode() {
+        let temp = create_repo_tree();
+        let root = temp.path();
+        let config = UiConfig { show_hidden_files: true, ..UiConfig::default() };
+        let options = ScanOptions::new(root, TreeVisibility::Normal, &config);
+        assert_eq!(listed(root, &scan_directory(&root.to_path_buf(), 1, 1000, &options)), [".env", ".gitignore", "main.rs"]);
+    }
+
+    #[test]
+    fn test_list_directory_modes() {
+        let temp = create_repo_tree();
+        let root = temp.path();
+        let gitignored = gitignore_check(root);
+
+        let names = |visibility| listed(root, &list_directory(root, 0, &options(root, visibility), &gitignored));
+        assert_eq!(names(TreeVisibility::Normal), ["main.rs"]);
+        assert_eq!(names(TreeVisibility::ShowAll), [".git!", "generated!", "target!", ".env", ".gitignore", "main.rs", "scratch.tmp!"]);
+
+        // Children of an ignored directory are ignored too
+        let children = list_directory(&root.join("target"), 1, &options(root, TreeVisibility::ShowAll), &gitignored);
+        assert_eq!(listed(root, &children), ["target/debug!"]);
+    }
 }
@@ -0,0 +1,97 @@
+//! Dormant Tabs
+//!
+//! Opening many folders at once (a Finder selection, the Dock) would spawn
+//! a shell per tab before the first frame. Past `ui.dormant_tab_batch`,
+//! tabs open dormant instead: only their layout and directories are kept,
+//! and the shells start when the tab is first shown, or for the newest
+//! few once the app has been idle for a while. A restored session brings
+//! back every tab but the active one the same way.
+
+use std::path::PathBuf;
+use std::time::Instant;
+use crate::layout::LayoutSpec;
+
+/// A pane waiting for its tab to wake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DormantPane {
+    /// A shell started in `dir`
+    Terminal {
+        dir: PathBuf,
+        /// Name shown instead of the directory
+        name: Option<String>,
+    },
+    /// A file viewer, loaded when woken
+    File(PathBuf),
+    Empty,
+}
+
+/// What a dormant tab becomes when woken
+#[derive(Debug, Clone)]
+pub struct DormantLayout {
+    /// Arrangement; leaf indices point into `panes`
+    pub spec: LayoutSpec,
+    pub panes: Vec<DormantPane>,
+    /// Index of the pane focused once awake
+    pub focused: usize,
+    /// When the tab was opened, for waking the newest first
+    pub since: Instant,
+}
+
+impl DormantLayout {
+    /// One terminal in `dir`
+    pub fn single(dir: PathBuf, since: Instant) -> Self {
+        Self {
+            spec: LayoutSpec::Pane(0),
+            panes: vec![DormantPane::Terminal { dir, name: None }],
+            focused: 0,
+            since,
+        }
+    }
+}
+
+/// How many of `count` folders opened together start dormant
+///
+/// Within the limit (or with it at 0) all start at once; past it, all but
+/// the last, which is the tab shown.
+pub fn dormant_in_batch(count: usize, batch: usize) -> usize {
+    if batch == 0 || count <= batch {
+        0
+    } else {
+        count - 1
+    }
+}
+
+/// Tabs to wake once the app is idle: the `count` newest of `(tab index, since)`
+pub fn idle_wake_order(dormant: &[(usize, Instant)], count: usize) -> Vec<usize> {
+    let mut newest = dormant.to_vec();
+    // Stable: of tabs opened together, the one further right wakes first
+    newest.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+    newest.into_iter().take(count).map(|(index, _)| index).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_small_batches_start_at_once() {
+        assert_eq!(dormant_in_batch(1, 3), 0);
+        assert_eq!(dormant_in_batch(3, 3), 0);
+        assert_eq!(dormant_in_batch(4, 3), 3);
+        assert_eq!(dormant_in_batch(15, 3), 14);
+        // 0 turns dormant tabs off
+        assert_eq!(dormant_in_batch(15, 0), 0);
+    }
+
+    #[test]
+    fn test_idle_wakes_newest_first() {
+        let start = Instant::now();
+        let later = start + Duration::from_secs(30);
+        let dormant = [(1, start), (2, start), (3, start), (5, later), (6, later)];
+
+        assert_eq!(idle_wake_order(&dormant, 3), [6, 5, 3]);
+        assert_eq!(idle_wake_order(&dormant, 0), Vec::<usize>::new());
+        assert_eq!(idle_wake_order(&dormant, 10).len(), 5);
+    }
+}
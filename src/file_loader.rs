@@ -0,0 +1,308 @@
+//! File Loading for the File Viewer
+//!
+//! Decides how much of a file to read and where: small files are read on
+//! the UI thread, larger ones in the background, and very large ones only
+//! as a head and tail preview. Invalid UTF-8 is replaced instead of failing
+//! the whole file.
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::config::FileViewerConfig;
+
+/// How much of a file the viewer asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Whole file, or head and tail when it's very large
+    Preview,
+    /// Whole file regardless of size
+    Full,
+    /// Only the end of the file
+    Tail,
+}
+
+/// Where and how a file is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadTier {
+    /// Small enough to read on the UI thread
+    Inline,
+    /// Read in the background
+    Background,
+    /// Read only the head and tail, in the background
+    Truncated,
+}
+
+/// Size limits in bytes (from `FileViewerConfig`)
+#[derive(Debug, Clone, Copy)]
+pub struct LoadLimits {
+    /// Files above this are read in the background
+    pub background_bytes: u64,
+    /// Files above this are previewed as head and tail
+    pub truncate_bytes: u64,
+    /// Size of the head and of the tail in a preview
+    pub preview_bytes: u64,
+}
+
+impl LoadLimits {
+    pub fn new(config: &FileViewerConfig) -> Self {
+        Self {
+            background_bytes: config.async_load_mb * 1024 * 1024,
+            truncate_bytes: config.truncate_mb * 1024 * 1024,
+            preview_bytes: config.preview_kb * 1024,
+        }
+    }
+
+    /// Bytes read for a tail view
+    fn tail_bytes(&self) -> u64 {
+        self.background_bytes.max(self.preview_bytes)
+    }
+}
+
+/// Read strategy for a file of `size` bytes
+pub fn load_tier(size: u64, mode: LoadMode, limits: &LoadLimits) -> LoadTier {
+    let read_size = match mode {
+        LoadMode::Tail => size.min(limits.tail_bytes()),
+        LoadMode::Preview | LoadMode::Full => size,
+    };
+    if mode == LoadMode::Preview && size > limits.truncate_bytes {
+        LoadTier::Truncated
+    } else if read_size > limits.background_bytes {
+        LoadTier::Background
+    } else {
+        LoadTier::Inline
+    }
+}
+
+/// File contents prepared for the viewer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLoad {
+    pub content: String,
+    /// Bytes of the file that weren't loaded
+    pub skipped: u64,
+    /// Invalid UTF-8 was replaced
+    pub lossy: bool,
+}
+
+/// Read a file for the viewer (blocking)
+pub fn read_file(path: &Path, mode: LoadMode, limits: &LoadLimits) -> Result<FileLoad, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let size = file.metadata().map_err(|e| format!("Failed to read {}: {}", path.display(), e))?.len();
+    let read_error = |e: std::io::Error| format!("Failed to read {}: {}", path.display(), e);
+
+    match (mode, load_tier(size, mode, limits)) {
+        (LoadMode::Tail, _) if size > limits.tail_bytes() => {
+            let bytes = read_range(&mut file, size - limits.tail_bytes(), limits.tail_bytes()).map_err(read_error)?;
+            let start = char_start(&bytes);
+            let (text, lossy) = decode(&bytes[start..]);
+            Ok(FileLoad {
+                content: text,
+                skipped: size - (bytes.len() - start) as u64,
+                lossy,
+            })
+        }
+        (_, LoadTier::Truncated) => {
+            let head = read_range(&mut file, 0, limits.preview_bytes).map_err(read_error)?;
+            let tail_start = size.saturating_sub(limits.preview_bytes).max(head.len() as u64);
+            let tail = read_range(&mut file, tail_start, size - tail_start).map_err(read_error)?;
+
+            let head = &head[..char_end(&head)];
+            let tail = &tail[char_start(&tail)..];
+            let skipped = size - (head.len() + tail.len()) as u64;
+            let (head_text, head_lossy) = decode(head);
+            let (tail_text, tail_lossy) = decode(tail);
+            Ok(FileLoad {
+                content: format!("{}\n\n[... {} bytes not shown ...]\n\n{}", head_text, skipped, tail_text),
+                skipped,
+                lossy: head_lossy || tail_lossy,
+            })
+        }
+        _ => {
+            let mut bytes = Vec::with_capacity(size as usize);
+            file.read_to_end(&mut bytes).map_err(read_error)?;
+            let (text, lossy) = decode(&bytes);
+            Ok(FileLoad { content: text, skipped: 0, lossy })
+        }
+    }
+}
+
+/// Read up to `len` bytes starting at `offset`
+fn read_range(file: &mut File, offset: u64, len: u64) -> std::io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.take(len).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Text of `bytes`, and whether invalid UTF-8 had to be replaced
+fn decode(bytes: &[u8]) -> (String, bool) {
+    match String::from_utf8_lossy(bytes) {
+        Cow::Borrowed(text) => (text.to_string(), false),
+        Cow::Owned(text) => (text, true),
+    }
+}
+
+/// Length of `bytes` without a code point cut off at the end
+fn char_end(bytes: &[u8]) -> usize {
+    // A code point is at most 4 bytes, so its lead byte is in the last 4
+    for back in 1..=bytes.len().min(4) {
+        let index = bytes.len() - back;
+        let byte = bytes[index];
+        if byte & 0xC0 == 0x80 {
+            continue;
+        }
+        let width = match byte {
+            0xF0..=0xF7 => 4,
+            0xE0..=0xEF => 3,
+            0xC0..=0xDF => 2,
+            _ => 1,
+        };
+        return if back < width { index } else { bytes.len() };
+    }
+    bytes.len()
+}
+
+/// Offset of the first code point that starts inside `bytes`
+fn char_start(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .take(3)
+        .take_while(|byte| *byte & 0xC0 == 0x80)
+        .count()
+}
+
+/// `text` with tabs expanded to spaces, for display
+///
+/// Tab stops are every `width` columns, counted from the start of each
+/// line. A width of 0 leaves tabs alone.
+pub fn expand_tabs(text: &str, width: u8) -> Cow<'_, str> {
+    if width == 0 || !text.contains('\t') {
+        return Cow::Borrowed(text);
+    }
+    let width = width as usize;
+    let mut out = String::with_capacity(text.len());
+    let mut column = 0;
+    for c in text.chars() {
+        match c {
+            '\t' => {
+                let spaces = width - column % width;
+                out.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            '\n' => {
+                out.push(c);
+                column = 0;
+            }
+            _ => {
+                out.push(c);
+                column += 1;
+            }
+        }
+    }
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn limits() -> LoadLimits {
+        LoadLimits {
+            background_bytes: 100,
+            truncate_bytes: 1000,
+            preview_bytes: 10,
+        }
+    }
+
+    #[test]
+    fn test_load_tier_by_size() {
+        let limits = limits();
+        assert_eq!(load_tier(0, LoadMode::Preview, &limits), LoadTier::Inline);
+        assert_eq!(load_tier(100, LoadMode::Preview, &limits), LoadTier::Inline);
+        assert_eq!(load_tier(101, LoadMode::Preview, &limits), LoadTier::Background);
+        assert_eq!(load_tier(1000, LoadMode::Preview, &limits), LoadTier::Background);
+        assert_eq!(load_tier(1001, LoadMode::Preview, &limits), LoadTier::Truncated);
+    }
+
+    #[test]
+    fn test_full_and_tail_are_never_truncated() {
+        let limits = limits();
+        assert_eq!(load_tier(5000, LoadMode::Full, &limits), LoadTier::Background);
+        assert_eq!(load_tier(50, LoadMode::Full, &limits), LoadTier::Inline);
+        // A tail is at most `background_bytes`, so it's read inline
+        assert_eq!(load_tier(5000, LoadMode::Tail, &limits), LoadTier::Inline);
+    }
+
+    #[test]
+    fn test_char_boundaries() {
+        let text = "a한b".as_bytes(); // 'a', 3-byte '한', 'b'
+        assert_eq!(char_end(&text[..1]), 1);
+        assert_eq!(char_end(&text[..2]), 1);
+        assert_eq!(char_end(&text[..3]), 1);
+        assert_eq!(char_end(&text[..4]), 4);
+        assert_eq!(char_end(&text[..5]), 5);
+
+        assert_eq!(char_start(&text[1..]), 0);
+        assert_eq!(char_start(&text[2..]), 2);
+        assert_eq!(char_start(&text[3..]), 1);
+        assert_eq!(char_start(&text[4..]), 0);
+        assert_eq!(char_end(&[]), 0);
+        assert_eq!(char_start(&[]), 0);
+    }
+
+    #[test]
+    fn test_truncated_preview_keeps_code_points_whole() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("big.log");
+        // '한' straddles the end of the 10-byte head and the 10-byte tail
+        // starts inside '세'
+        let text = format!("123456789한{}세xyzabcde", "-".repeat(2000));
+        std::fs::write(&path, &text).unwrap();
+
+        let load = read_file(&path, LoadMode::Preview, &limits()).unwrap();
+        assert!(!load.lossy);
+        assert!(load.content.starts_with("123456789\n\n[... "));
+        assert!(load.content.ends_with(" bytes not shown ...]\n\nxyzabcde"));
+        assert_eq!(load.skipped, text.len() as u64 - 9 - 8);
+    }
+
+    #[test]
+    fn test_tail_and_full_modes() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("big.log");
+        let text = "x".repeat(1500) + "end";
+        std::fs::write(&path, &text).unwrap();
+
+        let tail = read_file(&path, LoadMode::Tail, &limits()).unwrap();
+        assert_eq!(tail.content.len(), 100);
+        assert!(tail.content.ends_with("end"));
+        assert_eq!(tail.skipped, 1403);
+
+        let full = read_file(&path, LoadMode::Full, &limits()).unwrap();
+        assert_eq!(full.content, text);
+        assert_eq!(full.skipped, 0);
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_replaced() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("binary.bin");
+        std::fs::write(&path, b"ok\xFF\xFEok").unwrap();
+
+        let load = read_file(&path, LoadMode::Preview, &limits()).unwrap();
+        assert!(load.lossy);
+        assert_eq!(load.content, "ok\u{FFFD}\u{FFFD}ok");
+
+        assert!(read_file(&temp.path().join("missing"), LoadMode::Preview, &limits()).is_err());
+    }
+
+    #[test]
+    fn test_expand_tabs_to_tab_stops() {
+        assert_eq!(expand_tabs("a\tbc\td\n\tx", 4), "a   bc  d\n    x");
+        assert_eq!(expand_tabs("abcd\te", 4), "abcd    e");
+        assert_eq!(expand_tabs("a\tb", 0), "a\tb");
+        assert!(matches!(expand_tabs("no tabs", 4), Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,209 @@
+//! Font Diagnostics
+//!
+//! Finds which font serves each glyph of a few probe strings (box drawing,
+//! powerline, Nerd Font icons, CJK, emoji), so glyphs that show up as
+//! tofu boxes can be explained and, where possible, fixed.
+
+use egui::epaint::text::Fonts;
+use egui::{FontDefinitions, FontFamily, FontId};
+
+use crate::config::FontConfig;
+
+/// What a probe covers, which decides the remedy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeKind {
+    Symbols,
+    Icons,
+    Cjk,
+    Emoji,
+}
+
+/// A named set of glyphs to check
+#[derive(Debug, Clone, Copy)]
+pub struct Probe {
+    pub name: &'static str,
+    pub sample: &'static str,
+    pub kind: ProbeKind,
+}
+
+/// Glyphs commonly printed by shells, prompts and TUIs
+pub const PROBES: &[Probe] = &[
+    Probe { name: "Box drawing", sample: "─│┌┐└┘├┤┬┴┼═║╔╗█▀▄░▒▓", kind: ProbeKind::Symbols },
+    Probe { name: "Powerline", sample: "\u{e0a0}\u{e0a1}\u{e0a2}\u{e0b0}\u{e0b1}\u{e0b2}\u{e0b3}", kind: ProbeKind::Icons },
+    Probe { name: "Nerd Font icons", sample: "\u{e7a8}\u{e60b}\u{f023}\u{f07b}\u{f15b}\u{f09b}\u{e725}", kind: ProbeKind::Icons },
+    Probe { name: "Hangul", sample: "한글가나다라마", kind: ProbeKind::Cjk },
+    Probe { name: "Japanese", sample: "日本語ひらがなカタカナ", kind: ProbeKind::Cjk },
+    Probe { name: "Emoji", sample: "😀🚀✅🔥📁⚠", kind: ProbeKind::Emoji },
+];
+
+/// Where one glyph comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlyphReport {
+    pub glyph: char,
+    /// Font that has the glyph, `None` if it falls back to the replacement character
+    pub font: Option<String>,
+}
+
+/// Result of one probe
+#[derive(Debug, Clone)]
+pub struct ProbeReport {
+    pub name: &'static str,
+    pub kind: ProbeKind,
+    pub glyphs: Vec<GlyphReport>,
+}
+
+impl ProbeReport {
+    /// Number of glyphs no font has
+    pub fn missing(&self) -> usize {
+        self.glyphs.iter().filter(|glyph| glyph.font.is_none()).count()
+    }
+
+    /// Fonts that served the glyphs, in order of first use
+    pub fn fonts(&self) -> Vec<&str> {
+        let mut fonts: Vec<&str> = Vec::new();
+        for font in self.glyphs.iter().filter_map(|glyph| glyph.font.as_deref()) {
+            if !fonts.contains(&font) {
+                fonts.push(font);
+            }
+        }
+        fonts
+    }
+}
+
+/// Check every probe against the fonts of `family`, in fallback order
+pub fn diagnose(definitions: &FontDefinitions, family: &FontFamily) -> Vec<ProbeReport> {
+    let chain = definitions.families.get(family).cloned().unwrap_or_default();
+
+    // A family per font, so each can be asked on its own
+    let mut definitions = definitions.clone();
+    let single: Vec<(String, FontFamily)> = chain
+        .into_iter()
+        .map(|name| {
+            let family = FontFamily::Name(format!("diagnose:{}", name).into());
+            definitions.families.insert(family.clone(), vec![name.clone()]);
+            (name, family)
+        })
+        .collect();
+    let fonts = Fonts::new(1.0, 1024, definitions);
+
+    PROBES
+        .iter()
+        .map(|probe| ProbeReport {
+            name: probe.name,
+            kind: probe.kind,
+            glyphs: probe
+                .sample
+                .chars()
+                .map(|glyph| GlyphReport {
+                    glyph,
+                    font: single
+                        .iter()
+                        .find(|(_, family)| fonts.has_glyph(&FontId::new(14.0, family.clone()), glyph))
+                        .map(|(name, _)| name.clone()),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// A fix offered for missing glyphs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Remedy {
+    /// Turn on `font.cjk_fallback`
+    EnableCjkFallback,
+    /// Open Preferences to set `font.fallback_font`
+    SetFallbackFont,
+}
+
+impl Remedy {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Remedy::EnableCjkFallback => "Enable CJK Fallback",
+            Remedy::SetFallbackFont => "Set Fallback Font…",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Remedy::EnableCjkFallback => "Search the system for a CJK font to fall back to",
+            Remedy::SetFallbackFont => "Point Preferences > General > Fallback Font at a font with these glyphs (e.g. a Nerd Font)",
+        }
+    }
+}
+
+/// Fixes for the missing glyphs in `reports` under `config`
+pub fn remedies(reports: &[ProbeReport], config: &FontConfig) -> Vec<Remedy> {
+    let mut remedies = Vec::new();
+    let missing: Vec<&ProbeReport> = reports.iter().filter(|report| report.missing() > 0).collect();
+    if !config.cjk_fallback && missing.iter().any(|report| report.kind == ProbeKind::Cjk) {
+        remedies.push(Remedy::EnableCjkFallback);
+    }
+    // CJK with the search already on means no system font was found
+    if missing.iter().any(|report| report.kind != ProbeKind::Cjk || config.cjk_fallback) {
+        remedies.push(Remedy::SetFallbackFont);
+    }
+    remedies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report<'a>(reports: &'a [ProbeReport], name: &str) -> &'a ProbeReport {
+        reports.iter().find(|report| report.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_diagnose_builtin_fonts() {
+        let reports = diagnose(&FontDefinitions::default(), &FontFamily::Monospace);
+        assert_eq!(reports.len(), PROBES.len());
+
+        let box_drawing = report(&reports, "Box drawing");
+        assert_eq!(box_drawing.missing(), 0);
+        assert_eq!(box_drawing.fonts()[0], "Hack");
+
+        // No bundled font covers CJK
+        let hangul = report(&reports, "Hangul");
+        assert_eq!(hangul.missing(), hangul.glyphs.len());
+        assert!(hangul.fonts().is_empty());
+
+        let emoji = report(&reports, "Emoji");
+        assert_eq!(emoji.missing(), 0);
+        assert!(!emoji.fonts().contains(&"Hack"));
+    }
+
+    #[test]
+    fn test_diagnose_follows_family_order() {
+        let mut definitions = FontDefinitions::default();
+        definitions.families.insert(FontFamily::Monospace, vec!["Hack".to_owned()]);
+        let reports = diagnose(&definitions, &FontFamily::Monospace);
+        assert_eq!(report(&reports, "Box drawing").fonts(), ["Hack"]);
+        assert!(report(&reports, "Emoji").missing() > 0);
+
+        // Fonts earlier in the family win, later ones fill the gaps
+        definitions.families.insert(FontFamily::Monospace, vec!["NotoEmoji-Regular".to_owned(), "Hack".to_owned()]);
+        let reports = diagnose(&definitions, &FontFamily::Monospace);
+        assert_eq!(report(&reports, "Box drawing").fonts(), ["Hack"]);
+        assert_eq!(report(&reports, "Emoji").fonts()[0], "NotoEmoji-Regular");
+    }
+
+    #[test]
+    fn test_diagnose_unknown_family() {
+        let reports = diagnose(&FontDefinitions::default(), &FontFamily::Name("missing".into()));
+        assert!(reports.iter().all(|report| report.missing() == report.glyphs.len()));
+    }
+
+    #[test]
+    fn test_remedies() {
+        let reports = diagnose(&FontDefinitions::default(), &FontFamily::Monospace);
+        let cjk_only: Vec<ProbeReport> = reports.iter().filter(|r| r.kind == ProbeKind::Cjk).cloned().collect();
+
+        let off = FontConfig { cjk_fallback: false, ..FontConfig::default() };
+        assert_eq!(remedies(&cjk_only, &off), [Remedy::EnableCjkFallback]);
+        // Searching found nothing, so only a custom font helps
+        assert_eq!(remedies(&cjk_only, &FontConfig::default()), [Remedy::SetFallbackFont]);
+
+        let found: Vec<ProbeReport> = reports.into_iter().filter(|r| r.missing() == 0).collect();
+        assert!(remedies(&found, &off).is_empty());
+    }
+}
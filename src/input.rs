@@ -0,0 +1,384 @@
+//! Input Routing
+//!
+//! Decides, in a single ordered pass over a frame's events, which events
+//! reach the terminal and which are handled by the app (IME commits,
+//! Shift+Enter newline injection, scrollback paging). Handling everything in one pass keeps
+//! writes in event order, e.g. a committed syllable is written before a
+//! newline that follows it.
+//!
+//! Also places the OS's IME candidate window at the terminal cursor.
+
+use egui::{Event, ImeEvent, Key, Rect, Vec2};
+
+/// What to do with one input event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputAction {
+    /// Leave the event in the queue (terminal view / widgets handle it)
+    Pass,
+    /// Remove the event
+    Drop,
+    /// Remove the event and write a newline to the focused terminal
+    InsertNewline,
+    /// Remove the event and write committed IME text to the focused terminal
+    CommitText(String),
+    /// Remove the event and scroll the focused terminal's history by pages
+    /// (positive is up)
+    ScrollPages(i32),
+    /// Remove the event and scroll the focused terminal to the bottom (End
+    /// while it's scrolled back; set by the app, which knows the view)
+    ScrollToBottom,
+}
+
+impl InputAction {
+    /// Should the event stay in egui's queue?
+    pub fn keeps_event(&self) -> bool {
+        matches!(self, InputAction::Pass)
+    }
+}
+
+/// Routes input events, tracking IME composition across frames
+#[derive(Debug, Default)]
+pub struct InputRouter {
+    /// IME preedit is active
+    composing: bool,
+}
+
+impl InputRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Is IME composition in progress?
+    #[cfg(test)]
+    pub fn is_composing(&self) -> bool {
+        self.composing
+    }
+
+    /// Decide an action for each event, in order
+    ///
+    /// IME state is always tracked; when the terminal doesn't have keyboard
+    /// focus every event is passed through to the focused widget. While a
+    /// fullscreen app owns the terminal (`alt_screen`), scrollback keys are
+    /// passed through to the app.
+    pub fn route(&mut self, events: &[Event], terminal_focused: bool, alt_screen: bool) -> Vec<InputAction> {
+        events
+            .iter()
+            .map(|event| {
+                let action = self.route_event(event, alt_screen);
+                if terminal_focused { action } else { InputAction::Pass }
+            })
+            .collect()
+    }
+
+    fn route_event(&mut self, event: &Event, alt_screen: bool) -> InputAction {
+        match event {
+            Event::Ime(ImeEvent::Preedit(text)) => {
+                self.composing = !text.is_empty();
+                InputAction::Pass
+            }
+            Event::Ime(ImeEvent::Commit(text)) => {
+                self.composing = false;
+                if text.is_empty() {
+                    InputAction::Drop
+                } else {
+                    InputAction::CommitText(text.clone())
+                }
+            }
+            Event::Ime(ImeEvent::Disabled) => {
+                self.composing = false;
+                InputAction::Pass
+            }
+            // Don't set composing on Enabled - wait for actual preedit text
+            Event::Ime(ImeEvent::Enabled) => InputAction::Pass,
+
+            // Text produced by the IME arrives via Commit
+            Event::Text(_) if self.composing => InputAction::Drop,
+
+            Event::Key { key: Key::Enter, pressed: true, modifiers, .. } => {
+                if self.composing {
+                    // The IME owns Enter while composing (confirms the preedit)
+                    InputAction::Drop
+                } else if modifiers.command && modifiers.shift {
+                    // Cmd+Shift+Enter is an app shortcut
+                    InputAction::Drop
+                } else if modifiers.shift && !modifiers.command && !modifiers.ctrl {
+                    InputAction::InsertNewline
+                } else {
+                    InputAction::Pass
+                }
+            }
+
+            // Shift+PageUp/PageDown: scrollback paging (the app gets them in alt screen)
+            Event::Key { key: key @ (Key::PageUp | Key::PageDown), pressed: true, modifiers, .. }
+                if modifiers.shift_only() && !alt_screen =>
+            {
+                InputAction::ScrollPages(if *key == Key::PageUp { 1 } else { -1 })
+            }
+
+            _ => InputAction::Pass,
+        }
+    }
+}
+
+/// What gets this frame's keystrokes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyboardOwner {
+    /// The focused pane's terminal
+    Terminal,
+    /// The sidebar tree (arrow keys move the selection)
+    Sidebar,
+    /// A palette: commands, snippets, clipboard history, history, SSH hosts
+    Palette,
+    /// Preferences drawn inside the main window (viewports embedded)
+    Preferences,
+    SidebarFilter,
+    SearchBar,
+    /// A dialog, prompt, pane pick or note being edited
+    Modal,
+}
+
+/// Surfaces that can take the keyboard, as open right now
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeyboardSurfaces {
+    pub modal: bool,
+    pub palette: bool,
+    pub preferences: bool,
+    pub sidebar_focused: bool,
+    /// Project search is showing in the sidebar
+    pub search_bar: bool,
+    /// The sidebar filter box is open
+    pub sidebar_filter: bool,
+}
+
+impl KeyboardOwner {
+    /// The topmost open surface: modals over palettes over Preferences,
+    /// then the sidebar's fields when it has focus, then the terminal
+    pub fn resolve(surfaces: KeyboardSurfaces) -> Self {
+        if surfaces.modal {
+            KeyboardOwner::Modal
+        } else if surfaces.palette {
+            KeyboardOwner::Palette
+        } else if surfaces.preferences {
+            KeyboardOwner::Preferences
+        } else if !surfaces.sidebar_focused {
+            KeyboardOwner::Terminal
+        } else if surfaces.search_bar {
+            KeyboardOwner::SearchBar
+        } else if surfaces.sidebar_filter {
+            KeyboardOwner::SidebarFilter
+        } else {
+            KeyboardOwner::Sidebar
+        }
+    }
+
+    /// Do app shortcuts (Cmd+W, Cmd+T, Cmd+V, …) apply? Overlays keep every key
+    pub fn takes_shortcuts(self) -> bool {
+        !matches!(self, KeyboardOwner::Palette | KeyboardOwner::Preferences | KeyboardOwner::Modal)
+    }
+}
+
+/// Screen rect of the cell the IME candidate window should sit by
+///
+/// `cursor` is the terminal cursor's row in view and its column. When it is
+/// scrolled out of view the pane's bottom-left cell is used, and without a
+/// cell size (nothing drawn yet) the whole pane.
+pub fn ime_cursor_rect(pane: Rect, cell: Vec2, cursor: (i32, usize)) -> Rect {
+    if cell.x <= 0.0 || cell.y <= 0.0 {
+        return pane;
+    }
+    let rows = (pane.height() / cell.y).floor().max(1.0) as i32;
+    let columns = (pane.width() / cell.x).floor().max(1.0) as usize;
+    let (row, column) = match cursor {
+        (row, column) if (0..rows).contains(&row) => (row, column.min(columns - 1)),
+        _ => (rows - 1, 0),
+    };
+    let min = pane.min + egui::vec2(column as f32 * cell.x, row as f32 * cell.y);
+    Rect::from_min_size(min, cell)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::Modifiers;
+
+    fn key(key: Key, pressed: bool, modifiers: Modifiers) -> Event {
+        Event::Key { key, physical_key: None, pressed, repeat: false, modifiers }
+    }
+
+    fn preedit(text: &str) -> Event {
+        Event::Ime(ImeEvent::Preedit(text.to_string()))
+    }
+
+    fn commit(text: &str) -> Event {
+        Event::Ime(ImeEvent::Commit(text.to_string()))
+    }
+
+    #[test]
+    fn test_topmost_surface_owns_the_keyboard() {
+        let resolve = KeyboardOwner::resolve;
+        assert_eq!(resolve(KeyboardSurfaces::default()), KeyboardOwner::Terminal);
+
+        // The sidebar's fields only count while it has focus
+        let sidebar = KeyboardSurfaces { search_bar: true, sidebar_filter: true, ..Default::default() };
+        assert_eq!(resolve(sidebar), KeyboardOwner::Terminal);
+        let focused = KeyboardSurfaces { sidebar_focused: true, ..sidebar };
+        assert_eq!(resolve(focused), KeyboardOwner::SearchBar);
+        assert_eq!(resolve(KeyboardSurfaces { search_bar: false, ..focused }), KeyboardOwner::SidebarFilter);
+        assert_eq!(
+            resolve(KeyboardSurfaces { sidebar_focused: true, ..Default::default() }),
+            KeyboardOwner::Sidebar,
+        );
+
+        // Overlays win over whatever is under them
+        let preferences = KeyboardSurfaces { preferences: true, ..focused };
+        assert_eq!(resolve(preferences), KeyboardOwner::Preferences);
+        let palette = KeyboardSurfaces { palette: true, ..preferences };
+        assert_eq!(resolve(palette), KeyboardOwner::Palette);
+        assert_eq!(resolve(KeyboardSurfaces { modal: true, ..palette }), KeyboardOwner::Modal);
+    }
+
+    #[test]
+    fn test_overlays_keep_app_shortcuts() {
+        assert!(KeyboardOwner::Terminal.takes_shortcuts());
+        assert!(KeyboardOwner::SidebarFilter.takes_shortcuts());
+        assert!(!KeyboardOwner::Palette.takes_shortcuts());
+        assert!(!KeyboardOwner::Preferences.takes_shortcuts());
+        assert!(!KeyboardOwner::Modal.takes_shortcuts());
+    }
+
+    #[test]
+    fn test_plain_enter_hardware_keyboard() {
+        let mut router = InputRouter::new();
+        let events = vec![
+            Event::Text("ls".to_string()),
+            key(Key::Enter, true, Modifiers::NONE),
+            key(Key::Enter, false, Modifiers::NONE),
+        ];
+        let actions = router.route(&events, true, false);
+        assert!(actions.iter().all(|a| *a == InputAction::Pass));
+    }
+
+    #[test]
+    fn test_shift_enter_inserts_newline() {
+        let mut router = InputRouter::new();
+        let events = vec![key(Key::Enter, true, Modifiers::SHIFT)];
+        assert_eq!(router.route(&events, true, false), vec![InputAction::InsertNewline]);
+    }
+
+    #[test]
+    fn test_shift_enter_while_composing() {
+        let mut router = InputRouter::new();
+
+        // Preedit in an earlier frame
+        router.route(&[preedit("ㅎ")], true, false);
+        assert!(router.is_composing());
+
+        // Shift+Enter without a commit: neither newline nor terminal Enter
+        let actions = router.route(&[key(Key::Enter, true, Modifiers::SHIFT)], true, false);
+        assert_eq!(actions, vec![InputAction::Drop]);
+
+        // IME commits then delivers Shift+Enter: syllable first, then newline
+        let events = vec![commit("한"), key(Key::Enter, true, Modifiers::SHIFT)];
+        let actions = router.route(&events, true, false);
+        assert_eq!(actions, vec![
+            InputAction::CommitText("한".to_string()),
+            InputAction::InsertNewline,
+        ]);
+        assert!(!router.is_composing());
+    }
+
+    #[test]
+    fn test_commit_followed_by_enter() {
+        let mut router = InputRouter::new();
+        router.route(&[preedit("하")], true, false);
+
+        let events = vec![
+            preedit(""),
+            commit("한"),
+            key(Key::Enter, true, Modifiers::NONE),
+        ];
+        let actions = router.route(&events, true, false);
+
+        // Enter is not swallowed and comes after the committed text
+        assert_eq!(actions, vec![
+            InputAction::Pass,
+            InputAction::CommitText("한".to_string()),
+            InputAction::Pass,
+        ]);
+    }
+
+    #[test]
+    fn test_text_dropped_while_composing() {
+        let mut router = InputRouter::new();
+        let events = vec![preedit("ㄱ"), Event::Text("ㄱ".to_string())];
+        let actions = router.route(&events, true, false);
+        assert_eq!(actions, vec![InputAction::Pass, InputAction::Drop]);
+    }
+
+    #[test]
+    fn test_cmd_shift_enter_reserved_for_shortcut() {
+        let mut router = InputRouter::new();
+        let modifiers = Modifiers { shift: true, command: true, ..Modifiers::NONE };
+        let actions = router.route(&[key(Key::Enter, true, modifiers)], true, false);
+        assert_eq!(actions, vec![InputAction::Drop]);
+    }
+
+    #[test]
+    fn test_shift_page_keys_scroll_history() {
+        let mut router = InputRouter::new();
+        let events = vec![
+            key(Key::PageUp, true, Modifiers::SHIFT),
+            key(Key::PageDown, true, Modifiers::SHIFT),
+            key(Key::PageUp, true, Modifiers::NONE),
+        ];
+        assert_eq!(router.route(&events, true, false), vec![
+            InputAction::ScrollPages(1),
+            InputAction::ScrollPages(-1),
+            InputAction::Pass,
+        ]);
+    }
+
+    #[test]
+    fn test_page_keys_pass_through_in_alt_screen() {
+        let mut router = InputRouter::new();
+        let events = vec![
+            key(Key::PageUp, true, Modifiers::SHIFT),
+            key(Key::PageDown, true, Modifiers::SHIFT),
+        ];
+        let actions = router.route(&events, true, true);
+        assert!(actions.iter().all(InputAction::keeps_event));
+    }
+
+    #[test]
+    fn test_unfocused_terminal_passes_everything() {
+        let mut router = InputRouter::new();
+        let events = vec![preedit("ㅎ"), commit("한"), key(Key::Enter, true, Modifiers::SHIFT)];
+        let actions = router.route(&events, false, false);
+        assert!(actions.iter().all(InputAction::keeps_event));
+        // IME state still tracked
+        assert!(!router.is_composing());
+    }
+
+    #[test]
+    fn test_ime_rect_follows_the_cursor() {
+        let pane = Rect::from_min_size(egui::pos2(100.0, 50.0), egui::vec2(80.0, 40.0));
+        let rect = ime_cursor_rect(pane, egui::vec2(8.0, 10.0), (2, 3));
+        assert_eq!(rect, Rect::from_min_size(egui::pos2(124.0, 70.0), egui::vec2(8.0, 10.0)));
+
+        // Past the right edge (a pending wrap) stays in the pane
+        let rect = ime_cursor_rect(pane, egui::vec2(8.0, 10.0), (0, 40));
+        assert_eq!(rect.min, egui::pos2(172.0, 50.0));
+    }
+
+    #[test]
+    fn test_ime_rect_falls_back_when_cursor_is_unknown() {
+        let pane = Rect::from_min_size(egui::pos2(100.0, 50.0), egui::vec2(80.0, 40.0));
+        // Scrolled back so the cursor is below the view: bottom-left cell
+        let rect = ime_cursor_rect(pane, egui::vec2(8.0, 10.0), (7, 3));
+        assert_eq!(rect.min, egui::pos2(100.0, 80.0));
+        assert_eq!(ime_cursor_rect(pane, egui::vec2(8.0, 10.0), (-1, 0)).min, egui::pos2(100.0, 80.0));
+
+        // No cell size yet
+        assert_eq!(ime_cursor_rect(pane, Vec2::ZERO, (0, 0)), pane);
+    }
+}
@@ -0,0 +1,424 @@
+//! Single-Instance IPC
+//!
+//! The first VibeTerm binds a Unix socket in the config directory. Later
+//! launches connect to it, send their request as one JSON command per line
+//! and exit, so the running window opens a tab instead of a second app
+//! starting.
+//!
+//! Queries get one JSON line back on the same connection: `vibeterm
+//! --context` (or a `vibeterm-context` symlink) prints the pinned-file
+//! context, for `llm "$(vibeterm-context)" "explain this"`.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+#[cfg(unix)]
+use std::sync::{Arc, OnceLock};
+
+use crate::config::Config;
+
+/// Socket file name inside the config directory
+const SOCKET_NAME: &str = "vibeterm.sock";
+
+/// How long a query waits for the window to answer
+#[cfg(unix)]
+const QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// A request from another VibeTerm launch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum IpcCommand {
+    /// Open a terminal tab in `dir` (the current directory if unset)
+    NewTab { dir: Option<PathBuf> },
+    /// Show a file in a file viewer tab
+    OpenFile { path: PathBuf },
+    /// Raise and focus the window
+    FocusWindow,
+    /// Reply with the context snapshot as JSON
+    Context,
+}
+
+impl IpcCommand {
+    /// Does the sender wait for a reply line?
+    pub fn is_query(&self) -> bool {
+        matches!(self, IpcCommand::Context)
+    }
+}
+
+/// A received command, answered with [`IpcRequest::respond`] if it's a query
+#[derive(Debug)]
+pub struct IpcRequest {
+    pub command: IpcCommand,
+    reply: Option<Sender<String>>,
+}
+
+impl IpcRequest {
+    /// Send `json` back to the client; it goes out as one line
+    pub fn respond(&self, json: String) {
+        if let Some(reply) = &self.reply {
+            let _ = reply.send(json);
+        }
+    }
+}
+
+/// The reply line for a query that failed
+pub fn error_response(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+/// Command line options
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchArgs {
+    /// `--new-instance`: start a separate app even if one is running
+    pub new_instance: bool,
+    /// `--context`: print the running instance's context snapshot and exit
+    pub context: bool,
+    /// Directory to open the first tab in, or file to show
+    pub path: Option<PathBuf>,
+}
+
+impl LaunchArgs {
+    /// Parse arguments (without the program name)
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut parsed = Self::default();
+        for arg in args {
+            if arg == "--new-instance" {
+                parsed.new_instance = true;
+            } else if arg == "--context" {
+                parsed.context = true;
+            } else if arg.starts_with('-') {
+                log::warn!("Ignoring unknown option {}", arg);
+            } else if parsed.path.is_none() {
+                parsed.path = Some(PathBuf::from(arg));
+            }
+        }
+        parsed
+    }
+
+    /// Commands sent to a running instance for this launch
+    pub fn forward_commands(&self) -> Vec<IpcCommand> {
+        let open = match &self.path {
+            Some(path) => match std::fs::canonicalize(path) {
+                Ok(path) if path.is_file() => IpcCommand::OpenFile { path },
+                path => IpcCommand::NewTab { dir: path.ok() },
+            },
+            None => IpcCommand::NewTab { dir: std::env::current_dir().ok() },
+        };
+        vec![open, IpcCommand::FocusWindow]
+    }
+}
+
+/// Default socket location
+pub fn socket_path() -> PathBuf {
+    Config::config_dir().join(SOCKET_NAME)
+}
+
+/// Outcome of looking for a running instance
+pub enum Startup {
+    /// The running instance took the commands; this launch should exit
+    Forwarded,
+    /// No instance is running; this launch is now the primary one
+    Primary(IpcListener),
+}
+
+/// Send `commands` to the instance listening on `path`, or become that
+/// instance by binding the socket
+///
+/// A socket file nobody listens on (left by a crash) is removed first.
+#[cfg(unix)]
+pub fn connect_or_bind(path: &Path, commands: &[IpcCommand]) -> Result<Startup, String> {
+    use std::io::Write;
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    match UnixStream::connect(path) {
+        Ok(mut stream) => {
+            let mut message = String::new();
+            for command in commands {
+                let line = serde_json::to_string(command).map_err(|e| e.to_string())?;
+                message.push_str(&line);
+                message.push('\n');
+            }
+            stream
+                .write_all(message.as_bytes())
+                .map_err(|e| format!("Failed to send to running instance: {}", e))?;
+            return Ok(Startup::Forwarded);
+        }
+        Err(e) if path.exists() => {
+            log::info!("Removing stale socket {} ({})", path.display(), e);
+            std::fs::remove_file(path).map_err(|e| format!("Failed to remove stale socket: {}", e))?;
+        }
+        Err(_) => {}
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let listener = UnixListener::bind(path).map_err(|e| format!("Failed to bind {}: {}", path.display(), e))?;
+    Ok(Startup::Primary(IpcListener { listener, path: path.to_path_buf() }))
+}
+
+/// Send a query to the instance listening on `path` and return its reply line
+#[cfg(unix)]
+pub fn query(path: &Path, command: &IpcCommand) -> Result<String, String> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(path).map_err(|e| format!("VibeTerm isn't running ({})", e))?;
+    let mut line = serde_json::to_string(command).map_err(|e| e.to_string())?;
+    line.push('\n');
+    stream.write_all(line.as_bytes()).map_err(|e| format!("Failed to send to VibeTerm: {}", e))?;
+    let _ = stream.set_read_timeout(Some(QUERY_TIMEOUT * 2));
+
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|e| format!("No reply from VibeTerm: {}", e))?;
+    match reply.trim_end() {
+        "" => Err("No reply from VibeTerm".to_string()),
+        reply => Ok(reply.to_string()),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn query(_path: &Path, _command: &IpcCommand) -> Result<String, String> {
+    Err("Not supported on this platform".to_string())
+}
+
+/// Sockets aren't supported here; every launch is a primary instance
+#[cfg(not(unix))]
+pub fn connect_or_bind(_path: &Path, _commands: &[IpcCommand]) -> Result<Startup, String> {
+    Ok(Startup::Primary(IpcListener {}))
+}
+
+/// The primary instance's socket, bound but not answered yet: no thread
+/// runs until `serve`, so startup can still change the environment
+pub struct IpcListener {
+    #[cfg(unix)]
+    listener: std::os::unix::net::UnixListener,
+    #[cfg(unix)]
+    path: PathBuf,
+}
+
+impl IpcListener {
+    /// Answer on a thread of its own; launches that connected meanwhile
+    /// waited in the socket's backlog
+    #[cfg(unix)]
+    pub fn serve(self) -> IpcServer {
+        IpcServer::start(self.listener, self.path)
+    }
+
+    #[cfg(not(unix))]
+    pub fn serve(self) -> IpcServer {
+        let (_sender, receiver) = std::sync::mpsc::channel();
+        IpcServer { receiver }
+    }
+}
+
+/// Listening side of the socket, owned by the primary instance
+pub struct IpcServer {
+    receiver: Receiver<IpcRequest>,
+    #[cfg(unix)]
+    path: PathBuf,
+    /// Woken when a command arrives, once the UI exists
+    #[cfg(unix)]
+    waker: Arc<OnceLock<egui::Context>>,
+}
+
+impl IpcServer {
+    #[cfg(unix)]
+    fn start(listener: std::os::unix::net::UnixListener, path: PathBuf) -> Self {
+        use std::io::{BufRead, BufReader, Write};
+        use std::time::Duration;
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let waker: Arc<OnceLock<egui::Context>> = Arc::new(OnceLock::new());
+        let thread_waker = Arc::clone(&waker);
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                // A client that never writes mustn't stall the listener
+                let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+                for line in BufReader::new(&stream).lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    let command = match serde_json::from_str::<IpcCommand>(&line) {
+                        Ok(command) => command,
+                        Err(e) => {
+                            log::warn!("Ignoring IPC message {:?}: {}", line, e);
+                            continue;
+                        }
+                    };
+                    if !command.is_query() {
+                        if sender.send(IpcRequest { command, reply: None }).is_err() {
+                            return;
+                        }
+                        continue;
+                    }
+
+                    // The window answers on its next frame
+                    let (reply_tx, reply_rx) = std::sync::mpsc::channel();
+                    if sender.send(IpcRequest { command, reply: Some(reply_tx) }).is_err() {
+                        return;
+                    }
+                    if let Some(ctx) = thread_waker.get() {
+                        ctx.request_repaint();
+                    }
+                    let reply = reply_rx
+                        .recv_timeout(QUERY_TIMEOUT)
+                        .unwrap_or_else(|_| error_response("VibeTerm didn't answer"));
+                    let _ = (&stream).write_all(format!("{}\n", reply).as_bytes());
+                }
+                if let Some(ctx) = thread_waker.get() {
+                    ctx.request_repaint();
+                }
+            }
+        });
+
+        Self { receiver, path, waker }
+    }
+
+    /// Repaint `ctx` when commands arrive
+    pub fn attach(&self, _ctx: &egui::Context) {
+        #[cfg(unix)]
+        let _ = self.waker.set(_ctx.clone());
+    }
+
+    /// Next received command, if any
+    pub fn try_recv(&self) -> Option<IpcRequest> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+#[cfg(unix)]
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use tempfile::TempDir;
+
+    fn recv(server: &IpcServer) -> Option<IpcCommand> {
+        recv_request(server).map(|request| request.command)
+    }
+
+    fn recv_request(server: &IpcServer) -> Option<IpcRequest> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let Some(request) = server.try_recv() {
+                return Some(request);
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        None
+    }
+
+    #[test]
+    fn test_second_launch_forwards_to_primary() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(SOCKET_NAME);
+        let commands = [
+            IpcCommand::NewTab { dir: Some(PathBuf::from("/tmp/project")) },
+            IpcCommand::FocusWindow,
+        ];
+
+        let Ok(Startup::Primary(listener)) = connect_or_bind(&path, &commands) else {
+            panic!("first launch should become the primary instance");
+        };
+        assert!(matches!(connect_or_bind(&path, &commands), Ok(Startup::Forwarded)));
+        // Sent before the primary instance answers; the commands wait for it
+        let server = listener.serve();
+        assert_eq!(recv(&server), Some(commands[0].clone()));
+        assert_eq!(recv(&server), Some(IpcCommand::FocusWindow));
+
+        // The socket goes away with the primary instance
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_stale_socket_is_replaced() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(SOCKET_NAME);
+        // Dropping a listener leaves its file behind, like a crashed instance
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let Ok(Startup::Primary(_listener)) = connect_or_bind(&path, &[IpcCommand::FocusWindow]) else {
+            panic!("stale socket should be reclaimed");
+        };
+        assert!(matches!(connect_or_bind(&path, &[IpcCommand::FocusWindow]), Ok(Startup::Forwarded)));
+    }
+
+    #[test]
+    fn test_parse_launch_args() {
+        let args = |list: &[&str]| LaunchArgs::parse(list.iter().map(|s| s.to_string()));
+        assert_eq!(args(&[]), LaunchArgs::default());
+        assert_eq!(
+            args(&["--new-instance", "~/src", "extra"]),
+            LaunchArgs { new_instance: true, context: false, path: Some(PathBuf::from("~/src")) },
+        );
+        assert!(args(&["--context"]).context);
+        assert_eq!(args(&["--bogus"]), LaunchArgs::default());
+    }
+
+    #[test]
+    fn test_command_wire_format() {
+        let json = serde_json::to_string(&IpcCommand::NewTab { dir: Some(PathBuf::from("/a")) }).unwrap();
+        assert_eq!(json, r#"{"cmd":"new_tab","dir":"/a"}"#);
+        assert_eq!(serde_json::from_str::<IpcCommand>(r#"{"cmd":"focus_window"}"#).unwrap(), IpcCommand::FocusWindow);
+        let json = serde_json::to_string(&IpcCommand::OpenFile { path: PathBuf::from("/a/notes.md") }).unwrap();
+        assert_eq!(json, r#"{"cmd":"open_file","path":"/a/notes.md"}"#);
+    }
+
+    #[test]
+    fn test_files_are_forwarded_for_the_file_viewer() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("notes.md");
+        std::fs::write(&file, "# notes").unwrap();
+        let launch = |path: &Path| LaunchArgs { new_instance: false, context: false, path: Some(path.to_path_buf()) };
+
+        let dir = std::fs::canonicalize(temp.path()).unwrap();
+        assert_eq!(launch(temp.path()).forward_commands()[0], IpcCommand::NewTab { dir: Some(dir.clone()) });
+        assert_eq!(launch(&file).forward_commands()[0], IpcCommand::OpenFile { path: dir.join("notes.md") });
+    }
+
+    #[test]
+    fn test_query_gets_one_reply_line() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(SOCKET_NAME);
+        let Ok(Startup::Primary(listener)) = connect_or_bind(&path, &[]) else {
+            panic!("first launch should become the primary instance");
+        };
+        let server = listener.serve();
+
+        let client_path = path.clone();
+        let client = std::thread::spawn(move || query(&client_path, &IpcCommand::Context));
+        let request = recv_request(&server).expect("query");
+        assert_eq!(request.command, IpcCommand::Context);
+        // Newlines in the answer can't split the frame: JSON escapes them
+        let json = serde_json::json!({ "files": [{ "content": "line 1\nline 2\n" }] }).to_string();
+        request.respond(json.clone());
+        let reply = client.join().unwrap().unwrap();
+        assert_eq!(reply, json);
+        let value: serde_json::Value = serde_json::from_str(&reply).unwrap();
+        assert_eq!(value["files"][0]["content"], "line 1\nline 2\n");
+
+        // A window that never answers gets the client an error, not a hang
+        let client_path = path.clone();
+        let client = std::thread::spawn(move || query(&client_path, &IpcCommand::Context));
+        let request = recv_request(&server).expect("query");
+        drop(request);
+        let reply = client.join().unwrap().unwrap();
+        assert_eq!(reply, error_response("VibeTerm didn't answer"));
+        assert_eq!(serde_json::to_string(&IpcCommand::Context).unwrap(), r#"{"cmd":"context"}"#);
+    }
+}
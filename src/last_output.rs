@@ -0,0 +1,173 @@
+//! Copy Last Output
+//!
+//! Finds the output of the last finished command in a terminal's
+//! scrollback. egui_term doesn't pass OSC 133 marks through, so prompts
+//! are recognized by a configurable pattern; the output is everything
+//! between the last two prompts. Without two prompts the last lines are
+//! used instead.
+
+use alacritty_terminal::grid::Grid;
+use alacritty_terminal::index::Line;
+use alacritty_terminal::term::cell::Cell;
+use egui::{Key, KeyboardShortcut, Modifiers};
+use regex::Regex;
+
+/// Text picked by [`extract`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastOutput {
+    pub text: String,
+    pub line_count: usize,
+    /// No prompts were found, so `text` is just the last lines
+    pub fallback: bool,
+}
+
+/// Scrollback and screen down to the cursor as plain text lines
+pub fn grid_lines(grid: &Grid<Cell>) -> Vec<String> {
+    crate::session_log::text_since(grid, Line(i32::MIN))
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Output of the last command in `lines` (the last line is the cursor's)
+///
+/// Commands that printed nothing, like an empty Enter, are skipped. A
+/// command still running has no prompt after it, so the one before it is
+/// used.
+pub fn extract(lines: &[String], prompt: Option<&Regex>, fallback_lines: usize) -> LastOutput {
+    let prompts: Vec<usize> = match prompt {
+        Some(prompt) => (0..lines.len()).filter(|&i| prompt.is_match(&lines[i])).collect(),
+        None => Vec::new(),
+    };
+
+    if prompts.len() >= 2 {
+        let region = prompts
+            .windows(2)
+            .rev()
+            .map(|pair| trim_blank_lines(&lines[pair[0] + 1..pair[1]]))
+            .find(|region| !region.is_empty())
+            .unwrap_or_default();
+        return LastOutput {
+            text: region.join("\n"),
+            line_count: region.len(),
+            fallback: false,
+        };
+    }
+
+    // The cursor line holds the prompt being typed at
+    let before_cursor = &lines[..lines.len().saturating_sub(1)];
+    let mut tail: Vec<&String> = before_cursor
+        .iter()
+        .rev()
+        .filter(|line| !line.trim().is_empty())
+        .take(fallback_lines)
+        .collect();
+    tail.reverse();
+    LastOutput {
+        text: tail.iter().map(|line| line.as_str()).collect::<Vec<_>>().join("\n"),
+        line_count: tail.len(),
+        fallback: true,
+    }
+}
+
+fn trim_blank_lines(lines: &[String]) -> &[String] {
+    let start = lines.iter().position(|line| !line.trim().is_empty()).unwrap_or(lines.len());
+    let end = lines.iter().rposition(|line| !line.trim().is_empty()).map_or(start, |i| i + 1);
+    &lines[start..end]
+}
+
+/// Parse a shortcut written like the palette shows them, e.g. `Cmd+Alt+C`
+pub fn parse_shortcut(text: &str) -> Option<KeyboardShortcut> {
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    let key = Key::from_name(parts.pop()?)?;
+
+    let mut modifiers = Modifiers::NONE;
+    for part in parts {
+        modifiers |= match part.to_lowercase().as_str() {
+            "cmd" | "command" => Modifiers::COMMAND,
+            "ctrl" | "control" => Modifiers::CTRL,
+            "alt" | "option" => Modifiers::ALT,
+            "shift" => Modifiers::SHIFT,
+            _ => return None,
+        };
+    }
+    Some(KeyboardShortcut::new(modifiers, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROMPT: &str = r"^\S*\s?\$(\s|$)";
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_output_between_last_two_prompts() {
+        let prompt = Regex::new(PROMPT).unwrap();
+        let scrollback = lines(&[
+            "~/p $ ls",
+            "a.txt",
+            "~/p $ cargo build",
+            "   Compiling demo",
+            "error: boom",
+            "",
+            "~/p $",
+        ]);
+        let output = extract(&scrollback, Some(&prompt), 50);
+        assert_eq!(output.text, "   Compiling demo\nerror: boom");
+        assert_eq!(output.line_count, 2);
+        assert!(!output.fallback);
+    }
+
+    #[test]
+    fn test_empty_commands_and_running_command_are_skipped() {
+        let prompt = Regex::new(PROMPT).unwrap();
+        let scrollback = lines(&["~/p $ echo hi", "hi", "~/p $", "~/p $ sleep 10", "still going"]);
+        let output = extract(&scrollback, Some(&prompt), 50);
+        assert_eq!(output.text, "hi");
+        assert!(!output.fallback);
+
+        // Two prompts but no output at all
+        let output = extract(&lines(&["~/p $ cd ..", "~ $"]), Some(&prompt), 50);
+        assert_eq!((output.text.as_str(), output.line_count, output.fallback), ("", 0, false));
+    }
+
+    #[test]
+    fn test_fallback_takes_last_non_empty_lines() {
+        let prompt = Regex::new(PROMPT).unwrap();
+        let scrollback = lines(&["one", "", "two", "three", "> "]);
+        let output = extract(&scrollback, Some(&prompt), 2);
+        assert_eq!(output.text, "two\nthree");
+        assert!(output.fallback);
+
+        assert_eq!(extract(&scrollback, None, 50).line_count, 3);
+        assert_eq!(extract(&[], None, 50).line_count, 0);
+    }
+
+    #[test]
+    fn test_default_prompt_pattern() {
+        let config = crate::config::CopyLastOutputConfig::default();
+        let prompt = Regex::new(&config.prompt_pattern).unwrap();
+        for line in ["user@host:~/p$ ls", "~/p ❯ git status", "➜  p", "bash-5.2$", "root@box:/# ls"] {
+            assert!(prompt.is_match(line), "{}", line);
+        }
+        for line in ["error: boom", "  Compiling demo", "Total: 5 $ spent"] {
+            assert!(!prompt.is_match(line), "{}", line);
+        }
+        assert!(parse_shortcut(&config.shortcut).is_some());
+    }
+
+    #[test]
+    fn test_parse_shortcut() {
+        assert_eq!(
+            parse_shortcut("Cmd+Alt+C"),
+            Some(KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::ALT, Key::C))
+        );
+        assert_eq!(parse_shortcut("ctrl + shift + O"), Some(KeyboardShortcut::new(Modifiers::CTRL | Modifiers::SHIFT, Key::O)));
+        assert_eq!(parse_shortcut("Hyper+C"), None);
+        assert_eq!(parse_shortcut("Cmd+"), None);
+    }
+}
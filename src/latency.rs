@@ -0,0 +1,168 @@
+//! Input Latency Measurement
+//!
+//! Measures the time from a keypress to the first visible change in the
+//! focused terminal's grid. Used by the optional latency overlay.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use alacritty_terminal::grid::Grid;
+use alacritty_terminal::term::cell::Cell;
+
+/// Number of samples kept for the running average
+const MAX_SAMPLES: usize = 32;
+
+/// Give up on a probe if nothing changes (e.g. a key without echo)
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Delay of the follow-up repaint that catches the PTY echo
+pub const ECHO_REPAINT_DELAY: Duration = Duration::from_millis(8);
+
+/// Keypress → glyph change latency tracker
+#[derive(Debug, Default)]
+pub struct LatencyProbe {
+    /// Keypress time and grid fingerprint at that time
+    pending: Option<(Instant, u64)>,
+    /// Recent measurements, oldest first
+    samples: VecDeque<Duration>,
+}
+
+impl LatencyProbe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start measuring from a keypress
+    ///
+    /// A probe already in flight is kept so bursts of typing measure the
+    /// first key of the burst.
+    pub fn start(&mut self, at: Instant, fingerprint: u64) {
+        if self.pending.is_none() {
+            self.pending = Some((at, fingerprint));
+        }
+    }
+
+    /// Is a measurement in flight?
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Compare the current grid against the one at keypress time
+    ///
+    /// Returns the latency once the grid has changed.
+    pub fn observe(&mut self, now: Instant, fingerprint: u64) -> Option<Duration> {
+        let (start, before) = self.pending?;
+        let elapsed = now.saturating_duration_since(start);
+
+        if fingerprint != before {
+            self.pending = None;
+            if self.samples.len() == MAX_SAMPLES {
+                self.samples.pop_front();
+            }
+            self.samples.push_back(elapsed);
+            return Some(elapsed);
+        }
+
+        if elapsed > PROBE_TIMEOUT {
+            self.pending = None;
+        }
+        None
+    }
+
+    /// Most recent measurement
+    pub fn last(&self) -> Option<Duration> {
+        self.samples.back().copied()
+    }
+
+    /// Average of recent measurements
+    pub fn average(&self) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let total: Duration = self.samples.iter().sum();
+        Some(total / self.samples.len() as u32)
+    }
+}
+
+/// Hash of the visible grid contents and cursor position
+pub fn grid_fingerprint(grid: &Grid<Cell>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    for indexed in grid.display_iter() {
+        indexed.c.hash(&mut hasher);
+    }
+
+    let cursor = grid.cursor.point;
+    cursor.line.0.hash(&mut hasher);
+    cursor.column.0.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measures_first_change() {
+        let mut probe = LatencyProbe::new();
+        let start = Instant::now();
+
+        probe.start(start, 1);
+        assert!(probe.is_pending());
+
+        // Unchanged grid: still waiting
+        assert_eq!(probe.observe(start + Duration::from_millis(5), 1), None);
+        assert!(probe.is_pending());
+
+        // Changed grid: measurement recorded
+        let latency = probe.observe(start + Duration::from_millis(12), 2);
+        assert_eq!(latency, Some(Duration::from_millis(12)));
+        assert!(!probe.is_pending());
+        assert_eq!(probe.last(), Some(Duration::from_millis(12)));
+    }
+
+    #[test]
+    fn test_burst_keeps_first_keypress() {
+        let mut probe = LatencyProbe::new();
+        let start = Instant::now();
+
+        probe.start(start, 1);
+        probe.start(start + Duration::from_millis(3), 1);
+
+        let latency = probe.observe(start + Duration::from_millis(10), 2);
+        assert_eq!(latency, Some(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn test_timeout_drops_probe() {
+        let mut probe = LatencyProbe::new();
+        let start = Instant::now();
+
+        probe.start(start, 1);
+        assert_eq!(probe.observe(start + PROBE_TIMEOUT * 2, 1), None);
+        assert!(!probe.is_pending());
+        assert_eq!(probe.last(), None);
+    }
+
+    #[test]
+    fn test_average_uses_recent_samples() {
+        let mut probe = LatencyProbe::new();
+        let start = Instant::now();
+
+        for ms in [10, 20, 30] {
+            probe.start(start, 0);
+            probe.observe(start + Duration::from_millis(ms), 1);
+        }
+        assert_eq!(probe.average(), Some(Duration::from_millis(20)));
+
+        // Old samples roll off
+        for _ in 0..MAX_SAMPLES {
+            probe.start(start, 0);
+            probe.observe(start + Duration::from_millis(4), 1);
+        }
+        assert_eq!(probe.average(), Some(Duration::from_millis(4)));
+    }
+}
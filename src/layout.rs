@@ -4,7 +4,9 @@
 //! Each node is either a Leaf (containing a pane) or a Split (dividing space between two children).
 
 use egui::Rect;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 
 // ============================================================================
 // Constants
@@ -22,12 +24,16 @@ pub const DEFAULT_SPLIT_RATIO: f32 = 0.5;
 /// Width of the divider between panes in pixels
 pub const DIVIDER_WIDTH: f32 = 4.0;
 
+/// Panes smaller than this (either side, px) get a marker instead of content
+pub const MIN_RENDER_SIZE: f32 = 10.0;
+
 // ============================================================================
 // Core Types
 // ============================================================================
 
 /// Direction of a split in the layout tree
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SplitDirection {
     /// Left | Right split
     Horizontal,
@@ -39,6 +45,93 @@ pub enum SplitDirection {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PaneId(pub u64);
 
+/// Per-pane settings kept beside the tree, keyed by pane ID
+///
+/// Tree operations keep pane IDs, so a pane's metadata follows it around.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PaneMeta {
+    /// Color washed over the pane and used for its unfocused border
+    pub tint: Option<egui::Color32>,
+}
+
+/// Drop metadata of panes no longer in `root`, and entries with nothing set
+pub fn prune_pane_meta<T>(meta: &mut HashMap<PaneId, PaneMeta>, root: &LayoutNode<T>) {
+    meta.retain(|id, m| *m != PaneMeta::default() && root.get_content(*id).is_some());
+}
+
+/// How a split shares its length between its children
+///
+/// A ratio scales with the split; a fixed size keeps one child at a pixel
+/// length when the window grows or shrinks.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitSize {
+    /// First child's portion, 0.0-1.0
+    Ratio(f32),
+    /// First child's length (px)
+    FixedFirst(f32),
+    /// Second child's length (px)
+    FixedSecond(f32),
+}
+
+impl Default for SplitSize {
+    fn default() -> Self {
+        SplitSize::Ratio(DEFAULT_SPLIT_RATIO)
+    }
+}
+
+impl SplitSize {
+    /// First child's length in a split with `available` px between the children
+    ///
+    /// A fixed size is clamped so neither child drops below the minimum ratio.
+    pub fn first_length(&self, available: f32) -> f32 {
+        let clamp = |length: f32| length.clamp(available * MIN_SPLIT_RATIO, available * MAX_SPLIT_RATIO);
+        match *self {
+            SplitSize::Ratio(ratio) => available * ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO),
+            SplitSize::FixedFirst(length) => clamp(length),
+            SplitSize::FixedSecond(length) => available - clamp(length),
+        }
+    }
+
+    /// First child's portion of `available` px
+    pub fn ratio(&self, available: f32) -> f32 {
+        match self {
+            SplitSize::Ratio(ratio) => ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO),
+            _ if available > 0.0 => self.first_length(available) / available,
+            _ => DEFAULT_SPLIT_RATIO,
+        }
+    }
+
+    /// Same kind of size, moved to `ratio` of `available` px (divider drags)
+    pub fn with_ratio(self, ratio: f32, available: f32) -> Self {
+        let ratio = ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO);
+        match self {
+            SplitSize::Ratio(_) => SplitSize::Ratio(ratio),
+            SplitSize::FixedFirst(_) => SplitSize::FixedFirst(available * ratio),
+            SplitSize::FixedSecond(_) => SplitSize::FixedSecond(available * (1.0 - ratio)),
+        }
+    }
+
+    /// Keep the first (or second) child at its current length
+    pub fn locked(self, second: bool, available: f32) -> Self {
+        let first = self.first_length(available);
+        if second {
+            SplitSize::FixedSecond(available - first)
+        } else {
+            SplitSize::FixedFirst(first)
+        }
+    }
+
+    /// Ratio giving the children their current lengths
+    pub fn unlocked(self, available: f32) -> Self {
+        SplitSize::Ratio(self.ratio(available))
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        !matches!(self, SplitSize::Ratio(_))
+    }
+}
+
 /// A node in the binary split tree layout
 pub enum LayoutNode<T> {
     /// A leaf node containing actual content
@@ -49,8 +142,8 @@ pub enum LayoutNode<T> {
     /// A split node dividing space between two children
     Split {
         direction: SplitDirection,
-        /// Ratio from 0.0-1.0, representing the first child's portion
-        ratio: f32,
+        /// How the children share the split's length
+        size: SplitSize,
         first: Box<LayoutNode<T>>,
         second: Box<LayoutNode<T>>,
     },
@@ -68,6 +161,8 @@ pub struct DividerInfo {
     pub direction: SplitDirection,
     /// Screen rectangle of the divider
     pub rect: Rect,
+    /// Screen rectangle of the split it divides
+    pub parent_rect: Rect,
 }
 
 /// Result of computing layout for the entire tree
@@ -97,23 +192,42 @@ impl Default for ComputedLayout {
 // Helper Functions
 // ============================================================================
 
+/// `rect` with NaN or infinite coordinates zeroed and negative sizes made empty
+fn clamp_rect(rect: Rect) -> Rect {
+    let min = if rect.min.is_finite() { rect.min } else { egui::Pos2::ZERO };
+    let size = if rect.size().is_finite() { rect.size().max(egui::Vec2::ZERO) } else { egui::Vec2::ZERO };
+    Rect::from_min_size(min, size)
+}
+
+/// Is a pane rect big enough to draw its content in?
+pub fn is_renderable(rect: Rect) -> bool {
+    rect.width() >= MIN_RENDER_SIZE && rect.height() >= MIN_RENDER_SIZE
+}
+
+/// Length a split in `rect` shares between its children (px)
+pub fn available_length(rect: Rect, direction: SplitDirection, divider_width: f32) -> f32 {
+    match direction {
+        SplitDirection::Horizontal => (rect.width() - divider_width).max(0.0),
+        SplitDirection::Vertical => (rect.height() - divider_width).max(0.0),
+    }
+}
+
 /// Split a rect into two parts with a divider between them
 ///
 /// Returns (first_rect, divider_rect, second_rect)
 fn split_rect(
     rect: Rect,
     direction: SplitDirection,
-    ratio: f32,
+    size: SplitSize,
     divider_width: f32,
 ) -> (Rect, Rect, Rect) {
-    let ratio = ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO);
-
     match direction {
         SplitDirection::Horizontal => {
-            // Left | Right
+            // Left | Right; a rect narrower than the divider gives empty halves
+            let divider_width = divider_width.min(rect.width());
             let available_width = rect.width() - divider_width;
-            let first_width = available_width * ratio;
-            let second_width = available_width * (1.0 - ratio);
+            let first_width = size.first_length(available_width);
+            let second_width = available_width - first_width;
 
             let first_rect = Rect::from_min_size(
                 rect.min,
@@ -132,9 +246,10 @@ fn split_rect(
         }
         SplitDirection::Vertical => {
             // Top / Bottom
+            let divider_width = divider_width.min(rect.height());
             let available_height = rect.height() - divider_width;
-            let first_height = available_height * ratio;
-            let second_height = available_height * (1.0 - ratio);
+            let first_height = size.first_length(available_height);
+            let second_height = available_height - first_height;
 
             let first_rect = Rect::from_min_size(
                 rect.min,
@@ -167,19 +282,22 @@ impl<T> LayoutNode<T> {
         path: &mut Vec<bool>,
         output: &mut ComputedLayout,
     ) {
+        debug_assert!(rect.is_finite() && !rect.is_negative(), "degenerate layout rect {:?}", rect);
+        let rect = clamp_rect(rect);
         match self {
             LayoutNode::Leaf { id, .. } => {
                 output.pane_rects.insert(*id, rect);
             }
-            LayoutNode::Split { direction, ratio, first, second } => {
+            LayoutNode::Split { direction, size, first, second } => {
                 let (first_rect, divider_rect, second_rect) =
-                    split_rect(rect, *direction, *ratio, divider_width);
+                    split_rect(rect, *direction, *size, divider_width);
 
                 // Record divider with current path
                 output.dividers.push(DividerInfo {
                     path: path.clone(),
                     direction: *direction,
                     rect: divider_rect,
+                    parent_rect: rect,
                 });
 
                 // Recurse into first child
@@ -205,6 +323,12 @@ impl<T> LayoutNode<T> {
         }
     }
 
+    /// Number of splits above a pane, or None if it isn't in the tree
+    pub fn pane_depth(&self, target: PaneId) -> Option<usize> {
+        let mut path = Vec::new();
+        self.find_path_to_pane(target, &mut path).then_some(path.len())
+    }
+
     /// Collect all pane IDs in DFS order
     pub fn collect_pane_ids(&self, out: &mut Vec<PaneId>) {
         match self {
@@ -260,6 +384,29 @@ impl<T> LayoutNode<T> {
         }
     }
 
+    /// Size of the split at given path
+    pub fn size_at_path(&self, path: &[bool]) -> Option<SplitSize> {
+        match self.get_node_at_path(path)? {
+            LayoutNode::Split { size, .. } => Some(*size),
+            LayoutNode::Leaf { .. } => None,
+        }
+    }
+
+    /// Set the size of the split at given path (ratios clamped to the allowed range)
+    pub fn set_size_at_path(&mut self, path: &[bool], new_size: SplitSize) -> bool {
+        match self.get_split_at_path_mut(path) {
+            Some(LayoutNode::Split { size, .. }) => {
+                *size = match new_size {
+                    SplitSize::Ratio(ratio) => SplitSize::Ratio(ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO)),
+                    SplitSize::FixedFirst(length) => SplitSize::FixedFirst(length.max(0.0)),
+                    SplitSize::FixedSecond(length) => SplitSize::FixedSecond(length.max(0.0)),
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Get the node at given path (for accessing content)
     pub fn get_node_at_path(&self, path: &[bool]) -> Option<&Self> {
         if path.is_empty() {
@@ -328,6 +475,26 @@ impl<T> LayoutNode<T> {
         }
     }
 
+    /// First pane (in DFS order) whose content matches `predicate`
+    pub fn find_pane(&self, predicate: &impl Fn(&T) -> bool) -> Option<PaneId> {
+        match self {
+            LayoutNode::Leaf { id, content } => predicate(content).then_some(*id),
+            LayoutNode::Split { first, second, .. } => {
+                first.find_pane(predicate).or_else(|| second.find_pane(predicate))
+            }
+        }
+    }
+
+    /// Swap a leaf's content, keeping its PaneId and position
+    ///
+    /// Returns the old content, or gives `content` back if the pane isn't found.
+    pub fn replace_content(&mut self, target: PaneId, content: T) -> Result<T, T> {
+        match self.get_content_mut(target) {
+            Some(slot) => Ok(std::mem::replace(slot, content)),
+            None => Err(content),
+        }
+    }
+
     /// Collect all pane contents as mutable references in a single traversal
     /// This is O(n) instead of O(n²) when iterating all panes
     pub fn collect_contents_mut(&mut self) -> Vec<(PaneId, &mut T)> {
@@ -390,7 +557,7 @@ pub fn extract_pane<T>(
             // Not the target
             None
         }
-        LayoutNode::Split { direction, ratio, first, second } => {
+        LayoutNode::Split { direction, size, first, second } => {
             // Check if first child IS the target leaf
             if let LayoutNode::Leaf { id, .. } = first.as_ref() {
                 if *id == target_id {
@@ -420,7 +587,7 @@ pub fn extract_pane<T>(
                     return Some((
                         LayoutNode::Split {
                             direction,
-                            ratio,
+                            size,
                             first: Box::new(new_first),
                             second,
                         },
@@ -433,7 +600,7 @@ pub fn extract_pane<T>(
                     return Some((
                         LayoutNode::Split {
                             direction,
-                            ratio,
+                            size,
                             first,
                             second: Box::new(new_second),
                         },
@@ -480,7 +647,7 @@ pub fn insert_adjacent<T>(
 
                     (LayoutNode::Split {
                         direction: split_direction,
-                        ratio: DEFAULT_SPLIT_RATIO,
+                        size: SplitSize::default(),
                         first: Box::new(first),
                         second: Box::new(second),
                     }, None)
@@ -491,13 +658,13 @@ pub fn insert_adjacent<T>(
             LayoutNode::Leaf { id, content } => {
                 (LayoutNode::Leaf { id, content }, new_content)
             }
-            LayoutNode::Split { direction, ratio, first, second } => {
+            LayoutNode::Split { direction, size, first, second } => {
                 let (new_first, remaining) = insert_impl(*first, target_id, new_id, new_content, split_direction, before);
                 if remaining.is_none() {
                     // Inserted in first branch
                     return (LayoutNode::Split {
                         direction,
-                        ratio,
+                        size,
                         first: Box::new(new_first),
                         second,
                     }, None);
@@ -506,7 +673,7 @@ pub fn insert_adjacent<T>(
                 let (new_second, remaining) = insert_impl(*second, target_id, new_id, remaining, split_direction, before);
                 (LayoutNode::Split {
                     direction,
-                    ratio,
+                    size,
                     first: Box::new(new_first),
                     second: Box::new(new_second),
                 }, remaining)
@@ -517,3 +684,1337 @@ pub fn insert_adjacent<T>(
     let (result, _) = insert_impl(node, target_id, new_id, Some(new_content), split_direction, before);
     result
 }
+
+/// Transform a LayoutNode by splitting a target leaf
+pub fn split_node<T>(
+    node: LayoutNode<T>,
+    target_id: PaneId,
+    direction: SplitDirection,
+    new_pane_id: PaneId,
+    new_content: Option<T>,
+) -> (LayoutNode<T>, Option<T>) {
+    match node {
+        LayoutNode::Leaf { id, content } if id == target_id => {
+            // Found the target - split it, consume new_content
+            let new_content = new_content.expect("new_content should be available when target is found");
+            (LayoutNode::Split {
+                direction,
+                size: SplitSize::default(),
+                first: Box::new(LayoutNode::Leaf { id, content }),
+                second: Box::new(LayoutNode::Leaf { id: new_pane_id, content: new_content }),
+            }, None)
+        }
+        LayoutNode::Leaf { id, content } => {
+            // Not the target, return unchanged with content passed through
+            (LayoutNode::Leaf { id, content }, new_content)
+        }
+        LayoutNode::Split { direction: dir, size, first, second } => {
+            // Recurse into first child
+            let (new_first, remaining) = split_node(*first, target_id, direction, new_pane_id, new_content);
+            // Recurse into second child with whatever content is remaining
+            let (new_second, remaining) = split_node(*second, target_id, direction, new_pane_id, remaining);
+            (LayoutNode::Split {
+                direction: dir,
+                size,
+                first: Box::new(new_first),
+                second: Box::new(new_second),
+            }, remaining)
+        }
+    }
+}
+
+/// Remove a pane from the tree, promoting its sibling
+pub fn close_node<T>(node: LayoutNode<T>, target_id: PaneId) -> Option<LayoutNode<T>> {
+    match node {
+        LayoutNode::Leaf { id, .. } if id == target_id => None,
+        LayoutNode::Leaf { id, content } => Some(LayoutNode::Leaf { id, content }),
+        LayoutNode::Split { direction, size, first, second } => {
+            // Check if either direct child is the target
+            if let LayoutNode::Leaf { id, .. } = first.as_ref() {
+                if *id == target_id {
+                    return Some(*second);
+                }
+            }
+            if let LayoutNode::Leaf { id, .. } = second.as_ref() {
+                if *id == target_id {
+                    return Some(*first);
+                }
+            }
+
+            // Recurse
+            let new_first = close_node(*first, target_id);
+            let new_second = close_node(*second, target_id);
+
+            match (new_first, new_second) {
+                (Some(f), Some(s)) => Some(LayoutNode::Split {
+                    direction,
+                    size,
+                    first: Box::new(f),
+                    second: Box::new(s),
+                }),
+                (Some(f), None) => Some(f),
+                (None, Some(s)) => Some(s),
+                (None, None) => None,
+            }
+        }
+    }
+}
+
+/// Remove a pane, returning the new tree and the pane to focus instead
+///
+/// Focus goes to the previous pane in DFS order, or the next one when the
+/// first pane closes. The last pane and unknown panes aren't closed.
+pub fn close_pane<T>(root: LayoutNode<T>, pane_id: PaneId) -> Result<(LayoutNode<T>, PaneId), LayoutNode<T>> {
+    let Some(new_focus) = focus_after_removal(&root, pane_id) else {
+        return Err(root);
+    };
+
+    let root = close_node(root, pane_id).expect("a sibling remains when closing one of several panes");
+    Ok((root, new_focus))
+}
+
+/// Take a pane out of the tree so it can become a tab of its own
+///
+/// Returns the remaining tree (sibling promoted as in [`close_node`]), the
+/// pane's content, and the pane to focus instead, chosen like
+/// [`close_pane`]. The last pane and unknown panes stay in the tree.
+pub fn detach_pane<T>(root: LayoutNode<T>, pane_id: PaneId) -> Result<(LayoutNode<T>, T, PaneId), LayoutNode<T>> {
+    // Checked up front because a failed extraction consumes the tree
+    let Some(new_focus) = focus_after_removal(&root, pane_id) else {
+        return Err(root);
+    };
+
+    let (root, content) = extract_pane(root, pane_id).expect("pane is present and not alone");
+    Ok((root, content, new_focus))
+}
+
+/// Pane to focus once `pane_id` leaves the tree: the previous one in DFS
+/// order, or the next one for the first pane
+///
+/// `None` if the pane is missing or the only one.
+fn focus_after_removal<T>(root: &LayoutNode<T>, pane_id: PaneId) -> Option<PaneId> {
+    let mut pane_ids = Vec::new();
+    root.collect_pane_ids(&mut pane_ids);
+    let idx = pane_ids.iter().position(|id| *id == pane_id)?;
+    if pane_ids.len() <= 1 {
+        return None;
+    }
+    Some(if idx > 0 { pane_ids[idx - 1] } else { pane_ids[1] })
+}
+
+/// Pane after (or before) `current` in DFS order, wrapping around
+pub fn cycle_focus(pane_ids: &[PaneId], current: PaneId, forward: bool) -> Option<PaneId> {
+    let idx = pane_ids.iter().position(|id| *id == current)?;
+    let target = if forward {
+        (idx + 1) % pane_ids.len()
+    } else if idx == 0 {
+        pane_ids.len() - 1
+    } else {
+        idx - 1
+    };
+    Some(pane_ids[target])
+}
+
+/// Where a pane can be dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropZone {
+    /// Drop at top edge (creates vertical split, new pane above)
+    Top(PaneId),
+    /// Drop at bottom edge (creates vertical split, new pane below)
+    Bottom(PaneId),
+    /// Drop at left edge (creates horizontal split, new pane left)
+    Left(PaneId),
+    /// Drop at right edge (creates horizontal split, new pane right)
+    Right(PaneId),
+}
+
+impl DropZone {
+    /// Target pane, split direction, and whether the dropped pane goes first
+    fn placement(self) -> (PaneId, SplitDirection, bool) {
+        match self {
+            DropZone::Top(id) => (id, SplitDirection::Vertical, true),
+            DropZone::Bottom(id) => (id, SplitDirection::Vertical, false),
+            DropZone::Left(id) => (id, SplitDirection::Horizontal, true),
+            DropZone::Right(id) => (id, SplitDirection::Horizontal, false),
+        }
+    }
+}
+
+/// Drop zone with rendering info
+#[derive(Debug)]
+pub struct DropZoneInfo {
+    /// The zone type
+    pub zone: DropZone,
+    /// Hit-test rectangle (25% of edge)
+    pub rect: egui::Rect,
+    /// Visual highlight rectangle (50% preview)
+    pub highlight_rect: egui::Rect,
+}
+
+/// Compute drop zones for all panes except the source pane
+pub fn compute_drop_zones(layout: &ComputedLayout, source_id: PaneId) -> Vec<DropZoneInfo> {
+    let mut zones = Vec::new();
+    let edge_ratio = 0.25;
+
+    for (pane_id, rect) in &layout.pane_rects {
+        if *pane_id == source_id {
+            continue; // Skip source pane
+        }
+
+        let w = rect.width();
+        let h = rect.height();
+
+        // Top zone (25% of height from top)
+        zones.push(DropZoneInfo {
+            zone: DropZone::Top(*pane_id),
+            rect: egui::Rect::from_min_size(rect.min, egui::vec2(w, h * edge_ratio)),
+            highlight_rect: egui::Rect::from_min_size(rect.min, egui::vec2(w, h * 0.5)),
+        });
+
+        // Bottom zone (25% of height from bottom)
+        zones.push(DropZoneInfo {
+            zone: DropZone::Bottom(*pane_id),
+            rect: egui::Rect::from_min_size(
+                egui::pos2(rect.min.x, rect.max.y - h * edge_ratio),
+                egui::vec2(w, h * edge_ratio),
+            ),
+            highlight_rect: egui::Rect::from_min_size(
+                egui::pos2(rect.min.x, rect.min.y + h * 0.5),
+                egui::vec2(w, h * 0.5),
+            ),
+        });
+
+        // Left zone (25% of width from left)
+        zones.push(DropZoneInfo {
+            zone: DropZone::Left(*pane_id),
+            rect: egui::Rect::from_min_size(rect.min, egui::vec2(w * edge_ratio, h)),
+            highlight_rect: egui::Rect::from_min_size(rect.min, egui::vec2(w * 0.5, h)),
+        });
+
+        // Right zone (25% of width from right)
+        zones.push(DropZoneInfo {
+            zone: DropZone::Right(*pane_id),
+            rect: egui::Rect::from_min_size(
+                egui::pos2(rect.max.x - w * edge_ratio, rect.min.y),
+                egui::vec2(w * edge_ratio, h),
+            ),
+            highlight_rect: egui::Rect::from_min_size(
+                egui::pos2(rect.min.x + w * 0.5, rect.min.y),
+                egui::vec2(w * 0.5, h),
+            ),
+        });
+    }
+
+    zones
+}
+
+/// Move `source_id` to the drop zone's side of its target pane
+///
+/// The moved pane keeps its id and content (and so its PTY). The tree is
+/// handed back unchanged if the move isn't possible: the source is the
+/// only pane, or either pane is missing, or the target is the source.
+pub fn drop_pane<T>(root: LayoutNode<T>, source_id: PaneId, zone: DropZone) -> Result<LayoutNode<T>, LayoutNode<T>> {
+    let (target_id, direction, before) = zone.placement();
+    if target_id == source_id || root.get_content(target_id).is_none() {
+        return Err(root);
+    }
+    // Checked up front because a failed extraction consumes the tree
+    if root.get_content(source_id).is_none() || root.pane_count() < 2 {
+        return Err(root);
+    }
+
+    let (without_source, content) = extract_pane(root, source_id).expect("source pane is present and not alone");
+    Ok(insert_adjacent(without_source, target_id, source_id, content, direction, before))
+}
+
+/// Depth of the dropped pane if `source_id` were dropped on `zone`
+///
+/// Taking the source out lifts its sibling's subtree one level, which
+/// matters when the target is in it.
+pub fn depth_after_drop<T>(root: &LayoutNode<T>, source_id: PaneId, zone: DropZone) -> Option<usize> {
+    let (target_id, _, _) = zone.placement();
+    let (mut source_path, mut target_path) = (Vec::new(), Vec::new());
+    if !root.find_path_to_pane(source_id, &mut source_path) || !root.find_path_to_pane(target_id, &mut target_path) {
+        return None;
+    }
+    let parent = &source_path[..source_path.len().saturating_sub(1)];
+    let lifted = !source_path.is_empty() && target_path.starts_with(parent);
+    Some(target_path.len() - usize::from(lifted) + 1)
+}
+
+/// Direction for keyboard pane navigation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaneDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl PaneDirection {
+    /// Split direction, and whether the moved pane goes first, when placing
+    /// a pane on this side of another
+    fn placement(self) -> (SplitDirection, bool) {
+        match self {
+            PaneDirection::Left => (SplitDirection::Horizontal, true),
+            PaneDirection::Right => (SplitDirection::Horizontal, false),
+            PaneDirection::Up => (SplitDirection::Vertical, true),
+            PaneDirection::Down => (SplitDirection::Vertical, false),
+        }
+    }
+}
+
+/// Nearest pane on one side of `from`, by screen geometry
+///
+/// Only panes that overlap `from` across the direction count; among those
+/// the closest edge wins, then the largest overlap, then top/left.
+pub fn pane_in_direction(layout: &ComputedLayout, from: PaneId, direction: PaneDirection) -> Option<PaneId> {
+    let origin = *layout.pane_rects.get(&from)?;
+
+    let mut best: Option<(f32, f32, f32, PaneId)> = None;
+    for (&id, rect) in &layout.pane_rects {
+        if id == from {
+            continue;
+        }
+        // Gap to the candidate and overlap on the other axis
+        let (gap, overlap, position) = match direction {
+            PaneDirection::Left => (origin.min.x - rect.max.x, span_overlap(origin.y_range(), rect.y_range()), rect.min.y),
+            PaneDirection::Right => (rect.min.x - origin.max.x, span_overlap(origin.y_range(), rect.y_range()), rect.min.y),
+            PaneDirection::Up => (origin.min.y - rect.max.y, span_overlap(origin.x_range(), rect.x_range()), rect.min.x),
+            PaneDirection::Down => (rect.min.y - origin.max.y, span_overlap(origin.x_range(), rect.x_range()), rect.min.x),
+        };
+        if gap < -0.5 || overlap <= 0.5 {
+            continue;
+        }
+
+        let better = match best {
+            None => true,
+            Some((best_gap, best_overlap, best_position, _)) => {
+                (gap, -overlap, position) < (best_gap, -best_overlap, best_position)
+            }
+        };
+        if better {
+            best = Some((gap, overlap, position, id));
+        }
+    }
+    best.map(|(_, _, _, id)| id)
+}
+
+fn span_overlap(a: egui::Rangef, b: egui::Rangef) -> f32 {
+    a.max.min(b.max) - a.min.max(b.min)
+}
+
+/// Move a pane past its neighbor in `direction`, keeping its `PaneId`
+///
+/// Without a neighbor on that side the pane moves to that outer edge of the
+/// whole tree. The tree is returned unchanged if the pane is missing or alone.
+pub fn move_pane<T>(root: LayoutNode<T>, pane_id: PaneId, direction: PaneDirection) -> LayoutNode<T> {
+    let mut layout = ComputedLayout::new();
+    let bounds = Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(1000.0, 1000.0));
+    root.compute_layout(bounds, DIVIDER_WIDTH, &mut Vec::new(), &mut layout);
+    if layout.pane_rects.len() < 2 || !layout.pane_rects.contains_key(&pane_id) {
+        return root;
+    }
+
+    let neighbor = pane_in_direction(&layout, pane_id, direction);
+    let Some((rest, content)) = extract_pane(root, pane_id) else {
+        unreachable!("pane {} is in a tree of two or more panes", pane_id.0);
+    };
+    let (split_direction, before) = direction.placement();
+
+    match neighbor {
+        Some(target) => insert_adjacent(rest, target, pane_id, content, split_direction, before),
+        None => {
+            let moved = Box::new(LayoutNode::Leaf { id: pane_id, content });
+            let rest = Box::new(rest);
+            let (first, second) = if before { (moved, rest) } else { (rest, moved) };
+            LayoutNode::Split {
+                direction: split_direction,
+                size: SplitSize::default(),
+                first,
+                second,
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Layout Description
+// ============================================================================
+
+/// Declarative pane arrangement, e.g. `h(0, v(1, 2), 0.6)`
+///
+/// `h(a, b)` puts `a` left of `b`, `v(a, b)` puts `a` above `b`; numbers are
+/// pane indices and the optional third argument is the first child's ratio,
+/// or a fixed size: `200px` for the first child, `-200px` for the second.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutSpec {
+    Pane(usize),
+    Split {
+        direction: SplitDirection,
+        size: SplitSize,
+        first: Box<LayoutSpec>,
+        second: Box<LayoutSpec>,
+    },
+}
+
+impl LayoutSpec {
+    /// Parse a layout description
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut parser = SpecParser { chars: input.chars().collect(), pos: 0 };
+        let spec = parser.node()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            return Err(format!("Unexpected '{}' at column {}", parser.chars[parser.pos], parser.pos + 1));
+        }
+        Ok(spec)
+    }
+
+    /// `count` panes side by side with equal widths
+    pub fn row(count: usize) -> Option<Self> {
+        let mut indices = (0..count).rev();
+        let mut spec = LayoutSpec::Pane(indices.next()?);
+        for (placed, index) in indices.enumerate() {
+            // The new pane takes 1/(n+1) so every column ends up the same width
+            spec = LayoutSpec::Split {
+                direction: SplitDirection::Horizontal,
+                size: SplitSize::Ratio(1.0 / (placed + 2) as f32),
+                first: Box::new(LayoutSpec::Pane(index)),
+                second: Box::new(spec),
+            };
+        }
+        Some(spec)
+    }
+
+    /// Pane indices in left-to-right / top-to-bottom order
+    pub fn pane_indices(&self) -> Vec<usize> {
+        match self {
+            LayoutSpec::Pane(index) => vec![*index],
+            LayoutSpec::Split { first, second, .. } => {
+                let mut indices = first.pane_indices();
+                indices.extend(second.pane_indices());
+                indices
+            }
+        }
+    }
+}
+
+/// Recursive-descent parser for [`LayoutSpec`]
+struct SpecParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl SpecParser {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.get(self.pos) {
+            Some(&c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(&c) => Err(format!("Expected '{}' but found '{}' at column {}", expected, c, self.pos + 1)),
+            None => Err(format!("Expected '{}' but the layout ended", expected)),
+        }
+    }
+
+    /// Digits and dots starting at the current position
+    fn number(&mut self) -> String {
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// A ratio, or a fixed size in px (negative for the second child)
+    fn size(&mut self) -> Result<SplitSize, String> {
+        let column = self.pos + 1;
+        let second = self.chars.get(self.pos) == Some(&'-');
+        if second {
+            self.pos += 1;
+        }
+        let text = self.number();
+        let rest: String = self.chars[self.pos..].iter().take(2).collect();
+        if rest == "px" {
+            self.pos += 2;
+            let length = text
+                .parse::<f32>()
+                .ok()
+                .filter(|length| length.is_finite())
+                .ok_or_else(|| format!("Invalid size '{}px' at column {}", text, column))?;
+            return Ok(if second { SplitSize::FixedSecond(length) } else { SplitSize::FixedFirst(length) });
+        }
+        text.parse::<f32>()
+            .ok()
+            .filter(|r| !second && (MIN_SPLIT_RATIO..=MAX_SPLIT_RATIO).contains(r))
+            .map(SplitSize::Ratio)
+            .ok_or_else(|| format!(
+                "Invalid ratio '{}{}' at column {} (expected {}-{} or a size like 200px)",
+                if second { "-" } else { "" }, text, column, MIN_SPLIT_RATIO, MAX_SPLIT_RATIO
+            ))
+    }
+
+    fn node(&mut self) -> Result<LayoutSpec, String> {
+        self.skip_whitespace();
+        let direction = match self.chars.get(self.pos) {
+            Some('h') | Some('H') => SplitDirection::Horizontal,
+            Some('v') | Some('V') => SplitDirection::Vertical,
+            Some(c) if c.is_ascii_digit() => {
+                let column = self.pos + 1;
+                let text = self.number();
+                return text
+                    .parse()
+                    .map(LayoutSpec::Pane)
+                    .map_err(|_| format!("Invalid pane index '{}' at column {}", text, column));
+            }
+            Some(c) => return Err(format!("Unexpected '{}' at column {}", c, self.pos + 1)),
+            None => return Err("Layout is empty".to_string()),
+        };
+        self.pos += 1;
+
+        self.expect('(')?;
+        let first = self.node()?;
+        self.expect(',')?;
+        let second = self.node()?;
+
+        self.skip_whitespace();
+        let mut size = SplitSize::default();
+        if self.chars.get(self.pos) == Some(&',') {
+            self.pos += 1;
+            self.skip_whitespace();
+            size = self.size()?;
+        }
+        self.expect(')')?;
+
+        Ok(LayoutSpec::Split {
+            direction,
+            size,
+            first: Box::new(first),
+            second: Box::new(second),
+        })
+    }
+}
+
+// ============================================================================
+// Divider Dragging
+// ============================================================================
+
+/// Step for Alt-quantized divider drags
+pub const RATIO_STEP: f32 = 0.05;
+
+/// Divider drag in progress
+#[derive(Debug, Clone)]
+pub struct DividerDrag {
+    /// Workspace the divider belongs to
+    pub workspace: usize,
+    /// Index into `ComputedLayout::dividers`
+    pub divider: usize,
+    /// Path to the split being resized
+    pub path: Vec<bool>,
+    /// Size when the drag started
+    pub original_size: SplitSize,
+}
+
+impl DividerDrag {
+    /// Put the split back to its size from before the drag
+    pub fn restore<T>(&self, root: &mut LayoutNode<T>) {
+        root.set_size_at_path(&self.path, self.original_size);
+    }
+}
+
+/// Split ratio for a divider dragged to `pos` within its split's rect
+///
+/// With `quantize` the ratio snaps to `RATIO_STEP` increments.
+pub fn drag_ratio(pos: egui::Pos2, parent_rect: Rect, direction: SplitDirection, quantize: bool) -> f32 {
+    let available = available_length(parent_rect, direction, DIVIDER_WIDTH);
+    let ratio = match direction {
+        SplitDirection::Horizontal => (pos.x - parent_rect.left()) / available,
+        SplitDirection::Vertical => (pos.y - parent_rect.top()) / available,
+    };
+    let ratio = if quantize { quantize_ratio(ratio, RATIO_STEP) } else { ratio };
+    ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO)
+}
+
+/// Round a ratio to the nearest multiple of `step`, within the allowed range
+pub fn quantize_ratio(ratio: f32, step: f32) -> f32 {
+    ((ratio / step).round() * step).clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO)
+}
+
+/// Whole percentages of both sides of a split, adding up to 100
+pub fn split_percentages(ratio: f32) -> (u32, u32) {
+    let first = (ratio.clamp(0.0, 1.0) * 100.0).round() as u32;
+    (first, 100 - first)
+}
+
+/// Extra px on each side of a divider that still grab it
+pub const DIVIDER_GRAB_MARGIN: f32 = 3.0;
+
+/// Area that grabs a divider: its strip widened across the split
+pub fn divider_hit_rect(rect: Rect, direction: SplitDirection, margin: f32) -> Rect {
+    match direction {
+        SplitDirection::Horizontal => rect.expand2(egui::vec2(margin, 0.0)),
+        SplitDirection::Vertical => rect.expand2(egui::vec2(0.0, margin)),
+    }
+}
+
+/// What a button press in the pane area belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressOwner {
+    /// Index into `ComputedLayout::dividers`
+    Divider(usize),
+    Pane(PaneId),
+    Nothing,
+}
+
+/// Who gets a press at `pos`
+///
+/// A divider's widened hit area overlaps the panes next to it, and there
+/// the divider wins: pane dragging must not start from a press meant to
+/// resize. The divider's interaction is allocated after pane content for
+/// the same reason, so the pane widgets don't take the press either.
+pub fn press_owner(pos: egui::Pos2, layout: &ComputedLayout, margin: f32) -> PressOwner {
+    let divider = layout.dividers
+        .iter()
+        .position(|divider| divider_hit_rect(divider.rect, divider.direction, margin).contains(pos));
+    if let Some(index) = divider {
+        return PressOwner::Divider(index);
+    }
+    layout.pane_rects
+        .iter()
+        .find(|(_, rect)| rect.contains(pos))
+        .map_or(PressOwner::Nothing, |(pane_id, _)| PressOwner::Pane(*pane_id))
+}
+
+/// Should a divider drag be cancelled because the button was released
+/// outside the window?
+pub fn released_outside(pointer: Option<egui::Pos2>, window: Rect) -> bool {
+    !pointer.is_some_and(|pos| window.contains(pos))
+}
+
+// ============================================================================
+// Resize Debouncing
+// ============================================================================
+
+/// Should a pending pane size be sent to the PTY now?
+///
+/// While a divider or the window is being dragged the size changes every
+/// frame; forwarding each one makes full-screen TUIs redraw constantly.
+/// The size is applied once it has been stable for `debounce`, or right
+/// away when a divider drag ends.
+pub fn should_commit_resize(since_last_change: Duration, debounce: Duration, drag_ended: bool) -> bool {
+    drag_ended || since_last_change >= debounce
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resize_waits_for_stable_size() {
+        let debounce = Duration::from_millis(100);
+
+        assert!(!should_commit_resize(Duration::ZERO, debounce, false));
+        assert!(!should_commit_resize(Duration::from_millis(99), debounce, false));
+        assert!(should_commit_resize(Duration::from_millis(100), debounce, false));
+        assert!(should_commit_resize(Duration::from_secs(2), debounce, false));
+    }
+
+    #[test]
+    fn test_resize_commits_when_drag_ends() {
+        let debounce = Duration::from_millis(100);
+        assert!(should_commit_resize(Duration::ZERO, debounce, true));
+        assert!(should_commit_resize(Duration::from_millis(50), debounce, true));
+    }
+
+    #[test]
+    fn test_zero_debounce_commits_immediately() {
+        assert!(should_commit_resize(Duration::ZERO, Duration::ZERO, false));
+    }
+
+    #[test]
+    fn test_parse_layout_spec() {
+        let spec = LayoutSpec::parse("h(0, v(1, 2), 0.6)").unwrap();
+        match &spec {
+            LayoutSpec::Split { direction, size, first, second } => {
+                assert_eq!(*direction, SplitDirection::Horizontal);
+                assert_eq!(*size, SplitSize::Ratio(0.6));
+                assert_eq!(**first, LayoutSpec::Pane(0));
+                assert!(matches!(**second, LayoutSpec::Split { direction: SplitDirection::Vertical, .. }));
+            }
+            _ => panic!("expected split"),
+        }
+        assert_eq!(spec.pane_indices(), vec![0, 1, 2]);
+        assert_eq!(LayoutSpec::parse(" 3 ").unwrap(), LayoutSpec::Pane(3));
+
+        let fixed = |text| match LayoutSpec::parse(text).unwrap() {
+            LayoutSpec::Split { size, .. } => size,
+            LayoutSpec::Pane(_) => panic!("expected split"),
+        };
+        assert_eq!(fixed("v(0, 1, 200px)"), SplitSize::FixedFirst(200.0));
+        assert_eq!(fixed("v(0, 1, -180.5px)"), SplitSize::FixedSecond(180.5));
+    }
+
+    #[test]
+    fn test_parse_layout_spec_errors() {
+        assert!(LayoutSpec::parse("").is_err());
+        assert!(LayoutSpec::parse("h(0)").is_err());
+        assert!(LayoutSpec::parse("h(0, 1").is_err());
+        assert!(LayoutSpec::parse("x(0, 1)").is_err());
+        assert!(LayoutSpec::parse("h(0, 1, 1.5)").is_err());
+        assert!(LayoutSpec::parse("h(0, 1, -0.5)").is_err());
+        assert!(LayoutSpec::parse("h(0, 1, px)").is_err());
+        assert!(LayoutSpec::parse("h(0, 1) 2").is_err());
+    }
+
+    #[test]
+    fn test_row_layout_has_equal_widths() {
+        assert_eq!(LayoutSpec::row(0), None);
+        assert_eq!(LayoutSpec::row(1), Some(LayoutSpec::Pane(0)));
+
+        let spec = LayoutSpec::row(3).unwrap();
+        assert_eq!(spec.pane_indices(), vec![0, 1, 2]);
+        match spec {
+            LayoutSpec::Split { size: SplitSize::Ratio(ratio), second, .. } => {
+                assert!((ratio - 1.0 / 3.0).abs() < 1e-6);
+                assert!(matches!(*second, LayoutSpec::Split { size: SplitSize::Ratio(ratio), .. } if (ratio - 0.5).abs() < 1e-6));
+            }
+            _ => panic!("expected split"),
+        }
+    }
+
+    #[test]
+    fn test_replace_content_keeps_pane_id() {
+        let mut root = LayoutNode::Split {
+            direction: SplitDirection::Horizontal,
+            size: SplitSize::Ratio(0.3),
+            first: Box::new(LayoutNode::Leaf { id: PaneId(0), content: "viewer" }),
+            second: Box::new(LayoutNode::Leaf { id: PaneId(1), content: "shell" }),
+        };
+
+        assert_eq!(root.replace_content(PaneId(0), "terminal"), Ok("viewer"));
+        assert_eq!(root.get_content(PaneId(0)), Some(&"terminal"));
+        assert_eq!(root.get_content(PaneId(1)), Some(&"shell"));
+        assert!(matches!(root, LayoutNode::Split { size: SplitSize::Ratio(ratio), .. } if (ratio - 0.3).abs() < f32::EPSILON));
+
+        assert_eq!(root.replace_content(PaneId(7), "lost"), Err("lost"));
+    }
+
+    /// Tree nested `depth` levels deep, alternating directions, newest pane innermost
+    fn nested(depth: u64) -> LayoutNode<()> {
+        let mut root = LayoutNode::Leaf { id: PaneId(depth), content: () };
+        for id in (0..depth).rev() {
+            root = LayoutNode::Split {
+                direction: if id % 2 == 0 { SplitDirection::Horizontal } else { SplitDirection::Vertical },
+                size: SplitSize::default(),
+                first: Box::new(LayoutNode::Leaf { id: PaneId(id), content: () }),
+                second: Box::new(root),
+            };
+        }
+        root
+    }
+
+    #[test]
+    fn test_deep_tree_layout_stays_sane() {
+        let root = nested(10);
+        assert_eq!(root.pane_depth(PaneId(10)), Some(10));
+        assert_eq!(root.pane_depth(PaneId(99)), None);
+
+        let mut layout = ComputedLayout::new();
+        let rect = Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(300.0, 300.0));
+        root.compute_layout(rect, DIVIDER_WIDTH, &mut Vec::new(), &mut layout);
+        assert_eq!(layout.pane_rects.len(), 11);
+        for rect in layout.pane_rects.values().chain(layout.dividers.iter().map(|d| &d.rect)) {
+            assert!(rect.is_finite() && !rect.is_negative(), "bad rect {:?}", rect);
+            assert!(rect.max.x <= 300.0 + 0.01 && rect.max.y <= 300.0 + 0.01);
+        }
+
+        // The outer panes are drawn, the innermost ones get a marker
+        assert!(is_renderable(layout.pane_rects[&PaneId(0)]));
+        assert!(!is_renderable(layout.pane_rects[&PaneId(10)]));
+    }
+
+    #[test]
+    fn test_degenerate_rects_are_clamped() {
+        assert_eq!(clamp_rect(Rect::from_min_size(egui::pos2(5.0, 5.0), egui::vec2(-3.0, 2.0))).size(), egui::vec2(0.0, 2.0));
+        let nan = Rect::from_min_max(egui::pos2(f32::NAN, 0.0), egui::pos2(1.0, 1.0));
+        assert!(clamp_rect(nan).is_finite());
+
+        // A split narrower than its divider gives empty halves
+        let (first, _, second) = split_rect(Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(2.0, 50.0)), SplitDirection::Horizontal, SplitSize::Ratio(0.5), DIVIDER_WIDTH);
+        assert_eq!((first.width(), second.width()), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_depth_after_drop() {
+        let root = tree("h(0, v(1, 2))");
+        // Next to a pane at depth 2
+        assert_eq!(depth_after_drop(&root, PaneId(0), DropZone::Left(PaneId(2))), Some(2));
+        assert_eq!(depth_after_drop(&root, PaneId(1), DropZone::Left(PaneId(0))), Some(2));
+        // Pane 2 moves up a level once pane 1 is out
+        assert_eq!(depth_after_drop(&root, PaneId(1), DropZone::Top(PaneId(2))), Some(2));
+        assert_eq!(depth_after_drop(&root, PaneId(7), DropZone::Top(PaneId(2))), None);
+
+        let deep = nested(4);
+        assert_eq!(depth_after_drop(&deep, PaneId(0), DropZone::Right(PaneId(4))), Some(4));
+        assert_eq!(depth_after_drop(&deep, PaneId(4), DropZone::Right(PaneId(3))), Some(4));
+        assert_eq!(depth_after_drop(&deep, PaneId(1), DropZone::Right(PaneId(4))), Some(4));
+        assert_eq!(depth_after_drop(&deep, PaneId(2), DropZone::Right(PaneId(0))), Some(2));
+    }
+
+    /// Tree with pane ids taken from a layout description
+    fn tree(spec: &str) -> LayoutNode<()> {
+        fn build(spec: &LayoutSpec) -> LayoutNode<()> {
+            match spec {
+                LayoutSpec::Pane(index) => LayoutNode::Leaf { id: PaneId(*index as u64), content: () },
+                LayoutSpec::Split { direction, size, first, second } => LayoutNode::Split {
+                    direction: *direction,
+                    size: *size,
+                    first: Box::new(build(first)),
+                    second: Box::new(build(second)),
+                },
+            }
+        }
+        build(&LayoutSpec::parse(spec).unwrap())
+    }
+
+    /// Layout description of a tree, without ratios
+    fn describe<T>(node: &LayoutNode<T>) -> String {
+        match node {
+            LayoutNode::Leaf { id, .. } => id.0.to_string(),
+            LayoutNode::Split { direction, first, second, .. } => {
+                let tag = match direction {
+                    SplitDirection::Horizontal => "h",
+                    SplitDirection::Vertical => "v",
+                };
+                format!("{}({}, {})", tag, describe(first), describe(second))
+            }
+        }
+    }
+
+    #[test]
+    fn test_move_pane_in_grid() {
+        // 0 | 2
+        // --+--
+        // 1 | 3
+        let grid = "h(v(0, 1), v(2, 3))";
+
+        let moved = move_pane(tree(grid), PaneId(2), PaneDirection::Left);
+        assert_eq!(describe(&moved), "h(v(h(2, 0), 1), 3)");
+
+        let moved = move_pane(tree(grid), PaneId(1), PaneDirection::Right);
+        assert_eq!(describe(&moved), "h(0, v(2, h(3, 1)))");
+
+        let moved = move_pane(tree(grid), PaneId(0), PaneDirection::Down);
+        assert_eq!(describe(&moved), "h(v(1, 0), v(2, 3))");
+
+        let moved = move_pane(tree(grid), PaneId(3), PaneDirection::Up);
+        assert_eq!(describe(&moved), "h(v(0, 1), v(3, 2))");
+    }
+
+    #[test]
+    fn test_move_pane_at_workspace_edge() {
+        let grid = "h(v(0, 1), v(2, 3))";
+
+        let moved = move_pane(tree(grid), PaneId(0), PaneDirection::Left);
+        assert_eq!(describe(&moved), "h(0, h(1, v(2, 3)))");
+
+        let moved = move_pane(tree(grid), PaneId(3), PaneDirection::Down);
+        assert_eq!(describe(&moved), "v(h(v(0, 1), 2), 3)");
+
+        let moved = move_pane(tree(grid), PaneId(2), PaneDirection::Up);
+        assert_eq!(describe(&moved), "v(2, h(v(0, 1), 3))");
+    }
+
+    #[test]
+    fn test_move_pane_keeps_lone_or_missing_pane() {
+        assert_eq!(describe(&move_pane(tree("0"), PaneId(0), PaneDirection::Left)), "0");
+        assert_eq!(describe(&move_pane(tree("h(0, 1)"), PaneId(9), PaneDirection::Up)), "h(0, 1)");
+    }
+
+    #[test]
+    fn test_cancelled_drag_restores_ratio() {
+        let mut root = tree("h(0, v(1, 2, 0.3))");
+        let drag = DividerDrag {
+            workspace: 0,
+            divider: 1,
+            path: vec![true],
+            original_size: root.size_at_path(&[true]).unwrap(),
+        };
+
+        assert!(root.set_size_at_path(&[true], SplitSize::Ratio(0.8)));
+        assert_eq!(root.size_at_path(&[true]), Some(SplitSize::Ratio(0.8)));
+
+        drag.restore(&mut root);
+        assert_eq!(root.size_at_path(&[true]), Some(SplitSize::Ratio(0.3)));
+        assert_eq!(root.size_at_path(&[]), Some(SplitSize::Ratio(0.5)));
+        assert!(!root.set_size_at_path(&[false], SplitSize::Ratio(0.4)));
+    }
+
+    fn widths(root: &LayoutNode<()>, width: f32) -> (f32, f32) {
+        let mut layout = ComputedLayout::new();
+        let rect = Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width, 300.0));
+        root.compute_layout(rect, DIVIDER_WIDTH, &mut Vec::new(), &mut layout);
+        (layout.pane_rects[&PaneId(0)].width(), layout.pane_rects[&PaneId(1)].width())
+    }
+
+    #[test]
+    fn test_fixed_split_keeps_its_size_as_the_parent_resizes() {
+        let mut root = tree("h(0, 1)");
+        for width in [400.0, 1000.0, 2000.0] {
+            let (first, second) = widths(&root, width);
+            assert!((first - second).abs() < 0.01);
+        }
+
+        root.set_size_at_path(&[], SplitSize::FixedFirst(200.0));
+        for width in [400.0, 1000.0, 2000.0] {
+            let (first, second) = widths(&root, width);
+            assert!((first - 200.0).abs() < 0.01, "{} wide", width);
+            assert!((first + second + DIVIDER_WIDTH - width).abs() < 0.01);
+        }
+
+        root.set_size_at_path(&[], SplitSize::FixedSecond(200.0));
+        for width in [400.0, 1000.0, 2000.0] {
+            let (_, second) = widths(&root, width);
+            assert!((second - 200.0).abs() < 0.01, "{} wide", width);
+        }
+    }
+
+    #[test]
+    fn test_fixed_split_clamps_when_the_parent_is_too_small() {
+        let mut root = tree("h(0, 1)");
+        root.set_size_at_path(&[], SplitSize::FixedFirst(200.0));
+        let available = 150.0;
+        let (first, second) = widths(&root, available + DIVIDER_WIDTH);
+        assert!((first - available * MAX_SPLIT_RATIO).abs() < 0.01);
+        assert!((second - available * MIN_SPLIT_RATIO).abs() < 0.01);
+
+        // Growing again brings the locked size back
+        let (first, _) = widths(&root, 800.0);
+        assert!((first - 200.0).abs() < 0.01);
+
+        root.set_size_at_path(&[], SplitSize::FixedSecond(200.0));
+        let (first, second) = widths(&root, available + DIVIDER_WIDTH);
+        assert!((first - available * MIN_SPLIT_RATIO).abs() < 0.01);
+        assert!((second - available * MAX_SPLIT_RATIO).abs() < 0.01);
+
+        let (first, second) = widths(&root, 0.0);
+        assert_eq!((first, second), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_lock_drag_and_unlock_keep_the_current_sizes() {
+        let available = 800.0;
+        let size = SplitSize::Ratio(0.25);
+
+        let first = size.locked(false, available);
+        assert_eq!(first, SplitSize::FixedFirst(200.0));
+        let second = size.locked(true, available);
+        assert_eq!(second, SplitSize::FixedSecond(600.0));
+        assert!((first.first_length(available) - second.first_length(available)).abs() < 0.01);
+
+        // Dragging a fixed split moves its px size, not its kind
+        assert_eq!(first.with_ratio(0.5, available), SplitSize::FixedFirst(400.0));
+        assert_eq!(second.with_ratio(0.5, available), SplitSize::FixedSecond(400.0));
+        assert_eq!(size.with_ratio(0.95, available), SplitSize::Ratio(MAX_SPLIT_RATIO));
+
+        // Unlocking gives the ratio for the sizes at the time
+        assert_eq!(first.unlocked(available), SplitSize::Ratio(0.25));
+        assert_eq!(first.unlocked(1600.0), SplitSize::Ratio(0.125));
+        assert!(!second.unlocked(available).is_fixed());
+    }
+
+    #[test]
+    fn test_quantize_ratio() {
+        assert!((quantize_ratio(0.52, RATIO_STEP) - 0.5).abs() < 1e-6);
+        assert!((quantize_ratio(0.53, RATIO_STEP) - 0.55).abs() < 1e-6);
+        assert!((quantize_ratio(0.333, RATIO_STEP) - 0.35).abs() < 1e-6);
+        assert!((quantize_ratio(0.01, RATIO_STEP) - MIN_SPLIT_RATIO).abs() < 1e-6);
+        assert!((quantize_ratio(0.99, RATIO_STEP) - MAX_SPLIT_RATIO).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_divider_hit_rect_widens_across_the_split() {
+        let strip = Rect::from_min_size(egui::pos2(100.0, 0.0), egui::vec2(4.0, 50.0));
+        let hit = divider_hit_rect(strip, SplitDirection::Horizontal, 3.0);
+        assert_eq!(hit, Rect::from_min_max(egui::pos2(97.0, 0.0), egui::pos2(107.0, 50.0)));
+
+        let strip = Rect::from_min_size(egui::pos2(0.0, 100.0), egui::vec2(50.0, 4.0));
+        let hit = divider_hit_rect(strip, SplitDirection::Vertical, 3.0);
+        assert_eq!(hit, Rect::from_min_max(egui::pos2(0.0, 97.0), egui::pos2(50.0, 107.0)));
+    }
+
+    #[test]
+    fn test_press_near_divider_belongs_to_it() {
+        // Left and right panes with a 4px divider at x 100..104
+        let mut layout = ComputedLayout::new();
+        layout.pane_rects.insert(PaneId(0), Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(100.0, 50.0)));
+        layout.pane_rects.insert(PaneId(1), Rect::from_min_max(egui::pos2(104.0, 0.0), egui::pos2(200.0, 50.0)));
+        layout.dividers.push(DividerInfo {
+            path: Vec::new(),
+            direction: SplitDirection::Horizontal,
+            rect: Rect::from_min_max(egui::pos2(100.0, 0.0), egui::pos2(104.0, 50.0)),
+            parent_rect: Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(200.0, 50.0)),
+        });
+
+        let owner = |x: f32| press_owner(egui::pos2(x, 20.0), &layout, DIVIDER_GRAB_MARGIN);
+        assert_eq!(owner(102.0), PressOwner::Divider(0));
+        // Inside the panes but within the margin: still the divider, not a pane drag
+        assert_eq!(owner(97.5), PressOwner::Divider(0));
+        assert_eq!(owner(106.5), PressOwner::Divider(0));
+        assert_eq!(owner(96.0), PressOwner::Pane(PaneId(0)));
+        assert_eq!(owner(108.0), PressOwner::Pane(PaneId(1)));
+        assert_eq!(press_owner(egui::pos2(300.0, 20.0), &layout, DIVIDER_GRAB_MARGIN), PressOwner::Nothing);
+    }
+
+    #[test]
+    fn test_drag_ratio_uses_split_rect() {
+        let parent = Rect::from_min_size(egui::pos2(100.0, 0.0), egui::vec2(204.0, 50.0));
+        let pos = egui::pos2(171.0, 10.0);
+
+        let ratio = drag_ratio(pos, parent, SplitDirection::Horizontal, false);
+        assert!((ratio - 0.355).abs() < 1e-6);
+        let ratio = drag_ratio(pos, parent, SplitDirection::Horizontal, true);
+        assert!((ratio - 0.35).abs() < 1e-6);
+        let ratio = drag_ratio(egui::pos2(0.0, 0.0), parent, SplitDirection::Horizontal, false);
+        assert!((ratio - MIN_SPLIT_RATIO).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_split_percentages_and_release() {
+        assert_eq!(split_percentages(0.5), (50, 50));
+        assert_eq!(split_percentages(1.0 / 3.0), (33, 67));
+        assert_eq!(split_percentages(0.999), (100, 0));
+
+        let window = Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0));
+        assert!(!released_outside(Some(egui::pos2(10.0, 10.0)), window));
+        assert!(released_outside(Some(egui::pos2(900.0, 10.0)), window));
+        assert!(released_outside(None, window));
+    }
+
+    // ------------------------------------------------------------------
+    // Tree operations with a lightweight content type
+    // ------------------------------------------------------------------
+
+    /// Test content remembering the pane it was created for
+    #[derive(Debug, PartialEq)]
+    struct Content(u64);
+
+    /// Three levels deep: 0 over (1 | 2) on the left, 3 over 4 on the right
+    const DEEP: &str = "h(v(0, h(1, 2)), v(3, 4))";
+
+    fn labeled(spec: &str) -> LayoutNode<Content> {
+        fn build(spec: &LayoutSpec) -> LayoutNode<Content> {
+            match spec {
+                LayoutSpec::Pane(index) => LayoutNode::Leaf { id: PaneId(*index as u64), content: Content(*index as u64) },
+                LayoutSpec::Split { direction, size, first, second } => LayoutNode::Split {
+                    direction: *direction,
+                    size: *size,
+                    first: Box::new(build(first)),
+                    second: Box::new(build(second)),
+                },
+            }
+        }
+        build(&LayoutSpec::parse(spec).unwrap())
+    }
+
+    fn ids(root: &LayoutNode<Content>) -> Vec<PaneId> {
+        let mut ids = Vec::new();
+        root.collect_pane_ids(&mut ids);
+        ids
+    }
+
+    /// Ids are unique and every pane still holds its own content
+    fn assert_consistent(root: &LayoutNode<Content>) {
+        let ids = ids(root);
+        let unique: std::collections::HashSet<_> = ids.iter().collect();
+        assert_eq!(unique.len(), ids.len(), "duplicate ids in {}", describe(root));
+        assert_eq!(root.pane_count(), ids.len());
+        for id in ids {
+            assert_eq!(root.get_content(id), Some(&Content(id.0)), "{}", describe(root));
+        }
+    }
+
+    fn all_zones(target: PaneId) -> [DropZone; 4] {
+        [DropZone::Top(target), DropZone::Bottom(target), DropZone::Left(target), DropZone::Right(target)]
+    }
+
+    #[test]
+    fn test_split_every_leaf_in_both_directions() {
+        for id in ids(&labeled(DEEP)) {
+            for (direction, tag) in [(SplitDirection::Horizontal, "h"), (SplitDirection::Vertical, "v")] {
+                let (root, leftover) = split_node(labeled(DEEP), id, direction, PaneId(9), Some(Content(9)));
+                assert!(leftover.is_none());
+                let expected = DEEP.replace(&id.0.to_string(), &format!("{}({}, 9)", tag, id.0));
+                assert_eq!(describe(&root), expected);
+                assert_eq!(root.pane_count(), 6);
+                assert_consistent(&root);
+            }
+        }
+
+        // Splitting a missing pane hands the content back
+        let (root, leftover) = split_node(labeled(DEEP), PaneId(7), SplitDirection::Vertical, PaneId(9), Some(Content(9)));
+        assert_eq!(leftover, Some(Content(9)));
+        assert_eq!(describe(&root), DEEP);
+    }
+
+    #[test]
+    fn test_close_every_leaf_promotes_sibling_and_moves_focus() {
+        let cases = [
+            (0, "h(h(1, 2), v(3, 4))", 1),
+            (1, "h(v(0, 2), v(3, 4))", 0),
+            (2, "h(v(0, 1), v(3, 4))", 1),
+            (3, "h(v(0, h(1, 2)), 4)", 2),
+            (4, "h(v(0, h(1, 2)), 3)", 3),
+        ];
+        for (closed, expected, focus) in cases {
+            let (root, new_focus) = close_pane(labeled(DEEP), PaneId(closed)).ok().unwrap();
+            assert_eq!(describe(&root), expected);
+            assert_eq!(new_focus, PaneId(focus));
+            assert_consistent(&root);
+        }
+
+        // A promoted subtree keeps its own ratio
+        let (root, _) = close_pane(labeled("h(0, v(1, 2, 0.3))"), PaneId(0)).ok().unwrap();
+        assert_eq!(root.size_at_path(&[]), Some(SplitSize::Ratio(0.3)));
+
+        let lone = close_pane(labeled("0"), PaneId(0)).err().unwrap();
+        assert_eq!(describe(&lone), "0");
+        let unknown = close_pane(labeled(DEEP), PaneId(8)).err().unwrap();
+        assert_eq!(describe(&unknown), DEEP);
+    }
+
+    #[test]
+    fn test_detach_pane_keeps_content_and_promotes_sibling() {
+        let (rest, content, focus) = detach_pane(labeled(DEEP), PaneId(2)).ok().unwrap();
+        assert_eq!(content, Content(2));
+        assert_eq!(describe(&rest), "h(v(0, 1), v(3, 4))");
+        assert_eq!(focus, PaneId(1));
+        assert_consistent(&rest);
+
+        // The detached content becomes the root of a new tree
+        let tab = LayoutNode::Leaf { id: PaneId(0), content };
+        assert_eq!(tab.find_pane(&|c: &Content| c.0 == 2), Some(PaneId(0)));
+        assert_eq!(rest.find_pane(&|c: &Content| c.0 == 2), None);
+        assert_eq!(rest.find_pane(&|c: &Content| c.0 == 4), Some(PaneId(4)));
+
+        let (rest, content, focus) = detach_pane(labeled("h(0, 1)"), PaneId(0)).ok().unwrap();
+        assert_eq!((describe(&rest).as_str(), content, focus), ("1", Content(0), PaneId(1)));
+
+        let lone = detach_pane(labeled("0"), PaneId(0)).err().unwrap();
+        assert_eq!(lone.get_content(PaneId(0)), Some(&Content(0)));
+        let unknown = detach_pane(labeled(DEEP), PaneId(8)).err().unwrap();
+        assert_eq!(describe(&unknown), DEEP);
+    }
+
+    #[test]
+    fn test_cycle_focus_wraps() {
+        let order = ids(&labeled(DEEP));
+        assert_eq!(cycle_focus(&order, PaneId(4), true), Some(PaneId(0)));
+        assert_eq!(cycle_focus(&order, PaneId(0), false), Some(PaneId(4)));
+        assert_eq!(cycle_focus(&order, PaneId(2), true), Some(PaneId(3)));
+        assert_eq!(cycle_focus(&[PaneId(0)], PaneId(0), true), Some(PaneId(0)));
+        assert_eq!(cycle_focus(&order, PaneId(8), true), None);
+    }
+
+    #[test]
+    fn test_extract_and_reinsert_restores_tree() {
+        // Each leaf whose sibling is a leaf goes back where it came from
+        let cases = [
+            (1, 2, SplitDirection::Horizontal, true),
+            (2, 1, SplitDirection::Horizontal, false),
+            (3, 4, SplitDirection::Vertical, true),
+            (4, 3, SplitDirection::Vertical, false),
+        ];
+        for (pane, sibling, direction, before) in cases {
+            let (without, content) = extract_pane(labeled(DEEP), PaneId(pane)).unwrap();
+            assert_eq!(without.pane_count(), 4);
+            assert!(without.get_content(PaneId(pane)).is_none());
+            assert_eq!(content, Content(pane));
+            assert_consistent(&without);
+
+            let restored = insert_adjacent(without, PaneId(sibling), PaneId(pane), content, direction, before);
+            assert_eq!(describe(&restored), DEEP);
+            assert_consistent(&restored);
+        }
+
+        assert!(extract_pane(labeled("0"), PaneId(0)).is_none());
+    }
+
+    /// Tint per original pane, so moved metadata can be told apart
+    fn tints(root: &LayoutNode<Content>) -> HashMap<PaneId, PaneMeta> {
+        ids(root)
+            .into_iter()
+            .map(|id| (id, PaneMeta { tint: Some(egui::Color32::from_gray(id.0 as u8)) }))
+            .collect()
+    }
+
+    /// Every pane's metadata is still the one made for its content
+    fn assert_meta_follows(root: &LayoutNode<Content>, meta: &HashMap<PaneId, PaneMeta>) {
+        for id in ids(root) {
+            let content = root.get_content(id).unwrap();
+            assert_eq!(meta[&id].tint, Some(egui::Color32::from_gray(content.0 as u8)), "{}", describe(root));
+        }
+    }
+
+    #[test]
+    fn test_pane_meta_follows_extract_and_insert() {
+        let meta = tints(&labeled(DEEP));
+        for &(pane, sibling) in &[(1, 0), (2, 4), (4, 3)] {
+            let (without, content) = extract_pane(labeled(DEEP), PaneId(pane)).unwrap();
+            let mut pruned = meta.clone();
+            prune_pane_meta(&mut pruned, &without);
+            assert!(!pruned.contains_key(&PaneId(pane)));
+            assert_meta_follows(&without, &pruned);
+
+            let moved = insert_adjacent(without, PaneId(sibling), PaneId(pane), content, SplitDirection::Vertical, false);
+            assert_meta_follows(&moved, &meta);
+        }
+
+        for zone in all_zones(PaneId(0)) {
+            let root = drop_pane(labeled(DEEP), PaneId(3), zone).ok().unwrap();
+            assert_meta_follows(&root, &meta);
+        }
+    }
+
+    #[test]
+    fn test_prune_pane_meta() {
+        let root = labeled("h(0, 1)");
+        let mut meta = tints(&labeled(DEEP));
+        meta.insert(PaneId(1), PaneMeta::default());
+        prune_pane_meta(&mut meta, &root);
+        assert_eq!(meta.keys().collect::<Vec<_>>(), [&PaneId(0)]);
+    }
+
+    #[test]
+    fn test_drop_every_pane_on_every_zone() {
+        let order = ids(&labeled(DEEP));
+        for &source in &order {
+            for &target in order.iter().filter(|&&id| id != source) {
+                for zone in all_zones(target) {
+                    let root = drop_pane(labeled(DEEP), source, zone).ok().unwrap();
+                    assert_eq!(root.pane_count(), 5);
+                    assert_consistent(&root);
+
+                    // The source ends up directly beside the target
+                    let (_, direction, before) = zone.placement();
+                    let tag = if direction == SplitDirection::Horizontal { "h" } else { "v" };
+                    let pair = if before {
+                        format!("{}({}, {})", tag, source.0, target.0)
+                    } else {
+                        format!("{}({}, {})", tag, target.0, source.0)
+                    };
+                    assert!(describe(&root).contains(&pair), "{:?} -> {}", zone, describe(&root));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_refused_drops_return_tree_unchanged() {
+        let onto_itself = drop_pane(labeled(DEEP), PaneId(1), DropZone::Left(PaneId(1))).err().unwrap();
+        assert_eq!(describe(&onto_itself), DEEP);
+        let missing_source = drop_pane(labeled(DEEP), PaneId(8), DropZone::Top(PaneId(1))).err().unwrap();
+        assert_eq!(describe(&missing_source), DEEP);
+        let missing_target = drop_pane(labeled(DEEP), PaneId(1), DropZone::Top(PaneId(8))).err().unwrap();
+        assert_eq!(describe(&missing_target), DEEP);
+        let lone = drop_pane(labeled("0"), PaneId(0), DropZone::Right(PaneId(0))).err().unwrap();
+        assert_eq!(describe(&lone), "0");
+    }
+
+    #[test]
+    fn test_drop_zone_geometry() {
+        let mut layout = ComputedLayout::new();
+        layout.pane_rects.insert(PaneId(0), Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(400.0, 200.0)));
+        layout.pane_rects.insert(PaneId(1), Rect::from_min_max(egui::pos2(400.0, 0.0), egui::pos2(800.0, 200.0)));
+
+        let zones = compute_drop_zones(&layout, PaneId(0));
+        assert_eq!(zones.len(), 4, "the dragged pane has no zones");
+        let zone = |zone: DropZone| zones.iter().find(|info| info.zone == zone).unwrap();
+        let rect = |x0: f32, y0: f32, x1: f32, y1: f32| Rect::from_min_max(egui::pos2(x0, y0), egui::pos2(x1, y1));
+
+        let top = zone(DropZone::Top(PaneId(1)));
+        assert_eq!((top.rect, top.highlight_rect), (rect(400.0, 0.0, 800.0, 50.0), rect(400.0, 0.0, 800.0, 100.0)));
+        let bottom = zone(DropZone::Bottom(PaneId(1)));
+        assert_eq!((bottom.rect, bottom.highlight_rect), (rect(400.0, 150.0, 800.0, 200.0), rect(400.0, 100.0, 800.0, 200.0)));
+        let left = zone(DropZone::Left(PaneId(1)));
+        assert_eq!((left.rect, left.highlight_rect), (rect(400.0, 0.0, 500.0, 200.0), rect(400.0, 0.0, 600.0, 200.0)));
+        let right = zone(DropZone::Right(PaneId(1)));
+        assert_eq!((right.rect, right.highlight_rect), (rect(700.0, 0.0, 800.0, 200.0), rect(600.0, 0.0, 800.0, 200.0)));
+
+        // The pane's center is left for dropping nowhere
+        let center = egui::pos2(600.0, 100.0);
+        assert!(zones.iter().all(|info| !info.rect.contains(center)));
+    }
+
+    /// Small deterministic generator for the randomized test below
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    #[test]
+    fn test_random_operations_keep_invariants() {
+        for seed in 1..=20u64 {
+            let mut rng = XorShift(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let mut root = labeled("0");
+            let mut next_id = 1;
+
+            for _ in 0..200 {
+                let order = ids(&root);
+                let pick = order[rng.below(order.len())];
+                let count = root.pane_count();
+                match rng.below(4) {
+                    0 => {
+                        let direction = if rng.below(2) == 0 { SplitDirection::Horizontal } else { SplitDirection::Vertical };
+                        let (new_root, leftover) = split_node(root, pick, direction, PaneId(next_id), Some(Content(next_id)));
+                        assert!(leftover.is_none());
+                        root = new_root;
+                        next_id += 1;
+                        assert_eq!(root.pane_count(), count + 1);
+                    }
+                    1 => {
+                        root = match close_pane(root, pick) {
+                            Ok((new_root, focus)) => {
+                                assert_eq!(new_root.pane_count(), count - 1);
+                                assert!(new_root.get_content(focus).is_some());
+                                new_root
+                            }
+                            Err(unchanged) => {
+                                assert_eq!(count, 1);
+                                unchanged
+                            }
+                        };
+                    }
+                    2 => {
+                        let target = order[rng.below(order.len())];
+                        let zone = all_zones(target)[rng.below(4)];
+                        root = drop_pane(root, pick, zone).unwrap_or_else(|unchanged| {
+                            assert!(target == pick || count == 1);
+                            unchanged
+                        });
+                        assert_eq!(root.pane_count(), count, "a move keeps the pane count");
+                    }
+                    _ => {
+                        let direction = [PaneDirection::Left, PaneDirection::Right, PaneDirection::Up, PaneDirection::Down][rng.below(4)];
+                        root = move_pane(root, pick, direction);
+                        assert_eq!(root.pane_count(), count);
+                    }
+                }
+                assert_consistent(&root);
+            }
+        }
+    }
+}
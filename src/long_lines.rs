@@ -0,0 +1,146 @@
+//! Long Lines
+//!
+//! A line longer than the terminal is wide wraps onto following rows, and
+//! a minified JSON dump can fill dozens of them. The grid has no logical
+//! line metadata, only a wrap flag on the last cell of each row that
+//! continues below, so logical lines are put back together from those.
+
+use alacritty_terminal::grid::{Dimensions, Grid};
+use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::term::cell::{Cell, Flags};
+
+/// Does `line` continue on the row below?
+fn wraps(grid: &Grid<Cell>, line: Line) -> bool {
+    grid[line][grid.last_column()].flags.contains(Flags::WRAPLINE)
+}
+
+/// First and last row of the logical line `line` is part of
+pub fn logical_bounds(grid: &Grid<Cell>, line: Line) -> (Line, Line) {
+    bounds_within(grid, line, grid.total_lines())
+}
+
+/// `logical_bounds`, looking at most `above` rows up from `line`
+fn bounds_within(grid: &Grid<Cell>, line: Line, above: usize) -> (Line, Line) {
+    let above = i32::try_from(above).unwrap_or(i32::MAX);
+    let top = Line((-(grid.history_size() as i32)).max(line.0.saturating_sub(above)));
+    let bottom = Line(grid.screen_lines() as i32 - 1);
+    let mut start = line;
+    while start > top && wraps(grid, Line(start.0 - 1)) {
+        start.0 -= 1;
+    }
+    let mut end = line;
+    while end < bottom && wraps(grid, end) {
+        end.0 += 1;
+    }
+    (start, end)
+}
+
+/// Text of the logical line `line` is part of, without the wraps
+pub fn logical_line(grid: &Grid<Cell>, line: Line) -> String {
+    let (start, end) = logical_bounds(grid, line);
+    let mut text = String::new();
+    for line in (start.0..=end.0).map(Line) {
+        text.push_str(&crate::session_log::row_text(grid, line, true));
+        // Blanks before a wrap are part of the line (row_text drops them),
+        // unless they only pad a wide character over to the next row
+        let padded = grid[line][grid.last_column()].flags.contains(Flags::LEADING_WIDE_CHAR_SPACER);
+        if line < end && !padded {
+            text.extend(std::iter::repeat_n(' ', grid.columns() - used_columns(grid, line)));
+        }
+    }
+    text
+}
+
+/// Viewport rows continuing a logical line longer than `min_columns`
+///
+/// Runs every frame, so a line reaching far up into the history is only
+/// followed as far as it takes to tell it's long.
+pub fn wrap_marker_rows(grid: &Grid<Cell>, min_columns: usize) -> Vec<usize> {
+    let offset = grid.display_offset() as i32;
+    let rows = grid.screen_lines() as i32;
+    let long_rows = min_columns / grid.columns().max(1) + 1;
+    let mut markers = Vec::new();
+    let mut row = 0;
+    while row < rows {
+        let (start, end) = bounds_within(grid, Line(row - offset), long_rows);
+        let last_row = (end.0 + offset).min(rows - 1);
+        if end > start && logical_columns(grid, start, end) > min_columns {
+            let first_continuation = (start.0 + 1 + offset).max(row);
+            markers.extend((first_continuation..=last_row).map(|row| row as usize));
+        }
+        row = last_row + 1;
+    }
+    markers
+}
+
+/// Columns used by the rows `start..=end`: all of them but the last, which
+/// is counted up to its last non-blank cell
+fn logical_columns(grid: &Grid<Cell>, start: Line, end: Line) -> usize {
+    (end.0 - start.0) as usize * grid.columns() + used_columns(grid, end)
+}
+
+/// Columns of `line` up to its last non-blank cell
+fn used_columns(grid: &Grid<Cell>, line: Line) -> usize {
+    let row = &grid[line];
+    (0..grid.columns())
+        .rev()
+        .find(|&col| row[Column(col)].c != ' ')
+        .map_or(0, |col| col + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLUMNS: usize = 4;
+
+    /// Print `lines` into a screen of `rows`, wrapping them like a terminal
+    fn grid(rows: usize, lines: &[&str]) -> Grid<Cell> {
+        let mut grid: Grid<Cell> = Grid::new(rows, COLUMNS, 0);
+        let mut row = 0;
+        for text in lines {
+            let chars: Vec<char> = text.chars().collect();
+            for (n, chunk) in chars.chunks(COLUMNS).enumerate() {
+                if n > 0 {
+                    grid[Line(row - 1)][Column(COLUMNS - 1)].flags.insert(Flags::WRAPLINE);
+                }
+                for (col, c) in chunk.iter().enumerate() {
+                    grid[Line(row)][Column(col)].c = *c;
+                }
+                row += 1;
+            }
+            if chars.is_empty() {
+                row += 1;
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_logical_lines_are_joined_from_any_row() {
+        let grid = grid(6, &["$ ls", "abc efghij", "ok"]);
+        assert_eq!(logical_bounds(&grid, Line(2)), (Line(1), Line(3)));
+        assert_eq!(logical_line(&grid, Line(1)), "abc efghij");
+        // Including the blank the first row ends with
+        assert_eq!(logical_line(&grid, Line(3)), "abc efghij");
+        assert_eq!(logical_line(&grid, Line(0)), "$ ls");
+        assert_eq!(logical_line(&grid, Line(4)), "ok");
+    }
+
+    #[test]
+    fn test_only_long_lines_get_markers() {
+        let grid = grid(8, &["abcdef", "x", "abcdefghijklm"]);
+        // The 6-column line is under the threshold; the 13-column one isn't
+        assert_eq!(wrap_marker_rows(&grid, 8), [4, 5, 6]);
+        assert_eq!(wrap_marker_rows(&grid, 4), [1, 4, 5, 6]);
+        assert_eq!(wrap_marker_rows(&grid, 20), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_marker_walk_stops_once_a_line_is_long() {
+        let grid = grid(8, &["abcdefghijklmnopqrstuvwxyz"]);
+        assert_eq!(logical_bounds(&grid, Line(6)), (Line(0), Line(6)));
+        assert_eq!(bounds_within(&grid, Line(6), 2), (Line(4), Line(6)));
+        assert_eq!(wrap_marker_rows(&grid, 8), [1, 2, 3, 4, 5, 6]);
+    }
+}
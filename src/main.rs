@@ -3,26 +3,131 @@
 //! Built with egui + egui_term (Alacritty backend)
 
 mod app;
+mod blame;
+mod capabilities;
+mod cell_width;
+mod clipboard_history;
+mod clipboard_paste;
+mod color_test;
+mod command_history;
 mod config;
+mod config_store;
 mod context;
+mod device_attributes;
+mod diagnostics;
+mod dir_hooks;
 mod directory_scanner;
+mod dormant;
+mod file_loader;
+mod font_diagnostics;
+mod input;
+mod ipc;
+mod last_output;
+mod latency;
 mod layout;
+mod long_lines;
 mod menu;
+mod nav_history;
+mod notifier;
+mod open_events;
+mod pane_diff;
+mod pane_view;
+mod paste_image;
+mod presentation;
+mod progress;
 mod project;
+mod project_search;
 mod pty_tracker;
+mod quit;
+mod responsive;
+mod root_switch;
+mod run_all;
+mod scrollback_marks;
+mod session;
+mod session_log;
+mod settings_bundle;
+mod shell_env;
+mod shell_path;
+mod shortcuts;
+mod sidebar_reveal;
+mod snippets;
+mod ssh_config;
+mod startup;
+mod stats;
+mod terminal_backend;
 mod theme;
+mod throughput;
+mod thumbnails;
+mod trash;
+mod tree_state;
 mod ui;
+mod update_check;
 mod watcher;
 
 use app::VibeTermApp;
 
 fn main() -> eframe::Result<()> {
+    let mut args = ipc::LaunchArgs::parse(std::env::args().skip(1));
+
+    // `vibeterm-context` (a symlink to this binary) is `vibeterm --context`
+    let program = std::env::args().next().map(std::path::PathBuf::from);
+    if program.is_some_and(|program| program.file_stem().is_some_and(|stem| stem == "vibeterm-context")) {
+        args.context = true;
+    }
+    if args.context {
+        match ipc::query(&ipc::socket_path(), &ipc::IpcCommand::Context) {
+            Ok(json) => {
+                println!("{}", json);
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut startup = startup::StartupReport::start();
+    // Hand off to a running instance before anything is printed
+    let mut ipc_listener = None;
+    let mut ipc_error = None;
+    if !args.new_instance && config::Config::load().ui.single_instance {
+        match ipc::connect_or_bind(&ipc::socket_path(), &args.forward_commands()) {
+            Ok(ipc::Startup::Forwarded) => std::process::exit(0),
+            Ok(ipc::Startup::Primary(listener)) => ipc_listener = Some(listener),
+            Err(e) => ipc_error = Some(e),
+        }
+    }
+    startup.lap("single instance");
+
     // Initialize logging
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
         .format_timestamp_millis()
         .init();
 
     log::info!("VibeTerm v{} starting...", env!("CARGO_PKG_VERSION"));
+    if let Some(e) = ipc_error {
+        log::warn!("Single-instance mode unavailable: {}", e);
+    }
+    // Before any thread starts, since it sets variables
+    shell_env::import(&config::Config::load().advanced);
+    if let Some(path) = &args.path {
+        // A file opens next to the first tab, which starts in its directory
+        let dir = if path.is_file() {
+            open_events::push(std::fs::canonicalize(path).ok());
+            path.parent().filter(|dir| !dir.as_os_str().is_empty())
+        } else {
+            Some(path.as_path())
+        };
+        if let Some(dir) = dir {
+            if let Err(e) = std::env::set_current_dir(dir) {
+                log::warn!("Cannot open {}: {}", dir.display(), e);
+            }
+        }
+    }
+    open_events::install();
+    let ipc_server = ipc_listener.map(ipc::IpcListener::serve);
+    startup.lap("shell environment");
 
     // eframe native options
     let native_options = eframe::NativeOptions {
@@ -45,10 +150,15 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "VibeTerm",
         native_options,
-        Box::new(|cc| {
-            // Set up native menu bar
-            menu::setup_menu_bar();
-            Ok(Box::new(VibeTermApp::new(cc)))
+        Box::new(move |cc| {
+            startup.lap("window");
+            // Set up native menu bar (other platforms draw one in the window)
+            #[cfg(target_os = "macos")]
+            {
+                menu::setup_menu_bar();
+                startup.lap("menu");
+            }
+            Ok(Box::new(VibeTermApp::new(cc, ipc_server, startup)))
         }),
     )
 }
@@ -1,7 +1,9 @@
 //! Native macOS Menu Bar
 //!
-//! Uses muda crate for cross-platform native menu support
+//! Uses muda crate for the native menu on macOS. Other platforms get an
+//! in-window menu bar with the same actions.
 
+#[cfg(target_os = "macos")]
 use muda::{
     accelerator::Accelerator,
     Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu,
@@ -9,6 +11,8 @@ use muda::{
 use std::sync::mpsc;
 use std::sync::{Mutex, OnceLock};
 
+use crate::shortcuts::shortcut_label;
+
 /// Menu action events
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MenuAction {
@@ -20,15 +24,20 @@ pub enum MenuAction {
     SplitVertical,
     ToggleSidebar,
     Preferences,
+    OpenConfigFile,
+    RevealConfigFolder,
+    EditConfig,
     About,
     Quit,
 }
 
 /// Global menu event receiver (wrapped in Mutex for Sync)
 static MENU_RECEIVER: OnceLock<Mutex<mpsc::Receiver<MenuAction>>> = OnceLock::new();
+#[cfg(target_os = "macos")]
 static MENU_SENDER: OnceLock<mpsc::Sender<MenuAction>> = OnceLock::new();
 
 /// Set up the native menu bar
+#[cfg(target_os = "macos")]
 pub fn setup_menu_bar() {
     // Create channel for menu events
     let (sender, receiver) = mpsc::channel();
@@ -55,9 +64,33 @@ pub fn setup_menu_bar() {
         None::<Accelerator>,
     );
 
+    let open_config_item = MenuItem::with_id(
+        "open_config_file",
+        "Open Config File",
+        true,
+        None::<Accelerator>,
+    );
+
+    let reveal_config_item = MenuItem::with_id(
+        "reveal_config_folder",
+        "Reveal Config Folder",
+        true,
+        None::<Accelerator>,
+    );
+
+    let edit_config_item = MenuItem::with_id(
+        "edit_config",
+        "Edit Config in $EDITOR",
+        true,
+        None::<Accelerator>,
+    );
+
     let _ = app_menu.append(&about_item);
     let _ = app_menu.append(&PredefinedMenuItem::separator());
     let _ = app_menu.append(&preferences_item);
+    let _ = app_menu.append(&open_config_item);
+    let _ = app_menu.append(&reveal_config_item);
+    let _ = app_menu.append(&edit_config_item);
     let _ = app_menu.append(&PredefinedMenuItem::separator());
     let _ = app_menu.append(&PredefinedMenuItem::services(None));
     let _ = app_menu.append(&PredefinedMenuItem::separator());
@@ -179,11 +212,7 @@ pub fn setup_menu_bar() {
     let _ = menu_bar.append(&window_menu);
     let _ = menu_bar.append(&help_menu);
 
-    // Initialize menu bar on macOS
-    #[cfg(target_os = "macos")]
-    {
-        let _ = menu_bar.init_for_nsapp();
-    }
+    let _ = menu_bar.init_for_nsapp();
 
     // Set up menu event handler
     std::thread::spawn(move || {
@@ -198,6 +227,9 @@ pub fn setup_menu_bar() {
                         "split_horizontal" => Some(MenuAction::SplitHorizontal),
                         "split_vertical" => Some(MenuAction::SplitVertical),
                         "preferences" => Some(MenuAction::Preferences),
+                        "open_config_file" => Some(MenuAction::OpenConfigFile),
+                        "reveal_config_folder" => Some(MenuAction::RevealConfigFolder),
+                        "edit_config" => Some(MenuAction::EditConfig),
                         "about" => Some(MenuAction::About),
                         _ => None,
                     };
@@ -216,3 +248,57 @@ pub fn setup_menu_bar() {
 pub fn poll_menu_event() -> Option<MenuAction> {
     MENU_RECEIVER.get()?.lock().ok()?.try_recv().ok()
 }
+
+/// Item of the in-window menu bar; `None` is a separator
+type StripItem = Option<(&'static str, Option<&'static str>, MenuAction)>;
+
+/// Menus of the in-window menu bar
+const STRIP_MENUS: &[(&str, &[StripItem])] = &[
+    ("File", &[
+        Some(("New Tab", Some("Cmd+T"), MenuAction::NewTab)),
+        Some(("Close Tab", Some("Cmd+W"), MenuAction::CloseTab)),
+        None,
+        Some(("Preferences...", Some("Cmd+,"), MenuAction::Preferences)),
+        Some(("Open Config File", None, MenuAction::OpenConfigFile)),
+        Some(("Reveal Config Folder", None, MenuAction::RevealConfigFolder)),
+        Some(("Edit Config in $EDITOR", None, MenuAction::EditConfig)),
+        None,
+        Some(("Quit", None, MenuAction::Quit)),
+    ]),
+    ("View", &[
+        Some(("Toggle Sidebar", Some("Cmd+B"), MenuAction::ToggleSidebar)),
+        None,
+        Some(("Split Pane Horizontally", Some("Cmd+D"), MenuAction::SplitHorizontal)),
+        Some(("Split Pane Vertically", Some("Cmd+Shift+D"), MenuAction::SplitVertical)),
+    ]),
+    ("Help", &[
+        Some(("About VibeTerm", None, MenuAction::About)),
+    ]),
+];
+
+/// Draw the in-window menu bar (platforms without the native one) and
+/// return the chosen action
+pub fn show_menu_strip(ui: &mut egui::Ui) -> Option<MenuAction> {
+    let mut chosen = None;
+    egui::menu::bar(ui, |ui| {
+        for (title, items) in STRIP_MENUS {
+            ui.menu_button(*title, |ui| {
+                for item in *items {
+                    let Some((label, shortcut, action)) = item else {
+                        ui.separator();
+                        continue;
+                    };
+                    let mut button = egui::Button::new(*label);
+                    if let Some(shortcut) = shortcut {
+                        button = button.shortcut_text(shortcut_label(shortcut));
+                    }
+                    if ui.add(button).clicked() {
+                        chosen = Some(*action);
+                        ui.close_menu();
+                    }
+                }
+            });
+        }
+    });
+    chosen
+}
@@ -0,0 +1,133 @@
+//! Navigation History
+//!
+//! Back/forward stacks, as in a browser: visiting somewhere new pushes the
+//! place being left onto the back stack and forgets the forward one. Used
+//! for the sidebar root of each workspace.
+
+use std::collections::VecDeque;
+
+/// Result of going back or forward
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step<T> {
+    /// Where to go; `None` if nothing usable was left
+    pub target: Option<T>,
+    /// Entries passed over because they were no longer usable
+    pub skipped: Vec<T>,
+}
+
+/// Back and forward stacks, each holding at most `cap` entries
+#[derive(Debug, Clone)]
+pub struct NavHistory<T> {
+    /// Most recent last
+    back: VecDeque<T>,
+    /// Next forward last
+    forward: Vec<T>,
+    cap: usize,
+}
+
+impl<T: PartialEq> NavHistory<T> {
+    pub fn new(cap: usize) -> Self {
+        Self { back: VecDeque::new(), forward: Vec::new(), cap }
+    }
+
+    /// Leave `previous` for somewhere new
+    pub fn visit(&mut self, previous: T) {
+        self.forward.clear();
+        if self.back.back() != Some(&previous) {
+            self.back.push_back(previous);
+        }
+        while self.back.len() > self.cap {
+            self.back.pop_front();
+        }
+    }
+
+    /// Go back from `current`, passing over entries `usable` rejects
+    pub fn back(&mut self, current: T, usable: impl Fn(&T) -> bool) -> Step<T> {
+        let mut skipped = Vec::new();
+        while let Some(entry) = self.back.pop_back() {
+            if usable(&entry) {
+                self.forward.push(current);
+                return Step { target: Some(entry), skipped };
+            }
+            skipped.push(entry);
+        }
+        Step { target: None, skipped }
+    }
+
+    /// Go forward from `current`, passing over entries `usable` rejects
+    pub fn forward(&mut self, current: T, usable: impl Fn(&T) -> bool) -> Step<T> {
+        let mut skipped = Vec::new();
+        while let Some(entry) = self.forward.pop() {
+            if usable(&entry) {
+                self.back.push_back(current);
+                return Step { target: Some(entry), skipped };
+            }
+            skipped.push(entry);
+        }
+        Step { target: None, skipped }
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_back_and_forward_retrace_visits() {
+        let mut history = NavHistory::new(20);
+        assert!(!history.can_go_back());
+        history.visit("a");
+        history.visit("b");
+
+        // At "c"
+        let step = history.back("c", |_| true);
+        assert_eq!(step, Step { target: Some("b"), skipped: Vec::new() });
+        assert_eq!(history.back("b", |_| true).target, Some("a"));
+        assert_eq!(history.back("a", |_| true).target, None);
+        assert!(history.can_go_forward());
+        assert_eq!(history.forward("a", |_| true).target, Some("b"));
+        assert_eq!(history.forward("b", |_| true).target, Some("c"));
+        assert!(!history.can_go_forward());
+
+        // Visiting from the middle drops what was ahead
+        history.back("c", |_| true);
+        history.visit("b");
+        assert!(!history.can_go_forward());
+        assert_eq!(history.back("d", |_| true).target, Some("b"));
+    }
+
+    #[test]
+    fn test_unusable_entries_are_skipped() {
+        let mut history = NavHistory::new(20);
+        history.visit("a");
+        history.visit("gone");
+        let step = history.back("c", |entry| *entry != "gone");
+        assert_eq!(step, Step { target: Some("a"), skipped: vec!["gone"] });
+        // Skipped entries are forgotten; only "c" is ahead
+        assert_eq!(history.forward("a", |_| true).target, Some("c"));
+        assert!(!history.can_go_forward());
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut history = NavHistory::new(3);
+        for n in 0..10 {
+            history.visit(n);
+        }
+        let mut visited = Vec::new();
+        let mut at = 10;
+        while let Some(previous) = history.back(at, |_| true).target {
+            visited.push(previous);
+            at = previous;
+        }
+        assert_eq!(visited, [9, 8, 7]);
+    }
+}
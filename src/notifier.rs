@@ -0,0 +1,233 @@
+//! Notifier
+//!
+//! Bells and notices go through here, so Do Not Disturb silences all of
+//! them. DND is on while toggled by hand or during the quiet hours of
+//! `ui.dnd_schedule`; notices it held back are counted and summarized when
+//! it ends. Toggling by hand overrides the schedule until its next start
+//! or end.
+
+use std::collections::BTreeMap;
+
+/// Something that would get the user's attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Notice {
+    /// A terminal rang its bell
+    Bell,
+    /// A newer VibeTerm release was found
+    Update,
+}
+
+impl Notice {
+    /// "3 bells", "1 update notice"
+    fn count_label(self, count: usize) -> String {
+        let (one, many) = match self {
+            Notice::Bell => ("bell", "bells"),
+            Notice::Update => ("update notice", "update notices"),
+        };
+        format!("{} {}", count, if count == 1 { one } else { many })
+    }
+}
+
+/// Daily quiet hours in minutes after local midnight; may wrap past midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DndSchedule {
+    start: u32,
+    end: u32,
+}
+
+impl DndSchedule {
+    /// Parse quiet hours written like `22:00-08:00`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (start, end) = text
+            .split_once('-')
+            .ok_or_else(|| format!("Expected HH:MM-HH:MM, got {:?}", text))?;
+        let schedule = Self {
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        };
+        if schedule.start == schedule.end {
+            return Err(format!("Quiet hours {:?} start and end at the same time", text));
+        }
+        Ok(schedule)
+    }
+
+    /// Are these quiet hours at `minute` after local midnight?
+    pub fn contains(&self, minute: u32) -> bool {
+        if self.start < self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+/// Minutes after midnight of a `HH:MM` time
+fn parse_time(text: &str) -> Result<u32, String> {
+    let text = text.trim();
+    let invalid = || format!("Invalid time {:?}, expected HH:MM", text);
+    let (hours, minutes) = text.split_once(':').ok_or_else(invalid)?;
+    let hours: u32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 23 || minutes > 59 || text.len() != 5 {
+        return Err(invalid());
+    }
+    Ok(hours * 60 + minutes)
+}
+
+/// Routes notices and tracks Do Not Disturb
+#[derive(Debug, Default)]
+pub struct Notifier {
+    schedule: Option<DndSchedule>,
+    /// Hand toggle, overriding the schedule
+    manual: Option<bool>,
+    /// Schedule state as of the last `update`
+    scheduled: bool,
+    /// DND state as of the last `update`
+    active: bool,
+    suppressed: BTreeMap<Notice, usize>,
+}
+
+impl Notifier {
+    /// Notifier with the quiet hours of `ui.dnd_schedule` (empty for none)
+    pub fn new(schedule: &str) -> Self {
+        let mut notifier = Self::default();
+        notifier.configure(schedule);
+        notifier
+    }
+
+    /// Apply changed quiet hours
+    pub fn configure(&mut self, schedule: &str) {
+        self.schedule = match schedule.trim() {
+            "" => None,
+            schedule => DndSchedule::parse(schedule)
+                .map_err(|e| log::warn!("Ignoring ui.dnd_schedule: {}", e))
+                .ok(),
+        };
+    }
+
+    /// Are quiet hours configured?
+    pub fn has_schedule(&self) -> bool {
+        self.schedule.is_some()
+    }
+
+    /// Is Do Not Disturb on?
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Turn DND off if it's on and on if it's off, until the schedule next changes
+    pub fn toggle(&mut self) {
+        self.manual = Some(!self.active);
+    }
+
+    /// Follow the schedule at `minute` after local midnight
+    ///
+    /// Returns the summary to show when DND just ended.
+    pub fn update(&mut self, minute: u32) -> Option<String> {
+        let scheduled = self.schedule.is_some_and(|schedule| schedule.contains(minute));
+        if scheduled != self.scheduled {
+            self.scheduled = scheduled;
+            self.manual = None;
+        }
+
+        let was_active = self.active;
+        self.active = self.manual.unwrap_or(scheduled);
+        (was_active && !self.active).then(|| self.take_summary())
+    }
+
+    /// Should `notice` reach the user? Counted instead while DND is on.
+    pub fn notify(&mut self, notice: Notice) -> bool {
+        if self.active {
+            *self.suppressed.entry(notice).or_default() += 1;
+        }
+        !self.active
+    }
+
+    fn take_summary(&mut self) -> String {
+        let held: Vec<String> = std::mem::take(&mut self.suppressed)
+            .into_iter()
+            .map(|(notice, count)| notice.count_label(count))
+            .collect();
+        if held.is_empty() {
+            "Do Not Disturb off".to_string()
+        } else {
+            format!("Do Not Disturb off · held back {}", held.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hours: u32, minutes: u32) -> u32 {
+        hours * 60 + minutes
+    }
+
+    #[test]
+    fn test_schedule_parser() {
+        let night = DndSchedule::parse("22:00-08:00").unwrap();
+        assert!(night.contains(at(23, 30)));
+        assert!(night.contains(at(0, 0)));
+        assert!(night.contains(at(7, 59)));
+        assert!(!night.contains(at(8, 0)));
+        assert!(!night.contains(at(21, 59)));
+
+        let lunch = DndSchedule::parse(" 12:00 - 13:30 ").unwrap();
+        assert!(lunch.contains(at(12, 0)));
+        assert!(!lunch.contains(at(13, 30)));
+        assert!(!lunch.contains(at(11, 0)));
+
+        for bad in ["22:00", "24:00-08:00", "22:60-08:00", "10pm-8am", "9:00-17:00", "08:00-08:00", ""] {
+            assert!(DndSchedule::parse(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_suppressed_notices_are_counted() {
+        let mut notifier = Notifier::new("");
+        assert!(notifier.notify(Notice::Bell));
+        notifier.toggle();
+        assert_eq!(notifier.update(0), None);
+        assert!(notifier.is_active());
+        for _ in 0..3 {
+            assert!(!notifier.notify(Notice::Bell));
+        }
+        assert!(!notifier.notify(Notice::Update));
+
+        notifier.toggle();
+        assert_eq!(notifier.update(0).as_deref(), Some("Do Not Disturb off · held back 3 bells, 1 update notice"));
+        assert!(notifier.notify(Notice::Bell));
+
+        // Counts start over
+        notifier.toggle();
+        notifier.update(0);
+        notifier.toggle();
+        assert_eq!(notifier.update(0).as_deref(), Some("Do Not Disturb off"));
+    }
+
+    #[test]
+    fn test_schedule_and_hand_toggle() {
+        let mut notifier = Notifier::new("22:00-08:00");
+        notifier.update(at(21, 0));
+        assert!(!notifier.is_active());
+        notifier.update(at(22, 0));
+        assert!(notifier.is_active());
+
+        // Turned off by hand for the rest of the night
+        notifier.toggle();
+        assert!(notifier.update(at(23, 0)).is_some());
+        notifier.update(at(3, 0));
+        assert!(!notifier.is_active());
+
+        // Quiet hours end, and the next ones apply again
+        notifier.update(at(8, 0));
+        notifier.update(at(22, 30));
+        assert!(notifier.is_active());
+        assert!(notifier.update(at(8, 0)).is_some());
+
+        // A broken schedule is ignored
+        notifier.configure("late");
+        assert!(!notifier.has_schedule());
+    }
+}
@@ -0,0 +1,105 @@
+//! Files and Folders Opened by the OS
+//!
+//! Folders dropped on the Dock icon and files sent with Finder's "Open
+//! With" reach a macOS app as `application:openURLs:` calls on its
+//! application delegate. winit's delegate doesn't handle them, so the
+//! method is added to it as launching starts. The first paths arrive before
+//! the window exists, so they're queued here until the app takes them.
+//! Elsewhere paths only arrive on the command line and through IPC.
+
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+/// Paths waiting for the app
+static QUEUE: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Woken when paths arrive, once the UI exists
+static WAKER: OnceLock<egui::Context> = OnceLock::new();
+
+/// Start receiving open requests from the OS (call before the event loop runs)
+pub fn install() {
+    #[cfg(target_os = "macos")]
+    macos::install();
+}
+
+/// Queue paths to open
+pub fn push(paths: impl IntoIterator<Item = PathBuf>) {
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner()).extend(paths);
+    if let Some(ctx) = WAKER.get() {
+        ctx.request_repaint();
+    }
+}
+
+/// Repaint `ctx` when paths arrive
+pub fn attach(ctx: &egui::Context) {
+    let _ = WAKER.set(ctx.clone());
+}
+
+/// Queued paths, oldest first
+pub fn take() -> Vec<PathBuf> {
+    std::mem::take(&mut *QUEUE.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::path::PathBuf;
+    use std::ptr::NonNull;
+    use block2::RcBlock;
+    use objc2::runtime::{AnyObject, Bool, Sel};
+    use objc2::{msg_send, sel};
+    use objc2_foundation::{NSArray, NSNotification, NSNotificationCenter, NSString, NSURL};
+
+    /// Add `application:openURLs:` to the application delegate once winit
+    /// has set it, before AppKit delivers the launch's open events
+    pub fn install() {
+        let name = NSString::from_str("NSApplicationWillFinishLaunchingNotification");
+        let block = RcBlock::new(|notification: NonNull<NSNotification>| {
+            // SAFETY: AppKit posts this on the main thread with NSApp as the object
+            unsafe {
+                let Some(app) = notification.as_ref().object() else {
+                    return;
+                };
+                let delegate: *mut AnyObject = msg_send![&*app, delegate];
+                let Some(delegate) = delegate.as_ref() else {
+                    log::warn!("No application delegate; files opened from Finder are ignored");
+                    return;
+                };
+                let imp = std::mem::transmute::<
+                    extern "C" fn(*mut AnyObject, Sel, *mut AnyObject, *mut NSArray<NSURL>),
+                    unsafe extern "C" fn(),
+                >(open_urls);
+                let added = objc2::ffi::class_addMethod(
+                    delegate.class() as *const _ as *mut _,
+                    sel!(application:openURLs:).as_ptr(),
+                    Some(imp),
+                    c"v@:@@".as_ptr(),
+                );
+                if !Bool::from_raw(added).as_bool() {
+                    log::warn!("Application delegate already handles opened files");
+                }
+            }
+        });
+        // SAFETY: the block matches the observer signature; the center keeps
+        // the registration for the life of the app
+        let observer = unsafe {
+            NSNotificationCenter::defaultCenter()
+                .addObserverForName_object_queue_usingBlock(Some(&name), None, None, &block)
+        };
+        std::mem::forget(observer);
+    }
+
+    extern "C" fn open_urls(_this: *mut AnyObject, _cmd: Sel, _app: *mut AnyObject, urls: *mut NSArray<NSURL>) {
+        // SAFETY: AppKit passes an array of file URLs
+        let Some(urls) = (unsafe { urls.as_ref() }) else {
+            return;
+        };
+        let paths: Vec<PathBuf> = urls
+            .iter()
+            .filter_map(|url| unsafe { url.path() })
+            .map(|path| PathBuf::from(path.to_string()))
+            .collect();
+        log::info!("Opening {} path(s) from Finder", paths.len());
+        super::push(paths);
+    }
+}
+
@@ -0,0 +1,198 @@
+//! Pane Output Diff
+//!
+//! Text snapshots of two terminals and a line diff between them, for
+//! "Diff Panes…". Changed lines carry the parts that differ, so the view
+//! can highlight them within the line.
+
+use std::time::Duration;
+
+use alacritty_terminal::grid::{Dimensions, Grid};
+use alacritty_terminal::index::Line;
+use alacritty_terminal::term::cell::Cell;
+use similar::{ChangeTag, TextDiff};
+
+/// Time the diff may take before it settles for a coarser result
+const DIFF_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How much of each terminal is compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffScope {
+    /// The screen, down to the cursor
+    Screen,
+    /// The history and the screen
+    Scrollback,
+}
+
+impl DiffScope {
+    /// Choices offered by the palette, in order
+    pub const ALL: [DiffScope; 2] = [DiffScope::Screen, DiffScope::Scrollback];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DiffScope::Screen => "Visible screen",
+            DiffScope::Scrollback => "Whole scrollback",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scope| scope.label() == label)
+    }
+}
+
+/// Plain text of a terminal, without trailing blank lines
+pub fn snapshot(grid: &Grid<Cell>, scope: DiffScope) -> String {
+    let mut text = match scope {
+        DiffScope::Screen => crate::session_log::screen_lines(grid, true),
+        DiffScope::Scrollback => crate::session_log::text_since(grid, Line(-(grid.history_size() as i32))),
+    };
+    text.truncate(text.trim_end().len());
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text
+}
+
+/// Which side a diff line is from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// In both
+    Same,
+    /// Only on the left
+    Removed,
+    /// Only on the right
+    Added,
+}
+
+/// One line of a diff, split into parts that are (or aren't) changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    /// (changed, text) parts, without the newline
+    pub segments: Vec<(bool, String)>,
+}
+
+/// A line diff of two texts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PaneDiff {
+    pub lines: Vec<DiffLine>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Line diff from `left` to `right`
+pub fn diff(left: &str, right: &str) -> PaneDiff {
+    let text_diff = TextDiff::configure().timeout(DIFF_TIMEOUT).diff_lines(left, right);
+    let mut result = PaneDiff::default();
+    for op in text_diff.ops() {
+        for change in text_diff.iter_inline_changes(op) {
+            let kind = match change.tag() {
+                ChangeTag::Equal => LineKind::Same,
+                ChangeTag::Delete => {
+                    result.removed += 1;
+                    LineKind::Removed
+                }
+                ChangeTag::Insert => {
+                    result.added += 1;
+                    LineKind::Added
+                }
+            };
+            let segments = change
+                .iter_strings_lossy()
+                .map(|(changed, text)| (changed, text.trim_end_matches(['\n', '\r']).to_string()))
+                .filter(|(_, text)| !text.is_empty())
+                .collect();
+            result.lines.push(DiffLine { kind, segments });
+        }
+    }
+    result
+}
+
+/// Diff as text: `-`/`+`/space prefixes, changed parts in `[-…-]`/`{+…+}`
+pub fn format_diff(diff: &PaneDiff) -> String {
+    let mut out = String::new();
+    for line in &diff.lines {
+        let (prefix, open, close) = match line.kind {
+            LineKind::Same => (' ', "", ""),
+            LineKind::Removed => ('-', "[-", "-]"),
+            LineKind::Added => ('+', "{+", "+}"),
+        };
+        out.push(prefix);
+        out.push(' ');
+        // A fully changed line needs no markers
+        let whole = line.segments.iter().all(|(changed, _)| *changed);
+        for (changed, text) in &line.segments {
+            if *changed && !whole {
+                out.push_str(open);
+                out.push_str(text);
+                out.push_str(close);
+            } else {
+                out.push_str(text);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: &str = "\
+$ uname -a
+Linux web-1 6.1.0 x86_64
+$ df -h /
+/dev/sda1  40G  12G  28G  30% /
+$ uptime
+";
+
+    const RIGHT: &str = "\
+$ uname -a
+Linux web-2 6.1.0 x86_64
+$ df -h /
+/dev/sda1  40G  12G  28G  30% /
+$ uptime
+ up 3 days
+";
+
+    #[test]
+    fn test_diff_counts_and_marks_changes() {
+        let diff = diff(LEFT, RIGHT);
+        assert_eq!((diff.removed, diff.added), (1, 2));
+        let kinds: Vec<_> = diff.lines.iter().map(|line| line.kind).collect();
+        assert_eq!(kinds, [
+            LineKind::Same,
+            LineKind::Removed,
+            LineKind::Added,
+            LineKind::Same,
+            LineKind::Same,
+            LineKind::Same,
+            LineKind::Added,
+        ]);
+        assert!(diff.lines[1].segments.contains(&(true, "web-1".to_string())));
+    }
+
+    #[test]
+    fn test_format_diff_fixture() {
+        // Changes are marked by whole words
+        let expected = "  $ uname -a
+- Linux [-web-1-] 6.1.0 x86_64
++ Linux {+web-2+} 6.1.0 x86_64
+  $ df -h /
+  /dev/sda1  40G  12G  28G  30% /
+  $ uptime
++  up 3 days
+";
+        assert_eq!(format_diff(&diff(LEFT, RIGHT)), expected);
+    }
+
+    #[test]
+    fn test_identical_and_empty_sides() {
+        let same = diff(LEFT, LEFT);
+        assert_eq!((same.removed, same.added), (0, 0));
+        assert!(format_diff(&same).lines().all(|line| line.starts_with("  ")));
+
+        assert_eq!(format_diff(&diff("", "a\nb\n")), "+ a\n+ b\n");
+        assert_eq!(format_diff(&diff("a\n", "")), "- a\n");
+    }
+}
@@ -0,0 +1,136 @@
+//! Pane Views Across Tab Switches
+//!
+//! A tab in the background keeps where its panes were scrolled to, and
+//! they're put back when it's shown again. alacritty_terminal already keeps
+//! a scrolled-back view on the same lines as output arrives, by growing its
+//! display offset, so a terminal's view only needs putting back when
+//! something sent it to the bottom meanwhile (a resize, a reset).
+//!
+//! That same behavior leaves a scrolled-back view where it is while a build
+//! keeps printing; `Follow` counts the lines arriving below it and decides
+//! when the view goes back to the bottom.
+
+/// Display offset to scroll back to when a tab is shown again, given the
+/// one saved when it was hidden and the current one
+///
+/// `None` when the view is still scrolled back (alacritty kept it in place)
+/// or was at the bottom to begin with. Offset 0 is the bottom, so a history
+/// trimmed below the saved offset goes as far back as it can.
+pub fn restored_offset(saved: usize, current: usize, history_size: usize) -> Option<usize> {
+    (saved > 0 && current == 0).then_some(saved.min(history_size))
+}
+
+/// Whether a terminal's view follows new output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Follow {
+    /// At the bottom, moving with new output
+    #[default]
+    Pinned,
+    /// Scrolled back, with this many lines printed below since
+    Detached { unseen: usize },
+}
+
+/// What sends a scrolled-back view to the bottom
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FollowTriggers {
+    /// New output (`terminal.scroll_on_output`)
+    pub output: bool,
+    /// Input written to the PTY (`terminal.scroll_on_keystroke`)
+    pub keystroke: bool,
+}
+
+impl Follow {
+    /// Update after a frame, given the display offsets at the last update and
+    /// now, the lines of output since, and whether input was written
+    ///
+    /// Writing input scrolls the backend to the bottom, so without the
+    /// keystroke trigger the view goes back up. Returns the display offset
+    /// to scroll to, when the view has to move.
+    pub fn step(&mut self, before: usize, after: usize, new_lines: usize, typed: bool, triggers: FollowTriggers) -> Option<usize> {
+        let restore = (typed && !triggers.keystroke && before > 0 && after == 0).then_some(before + new_lines);
+        let offset = restore.unwrap_or(after);
+        if offset == 0 {
+            *self = Follow::Pinned;
+            return None;
+        }
+        if new_lines > 0 && triggers.output {
+            *self = Follow::Pinned;
+            return Some(0);
+        }
+        match self {
+            Follow::Pinned => *self = Follow::Detached { unseen: new_lines },
+            Follow::Detached { unseen } => *unseen += new_lines,
+        }
+        restore
+    }
+
+    /// Lines printed below a scrolled-back view, `None` at the bottom
+    pub fn unseen(&self) -> Option<usize> {
+        match self {
+            Follow::Pinned => None,
+            Follow::Detached { unseen } => Some(*unseen),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFAULT_TRIGGERS: FollowTriggers = FollowTriggers { output: false, keystroke: true };
+
+    #[test]
+    fn test_views_go_back_only_when_reset() {
+        // Still scrolled back: alacritty kept the view on its lines
+        assert_eq!(restored_offset(120, 170, 350), None);
+        // Sent to the bottom while hidden
+        assert_eq!(restored_offset(120, 0, 350), Some(120));
+        // History trimmed below the saved offset: as far back as it goes
+        assert_eq!(restored_offset(400, 0, 350), Some(350));
+        // At the bottom it stays there
+        assert_eq!(restored_offset(0, 0, 500), None);
+    }
+
+    #[test]
+    fn test_follow_detaches_and_counts_output() {
+        let mut follow = Follow::default();
+        assert_eq!(follow.step(0, 0, 5, false, DEFAULT_TRIGGERS), None);
+        assert_eq!(follow.unseen(), None);
+
+        // Scrolling up detaches; output below doesn't move the view
+        assert_eq!(follow.step(0, 10, 0, false, DEFAULT_TRIGGERS), None);
+        assert_eq!(follow.unseen(), Some(0));
+        assert_eq!(follow.step(10, 13, 3, false, DEFAULT_TRIGGERS), None);
+        assert_eq!(follow.step(13, 20, 7, false, DEFAULT_TRIGGERS), None);
+        assert_eq!(follow.unseen(), Some(10));
+
+        // Scrolling back down (End, the pill, the wheel) re-pins
+        assert_eq!(follow.step(20, 0, 0, false, DEFAULT_TRIGGERS), None);
+        assert_eq!(follow, Follow::Pinned);
+    }
+
+    #[test]
+    fn test_follow_triggers() {
+        // Typing re-pins by default; the backend already scrolled down
+        let mut follow = Follow::Detached { unseen: 4 };
+        assert_eq!(follow.step(30, 0, 0, true, DEFAULT_TRIGGERS), None);
+        assert_eq!(follow, Follow::Pinned);
+
+        // Without the keystroke trigger the view goes back, past any new lines
+        let no_keystroke = FollowTriggers { output: false, keystroke: false };
+        let mut follow = Follow::Detached { unseen: 4 };
+        assert_eq!(follow.step(30, 0, 2, true, no_keystroke), Some(32));
+        assert_eq!(follow.unseen(), Some(6));
+        // Typing at the bottom stays there
+        let mut follow = Follow::Pinned;
+        assert_eq!(follow.step(0, 0, 1, true, no_keystroke), None);
+        assert_eq!(follow, Follow::Pinned);
+
+        // With the output trigger new lines pull the view down
+        let on_output = FollowTriggers { output: true, keystroke: true };
+        let mut follow = Follow::Detached { unseen: 0 };
+        assert_eq!(follow.step(15, 15, 0, false, on_output), None);
+        assert_eq!(follow.step(15, 16, 1, false, on_output), Some(0));
+        assert_eq!(follow, Follow::Pinned);
+    }
+}
@@ -0,0 +1,211 @@
+//! Pasted Images
+//!
+//! Smart paste saves a clipboard image to a file and types a marker with
+//! its path into the terminal. Where the file goes, its encoding, its name
+//! and the marker text come from `[terminal.paste_image]`; old pastes can
+//! be deleted at startup.
+
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use image::RgbaImage;
+
+use crate::config::{PasteImageConfig, PasteImageFormat};
+
+/// Directory pasted images are saved to
+pub fn directory(config: &PasteImageConfig) -> PathBuf {
+    let directory = crate::session_log::expand_home(&config.directory);
+    if directory.as_os_str().is_empty() || directory.starts_with("~") {
+        return std::env::temp_dir().join("vibeterm-paste");
+    }
+    directory
+}
+
+/// File extension for a format
+fn extension(format: PasteImageFormat) -> &'static str {
+    match format {
+        PasteImageFormat::Png => "png",
+        PasteImageFormat::Jpeg { .. } => "jpg",
+        PasteImageFormat::Webp => "webp",
+    }
+}
+
+/// File name from the template, with `{timestamp}` (ms since the epoch)
+/// and `{date}` (`YYYY-MM-DD`) expanded
+///
+/// Path separators are replaced so the file stays in the paste directory.
+pub fn expand_filename(template: &str, now: SystemTime, date: &str, format: PasteImageFormat) -> String {
+    let millis = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis());
+    let stem = template
+        .replace("{timestamp}", &millis.to_string())
+        .replace("{date}", date)
+        .replace(['/', '\\'], "_");
+    let stem = if stem.trim().is_empty() { millis.to_string() } else { stem };
+    format!("{}.{}", stem, extension(format))
+}
+
+/// Marker text from the template, with `{path}` and `{quoted_path}` expanded
+pub fn expand_marker(template: &str, path: &Path) -> String {
+    template
+        .replace("{quoted_path}", &crate::ui::shell_quote(path))
+        .replace("{path}", &path.to_string_lossy())
+}
+
+/// Save `image` as configured; returns the file's path
+///
+/// Fails rather than overwrite a file of the same name, e.g. from a
+/// template without `{timestamp}`.
+pub fn save(image: &RgbaImage, config: &PasteImageConfig) -> Result<PathBuf, String> {
+    let directory = directory(config);
+    std::fs::create_dir_all(&directory)
+        .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+    let name = expand_filename(&config.filename_template, SystemTime::now(), &crate::session_log::local_date(), config.format);
+    let path = directory.join(name);
+
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => format!("{} already exists; not overwriting it", path.display()),
+            _ => format!("Failed to create {}: {}", path.display(), e),
+        })?;
+    let mut writer = BufWriter::new(file);
+    let result = match config.format {
+        PasteImageFormat::Png => image.write_to(&mut writer, image::ImageFormat::Png),
+        PasteImageFormat::Jpeg { quality } => {
+            // JPEG has no alpha channel
+            let rgb = image::DynamicImage::ImageRgba8(image.clone()).to_rgb8();
+            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, quality.clamp(1, 100));
+            rgb.write_with_encoder(encoder)
+        }
+        PasteImageFormat::Webp => image.write_to(&mut writer, image::ImageFormat::WebP),
+    };
+    if let Err(e) = result {
+        drop(writer);
+        let _ = std::fs::remove_file(&path);
+        return Err(format!("Failed to encode {}: {}", path.display(), e));
+    }
+    Ok(path)
+}
+
+/// Literal start of the file names a template produces
+///
+/// Cleanup only touches files starting with it, so pointing the paste
+/// directory at a folder of other images can't delete them. Templates
+/// starting with a placeholder have no prefix and are never cleaned up.
+fn name_prefix(template: &str) -> &str {
+    &template[..template.find('{').unwrap_or(template.len())]
+}
+
+/// Is a file in the paste directory an old paste?
+fn is_expired(name: &str, modified: SystemTime, prefix: &str, now: SystemTime, max_age: Duration) -> bool {
+    let is_image = [".png", ".jpg", ".webp"].iter().any(|ext| name.ends_with(ext));
+    let old = now.duration_since(modified).is_ok_and(|age| age > max_age);
+    !prefix.is_empty() && name.starts_with(prefix) && is_image && old
+}
+
+/// Delete pastes older than `cleanup_after_days`; returns how many
+pub fn cleanup(config: &PasteImageConfig, now: SystemTime) -> usize {
+    let prefix = name_prefix(&config.filename_template);
+    if config.cleanup_after_days == 0 || prefix.is_empty() {
+        return 0;
+    }
+    let Ok(read_dir) = std::fs::read_dir(directory(config)) else {
+        return 0;
+    };
+    let max_age = Duration::from_secs(u64::from(config.cleanup_after_days) * 86_400);
+    let mut removed = 0;
+    for entry in read_dir.filter_map(|e| e.ok()) {
+        let Ok(modified) = entry.metadata().and_then(|meta| meta.modified()) else {
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if is_expired(&name, modified, prefix, now, max_age) && std::fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    fn at(millis: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_filename_template() {
+        let now = at(1_700_000_000_123);
+        assert_eq!(
+            expand_filename("paste-{date}-{timestamp}", now, "2023-11-14", PasteImageFormat::Png),
+            "paste-2023-11-14-1700000000123.png"
+        );
+        assert_eq!(
+            expand_filename("shots/{date}", now, "2023-11-14", PasteImageFormat::Jpeg { quality: 80 }),
+            "shots_2023-11-14.jpg"
+        );
+        // An empty name falls back to the timestamp
+        assert_eq!(expand_filename(" ", now, "2023-11-14", PasteImageFormat::Webp), "1700000000123.webp");
+    }
+
+    #[test]
+    fn test_marker_template() {
+        let path = Path::new("/tmp/it's.png");
+        assert_eq!(expand_marker("[image: {path}]\n", path), "[image: /tmp/it's.png]\n");
+        assert_eq!(expand_marker("{quoted_path} ", path), r"'/tmp/it'\''s.png' ");
+    }
+
+    #[test]
+    fn test_cleanup_age_filter() {
+        let now = at(100 * 86_400 * 1000);
+        let prefix = name_prefix("paste-{date}-{timestamp}");
+        assert_eq!(prefix, "paste-");
+        let old = now - 8 * DAY;
+        assert!(is_expired("paste-2023-11-14-1.png", old, prefix, now, 7 * DAY));
+        assert!(is_expired("paste-x.jpg", old, prefix, now, 7 * DAY));
+        // Too new, not a paste, or not an image
+        assert!(!is_expired("paste-x.png", now - 6 * DAY, prefix, now, 7 * DAY));
+        assert!(!is_expired("holiday.png", old, prefix, now, 7 * DAY));
+        assert!(!is_expired("paste-notes.txt", old, prefix, now, 7 * DAY));
+        // Without a literal prefix nothing is a paste
+        assert!(!is_expired("1.png", old, name_prefix("{timestamp}"), now, 7 * DAY));
+    }
+
+    #[test]
+    fn test_save_and_cleanup() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config = PasteImageConfig {
+            directory: dir.path().join("pastes"),
+            ..Default::default()
+        };
+        let image = RgbaImage::from_pixel(4, 3, image::Rgba([200, 10, 10, 255]));
+        for format in [PasteImageFormat::Png, PasteImageFormat::Jpeg { quality: 90 }, PasteImageFormat::Webp] {
+            config.format = format;
+            let path = save(&image, &config).unwrap();
+            assert!(path.starts_with(dir.path().join("pastes")));
+            let decoded = image::open(&path).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (4, 3));
+        }
+        std::fs::write(dir.path().join("pastes/keep.png"), "").unwrap();
+
+        // A name that's taken is left alone
+        config.filename_template = "keep".to_string();
+        config.format = PasteImageFormat::Png;
+        assert!(save(&image, &config).unwrap_err().contains("already exists"));
+        assert_eq!(std::fs::read(dir.path().join("pastes/keep.png")).unwrap(), b"");
+        config.filename_template = PasteImageConfig::default().filename_template;
+
+        assert_eq!(cleanup(&config, SystemTime::now() + 2 * DAY), 0);
+        config.cleanup_after_days = 1;
+        assert_eq!(cleanup(&config, SystemTime::now()), 0);
+        assert_eq!(cleanup(&config, SystemTime::now() + 2 * DAY), 3);
+        let left: Vec<_> = std::fs::read_dir(dir.path().join("pastes")).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(left, ["keep.png"]);
+    }
+}
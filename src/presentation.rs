@@ -0,0 +1,115 @@
+//! Presentation Mode
+//!
+//! Distraction-free view showing only the panes: the tab bar, status bar,
+//! sidebar and pane footers are hidden and the terminal font is enlarged.
+//! Everything changed on entry is remembered and put back on exit. The
+//! state lives only in the window and isn't saved.
+
+use std::time::{Duration, Instant};
+
+/// How long the "how to exit" hint stays up
+pub const HINT_DURATION: Duration = Duration::from_secs(2);
+
+/// Shortcut shown in the exit hint and the palette
+pub const SHORTCUT_LABEL: &str = "Cmd+Alt+Enter";
+
+/// Window state to put back when presentation mode ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Restore {
+    pub sidebar_visible: bool,
+    /// The window went fullscreen on entry and should leave it again
+    pub leave_fullscreen: bool,
+}
+
+/// Presentation mode of one window
+#[derive(Debug, Default)]
+pub struct PresentationMode {
+    /// Set while active
+    restore: Option<Restore>,
+    /// When the exit hint was shown
+    hint_shown_at: Option<Instant>,
+}
+
+impl PresentationMode {
+    pub fn is_active(&self) -> bool {
+        self.restore.is_some()
+    }
+
+    /// Enter, remembering the window state; returns whether to go fullscreen
+    pub fn enter(&mut self, sidebar_visible: bool, fullscreen: bool, was_fullscreen: bool, now: Instant) -> bool {
+        let enter_fullscreen = fullscreen && !was_fullscreen;
+        self.restore = Some(Restore {
+            sidebar_visible,
+            leave_fullscreen: enter_fullscreen,
+        });
+        self.hint_shown_at = Some(now);
+        enter_fullscreen
+    }
+
+    /// Leave, returning what to restore
+    pub fn exit(&mut self) -> Option<Restore> {
+        self.hint_shown_at = None;
+        self.restore.take()
+    }
+
+    /// Terminal font size with the presentation bump applied
+    pub fn font_size(&self, base: f32, bump: f32) -> f32 {
+        if self.is_active() {
+            base + bump
+        } else {
+            base
+        }
+    }
+
+    /// Time left before the exit hint disappears
+    pub fn hint_remaining(&self, now: Instant) -> Option<Duration> {
+        let shown_at = self.hint_shown_at?;
+        HINT_DURATION
+            .checked_sub(now.saturating_duration_since(shown_at))
+            .filter(|left| !left.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_restores_prior_state() {
+        let mut mode = PresentationMode::default();
+        let now = Instant::now();
+        assert_eq!(mode.font_size(14.0, 4.0), 14.0);
+
+        assert!(mode.enter(false, true, false, now));
+        assert!(mode.is_active());
+        assert_eq!(mode.font_size(14.0, 4.0), 18.0);
+
+        let restore = mode.exit().unwrap();
+        assert_eq!(restore, Restore { sidebar_visible: false, leave_fullscreen: true });
+        assert!(!mode.is_active());
+        assert_eq!(mode.font_size(14.0, 4.0), 14.0);
+        assert!(mode.exit().is_none());
+    }
+
+    #[test]
+    fn test_window_already_fullscreen_stays_fullscreen() {
+        let mut mode = PresentationMode::default();
+        assert!(!mode.enter(true, true, true, Instant::now()));
+        assert!(!mode.exit().unwrap().leave_fullscreen);
+
+        // Fullscreen turned off in the config
+        assert!(!mode.enter(true, false, false, Instant::now()));
+        assert!(!mode.exit().unwrap().leave_fullscreen);
+    }
+
+    #[test]
+    fn test_hint_hides_after_two_seconds() {
+        let mut mode = PresentationMode::default();
+        let now = Instant::now();
+        assert!(mode.hint_remaining(now).is_none());
+
+        mode.enter(true, false, false, now);
+        assert_eq!(mode.hint_remaining(now + Duration::from_millis(500)), Some(Duration::from_millis(1500)));
+        assert!(mode.hint_remaining(now + HINT_DURATION).is_none());
+    }
+}
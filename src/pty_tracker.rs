@@ -3,10 +3,39 @@
 //! This module provides functionality to track the current working directory
 //! of PTY child processes. On macOS, this uses libproc to query process info.
 //! On Linux, this reads from /proc/{pid}/cwd.
+//!
+//! It also locates the shell process spawned for a new terminal, since
+//! egui_term doesn't expose the child PID directly, and notices when the
+//! terminal's foreground job runs as root. The shell is recognised by the
+//! `ALACRITTY_WINDOW_ID` alacritty_terminal sets to the terminal's ID in the
+//! environment of the process it spawns on the PTY.
 
+use std::collections::HashSet;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+/// How long to keep looking for a freshly spawned shell
+pub const SHELL_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Delay between shell discovery scans
+pub const SHELL_DISCOVERY_RETRY: Duration = Duration::from_millis(100);
+
+/// Shell PIDs already attached to a tracker, shared between discovery tasks
+pub type ClaimedPids = Arc<Mutex<HashSet<u32>>>;
+
+/// Minimal process information used for shell discovery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    /// Process group ID
+    pub pgid: u32,
+}
+
+/// Environment variable holding the terminal ID a PTY child was spawned for
+const TERMINAL_ID_VAR: &str = "ALACRITTY_WINDOW_ID";
+
 /// Tracks PTY process working directory
 pub struct PtyTracker {
     /// PTY child process ID
@@ -17,6 +46,48 @@ pub struct PtyTracker {
     last_poll: Instant,
     /// Polling interval
     poll_interval: Duration,
+    /// Root detection state, `None` while detection is off
+    elevation: Option<Elevation>,
+}
+
+/// What changed in a [`PtyTracker::poll`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PollChanges {
+    pub cwd: bool,
+    pub elevated: bool,
+}
+
+/// Whether the foreground process group of a terminal runs as root
+///
+/// The group leader's effective UID is only looked up when the foreground
+/// group changes, so an unchanged job costs nothing beyond the poll
+/// itself. While the shell sits at its prompt the shell is the group.
+#[derive(Debug, Default)]
+struct Elevation {
+    /// Foreground process group and its leader's effective UID
+    foreground: Option<(u32, u32)>,
+}
+
+impl Elevation {
+    fn is_elevated(&self) -> bool {
+        matches!(self.foreground, Some((_, 0)))
+    }
+
+    /// Record the current foreground group; returns whether the elevated state changed
+    ///
+    /// If the UID can't be read (the job already exited) the previous state
+    /// is kept and the lookup is retried next poll.
+    fn update(&mut self, pgid: u32, effective_uid: impl FnOnce(u32) -> Option<u32>) -> bool {
+        if matches!(self.foreground, Some((known, _)) if known == pgid) {
+            return false;
+        }
+        let Some(uid) = effective_uid(pgid) else {
+            return false;
+        };
+        let was_elevated = self.is_elevated();
+        self.foreground = Some((pgid, uid));
+        self.is_elevated() != was_elevated
+    }
 }
 
 impl PtyTracker {
@@ -30,6 +101,7 @@ impl PtyTracker {
             current_dir,
             last_poll: Instant::now(),
             poll_interval: Duration::from_millis(500),
+            elevation: None,
         })
     }
 
@@ -48,12 +120,28 @@ impl PtyTracker {
         self.poll_interval = interval;
     }
 
-    /// Poll for CWD changes if the interval has elapsed
-    ///
-    /// Returns true if the CWD has changed since the last poll
-    pub fn poll(&mut self) -> bool {
+    /// Turn root detection on or off
+    pub fn set_detect_elevation(&mut self, enabled: bool) {
+        if enabled != self.elevation.is_some() {
+            self.elevation = enabled.then(Elevation::default);
+        }
+    }
+
+    /// Whether the terminal's foreground job runs as root
+    pub fn is_elevated(&self) -> bool {
+        self.elevation.as_ref().is_some_and(Elevation::is_elevated)
+    }
+
+    /// Is a job other than the shell in the foreground (checked now)?
+    pub fn has_foreground_job(&self) -> bool {
+        get_foreground_pgid(self.pid).is_some_and(|pgid| pgid != self.pid)
+    }
+
+    /// Poll for CWD and privilege changes if the interval has elapsed
+    pub fn poll(&mut self) -> PollChanges {
+        let mut changes = PollChanges::default();
         if self.last_poll.elapsed() < self.poll_interval {
-            return false;
+            return changes;
         }
 
         self.last_poll = Instant::now();
@@ -61,13 +149,218 @@ impl PtyTracker {
         if let Some(new_dir) = get_process_cwd(self.pid) {
             if new_dir != self.current_dir {
                 self.current_dir = new_dir;
-                return true;
+                changes.cwd = true;
             }
         }
         // If we can't get the CWD, keep the last known value
 
-        false
+        if let Some(elevation) = &mut self.elevation {
+            if let Some(pgid) = get_foreground_pgid(self.pid) {
+                changes.elevated = elevation.update(pgid, get_effective_uid);
+            }
+        }
+
+        changes
+    }
+}
+
+/// Pick the shell PID of terminal `terminal_id` from a process list
+///
+/// Considers direct children of `parent` that lead their own process group
+/// (a PTY child starts a new session) and are not already claimed, and takes
+/// the one whose environment names the terminal, looked up with
+/// `terminal_id_of`. Other children, like git or a formatter, inherit this
+/// process's group and are never picked.
+pub fn select_shell_pid(
+    processes: &[ProcessInfo],
+    parent: u32,
+    terminal_id: u64,
+    claimed: &HashSet<u32>,
+    terminal_id_of: impl Fn(u32) -> Option<u64>,
+) -> Option<u32> {
+    processes
+        .iter()
+        .filter(|p| p.ppid == parent && p.pgid == p.pid && !claimed.contains(&p.pid))
+        .find(|p| terminal_id_of(p.pid) == Some(terminal_id))
+        .map(|p| p.pid)
+}
+
+/// Scan for the shell of terminal `terminal_id` among this process's
+/// children and claim it
+///
+/// Claimed PIDs whose process no longer exists are released first, so
+/// recycled PIDs can be picked up again.
+pub fn claim_shell_pid(claimed: &ClaimedPids, terminal_id: u64) -> Option<u32> {
+    let processes = list_processes();
+    let mut claimed = claimed.lock().ok()?;
+
+    claimed.retain(|pid| processes.iter().any(|p| p.pid == *pid));
+
+    let pid = select_shell_pid(&processes, std::process::id(), terminal_id, &claimed, get_terminal_id)?;
+    claimed.insert(pid);
+    Some(pid)
+}
+
+/// Release a previously claimed shell PID
+pub fn release_shell_pid(claimed: &ClaimedPids, pid: u32) {
+    if let Ok(mut claimed) = claimed.lock() {
+        claimed.remove(&pid);
+    }
+}
+
+/// List all processes (macOS implementation)
+#[cfg(target_os = "macos")]
+fn list_processes() -> Vec<ProcessInfo> {
+    use libproc::libproc::bsd_info::BSDInfo;
+    use libproc::libproc::proc_pid::pidinfo;
+    use libproc::processes::{pids_by_type, ProcFilter};
+
+    let Ok(pids) = pids_by_type(ProcFilter::All) else {
+        return Vec::new();
+    };
+
+    pids.into_iter()
+        .filter_map(|pid| {
+            let info = pidinfo::<BSDInfo>(pid as i32, 0).ok()?;
+            Some(ProcessInfo {
+                pid,
+                ppid: info.pbi_ppid,
+                pgid: info.pbi_pgid,
+            })
+        })
+        .collect()
+}
+
+/// List all processes (Linux implementation)
+///
+/// Reads /proc/{pid}/stat for the parent PID and process group.
+#[cfg(target_os = "linux")]
+fn list_processes() -> Vec<ProcessInfo> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_string_lossy().parse::<u32>().ok()?;
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+            parse_proc_stat(pid, &stat)
+        })
+        .collect()
+}
+
+/// Parse a /proc/{pid}/stat line
+///
+/// Format: pid (comm) state ppid pgrp ...
+/// comm may contain spaces and parens, so split after the last ')'.
+#[cfg(target_os = "linux")]
+fn parse_proc_stat(pid: u32, stat: &str) -> Option<ProcessInfo> {
+    let close_paren = stat.rfind(')')?;
+    let fields: Vec<&str> = stat.get(close_paren + 2..)?.split_whitespace().collect();
+
+    Some(ProcessInfo {
+        pid,
+        ppid: fields.get(1)?.parse().ok()?,
+        pgid: fields.get(2)?.parse().ok()?,
+    })
+}
+
+/// Fallback for unsupported platforms - no process information
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn list_processes() -> Vec<ProcessInfo> {
+    Vec::new()
+}
+
+/// Terminal ID a PTY child was spawned for, from its environment
+fn get_terminal_id(pid: u32) -> Option<u64> {
+    parse_terminal_id(&read_environment(pid)?)
+}
+
+/// `ALACRITTY_WINDOW_ID` from a NUL-separated environment block
+fn parse_terminal_id(environment: &[u8]) -> Option<u64> {
+    let value = environment
+        .split(|&byte| byte == 0)
+        .find_map(|entry| entry.strip_prefix(TERMINAL_ID_VAR.as_bytes())?.strip_prefix(b"="))?;
+    std::str::from_utf8(value).ok()?.parse().ok()
+}
+
+/// Initial environment of a process (macOS implementation)
+///
+/// Uses sysctl KERN_PROCARGS2, which only works for processes of the same user.
+#[cfg(target_os = "macos")]
+fn read_environment(pid: u32) -> Option<Vec<u8>> {
+    use std::mem;
+    use std::ptr;
+
+    let mut arg_max: libc::c_int = 0;
+    let mut size = mem::size_of::<libc::c_int>();
+    let mut mib = [libc::CTL_KERN, libc::KERN_ARGMAX];
+    let ret = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as u32,
+            &mut arg_max as *mut _ as *mut libc::c_void,
+            &mut size,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 || arg_max <= 0 {
+        return None;
     }
+
+    let mut buffer = vec![0u8; arg_max as usize];
+    let mut size = buffer.len();
+    let mut mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid as libc::c_int];
+    let ret = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as u32,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            &mut size,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 {
+        return None;
+    }
+
+    buffer.truncate(size);
+    procargs_environment(&buffer).map(<[u8]>::to_vec)
+}
+
+/// The environment in a KERN_PROCARGS2 buffer
+///
+/// Layout: argc as an int, the executable path and its NUL padding, argc
+/// NUL-terminated arguments, then the NUL-separated environment.
+#[cfg(any(target_os = "macos", test))]
+fn procargs_environment(procargs: &[u8]) -> Option<&[u8]> {
+    let argc = i32::from_ne_bytes(procargs.get(..4)?.try_into().ok()?);
+    let rest = &procargs[4..];
+
+    let path_end = rest.iter().position(|&byte| byte == 0)?;
+    let rest = &rest[path_end..];
+    let mut rest = &rest[rest.iter().position(|&byte| byte != 0)?..];
+
+    for _ in 0..argc {
+        let arg_end = rest.iter().position(|&byte| byte == 0)?;
+        rest = &rest[arg_end + 1..];
+    }
+    Some(rest)
+}
+
+/// Initial environment of a process (Linux implementation)
+#[cfg(target_os = "linux")]
+fn read_environment(pid: u32) -> Option<Vec<u8>> {
+    std::fs::read(format!("/proc/{}/environ", pid)).ok()
+}
+
+/// Fallback for unsupported platforms - no environment, so no shell is found
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn read_environment(_pid: u32) -> Option<Vec<u8>> {
+    None
 }
 
 /// Get the current working directory of a process by PID (macOS implementation)
@@ -75,7 +368,7 @@ impl PtyTracker {
 /// Uses libproc's proc_pidinfo with PROC_PIDVNODEPATHINFO flavor to get the
 /// process's current directory (pvi_cdir).
 #[cfg(target_os = "macos")]
-fn get_process_cwd(pid: u32) -> Option<PathBuf> {
+pub fn get_process_cwd(pid: u32) -> Option<PathBuf> {
     use std::ffi::CStr;
     use std::mem;
 
@@ -134,13 +427,74 @@ fn get_process_cwd(pid: u32) -> Option<PathBuf> {
 ///
 /// Reads the /proc/{pid}/cwd symlink to get the process's current directory.
 #[cfg(target_os = "linux")]
-fn get_process_cwd(pid: u32) -> Option<PathBuf> {
+pub fn get_process_cwd(pid: u32) -> Option<PathBuf> {
     std::fs::read_link(format!("/proc/{}/cwd", pid)).ok()
 }
 
 /// Fallback for unsupported platforms - always returns None
 #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-fn get_process_cwd(_pid: u32) -> Option<PathBuf> {
+pub fn get_process_cwd(_pid: u32) -> Option<PathBuf> {
+    None
+}
+
+/// Foreground process group of the terminal a process is attached to (macOS)
+#[cfg(target_os = "macos")]
+fn get_foreground_pgid(pid: u32) -> Option<u32> {
+    use libproc::libproc::bsd_info::BSDInfo;
+    use libproc::libproc::proc_pid::pidinfo;
+
+    let info = pidinfo::<BSDInfo>(pid as i32, 0).ok()?;
+    Some(info.e_tpgid).filter(|&pgid| pgid > 0)
+}
+
+/// Effective UID of a process (macOS)
+#[cfg(target_os = "macos")]
+fn get_effective_uid(pid: u32) -> Option<u32> {
+    use libproc::libproc::bsd_info::BSDInfo;
+    use libproc::libproc::proc_pid::pidinfo;
+
+    pidinfo::<BSDInfo>(pid as i32, 0).ok().map(|info| info.pbi_uid)
+}
+
+/// Foreground process group of the terminal a process is attached to (Linux)
+///
+/// tpgid is field 8 of /proc/{pid}/stat.
+#[cfg(target_os = "linux")]
+fn get_foreground_pgid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    parse_tpgid(&stat)
+}
+
+#[cfg(target_os = "linux")]
+fn parse_tpgid(stat: &str) -> Option<u32> {
+    let close_paren = stat.rfind(')')?;
+    let tpgid: i64 = stat.get(close_paren + 2..)?.split_whitespace().nth(5)?.parse().ok()?;
+    // -1 without a controlling terminal
+    u32::try_from(tpgid).ok().filter(|&pgid| pgid > 0)
+}
+
+/// Effective UID of a process (Linux)
+#[cfg(target_os = "linux")]
+fn get_effective_uid(pid: u32) -> Option<u32> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    parse_status_euid(&status)
+}
+
+/// Effective UID from /proc/{pid}/status ("Uid: real effective saved fs")
+#[cfg(target_os = "linux")]
+fn parse_status_euid(status: &str) -> Option<u32> {
+    let line = status.lines().find_map(|line| line.strip_prefix("Uid:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Fallback for unsupported platforms - privileges are never detected
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn get_foreground_pgid(_pid: u32) -> Option<u32> {
+    None
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn get_effective_uid(_pid: u32) -> Option<u32> {
     None
 }
 
@@ -173,14 +527,156 @@ mod tests {
         let pid = std::process::id();
         let mut tracker = PtyTracker::new(pid).unwrap();
 
-        // First poll after creation should return false (no change, interval not elapsed)
-        assert!(!tracker.poll());
+        // First poll after creation should report nothing (no change, interval not elapsed)
+        assert_eq!(tracker.poll(), PollChanges::default());
 
         // Set a very short interval
         tracker.set_interval(Duration::from_millis(1));
         std::thread::sleep(Duration::from_millis(5));
 
         // Now poll should work (interval elapsed), but no change expected
-        assert!(!tracker.poll());
+        assert_eq!(tracker.poll(), PollChanges::default());
+    }
+
+    fn proc_info(pid: u32, ppid: u32, pgid: u32) -> ProcessInfo {
+        ProcessInfo { pid, ppid, pgid }
+    }
+
+    /// Terminal ID in the environment of each process, by PID
+    fn terminal_ids(pid: u32) -> Option<u64> {
+        match pid {
+            200 => Some(1),
+            300 => Some(2),
+            // Inherited from a terminal this app was started in
+            400 => Some(2),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_select_matches_terminal_id() {
+        let processes = [
+            proc_info(100, 1, 100),
+            proc_info(200, 42, 200),
+            proc_info(300, 42, 300),
+            proc_info(400, 42, 42), // `git status` run by the app
+            proc_info(500, 7, 500), // not our child
+        ];
+
+        assert_eq!(select_shell_pid(&processes, 42, 1, &HashSet::new(), terminal_ids), Some(200));
+        assert_eq!(select_shell_pid(&processes, 42, 2, &HashSet::new(), terminal_ids), Some(300));
+        assert_eq!(select_shell_pid(&processes, 42, 3, &HashSet::new(), terminal_ids), None);
+    }
+
+    #[test]
+    fn test_select_skips_claimed() {
+        let processes = [proc_info(200, 42, 200), proc_info(300, 42, 300)];
+        let claimed: HashSet<u32> = [300].into_iter().collect();
+
+        assert_eq!(select_shell_pid(&processes, 42, 1, &claimed, terminal_ids), Some(200));
+        assert_eq!(select_shell_pid(&processes, 42, 2, &claimed, terminal_ids), None);
+    }
+
+    #[test]
+    fn test_racing_panes_get_their_own_pids() {
+        // Two shells spawned in quick succession, both seen by the same scan,
+        // found in the opposite order
+        let processes = [proc_info(200, 42, 200), proc_info(300, 42, 300)];
+
+        assert_eq!(select_shell_pid(&processes, 42, 2, &HashSet::new(), terminal_ids), Some(300));
+        assert_eq!(select_shell_pid(&processes, 42, 1, &HashSet::new(), terminal_ids), Some(200));
+    }
+
+    #[test]
+    fn test_parse_terminal_id() {
+        assert_eq!(parse_terminal_id(b"HOME=/home/me\0ALACRITTY_WINDOW_ID=7\0WINDOWID=7\0"), Some(7));
+        assert_eq!(parse_terminal_id(b"ALACRITTY_WINDOW_ID_X=7\0TERM=xterm\0"), None);
+        assert_eq!(parse_terminal_id(b""), None);
+
+        // argc, executable path with padding, arguments, environment
+        let mut procargs = 2i32.to_ne_bytes().to_vec();
+        procargs.extend_from_slice(b"/bin/zsh\0\0\0\0-zsh\0-l\0ALACRITTY_WINDOW_ID=12\0\0");
+        let environment = procargs_environment(&procargs).unwrap();
+        assert_eq!(parse_terminal_id(environment), Some(12));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_proc_stat() {
+        let stat = "1234 (my (weird) shell) S 42 1234 1234 0 -1 4194304 100 0 0 0 \
+                    0 0 0 0 20 0 1 0 98765 1000000 100";
+        let info = parse_proc_stat(1234, stat).unwrap();
+        assert_eq!(info.ppid, 42);
+        assert_eq!(info.pgid, 1234);
+    }
+
+    #[test]
+    fn test_elevation_enter_and_leave_root() {
+        let mut elevation = Elevation::default();
+        let uids = |pgid: u32| Some(if pgid == 900 { 0 } else { 501 });
+
+        // Shell at its prompt
+        assert!(!elevation.update(100, uids));
+        assert!(!elevation.is_elevated());
+
+        // `sudo -i` takes the foreground
+        assert!(elevation.update(900, uids));
+        assert!(elevation.is_elevated());
+
+        // Back at the user's prompt
+        assert!(elevation.update(100, uids));
+        assert!(!elevation.is_elevated());
+    }
+
+    #[test]
+    fn test_elevation_skips_lookup_for_same_group() {
+        let mut elevation = Elevation::default();
+        assert!(elevation.update(900, |_| Some(0)));
+
+        let mut lookups = 0;
+        assert!(!elevation.update(900, |_| {
+            lookups += 1;
+            Some(501)
+        }));
+        assert_eq!(lookups, 0);
+        assert!(elevation.is_elevated());
+    }
+
+    #[test]
+    fn test_elevation_keeps_state_when_uid_unreadable() {
+        let mut elevation = Elevation::default();
+        elevation.update(900, |_| Some(0));
+
+        // The job exited before its UID could be read
+        assert!(!elevation.update(901, |_| None));
+        assert!(elevation.is_elevated());
+
+        // Retried on the next poll
+        assert!(elevation.update(901, |_| Some(501)));
+    }
+
+    #[test]
+    fn test_detection_toggle() {
+        let mut tracker = PtyTracker::new(std::process::id()).unwrap();
+        tracker.set_detect_elevation(true);
+        tracker.elevation.as_mut().unwrap().update(900, |_| Some(0));
+        assert!(tracker.is_elevated());
+
+        tracker.set_detect_elevation(true);
+        assert!(tracker.is_elevated());
+        tracker.set_detect_elevation(false);
+        assert!(!tracker.is_elevated());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_tpgid_and_euid() {
+        let stat = "1234 (bash) S 42 1234 1234 34816 5678 4194304 100";
+        assert_eq!(parse_tpgid(stat), Some(5678));
+        assert_eq!(parse_tpgid("1 (init) S 0 1 1 0 -1 4194560"), None);
+
+        let status = "Name:\tsudo\nUmask:\t0022\nUid:\t501\t0\t0\t0\nGid:\t20\t20\t20\t20\n";
+        assert_eq!(parse_status_euid(status), Some(0));
+        assert_eq!(get_effective_uid(std::process::id()), Some(unsafe { libc::geteuid() }));
     }
 }
@@ -0,0 +1,164 @@
+//! Quit Confirmation and Shutdown
+//!
+//! Closing the window with jobs running, session logs open or background
+//! work unfinished asks first (`ui.confirm_quit`). Once quitting, shells
+//! get a hangup and a short while to exit before the app goes.
+
+use std::time::{Duration, Instant};
+
+/// How long quitting waits for shells to exit after the hangup
+pub const HANGUP_WAIT: Duration = Duration::from_millis(500);
+
+/// How long quitting waits for session logs to be written
+pub const LOG_FLUSH_WAIT: Duration = Duration::from_secs(1);
+
+/// What a pane is doing, for the quit dialog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaneActivity {
+    /// Name of the pane's tab
+    pub workspace: String,
+    /// Short name of the pane
+    pub pane: String,
+    /// A job other than the shell runs in the foreground
+    pub running_job: bool,
+    /// The pane's output is being logged
+    pub logging: bool,
+}
+
+/// Background work that quitting would cut short
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackgroundWork {
+    /// Sidebar directory scans in progress
+    pub dir_scans: usize,
+    /// A background `git fetch` is running
+    pub git_fetch: bool,
+    /// A project search is running
+    pub project_search: bool,
+}
+
+/// Everything quitting now would interrupt
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuitSummary {
+    /// Tabs with running jobs, and how many
+    pub running: Vec<(String, usize)>,
+    /// Panes being logged ("tab › pane")
+    pub logging: Vec<String>,
+    /// Background work, described
+    pub tasks: Vec<String>,
+}
+
+impl QuitSummary {
+    pub fn collect(panes: &[PaneActivity], work: BackgroundWork) -> Self {
+        let mut summary = QuitSummary::default();
+        for pane in panes {
+            if pane.running_job {
+                match summary.running.iter_mut().find(|(workspace, _)| *workspace == pane.workspace) {
+                    Some((_, count)) => *count += 1,
+                    None => summary.running.push((pane.workspace.clone(), 1)),
+                }
+            }
+            if pane.logging {
+                summary.logging.push(format!("{} › {}", pane.workspace, pane.pane));
+            }
+        }
+
+        match work.dir_scans {
+            0 => {}
+            1 => summary.tasks.push("1 directory scan".to_string()),
+            n => summary.tasks.push(format!("{} directory scans", n)),
+        }
+        if work.git_fetch {
+            summary.tasks.push("git fetch".to_string());
+        }
+        if work.project_search {
+            summary.tasks.push("project search".to_string());
+        }
+        summary
+    }
+
+    /// Nothing would be lost by quitting
+    pub fn is_empty(&self) -> bool {
+        self.running.is_empty() && self.logging.is_empty() && self.tasks.is_empty()
+    }
+}
+
+/// Should a close request be held for the quit dialog?
+pub fn should_confirm(confirm_quit: bool, summary: &QuitSummary) -> bool {
+    confirm_quit && !summary.is_empty()
+}
+
+/// Send SIGHUP to the shells and wait up to `timeout` for them to exit
+///
+/// Returns how many are still running; the PTYs closing ends them anyway.
+#[cfg(unix)]
+pub fn hang_up(pids: &[u32], timeout: Duration) -> usize {
+    let alive = |pid: u32| unsafe { libc::kill(pid as libc::pid_t, 0) == 0 };
+    for &pid in pids {
+        unsafe {
+            libc::kill(pid as libc::pid_t, libc::SIGHUP);
+        }
+    }
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = pids.iter().filter(|&&pid| alive(pid)).count();
+        if remaining == 0 || Instant::now() >= deadline {
+            return remaining;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[cfg(not(unix))]
+pub fn hang_up(pids: &[u32], _timeout: Duration) -> usize {
+    pids.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pane(workspace: &str, pane: &str, running_job: bool, logging: bool) -> PaneActivity {
+        PaneActivity {
+            workspace: workspace.to_string(),
+            pane: pane.to_string(),
+            running_job,
+            logging,
+        }
+    }
+
+    #[test]
+    fn test_idle_app_quits_without_asking() {
+        let panes = [pane("shell-1", "~", false, false), pane("api", "src", false, false)];
+        let summary = QuitSummary::collect(&panes, BackgroundWork::default());
+        assert!(summary.is_empty());
+        assert!(!should_confirm(true, &summary));
+    }
+
+    #[test]
+    fn test_summary_groups_jobs_by_tab() {
+        let panes = [
+            pane("api", "server", true, true),
+            pane("api", "tests", true, false),
+            pane("web", "~", false, true),
+            pane("notes", "~", true, false),
+        ];
+        let work = BackgroundWork { dir_scans: 2, git_fetch: true, project_search: false };
+        let summary = QuitSummary::collect(&panes, work);
+
+        assert_eq!(summary.running, [("api".to_string(), 2), ("notes".to_string(), 1)]);
+        assert_eq!(summary.logging, ["api › server", "web › ~"]);
+        assert_eq!(summary.tasks, ["2 directory scans", "git fetch"]);
+        assert!(should_confirm(true, &summary));
+        // Turned off, nothing holds the window
+        assert!(!should_confirm(false, &summary));
+    }
+
+    #[test]
+    fn test_background_work_alone_asks() {
+        let work = BackgroundWork { dir_scans: 1, git_fetch: false, project_search: true };
+        let summary = QuitSummary::collect(&[], work);
+        assert_eq!(summary.tasks, ["1 directory scan", "project search"]);
+        assert!(should_confirm(true, &summary));
+    }
+}
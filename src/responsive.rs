@@ -0,0 +1,138 @@
+//! Responsive Layout for Small Windows
+//!
+//! Decides which parts of the chrome to shrink or hide for the window size,
+//! so the panes keep most of a small window. The sidebar is hidden apart
+//! from the user's own Cmd+B state and comes back when the window grows.
+
+use egui::{Rect, Vec2};
+
+use crate::config::UiConfig;
+use crate::layout::SplitDirection;
+
+/// Chrome adjustments for the current window size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Responsive {
+    /// Sidebar hidden because the window is narrow
+    pub sidebar_auto_hidden: bool,
+    /// User showed the sidebar again while the window is narrow
+    pub sidebar_override: bool,
+    /// Tabs show only their numbers
+    pub compact_tabs: bool,
+    /// Optional status bar segments are left out
+    pub compact_status: bool,
+}
+
+impl Responsive {
+    /// State for a window of `size`, carrying over the sidebar override
+    pub fn update(self, size: Vec2, config: &UiConfig) -> Self {
+        let narrow = size.x < config.sidebar_auto_hide_width;
+        // Growing past the threshold forgets the override
+        let sidebar_override = narrow && self.sidebar_override;
+        Self {
+            sidebar_auto_hidden: narrow && !sidebar_override,
+            sidebar_override,
+            compact_tabs: size.x < config.compact_tab_bar_width,
+            compact_status: size.x < config.compact_status_bar_width,
+        }
+    }
+
+    /// Is the sidebar drawn, given the user's toggle?
+    pub fn sidebar_shown(&self, user_visible: bool) -> bool {
+        user_visible && !self.sidebar_auto_hidden
+    }
+
+    /// Toggle the sidebar (Cmd+B) and return the user's new toggle state
+    ///
+    /// An auto-hidden sidebar is brought back rather than toggled off.
+    pub fn toggle_sidebar(&mut self, user_visible: bool) -> bool {
+        if self.sidebar_auto_hidden {
+            self.sidebar_auto_hidden = false;
+            self.sidebar_override = true;
+            true
+        } else {
+            !user_visible
+        }
+    }
+}
+
+/// Would both halves of `pane` stay at least `min_size` after a split?
+pub fn split_allowed(pane: Rect, direction: SplitDirection, divider: f32, min_size: Vec2) -> bool {
+    // Nothing laid out yet
+    if !pane.is_positive() {
+        return true;
+    }
+    match direction {
+        SplitDirection::Horizontal => (pane.width() - divider) / 2.0 >= min_size.x,
+        SplitDirection::Vertical => (pane.height() - divider) / 2.0 >= min_size.y,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::vec2;
+
+    fn config() -> UiConfig {
+        UiConfig {
+            sidebar_auto_hide_width: 700.0,
+            compact_tab_bar_width: 600.0,
+            compact_status_bar_width: 800.0,
+            ..UiConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let config = config();
+        let wide = Responsive::default().update(vec2(1200.0, 800.0), &config);
+        assert_eq!(wide, Responsive::default());
+
+        let medium = Responsive::default().update(vec2(750.0, 800.0), &config);
+        assert!(!medium.sidebar_auto_hidden && !medium.compact_tabs && medium.compact_status);
+
+        let small = Responsive::default().update(vec2(500.0, 300.0), &config);
+        assert!(small.sidebar_auto_hidden && small.compact_tabs && small.compact_status);
+    }
+
+    #[test]
+    fn test_auto_hidden_sidebar_returns_with_user_state() {
+        let config = config();
+        let narrow = Responsive::default().update(vec2(600.0, 800.0), &config);
+        assert!(!narrow.sidebar_shown(true));
+
+        let wide = narrow.update(vec2(900.0, 800.0), &config);
+        assert!(wide.sidebar_shown(true));
+        // The user's own choice to hide it is kept
+        assert!(!wide.sidebar_shown(false));
+    }
+
+    #[test]
+    fn test_toggle_while_auto_hidden_shows_sidebar() {
+        let config = config();
+        let mut state = Responsive::default().update(vec2(600.0, 800.0), &config);
+        let user_visible = state.toggle_sidebar(false);
+        assert!(user_visible && state.sidebar_shown(user_visible));
+
+        // Resizing while still narrow keeps it shown
+        state = state.update(vec2(550.0, 800.0), &config);
+        assert!(state.sidebar_shown(user_visible));
+
+        // After growing and shrinking again it's auto-hidden once more
+        state = state.update(vec2(900.0, 800.0), &config).update(vec2(600.0, 800.0), &config);
+        assert!(!state.sidebar_shown(user_visible));
+
+        // A normal toggle when it isn't auto-hidden
+        let mut wide = Responsive::default();
+        assert!(!wide.toggle_sidebar(true));
+    }
+
+    #[test]
+    fn test_split_allowed() {
+        let min = vec2(200.0, 100.0);
+        let pane = Rect::from_min_size(egui::pos2(0.0, 0.0), vec2(404.0, 150.0));
+        assert!(split_allowed(pane, SplitDirection::Horizontal, 4.0, min));
+        assert!(!split_allowed(pane.with_max_x(300.0), SplitDirection::Horizontal, 4.0, min));
+        assert!(!split_allowed(pane, SplitDirection::Vertical, 4.0, min));
+        assert!(split_allowed(Rect::NOTHING, SplitDirection::Vertical, 4.0, min));
+    }
+}
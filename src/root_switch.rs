@@ -0,0 +1,179 @@
+//! Sidebar Root Switching
+//!
+//! Rate limits switching a workspace's sidebar to a pane's project root,
+//! so a script that `cd`s through many directories doesn't rescan the tree
+//! on every CWD poll. A switch waits until the pane's directory has been
+//! stable for a dwell time, happens at most once per interval, and newer
+//! requests replace queued ones.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::layout::PaneId;
+
+/// Directory must be unchanged this long before the sidebar follows it
+const DEFAULT_DWELL: Duration = Duration::from_secs(2);
+
+/// Minimum time between two switches of one workspace
+const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Queued switch
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pending {
+    pane: PaneId,
+    target: PathBuf,
+    /// When the pane's directory last changed (`None`: long ago)
+    changed_at: Option<Instant>,
+}
+
+/// Decides when a workspace's sidebar root may switch
+#[derive(Debug, Clone)]
+pub struct RootSwitchGovernor {
+    dwell: Duration,
+    min_interval: Duration,
+    pending: Option<Pending>,
+    last_switch: Option<Instant>,
+}
+
+impl Default for RootSwitchGovernor {
+    fn default() -> Self {
+        Self::new(DEFAULT_DWELL, DEFAULT_MIN_INTERVAL)
+    }
+}
+
+impl RootSwitchGovernor {
+    pub fn new(dwell: Duration, min_interval: Duration) -> Self {
+        Self {
+            dwell,
+            min_interval,
+            pending: None,
+            last_switch: None,
+        }
+    }
+
+    /// Apply changed timing settings
+    pub fn set_timing(&mut self, dwell: Duration, min_interval: Duration) {
+        self.dwell = dwell;
+        self.min_interval = min_interval;
+    }
+
+    /// Ask to show `target` for `pane`, whose directory last changed at
+    /// `changed_at`; replaces any queued request
+    pub fn request(&mut self, pane: PaneId, target: PathBuf, changed_at: Option<Instant>) {
+        self.pending = Some(Pending { pane, target, changed_at });
+    }
+
+    /// Root to switch to now, if any
+    ///
+    /// Requests for panes other than `focused` are dropped, as are requests
+    /// for the root already shown. While `blocked` (a fullscreen app runs
+    /// in the pane) the request waits.
+    pub fn poll(&mut self, focused: PaneId, current_root: &Path, blocked: bool, now: Instant) -> Option<PathBuf> {
+        let pending = self.pending.as_ref()?;
+        if pending.pane != focused || pending.target == current_root {
+            self.pending = None;
+            return None;
+        }
+        if blocked || self.next_due(now).is_some_and(|due| due > now) {
+            return None;
+        }
+        self.last_switch = Some(now);
+        self.pending.take().map(|pending| pending.target)
+    }
+
+    /// When the queued request may be applied, `None` if nothing is queued
+    pub fn next_due(&self, now: Instant) -> Option<Instant> {
+        let pending = self.pending.as_ref()?;
+        let settled = pending.changed_at.map_or(now, |at| at + self.dwell);
+        let allowed = self.last_switch.map_or(now, |at| at + self.min_interval);
+        Some(settled.max(allowed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PANE: PaneId = PaneId(1);
+
+    /// Fake clock: a fixed start plus elapsed milliseconds
+    struct Clock(Instant);
+
+    impl Clock {
+        fn at(&self, ms: u64) -> Instant {
+            self.0 + Duration::from_millis(ms)
+        }
+    }
+
+    fn setup() -> (RootSwitchGovernor, Clock, PathBuf) {
+        (RootSwitchGovernor::default(), Clock(Instant::now()), PathBuf::from("/home/me"))
+    }
+
+    #[test]
+    fn test_waits_for_stable_directory() {
+        let (mut governor, clock, current) = setup();
+        governor.request(PANE, PathBuf::from("/a"), Some(clock.at(0)));
+        assert_eq!(governor.poll(PANE, &current, false, clock.at(1000)), None);
+        assert_eq!(governor.next_due(clock.at(1000)), Some(clock.at(2000)));
+        assert_eq!(governor.poll(PANE, &current, false, clock.at(2000)), Some(PathBuf::from("/a")));
+        assert_eq!(governor.next_due(clock.at(2000)), None);
+    }
+
+    #[test]
+    fn test_rapid_cds_collapse_to_latest() {
+        let (mut governor, clock, current) = setup();
+        // A directory change every 500ms keeps restarting the dwell
+        for (step, dir) in ["/a", "/b", "/c", "/d"].iter().enumerate() {
+            let at = clock.at(step as u64 * 500);
+            governor.request(PANE, PathBuf::from(dir), Some(at));
+            assert_eq!(governor.poll(PANE, &current, false, at), None);
+        }
+        assert_eq!(governor.poll(PANE, &current, false, clock.at(3000)), None);
+        assert_eq!(governor.poll(PANE, &current, false, clock.at(3500)), Some(PathBuf::from("/d")));
+    }
+
+    #[test]
+    fn test_switches_at_most_once_per_interval() {
+        let (mut governor, clock, current) = setup();
+        governor.request(PANE, PathBuf::from("/a"), None);
+        assert_eq!(governor.poll(PANE, &current, false, clock.at(0)), Some(PathBuf::from("/a")));
+
+        // Settled directories still wait out the interval
+        governor.request(PANE, PathBuf::from("/b"), None);
+        assert_eq!(governor.poll(PANE, Path::new("/a"), false, clock.at(1000)), None);
+        assert_eq!(governor.next_due(clock.at(1000)), Some(clock.at(3000)));
+        assert_eq!(governor.poll(PANE, Path::new("/a"), false, clock.at(3000)), Some(PathBuf::from("/b")));
+    }
+
+    #[test]
+    fn test_same_root_and_other_panes_are_dropped() {
+        let (mut governor, clock, current) = setup();
+        governor.request(PANE, current.clone(), None);
+        assert_eq!(governor.poll(PANE, &current, false, clock.at(0)), None);
+        assert_eq!(governor.next_due(clock.at(0)), None);
+
+        // Focus moved on before the switch happened
+        governor.request(PANE, PathBuf::from("/a"), Some(clock.at(0)));
+        assert_eq!(governor.poll(PaneId(2), &current, false, clock.at(5000)), None);
+        assert_eq!(governor.next_due(clock.at(5000)), None);
+    }
+
+    #[test]
+    fn test_blocked_while_alternate_screen() {
+        let (mut governor, clock, current) = setup();
+        governor.request(PANE, PathBuf::from("/a"), None);
+        assert_eq!(governor.poll(PANE, &current, true, clock.at(0)), None);
+        assert_eq!(governor.poll(PANE, &current, true, clock.at(60_000)), None);
+        assert_eq!(governor.poll(PANE, &current, false, clock.at(60_100)), Some(PathBuf::from("/a")));
+    }
+
+    #[test]
+    fn test_zero_timing_switches_immediately() {
+        let (_, clock, current) = setup();
+        let mut governor = RootSwitchGovernor::new(Duration::ZERO, Duration::ZERO);
+        for dir in ["/a", "/b"] {
+            governor.request(PANE, PathBuf::from(dir), Some(clock.at(0)));
+            assert_eq!(governor.poll(PANE, &current, false, clock.at(0)), Some(PathBuf::from(dir)));
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Run in All Panes
+//!
+//! Selects which terminals receive a one-off command sent to many panes.
+
+use std::path::PathBuf;
+
+use crate::layout::PaneId;
+
+/// Which panes a command is sent to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunScope {
+    /// Panes of the active workspace
+    CurrentWorkspace,
+    /// Panes of every workspace
+    AllWorkspaces,
+    /// Panes (any workspace) whose directory matches a glob
+    DirectoryGlob,
+}
+
+impl RunScope {
+    pub const ALL: [RunScope; 3] = [
+        RunScope::CurrentWorkspace,
+        RunScope::AllWorkspaces,
+        RunScope::DirectoryGlob,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RunScope::CurrentWorkspace => "Current workspace",
+            RunScope::AllWorkspaces => "All workspaces",
+            RunScope::DirectoryGlob => "Directory glob",
+        }
+    }
+}
+
+/// A pane that could receive the command
+#[derive(Debug, Clone)]
+pub struct RunTarget {
+    pub workspace: usize,
+    pub pane_id: PaneId,
+    /// Terminal ID, `None` for read-only panes (file viewers)
+    pub terminal_id: Option<u64>,
+    /// Terminal working directory or viewed file path
+    pub dir: PathBuf,
+}
+
+impl RunTarget {
+    /// Short description for logs and the skipped list
+    pub fn label(&self) -> String {
+        format!("tab {} pane {} ({})", self.workspace + 1, self.pane_id.0, self.dir.display())
+    }
+}
+
+/// Result of applying a scope to the candidate panes
+#[derive(Debug, Default)]
+pub struct RunSelection<'a> {
+    /// Terminals that will receive the command
+    pub run: Vec<&'a RunTarget>,
+    /// In-scope panes that can't receive input
+    pub skipped: Vec<&'a RunTarget>,
+}
+
+/// Apply a scope to the candidate panes
+///
+/// An invalid glob matches nothing.
+pub fn select_targets<'a>(
+    targets: &'a [RunTarget],
+    scope: RunScope,
+    active_workspace: usize,
+    glob: &str,
+) -> RunSelection<'a> {
+    let pattern = match scope {
+        RunScope::DirectoryGlob => glob_regex(glob),
+        _ => None,
+    };
+
+    let mut selection = RunSelection::default();
+    for target in targets {
+        let in_scope = match scope {
+            RunScope::CurrentWorkspace => target.workspace == active_workspace,
+            RunScope::AllWorkspaces => true,
+            RunScope::DirectoryGlob => pattern
+                .as_ref()
+                .is_some_and(|re| re.is_match(&target.dir.to_string_lossy())),
+        };
+        if !in_scope {
+            continue;
+        }
+
+        if target.terminal_id.is_some() {
+            selection.run.push(target);
+        } else {
+            selection.skipped.push(target);
+        }
+    }
+    selection
+}
+
+/// Compile a shell-style glob into an anchored regex
+///
+/// `*` and `?` stay within one path component, `**` crosses them and a
+/// leading `~` is the home directory.
+pub(crate) fn glob_regex(glob: &str) -> Option<regex::Regex> {
+    let glob = glob.trim();
+    if glob.is_empty() {
+        return None;
+    }
+
+    let expanded = match (glob.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) => format!("{}{}", home.display(), rest),
+        _ => glob.to_string(),
+    };
+
+    let mut pattern = String::from("^");
+    let mut chars = expanded.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                pattern.push_str(".*");
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+
+    regex::Regex::new(&pattern).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn target(workspace: usize, pane: u64, terminal_id: Option<u64>, dir: &str) -> RunTarget {
+        RunTarget {
+            workspace,
+            pane_id: PaneId(pane),
+            terminal_id,
+            dir: PathBuf::from(dir),
+        }
+    }
+
+    fn glob_matches(glob: &str, path: &Path) -> bool {
+        glob_regex(glob).is_some_and(|re| re.is_match(&path.to_string_lossy()))
+    }
+
+    fn targets() -> Vec<RunTarget> {
+        vec![
+            target(0, 0, Some(1), "/srv/api"),
+            target(0, 1, None, "/srv/api/README.md"),
+            target(1, 0, Some(2), "/srv/web"),
+            target(1, 1, Some(3), "/home/me/notes"),
+        ]
+    }
+
+    #[test]
+    fn test_current_workspace_scope() {
+        let targets = targets();
+        let selection = select_targets(&targets, RunScope::CurrentWorkspace, 0, "");
+        assert_eq!(selection.run.len(), 1);
+        assert_eq!(selection.run[0].terminal_id, Some(1));
+        // File viewer is reported, not written to
+        assert_eq!(selection.skipped.len(), 1);
+    }
+
+    #[test]
+    fn test_all_workspaces_scope() {
+        let targets = targets();
+        let selection = select_targets(&targets, RunScope::AllWorkspaces, 0, "");
+        let ids: Vec<_> = selection.run.iter().filter_map(|t| t.terminal_id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(selection.skipped.len(), 1);
+    }
+
+    #[test]
+    fn test_directory_glob_scope() {
+        let targets = targets();
+        let selection = select_targets(&targets, RunScope::DirectoryGlob, 0, "/srv/*");
+        let ids: Vec<_> = selection.run.iter().filter_map(|t| t.terminal_id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert!(selection.skipped.is_empty());
+
+        // Empty glob matches nothing
+        let selection = select_targets(&targets, RunScope::DirectoryGlob, 0, "  ");
+        assert!(selection.run.is_empty());
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("/srv/*", Path::new("/srv/api")));
+        assert!(!glob_matches("/srv/*", Path::new("/srv/api/src")));
+        assert!(glob_matches("/srv/**", Path::new("/srv/api/src")));
+        assert!(glob_matches("**/api", Path::new("/srv/api")));
+        assert!(glob_matches("/srv/ap?", Path::new("/srv/api")));
+        assert!(!glob_matches("/srv/ap?", Path::new("/srv/apis")));
+        // Regex metacharacters are literal
+        assert!(glob_matches("/srv/a.b", Path::new("/srv/a.b")));
+        assert!(!glob_matches("/srv/a.b", Path::new("/srv/axb")));
+    }
+
+    #[test]
+    fn test_glob_home_expansion() {
+        if let Some(home) = dirs::home_dir() {
+            assert!(glob_matches("~/*", &home.join("project")));
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Scrollback Marks
+//!
+//! Named positions in a terminal's output to jump back to. A mark keeps the
+//! absolute index of its line: lines are numbered in the order they
+//! scrolled into the history, so the index stays put while output scrolls.
+//! Once the history is full its oldest lines are dropped; marks on them are
+//! kept as trimmed so the jump list can still show what they were. Reflow
+//! on resize can move the text under a mark by a few lines.
+
+use std::time::SystemTime;
+
+/// One named position
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mark {
+    pub label: String,
+    /// Absolute index of the marked line
+    line: i64,
+    pub at: SystemTime,
+    /// Text of the marked line when it was set
+    pub preview: String,
+    /// The line has dropped out of the history
+    pub trimmed: bool,
+}
+
+/// Marks of one terminal, top to bottom
+#[derive(Debug, Default)]
+pub struct ScrollbackMarks {
+    marks: Vec<Mark>,
+    /// Lines that have scrolled into the history so far
+    scrolled: i64,
+    /// History size at the last sync
+    history: usize,
+}
+
+impl ScrollbackMarks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Account for lines that scrolled into the history since the last sync
+    ///
+    /// `new_lines` is `None` when more scrolled by than the history holds,
+    /// which takes every mark so far with it.
+    pub fn sync(&mut self, new_lines: Option<usize>, history: usize) {
+        match new_lines {
+            Some(lines) => self.scrolled += lines as i64,
+            None => {
+                self.scrolled += history as i64;
+                for mark in &mut self.marks {
+                    mark.trimmed = true;
+                }
+            }
+        }
+        self.history = history;
+
+        // Dropped off the top of a full history, or cleared with it
+        let oldest = self.scrolled - history as i64;
+        for mark in &mut self.marks {
+            mark.trimmed |= mark.line < oldest;
+        }
+    }
+
+    /// Absolute index of grid line `line` (negative lines are history)
+    pub fn position(&self, line: i32) -> i64 {
+        self.scrolled + line as i64
+    }
+
+    /// Add a mark at `line`, an absolute index from `position`
+    pub fn add(&mut self, label: String, line: i64, preview: String, at: SystemTime) {
+        let idx = self.marks.partition_point(|mark| mark.line <= line);
+        self.marks.insert(idx, Mark { label, line, at, preview, trimmed: false });
+    }
+
+    pub fn marks(&self) -> &[Mark] {
+        &self.marks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+
+    /// Grid line of the mark at `idx`, or `None` if it was trimmed
+    pub fn grid_line(&self, idx: usize) -> Option<i32> {
+        let mark = self.marks.get(idx).filter(|mark| !mark.trimmed)?;
+        Some((mark.line - self.scrolled) as i32)
+    }
+
+    /// Grid lines of the marks still in the scrollback
+    pub fn grid_lines(&self) -> impl Iterator<Item = i32> + '_ {
+        (0..self.marks.len()).filter_map(|idx| self.grid_line(idx))
+    }
+
+    /// Nearest mark above grid line `line`
+    pub fn previous(&self, line: i32) -> Option<i32> {
+        self.grid_lines().filter(|&mark| mark < line).last()
+    }
+
+    /// Nearest mark below grid line `line`
+    pub fn next(&self, line: i32) -> Option<i32> {
+        self.grid_lines().find(|&mark| mark > line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marks_at(lines: &[i32]) -> ScrollbackMarks {
+        let mut marks = ScrollbackMarks::new();
+        for &line in lines {
+            marks.add(format!("line {}", line), marks.position(line), String::new(), SystemTime::UNIX_EPOCH);
+        }
+        marks
+    }
+
+    #[test]
+    fn test_marks_follow_scrolling_output() {
+        // 100 lines of history, marks on a history line and a screen line
+        let mut marks = ScrollbackMarks::new();
+        marks.sync(Some(100), 100);
+        marks.add("build".to_string(), marks.position(-10), String::new(), SystemTime::UNIX_EPOCH);
+        marks.add("prompt".to_string(), marks.position(3), String::new(), SystemTime::UNIX_EPOCH);
+        assert_eq!(marks.grid_line(0), Some(-10));
+
+        marks.sync(Some(25), 125);
+        assert_eq!(marks.grid_line(0), Some(-35));
+        assert_eq!(marks.grid_line(1), Some(-22));
+        assert_eq!(marks.marks()[0].label, "build");
+    }
+
+    #[test]
+    fn test_trimmed_history_invalidates_marks() {
+        // A full history of 100 lines keeps its size while lines drop off the top
+        let mut marks = ScrollbackMarks::new();
+        marks.sync(Some(100), 100);
+        marks.add("old".to_string(), marks.position(-95), String::new(), SystemTime::UNIX_EPOCH);
+        marks.add("new".to_string(), marks.position(-20), String::new(), SystemTime::UNIX_EPOCH);
+
+        marks.sync(Some(5), 100);
+        assert_eq!(marks.grid_line(0), Some(-100));
+        assert!(!marks.marks()[0].trimmed);
+
+        marks.sync(Some(1), 100);
+        assert!(marks.marks()[0].trimmed);
+        assert_eq!(marks.grid_line(0), None);
+        assert_eq!(marks.grid_line(1), Some(-26));
+
+        // Trimmed stays trimmed even if the history grows again
+        marks.sync(Some(0), 500);
+        assert!(marks.marks()[0].trimmed);
+
+        // More output than the history holds takes everything
+        marks.sync(None, 500);
+        assert!(marks.marks().iter().all(|mark| mark.trimmed));
+        assert_eq!(marks.grid_lines().count(), 0);
+    }
+
+    #[test]
+    fn test_cleared_history_trims_its_marks() {
+        let mut marks = ScrollbackMarks::new();
+        marks.sync(Some(50), 50);
+        marks.add("history".to_string(), marks.position(-5), String::new(), SystemTime::UNIX_EPOCH);
+        marks.add("screen".to_string(), marks.position(2), String::new(), SystemTime::UNIX_EPOCH);
+        marks.sync(Some(0), 0);
+        assert!(marks.marks()[0].trimmed);
+        assert_eq!(marks.grid_line(1), Some(2));
+    }
+
+    #[test]
+    fn test_previous_and_next() {
+        let marks = marks_at(&[5, -40, -10]);
+        let lines: Vec<i32> = marks.grid_lines().collect();
+        assert_eq!(lines, [-40, -10, 5]);
+        assert_eq!(marks.previous(-10), Some(-40));
+        assert_eq!(marks.previous(0), Some(-10));
+        assert_eq!(marks.previous(-40), None);
+        assert_eq!(marks.next(-40), Some(-10));
+        assert_eq!(marks.next(5), None);
+    }
+}
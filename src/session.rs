@@ -0,0 +1,257 @@
+//! Session Restore
+//!
+//! The open tabs, their split layouts, each pane's directory or file and
+//! tint, and the tabs' output notes as plain text, saved to `~/.config/vibeterm/session.toml` on exit and while running
+//! (with `ui.restore_session`). The next launch rebuilds the same tabs with
+//! new shells; a missing or corrupt file means the usual single tab.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::layout::{LayoutNode, LayoutSpec, PaneId, SplitDirection, SplitSize};
+
+/// A pane tree without its terminals, as saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SerializableLayout {
+    /// A shell started in `dir`
+    Terminal {
+        dir: PathBuf,
+        /// Name shown instead of the directory
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tint: Option<String>,
+    },
+    /// A file viewer
+    File {
+        path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tint: Option<String>,
+    },
+    /// An empty pane (also what panes that can't be saved come back as)
+    Empty {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tint: Option<String>,
+    },
+    Split {
+        direction: SplitDirection,
+        size: SplitSize,
+        first: Box<SerializableLayout>,
+        second: Box<SerializableLayout>,
+    },
+}
+
+impl SerializableLayout {
+    /// Describe `node`, each pane with `leaf`
+    pub fn capture<T>(node: &LayoutNode<T>, leaf: &impl Fn(PaneId, &T) -> SerializableLayout) -> Self {
+        match node {
+            LayoutNode::Leaf { id, content } => leaf(*id, content),
+            LayoutNode::Split { direction, size, first, second } => SerializableLayout::Split {
+                direction: *direction,
+                size: *size,
+                first: Box::new(Self::capture(first, leaf)),
+                second: Box::new(Self::capture(second, leaf)),
+            },
+        }
+    }
+
+    /// Describe a layout description, pane `n` with `pane(n)` (dormant tabs)
+    pub fn from_spec(spec: &LayoutSpec, pane: &impl Fn(usize) -> SerializableLayout) -> Self {
+        match spec {
+            LayoutSpec::Pane(index) => pane(*index),
+            LayoutSpec::Split { direction, size, first, second } => SerializableLayout::Split {
+                direction: *direction,
+                size: *size,
+                first: Box::new(Self::from_spec(first, pane)),
+                second: Box::new(Self::from_spec(second, pane)),
+            },
+        }
+    }
+
+    /// The layout description, with the panes it indexes in pane order
+    /// (the reverse of `from_spec`)
+    pub fn to_spec(&self) -> (LayoutSpec, Vec<SerializableLayout>) {
+        let mut panes = Vec::new();
+        let spec = self.to_spec_into(&mut panes);
+        (spec, panes)
+    }
+
+    fn to_spec_into(&self, panes: &mut Vec<SerializableLayout>) -> LayoutSpec {
+        match self {
+            SerializableLayout::Split { direction, size, first, second } => LayoutSpec::Split {
+                direction: *direction,
+                size: *size,
+                first: Box::new(first.to_spec_into(panes)),
+                second: Box::new(second.to_spec_into(panes)),
+            },
+            pane => {
+                panes.push(pane.clone());
+                LayoutSpec::Pane(panes.len() - 1)
+            }
+        }
+    }
+
+    /// Pane tint as `#RRGGBB`
+    pub fn tint(&self) -> Option<&str> {
+        match self {
+            SerializableLayout::Terminal { tint, .. }
+            | SerializableLayout::File { tint, .. }
+            | SerializableLayout::Empty { tint } => tint.as_deref(),
+            SerializableLayout::Split { .. } => None,
+        }
+    }
+
+    /// Is every pane empty?
+    pub fn is_blank(&self) -> bool {
+        match self {
+            SerializableLayout::Empty { .. } => true,
+            SerializableLayout::Split { first, second, .. } => first.is_blank() && second.is_blank(),
+            _ => false,
+        }
+    }
+}
+
+/// One saved tab
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTab {
+    pub name: String,
+    pub sidebar_root: PathBuf,
+    #[serde(default)]
+    pub pinned: bool,
+    /// Focused pane, counted in pane order
+    #[serde(default)]
+    pub focused: usize,
+    pub layout: SerializableLayout,
+    /// Output notes, as plain text
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// The saved tabs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Index of the active tab
+    pub active: usize,
+    pub tabs: Vec<SessionTab>,
+}
+
+/// Get the session file path
+pub fn session_path() -> PathBuf {
+    Config::config_dir().join("session.toml")
+}
+
+impl Session {
+    /// Load a saved session; `None` if there's none, it's corrupt or it has no tabs
+    pub fn load(path: &Path) -> Option<Self> {
+        let contents = std::fs::read_to_string(path).ok()?;
+        let session: Self = toml::from_str(&contents)
+            .map_err(|e| log::warn!("Ignoring corrupt session file {}: {}", path.display(), e))
+            .ok()?;
+        (!session.tabs.is_empty()).then_some(session)
+    }
+
+    /// Write the session file; a crash mid-write leaves the previous one
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let toml_string = toml::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize session: {}", e))?;
+        crate::config_store::write_atomic(path, &toml_string)
+    }
+}
+
+/// `dir`, or its closest ancestor that still exists
+pub fn existing_dir(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors().find(|dir| dir.is_dir()).map(Path::to_path_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(id: u64, content: &str) -> LayoutNode<String> {
+        LayoutNode::Leaf { id: PaneId(id), content: content.to_string() }
+    }
+
+    #[test]
+    fn test_layouts_survive_a_round_trip() {
+        let node = LayoutNode::Split {
+            direction: SplitDirection::Horizontal,
+            size: SplitSize::Ratio(0.3),
+            first: Box::new(leaf(4, "/src")),
+            second: Box::new(LayoutNode::Split {
+                direction: SplitDirection::Vertical,
+                size: SplitSize::FixedSecond(200.0),
+                first: Box::new(leaf(7, "notes.md")),
+                second: Box::new(leaf(2, "")),
+            }),
+        };
+        let tint = |id: PaneId| (id == PaneId(7)).then(|| "#E06C75".to_string());
+        let layout = SerializableLayout::capture(&node, &|id, content: &String| match content.as_str() {
+            "" => SerializableLayout::Empty { tint: tint(id) },
+            file if file.ends_with(".md") => SerializableLayout::File { path: PathBuf::from(file), tint: tint(id) },
+            dir => SerializableLayout::Terminal { dir: PathBuf::from(dir), name: Some("build".to_string()), tint: tint(id) },
+        });
+        let session = Session {
+            active: 0,
+            tabs: vec![SessionTab {
+                name: "project".to_string(),
+                sidebar_root: PathBuf::from("/src"),
+                pinned: true,
+                focused: 1,
+                layout: layout.clone(),
+                notes: vec!["error[E0308]: mismatched types\n --> src/main.rs:4:5".to_string()],
+            }],
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.toml");
+        session.save(&path).unwrap();
+        assert_eq!(Session::load(&path), Some(session));
+
+        // Dormant tabs keep it as a description and its panes
+        let (spec, panes) = layout.to_spec();
+        assert_eq!(spec.pane_indices(), [0, 1, 2]);
+        assert_eq!(panes[2], SerializableLayout::Empty { tint: None });
+        assert_eq!(panes[1].tint(), Some("#E06C75"));
+        assert_eq!(SerializableLayout::from_spec(&spec, &|pane| panes[pane].clone()), layout);
+    }
+
+    #[test]
+    fn test_unusable_session_files_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.toml");
+        assert_eq!(Session::load(&path), None);
+
+        std::fs::write(&path, "active = 0\n[[tabs]]\nname = 3\n").unwrap();
+        assert_eq!(Session::load(&path), None);
+
+        Session::default().save(&path).unwrap();
+        assert_eq!(Session::load(&path), None);
+    }
+
+    #[test]
+    fn test_older_session_files_still_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.toml");
+        let old = "active = 0\n\n[[tabs]]\nname = \"src\"\nsidebar_root = \"/src\"\n\n[tabs.layout]\nkind = \"terminal\"\ndir = \"/src\"\n";
+        std::fs::write(&path, old).unwrap();
+
+        let session = Session::load(&path).unwrap();
+        assert_eq!(session.tabs[0].layout, SerializableLayout::Terminal { dir: PathBuf::from("/src"), name: None, tint: None });
+        assert!(session.tabs[0].notes.is_empty());
+    }
+
+    #[test]
+    fn test_missing_directories_fall_back_to_a_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(existing_dir(&dir.path().join("gone/deeper")), Some(dir.path().to_path_buf()));
+        assert!(SerializableLayout::Split {
+            direction: SplitDirection::Horizontal,
+            size: SplitSize::default(),
+            first: Box::new(SerializableLayout::Empty { tint: None }),
+            second: Box::new(SerializableLayout::Empty { tint: None }),
+        }.is_blank());
+    }
+}
@@ -0,0 +1,596 @@
+//! Session Logging
+//!
+//! Appends terminal output to per-pane log files. egui_term doesn't expose
+//! the raw PTY stream, so output is read from the terminal grid: lines are
+//! logged as they scroll into the history, and the rest of the screen when
+//! logging stops. Files are written on a background thread so the render
+//! thread never waits on the disk.
+
+use alacritty_terminal::grid::{Dimensions, Grid};
+use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::term::cell::{Cell, Flags};
+use alacritty_terminal::vte::ansi::{Color, NamedColor};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Attributes re-encoded as SGR when ANSI is kept
+const STYLE_FLAGS: Flags = Flags::BOLD
+    .union(Flags::DIM)
+    .union(Flags::ITALIC)
+    .union(Flags::UNDERLINE)
+    .union(Flags::INVERSE)
+    .union(Flags::STRIKEOUT);
+
+/// Lines compared to find the last logged output once the history is full
+const ANCHOR_LINES: usize = 3;
+
+// ============================================================================
+// Grid Capture
+// ============================================================================
+
+/// Tracks which grid lines of one terminal were already logged
+#[derive(Debug, Default)]
+pub struct LineCapture {
+    /// History size at the last capture
+    history_seen: usize,
+    /// Plain text of the newest logged history lines (oldest first)
+    anchor: Vec<String>,
+}
+
+impl LineCapture {
+    /// Start capturing; existing history is not logged
+    pub fn new(grid: &Grid<Cell>) -> Self {
+        let history = grid.history_size();
+        Self {
+            history_seen: history,
+            anchor: history_tail(grid, history),
+        }
+    }
+
+    /// Lines that scrolled into the history since the last call
+    ///
+    /// While the history grows its size tells how many lines are new. Once
+    /// it is full the oldest lines drop off, so the previously logged lines
+    /// are searched for instead; if they're gone a marker is written.
+    pub fn scrolled_lines(&mut self, grid: &Grid<Cell>, strip_ansi: bool) -> String {
+        let Some(new_lines) = self.advance(grid) else {
+            return "[... output skipped ...]\n".to_string();
+        };
+
+        let mut out = String::new();
+        for line in (1..=new_lines).rev() {
+            push_row(&mut out, grid, Line(-(line as i32)), strip_ansi);
+        }
+        out
+    }
+
+    /// Number of rows that scrolled into the history since the last call,
+    /// or `None` if the previously seen rows are gone
+    pub fn advance(&mut self, grid: &Grid<Cell>) -> Option<usize> {
+        let history = grid.history_size();
+
+        let new_lines = if history > self.history_seen {
+            Some(history - self.history_seen)
+        } else if history < self.history_seen {
+            // History cleared (reset, `clear` with scrollback)
+            Some(0)
+        } else {
+            find_anchor(grid, history, &self.anchor)
+        };
+
+        self.history_seen = history;
+        self.anchor = history_tail(grid, history);
+        new_lines
+    }
+}
+
+/// Screen lines down to the cursor, logged when logging stops
+pub fn screen_lines(grid: &Grid<Cell>, strip_ansi: bool) -> String {
+    let mut out = String::new();
+    for line in 0..=grid.cursor.point.line.0 {
+        push_row(&mut out, grid, Line(line), strip_ansi);
+    }
+    out
+}
+
+/// Plain text from `from` down to the cursor (negative lines are history)
+pub fn text_since(grid: &Grid<Cell>, from: Line) -> String {
+    let from = from.max(Line(-(grid.history_size() as i32)));
+    let mut out = String::new();
+    for line in from.0..=grid.cursor.point.line.0 {
+        push_row(&mut out, grid, Line(line), true);
+    }
+    out
+}
+
+/// Size of the plain text of the newest `lines` history lines, with newlines
+pub fn history_bytes(grid: &Grid<Cell>, lines: usize) -> usize {
+    (1..=lines.min(grid.history_size()))
+        .map(|line| row_text(grid, Line(-(line as i32)), true).len() + 1)
+        .sum()
+}
+
+/// Plain text of the newest history lines
+fn history_tail(grid: &Grid<Cell>, history: usize) -> Vec<String> {
+    let count = history.min(ANCHOR_LINES);
+    (1..=count)
+        .rev()
+        .map(|line| row_text(grid, Line(-(line as i32)), true))
+        .collect()
+}
+
+/// How many lines were added below the anchor, if it's still in the history
+///
+/// Older anchor lines may already have dropped off the top of the history;
+/// the ones still present must match.
+fn find_anchor(grid: &Grid<Cell>, history: usize, anchor: &[String]) -> Option<usize> {
+    if anchor.is_empty() {
+        return Some(0);
+    }
+    // `offset` lines were added after the anchor's newest line
+    (0..history).find(|offset| {
+        anchor.iter().rev().enumerate()
+            .take_while(|(i, _)| offset + i < history)
+            .all(|(i, text)| row_text(grid, Line(-((offset + i + 1) as i32)), true) == *text)
+    })
+}
+
+/// Append a row, joining soft-wrapped rows with the next one
+fn push_row(out: &mut String, grid: &Grid<Cell>, line: Line, strip_ansi: bool) {
+    out.push_str(&row_text(grid, line, strip_ansi));
+    let last = &grid[line][grid.last_column()];
+    if !last.flags.contains(Flags::WRAPLINE) {
+        out.push('\n');
+    }
+}
+
+/// Text of one row without trailing blanks, optionally with SGR styling
+pub fn row_text(grid: &Grid<Cell>, line: Line, strip_ansi: bool) -> String {
+    let row = &grid[line];
+    let end = (0..grid.columns())
+        .rev()
+        .find(|&col| row[Column(col)].c != ' ')
+        .map_or(0, |col| col + 1);
+
+    let default_style = (Color::Named(NamedColor::Foreground), Color::Named(NamedColor::Background), Flags::empty());
+    let mut out = String::new();
+    let mut style = default_style;
+    for col in 0..end {
+        let cell = &row[Column(col)];
+        if cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER) {
+            continue;
+        }
+        if !strip_ansi {
+            let cell_style = (cell.fg, cell.bg, cell.flags & STYLE_FLAGS);
+            if style != cell_style {
+                out.push_str(&sgr(cell_style.0, cell_style.1, cell_style.2));
+                style = cell_style;
+            }
+        }
+        out.push(cell.c);
+        // Combining marks and variation selectors (emoji presentation)
+        out.extend(cell.zerowidth().into_iter().flatten());
+    }
+    if style != default_style {
+        out.push_str("\x1b[0m");
+    }
+    out
+}
+
+/// SGR sequence selecting a cell style from scratch
+fn sgr(fg: Color, bg: Color, flags: Flags) -> String {
+    let mut params = vec!["0".to_string()];
+    for (flag, code) in [
+        (Flags::BOLD, "1"),
+        (Flags::DIM, "2"),
+        (Flags::ITALIC, "3"),
+        (Flags::UNDERLINE, "4"),
+        (Flags::INVERSE, "7"),
+        (Flags::STRIKEOUT, "9"),
+    ] {
+        if flags.contains(flag) {
+            params.push(code.to_string());
+        }
+    }
+    params.extend(color_param(fg, 30));
+    params.extend(color_param(bg, 40));
+    format!("\x1b[{}m", params.join(";"))
+}
+
+/// SGR parameter for a color (`base` is 30 for foreground, 40 for background)
+fn color_param(color: Color, base: u8) -> Option<String> {
+    match color {
+        Color::Named(named) => {
+            let index = named as usize;
+            match index {
+                0..=7 => Some((base as usize + index).to_string()),
+                8..=15 => Some((base as usize + 60 + index - 8).to_string()),
+                _ => {
+                    // Dim variants map to their normal color
+                    let dim = NamedColor::DimBlack as usize;
+                    (dim..dim + 8)
+                        .contains(&index)
+                        .then(|| (base as usize + index - dim).to_string())
+                }
+            }
+        }
+        Color::Indexed(index) => Some(format!("{};5;{}", base + 8, index)),
+        Color::Spec(rgb) => Some(format!("{};2;{};{};{}", base + 8, rgb.r, rgb.g, rgb.b)),
+    }
+}
+
+// ============================================================================
+// File Writer
+// ============================================================================
+
+enum LogCommand {
+    Open { terminal_id: u64, directory: PathBuf, name: String },
+    Write { terminal_id: u64, text: String },
+    Close { terminal_id: u64 },
+}
+
+/// An open log file
+struct OpenLog {
+    directory: PathBuf,
+    /// File name without the date prefix
+    name: String,
+    date: String,
+    file: File,
+}
+
+impl OpenLog {
+    fn open(directory: PathBuf, name: String) -> Result<Self, String> {
+        std::fs::create_dir_all(&directory)
+            .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+        let date = local_date();
+        let file = open_append(&directory.join(log_file_name(&date, &name)))?;
+        Ok(Self { directory, name, date, file })
+    }
+
+    /// Append text, starting a new file when the date changes
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        let today = local_date();
+        if today != self.date {
+            self.file = open_append(&self.directory.join(log_file_name(&today, &self.name)))?;
+            self.date = today;
+        }
+        self.file
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("Failed to write session log: {}", e))
+    }
+}
+
+fn open_append(path: &Path) -> Result<File, String> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+/// Writes session logs on a background thread
+pub struct SessionLogWriter {
+    tx: Sender<LogCommand>,
+    /// Terminals whose log failed, with the error
+    errors: Receiver<(u64, String)>,
+    /// Disconnects when the writer thread exits
+    done: Receiver<()>,
+}
+
+impl SessionLogWriter {
+    pub fn spawn() -> Self {
+        let (tx, rx) = mpsc::channel::<LogCommand>();
+        let (error_tx, errors) = mpsc::channel();
+        let (done_tx, done) = mpsc::channel::<()>();
+
+        let spawned = std::thread::Builder::new()
+            .name("session-log".to_string())
+            .spawn(move || {
+                let _done = done_tx;
+                let mut logs: HashMap<u64, OpenLog> = HashMap::new();
+                for command in rx {
+                    let (terminal_id, result) = match command {
+                        LogCommand::Open { terminal_id, directory, name } => {
+                            (terminal_id, OpenLog::open(directory, name).map(|log| {
+                                logs.insert(terminal_id, log);
+                            }))
+                        }
+                        LogCommand::Write { terminal_id, text } => match logs.get_mut(&terminal_id) {
+                            Some(log) => (terminal_id, log.write(&text)),
+                            None => continue,
+                        },
+                        LogCommand::Close { terminal_id } => {
+                            logs.remove(&terminal_id);
+                            continue;
+                        }
+                    };
+                    if let Err(e) = result {
+                        // Stop this terminal's log; the app turns it off
+                        logs.remove(&terminal_id);
+                        let _ = error_tx.send((terminal_id, e));
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            log::warn!("Failed to start session log writer: {}", e);
+        }
+
+        Self { tx, errors, done }
+    }
+
+    /// Stop the writer once queued writes are done, waiting at most `timeout`
+    ///
+    /// Returns whether everything was written in time.
+    pub fn finish(self, timeout: std::time::Duration) -> bool {
+        drop(self.tx);
+        matches!(self.done.recv_timeout(timeout), Err(mpsc::RecvTimeoutError::Disconnected))
+    }
+
+    /// Start logging a terminal to `<date>-<name>.log` in `directory`
+    pub fn open(&self, terminal_id: u64, directory: PathBuf, name: String) {
+        let _ = self.tx.send(LogCommand::Open { terminal_id, directory, name });
+    }
+
+    pub fn write(&self, terminal_id: u64, text: String) {
+        if !text.is_empty() {
+            let _ = self.tx.send(LogCommand::Write { terminal_id, text });
+        }
+    }
+
+    pub fn close(&self, terminal_id: u64) {
+        let _ = self.tx.send(LogCommand::Close { terminal_id });
+    }
+
+    /// Logs that failed since the last call (terminal ID, error)
+    pub fn poll_errors(&self) -> Vec<(u64, String)> {
+        self.errors.try_iter().collect()
+    }
+}
+
+/// Log name for a pane (without the date prefix)
+pub fn log_name(workspace: &str, pane: u64) -> String {
+    let workspace: String = workspace
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}-{}", workspace, pane)
+}
+
+fn log_file_name(date: &str, name: &str) -> String {
+    format!("{}-{}.log", date, name)
+}
+
+/// Expand a leading `~` to the home directory
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Today's local date as `YYYY-MM-DD`
+pub fn local_date() -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    format_date(now + utc_offset_secs(now))
+}
+
+/// Minutes since local midnight
+pub fn local_minutes() -> u32 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    ((now + utc_offset_secs(now)).rem_euclid(86_400) / 60) as u32
+}
+
+#[cfg(unix)]
+pub fn utc_offset_secs(now: i64) -> i64 {
+    let time = now as libc::time_t;
+    // SAFETY: localtime_r only writes to the tm we pass in
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            0
+        } else {
+            tm.tm_gmtoff as i64
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn utc_offset_secs(_now: i64) -> i64 {
+    0
+}
+
+/// `YYYY-MM-DD` for seconds since the Unix epoch
+pub fn format_date(secs: i64) -> String {
+    // Civil-from-days (Howard Hinnant)
+    let days = secs.div_euclid(86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alacritty_terminal::vte::ansi::Rgb;
+
+    const COLUMNS: usize = 8;
+
+    fn grid(lines: usize, max_history: usize) -> Grid<Cell> {
+        Grid::new(lines, COLUMNS, max_history)
+    }
+
+    fn write_line(grid: &mut Grid<Cell>, line: i32, text: &str) {
+        for (col, c) in text.chars().enumerate() {
+            grid[Line(line)][Column(col)].c = c;
+        }
+    }
+
+    /// Print a line at the bottom of a full screen, scrolling the top line into history
+    fn print(grid: &mut Grid<Cell>, text: &str) {
+        let lines = grid.screen_lines() as i32;
+        grid.scroll_up(&(Line(0)..Line(lines)), 1);
+        write_line(grid, lines - 1, text);
+    }
+
+    #[test]
+    fn test_scrolled_lines_are_logged_once() {
+        let mut grid = grid(2, 100);
+        write_line(&mut grid, 0, "one");
+        write_line(&mut grid, 1, "two");
+        let mut capture = LineCapture::new(&grid);
+
+        print(&mut grid, "three");
+        print(&mut grid, "four");
+        assert_eq!(capture.scrolled_lines(&grid, true), "one\ntwo\n");
+        assert_eq!(capture.scrolled_lines(&grid, true), "");
+
+        print(&mut grid, "five");
+        assert_eq!(capture.scrolled_lines(&grid, true), "three\n");
+    }
+
+    #[test]
+    fn test_full_history_finds_last_logged_lines() {
+        let mut grid = grid(1, 4);
+        let mut capture = LineCapture::new(&grid);
+        for text in ["a", "b", "c", "d", "e"] {
+            print(&mut grid, text);
+        }
+        // History is full (a-d); the blank first line dropped off
+        assert_eq!(capture.scrolled_lines(&grid, true), "a\nb\nc\nd\n");
+
+        // Size stays 4 while lines keep arriving
+        print(&mut grid, "f");
+        print(&mut grid, "g");
+        assert_eq!(capture.scrolled_lines(&grid, true), "e\nf\n");
+
+        // Everything logged scrolled away before the next capture
+        for text in ["h", "i", "j", "k", "l"] {
+            print(&mut grid, text);
+        }
+        assert_eq!(capture.scrolled_lines(&grid, true), "[... output skipped ...]\n");
+        print(&mut grid, "m");
+        assert_eq!(capture.scrolled_lines(&grid, true), "l\n");
+    }
+
+    #[test]
+    fn test_screen_lines_stop_at_cursor() {
+        let mut grid = grid(3, 10);
+        write_line(&mut grid, 0, "$ ls");
+        write_line(&mut grid, 1, "src");
+        grid.cursor.point.line = Line(1);
+        assert_eq!(screen_lines(&grid, true), "$ ls\nsrc\n");
+    }
+
+    #[test]
+    fn test_wrapped_rows_are_joined() {
+        let mut grid = grid(2, 10);
+        write_line(&mut grid, 0, "abcdefgh");
+        grid[Line(0)][Column(COLUMNS - 1)].flags.insert(Flags::WRAPLINE);
+        write_line(&mut grid, 1, "ij");
+        grid.cursor.point.line = Line(1);
+        assert_eq!(screen_lines(&grid, true), "abcdefghij\n");
+    }
+
+    #[test]
+    fn test_zero_width_characters_are_kept() {
+        let mut grid = grid(1, 10);
+        write_line(&mut grid, 0, "\u{2764} e");
+        grid[Line(0)][Column(0)].push_zerowidth('\u{FE0F}');
+        grid[Line(0)][Column(2)].push_zerowidth('\u{301}');
+        assert_eq!(screen_lines(&grid, true), "\u{2764}\u{FE0F} e\u{301}\n");
+    }
+
+    #[test]
+    fn test_ansi_styles_are_reencoded() {
+        let mut grid = grid(1, 10);
+        write_line(&mut grid, 0, "ok go");
+        for col in 0..2 {
+            let cell = &mut grid[Line(0)][Column(col)];
+            cell.fg = Color::Named(NamedColor::Green);
+            cell.flags.insert(Flags::BOLD);
+        }
+        grid[Line(0)][Column(3)].fg = Color::Spec(Rgb { r: 1, g: 2, b: 3 });
+
+        assert_eq!(screen_lines(&grid, true), "ok go\n");
+        assert_eq!(
+            screen_lines(&grid, false),
+            "\x1b[0;1;32mok\x1b[0m \x1b[0;38;2;1;2;3mg\x1b[0mo\n"
+        );
+    }
+
+    #[test]
+    fn test_color_params() {
+        assert_eq!(color_param(Color::Named(NamedColor::Red), 30).as_deref(), Some("31"));
+        assert_eq!(color_param(Color::Named(NamedColor::BrightBlue), 40).as_deref(), Some("104"));
+        assert_eq!(color_param(Color::Named(NamedColor::DimCyan), 30).as_deref(), Some("36"));
+        assert_eq!(color_param(Color::Named(NamedColor::Foreground), 30), None);
+        assert_eq!(color_param(Color::Indexed(208), 30).as_deref(), Some("38;5;208"));
+    }
+
+    #[test]
+    fn test_log_file_names() {
+        assert_eq!(log_name("shell-2", 3), "shell-2-3");
+        assert_eq!(log_name("my project/api", 0), "my_project_api-0");
+        assert_eq!(log_file_name("2026-10-17", "shell-0"), "2026-10-17-shell-0.log");
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_791_244_799), "2026-10-05");
+        assert_eq!(format_date(-1), "1969-12-31");
+    }
+
+    #[test]
+    fn test_writer_appends_and_reports_errors() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let writer = SessionLogWriter::spawn();
+        writer.open(1, dir.path().to_path_buf(), "shell-0".to_string());
+        writer.write(1, "hello\n".to_string());
+        writer.close(1);
+
+        // A file where the directory should be makes opening fail
+        let blocked = dir.path().join("blocked");
+        std::fs::write(&blocked, "").unwrap();
+        writer.open(2, blocked, "shell-1".to_string());
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let mut errors = Vec::new();
+        while errors.is_empty() && std::time::Instant::now() < deadline {
+            errors = writer.poll_errors();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 2);
+
+        let path = dir.path().join(log_file_name(&local_date(), "shell-0"));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "hello\n");
+    }
+
+    #[test]
+    fn test_finish_writes_queued_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let writer = SessionLogWriter::spawn();
+        writer.open(1, dir.path().to_path_buf(), "shell-0".to_string());
+        writer.write(1, "last words\n".to_string());
+        assert!(writer.finish(std::time::Duration::from_secs(5)));
+
+        let path = dir.path().join(log_file_name(&local_date(), "shell-0"));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "last words\n");
+    }
+}
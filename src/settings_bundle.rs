@@ -0,0 +1,413 @@
+//! Settings Bundle Export and Import
+//!
+//! A bundle is a folder holding copies of the files in the config directory
+//! and the recently opened projects, plus a `manifest.toml` naming them and
+//! the VibeTerm version that wrote it. Importing backs up the files it
+//! replaces first, and rewrites paths under the exporting machine's home
+//! directory to this one's.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::tree_state::TreeState;
+
+/// Manifest file inside a bundle
+pub const MANIFEST_FILE: &str = "manifest.toml";
+
+/// Bundle layout version written by this build
+const FORMAT_VERSION: u32 = 1;
+
+/// Folder in the config directory holding pre-import backups
+const BACKUP_DIR: &str = "backups";
+
+/// A part of the configuration that can be exported on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Component {
+    /// Settings, theme and keyboard-related options
+    Config,
+    Snippets,
+    /// Projects whose layout commands were allowed to run
+    TrustedProjects,
+    /// Recently opened projects with their sidebar state (from the tree
+    /// state directory, gathered into one file)
+    RecentProjects,
+}
+
+impl Component {
+    pub const ALL: [Component; 4] = [
+        Component::Config,
+        Component::Snippets,
+        Component::TrustedProjects,
+        Component::RecentProjects,
+    ];
+
+    /// File name in the config directory and in the bundle
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Component::Config => "config.toml",
+            Component::Snippets => "snippets.toml",
+            Component::TrustedProjects => "project_consent.toml",
+            Component::RecentProjects => "recent_projects.toml",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Component::Config => "Settings and theme",
+            Component::Snippets => "Snippets",
+            Component::TrustedProjects => "Trusted project layouts",
+            Component::RecentProjects => "Recent projects",
+        }
+    }
+}
+
+/// Contents of `recent_projects.toml`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct RecentProjects {
+    /// Most recently visited first
+    project: Vec<TreeState>,
+}
+
+/// Contents of `manifest.toml`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    pub app_version: String,
+    /// Unix timestamp (seconds) of the export
+    pub created: u64,
+    /// Home directory of the exporting machine
+    #[serde(default)]
+    pub home: Option<String>,
+    pub components: Vec<Component>,
+}
+
+/// What importing one component would do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportItem {
+    pub component: Component,
+    /// A file of this component exists and would be replaced (for recent
+    /// projects: some are known, and the bundle's replace those)
+    pub overwrites: bool,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// `~` at the start of a typed path means the home directory
+pub fn expand_home(path: &str, home: &Path) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => home.join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Copy the config files in `config_dir`, and the projects saved in
+/// `tree_state_dir`, into a new bundle at `dest`
+pub fn export(config_dir: &Path, tree_state_dir: &Path, dest: &Path, home: &Path) -> Result<Manifest, String> {
+    if dest.read_dir().is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(format!("{} already exists and isn't empty", dest.display()));
+    }
+    std::fs::create_dir_all(dest).map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+
+    let mut components = Vec::new();
+    for component in Component::ALL {
+        if component == Component::RecentProjects {
+            let project = crate::tree_state::saved_states(tree_state_dir);
+            if project.is_empty() {
+                continue;
+            }
+            let text = toml::to_string_pretty(&RecentProjects { project })
+                .map_err(|e| format!("Failed to write recent projects: {}", e))?;
+            std::fs::write(dest.join(component.file_name()), text)
+                .map_err(|e| format!("Failed to write recent projects: {}", e))?;
+            components.push(component);
+            continue;
+        }
+        let source = config_dir.join(component.file_name());
+        if !source.is_file() {
+            continue;
+        }
+        std::fs::copy(&source, dest.join(component.file_name()))
+            .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+        components.push(component);
+    }
+    if components.is_empty() {
+        return Err("No settings files to export yet".to_string());
+    }
+
+    let manifest = Manifest {
+        format: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created: now_secs(),
+        home: Some(home.to_string_lossy().to_string()),
+        components,
+    };
+    let text = toml::to_string_pretty(&manifest).map_err(|e| format!("Failed to write manifest: {}", e))?;
+    std::fs::write(dest.join(MANIFEST_FILE), text).map_err(|e| format!("Failed to write manifest: {}", e))?;
+    Ok(manifest)
+}
+
+/// Read a bundle's manifest and check that it can be imported
+pub fn read_manifest(bundle: &Path) -> Result<Manifest, String> {
+    let path = bundle.join(MANIFEST_FILE);
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Not a settings bundle ({}: {})", path.display(), e))?;
+    let manifest: Manifest = toml::from_str(&text).map_err(|e| format!("Invalid manifest: {}", e))?;
+
+    if manifest.format > FORMAT_VERSION {
+        return Err(format!(
+            "Bundle was written by VibeTerm {} in a newer format; update VibeTerm to import it",
+            manifest.app_version
+        ));
+    }
+    if manifest.components.is_empty() {
+        return Err("Bundle contains no settings".to_string());
+    }
+    for component in &manifest.components {
+        let file = bundle.join(component.file_name());
+        let text = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Bundle is missing {} ({})", component.file_name(), e))?;
+        toml::from_str::<toml::Value>(&text)
+            .map_err(|e| format!("{} in the bundle is invalid: {}", component.file_name(), e))?;
+    }
+    Ok(manifest)
+}
+
+/// Components in `manifest` and whether each would replace a file
+pub fn import_plan(manifest: &Manifest, config_dir: &Path, tree_state_dir: &Path) -> Vec<ImportItem> {
+    manifest.components
+        .iter()
+        .map(|&component| ImportItem {
+            component,
+            overwrites: match component {
+                Component::RecentProjects => !crate::tree_state::saved_states(tree_state_dir).is_empty(),
+                _ => config_dir.join(component.file_name()).exists(),
+            },
+        })
+        .collect()
+}
+
+/// Replace `old_home` with `new_home` where it's a whole path prefix
+fn rewrite_home(text: &str, old_home: &str, new_home: &str) -> String {
+    let old_home = old_home.trim_end_matches('/');
+    if old_home.is_empty() || old_home == new_home.trim_end_matches('/') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find(old_home) {
+        let after = &rest[index + old_home.len()..];
+        out.push_str(&rest[..index]);
+        // Only a full path component, so /home/al doesn't match /home/alice
+        if after.is_empty() || after.starts_with(['/', '"', '\'']) {
+            out.push_str(new_home.trim_end_matches('/'));
+        } else {
+            out.push_str(old_home);
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Copy the `selected` components from `bundle` into `config_dir` (recent
+/// projects into `tree_state_dir`)
+///
+/// Files about to be replaced are first copied to a timestamped folder,
+/// which is returned.
+pub fn apply_import(
+    bundle: &Path,
+    manifest: &Manifest,
+    config_dir: &Path,
+    tree_state_dir: &Path,
+    selected: &[Component],
+    home: &Path,
+) -> Result<PathBuf, String> {
+    let backup = config_dir.join(BACKUP_DIR).join(format!("backup-{}", now_secs()));
+    std::fs::create_dir_all(&backup).map_err(|e| format!("Failed to create {}: {}", backup.display(), e))?;
+
+    let components: Vec<Component> = manifest.components
+        .iter()
+        .copied()
+        .filter(|component| selected.contains(component))
+        .collect();
+
+    let new_home = home.to_string_lossy();
+    let read = |component: &Component| {
+        let source = bundle.join(component.file_name());
+        let text = std::fs::read_to_string(&source)
+            .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+        Ok::<_, String>(match &manifest.home {
+            Some(old_home) => rewrite_home(&text, old_home, &new_home),
+            None => text,
+        })
+    };
+    let projects = if components.contains(&Component::RecentProjects) {
+        toml::from_str::<RecentProjects>(&read(&Component::RecentProjects)?)
+            .map_err(|e| format!("Invalid recent projects: {}", e))?
+            .project
+    } else {
+        Vec::new()
+    };
+
+    // Back up everything before replacing anything
+    for component in &components {
+        if *component == Component::RecentProjects {
+            for state in &projects {
+                let current = crate::tree_state::state_path(tree_state_dir, &state.root);
+                if current.is_file() {
+                    let dir = backup.join("tree_state");
+                    std::fs::create_dir_all(&dir)
+                        .and_then(|()| std::fs::copy(&current, dir.join(current.file_name().unwrap_or_default())))
+                        .map_err(|e| format!("Failed to back up {}: {}", current.display(), e))?;
+                }
+            }
+            continue;
+        }
+        let current = config_dir.join(component.file_name());
+        if current.is_file() {
+            std::fs::copy(&current, backup.join(component.file_name()))
+                .map_err(|e| format!("Failed to back up {}: {}", current.display(), e))?;
+        }
+    }
+
+    for component in &components {
+        if *component == Component::RecentProjects {
+            for state in &projects {
+                state.save(tree_state_dir)?;
+            }
+            continue;
+        }
+        std::fs::write(config_dir.join(component.file_name()), read(component)?)
+            .map_err(|e| format!("Failed to write {}: {}", component.file_name(), e))?;
+    }
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, name: &str, text: &str) {
+        std::fs::write(dir.join(name), text).unwrap();
+    }
+
+    #[test]
+    fn test_export_then_validate() {
+        let temp = TempDir::new().unwrap();
+        let config_dir = temp.path().join("config");
+        std::fs::create_dir(&config_dir).unwrap();
+        write(&config_dir, "config.toml", "[ui]\nshow_sidebar = false\n");
+        write(&config_dir, "snippets.toml", "");
+
+        let tree_state_dir = temp.path().join("tree_state");
+        let project = |root: &str, last_visited| TreeState { root: PathBuf::from(root), last_visited, ..Default::default() };
+        project("/home/old/api", 10).save(&tree_state_dir).unwrap();
+        project("/home/old/web", 20).save(&tree_state_dir).unwrap();
+
+        let bundle = temp.path().join("bundle");
+        let manifest = export(&config_dir, &tree_state_dir, &bundle, Path::new("/home/old")).unwrap();
+        assert_eq!(manifest.components, [Component::Config, Component::Snippets, Component::RecentProjects]);
+        assert_eq!(read_manifest(&bundle).unwrap(), manifest);
+        let recent: RecentProjects = toml::from_str(&std::fs::read_to_string(bundle.join("recent_projects.toml")).unwrap()).unwrap();
+        let roots: Vec<_> = recent.project.iter().map(|state| state.root.to_str().unwrap()).collect();
+        assert_eq!(roots, ["/home/old/web", "/home/old/api"]);
+
+        // Exporting over a non-empty folder is refused
+        assert!(export(&config_dir, &tree_state_dir, &bundle, Path::new("/home/old")).is_err());
+    }
+
+    #[test]
+    fn test_invalid_manifests_are_rejected() {
+        let temp = TempDir::new().unwrap();
+        let bundle = temp.path();
+        assert!(read_manifest(bundle).unwrap_err().starts_with("Not a settings bundle"));
+
+        write(bundle, MANIFEST_FILE, "format = 99\napp_version = \"9.0.0\"\ncreated = 0\ncomponents = [\"config\"]\n");
+        assert!(read_manifest(bundle).unwrap_err().contains("newer format"));
+
+        write(bundle, MANIFEST_FILE, "format = 1\napp_version = \"0.7.0\"\ncreated = 0\ncomponents = [\"config\"]\n");
+        assert!(read_manifest(bundle).unwrap_err().contains("missing config.toml"));
+
+        write(bundle, "config.toml", "not = [valid");
+        assert!(read_manifest(bundle).unwrap_err().contains("invalid"));
+
+        write(bundle, MANIFEST_FILE, "format = 1\napp_version = \"0.7.0\"\ncreated = 0\ncomponents = [\"keybindings\"]\n");
+        assert!(read_manifest(bundle).unwrap_err().starts_with("Invalid manifest"));
+    }
+
+    #[test]
+    fn test_selective_apply_backs_up_and_rewrites_home() {
+        let temp = TempDir::new().unwrap();
+        let bundle = temp.path().join("bundle");
+        std::fs::create_dir(&bundle).unwrap();
+        write(&bundle, "config.toml", "[terminal.session_logging]\ndirectory = \"/home/old/logs\"\n");
+        write(&bundle, "snippets.toml", "# bundled\n");
+        let manifest = Manifest {
+            format: 1,
+            app_version: "0.7.0".to_string(),
+            created: 0,
+            home: Some("/home/old".to_string()),
+            components: vec![Component::Config, Component::Snippets],
+        };
+
+        let config_dir = temp.path().join("config");
+        std::fs::create_dir(&config_dir).unwrap();
+        write(&config_dir, "config.toml", "# mine\n");
+        write(&config_dir, "snippets.toml", "# my snippets\n");
+
+        let tree_state_dir = temp.path().join("tree_state");
+        let plan = import_plan(&manifest, &config_dir, &tree_state_dir);
+        assert!(plan.iter().all(|item| item.overwrites));
+
+        let backup = apply_import(&bundle, &manifest, &config_dir, &tree_state_dir, &[Component::Config], Path::new("/Users/new")).unwrap();
+        let config = std::fs::read_to_string(config_dir.join("config.toml")).unwrap();
+        assert!(config.contains("\"/Users/new/logs\""));
+        assert_eq!(std::fs::read_to_string(backup.join("config.toml")).unwrap(), "# mine\n");
+
+        // Unselected components are left alone and not backed up
+        assert_eq!(std::fs::read_to_string(config_dir.join("snippets.toml")).unwrap(), "# my snippets\n");
+        assert!(!backup.join("snippets.toml").exists());
+    }
+
+    #[test]
+    fn test_recent_projects_import_under_the_new_home() {
+        let temp = TempDir::new().unwrap();
+        let bundle = temp.path().join("bundle");
+        std::fs::create_dir(&bundle).unwrap();
+        write(&bundle, "recent_projects.toml", "[[project]]\nroot = \"/home/old/api\"\nexpanded = [\"src\"]\nlast_visited = 5\n");
+        let manifest = Manifest {
+            format: 1,
+            app_version: "0.7.0".to_string(),
+            created: 0,
+            home: Some("/home/old".to_string()),
+            components: vec![Component::RecentProjects],
+        };
+        let config_dir = temp.path().join("config");
+        let tree_state_dir = temp.path().join("tree_state");
+        let mine = TreeState { root: PathBuf::from("/Users/new/api"), last_visited: 9, ..Default::default() };
+        mine.save(&tree_state_dir).unwrap();
+        assert!(import_plan(&manifest, &config_dir, &tree_state_dir)[0].overwrites);
+
+        let backup = apply_import(&bundle, &manifest, &config_dir, &tree_state_dir, &[Component::RecentProjects], Path::new("/Users/new")).unwrap();
+        let imported = TreeState::load(&tree_state_dir, Path::new("/Users/new/api")).unwrap();
+        assert_eq!(imported.expanded, [PathBuf::from("src")]);
+        assert_eq!(crate::tree_state::saved_states(&backup.join("tree_state")), [mine]);
+    }
+
+    #[test]
+    fn test_rewrite_home_only_matches_whole_components() {
+        assert_eq!(rewrite_home("\"/home/al/x\" /home/alice /home/al", "/home/al", "/u/b"), "\"/u/b/x\" /home/alice /u/b");
+        assert_eq!(rewrite_home("/home/al/x", "/home/al", "/home/al"), "/home/al/x");
+        assert_eq!(expand_home("~/bundle", Path::new("/home/me")), PathBuf::from("/home/me/bundle"));
+        assert_eq!(expand_home("~other", Path::new("/home/me")), PathBuf::from("~other"));
+    }
+}
@@ -0,0 +1,373 @@
+//! Login Shell Environment
+//!
+//! Apps launched from Finder or Spotlight get launchd's bare environment,
+//! so shells and tools started from VibeTerm miss PATH entries set up in
+//! the user's rc files. At startup the login shell is run once
+//! (`$SHELL -ilc 'env -0'`) and a few of its variables are copied into
+//! this process before any other thread starts. The result is cached
+//! until a shell rc file changes.
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{AdvancedConfig, Config};
+
+/// How long the login shell gets before it's killed
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Variables always imported
+const BASE_VARS: &[&str] = &["PATH", "LANG", "EDITOR", "SSH_AUTH_SOCK"];
+
+/// Printed before the environment, so rc file output can be skipped
+const MARKER: &str = "__VIBETERM_ENV__";
+
+/// Shell startup files, relative to the home directory
+const RC_FILES: &[&str] = &[
+    ".zshenv",
+    ".zprofile",
+    ".zshrc",
+    ".zlogin",
+    ".bash_profile",
+    ".bash_login",
+    ".bashrc",
+    ".profile",
+    ".config/fish/config.fish",
+];
+
+/// Variables from an earlier capture, valid while `key` matches
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct EnvCache {
+    key: String,
+    vars: BTreeMap<String, String>,
+}
+
+/// Import the login shell's variables, if enabled
+///
+/// Blocks until the shell printed them (up to its timeout) unless a cached
+/// capture is still valid. Call before any other thread starts: setting
+/// variables races with threads reading the environment.
+pub fn import(config: &AdvancedConfig) {
+    if !config.import_login_shell_env {
+        return;
+    }
+    let shell = login_shell();
+    let Some(home) = dirs::home_dir() else {
+        return;
+    };
+    let names = import_names(&config.login_shell_env_vars);
+    let key = cache_key(&shell, &home, &names);
+
+    if let Some(cache) = load_cache().filter(|cache| cache.key == key) {
+        log::info!("Using cached login shell environment");
+        apply(&cache.vars, &names);
+        return;
+    }
+
+    let captured = match capture(&shell, CAPTURE_TIMEOUT) {
+        Ok(captured) => captured,
+        Err(e) => {
+            log::warn!("Login shell environment not imported: {}", e);
+            return;
+        }
+    };
+    let vars = select(&captured, &names);
+    apply(&vars, &names);
+    if let Err(e) = save_cache(&EnvCache { key, vars }) {
+        log::warn!("{}", e);
+    }
+}
+
+/// The user's login shell
+fn login_shell() -> PathBuf {
+    std::env::var_os("SHELL")
+        .filter(|shell| !shell.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(if cfg!(target_os = "macos") { "/bin/zsh" } else { "/bin/sh" }))
+}
+
+/// Names to import: the base set plus the configured extras
+fn import_names(extra: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = BASE_VARS.iter().map(|name| name.to_string()).collect();
+    for name in extra {
+        let name = name.trim();
+        if !name.is_empty() && !names.iter().any(|known| known == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Run the login shell and read its environment, giving up after `timeout`
+///
+/// Background processes started by rc files (agents, `cmd &`) can keep the
+/// output pipe open after the shell exits; whatever arrived by the deadline
+/// is used and the reader thread is left behind.
+fn capture(shell: &Path, timeout: Duration) -> Result<BTreeMap<String, String>, String> {
+    let mut command = Command::new(shell);
+    command
+        .arg("-ilc")
+        .arg(format!("echo {}; env -0", MARKER))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    // Its own process group, so a timeout takes down what it started too
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", shell.display(), e))?;
+
+    // Read on a thread of its own so a chatty rc file can't fill the pipe
+    let mut stdout = child.stdout.take().ok_or("No shell output")?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = [0; 8192];
+        loop {
+            match stdout.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if tx.send(buf[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+    });
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+            Ok(None) => {
+                kill(&mut child);
+                return Err(format!("{} took longer than {:?}", shell.display(), timeout));
+            }
+            Err(e) => {
+                kill(&mut child);
+                return Err(format!("Failed to wait for {}: {}", shell.display(), e));
+            }
+        }
+    }
+
+    let mut output = Vec::new();
+    loop {
+        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(chunk) => output.extend(chunk),
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {
+                log::warn!("{} left its output open; using what it printed", shell.display());
+                break;
+            }
+        }
+    }
+    let vars = parse_env(&output);
+    if vars.is_empty() {
+        return Err(format!("{} printed no environment", shell.display()));
+    }
+    Ok(vars)
+}
+
+/// Kill the shell and everything in its process group
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: signals the group `process_group(0)` gave the shell
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Parse `env -0` output, skipping anything printed before the marker
+fn parse_env(output: &[u8]) -> BTreeMap<String, String> {
+    let marker = format!("{}\n", MARKER);
+    let start = output
+        .windows(marker.len())
+        .position(|window| window == marker.as_bytes())
+        .map_or(0, |at| at + marker.len());
+
+    output[start..]
+        .split(|byte| *byte == 0)
+        .filter_map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            let (name, value) = entry.split_once('=')?;
+            (!name.is_empty() && !name.contains(char::is_whitespace))
+                .then(|| (name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Variables to import from a capture
+///
+/// Only `names` are taken, and only with a value: an empty PATH from a
+/// broken rc file must not replace a working one.
+fn select(captured: &BTreeMap<String, String>, names: &[String]) -> BTreeMap<String, String> {
+    names
+        .iter()
+        .filter_map(|name| {
+            let value = captured.get(name).filter(|value| !value.is_empty())?;
+            Some((name.clone(), value.clone()))
+        })
+        .collect()
+}
+
+/// Changes to make to an environment read through `current`
+fn changes(
+    vars: &BTreeMap<String, String>,
+    names: &[String],
+    current: impl Fn(&str) -> Option<String>,
+) -> Vec<(String, String)> {
+    vars.iter()
+        .filter(|(name, _)| names.contains(name))
+        .filter(|(name, value)| current(name).as_ref() != Some(*value))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+/// Set the imported variables in this process
+fn apply(vars: &BTreeMap<String, String>, names: &[String]) {
+    let changes = changes(vars, names, |name| std::env::var(name).ok());
+    for (name, value) in &changes {
+        std::env::set_var(name, value);
+    }
+    if !changes.is_empty() {
+        let names: Vec<_> = changes.iter().map(|(name, _)| name.as_str()).collect();
+        log::info!("Imported {} from the login shell", names.join(", "));
+    }
+}
+
+/// Cache key: the shell, the imported names and the rc files' mtimes
+fn cache_key(shell: &Path, home: &Path, names: &[String]) -> String {
+    let mut key = format!("{}|{}", shell.display(), names.join(","));
+    for file in RC_FILES {
+        let modified = std::fs::metadata(home.join(file))
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok());
+        if let Some(modified) = modified {
+            key.push_str(&format!("|{}={}", file, modified.as_nanos()));
+        }
+    }
+    key
+}
+
+fn cache_path() -> PathBuf {
+    Config::data_dir().join("shell_env.toml")
+}
+
+fn load_cache() -> Option<EnvCache> {
+    let contents = std::fs::read_to_string(cache_path()).ok()?;
+    toml::from_str(&contents).ok()
+}
+
+fn save_cache(cache: &EnvCache) -> Result<(), String> {
+    let contents = toml::to_string_pretty(cache)
+        .map_err(|e| format!("Failed to serialize shell environment cache: {}", e))?;
+    crate::config_store::write_atomic(&cache_path(), &contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(extra: &[&str]) -> Vec<String> {
+        import_names(&extra.iter().map(|name| name.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_parse_env_skips_rc_output() {
+        let output = b"Welcome!\nlast login\n__VIBETERM_ENV__\nPATH=/usr/bin:/opt/bin\0MULTI=a\nb\0EMPTY=\0junk\0EQ=a=b\0";
+        let vars = parse_env(output);
+        assert_eq!(vars.get("PATH").map(String::as_str), Some("/usr/bin:/opt/bin"));
+        assert_eq!(vars.get("MULTI").map(String::as_str), Some("a\nb"));
+        assert_eq!(vars.get("EMPTY").map(String::as_str), Some(""));
+        assert_eq!(vars.get("EQ").map(String::as_str), Some("a=b"));
+        assert_eq!(vars.len(), 4);
+
+        // Without the marker everything is parsed
+        assert_eq!(parse_env(b"A=1\0B=2\0").len(), 2);
+    }
+
+    #[test]
+    fn test_only_allowlisted_values_are_imported() {
+        let names = names(&["GOPATH", " PATH ", ""]);
+        assert_eq!(names, ["PATH", "LANG", "EDITOR", "SSH_AUTH_SOCK", "GOPATH"]);
+
+        let captured: BTreeMap<String, String> = [
+            ("PATH", "/opt/homebrew/bin:/usr/bin"),
+            ("LANG", ""),
+            ("GOPATH", "/go"),
+            ("SECRET", "x"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        let vars = select(&captured, &names);
+        assert_eq!(vars.keys().collect::<Vec<_>>(), ["GOPATH", "PATH"]);
+
+        // Values already set are left alone
+        let current = |name: &str| (name == "GOPATH").then(|| "/go".to_string());
+        assert_eq!(
+            changes(&vars, &names, current),
+            [("PATH".to_string(), "/opt/homebrew/bin:/usr/bin".to_string())]
+        );
+    }
+
+    /// A stand-in login shell running `script`
+    #[cfg(unix)]
+    fn fake_shell(dir: &Path, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let shell = dir.join("shell");
+        std::fs::write(&shell, format!("#!/bin/sh\n{}", script)).unwrap();
+        std::fs::set_permissions(&shell, std::fs::Permissions::from_mode(0o755)).unwrap();
+        shell
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_capture_does_not_wait_for_background_processes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let timeout = Duration::from_millis(500);
+
+        // The backgrounded sleep keeps stdout open after the shell exits
+        let shell = fake_shell(dir.path(), "sleep 30 &\necho __VIBETERM_ENV__\nprintf 'PATH=/opt/bin\\0'\n");
+        let started = Instant::now();
+        let vars = capture(&shell, timeout).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(vars.get("PATH").map(String::as_str), Some("/opt/bin"));
+
+        // A shell that never finishes is killed at the deadline
+        let shell = fake_shell(dir.path(), "sleep 30\n");
+        let started = Instant::now();
+        assert!(capture(&shell, timeout).is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_cache_key_follows_rc_files() {
+        let home = tempfile::TempDir::new().unwrap();
+        let shell = Path::new("/bin/zsh");
+        let names = names(&[]);
+        let empty = cache_key(shell, home.path(), &names);
+
+        std::fs::write(home.path().join(".zshrc"), "export A=1\n").unwrap();
+        let with_rc = cache_key(shell, home.path(), &names);
+        assert_ne!(with_rc, empty);
+        assert_eq!(cache_key(shell, home.path(), &names), with_rc);
+
+        assert_ne!(cache_key(Path::new("/bin/bash"), home.path(), &names), with_rc);
+        assert_ne!(cache_key(shell, home.path(), &self::names(&["GOPATH"])), with_rc);
+    }
+}
@@ -0,0 +1,133 @@
+//! Shell Selection
+//!
+//! Picks the shell new terminals run: `terminal.shell` from the config, or
+//! `$SHELL`. A shell that isn't there any more (fish after an uninstall) is
+//! replaced with the first usable standard shell, so tabs still open.
+
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Tried in order when the wanted shell can't run
+pub const FALLBACK_SHELLS: &[&str] = &["/bin/zsh", "/bin/bash", "/bin/sh"];
+
+/// Shell for new terminals, set by the app from the config
+static SHELL: RwLock<Option<String>> = RwLock::new(None);
+
+/// Shell to run and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellChoice {
+    pub path: String,
+    /// Shell that was asked for but can't run, if `path` replaces it
+    pub replaced: Option<String>,
+}
+
+/// Pick the shell to run
+///
+/// `configured` wins over `env_shell` when not empty. `usable` tells
+/// whether a shell can be run; without any usable shell the wanted one is
+/// returned as is, and spawning reports the error.
+pub fn choose_shell(configured: &str, env_shell: Option<&str>, usable: impl Fn(&str) -> bool) -> ShellChoice {
+    let configured = configured.trim();
+    let wanted = Some(configured)
+        .filter(|shell| !shell.is_empty())
+        .or(env_shell.map(str::trim).filter(|shell| !shell.is_empty()));
+
+    if let Some(wanted) = wanted.filter(|shell| usable(shell)) {
+        return ShellChoice { path: wanted.to_string(), replaced: None };
+    }
+
+    let default = if cfg!(target_os = "windows") { "cmd.exe" } else { "/bin/bash" };
+    match FALLBACK_SHELLS.iter().find(|shell| usable(shell)) {
+        Some(fallback) => ShellChoice {
+            path: fallback.to_string(),
+            replaced: wanted.map(str::to_string),
+        },
+        None => ShellChoice {
+            path: wanted.unwrap_or(default).to_string(),
+            replaced: None,
+        },
+    }
+}
+
+/// Can `shell` be run? Bare names are looked up on `PATH`.
+pub fn is_usable(shell: &str) -> bool {
+    let path = Path::new(shell);
+    if path.components().count() > 1 {
+        return is_executable(path);
+    }
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| is_executable(&dir.join(shell)))
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Pick the shell for `terminal.shell` and use it for new terminals
+pub fn configure(configured: &str) -> ShellChoice {
+    let env_shell = std::env::var("SHELL").ok();
+    let choice = choose_shell(configured, env_shell.as_deref(), is_usable);
+    if let Some(replaced) = &choice.replaced {
+        log::warn!("Shell {} can't be run; using {}", replaced, choice.path);
+    }
+    *SHELL.write().unwrap_or_else(|e| e.into_inner()) = Some(choice.path.clone());
+    choice
+}
+
+/// Shell for new terminals (`$SHELL` checked now if the app hasn't set one)
+pub fn shell() -> String {
+    let shell = SHELL.read().unwrap_or_else(|e| e.into_inner()).clone();
+    shell.unwrap_or_else(|| configure("").path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn only(shells: &'static [&'static str]) -> impl Fn(&str) -> bool {
+        move |shell| shells.contains(&shell)
+    }
+
+    #[test]
+    fn test_configured_shell_wins() {
+        let choice = choose_shell("/usr/bin/fish", Some("/bin/zsh"), only(&["/usr/bin/fish", "/bin/zsh"]));
+        assert_eq!(choice, ShellChoice { path: "/usr/bin/fish".to_string(), replaced: None });
+
+        // Blank falls back to $SHELL
+        let choice = choose_shell("  ", Some("/bin/zsh"), only(&["/bin/zsh"]));
+        assert_eq!(choice.path, "/bin/zsh");
+    }
+
+    #[test]
+    fn test_missing_shell_is_replaced() {
+        let choice = choose_shell("", Some("/opt/homebrew/bin/fish"), only(&["/bin/bash", "/bin/sh"]));
+        assert_eq!(choice, ShellChoice {
+            path: "/bin/bash".to_string(),
+            replaced: Some("/opt/homebrew/bin/fish".to_string()),
+        });
+
+        // Earlier candidates first
+        let choice = choose_shell("/bin/fish", None, only(&["/bin/sh", "/bin/zsh"]));
+        assert_eq!(choice.path, "/bin/zsh");
+    }
+
+    #[test]
+    fn test_no_wanted_shell_uses_a_fallback_quietly() {
+        let choice = choose_shell("", None, only(&["/bin/sh"]));
+        assert_eq!(choice, ShellChoice { path: "/bin/sh".to_string(), replaced: None });
+    }
+
+    #[test]
+    fn test_nothing_usable_keeps_the_wanted_shell() {
+        let choice = choose_shell("", Some("/bin/fish"), only(&[]));
+        assert_eq!(choice, ShellChoice { path: "/bin/fish".to_string(), replaced: None });
+    }
+}
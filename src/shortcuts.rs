@@ -0,0 +1,107 @@
+//! Platform Shortcut Conventions
+//!
+//! App shortcuts use Cmd on macOS and Ctrl on Linux and Windows. Labels are
+//! written the macOS way ("Cmd+T") and translated for display.
+
+use egui::Modifiers;
+use std::borrow::Cow;
+
+/// Running on macOS?
+pub const IS_MAC: bool = cfg!(target_os = "macos");
+
+/// Is the primary shortcut key held: Cmd on macOS, Ctrl elsewhere?
+pub fn primary_modifier(modifiers: Modifiers) -> bool {
+    primary_held(modifiers, IS_MAC)
+}
+
+/// Is the primary shortcut key the only modifier held?
+pub fn primary_only(modifiers: Modifiers) -> bool {
+    primary_only_held(modifiers, IS_MAC)
+}
+
+/// Is the shortcut for a key shells bind to Ctrl (Ctrl+R) held: Cmd alone
+/// on macOS, Ctrl+Shift elsewhere so the shell keeps Ctrl?
+pub fn primary_beside_shell(modifiers: Modifiers) -> bool {
+    primary_beside_shell_held(modifiers, IS_MAC)
+}
+
+/// Shortcut label for this platform ("Cmd+T" becomes "Ctrl+T" off macOS)
+pub fn shortcut_label(label: &str) -> Cow<'_, str> {
+    label_for(label, IS_MAC)
+}
+
+fn primary_held(modifiers: Modifiers, mac: bool) -> bool {
+    if mac {
+        modifiers.mac_cmd
+    } else {
+        modifiers.ctrl
+    }
+}
+
+fn primary_only_held(modifiers: Modifiers, mac: bool) -> bool {
+    let others = if mac { modifiers.ctrl } else { modifiers.mac_cmd };
+    primary_held(modifiers, mac) && !others && !modifiers.alt && !modifiers.shift
+}
+
+fn primary_beside_shell_held(modifiers: Modifiers, mac: bool) -> bool {
+    if mac {
+        primary_only_held(modifiers, mac)
+    } else {
+        primary_only_held(Modifiers { shift: false, ..modifiers }, mac) && modifiers.shift
+    }
+}
+
+fn label_for(label: &str, mac: bool) -> Cow<'_, str> {
+    if mac || !label.contains("Cmd") {
+        Cow::Borrowed(label)
+    } else {
+        Cow::Owned(label.replace("Cmd", "Ctrl"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CMD: Modifiers = Modifiers { mac_cmd: true, command: true, ..Modifiers::NONE };
+    const CTRL: Modifiers = Modifiers { ctrl: true, command: true, ..Modifiers::NONE };
+    /// Ctrl on macOS, where egui leaves `command` to Cmd
+    const MAC_CTRL: Modifiers = Modifiers::CTRL;
+
+    #[test]
+    fn test_primary_modifier_mac() {
+        assert!(primary_held(CMD, true));
+        assert!(!primary_held(MAC_CTRL, true));
+        assert!(primary_only_held(CMD, true));
+        assert!(!primary_only_held(Modifiers { shift: true, ..CMD }, true));
+        assert!(!primary_only_held(Modifiers { ctrl: true, ..CMD }, true));
+    }
+
+    #[test]
+    fn test_primary_modifier_linux_and_windows() {
+        assert!(primary_held(CTRL, false));
+        assert!(!primary_held(Modifiers::NONE, false));
+        // The Super/Windows key isn't a shortcut key there
+        assert!(!primary_held(Modifiers { mac_cmd: true, ..Modifiers::NONE }, false));
+        assert!(primary_only_held(CTRL, false));
+        assert!(!primary_only_held(Modifiers { alt: true, ..CTRL }, false));
+    }
+
+    #[test]
+    fn test_shell_keys_need_shift_off_mac() {
+        assert!(primary_beside_shell_held(CMD, true));
+        assert!(!primary_beside_shell_held(MAC_CTRL, true));
+        // Plain Ctrl+R stays the shell's reverse search
+        assert!(!primary_beside_shell_held(CTRL, false));
+        assert!(primary_beside_shell_held(Modifiers { shift: true, ..CTRL }, false));
+        assert!(!primary_beside_shell_held(Modifiers { shift: true, alt: true, ..CTRL }, false));
+    }
+
+    #[test]
+    fn test_shortcut_labels() {
+        assert_eq!(label_for("Cmd+Shift+D", true), "Cmd+Shift+D");
+        assert_eq!(label_for("Cmd+Shift+D", false), "Ctrl+Shift+D");
+        assert_eq!(label_for("Cmd+click to cd here", false), "Ctrl+click to cd here");
+        assert!(matches!(label_for("Ctrl+Tab", false), Cow::Borrowed("Ctrl+Tab")));
+    }
+}
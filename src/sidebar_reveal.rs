@@ -0,0 +1,180 @@
+//! Auto-Hide Sidebar
+//!
+//! With `ui.sidebar_mode = "autohide"` the sidebar leaves a thin strip on
+//! the left edge. Hovering the strip (or Cmd+B) slides the sidebar out
+//! above the panes without resizing them, and it slides back once the
+//! pointer has been away from both for [`HIDE_DELAY`]. `"hidden"` drops
+//! the strip, so only Cmd+B brings the sidebar out.
+
+use std::time::{Duration, Instant};
+
+/// Width of the hover strip on the left edge (px)
+pub const STRIP_WIDTH: f32 = 6.0;
+
+/// How long the pointer must stay away before the sidebar slides back
+pub const HIDE_DELAY: Duration = Duration::from_millis(400);
+
+/// Length of the slide in or out
+pub const SLIDE_DURATION: Duration = Duration::from_millis(120);
+
+/// Whether the overlay sidebar is out, and its slide
+#[derive(Debug, Default)]
+pub struct SidebarReveal {
+    open: bool,
+    /// When `open` last changed
+    changed_at: Option<Instant>,
+    /// The pointer has been over the sidebar since it came out
+    hovered: bool,
+    /// When the pointer left the strip and the sidebar
+    left_at: Option<Instant>,
+    /// Put away under the pointer; hovering reveals again once it has left
+    wait_for_leave: bool,
+}
+
+impl SidebarReveal {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self, now: Instant) {
+        if !self.open {
+            self.open = true;
+            self.changed_at = Some(now);
+            self.hovered = false;
+            self.left_at = None;
+            self.wait_for_leave = false;
+        }
+    }
+
+    pub fn close(&mut self, now: Instant) {
+        if self.open {
+            self.open = false;
+            self.changed_at = Some(now);
+            self.left_at = None;
+            self.wait_for_leave = true;
+        }
+    }
+
+    /// Cmd+B
+    pub fn toggle(&mut self, now: Instant) {
+        if self.open {
+            self.close(now);
+        } else {
+            self.open(now);
+        }
+    }
+
+    /// Note whether the pointer is over the strip or the sidebar
+    ///
+    /// `pinned` (the sidebar has keyboard focus) keeps it out. Opened from
+    /// the keyboard, it stays until the pointer has come and gone.
+    pub fn update(&mut self, over: bool, pinned: bool, now: Instant) {
+        if over && self.wait_for_leave && !self.open {
+            return;
+        }
+        self.wait_for_leave = false;
+        if over {
+            self.open(now);
+            self.hovered = true;
+            self.left_at = None;
+            return;
+        }
+        if !self.open || pinned || !self.hovered {
+            self.left_at = None;
+            return;
+        }
+        let left_at = *self.left_at.get_or_insert(now);
+        if now.saturating_duration_since(left_at) >= HIDE_DELAY {
+            self.close(now);
+        }
+    }
+
+    /// How far out the sidebar is, from 0 (hidden) to 1, eased
+    pub fn progress(&self, now: Instant) -> f32 {
+        let t = self.changed_at.map_or(1.0, |at| {
+            (now.saturating_duration_since(at).as_secs_f32() / SLIDE_DURATION.as_secs_f32()).min(1.0)
+        });
+        let eased = 1.0 - (1.0 - t).powi(3);
+        if self.open {
+            eased
+        } else {
+            1.0 - eased
+        }
+    }
+
+    /// When to draw again for the slide or a pending hide
+    pub fn repaint_after(&self, now: Instant) -> Option<Duration> {
+        let sliding = self.changed_at.is_some_and(|at| now.saturating_duration_since(at) < SLIDE_DURATION);
+        if sliding {
+            return Some(Duration::ZERO);
+        }
+        self.left_at.map(|left_at| HIDE_DELAY.saturating_sub(now.saturating_duration_since(left_at)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_hover_reveals_and_leaving_hides_after_delay() {
+        let start = Instant::now();
+        let mut reveal = SidebarReveal::default();
+        assert_eq!(reveal.progress(start), 0.0);
+
+        reveal.update(true, false, start);
+        assert!(reveal.is_open());
+        assert_eq!(reveal.repaint_after(start + ms(60)), Some(Duration::ZERO));
+        assert!(reveal.progress(start + ms(60)) > 0.5);
+        assert_eq!(reveal.progress(start + SLIDE_DURATION), 1.0);
+
+        reveal.update(false, false, start + ms(500));
+        assert_eq!(reveal.repaint_after(start + ms(600)), Some(ms(300)));
+        // Coming back in time cancels the hide
+        reveal.update(true, false, start + ms(700));
+        reveal.update(false, false, start + ms(800));
+        reveal.update(false, false, start + ms(1100));
+        assert!(reveal.is_open());
+        reveal.update(false, false, start + ms(1200));
+        assert!(!reveal.is_open());
+        assert_eq!(reveal.progress(start + ms(1200) + SLIDE_DURATION), 0.0);
+    }
+
+    #[test]
+    fn test_focus_and_keyboard_keep_it_out() {
+        let start = Instant::now();
+        let mut reveal = SidebarReveal::default();
+        reveal.update(true, false, start);
+        reveal.update(false, true, start + ms(100));
+        reveal.update(false, true, start + ms(2000));
+        assert!(reveal.is_open());
+
+        // Unpinned, the delay starts then
+        reveal.update(false, false, start + ms(3000));
+        reveal.update(false, false, start + ms(3400));
+        assert!(!reveal.is_open());
+
+        // Cmd+B with the pointer elsewhere stays out until toggled
+        reveal.toggle(start + ms(4000));
+        reveal.update(false, false, start + ms(9000));
+        assert!(reveal.is_open());
+        assert_eq!(reveal.repaint_after(start + ms(9000)), None);
+        reveal.toggle(start + ms(9100));
+        assert!(!reveal.is_open());
+
+        // Put away under the pointer, it stays away until the pointer leaves
+        reveal.update(false, false, start + ms(9150));
+        reveal.update(true, false, start + ms(9200));
+        assert!(reveal.is_open());
+        reveal.close(start + ms(9300));
+        reveal.update(true, false, start + ms(9400));
+        assert!(!reveal.is_open());
+        reveal.update(false, false, start + ms(9500));
+        reveal.update(true, false, start + ms(9600));
+        assert!(reveal.is_open());
+    }
+}
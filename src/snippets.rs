@@ -0,0 +1,257 @@
+//! Command Snippets
+//!
+//! User-defined command templates loaded from `~/.config/vibeterm/snippets.toml`:
+//!
+//! ```toml
+//! [[snippet]]
+//! name = "Tail deployment logs"
+//! command = "kubectl logs -f deployment/{name} -n {namespace}"
+//! tags = ["k8s"]
+//! ```
+//!
+//! `{placeholder}` tokens are filled in before the command is typed;
+//! `{{` and `}}` produce literal braces.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::config::Config;
+
+/// Written when the snippets file is opened for editing the first time
+const SNIPPETS_TEMPLATE: &str = r#"# VibeTerm snippets
+#
+# {placeholder} tokens are filled in before insertion; use {{ and }} for
+# literal braces.
+#
+# [[snippet]]
+# name = "Tail deployment logs"
+# command = "kubectl logs -f deployment/{name} -n {namespace}"
+# tags = ["k8s", "logs"]
+"#;
+
+/// A saved command template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// On-disk layout of snippets.toml
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SnippetFile {
+    #[serde(default, rename = "snippet")]
+    snippets: Vec<Snippet>,
+}
+
+/// Piece of a parsed command template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Literal(String),
+    Placeholder(String),
+}
+
+/// Get snippets file path
+pub fn snippets_path() -> PathBuf {
+    Config::config_dir().join("snippets.toml")
+}
+
+/// Load snippets from disk (empty if missing or invalid)
+pub fn load_snippets() -> Vec<Snippet> {
+    let path = snippets_path();
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+
+    match parse_snippets(&contents) {
+        Ok(snippets) => snippets,
+        Err(e) => {
+            log::warn!("Failed to parse {:?}: {}", path, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Parse the contents of a snippets file
+fn parse_snippets(contents: &str) -> Result<Vec<Snippet>, String> {
+    toml::from_str::<SnippetFile>(contents)
+        .map(|file| file.snippets)
+        .map_err(|e| e.to_string())
+}
+
+/// Make sure the snippets file exists so it can be opened for editing
+pub fn ensure_snippets_file() -> Result<PathBuf, String> {
+    let path = snippets_path();
+    if !path.exists() {
+        std::fs::create_dir_all(Config::config_dir())
+            .map_err(|e| format!("Failed to create config dir: {}", e))?;
+        std::fs::write(&path, SNIPPETS_TEMPLATE)
+            .map_err(|e| format!("Failed to write snippets file: {}", e))?;
+    }
+    Ok(path)
+}
+
+/// Split a command template into literals and placeholders
+pub fn parse_template(template: &str) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some('{') => return Err("Nested '{' in placeholder".to_string()),
+                        Some(c) => name.push(c),
+                        None => return Err("Unclosed '{' in template".to_string()),
+                    }
+                }
+
+                let name = name.trim();
+                if name.is_empty() {
+                    return Err("Empty placeholder '{}'".to_string());
+                }
+
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Placeholder(name.to_string()));
+            }
+            '}' => return Err("Unmatched '}' in template (use '}}' for a literal brace)".to_string()),
+            c => literal.push(c),
+        }
+    }
+
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+/// Placeholder names in order of first appearance
+pub fn placeholders(segments: &[Segment]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for segment in segments {
+        if let Segment::Placeholder(name) = segment {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+    }
+    names
+}
+
+/// Substitute placeholder values into a parsed template
+///
+/// Missing values are left empty.
+pub fn render_template(segments: &[Segment], values: &HashMap<String, String>) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Literal(text) => text.as_str(),
+            Segment::Placeholder(name) => values.get(name).map(String::as_str).unwrap_or(""),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(s: &str) -> Segment {
+        Segment::Literal(s.to_string())
+    }
+
+    fn placeholder(s: &str) -> Segment {
+        Segment::Placeholder(s.to_string())
+    }
+
+    #[test]
+    fn test_parse_placeholders() {
+        let segments = parse_template("kubectl logs -f deployment/{name} -n {ns}").unwrap();
+        assert_eq!(segments, vec![
+            literal("kubectl logs -f deployment/"),
+            placeholder("name"),
+            literal(" -n "),
+            placeholder("ns"),
+        ]);
+        assert_eq!(placeholders(&segments), vec!["name", "ns"]);
+    }
+
+    #[test]
+    fn test_plain_command_has_no_placeholders() {
+        let segments = parse_template("git status").unwrap();
+        assert_eq!(segments, vec![literal("git status")]);
+        assert!(placeholders(&segments).is_empty());
+    }
+
+    #[test]
+    fn test_escaped_braces() {
+        let segments = parse_template("echo ${{HOME}} {{}} {x}").unwrap();
+        assert_eq!(segments, vec![literal("echo ${HOME} {} "), placeholder("x")]);
+    }
+
+    #[test]
+    fn test_repeated_placeholder_listed_once() {
+        let segments = parse_template("cp {file} {file}.bak").unwrap();
+        assert_eq!(placeholders(&segments), vec!["file"]);
+    }
+
+    #[test]
+    fn test_placeholder_name_trimmed() {
+        let segments = parse_template("{ name }").unwrap();
+        assert_eq!(segments, vec![placeholder("name")]);
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(parse_template("echo {unclosed").is_err());
+        assert!(parse_template("echo }").is_err());
+        assert!(parse_template("echo {}").is_err());
+        assert!(parse_template("echo {a{b}}").is_err());
+    }
+
+    #[test]
+    fn test_render_template() {
+        let segments = parse_template("cp {file} {file}.bak && echo {{done}}").unwrap();
+        let values = HashMap::from([("file".to_string(), "a.txt".to_string())]);
+        assert_eq!(render_template(&segments, &values), "cp a.txt a.txt.bak && echo {done}");
+
+        // Missing values render empty
+        assert_eq!(render_template(&segments, &HashMap::new()), "cp  .bak && echo {done}");
+    }
+
+    #[test]
+    fn test_parse_snippets_file() {
+        let contents = r#"
+            [[snippet]]
+            name = "Logs"
+            command = "kubectl logs -f deployment/{name}"
+            tags = ["k8s"]
+
+            [[snippet]]
+            name = "Status"
+            command = "git status"
+        "#;
+        let snippets = parse_snippets(contents).unwrap();
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].tags, vec!["k8s"]);
+        assert!(snippets[1].tags.is_empty());
+
+        // The commented-out template parses to nothing
+        assert!(parse_snippets(SNIPPETS_TEMPLATE).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,359 @@
+//! SSH Hosts
+//!
+//! Hosts offered by "Connect to Host…": the `Host` blocks of
+//! `~/.ssh/config` plus the `[[ssh_hosts]]` entries of the config. Only the
+//! `ssh` command line is put together here; ssh reads its own config and
+//! asks for any password or passphrase itself, so nothing secret is stored.
+//! Wildcard hosts, `Match` blocks and `Include` are skipped with a warning.
+
+use std::path::{Path, PathBuf};
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+use crate::config::{parse_hex_color, Config, SshHostConfig};
+
+/// Hosts kept in the recently used list
+const MAX_RECENT: usize = 20;
+
+/// A host that can be connected to
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshHost {
+    /// Name in the list and the tab; the ssh config alias for ssh config hosts
+    pub name: String,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    /// Tint of the connection's pane
+    pub tint: Option<Color32>,
+    /// From `~/.ssh/config`, so ssh knows it by name
+    pub in_ssh_config: bool,
+}
+
+impl SshHost {
+    /// `ssh` command line connecting to the host
+    pub fn command(&self) -> String {
+        if self.in_ssh_config {
+            return format!("ssh {}", quote_arg(&self.name));
+        }
+        let mut command = "ssh".to_string();
+        if let Some(port) = self.port {
+            command.push_str(&format!(" -p {}", port));
+        }
+        let host = self.hostname.as_deref().unwrap_or(&self.name);
+        let target = match &self.user {
+            Some(user) => format!("{}@{}", user, host),
+            None => host.to_string(),
+        };
+        command.push(' ');
+        command.push_str(&quote_arg(&target));
+        command
+    }
+
+    /// `user@host:port` as far as it's known, for the list
+    pub fn detail(&self) -> String {
+        let mut detail = String::new();
+        if let Some(user) = &self.user {
+            detail.push_str(user);
+            detail.push('@');
+        }
+        detail.push_str(self.hostname.as_deref().unwrap_or(&self.name));
+        if let Some(port) = self.port {
+            detail.push_str(&format!(":{}", port));
+        }
+        detail
+    }
+}
+
+/// Quote an ssh argument for a POSIX shell when it needs it
+fn quote_arg(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || "@._-:%[]".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Hosts of an ssh config file's text, in file order
+///
+/// The first value of each option wins, as in ssh. Whatever is skipped is
+/// described in `warnings`.
+pub fn parse(text: &str, warnings: &mut Vec<String>) -> Vec<SshHost> {
+    let mut hosts: Vec<SshHost> = Vec::new();
+    // Hosts of the current `Host` block, as indices into `hosts`
+    let mut block: Vec<usize> = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, value) = match line.split_once(|c: char| c.is_whitespace() || c == '=') {
+            Some((keyword, value)) => (keyword, value.trim_start_matches(|c: char| c.is_whitespace() || c == '=').trim()),
+            None => (line, ""),
+        };
+        let value = value.trim_matches('"');
+
+        match keyword.to_ascii_lowercase().as_str() {
+            "host" => {
+                block.clear();
+                for pattern in value.split_whitespace() {
+                    if pattern.contains(['*', '?']) || pattern.starts_with('!') {
+                        warnings.push(format!("line {}: skipped wildcard host '{}'", number + 1, pattern));
+                        continue;
+                    }
+                    if hosts.iter().any(|host| host.name == pattern) {
+                        continue;
+                    }
+                    block.push(hosts.len());
+                    hosts.push(SshHost {
+                        name: pattern.to_string(),
+                        hostname: None,
+                        user: None,
+                        port: None,
+                        tint: None,
+                        in_ssh_config: true,
+                    });
+                }
+            }
+            "match" => {
+                block.clear();
+                warnings.push(format!("line {}: skipped Match block", number + 1));
+            }
+            "include" => {
+                warnings.push(format!("line {}: skipped Include {}", number + 1, value));
+            }
+            "hostname" => {
+                for &index in &block {
+                    hosts[index].hostname.get_or_insert_with(|| value.to_string());
+                }
+            }
+            "user" => {
+                for &index in &block {
+                    hosts[index].user.get_or_insert_with(|| value.to_string());
+                }
+            }
+            "port" => match value.parse::<u16>() {
+                Ok(port) => {
+                    for &index in &block {
+                        hosts[index].port.get_or_insert(port);
+                    }
+                }
+                Err(_) => warnings.push(format!("line {}: invalid port '{}'", number + 1, value)),
+            },
+            _ => {}
+        }
+    }
+    hosts
+}
+
+/// Hosts added in the config; an entry named after an ssh config host adds to it
+pub fn merge(mut hosts: Vec<SshHost>, configured: &[SshHostConfig], warnings: &mut Vec<String>) -> Vec<SshHost> {
+    for entry in configured {
+        let name = entry.name.trim();
+        if name.is_empty() {
+            warnings.push("ssh_hosts entry without a name".to_string());
+            continue;
+        }
+        let tint = match entry.tint.trim() {
+            "" => None,
+            hex if hex.len() == 7 && hex.starts_with('#') && hex[1..].chars().all(|c| c.is_ascii_hexdigit()) => {
+                Some(parse_hex_color(hex))
+            }
+            hex => {
+                warnings.push(format!("ssh_hosts '{}': tint '{}' is not a #RRGGBB color", name, hex));
+                None
+            }
+        };
+        let text = |value: &str| Some(value.trim().to_string()).filter(|value| !value.is_empty());
+        let port = (entry.port != 0).then_some(entry.port);
+
+        match hosts.iter_mut().find(|host| host.name == name) {
+            Some(host) => {
+                host.tint = tint.or(host.tint);
+                if host.in_ssh_config {
+                    continue;
+                }
+                host.hostname = text(&entry.hostname).or(host.hostname.take());
+                host.user = text(&entry.user).or(host.user.take());
+                host.port = port.or(host.port);
+            }
+            None => hosts.push(SshHost {
+                name: name.to_string(),
+                hostname: text(&entry.hostname),
+                user: text(&entry.user),
+                port,
+                tint,
+                in_ssh_config: false,
+            }),
+        }
+    }
+    hosts
+}
+
+/// `~/.ssh/config`
+pub fn user_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("config"))
+}
+
+/// Hosts from the ssh config at `path` (if readable) and `configured`
+pub fn load(path: Option<&Path>, configured: &[SshHostConfig]) -> Vec<SshHost> {
+    let mut warnings = Vec::new();
+    let text = path.and_then(|path| std::fs::read_to_string(path).ok()).unwrap_or_default();
+    let hosts = merge(parse(&text, &mut warnings), configured, &mut warnings);
+    for warning in warnings {
+        log::warn!("SSH hosts: {}", warning);
+    }
+    hosts
+}
+
+/// Names of the hosts connected to, most recent first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecentHosts {
+    pub names: Vec<String>,
+}
+
+impl RecentHosts {
+    pub fn state_path() -> PathBuf {
+        Config::data_dir().join("ssh_recent.toml")
+    }
+
+    /// Load the list, empty if missing or unreadable
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::state_path())
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        std::fs::create_dir_all(Config::data_dir())
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+        let toml_string = toml::to_string(self)
+            .map_err(|e| format!("Failed to serialize recent SSH hosts: {}", e))?;
+        std::fs::write(Self::state_path(), toml_string)
+            .map_err(|e| format!("Failed to write recent SSH hosts: {}", e))
+    }
+
+    /// Move `name` to the front
+    pub fn touch(&mut self, name: &str) {
+        self.names.retain(|recent| recent != name);
+        self.names.insert(0, name.to_string());
+        self.names.truncate(MAX_RECENT);
+    }
+
+    /// Recently used hosts first, newest first; the rest keep their order
+    pub fn sort(&self, hosts: &mut [SshHost]) {
+        hosts.sort_by_key(|host| self.names.iter().position(|name| *name == host.name).unwrap_or(usize::MAX));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = r#"
+# Work machines
+Include ~/.ssh/config.d/*
+
+Host *
+    ServerAliveInterval 60
+    User fallback
+
+Host build build-eu
+    HostName 10.0.0.12
+    User ci
+    Port 2222
+
+Host bastion
+    HostName=bastion.example.com
+    User ops
+    User ignored
+
+Host *.internal !secret
+    User internal
+
+Match host db
+    User dba
+
+Host db
+    Hostname db.example.com
+    Port none
+"#;
+
+    #[test]
+    fn test_parse_host_blocks() {
+        let mut warnings = Vec::new();
+        let hosts = parse(FIXTURE, &mut warnings);
+        let names: Vec<&str> = hosts.iter().map(|host| host.name.as_str()).collect();
+        assert_eq!(names, ["build", "build-eu", "bastion", "db"]);
+
+        assert_eq!(hosts[1].hostname.as_deref(), Some("10.0.0.12"));
+        assert_eq!(hosts[1].user.as_deref(), Some("ci"));
+        assert_eq!(hosts[1].port, Some(2222));
+        // `key=value`, and the first value wins
+        assert_eq!(hosts[2].hostname.as_deref(), Some("bastion.example.com"));
+        assert_eq!(hosts[2].user.as_deref(), Some("ops"));
+        // Options of skipped blocks don't leak into the next host
+        assert_eq!(hosts[3].user, None);
+        assert_eq!(hosts[3].port, None);
+
+        assert_eq!(warnings.len(), 6, "{:?}", warnings);
+        assert!(warnings[0].starts_with("line 3: skipped Include"));
+        assert!(warnings.iter().any(|warning| warning.contains("invalid port 'none'")));
+    }
+
+    #[test]
+    fn test_merge_configured_hosts() {
+        let mut warnings = Vec::new();
+        let hosts = parse(FIXTURE, &mut warnings);
+        let configured = [
+            SshHostConfig { name: "bastion".into(), tint: "#E06C75".into(), user: "me".into(), ..Default::default() },
+            SshHostConfig { name: "pi".into(), hostname: "192.168.1.20".into(), user: "pi".into(), port: 22, ..Default::default() },
+            SshHostConfig { name: "nas".into(), tint: "red".into(), ..Default::default() },
+            SshHostConfig::default(),
+        ];
+        let mut warnings = Vec::new();
+        let hosts = merge(hosts, &configured, &mut warnings);
+        assert_eq!(hosts.len(), 6);
+        assert_eq!(warnings.len(), 2);
+
+        // ssh already knows config hosts; only the tint is added
+        let bastion = hosts.iter().find(|host| host.name == "bastion").unwrap();
+        assert_eq!(bastion.tint, Some(parse_hex_color("#E06C75")));
+        assert_eq!(bastion.command(), "ssh bastion");
+
+        let pi = hosts.iter().find(|host| host.name == "pi").unwrap();
+        assert_eq!(pi.command(), "ssh -p 22 pi@192.168.1.20");
+        assert_eq!(pi.detail(), "pi@192.168.1.20:22");
+        let nas = hosts.iter().find(|host| host.name == "nas").unwrap();
+        assert_eq!((nas.tint, nas.command()), (None, "ssh nas".to_string()));
+    }
+
+    #[test]
+    fn test_commands_quote_odd_names() {
+        let host = SshHost {
+            name: "my host".into(),
+            hostname: None,
+            user: Some("o'neil".into()),
+            port: None,
+            tint: None,
+            in_ssh_config: false,
+        };
+        assert_eq!(host.command(), r"ssh 'o'\''neil@my host'");
+        assert_eq!(quote_arg("user@[::1]"), "user@[::1]");
+    }
+
+    #[test]
+    fn test_recent_hosts_sort_first() {
+        let mut hosts = parse(FIXTURE, &mut Vec::new());
+        let mut recent = RecentHosts::default();
+        recent.touch("db");
+        recent.touch("bastion");
+        recent.touch("db");
+        recent.sort(&mut hosts);
+        let names: Vec<&str> = hosts.iter().map(|host| host.name.as_str()).collect();
+        assert_eq!(names, ["db", "bastion", "build", "build-eu"]);
+    }
+}
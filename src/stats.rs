@@ -0,0 +1,479 @@
+//! Local Usage Stats
+//!
+//! Opt-in (`advanced.local_stats`) record of where terminal time goes: how
+//! long each project's tab was focused, commands submitted at prompts, and
+//! tabs and panes opened. Events are buffered and appended as JSON lines to
+//! `~/.local/share/vibeterm/stats.jsonl` every minute; nothing leaves the
+//! machine. The Usage Stats tab summarizes the file, off the UI thread,
+//! dropping events too old to show as it goes.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config::Config;
+
+/// How often buffered events are written out
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Days of commands in the per-day chart
+pub const CHART_DAYS: usize = 14;
+
+/// Days counted for project time and busiest hours
+pub const WEEK_DAYS: i64 = 7;
+
+/// Focus shorter than this isn't recorded (tab switching on the way elsewhere)
+const MIN_FOCUS_SECS: u64 = 2;
+
+/// Without input for this long, focus stops counting
+const IDLE_AFTER: Duration = Duration::from_secs(300);
+
+/// Days of events kept in the file: the chart's, plus one for time zones
+const KEEP_DAYS: i64 = CHART_DAYS as i64 + 1;
+
+/// Held while the stats file is written, so pruning never drops an append
+static FILE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// One recorded event; `at` is seconds since the Unix epoch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StatEvent {
+    /// A project's tab was focused for `seconds` from `at`
+    Focus { project: String, at: i64, seconds: u64 },
+    /// A command was submitted at a prompt
+    Command { at: i64 },
+    TabOpened { at: i64 },
+    PaneOpened { at: i64 },
+}
+
+impl StatEvent {
+    fn at(&self) -> i64 {
+        match self {
+            StatEvent::Focus { at, .. }
+            | StatEvent::Command { at }
+            | StatEvent::TabOpened { at }
+            | StatEvent::PaneOpened { at } => *at,
+        }
+    }
+}
+
+/// Seconds since the Unix epoch
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Project being focused since `since`
+struct FocusSpan {
+    project: String,
+    at: i64,
+    since: Instant,
+}
+
+/// Buffers events and appends them to the stats file
+pub struct StatsRecorder {
+    path: PathBuf,
+    buffer: Vec<StatEvent>,
+    focus: Option<FocusSpan>,
+    last_flush: Instant,
+    last_input: Instant,
+    /// Tabs and panes open at the last check
+    open: Option<(usize, usize)>,
+}
+
+impl StatsRecorder {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            buffer: Vec::new(),
+            focus: None,
+            last_flush: Instant::now(),
+            last_input: Instant::now(),
+            open: None,
+        }
+    }
+
+    /// Stats file path
+    pub fn stats_path() -> PathBuf {
+        Config::data_dir().join("stats.jsonl")
+    }
+
+    pub fn record(&mut self, event: StatEvent) {
+        self.buffer.push(event);
+    }
+
+    /// Note that the user typed or clicked
+    pub fn note_input(&mut self, now: Instant) {
+        self.last_input = now;
+    }
+
+    /// Has there been no input for `IDLE_AFTER`?
+    fn idle(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.last_input) >= IDLE_AFTER
+    }
+
+    /// Note the focused project (`None` while the window is in the background)
+    ///
+    /// Time after the last input doesn't count once `IDLE_AFTER` has passed.
+    pub fn focus(&mut self, project: Option<&Path>, now: Instant) {
+        let idle = self.idle(now);
+        let project = project
+            .filter(|_| !idle)
+            .map(|path| path.to_string_lossy().to_string());
+        if self.focus.as_ref().map(|span| &span.project) == project.as_ref() {
+            return;
+        }
+        self.end_focus(if idle { self.last_input } else { now });
+        self.focus = project.map(|project| FocusSpan { project, at: unix_now(), since: now });
+    }
+
+    /// Record the running focus span as ending at `end`
+    fn end_focus(&mut self, end: Instant) {
+        if let Some(span) = self.focus.take() {
+            let seconds = end.saturating_duration_since(span.since).as_secs();
+            if seconds >= MIN_FOCUS_SECS {
+                self.buffer.push(StatEvent::Focus { project: span.project, at: span.at, seconds });
+            }
+        }
+    }
+
+    /// Record tabs and panes opened since the last check, from how many are open
+    pub fn count_open(&mut self, tabs: usize, panes: usize) {
+        if let Some((old_tabs, old_panes)) = self.open {
+            let at = unix_now();
+            let new_tabs = tabs.saturating_sub(old_tabs);
+            // A new tab's first pane isn't a pane opened
+            let new_panes = panes.saturating_sub(old_panes).saturating_sub(new_tabs);
+            self.buffer.extend((0..new_tabs).map(|_| StatEvent::TabOpened { at }));
+            self.buffer.extend((0..new_panes).map(|_| StatEvent::PaneOpened { at }));
+        }
+        self.open = Some((tabs, panes));
+    }
+
+    /// Write out buffered events once `FLUSH_INTERVAL` has passed
+    pub fn tick(&mut self, now: Instant) {
+        if now.duration_since(self.last_flush) >= FLUSH_INTERVAL {
+            self.flush(now);
+        }
+    }
+
+    /// Write out buffered events, including the focus span so far
+    pub fn flush(&mut self, now: Instant) {
+        self.last_flush = now;
+        // Split a long focus so it's saved even if the app is killed
+        if self.idle(now) {
+            self.end_focus(self.last_input);
+        } else if let Some(span) = &self.focus {
+            let project = span.project.clone();
+            self.end_focus(now);
+            self.focus = Some(FocusSpan { project, at: unix_now(), since: now });
+        }
+        if self.buffer.is_empty() {
+            return;
+        }
+        match append(&self.path, &self.buffer) {
+            Ok(()) => self.buffer.clear(),
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+
+    /// Forget everything recorded, on disk and buffered
+    pub fn delete_all(&mut self) -> Result<(), String> {
+        self.buffer.clear();
+        if let Some(span) = &mut self.focus {
+            span.at = unix_now();
+            span.since = Instant::now();
+        }
+        delete_file(&self.path)
+    }
+}
+
+/// Delete the stats file at `path`, if there is one
+pub fn delete_file(path: &Path) -> Result<(), String> {
+    let _lock = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to delete usage stats: {}", e))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `events` as one JSON object per line
+fn json_lines(events: &[StatEvent]) -> Result<String, String> {
+    let mut lines = String::new();
+    for event in events {
+        let line = serde_json::to_string(event).map_err(|e| format!("Failed to serialize usage stats: {}", e))?;
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+    Ok(lines)
+}
+
+/// Append `events` to the file at `path`, one JSON object per line
+fn append(path: &Path, events: &[StatEvent]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create data directory: {}", e))?;
+    }
+    let lines = json_lines(events)?;
+    let _lock = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()))
+        .map_err(|e| format!("Failed to write usage stats: {}", e))
+}
+
+/// Recorded events at `path`; unreadable lines (a write cut short) are skipped
+pub fn load(path: &Path) -> Vec<StatEvent> {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Drop the events at `path` from before `since`, rewriting the file if any go
+pub fn prune(path: &Path, since: i64) -> Result<(), String> {
+    let _lock = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let events = load(path);
+    if events.iter().all(|event| event.at() >= since) {
+        return Ok(());
+    }
+    let kept: Vec<StatEvent> = events.into_iter().filter(|event| event.at() >= since).collect();
+    crate::config_store::write_atomic(path, &json_lines(&kept)?)
+}
+
+/// What the Usage Stats tab shows
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatsSummary {
+    /// Seconds focused per project over the last week, most first
+    pub project_time: Vec<(String, u64)>,
+    /// Commands per local day (`YYYY-MM-DD`), oldest first, `CHART_DAYS` days
+    pub commands_per_day: Vec<(String, u32)>,
+    /// Commands per local hour of day over the last week
+    pub commands_by_hour: [u32; 24],
+    pub tabs_opened: u32,
+    pub panes_opened: u32,
+}
+
+/// Summarize `events` as of `now`, with local time `offset` seconds from UTC
+pub fn summarize(events: &[StatEvent], now: i64, offset: i64) -> StatsSummary {
+    let today = (now + offset).div_euclid(86_400);
+    let week_start = today - (WEEK_DAYS - 1);
+    let chart_start = today - (CHART_DAYS as i64 - 1);
+
+    let mut summary = StatsSummary::default();
+    let mut project_time: HashMap<&str, u64> = HashMap::new();
+    let mut per_day = vec![0u32; CHART_DAYS];
+    for event in events {
+        let local = event.at() + offset;
+        let day = local.div_euclid(86_400);
+        if day > today {
+            continue;
+        }
+        let this_week = day >= week_start;
+        match event {
+            StatEvent::Focus { project, seconds, .. } if this_week => {
+                *project_time.entry(project).or_default() += seconds;
+            }
+            StatEvent::Command { .. } => {
+                if day >= chart_start {
+                    per_day[(day - chart_start) as usize] += 1;
+                }
+                if this_week {
+                    summary.commands_by_hour[(local.rem_euclid(86_400) / 3600) as usize] += 1;
+                }
+            }
+            StatEvent::TabOpened { .. } if this_week => summary.tabs_opened += 1,
+            StatEvent::PaneOpened { .. } if this_week => summary.panes_opened += 1,
+            _ => {}
+        }
+    }
+
+    summary.project_time = project_time
+        .into_iter()
+        .map(|(project, seconds)| (project.to_string(), seconds))
+        .collect();
+    summary.project_time.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    summary.commands_per_day = per_day
+        .into_iter()
+        .enumerate()
+        .map(|(index, count)| (crate::session_log::format_date((chart_start + index as i64) * 86_400), count))
+        .collect();
+    summary
+}
+
+/// Summary of the stats file at `path` as of now, in local time, after
+/// pruning events too old to show (reads the whole file: off the UI thread)
+pub fn summarize_file(path: &Path) -> StatsSummary {
+    let now = unix_now();
+    if let Err(e) = prune(path, now - KEEP_DAYS * 86_400) {
+        log::warn!("{}", e);
+    }
+    summarize(&load(path), now, crate::session_log::utc_offset_secs(now))
+}
+
+/// "2h 05m" style duration
+pub fn format_duration(seconds: u64) -> String {
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m", seconds / 60),
+        _ => format!("{}h {:02}m", seconds / 3600, seconds / 60 % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 86_400;
+    /// 2024-03-15 12:00 UTC
+    const NOW: i64 = 1_710_504_000;
+
+    fn focus(project: &str, at: i64, seconds: u64) -> StatEvent {
+        StatEvent::Focus { project: project.to_string(), at, seconds }
+    }
+
+    #[test]
+    fn test_project_time_adds_up_over_the_week() {
+        let events = [
+            focus("/code/api", NOW - 3600, 600),
+            focus("/code/web", NOW - 2 * DAY, 900),
+            focus("/code/api", NOW - 6 * DAY, 300),
+            // Before the week
+            focus("/code/web", NOW - 8 * DAY, 5000),
+        ];
+        let summary = summarize(&events, NOW, 0);
+        assert_eq!(summary.project_time, [
+            ("/code/api".to_string(), 900),
+            ("/code/web".to_string(), 900),
+        ]);
+    }
+
+    #[test]
+    fn test_commands_are_counted_per_local_day() {
+        let events = [
+            StatEvent::Command { at: NOW },
+            StatEvent::Command { at: NOW - 60 },
+            StatEvent::Command { at: NOW - DAY },
+            StatEvent::Command { at: NOW - 20 * DAY },
+        ];
+        let summary = summarize(&events, NOW, 0);
+        assert_eq!(summary.commands_per_day.len(), CHART_DAYS);
+        assert_eq!(summary.commands_per_day.last().unwrap(), &("2024-03-15".to_string(), 2));
+        assert_eq!(summary.commands_per_day[CHART_DAYS - 2], ("2024-03-14".to_string(), 1));
+        assert_eq!(summary.commands_per_day[0].0, "2024-03-02");
+        assert_eq!(summary.commands_per_day.iter().map(|(_, n)| n).sum::<u32>(), 3);
+
+        // 16:00 UTC is already tomorrow in UTC+9
+        let later = NOW + 4 * 3600;
+        let summary = summarize(&[StatEvent::Command { at: later }], later, 9 * 3600);
+        assert_eq!(summary.commands_per_day.last().unwrap(), &("2024-03-16".to_string(), 1));
+    }
+
+    #[test]
+    fn test_busiest_hours_use_local_time() {
+        let events = [
+            StatEvent::Command { at: NOW },
+            StatEvent::Command { at: NOW + 1800 },
+            StatEvent::Command { at: NOW - 3 * DAY },
+            StatEvent::Command { at: NOW - 10 * DAY },
+        ];
+        let summary = summarize(&events, NOW + 1800, -5 * 3600);
+        assert_eq!(summary.commands_by_hour[7], 3);
+        assert_eq!(summary.commands_by_hour.iter().sum::<u32>(), 3);
+    }
+
+    #[test]
+    fn test_opened_tabs_and_panes_come_from_open_counts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut recorder = StatsRecorder::new(dir.path().join("stats.jsonl"));
+        recorder.count_open(1, 1);
+        // A new tab, then a split
+        recorder.count_open(2, 2);
+        recorder.count_open(2, 3);
+        // Closing isn't opening
+        recorder.count_open(1, 1);
+        let summary = summarize(&recorder.buffer, unix_now(), 0);
+        assert_eq!((summary.tabs_opened, summary.panes_opened), (1, 1));
+    }
+
+    #[test]
+    fn test_events_round_trip_through_the_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("stats.jsonl");
+        let start = Instant::now();
+        let mut recorder = StatsRecorder::new(path.clone());
+        recorder.focus(Some(Path::new("/code/api")), start);
+        recorder.record(StatEvent::Command { at: 5 });
+        recorder.flush(start + Duration::from_secs(30));
+        recorder.record(StatEvent::Command { at: 6 });
+        recorder.flush(start + Duration::from_secs(31));
+
+        let events = load(&path);
+        assert!(matches!(&events[0], StatEvent::Command { at: 5 }));
+        assert!(matches!(&events[1], StatEvent::Focus { project, seconds: 30, .. } if project == "/code/api"));
+        assert!(matches!(&events[2], StatEvent::Command { at: 6 }));
+        // The second, 1s focus span is too short to keep
+        assert_eq!(events.len(), 3);
+
+        recorder.delete_all().unwrap();
+        assert!(load(&path).is_empty());
+    }
+
+    #[test]
+    fn test_idle_time_isnt_counted() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let start = Instant::now();
+        let mut recorder = StatsRecorder::new(dir.path().join("stats.jsonl"));
+        recorder.note_input(start);
+        recorder.focus(Some(Path::new("/code/api")), start);
+        // Last input 40s in; the next frame comes long after
+        recorder.note_input(start + Duration::from_secs(40));
+        recorder.focus(Some(Path::new("/code/api")), start + Duration::from_secs(40) + IDLE_AFTER * 2);
+        assert_eq!(recorder.buffer, [focus("/code/api", recorder.buffer[0].at(), 40)]);
+        assert!(recorder.focus.is_none());
+
+        // Flushed while idle, a span ends at the last input too
+        let later = start + IDLE_AFTER * 3;
+        recorder.note_input(later);
+        recorder.focus(Some(Path::new("/code/web")), later);
+        recorder.note_input(later + Duration::from_secs(20));
+        recorder.flush(later + Duration::from_secs(30) + IDLE_AFTER);
+        let seconds: Vec<u64> = load(&recorder.path)
+            .iter()
+            .filter_map(|event| match event {
+                StatEvent::Focus { seconds, .. } => Some(*seconds),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(seconds, [40, 20]);
+    }
+
+    #[test]
+    fn test_old_events_are_pruned() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("stats.jsonl");
+        let events = [StatEvent::Command { at: NOW - 30 * DAY }, StatEvent::Command { at: NOW - DAY }];
+        append(&path, &events).unwrap();
+        prune(&path, NOW - KEEP_DAYS * DAY).unwrap();
+        assert_eq!(load(&path), [StatEvent::Command { at: NOW - DAY }]);
+        // Nothing to drop leaves the file alone
+        prune(&path, NOW - KEEP_DAYS * DAY).unwrap();
+        assert_eq!(load(&path).len(), 1);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(42), "42s");
+        assert_eq!(format_duration(600), "10m");
+        assert_eq!(format_duration(7500), "2h 05m");
+    }
+}
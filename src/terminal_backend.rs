@@ -0,0 +1,199 @@
+//! Terminal Backend Handle
+//!
+//! The part of `egui_term::TerminalBackend` the app uses, behind a trait so
+//! the app can run without PTYs. Tests, and runs with `VIBETERM_HEADLESS`
+//! set, get a `MockBackend` with an empty screen instead of a shell.
+//! Otherwise a `PtyBackend` also scans the shell's output for progress
+//! reports.
+
+use alacritty_terminal::grid::Grid;
+use alacritty_terminal::term::cell::Cell;
+use egui_term::{BackendCommand, TerminalBackend, TerminalMode};
+
+use crate::progress::{ProgressFeed, ProgressReport};
+
+/// Screen size of mock terminals (lines, columns)
+const MOCK_SIZE: (usize, usize) = (24, 80);
+
+/// Run without PTYs (tests, or `VIBETERM_HEADLESS` set)
+pub fn headless() -> bool {
+    cfg!(test) || std::env::var_os("VIBETERM_HEADLESS").is_some()
+}
+
+/// A terminal the app can drive and read back
+pub trait TerminalBackendHandle {
+    fn id(&self) -> u64;
+
+    fn process_command(&mut self, cmd: BackendCommand);
+
+    /// Pull the latest screen from the terminal
+    fn sync(&mut self);
+
+    /// Screen as of the last sync
+    fn grid(&self) -> &Grid<Cell>;
+
+    /// Terminal modes as of the last sync
+    fn mode(&self) -> TerminalMode;
+
+    /// Height of one line (px)
+    fn cell_height(&self) -> u16;
+
+    /// Width of one column (px)
+    fn cell_width(&self) -> u16;
+
+    /// Selected text
+    fn selectable_content(&self) -> String;
+
+    /// The real backend, for drawing with `TerminalView` (`None` when mocked)
+    fn as_backend_mut(&mut self) -> Option<&mut TerminalBackend>;
+
+    /// Latest progress report in the output since the last call
+    fn take_progress(&mut self) -> Option<ProgressReport> {
+        None
+    }
+
+    /// Input written to a mock terminal so far (nothing for a real one)
+    #[cfg(test)]
+    fn written(&self) -> &[u8] {
+        &[]
+    }
+}
+
+/// egui_term backend with the progress reports its output tap found
+pub struct PtyBackend {
+    backend: TerminalBackend,
+    progress: ProgressFeed,
+}
+
+impl PtyBackend {
+    /// `progress` is fed from the backend's output tap
+    pub fn new(backend: TerminalBackend, progress: ProgressFeed) -> Self {
+        Self { backend, progress }
+    }
+}
+
+impl TerminalBackendHandle for PtyBackend {
+    fn id(&self) -> u64 {
+        self.backend.id
+    }
+
+    fn process_command(&mut self, cmd: BackendCommand) {
+        self.backend.process_command(cmd);
+    }
+
+    fn sync(&mut self) {
+        self.backend.sync();
+    }
+
+    fn grid(&self) -> &Grid<Cell> {
+        &self.backend.last_content().grid
+    }
+
+    fn mode(&self) -> TerminalMode {
+        self.backend.last_content().terminal_mode
+    }
+
+    fn cell_height(&self) -> u16 {
+        self.backend.last_content().terminal_size.cell_height
+    }
+
+    fn cell_width(&self) -> u16 {
+        self.backend.last_content().terminal_size.cell_width
+    }
+
+    fn selectable_content(&self) -> String {
+        self.backend.selectable_content()
+    }
+
+    fn as_backend_mut(&mut self) -> Option<&mut TerminalBackend> {
+        Some(&mut self.backend)
+    }
+
+    fn take_progress(&mut self) -> Option<ProgressReport> {
+        self.progress.take()
+    }
+}
+
+/// Terminal without a shell: an empty screen that ignores input (tests
+/// can read back what was written to it)
+pub struct MockBackend {
+    id: u64,
+    grid: Grid<Cell>,
+    written: Vec<u8>,
+}
+
+impl MockBackend {
+    pub fn new(id: u64) -> Self {
+        Self {
+            id,
+            grid: Grid::new(MOCK_SIZE.0, MOCK_SIZE.1, 0),
+            written: Vec::new(),
+        }
+    }
+}
+
+impl TerminalBackendHandle for MockBackend {
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn process_command(&mut self, cmd: BackendCommand) {
+        if let BackendCommand::Write(input) = cmd {
+            self.written.extend(input);
+        }
+    }
+
+    fn sync(&mut self) {}
+
+    fn grid(&self) -> &Grid<Cell> {
+        &self.grid
+    }
+
+    fn mode(&self) -> TerminalMode {
+        TerminalMode::empty()
+    }
+
+    fn cell_height(&self) -> u16 {
+        1
+    }
+
+    fn cell_width(&self) -> u16 {
+        1
+    }
+
+    fn selectable_content(&self) -> String {
+        String::new()
+    }
+
+    fn as_backend_mut(&mut self) -> Option<&mut TerminalBackend> {
+        None
+    }
+
+    #[cfg(test)]
+    fn written(&self) -> &[u8] {
+        &self.written
+    }
+}
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::index::{Column, Line};
+    use alacritty_terminal::term::test::TermSize;
+    use alacritty_terminal::term::{Config, Term};
+    use alacritty_terminal::vte::ansi::Processor;
+
+    #[test]
+    fn test_resize_reflows_long_lines() {
+        // The PTY resize path relies on this: narrowing a pane and widening
+        // it again must give long lines back, not leave them cut
+        let line = "0123456789".repeat(4);
+        let mut term = Term::new(Config::default(), &TermSize::new(40, 4), VoidListener);
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut term, format!("{}\r\n$ ", line).as_bytes());
+
+        term.resize(TermSize::new(10, 4));
+        term.resize(TermSize::new(40, 4));
+        let row: String = (0..40).map(|col| term.grid()[Line(0)][Column(col)].c).collect();
+        assert_eq!(row, line);
+    }
+}
@@ -2,8 +2,9 @@
 //!
 //! TUI-style aesthetics with user-customizable colors
 
+use std::cell::Cell;
 use egui::{Color32, CornerRadius, FontFamily, FontId, Stroke, Style, Visuals};
-use crate::config::{Config, RuntimeTheme};
+use crate::config::{Config, FontConfig, RuntimeTheme};
 
 // ========================================
 // Box Drawing Characters (TUI Style)
@@ -60,6 +61,7 @@ pub const TAB_BAR_HEIGHT: f32 = 24.0;
 #[allow(dead_code)]
 pub const SIDEBAR_WIDTH: f32 = 200.0;
 pub const STATUS_BAR_HEIGHT: f32 = 18.0;
+pub const BREADCRUMB_BAR_HEIGHT: f32 = 20.0;
 pub const DIVIDER_WIDTH: f32 = 1.0;
 
 // ========================================
@@ -144,97 +146,196 @@ pub fn apply_theme(ctx: &egui::Context, theme: &RuntimeTheme) {
     ctx.set_style(style);
 }
 
-/// Configure monospace fonts for terminal aesthetic with CJK support
-pub fn configure_fonts(ctx: &egui::Context) {
+/// System CJK font paths, tried in order
+#[cfg(target_os = "macos")]
+pub const CJK_FONT_PATHS: &[&str] = &[
+    "/System/Library/Fonts/AppleSDGothicNeo.ttc",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/Library/Fonts/Arial Unicode.ttf",
+];
+
+/// System CJK font paths, tried in order
+#[cfg(target_os = "linux")]
+pub const CJK_FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/truetype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+];
+
+/// System CJK font paths, tried in order
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub const CJK_FONT_PATHS: &[&str] = &[];
+
+/// Font name of the system CJK fallback
+pub const CJK_FONT: &str = "CJK";
+
+/// Font name of the user's `font.fallback_font`
+pub const CUSTOM_FONT: &str = "Custom";
+
+/// Built-in fonts plus the fallbacks enabled in `config`
+///
+/// The custom font is searched before the CJK font, so a Nerd Font that
+/// also covers CJK wins. Files that can't be read are skipped. The CJK font
+/// is passed in (see `read_cjk_font`), as reading it can be slow.
+pub fn font_definitions(config: &FontConfig, cjk_font: Option<&[u8]>) -> egui::FontDefinitions {
     let mut fonts = egui::FontDefinitions::default();
 
-    // Try to load system CJK font for Korean/Japanese/Chinese support
-    #[cfg(target_os = "macos")]
-    {
-        // macOS system Korean font paths
-        let cjk_font_paths = [
-            "/System/Library/Fonts/AppleSDGothicNeo.ttc",
-            "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
-            "/Library/Fonts/Arial Unicode.ttf",
-        ];
-
-        for path in &cjk_font_paths {
-            if let Ok(font_data) = std::fs::read(path) {
-                fonts.font_data.insert(
-                    "CJK".to_owned(),
-                    egui::FontData::from_owned(font_data).into(),
-                );
-                log::info!("Loaded CJK font from: {}", path);
-                break;
+    let custom = config.fallback_font.trim();
+    if !custom.is_empty() {
+        let home = dirs::home_dir().unwrap_or_default();
+        let path = crate::settings_bundle::expand_home(custom, &home);
+        match std::fs::read(&path) {
+            Ok(font_data) => {
+                fonts.font_data.insert(CUSTOM_FONT.to_owned(), egui::FontData::from_owned(font_data).into());
+                log::info!("Loaded fallback font from: {}", path.display());
             }
+            Err(e) => log::warn!("Failed to load fallback font {}: {}", path.display(), e),
         }
     }
 
-    #[cfg(target_os = "linux")]
-    {
-        // Linux CJK font paths
-        let cjk_font_paths = [
-            "/usr/share/fonts/truetype/noto/NotoSansCJK-Regular.ttc",
-            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
-            "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
-        ];
-
-        for path in &cjk_font_paths {
-            if let Ok(font_data) = std::fs::read(path) {
-                fonts.font_data.insert(
-                    "CJK".to_owned(),
-                    egui::FontData::from_owned(font_data).into(),
-                );
-                log::info!("Loaded CJK font from: {}", path);
-                break;
+    // System CJK font for Korean/Japanese/Chinese support
+    if let Some(font_data) = cjk_font.filter(|_| config.cjk_fallback) {
+        fonts.font_data.insert(CJK_FONT.to_owned(), egui::FontData::from_owned(font_data.to_vec()).into());
+    }
+
+    // Add fallbacks for both Proportional and Monospace
+    for name in [CUSTOM_FONT, CJK_FONT] {
+        if fonts.font_data.contains_key(name) {
+            for family in [FontFamily::Proportional, FontFamily::Monospace] {
+                fonts.families.entry(family).or_default().push(name.to_owned());
             }
         }
     }
 
-    // Add CJK font as fallback for both Proportional and Monospace
-    if fonts.font_data.contains_key("CJK") {
-        fonts
-            .families
-            .entry(FontFamily::Proportional)
-            .or_default()
-            .push("CJK".to_owned());
-
-        fonts
-            .families
-            .entry(FontFamily::Monospace)
-            .or_default()
-            .push("CJK".to_owned());
+    fonts
+}
+
+/// Configure monospace fonts for terminal aesthetic with CJK support
+pub fn configure_fonts(ctx: &egui::Context, config: &FontConfig, cjk_font: Option<&[u8]>) {
+    ctx.set_fonts(font_definitions(config, cjk_font));
+}
+
+/// Read the first of `paths` that can be read
+pub fn read_cjk_font(paths: &[&str]) -> Option<Vec<u8>> {
+    paths.iter().find_map(|path| {
+        let font_data = std::fs::read(path).ok()?;
+        log::info!("Loaded CJK font from: {}", path);
+        Some(font_data)
+    })
+}
+
+/// Progress of a CJK font read in the background
+#[derive(Debug, PartialEq, Eq)]
+pub enum CjkFontLoad {
+    Pending,
+    Found(Vec<u8>),
+    /// None of the paths could be read: only the built-in fonts are used
+    Missing,
+}
+
+/// A CJK font read on its own thread, so a slow disk doesn't hold up the window
+pub struct CjkFontLoader {
+    rx: std::sync::mpsc::Receiver<Option<Vec<u8>>>,
+}
+
+impl CjkFontLoader {
+    pub fn start(paths: &'static [&'static str]) -> Self {
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(read_cjk_font(paths));
+        });
+        Self { rx }
     }
 
-    ctx.set_fonts(fonts);
+    pub fn poll(&self) -> CjkFontLoad {
+        match self.rx.try_recv() {
+            Ok(Some(font_data)) => CjkFontLoad::Found(font_data),
+            Ok(None) | Err(std::sync::mpsc::TryRecvError::Disconnected) => CjkFontLoad::Missing,
+            Err(std::sync::mpsc::TryRecvError::Empty) => CjkFontLoad::Pending,
+        }
+    }
 }
 
 /// Get terminal theme for egui_term
 pub fn get_terminal_theme(config: &Config) -> egui_term::TerminalTheme {
-    use egui_term::{ColorPalette, TerminalTheme};
+    egui_term::TerminalTheme::new(Box::new(terminal_palette(config)))
+}
+
+/// Terminal theme for unfocused panes
+///
+/// Every color is blended toward the background by
+/// `ui.inactive_pane_desaturation`, so the focused terminal stands out.
+pub fn get_dim_terminal_theme(config: &Config) -> egui_term::TerminalTheme {
+    let mut palette = terminal_palette(config);
+    let factor = config.ui.inactive_pane_desaturation;
+    let background = palette.background.clone();
+    let fade = |color: &mut String| *color = crate::config::blend_hex_color(color, &background, factor);
+
+    for color in [
+        &mut palette.foreground,
+        &mut palette.black,
+        &mut palette.red,
+        &mut palette.green,
+        &mut palette.yellow,
+        &mut palette.blue,
+        &mut palette.magenta,
+        &mut palette.cyan,
+        &mut palette.white,
+        &mut palette.bright_black,
+        &mut palette.bright_red,
+        &mut palette.bright_green,
+        &mut palette.bright_yellow,
+        &mut palette.bright_blue,
+        &mut palette.bright_magenta,
+        &mut palette.bright_cyan,
+        &mut palette.bright_white,
+        &mut palette.dim_foreground,
+        &mut palette.dim_black,
+        &mut palette.dim_red,
+        &mut palette.dim_green,
+        &mut palette.dim_yellow,
+        &mut palette.dim_blue,
+        &mut palette.dim_magenta,
+        &mut palette.dim_cyan,
+        &mut palette.dim_white,
+    ] {
+        fade(color);
+    }
+    if let Some(color) = palette.bright_foreground.as_mut() {
+        fade(color);
+    }
+
+    egui_term::TerminalTheme::new(Box::new(palette))
+}
 
+/// Terminal colors from the configured theme
+///
+/// egui_term only reads `#RRGGBB` (anything else panics when drawn), so each
+/// color goes through the same parsing as the rest of the UI first.
+fn terminal_palette(config: &Config) -> egui_term::ColorPalette {
     let t = &config.theme;
-    let palette = ColorPalette {
-        foreground: t.text.clone(),
-        background: t.background.clone(),
-        black: t.black.clone(),
-        red: t.red.clone(),
-        green: t.green.clone(),
-        yellow: t.yellow.clone(),
-        blue: t.blue.clone(),
-        magenta: t.magenta.clone(),
-        cyan: t.cyan.clone(),
-        white: t.white.clone(),
-        bright_black: t.bright_black.clone(),
-        bright_red: t.bright_red.clone(),
-        bright_green: t.bright_green.clone(),
-        bright_yellow: t.bright_yellow.clone(),
-        bright_blue: t.bright_blue.clone(),
-        bright_magenta: t.bright_magenta.clone(),
-        bright_cyan: t.bright_cyan.clone(),
-        bright_white: t.bright_white.clone(),
+    let hex = |color: &str| crate::config::color32_to_hex(crate::config::parse_hex_color(color));
+    egui_term::ColorPalette {
+        foreground: hex(&t.text),
+        background: hex(&t.background),
+        black: hex(&t.black),
+        red: hex(&t.red),
+        green: hex(&t.green),
+        yellow: hex(&t.yellow),
+        blue: hex(&t.blue),
+        magenta: hex(&t.magenta),
+        cyan: hex(&t.cyan),
+        white: hex(&t.white),
+        bright_black: hex(&t.bright_black),
+        bright_red: hex(&t.bright_red),
+        bright_green: hex(&t.bright_green),
+        bright_yellow: hex(&t.bright_yellow),
+        bright_blue: hex(&t.bright_blue),
+        bright_magenta: hex(&t.bright_magenta),
+        bright_cyan: hex(&t.bright_cyan),
+        bright_white: hex(&t.bright_white),
         bright_foreground: None,
-        dim_foreground: t.text_dim.clone(),
+        dim_foreground: hex(&t.text_dim),
         dim_black: "#1A0F0C".to_string(),
         dim_red: "#9A5442".to_string(),
         dim_green: "#5A7C6B".to_string(),
@@ -243,9 +344,7 @@ pub fn get_terminal_theme(config: &Config) -> egui_term::TerminalTheme {
         dim_magenta: "#7E4652".to_string(),
         dim_cyan: "#4D7373".to_string(),
         dim_white: "#A8A59A".to_string(),
-    };
-
-    TerminalTheme::new(Box::new(palette))
+    }
 }
 
 /// Get monospace font ID
@@ -253,6 +352,34 @@ pub fn mono_font(size: f32) -> FontId {
     FontId::monospace(size)
 }
 
+thread_local! {
+    /// `font.ui_scale`, set at the start of each frame
+    static UI_SCALE: Cell<f32> = const { Cell::new(1.0) };
+}
+
+/// Set the scale `ui_font` and `ui_scaled` apply
+pub fn set_ui_scale(scale: f32) {
+    UI_SCALE.with(|cell| cell.set(scale));
+}
+
+/// `value` (a font size or bar height) times `font.ui_scale`
+pub fn ui_scaled(value: f32) -> f32 {
+    value * UI_SCALE.with(Cell::get)
+}
+
+/// Monospace font for UI chrome, scaled by `font.ui_scale`
+pub fn ui_font(size: f32) -> FontId {
+    mono_font(ui_scaled(size))
+}
+
+/// Text of a setting or command that can't work on this machine
+pub fn unavailable_text(text: impl Into<String>, size: f32, theme: &RuntimeTheme) -> egui::RichText {
+    egui::RichText::new(text)
+        .font(ui_font(size))
+        .color(theme.text_dim.gamma_multiply(0.6))
+        .italics()
+}
+
 /// Get default colors (for backwards compatibility)
 #[allow(dead_code)]
 pub mod colors {
@@ -268,3 +395,42 @@ pub mod colors {
     pub const BORDER: Color32 = Color32::from_rgb(0x4A, 0x2E, 0x28);
     pub const SELECTION: Color32 = Color32::from_rgb(0x46, 0x2E, 0x26);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ui_font_applies_scale() {
+        assert_eq!(ui_font(10.0).size, 10.0);
+        set_ui_scale(1.5);
+        assert_eq!(ui_font(10.0), FontId::monospace(15.0));
+        assert_eq!(ui_scaled(24.0), 36.0);
+        // The terminal's font isn't UI chrome
+        assert_eq!(mono_font(10.0).size, 10.0);
+        set_ui_scale(1.0);
+    }
+
+    #[test]
+    fn test_missing_cjk_font_falls_back_to_built_in_fonts() {
+        let loader = CjkFontLoader::start(&["/nonexistent/cjk-a.ttc", "/nonexistent/cjk-b.ttc"]);
+        let load = loop {
+            match loader.poll() {
+                CjkFontLoad::Pending => std::thread::sleep(std::time::Duration::from_millis(1)),
+                load => break load,
+            }
+        };
+        assert_eq!(load, CjkFontLoad::Missing);
+
+        let config = FontConfig { cjk_fallback: true, ..FontConfig::default() };
+        let fonts = font_definitions(&config, None);
+        assert!(!fonts.font_data.contains_key(CJK_FONT));
+        assert_eq!(fonts.families, egui::FontDefinitions::default().families);
+
+        // Found fonts come after the built-in ones, unless turned off
+        let fonts = font_definitions(&config, Some(b"font"));
+        assert_eq!(fonts.families[&FontFamily::Monospace].last().map(String::as_str), Some(CJK_FONT));
+        let off = FontConfig { cjk_fallback: false, ..config };
+        assert!(!font_definitions(&off, Some(b"font")).font_data.contains_key(CJK_FONT));
+    }
+}
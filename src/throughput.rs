@@ -0,0 +1,267 @@
+//! Terminal Throughput
+//!
+//! Output rate of each terminal, and "firehose" mode for terminals printing
+//! faster than anyone can read. egui_term doesn't report byte counts, so
+//! the rate is estimated from the text of the lines that scrolled into the
+//! history while the pane was on screen. A pane in firehose mode is redrawn
+//! at most every [`FIREHOSE_FRAME_INTERVAL`], and its output wakes the UI
+//! no more often than that either.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use egui_term::PtyEvent;
+
+/// Output counted for the rate
+pub const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Time between redraws of a pane in firehose mode (10 fps)
+pub const FIREHOSE_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Output of one terminal
+#[derive(Debug, Default)]
+struct TerminalRate {
+    /// When bytes were seen and how many, oldest first
+    samples: VecDeque<(Instant, usize)>,
+    firehose: bool,
+}
+
+impl TerminalRate {
+    fn forget_before(&mut self, now: Instant) {
+        while self.samples.front().is_some_and(|&(at, _)| now.saturating_duration_since(at) >= RATE_WINDOW) {
+            self.samples.pop_front();
+        }
+    }
+}
+
+/// Output rates and firehose state by terminal ID
+#[derive(Debug, Default)]
+pub struct ThroughputTracker {
+    terminals: HashMap<u64, TerminalRate>,
+    gate: RepaintGate,
+}
+
+impl ThroughputTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Note `bytes` of output from a terminal
+    pub fn record(&mut self, terminal_id: u64, bytes: usize, now: Instant) {
+        if bytes == 0 {
+            return;
+        }
+        let rate = self.terminals.entry(terminal_id).or_default();
+        rate.forget_before(now);
+        rate.samples.push_back((now, bytes));
+    }
+
+    /// Output per second over the last [`RATE_WINDOW`] (bytes)
+    pub fn rate(&self, terminal_id: u64, now: Instant) -> f64 {
+        let Some(rate) = self.terminals.get(&terminal_id) else {
+            return 0.0;
+        };
+        let bytes: usize = rate.samples
+            .iter()
+            .filter(|&&(at, _)| now.saturating_duration_since(at) < RATE_WINDOW)
+            .map(|&(_, bytes)| bytes)
+            .sum();
+        bytes as f64 / RATE_WINDOW.as_secs_f64()
+    }
+
+    /// Enter firehose mode above `enter` bytes/s, leave it below `exit`;
+    /// returns whether the terminal is in it
+    pub fn update_firehose(&mut self, terminal_id: u64, now: Instant, enter: f64, exit: f64) -> bool {
+        let rate = self.rate(terminal_id, now);
+        let Some(state) = self.terminals.get_mut(&terminal_id) else {
+            return false;
+        };
+        state.firehose = next_firehose(state.firehose, rate, enter, exit);
+        self.gate.set(terminal_id, state.firehose);
+        state.firehose
+    }
+
+    /// Is the terminal in firehose mode?
+    pub fn is_firehose(&self, terminal_id: u64) -> bool {
+        self.terminals.get(&terminal_id).is_some_and(|rate| rate.firehose)
+    }
+
+    /// Drop terminals without output in the last [`RATE_WINDOW`]
+    pub fn prune(&mut self, now: Instant) {
+        let gate = &self.gate;
+        self.terminals.retain(|&terminal_id, rate| {
+            rate.forget_before(now);
+            let keep = !rate.samples.is_empty();
+            if !keep {
+                gate.set(terminal_id, false);
+            }
+            keep
+        });
+    }
+
+    /// The gate that wakes the UI for terminal events, following the
+    /// firehose state kept here
+    pub fn repaint_gate(&self) -> RepaintGate {
+        self.gate.clone()
+    }
+}
+
+/// Decides how soon terminal events repaint the UI: output from a
+/// terminal in firehose mode waits up to [`FIREHOSE_FRAME_INTERVAL`]
+#[derive(Debug, Clone, Default)]
+pub struct RepaintGate {
+    firehose: Arc<Mutex<HashSet<u64>>>,
+}
+
+impl RepaintGate {
+    fn set(&self, terminal_id: u64, firehose: bool) {
+        let mut terminals = self.firehose.lock().unwrap_or_else(|e| e.into_inner());
+        if firehose {
+            terminals.insert(terminal_id);
+        } else {
+            terminals.remove(&terminal_id);
+        }
+    }
+
+    /// Time to wait before repainting for `event`
+    pub fn delay(&self, terminal_id: u64, event: &PtyEvent) -> Duration {
+        let firehose = || self.firehose.lock().unwrap_or_else(|e| e.into_inner()).contains(&terminal_id);
+        if matches!(event, PtyEvent::Wakeup) && firehose() {
+            FIREHOSE_FRAME_INTERVAL
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// Pass terminal events from `events` on to `app` on a thread of their
+    /// own, repainting `ctx` for each as the gate allows
+    pub fn forward(self, ctx: egui::Context, events: Receiver<(u64, PtyEvent)>, app: Sender<(u64, PtyEvent)>) {
+        std::thread::spawn(move || {
+            for (terminal_id, event) in events {
+                let delay = self.delay(terminal_id, &event);
+                if app.send((terminal_id, event)).is_err() {
+                    break;
+                }
+                ctx.request_repaint_after(delay);
+            }
+        });
+    }
+}
+
+/// Firehose state after seeing `rate`, with separate thresholds to enter
+/// and leave so a rate near one of them doesn't flicker
+fn next_firehose(firehose: bool, rate: f64, enter: f64, exit: f64) -> bool {
+    if firehose {
+        rate >= exit
+    } else {
+        rate > enter
+    }
+}
+
+/// "12.5 KB/s"
+pub fn format_rate(bytes_per_sec: f64) -> String {
+    format!("{}/s", crate::thumbnails::format_size(bytes_per_sec as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KB: f64 = 1024.0;
+
+    #[test]
+    fn test_rate_covers_the_last_second() {
+        let start = Instant::now();
+        let mut tracker = ThroughputTracker::new();
+        assert_eq!(tracker.rate(1, start), 0.0);
+
+        tracker.record(1, 4096, start);
+        tracker.record(1, 1024, start + Duration::from_millis(500));
+        tracker.record(2, 100, start);
+        assert_eq!(tracker.rate(1, start + Duration::from_millis(900)), 5.0 * KB);
+        // The first sample leaves the window
+        assert_eq!(tracker.rate(1, start + Duration::from_millis(1200)), KB);
+        assert_eq!(tracker.rate(1, start + Duration::from_secs(2)), 0.0);
+
+        tracker.prune(start + Duration::from_millis(1200));
+        assert!(tracker.terminals.contains_key(&1));
+        assert!(!tracker.terminals.contains_key(&2));
+        tracker.prune(start + Duration::from_secs(2));
+        assert!(tracker.terminals.is_empty());
+    }
+
+    #[test]
+    fn test_firehose_hysteresis() {
+        assert!(!next_firehose(false, 900.0, 1000.0, 200.0));
+        assert!(next_firehose(false, 1001.0, 1000.0, 200.0));
+        // Once in, it takes the lower threshold to get out
+        assert!(next_firehose(true, 500.0, 1000.0, 200.0));
+        assert!(next_firehose(true, 200.0, 1000.0, 200.0));
+        assert!(!next_firehose(true, 199.0, 1000.0, 200.0));
+    }
+
+    #[test]
+    fn test_firehose_follows_output() {
+        let start = Instant::now();
+        let mut tracker = ThroughputTracker::new();
+        let (enter, exit) = (100.0 * KB, 10.0 * KB);
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // 20 KB every 100 ms is 200 KB/s
+        for step in 0..10 {
+            tracker.record(7, 20 * 1024, at(step * 100));
+        }
+        assert!(tracker.update_firehose(7, at(950), enter, exit));
+
+        // Slowing to 50 KB/s stays in firehose mode
+        for step in 10..20 {
+            tracker.record(7, 5 * 1024, at(step * 100));
+        }
+        assert!(tracker.update_firehose(7, at(1950), enter, exit));
+        assert!(tracker.is_firehose(7));
+
+        // Silence ends it
+        assert!(!tracker.update_firehose(7, at(3000), enter, exit));
+        assert!(!tracker.update_firehose(8, at(3000), enter, exit));
+        assert_eq!(format_rate(200.0 * KB), "200.0 KB/s");
+    }
+
+    #[test]
+    fn test_firehose_output_repaints_less_often() {
+        let start = Instant::now();
+        let mut tracker = ThroughputTracker::new();
+        let gate = tracker.repaint_gate();
+        for step in 0..10 {
+            tracker.record(7, 20 * 1024, start + Duration::from_millis(step * 100));
+        }
+        tracker.update_firehose(7, start + Duration::from_millis(950), 100.0 * KB, 10.0 * KB);
+
+        assert_eq!(gate.delay(7, &PtyEvent::Wakeup), FIREHOSE_FRAME_INTERVAL);
+        assert_eq!(gate.delay(7, &PtyEvent::Bell), Duration::ZERO);
+        assert_eq!(gate.delay(8, &PtyEvent::Wakeup), Duration::ZERO);
+
+        // A terminal gone quiet wakes the UI right away again
+        tracker.prune(start + Duration::from_secs(3));
+        assert_eq!(gate.delay(7, &PtyEvent::Wakeup), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_gated_events_wake_the_ui_context() {
+        let ctx = egui::Context::default();
+        let (repaint_tx, repaint_rx) = std::sync::mpsc::channel();
+        ctx.set_request_repaint_callback(move |info| {
+            let _ = repaint_tx.send(info.delay);
+        });
+
+        let (gate_tx, gated) = std::sync::mpsc::channel();
+        let (app_tx, app_rx) = std::sync::mpsc::channel();
+        ThroughputTracker::new().repaint_gate().forward(ctx, gated, app_tx);
+        gate_tx.send((7, PtyEvent::Wakeup)).unwrap();
+
+        let timeout = Duration::from_secs(5);
+        assert!(matches!(app_rx.recv_timeout(timeout), Ok((7, PtyEvent::Wakeup))));
+        assert_eq!(repaint_rx.recv_timeout(timeout), Ok(Duration::ZERO));
+    }
+}
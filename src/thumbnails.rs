@@ -0,0 +1,315 @@
+//! Image Thumbnails
+//!
+//! Small previews of image files for sidebar hover tooltips. Images are
+//! decoded and scaled down on the blocking pool; the UI thread only turns
+//! the finished pixels into a texture. Thumbnails are kept in a small LRU
+//! keyed by path and modification time, and dropped when the watcher
+//! reports a change under their path.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Longest side of a thumbnail (px)
+pub const THUMBNAIL_SIZE: u32 = 160;
+
+/// How long the pointer rests on an entry before its thumbnail is loaded
+pub const HOVER_DWELL: Duration = Duration::from_millis(300);
+
+/// Thumbnails kept at most
+pub const MAX_THUMBNAILS: usize = 50;
+
+/// Pixel memory kept at most (bytes)
+pub const MAX_THUMBNAIL_BYTES: usize = 10 * 1024 * 1024;
+
+/// Extensions of files that get a thumbnail
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "webp", "ico", "tif", "tiff", "tga", "qoi",
+];
+
+/// Does `path` look like an image we can decode?
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// A decoded, scaled-down image
+pub struct ThumbnailImage {
+    pub image: egui::ColorImage,
+    /// Size of the original image (px)
+    pub width: u32,
+    pub height: u32,
+    /// Size of the file (bytes)
+    pub file_size: u64,
+}
+
+/// Decode `path` and scale it to fit [`THUMBNAIL_SIZE`] (blocking)
+pub fn decode(path: &Path) -> Result<ThumbnailImage, String> {
+    let file_size = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    let image = image::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?
+        .decode()
+        .map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?;
+
+    let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8();
+    let size = [thumbnail.width() as usize, thumbnail.height() as usize];
+    Ok(ThumbnailImage {
+        image: egui::ColorImage::from_rgba_unmultiplied(size, thumbnail.as_raw()),
+        width: image.width(),
+        height: image.height(),
+        file_size,
+    })
+}
+
+/// A ready thumbnail: its texture plus what the tooltip says about the file
+#[derive(Debug, Clone)]
+pub struct Thumbnail<T> {
+    pub texture: T,
+    pub width: u32,
+    pub height: u32,
+    pub file_size: u64,
+    /// Pixel memory of the thumbnail (bytes)
+    pub bytes: usize,
+}
+
+/// Cached state of one file
+#[derive(Debug, Clone)]
+pub enum ThumbnailSlot<T> {
+    /// Being decoded
+    Pending,
+    Ready(Thumbnail<T>),
+    /// Not an image we can read; the tooltip stays plain
+    Failed,
+}
+
+struct CacheEntry<T> {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    slot: ThumbnailSlot<T>,
+}
+
+/// Least recently used thumbnails, by count and pixel memory
+///
+/// Generic over the texture so tests can use plain values.
+pub struct ThumbnailCache<T> {
+    /// Least recently used first
+    entries: Vec<CacheEntry<T>>,
+    max_entries: usize,
+    max_bytes: usize,
+}
+
+impl<T> ThumbnailCache<T> {
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            max_entries,
+            max_bytes,
+        }
+    }
+
+    /// Cached state of `path`, without counting as a use
+    pub fn peek(&self, path: &Path) -> Option<&ThumbnailSlot<T>> {
+        self.entries.iter().find(|entry| entry.path == path).map(|entry| &entry.slot)
+    }
+
+    /// Note a use of `path` as last modified at `modified`
+    ///
+    /// Returns `true` when the caller should start decoding it: it isn't
+    /// cached, or the cached thumbnail is of an older version.
+    pub fn request(&mut self, path: &Path, modified: Option<SystemTime>) -> bool {
+        if let Some(index) = self.entries.iter().position(|entry| entry.path == path) {
+            let entry = self.entries.remove(index);
+            if entry.modified == modified {
+                self.entries.push(entry);
+                return false;
+            }
+        }
+        self.entries.push(CacheEntry {
+            path: path.to_path_buf(),
+            modified,
+            slot: ThumbnailSlot::Pending,
+        });
+        self.evict();
+        true
+    }
+
+    /// Store a finished decode of `path` as it was at `modified`
+    ///
+    /// Dropped unless that exact request is still pending: the entry may
+    /// have been evicted, invalidated or requested again for a newer file.
+    pub fn insert(&mut self, path: &Path, modified: Option<SystemTime>, result: Result<Thumbnail<T>, String>) {
+        let Some(entry) = self.entries.iter_mut().find(|entry| {
+            entry.path == path && entry.modified == modified && matches!(entry.slot, ThumbnailSlot::Pending)
+        }) else {
+            return;
+        };
+        entry.slot = match result {
+            Ok(thumbnail) => ThumbnailSlot::Ready(thumbnail),
+            Err(e) => {
+                log::debug!("No thumbnail: {}", e);
+                ThumbnailSlot::Failed
+            }
+        };
+        self.evict();
+    }
+
+    /// Forget thumbnails of `path` and anything under it
+    pub fn invalidate(&mut self, path: &Path) {
+        self.entries.retain(|entry| !entry.path.starts_with(path));
+    }
+
+    /// Pixel memory of the ready thumbnails (bytes)
+    pub fn bytes(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| match &entry.slot {
+                ThumbnailSlot::Ready(thumbnail) => thumbnail.bytes,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Drop least recently used entries until both limits hold
+    ///
+    /// The most recent entry always stays, however big.
+    fn evict(&mut self) {
+        while self.entries.len() > 1 && (self.entries.len() > self.max_entries || self.bytes() > self.max_bytes) {
+            self.entries.remove(0);
+        }
+    }
+}
+
+/// Human-readable file size ("512 B", "1.4 MB")
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thumbnail(bytes: usize) -> Result<Thumbnail<&'static str>, String> {
+        Ok(Thumbnail { texture: "texture", width: 640, height: 480, file_size: 1000, bytes })
+    }
+
+    fn time(secs: u64) -> Option<SystemTime> {
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    fn is_ready<T>(cache: &ThumbnailCache<T>, path: &str) -> bool {
+        matches!(cache.peek(Path::new(path)), Some(ThumbnailSlot::Ready(_)))
+    }
+
+    #[test]
+    fn test_requests_are_cached_until_the_file_changes() {
+        let mut cache = ThumbnailCache::new(10, 1 << 20);
+        let path = Path::new("/p/a.png");
+        assert!(cache.request(path, time(1)));
+        assert!(!cache.request(path, time(1)));
+        assert!(matches!(cache.peek(path), Some(ThumbnailSlot::Pending)));
+
+        cache.insert(path, time(1), thumbnail(100));
+        assert!(is_ready(&cache, "/p/a.png"));
+        assert!(!cache.request(path, time(1)));
+
+        // A newer file is decoded again, and the old result can't land
+        assert!(cache.request(path, time(2)));
+        cache.insert(path, time(1), thumbnail(100));
+        assert!(matches!(cache.peek(path), Some(ThumbnailSlot::Pending)));
+        cache.insert(path, time(2), Err("bad header".to_string()));
+        assert!(matches!(cache.peek(path), Some(ThumbnailSlot::Failed)));
+        assert_eq!(cache.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let mut cache = ThumbnailCache::new(2, 1 << 20);
+        for path in ["/a.png", "/b.png"] {
+            cache.request(Path::new(path), time(1));
+            cache.insert(Path::new(path), time(1), thumbnail(10));
+        }
+        // Using a makes b the oldest
+        assert!(!cache.request(Path::new("/a.png"), time(1)));
+        cache.request(Path::new("/c.png"), time(1));
+        assert_eq!(cache.entries.len(), 2);
+        assert!(is_ready(&cache, "/a.png"));
+        assert!(cache.peek(Path::new("/b.png")).is_none());
+
+        // A result for an evicted request is dropped
+        cache.insert(Path::new("/b.png"), time(1), thumbnail(10));
+        assert!(cache.peek(Path::new("/b.png")).is_none());
+    }
+
+    #[test]
+    fn test_memory_limit_evicts() {
+        let mut cache = ThumbnailCache::new(10, 250);
+        for path in ["/a.png", "/b.png", "/c.png"] {
+            cache.request(Path::new(path), time(1));
+            cache.insert(Path::new(path), time(1), thumbnail(100));
+        }
+        assert_eq!(cache.bytes(), 200);
+        assert!(cache.peek(Path::new("/a.png")).is_none());
+
+        // One thumbnail over the limit still stays
+        let mut small = ThumbnailCache::new(10, 50);
+        small.request(Path::new("/big.png"), time(1));
+        small.insert(Path::new("/big.png"), time(1), thumbnail(100));
+        assert!(is_ready(&small, "/big.png"));
+    }
+
+    #[test]
+    fn test_invalidation_covers_directories() {
+        let mut cache = ThumbnailCache::new(10, 1 << 20);
+        for path in ["/p/img/a.png", "/p/img/b.png", "/p/logo.png"] {
+            cache.request(Path::new(path), time(1));
+            cache.insert(Path::new(path), time(1), thumbnail(10));
+        }
+        cache.invalidate(Path::new("/p/img/a.png"));
+        assert_eq!(cache.entries.len(), 2);
+        cache.invalidate(Path::new("/p/img"));
+        assert_eq!(cache.entries.len(), 1);
+        assert!(is_ready(&cache, "/p/logo.png"));
+
+        // A pending decode that was invalidated doesn't come back
+        cache.request(Path::new("/p/new.png"), time(1));
+        cache.invalidate(Path::new("/p/new.png"));
+        cache.insert(Path::new("/p/new.png"), time(1), thumbnail(10));
+        assert!(cache.peek(Path::new("/p/new.png")).is_none());
+    }
+
+    #[test]
+    fn test_decode_scales_down() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("wide.png");
+        image::RgbaImage::new(640, 320).save(&path).unwrap();
+        let decoded = decode(&path).unwrap();
+        assert_eq!((decoded.width, decoded.height), (640, 320));
+        assert_eq!(decoded.image.size, [160, 80]);
+
+        std::fs::write(dir.path().join("broken.png"), "not a png").unwrap();
+        assert!(decode(&dir.path().join("broken.png")).is_err());
+    }
+
+    #[test]
+    fn test_is_image_and_sizes() {
+        assert!(is_image(Path::new("a/Logo.PNG")));
+        assert!(!is_image(Path::new("a/notes.txt")));
+        assert!(!is_image(Path::new("a/png")));
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MB");
+    }
+}
@@ -0,0 +1,385 @@
+//! Move to Trash
+//!
+//! Sidebar deletes go to the user's trash (the Finder's on macOS, the
+//! freedesktop trash in `~/.local/share/Trash` elsewhere) so they can be
+//! undone. Files that can't be moved there, e.g. on another disk, are
+//! copied to a temporary backup first if they're small enough.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Largest file backed up when it can't be moved to the trash
+pub const BACKUP_SIZE_LIMIT: u64 = 16 * 1024 * 1024;
+
+/// A trash items are moved into
+#[derive(Debug, Clone)]
+enum Bin {
+    /// Items in `files`, their `.trashinfo` files in `info` (only tests
+    /// use one on macOS)
+    #[cfg_attr(target_os = "macos", allow(dead_code))]
+    Freedesktop { files: PathBuf, info: PathBuf },
+    /// The Finder's trash, through NSFileManager (which handles other
+    /// volumes' trashes and "Put Back")
+    #[cfg(target_os = "macos")]
+    Finder,
+}
+
+impl Bin {
+    #[cfg(target_os = "macos")]
+    fn user() -> Self {
+        Bin::Finder
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn user() -> Self {
+        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        let trash = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .unwrap_or_else(|| home.join(".local/share"))
+            .join("Trash");
+        Bin::Freedesktop { files: trash.join("files"), info: trash.join("info") }
+    }
+}
+
+/// Where removed items go
+#[derive(Debug, Clone)]
+pub struct Trash {
+    bin: Bin,
+    /// Directory of fallback backups
+    backups: PathBuf,
+    backup_limit: u64,
+}
+
+/// Where a removed item is now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    Trash { file: PathBuf, info: Option<PathBuf> },
+    Backup(PathBuf),
+}
+
+/// A removed item that can be restored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trashed {
+    pub original: PathBuf,
+    pub location: Location,
+}
+
+impl Trash {
+    /// The user's trash, with backups in the system temp directory
+    pub fn user() -> Self {
+        Self {
+            bin: Bin::user(),
+            backups: std::env::temp_dir().join("vibeterm-deleted"),
+            backup_limit: BACKUP_SIZE_LIMIT,
+        }
+    }
+
+    /// Move `path` to the trash, or back it up and delete it
+    pub fn remove(&self, path: &Path) -> Result<Trashed, String> {
+        let name = path
+            .file_name()
+            .ok_or_else(|| format!("Can't delete {}", path.display()))?;
+        let original = std::path::absolute(path).map_err(|e| format!("Can't delete {}: {}", path.display(), e))?;
+
+        let trash_error = match self.move_to_trash(&original, name) {
+            Ok(location) => return Ok(Trashed { original, location }),
+            Err(e) => e,
+        };
+
+        let metadata = std::fs::symlink_metadata(&original)
+            .map_err(|e| format!("Can't delete {}: {}", path.display(), e))?;
+        if !metadata.is_file() || metadata.len() > self.backup_limit {
+            return Err(format!("Can't move {} to the trash: {}", name.to_string_lossy(), trash_error));
+        }
+
+        std::fs::create_dir_all(&self.backups)
+            .map_err(|e| format!("Failed to create backup dir: {}", e))?;
+        let backup = unique_path(&self.backups, name, None);
+        std::fs::copy(&original, &backup)
+            .map_err(|e| format!("Failed to back up {}: {}", name.to_string_lossy(), e))?;
+        if let Err(e) = std::fs::remove_file(&original) {
+            let _ = std::fs::remove_file(&backup);
+            return Err(format!("Failed to delete {}: {}", name.to_string_lossy(), e));
+        }
+        log::info!("Deleted {:?} with a backup ({})", original, trash_error);
+        Ok(Trashed { original, location: Location::Backup(backup) })
+    }
+
+    fn move_to_trash(&self, original: &Path, name: &std::ffi::OsStr) -> Result<Location, String> {
+        match &self.bin {
+            Bin::Freedesktop { files, info } => move_to_freedesktop_trash(files, info, original, name),
+            #[cfg(target_os = "macos")]
+            Bin::Finder => move_to_finder_trash(original),
+        }
+    }
+}
+
+/// Move `original` into a freedesktop trash
+fn move_to_freedesktop_trash(files: &Path, info_dir: &Path, original: &Path, name: &std::ffi::OsStr) -> Result<Location, String> {
+    std::fs::create_dir_all(files).map_err(|e| e.to_string())?;
+    let file = unique_path(files, name, Some(info_dir));
+
+    // The info file is written first, claiming the name
+    std::fs::create_dir_all(info_dir).map_err(|e| e.to_string())?;
+    let info = info_path(info_dir, &file);
+    let mut info_file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&info)
+        .map_err(|e| e.to_string())?;
+    let written = write!(
+        info_file,
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode(original),
+        deletion_date(SystemTime::now()),
+    );
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&info);
+        return Err(e.to_string());
+    }
+
+    if let Err(e) = std::fs::rename(original, &file) {
+        let _ = std::fs::remove_file(&info);
+        return Err(e.to_string());
+    }
+    Ok(Location::Trash { file, info: Some(info) })
+}
+
+/// Move `original` to the Finder's trash, where "Put Back" knows it
+#[cfg(target_os = "macos")]
+fn move_to_finder_trash(original: &Path) -> Result<Location, String> {
+    use objc2::rc::Retained;
+    use objc2_foundation::{NSFileManager, NSString, NSURL};
+
+    let path = original.to_str().ok_or("the path isn't valid UTF-8")?;
+    let mut resulting: Option<Retained<NSURL>> = None;
+    // SAFETY: `url` is a file URL, and `resulting` lives past the call
+    unsafe {
+        let url = NSURL::fileURLWithPath(&NSString::from_str(path));
+        NSFileManager::defaultManager()
+            .trashItemAtURL_resultingItemURL_error(&url, Some(&mut resulting))
+            .map_err(|e| e.localizedDescription().to_string())?;
+    }
+    // SAFETY: `path` only reads the URL
+    let file = resulting
+        .and_then(|url| unsafe { url.path() })
+        .map(|path| PathBuf::from(path.to_string()))
+        .ok_or("the trash didn't say where it went")?;
+    Ok(Location::Trash { file, info: None })
+}
+
+impl Trashed {
+    /// File name of the removed item
+    pub fn name(&self) -> String {
+        self.original
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.original.display().to_string())
+    }
+
+    /// Put the item back where it was
+    ///
+    /// Fails rather than overwrite something created there since.
+    pub fn restore(&self) -> Result<(), String> {
+        if std::fs::symlink_metadata(&self.original).is_ok() {
+            return Err(format!("Can't restore {}: it already exists", self.name()));
+        }
+        if let Some(parent) = self.original.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Can't restore {}: {}", self.name(), e))?;
+        }
+
+        match &self.location {
+            Location::Trash { file, info } => {
+                std::fs::rename(file, &self.original)
+                    .map_err(|e| format!("Can't restore {}: {}", self.name(), e))?;
+                if let Some(info) = info {
+                    let _ = std::fs::remove_file(info);
+                }
+            }
+            Location::Backup(backup) => {
+                std::fs::copy(backup, &self.original)
+                    .map_err(|e| format!("Can't restore {}: {}", self.name(), e))?;
+                let _ = std::fs::remove_file(backup);
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete the temporary backup once undo is no longer offered
+    ///
+    /// Items in the trash stay there.
+    pub fn discard_backup(&self) {
+        if let Location::Backup(backup) = &self.location {
+            let _ = std::fs::remove_file(backup);
+        }
+    }
+}
+
+/// Delete a file or directory for good
+pub fn delete_permanently(path: &Path) -> Result<(), String> {
+    let metadata = std::fs::symlink_metadata(path).map_err(|e| format!("Can't delete {}: {}", path.display(), e))?;
+    let result = if metadata.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    result.map_err(|e| format!("Failed to delete {}: {}", path.display(), e))
+}
+
+/// `dir/name`, or `dir/name 2`, `dir/name 3`... when taken (also in `info`)
+fn unique_path(dir: &Path, name: &std::ffi::OsStr, info: Option<&Path>) -> PathBuf {
+    let taken = |path: &Path| {
+        std::fs::symlink_metadata(path).is_ok() || info.is_some_and(|info| info_path(info, path).exists())
+    };
+    let first = dir.join(name);
+    if !taken(&first) {
+        return first;
+    }
+    let name = Path::new(name);
+    let stem = name.file_stem().unwrap_or(name.as_os_str()).to_string_lossy();
+    let ext = name.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+    (2..)
+        .map(|n| dir.join(format!("{} {}{}", stem, n, ext)))
+        .find(|path| !taken(path))
+        .unwrap_or(first)
+}
+
+/// `.trashinfo` file of a trashed item
+fn info_path(info_dir: &Path, file: &Path) -> PathBuf {
+    let mut name = file.file_name().unwrap_or_default().to_os_string();
+    name.push(".trashinfo");
+    info_dir.join(name)
+}
+
+/// Path with everything but unreserved characters and `/` escaped
+fn percent_encode(path: &Path) -> String {
+    let mut encoded = String::new();
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// `YYYY-MM-DDThh:mm:ss` of `time` (UTC)
+fn deletion_date(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year, month, day, rest / 3600, rest % 3600 / 60, rest % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn trash_in(dir: &Path) -> Trash {
+        Trash {
+            bin: Bin::Freedesktop { files: dir.join("Trash/files"), info: dir.join("Trash/info") },
+            backups: dir.join("backups"),
+            backup_limit: 1024,
+        }
+    }
+
+    #[test]
+    fn test_trash_and_restore() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let trash = trash_in(dir.path());
+        let file = dir.path().join("my file.rs");
+        let folder = dir.path().join("src");
+        std::fs::write(&file, "fn main() {}").unwrap();
+        std::fs::create_dir_all(folder.join("ui")).unwrap();
+
+        let trashed_file = trash.remove(&file).unwrap();
+        let trashed_dir = trash.remove(&folder).unwrap();
+        assert!(!file.exists() && !folder.exists());
+        let Location::Trash { file: in_trash, info: Some(info) } = &trashed_file.location else {
+            panic!("expected the trash: {:?}", trashed_file.location);
+        };
+        assert_eq!(std::fs::read_to_string(in_trash).unwrap(), "fn main() {}");
+        let info = std::fs::read_to_string(info).unwrap();
+        assert!(info.starts_with("[Trash Info]\nPath=/"), "{}", info);
+        assert!(info.contains("my%20file.rs\nDeletionDate="), "{}", info);
+
+        trashed_file.restore().unwrap();
+        trashed_dir.restore().unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "fn main() {}");
+        assert!(folder.join("ui").is_dir());
+        assert_eq!(std::fs::read_dir(dir.path().join("Trash/info")).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_trash_renames_on_collision_and_restore_keeps_newer_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let trash = trash_in(dir.path());
+        let file = dir.path().join("notes.md");
+
+        std::fs::write(&file, "first").unwrap();
+        let first = trash.remove(&file).unwrap();
+        std::fs::write(&file, "second").unwrap();
+        let second = trash.remove(&file).unwrap();
+        let Location::Trash { file: second_file, .. } = &second.location else { panic!() };
+        assert_eq!(second_file.file_name().unwrap(), "notes 2.md");
+
+        second.restore().unwrap();
+        assert!(first.restore().unwrap_err().contains("already exists"));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "second");
+    }
+
+    #[test]
+    fn test_backup_fallback() {
+        let dir = tempfile::TempDir::new().unwrap();
+        // A file where the trash should be makes moving there fail
+        std::fs::write(dir.path().join("Trash"), "").unwrap();
+        let trash = trash_in(dir.path());
+
+        let small = dir.path().join("small.txt");
+        std::fs::write(&small, "keep me").unwrap();
+        let trashed = trash.remove(&small).unwrap();
+        let Location::Backup(backup) = &trashed.location else { panic!("expected a backup") };
+        assert!(!small.exists() && backup.exists());
+        trashed.restore().unwrap();
+        assert_eq!(std::fs::read_to_string(&small).unwrap(), "keep me");
+        assert!(!backup.exists());
+
+        // Too big to back up, and directories aren't copied
+        let big = dir.path().join("big.bin");
+        std::fs::write(&big, vec![0u8; 2048]).unwrap();
+        assert!(trash.remove(&big).unwrap_err().starts_with("Can't move big.bin to the trash"));
+        assert!(big.exists());
+        std::fs::create_dir(dir.path().join("folder")).unwrap();
+        assert!(trash.remove(&dir.path().join("folder")).is_err());
+
+        let trashed = trash.remove(&small).unwrap();
+        trashed.discard_backup();
+        assert!(trashed.restore().is_err());
+    }
+
+    #[test]
+    fn test_deletion_date() {
+        assert_eq!(deletion_date(UNIX_EPOCH), "1970-01-01T00:00:00");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(deletion_date(leap_day), "2024-02-29T12:34:56");
+    }
+}
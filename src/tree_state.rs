@@ -0,0 +1,279 @@
+//! Sidebar Tree State
+//!
+//! Remembers which directories of a project's sidebar were expanded, and
+//! the selected entry, in `~/.local/share/vibeterm/tree_state/<hash>.toml`
+//! so the tree looks the same when the project is opened again.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+use crate::ui::{update_tree_flags, FileEntry};
+
+/// Expanded directories saved per project
+pub const MAX_EXPANDED: usize = 200;
+
+/// State of projects not visited for this long is deleted
+pub const MAX_AGE: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+/// Saved sidebar state of one project root
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TreeState {
+    /// Project root, checked on load in case of a file name collision
+    pub root: PathBuf,
+    /// Expanded directories relative to the root, parents first
+    pub expanded: Vec<PathBuf>,
+    /// Selected entry relative to the root
+    pub selected: Option<PathBuf>,
+    /// Unix time of the last save
+    pub last_visited: u64,
+}
+
+/// Get the tree state directory path
+pub fn state_dir() -> PathBuf {
+    Config::data_dir().join("tree_state")
+}
+
+/// State file of `root` in `dir`
+pub fn state_path(dir: &Path, root: &Path) -> PathBuf {
+    // FNV-1a, so file names stay the same across builds
+    let hash = root
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3));
+    dir.join(format!("{:016x}.toml", hash))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+impl TreeState {
+    /// Record the expanded directories and selection of a sidebar tree
+    pub fn capture(root: &Path, entries: &[FileEntry], selected: Option<usize>, now: SystemTime) -> Self {
+        let relative = |entry: &FileEntry| entry.path.strip_prefix(root).ok().map(Path::to_path_buf);
+        Self {
+            root: root.to_path_buf(),
+            expanded: entries
+                .iter()
+                .filter(|entry| entry.is_dir && entry.is_expanded)
+                .filter_map(relative)
+                .take(MAX_EXPANDED)
+                .collect(),
+            selected: selected.and_then(|idx| entries.get(idx)).and_then(relative),
+            last_visited: unix_secs(now),
+        }
+    }
+
+    /// Load the saved state of `root`, if any
+    pub fn load(dir: &Path, root: &Path) -> Option<Self> {
+        let contents = std::fs::read_to_string(state_path(dir, root)).ok()?;
+        let state: Self = toml::from_str(&contents)
+            .map_err(|e| log::warn!("Ignoring corrupt tree state for {:?}: {}", root, e))
+            .ok()?;
+        (state.root == root).then_some(state)
+    }
+
+    /// Save to `dir`, creating it if needed
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create tree state dir: {}", e))?;
+
+        let toml_string = toml::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize tree state: {}", e))?;
+
+        std::fs::write(state_path(dir, &self.root), toml_string)
+            .map_err(|e| format!("Failed to write tree state: {}", e))
+    }
+
+    /// Rebuild a tree from the top-level `entries`, expanding the saved
+    /// directories level by level
+    ///
+    /// `load_children(dir, depth)` lists a directory like the sidebar does.
+    /// Directories that no longer exist are skipped. Returns the entries
+    /// and the index of the restored selection.
+    pub fn restore(
+        &self,
+        entries: Vec<FileEntry>,
+        mut load_children: impl FnMut(&Path, usize) -> Vec<FileEntry>,
+    ) -> (Vec<FileEntry>, Option<usize>) {
+        let expanded: HashSet<PathBuf> = self.expanded.iter().map(|path| self.root.join(path)).collect();
+        let mut entries: Vec<FileEntry> = entries.into_iter().filter(|entry| entry.depth == 0).collect();
+
+        // Children are inserted right after their parent, so nested saved
+        // directories are reached later in the same pass
+        let mut idx = 0;
+        while idx < entries.len() {
+            let entry = &mut entries[idx];
+            if entry.is_dir && expanded.contains(&entry.path) {
+                entry.is_expanded = true;
+                let children = load_children(&entry.path, entry.depth + 1);
+                entries.splice(idx + 1..idx + 1, children);
+            }
+            idx += 1;
+        }
+        update_tree_flags(&mut entries);
+
+        let selected = self.selected.as_ref().and_then(|selected| {
+            let path = self.root.join(selected);
+            entries.iter().position(|entry| entry.path == path)
+        });
+        (entries, selected)
+    }
+}
+
+/// Every readable state in `dir`, most recently visited first
+pub fn saved_states(dir: &Path) -> Vec<TreeState> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut states: Vec<TreeState> = read_dir
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .filter_map(|contents| toml::from_str(&contents).ok())
+        .collect();
+    states.sort_by_key(|state| std::cmp::Reverse(state.last_visited));
+    states
+}
+
+/// Delete state files in `dir` not saved within `max_age` of `now`
+///
+/// Returns how many were removed. Unreadable files are removed too.
+pub fn prune(dir: &Path, now: SystemTime, max_age: Duration) -> usize {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let cutoff = unix_secs(now).saturating_sub(max_age.as_secs());
+    let mut removed = 0;
+    for path in read_dir.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.extension().is_none_or(|ext| ext != "toml") {
+            continue;
+        }
+        let stale = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| toml::from_str::<TreeState>(&contents).ok())
+            .is_none_or(|state| state.last_visited < cutoff);
+        if stale && std::fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(root: &Path, path: &str, is_dir: bool, depth: usize) -> FileEntry {
+        let path = root.join(path);
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        FileEntry::new(name, path, is_dir, depth)
+    }
+
+    /// `root/{src/{ui/{mod.rs}, main.rs}, docs/, README.md}`
+    fn children(root: &Path, dir: &Path, depth: usize) -> Vec<FileEntry> {
+        match dir.strip_prefix(root).unwrap().to_str().unwrap() {
+            "src" => vec![entry(root, "src/ui", true, depth), entry(root, "src/main.rs", false, depth)],
+            "src/ui" => vec![entry(root, "src/ui/mod.rs", false, depth)],
+            _ => Vec::new(),
+        }
+    }
+
+    fn top_level(root: &Path) -> Vec<FileEntry> {
+        vec![
+            entry(root, "docs", true, 0),
+            entry(root, "src", true, 0),
+            entry(root, "README.md", false, 0),
+        ]
+    }
+
+    fn names(entries: &[FileEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_capture_and_restore_nested_expansion() {
+        let root = Path::new("/work/project");
+        let (mut entries, _) = TreeState::default().restore(top_level(root), |_, _| unreachable!());
+        assert_eq!(names(&entries), ["docs", "src", "README.md"]);
+
+        // Expand src, then src/ui, and select mod.rs
+        entries[1].is_expanded = true;
+        entries.splice(2..2, children(root, &root.join("src"), 1));
+        entries[2].is_expanded = true;
+        entries.splice(3..3, children(root, &root.join("src/ui"), 2));
+        let state = TreeState::capture(root, &entries, Some(3), UNIX_EPOCH);
+        assert_eq!(state.expanded, [PathBuf::from("src"), PathBuf::from("src/ui")]);
+        assert_eq!(state.selected, Some(PathBuf::from("src/ui/mod.rs")));
+
+        // A deep scan comes back; only the saved directories open
+        let mut scanned = top_level(root);
+        scanned.push(entry(root, "src/main.rs", false, 1));
+        let (restored, selected) = state.restore(scanned, |dir, depth| children(root, dir, depth));
+        assert_eq!(names(&restored), ["docs", "src", "ui", "mod.rs", "main.rs", "README.md"]);
+        assert!(restored[1].is_expanded && restored[2].is_expanded && !restored[0].is_expanded);
+        assert_eq!(selected, Some(3));
+    }
+
+    #[test]
+    fn test_restore_skips_missing_paths() {
+        let root = Path::new("/work/project");
+        let state = TreeState {
+            root: root.to_path_buf(),
+            expanded: vec![PathBuf::from("gone"), PathBuf::from("src/ui")],
+            selected: Some(PathBuf::from("gone/file.rs")),
+            last_visited: 0,
+        };
+        // src/ui can't open while src is collapsed
+        let (restored, selected) = state.restore(top_level(root), |dir, depth| children(root, dir, depth));
+        assert_eq!(names(&restored), ["docs", "src", "README.md"]);
+        assert_eq!(selected, None);
+    }
+
+    #[test]
+    fn test_capture_caps_expanded_dirs() {
+        let root = Path::new("/work/project");
+        let entries: Vec<FileEntry> = (0..MAX_EXPANDED + 10)
+            .map(|i| {
+                let mut entry = entry(root, &format!("dir{}", i), true, 0);
+                entry.is_expanded = true;
+                entry
+            })
+            .collect();
+        let state = TreeState::capture(root, &entries, None, UNIX_EPOCH);
+        assert_eq!(state.expanded.len(), MAX_EXPANDED);
+        assert_eq!(state.expanded[0], PathBuf::from("dir0"));
+    }
+
+    #[test]
+    fn test_save_load_and_prune() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let now = UNIX_EPOCH + Duration::from_secs(200 * 24 * 60 * 60);
+        let fresh = TreeState {
+            root: PathBuf::from("/work/fresh"),
+            expanded: vec![PathBuf::from("src")],
+            selected: None,
+            last_visited: unix_secs(now) - 60,
+        };
+        let old = TreeState {
+            root: PathBuf::from("/work/old"),
+            last_visited: unix_secs(now - MAX_AGE) - 60,
+            ..TreeState::default()
+        };
+        fresh.save(dir.path()).unwrap();
+        old.save(dir.path()).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "keep").unwrap();
+
+        assert_eq!(TreeState::load(dir.path(), Path::new("/work/fresh")), Some(fresh.clone()));
+        assert_eq!(TreeState::load(dir.path(), Path::new("/work/other")), None);
+
+        assert_eq!(prune(dir.path(), now, MAX_AGE), 1);
+        assert!(TreeState::load(dir.path(), Path::new("/work/old")).is_none());
+        assert_eq!(TreeState::load(dir.path(), Path::new("/work/fresh")), Some(fresh));
+        assert!(dir.path().join("notes.txt").exists());
+    }
+}
@@ -0,0 +1,253 @@
+//! Breadcrumb Bar Component
+//!
+//! Path bar above the panes showing the focused terminal's directory.
+//! Clicking a segment `cd`s the terminal there; long paths fold their
+//! middle segments into an ellipsis menu.
+
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use egui::{Button, Frame, RichText, Ui};
+use crate::config::{AmbiguousWidth, RuntimeTheme};
+use crate::theme::ui_font;
+
+const FONT_SIZE: f32 = 11.0;
+const SEPARATOR: &str = "/";
+const ELLIPSIS: &str = "…";
+
+/// One clickable part of the path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub label: String,
+    /// Full path up to and including this segment
+    pub path: PathBuf,
+}
+
+/// Split `dir` into segments, starting at `~` when it's under `home`
+pub fn segments(dir: &Path, home: Option<&Path>) -> Vec<Segment> {
+    let (mut path, rest, first) = match home.and_then(|home| Some((home, dir.strip_prefix(home).ok()?))) {
+        Some((home, rest)) => (home.to_path_buf(), rest, "~"),
+        None => (PathBuf::from("/"), dir.strip_prefix("/").unwrap_or(dir), "/"),
+    };
+    let mut segments = vec![Segment {
+        label: first.to_string(),
+        path: path.clone(),
+    }];
+    for component in rest.components() {
+        if let Component::Normal(name) = component {
+            path.push(name);
+            segments.push(Segment {
+                label: name.to_string_lossy().into_owned(),
+                path: path.clone(),
+            });
+        }
+    }
+    segments
+}
+
+/// Segments to fold into the ellipsis so the rest fits in `max_chars`
+///
+/// `lengths` are the segment label lengths. The first and last segments
+/// always stay visible. Returns `None` when everything fits.
+pub fn collapsed_range(lengths: &[usize], max_chars: usize) -> Option<Range<usize>> {
+    // Each segment is followed by a separator with a space on each side
+    let width = |lengths: &[usize]| lengths.iter().map(|len| len + 3).sum::<usize>();
+    if lengths.len() <= 2 || width(lengths) <= max_chars {
+        return None;
+    }
+
+    let ellipsis = ELLIPSIS.chars().count() + 3;
+    let last = lengths.len() - 1;
+    let mut hidden = 1..2;
+    // Fold from the left so the segments next to the current directory stay
+    while hidden.end < last && width(&lengths[..1]) + ellipsis + width(&lengths[hidden.end..]) > max_chars {
+        hidden.end += 1;
+    }
+    Some(hidden)
+}
+
+/// Quote `path` for a POSIX shell
+pub fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', r"'\''"))
+}
+
+/// Command line that changes into `path`
+pub fn cd_command(path: &Path) -> String {
+    format!("cd {}\n", shell_quote(path))
+}
+
+/// What the user did in the breadcrumb bar
+#[derive(Debug, Default)]
+pub struct BreadcrumbResponse {
+    /// Segment clicked to `cd` into
+    pub cd: Option<PathBuf>,
+    pub set_sidebar_root: Option<PathBuf>,
+    pub copy_path: Option<PathBuf>,
+}
+
+/// Path bar for the focused terminal
+pub struct BreadcrumbBar<'a> {
+    dir: Option<&'a Path>,
+    theme: &'a RuntimeTheme,
+    /// Only `cd` when Cmd is held during the click
+    cd_requires_modifier: bool,
+    ambiguous_width: AmbiguousWidth,
+}
+
+impl<'a> BreadcrumbBar<'a> {
+    /// `dir` is `None` when the focused pane isn't a terminal
+    pub fn new(dir: Option<&'a Path>, theme: &'a RuntimeTheme) -> Self {
+        Self {
+            dir,
+            theme,
+            cd_requires_modifier: false,
+            ambiguous_width: AmbiguousWidth::Narrow,
+        }
+    }
+
+    pub fn cd_requires_modifier(mut self, required: bool) -> Self {
+        self.cd_requires_modifier = required;
+        self
+    }
+
+    /// How wide ambiguous-width characters in directory names are counted
+    pub fn ambiguous_width(mut self, width: AmbiguousWidth) -> Self {
+        self.ambiguous_width = width;
+        self
+    }
+
+    /// Show the bar and return user actions
+    pub fn show(&self, ui: &mut Ui) -> BreadcrumbResponse {
+        let mut response = BreadcrumbResponse::default();
+
+        Frame::NONE
+            .fill(self.theme.surface)
+            .inner_margin(egui::Margin::symmetric(6, 0))
+            .show(ui, |ui| {
+                ui.horizontal_centered(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    let Some(dir) = self.dir else {
+                        return;
+                    };
+
+                    let segments = segments(dir, dirs::home_dir().as_deref());
+                    let font = ui_font(FONT_SIZE);
+                    let char_width = ui.fonts(|fonts| fonts.glyph_width(&font, 'M')).max(1.0);
+                    let max_chars = (ui.available_width() / char_width) as usize;
+                    let lengths: Vec<usize> = segments
+                        .iter()
+                        .map(|s| crate::cell_width::str_width(&s.label, self.ambiguous_width))
+                        .collect();
+                    let hidden = collapsed_range(&lengths, max_chars).unwrap_or(0..0);
+
+                    for (idx, segment) in segments.iter().enumerate() {
+                        if hidden.contains(&idx) {
+                            if idx == hidden.start {
+                                self.ellipsis_menu(ui, &segments[hidden.clone()], &mut response);
+                                self.separator(ui);
+                            }
+                            continue;
+                        }
+                        let is_current = idx == segments.len() - 1;
+                        self.segment(ui, segment, is_current, &mut response);
+                        if !is_current && segment.label != SEPARATOR {
+                            self.separator(ui);
+                        }
+                    }
+                });
+            });
+
+        response
+    }
+
+    fn separator(&self, ui: &mut Ui) {
+        ui.label(RichText::new(format!(" {} ", SEPARATOR)).font(ui_font(FONT_SIZE)).color(self.theme.text_dim));
+    }
+
+    fn segment(&self, ui: &mut Ui, segment: &Segment, is_current: bool, response: &mut BreadcrumbResponse) {
+        let color = if is_current { self.theme.text } else { self.theme.text_dim };
+        let button = Button::new(RichText::new(&segment.label).font(ui_font(FONT_SIZE)).color(color)).frame(false);
+        let hint = if self.cd_requires_modifier {
+            crate::shortcuts::shortcut_label("Cmd+click to cd here")
+        } else {
+            "Click to cd here".into()
+        };
+        let item = ui.add(button).on_hover_text(format!("{}\n{}", segment.path.display(), hint));
+        item.context_menu(|ui| path_menu(ui, &segment.path, response));
+        if item.clicked() && (!self.cd_requires_modifier || ui.input(|i| crate::shortcuts::primary_modifier(i.modifiers))) {
+            response.cd = Some(segment.path.clone());
+        }
+    }
+
+    fn ellipsis_menu(&self, ui: &mut Ui, hidden: &[Segment], response: &mut BreadcrumbResponse) {
+        ui.menu_button(RichText::new(ELLIPSIS).font(ui_font(FONT_SIZE)).color(self.theme.text_dim), |ui| {
+            for segment in hidden {
+                let item = ui.button(&segment.label).on_hover_text(segment.path.display().to_string());
+                if item.clicked() {
+                    // Picking from the menu is already a deliberate action
+                    response.cd = Some(segment.path.clone());
+                    ui.close_menu();
+                }
+            }
+        });
+    }
+}
+
+/// Right-click menu of a segment
+fn path_menu(ui: &mut Ui, path: &Path, response: &mut BreadcrumbResponse) {
+    if ui.button("Set Sidebar Root Here").clicked() {
+        response.set_sidebar_root = Some(path.to_path_buf());
+        ui.close_menu();
+    }
+    if ui.button("Copy Path").clicked() {
+        response.copy_path = Some(path.to_path_buf());
+        ui.close_menu();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(segments: &[Segment]) -> Vec<&str> {
+        segments.iter().map(|s| s.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_segments_under_home() {
+        let home = Path::new("/home/ana");
+        let segments = segments(Path::new("/home/ana/src/vibeterm"), Some(home));
+        assert_eq!(labels(&segments), ["~", "src", "vibeterm"]);
+        assert_eq!(segments[0].path, home);
+        assert_eq!(segments[2].path, Path::new("/home/ana/src/vibeterm"));
+
+        assert_eq!(labels(&super::segments(home, Some(home))), ["~"]);
+    }
+
+    #[test]
+    fn test_segments_outside_home() {
+        let segments = segments(Path::new("/etc/nginx"), Some(Path::new("/home/ana")));
+        assert_eq!(labels(&segments), ["/", "etc", "nginx"]);
+        assert_eq!(segments[1].path, Path::new("/etc"));
+        assert_eq!(labels(&super::segments(Path::new("/"), None)), ["/"]);
+        // Only whole components count as being under home
+        assert_eq!(labels(&super::segments(Path::new("/home/anabel"), Some(Path::new("/home/ana"))))[0], "/");
+    }
+
+    #[test]
+    fn test_collapse_keeps_first_and_last() {
+        let lengths = [1, 10, 10, 10, 8];
+        assert_eq!(collapsed_range(&lengths, 100), None);
+        // "~ / … / projects / vibeterm"
+        assert_eq!(collapsed_range(&lengths, 35), Some(1..3));
+        // Too narrow for anything but the ends
+        assert_eq!(collapsed_range(&lengths, 5), Some(1..4));
+        assert_eq!(collapsed_range(&[1, 40], 5), None);
+    }
+
+    #[test]
+    fn test_cd_command_quotes_path() {
+        assert_eq!(cd_command(Path::new("/tmp/my dir")), "cd '/tmp/my dir'\n");
+        assert_eq!(cd_command(Path::new("/tmp/it's")), "cd '/tmp/it'\\''s'\n");
+        assert_eq!(shell_quote(Path::new("/a/$(rm -rf)")), "'/a/$(rm -rf)'");
+    }
+}
@@ -0,0 +1,151 @@
+//! Clipboard History palette for pasting earlier copies
+
+use std::time::SystemTime;
+use egui::RichText;
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use crate::clipboard_history::ClipEntry;
+use crate::config::RuntimeTheme;
+use crate::theme::ui_font;
+use super::palette_list::{self, ListKey};
+use super::status_bar::format_ago;
+
+/// What to do with the chosen entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardHistoryAction {
+    /// Paste into the focused terminal
+    Paste(String),
+    /// Put back on the clipboard
+    Copy(String),
+}
+
+/// Clipboard history palette state
+pub struct ClipboardHistoryPalette {
+    visible: bool,
+    entries: Vec<ClipEntry>,
+    query: String,
+    /// Indices into `entries`, best match first
+    filtered: Vec<usize>,
+    selected: usize,
+    matcher: SkimMatcherV2,
+}
+
+impl ClipboardHistoryPalette {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            entries: Vec::new(),
+            query: String::new(),
+            filtered: Vec::new(),
+            selected: 0,
+            matcher: SkimMatcherV2::default(),
+        }
+    }
+
+    /// Open the palette with the given entries, newest first
+    pub fn open(&mut self, entries: Vec<ClipEntry>) {
+        self.visible = true;
+        self.entries = entries;
+        self.query.clear();
+        self.update_filter();
+    }
+
+    /// Is palette visible?
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Update filtered entries based on query (text and source)
+    fn update_filter(&mut self) {
+        let mut scored: Vec<(usize, i64)> = self.entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| {
+                if self.query.is_empty() {
+                    return Some((idx, 0));
+                }
+                let haystack = format!("{} {}", entry.text, entry.source);
+                self.matcher.fuzzy_match(&haystack, &self.query).map(|score| (idx, score))
+            })
+            .collect();
+
+        // Stable, so equal scores stay newest first
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        self.filtered = scored.into_iter().map(|(idx, _)| idx).collect();
+        self.selected = 0;
+    }
+
+    /// Show palette and return the action for the chosen entry
+    pub fn show(&mut self, ctx: &egui::Context, theme: &RuntimeTheme) -> Option<ClipboardHistoryAction> {
+        if !self.visible {
+            return None;
+        }
+
+        let result = palette_list::window("clipboard_history_palette", ctx, theme)
+            .show(ctx, |ui| self.show_list(ui, theme))
+            .and_then(|r| r.inner)
+            .flatten();
+
+        if result.is_some() {
+            self.visible = false;
+        }
+
+        result
+    }
+
+    /// Entry search list
+    fn show_list(&mut self, ui: &mut egui::Ui, theme: &RuntimeTheme) -> Option<ClipboardHistoryAction> {
+        if palette_list::search_field(ui, &mut self.query, "Search clipboard history...", theme) {
+            self.update_filter();
+        }
+
+        ui.separator();
+
+        if self.entries.is_empty() {
+            ui.label(RichText::new("Nothing copied yet")
+                .font(ui_font(12.0))
+                .color(theme.text_dim));
+        }
+
+        let now = SystemTime::now();
+        let clicked = palette_list::rows(ui, theme, self.filtered.len(), self.selected, |ui, row, is_selected| {
+            let entry = &self.entries[self.filtered[row]];
+            ui.vertical(|ui| {
+                ui.label(RichText::new(entry.preview())
+                    .font(ui_font(12.0))
+                    .color(if is_selected { theme.text } else { theme.text_dim }));
+                ui.label(RichText::new(format!(
+                    "{} · {}",
+                    entry.source,
+                    format_ago(Some(entry.copied_at), now)
+                ))
+                    .font(ui_font(10.0))
+                    .color(theme.secondary));
+            });
+        });
+        let mut chosen = clicked.map(|row| (self.filtered[row], false));
+
+        ui.label(RichText::new(format!(
+            "Enter to paste, {} to copy",
+            crate::shortcuts::shortcut_label("Cmd+Enter")
+        ))
+            .font(ui_font(10.0))
+            .color(theme.text_dim));
+
+        match palette_list::navigate(ui, &mut self.selected, self.filtered.len()) {
+            Some(ListKey::Enter { primary }) => {
+                chosen = self.filtered.get(self.selected).map(|&idx| (idx, primary));
+            }
+            Some(ListKey::Escape) => self.visible = false,
+            None => {}
+        }
+
+        let (idx, copy) = chosen?;
+        let text = self.entries[idx].text.clone();
+        Some(if copy {
+            ClipboardHistoryAction::Copy(text)
+        } else {
+            ClipboardHistoryAction::Paste(text)
+        })
+    }
+}
@@ -0,0 +1,110 @@
+//! Color Test tab: the terminal palette painted the way the terminal paints it
+
+use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::vte::ansi::{Color, NamedColor};
+use egui::RichText;
+use egui_term::TerminalTheme;
+use crate::cell_width::char_width;
+use crate::color_test::Section;
+use crate::config::{AmbiguousWidth, RuntimeTheme};
+use crate::theme::{mono_font, ui_font};
+use super::pane_mirror::cell_colors;
+
+/// Button pressed in the Color Test tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorTestCommand {
+    /// Print the test with `printf` in a terminal of this tab
+    WriteScript,
+}
+
+/// Show the preview in `theme`, the colors terminals are drawn with
+pub fn show_color_test(
+    ui: &mut egui::Ui,
+    sections: &[Section],
+    theme: &TerminalTheme,
+    font_size: f32,
+    colors: &RuntimeTheme,
+) -> Option<ColorTestCommand> {
+    let mut command = None;
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Terminal Color Test").font(ui_font(13.0)).color(colors.text));
+        ui.label(RichText::new("follows the theme as you Apply it in Preferences")
+            .font(ui_font(11.0))
+            .color(colors.text_dim));
+        if ui.small_button("Write test script to terminal")
+            .on_hover_text("Print the same test with printf, through a real PTY")
+            .clicked()
+        {
+            command = Some(ColorTestCommand::WriteScript);
+        }
+    });
+    ui.add_space(6.0);
+
+    let background = theme.get_color(Color::Named(NamedColor::Background));
+    egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+        egui::Frame::NONE.fill(background).inner_margin(8).show(ui, |ui| {
+            ui.spacing_mut().item_spacing.y = 0.0;
+            for section in sections {
+                ui.add_space(8.0);
+                ui.label(RichText::new(section.title)
+                    .font(mono_font(font_size))
+                    .color(theme.get_color(Color::Named(NamedColor::DimForeground))));
+                ui.add_space(2.0);
+                for row in &section.rows {
+                    paint_row(ui, row, theme, font_size, background);
+                }
+            }
+        });
+    });
+    command
+}
+
+/// Paint a row cell by cell, as the terminal lays out its grid
+fn paint_row(ui: &mut egui::Ui, row: &[crate::color_test::Span], theme: &TerminalTheme, font_size: f32, background: egui::Color32) {
+    let font = mono_font(font_size);
+    let (cell_width, cell_height) = ui.fonts(|f| (f.glyph_width(&font, 'M'), f.row_height(&font)));
+    let columns: usize = row
+        .iter()
+        .flat_map(|span| span.text.chars())
+        .map(|c| char_width(c, AmbiguousWidth::Narrow))
+        .sum();
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(cell_width * columns as f32, cell_height), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+
+    let mut x = rect.left();
+    for span in row {
+        let (fg, bg) = cell_colors(theme, span.fg, span.bg, span.flags);
+        for c in span.text.chars() {
+            let width = cell_width * char_width(c, AmbiguousWidth::Narrow) as f32;
+            let cell = egui::Rect::from_min_size(egui::pos2(x, rect.top()), egui::vec2(width, cell_height));
+            x += width;
+            if bg != background {
+                painter.rect_filled(cell, 0.0, bg);
+            }
+            if c != ' ' {
+                let mut job = egui::text::LayoutJob::single_section(c.to_string(), egui::TextFormat {
+                    font_id: font.clone(),
+                    color: fg,
+                    italics: span.flags.contains(Flags::ITALIC),
+                    ..Default::default()
+                });
+                job.wrap.max_rows = 1;
+                let galley = painter.layout_job(job);
+                let pos = cell.center() - galley.size() / 2.0;
+                if span.flags.contains(Flags::BOLD) {
+                    // No bold monospace face: overstrike
+                    painter.galley(pos + egui::vec2(0.6, 0.0), galley.clone(), fg);
+                }
+                painter.galley(pos, galley, fg);
+            }
+
+            let stroke = egui::Stroke::new(1.0, fg);
+            if span.flags.contains(Flags::UNDERLINE) {
+                painter.hline(cell.x_range(), cell.bottom() - 2.0, stroke);
+            }
+            if span.flags.contains(Flags::STRIKEOUT) {
+                painter.hline(cell.x_range(), cell.center().y, stroke);
+            }
+        }
+    }
+}
@@ -1,10 +1,16 @@
 //! Command Palette for quick actions
+//!
+//! A command can ask for more input after it's picked (free text or one of
+//! a list of choices); the palette stays open until every step is answered.
 
-use egui::{Frame, Key, RichText, ScrollArea};
+use std::time::{Duration, Instant};
+use egui::{Key, RichText};
 use fuzzy_matcher::FuzzyMatcher;
 use fuzzy_matcher::skim::SkimMatcherV2;
 use crate::config::RuntimeTheme;
-use crate::theme::mono_font;
+use crate::theme::ui_font;
+use super::ConfirmState;
+use super::palette_list::{self, ListKey};
 
 /// A command in the palette
 #[derive(Debug, Clone)]
@@ -13,6 +19,50 @@ pub struct Command {
     pub label: &'static str,
     pub shortcut: Option<&'static str>,
     pub keywords: &'static [&'static str],
+    /// Needs confirming (second Enter or held Enter) and is shown in red
+    pub dangerous: bool,
+    /// Input asked for after the command is picked, in order
+    pub steps: &'static [PaletteStep],
+}
+
+/// Input a command asks for after it's picked
+#[derive(Debug, Clone, Copy)]
+pub enum PaletteStep {
+    /// Free text, checked by `validate` before moving on
+    Input {
+        prompt: &'static str,
+        validate: fn(&str) -> Result<(), String>,
+    },
+    /// One of a list the app supplies when the step opens
+    Choice { prompt: &'static str },
+}
+
+impl PaletteStep {
+    fn prompt(&self) -> &'static str {
+        match self {
+            PaletteStep::Input { prompt, .. } | PaletteStep::Choice { prompt } => prompt,
+        }
+    }
+}
+
+/// A picked command with the answers to its steps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteAction {
+    pub command: &'static str,
+    pub args: Vec<String>,
+}
+
+/// Choices for a `PaletteStep::Choice`, given the command ID and the
+/// answers so far
+pub type ChoiceSource<'a> = dyn Fn(&'static str, &[String]) -> Vec<String> + 'a;
+
+/// Text that isn't only whitespace
+fn not_blank(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() {
+        Err("Can't be empty".to_string())
+    } else {
+        Ok(())
+    }
 }
 
 /// All available commands
@@ -22,54 +72,505 @@ pub static COMMANDS: &[Command] = &[
         label: "New Tab",
         shortcut: Some("Cmd+T"),
         keywords: &["new", "tab", "create", "workspace"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "new_tab_at_end",
+        label: "New Tab at End",
+        shortcut: None,
+        keywords: &["new", "tab", "create", "end", "last"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "new_tab_after_current",
+        label: "New Tab After Current",
+        shortcut: None,
+        keywords: &["new", "tab", "create", "next", "right", "beside"],
+        dangerous: false,
+        steps: &[],
     },
     Command {
         id: "close_tab",
         label: "Close Tab",
         shortcut: Some("Cmd+W"),
         keywords: &["close", "tab", "remove", "workspace"],
+        dangerous: true,
+        steps: &[],
     },
     Command {
         id: "split_horizontal",
         label: "Split Horizontally",
         shortcut: Some("Cmd+D"),
         keywords: &["split", "horizontal", "pane", "divide"],
+        dangerous: false,
+        steps: &[],
     },
     Command {
         id: "split_vertical",
         label: "Split Vertically",
         shortcut: Some("Cmd+Shift+D"),
         keywords: &["split", "vertical", "pane", "divide"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "split_empty",
+        label: "New Empty Pane",
+        shortcut: None,
+        keywords: &["split", "empty", "blank", "placeholder", "pane"],
+        dangerous: false,
+        steps: &[],
     },
     Command {
         id: "close_pane",
         label: "Close Pane",
         shortcut: Some("Cmd+Shift+W"),
         keywords: &["close", "pane", "remove"],
+        dangerous: true,
+        steps: &[],
+    },
+    Command {
+        id: "move_pane_left",
+        label: "Move Pane Left",
+        shortcut: Some("Cmd+Alt+Shift+Left"),
+        keywords: &["move", "pane", "left", "reposition", "swap"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "move_pane_right",
+        label: "Move Pane Right",
+        shortcut: Some("Cmd+Alt+Shift+Right"),
+        keywords: &["move", "pane", "right", "reposition", "swap"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "move_pane_up",
+        label: "Move Pane Up",
+        shortcut: Some("Cmd+Alt+Shift+Up"),
+        keywords: &["move", "pane", "up", "reposition", "swap"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "move_pane_down",
+        label: "Move Pane Down",
+        shortcut: Some("Cmd+Alt+Shift+Down"),
+        keywords: &["move", "pane", "down", "reposition", "swap"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "move_pane_to_new_tab",
+        label: "Move Pane to New Tab",
+        shortcut: None,
+        keywords: &["move", "pane", "tab", "promote", "detach", "break out"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "mirror_pane",
+        label: "Mirror Pane to New Window",
+        shortcut: None,
+        keywords: &["mirror", "pane", "window", "share", "screen", "present", "copy", "second monitor"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "rename_tab",
+        label: "Rename Tab…",
+        shortcut: None,
+        keywords: &["rename", "tab", "name", "title", "workspace"],
+        dangerous: false,
+        steps: &[PaletteStep::Input { prompt: "New tab name", validate: not_blank }],
+    },
+    Command {
+        id: "go_to_tab",
+        label: "Go to Tab…",
+        shortcut: None,
+        keywords: &["go", "tab", "switch", "jump", "workspace", "find"],
+        dangerous: false,
+        steps: &[PaletteStep::Choice { prompt: "Tab" }],
     },
     Command {
         id: "toggle_sidebar",
         label: "Toggle Sidebar",
         shortcut: Some("Cmd+B"),
         keywords: &["sidebar", "toggle", "hide", "show"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "toggle_presentation_mode",
+        label: "Toggle Presentation Mode",
+        shortcut: Some(crate::presentation::SHORTCUT_LABEL),
+        keywords: &["presentation", "present", "fullscreen", "distraction", "focus", "zen", "chrome", "hide"],
+        dangerous: false,
+        steps: &[],
     },
     Command {
         id: "settings",
         label: "Open Settings",
         shortcut: None,
         keywords: &["settings", "config", "preferences"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "open_config_file",
+        label: "Open Config File",
+        shortcut: None,
+        keywords: &["config", "settings", "toml", "file", "open", "view"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "reveal_config_folder",
+        label: "Reveal Config Folder",
+        shortcut: None,
+        keywords: &["config", "settings", "folder", "directory", "reveal", "finder", "files"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "edit_config",
+        label: "Edit Config in $EDITOR",
+        shortcut: None,
+        keywords: &["config", "settings", "toml", "edit", "editor", "vim"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "font_diagnostics",
+        label: "Run Font Diagnostics",
+        shortcut: None,
+        keywords: &["font", "glyph", "tofu", "fallback", "nerd", "cjk", "emoji", "missing"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "startup_report",
+        label: "Show Startup Report",
+        shortcut: None,
+        keywords: &["startup", "launch", "slow", "timing", "performance", "profile"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "send_selection_next",
+        label: "Send Selection to Next Pane",
+        shortcut: Some("Cmd+Shift+Right"),
+        keywords: &["send", "selection", "pane", "next", "copy"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "send_selection_prev",
+        label: "Send Selection to Previous Pane",
+        shortcut: Some("Cmd+Shift+Left"),
+        keywords: &["send", "selection", "pane", "previous", "copy"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "send_selection_to",
+        label: "Send Selection to…",
+        shortcut: None,
+        keywords: &["send", "selection", "pane", "pick", "choose"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "diff_panes",
+        label: "Diff Panes…",
+        shortcut: None,
+        keywords: &["diff", "compare", "panes", "output", "side"],
+        dangerous: false,
+        steps: &[PaletteStep::Choice { prompt: "Compare" }],
+    },
+    Command {
+        id: "run_in_all_panes",
+        label: "Run in All Panes…",
+        shortcut: None,
+        keywords: &["run", "command", "all", "panes", "broadcast", "workspace"],
+        dangerous: true,
+        steps: &[],
+    },
+    Command {
+        id: "toggle_follow_sidebar",
+        label: "Toggle Follow Sidebar Selection",
+        shortcut: None,
+        keywords: &["follow", "preview", "sidebar", "selection", "file", "viewer", "pane", "sync"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "convert_to_terminal",
+        label: "Convert to Terminal Here",
+        shortcut: None,
+        keywords: &["convert", "terminal", "shell", "file", "viewer", "pane"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "convert_to_file_viewer",
+        label: "Convert to File Viewer…",
+        shortcut: None,
+        keywords: &["convert", "file", "viewer", "open", "terminal", "pane"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "jump_to_next_diagnostic",
+        label: "Jump to Next Diagnostic",
+        shortcut: Some("Cmd+Shift+J"),
+        keywords: &["diagnostic", "error", "warning", "compiler", "jump", "next", "goto", "line"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "copy_last_output",
+        label: "Copy Last Output",
+        // Configurable, so the default isn't shown
+        shortcut: None,
+        keywords: &["copy", "output", "last", "command", "clipboard", "result"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "insert_context",
+        label: "Insert Context into Terminal",
+        shortcut: None,
+        keywords: &["context", "pinned", "ai", "llm", "aider", "file", "path", "insert", "snapshot"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "insert_context_heredoc",
+        label: "Insert Context into Terminal as Here-Doc",
+        shortcut: None,
+        keywords: &["context", "pinned", "ai", "llm", "heredoc", "variable", "insert", "snapshot"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "search_in_project",
+        label: "Search in Project…",
+        shortcut: Some("Cmd+Shift+F"),
+        keywords: &["search", "find", "grep", "project", "text", "content", "ripgrep"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "pause_file_watcher",
+        label: "Pause File Watcher",
+        shortcut: None,
+        keywords: &["pause", "stop", "watcher", "file", "context", "events"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "resume_file_watcher",
+        label: "Resume File Watcher",
+        shortcut: None,
+        keywords: &["resume", "start", "watcher", "file", "context", "events"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "pause_git_status",
+        label: "Pause Git Status",
+        shortcut: None,
+        keywords: &["pause", "stop", "git", "status", "context", "polling"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "resume_git_status",
+        label: "Resume Git Status",
+        shortcut: None,
+        keywords: &["resume", "start", "git", "status", "context", "polling"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "toggle_session_logging",
+        label: "Start/Stop Logging",
+        shortcut: None,
+        keywords: &["log", "logging", "session", "record", "output", "save"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "export_settings_bundle",
+        label: "Export Settings Bundle…",
+        shortcut: None,
+        keywords: &["export", "backup", "settings", "config", "bundle", "migrate"],
+        dangerous: false,
+        steps: &[PaletteStep::Input { prompt: "Folder to create for the bundle", validate: not_blank }],
+    },
+    Command {
+        id: "import_settings_bundle",
+        label: "Import Settings Bundle…",
+        shortcut: None,
+        keywords: &["import", "restore", "settings", "config", "bundle", "migrate"],
+        dangerous: false,
+        steps: &[PaletteStep::Input { prompt: "Bundle folder", validate: not_blank }],
+    },
+    Command {
+        id: "open_project_layout",
+        label: "Open Project Layout",
+        shortcut: None,
+        keywords: &["project", "layout", "vibeterm.toml", "workspace", "restore"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "insert_snippet",
+        label: "Insert Snippet…",
+        shortcut: None,
+        keywords: &["snippet", "insert", "template", "command"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "clipboard_history",
+        label: "Clipboard History…",
+        shortcut: Some("Cmd+Shift+V"),
+        keywords: &["clipboard", "history", "paste", "copy", "yank"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "toggle_dnd",
+        label: "Toggle Do Not Disturb",
+        shortcut: None,
+        keywords: &["dnd", "do not disturb", "quiet", "mute", "bell", "notifications", "silence"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "ui_scale_up",
+        label: "Increase UI Scale",
+        shortcut: Some("Cmd+Alt+Plus"),
+        keywords: &["zoom", "scale", "ui", "bigger", "larger", "sidebar", "text", "size"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "ui_scale_down",
+        label: "Decrease UI Scale",
+        shortcut: Some("Cmd+Alt+Minus"),
+        keywords: &["zoom", "scale", "ui", "smaller", "sidebar", "text", "size"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "usage_stats",
+        label: "Usage Stats",
+        shortcut: None,
+        keywords: &["stats", "usage", "analytics", "time", "project", "dashboard"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "color_test",
+        label: "Terminal Color Test",
+        shortcut: None,
+        keywords: &["color", "colour", "ansi", "256", "truecolor", "palette", "theme", "preview", "test"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "history_peek",
+        label: "Search Command History…",
+        shortcut: Some(if crate::shortcuts::IS_MAC { "Cmd+R" } else { "Cmd+Shift+R" }),
+        keywords: &["history", "command", "previous", "search", "reverse", "recall"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "connect_to_host",
+        label: "Connect to Host…",
+        shortcut: None,
+        keywords: &["ssh", "remote", "server", "host", "connect"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "add_mark",
+        label: "Add Mark…",
+        shortcut: Some("Cmd+Shift+M"),
+        keywords: &["mark", "bookmark", "scrollback", "label", "line"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "jump_to_mark",
+        label: "Jump to Mark…",
+        shortcut: None,
+        keywords: &["mark", "bookmark", "scrollback", "jump", "list"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "previous_mark",
+        label: "Previous Mark",
+        shortcut: Some("Cmd+Shift+["),
+        keywords: &["mark", "bookmark", "scrollback", "up", "earlier"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "next_mark",
+        label: "Next Mark",
+        shortcut: Some("Cmd+Shift+]"),
+        keywords: &["mark", "bookmark", "scrollback", "down", "later"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "toggle_blame",
+        label: "Toggle Git Blame",
+        shortcut: None,
+        keywords: &["git", "blame", "author", "annotate", "file"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "edit_snippets",
+        label: "Edit Snippets",
+        shortcut: None,
+        keywords: &["snippet", "edit", "template", "config"],
+        dangerous: false,
+        steps: &[],
+    },
+    Command {
+        id: "pin_output",
+        label: "Pin Selected Output",
+        shortcut: None,
+        keywords: &["pin", "output", "note", "selection", "snapshot"],
+        dangerous: false,
+        steps: &[],
     },
     Command {
         id: "next_tab",
         label: "Next Tab",
         shortcut: Some("Cmd+]"),
         keywords: &["next", "tab", "switch"],
+        dangerous: false,
+        steps: &[],
     },
     Command {
         id: "prev_tab",
         label: "Previous Tab",
         shortcut: Some("Cmd+["),
         keywords: &["previous", "tab", "switch"],
+        dangerous: false,
+        steps: &[],
     },
 ];
 
@@ -80,13 +581,58 @@ struct CommandMatch {
     score: i64,
 }
 
+/// A picked command whose steps aren't all answered yet
+#[derive(Debug)]
+struct Flow {
+    command: &'static Command,
+    /// Answers so far; the current step is `steps[args.len()]`
+    args: Vec<String>,
+    /// Choices of the current step, if it's a `Choice`
+    choices: Vec<String>,
+    /// Why the last input was rejected
+    error: Option<String>,
+}
+
+impl Flow {
+    fn step(&self) -> PaletteStep {
+        self.command.steps[self.args.len()]
+    }
+}
+
+/// A line of the command or choice list
+struct Row {
+    label: String,
+    shortcut: Option<&'static str>,
+    dangerous: bool,
+    /// Waiting for a second Enter
+    armed: bool,
+    /// Why the command can't run here
+    disabled: Option<String>,
+}
+
+impl Row {
+    fn choice(choice: &str) -> Self {
+        Self { label: choice.to_string(), shortcut: None, dangerous: false, armed: false, disabled: None }
+    }
+}
+
 /// Command palette state
 pub struct CommandPalette {
     visible: bool,
+    /// Command filter, or the current step's input
     query: String,
     filtered: Vec<CommandMatch>,
     selected: usize,
     matcher: SkimMatcherV2,
+    flow: Option<Flow>,
+    /// Commands left out of the list (e.g. "Pause" while already paused)
+    hidden: Vec<&'static str>,
+    /// Commands listed greyed out because they can't run here, and why
+    disabled: Vec<(&'static str, String)>,
+    /// Dangerous command waiting for its second Enter
+    confirm: ConfirmState<&'static str>,
+    /// Ask before running dangerous commands (`ui.confirm_dangerous_commands`)
+    confirm_dangerous: bool,
 }
 
 impl CommandPalette {
@@ -103,12 +649,19 @@ impl CommandPalette {
             filtered,
             selected: 0,
             matcher,
+            flow: None,
+            hidden: Vec::new(),
+            disabled: Vec::new(),
+            confirm: ConfirmState::new(),
+            confirm_dangerous: true,
         }
     }
 
     /// Toggle visibility
     pub fn toggle(&mut self) {
         self.visible = !self.visible;
+        self.flow = None;
+        self.confirm.reset();
         if self.visible {
             self.query.clear();
             self.update_filter();
@@ -116,6 +669,143 @@ impl CommandPalette {
         }
     }
 
+    /// Open the palette on the steps of command `id`
+    pub fn start(&mut self, id: &str, choices: &ChoiceSource) -> Option<PaletteAction> {
+        let command = COMMANDS.iter().find(|command| command.id == id)?;
+        if self.disabled_reason(id).is_some() {
+            return None;
+        }
+        self.visible = true;
+        self.choose_command(command, choices)
+    }
+
+    /// Pick a command; finishes it at once if it has no steps
+    fn choose_command(&mut self, command: &'static Command, choices: &ChoiceSource) -> Option<PaletteAction> {
+        self.flow = Some(Flow { command, args: Vec::new(), choices: Vec::new(), error: None });
+        self.advance(choices)
+    }
+
+    /// Answer the current step; invalid input keeps the step open
+    fn submit(&mut self, value: String, choices: &ChoiceSource) -> Option<PaletteAction> {
+        let flow = self.flow.as_mut()?;
+        match flow.step() {
+            PaletteStep::Input { validate, .. } => {
+                if let Err(error) = validate(&value) {
+                    flow.error = Some(error);
+                    return None;
+                }
+            }
+            PaletteStep::Choice { .. } => {
+                if !flow.choices.contains(&value) {
+                    return None;
+                }
+            }
+        }
+        flow.args.push(value);
+        self.advance(choices)
+    }
+
+    /// Open the next step, or finish the flow after the last one
+    fn advance(&mut self, choices: &ChoiceSource) -> Option<PaletteAction> {
+        let flow = self.flow.as_mut()?;
+        self.query.clear();
+        self.selected = 0;
+        flow.error = None;
+
+        if flow.args.len() == flow.command.steps.len() {
+            let flow = self.flow.take()?;
+            self.visible = false;
+            return Some(PaletteAction { command: flow.command.id, args: flow.args });
+        }
+        flow.choices = match flow.step() {
+            PaletteStep::Choice { .. } => choices(flow.command.id, &flow.args),
+            PaletteStep::Input { .. } => Vec::new(),
+        };
+        None
+    }
+
+    /// Esc: back to the previous step or the command list, then close
+    fn back(&mut self, choices: &ChoiceSource) {
+        self.query.clear();
+        self.selected = 0;
+        match &mut self.flow {
+            Some(flow) if !flow.args.is_empty() => {
+                flow.args.pop();
+                self.advance(choices);
+            }
+            Some(_) => {
+                self.flow = None;
+                self.update_filter();
+            }
+            None => self.visible = false,
+        }
+    }
+
+    /// Choices of the current step matching the query, best first
+    fn filtered_choices(&self) -> Vec<&str> {
+        let Some(flow) = &self.flow else {
+            return Vec::new();
+        };
+        if self.query.is_empty() {
+            return flow.choices.iter().map(String::as_str).collect();
+        }
+        let mut matches: Vec<(i64, &str)> = flow.choices
+            .iter()
+            .filter_map(|choice| Some((self.matcher.fuzzy_match(choice, &self.query)?, choice.as_str())))
+            .collect();
+        matches.sort_by_key(|(score, _)| -score);
+        matches.into_iter().map(|(_, choice)| choice).collect()
+    }
+
+    /// Leave commands out of the list; they can still be started by ID
+    pub fn set_hidden(&mut self, ids: Vec<&'static str>) {
+        if ids != self.hidden {
+            self.hidden = ids;
+            if self.flow.is_none() {
+                self.update_filter();
+            }
+        }
+    }
+
+    /// Grey out commands that can't run here; they can't be picked or started
+    pub fn set_disabled(&mut self, disabled: Vec<(&'static str, String)>) {
+        self.disabled = disabled;
+    }
+
+    /// Why command `id` can't run, if it's disabled
+    fn disabled_reason(&self, id: &str) -> Option<&str> {
+        self.disabled
+            .iter()
+            .find(|(disabled, _)| *disabled == id)
+            .map(|(_, reason)| reason.as_str())
+    }
+
+    /// Ask for confirmation before running dangerous commands
+    pub fn set_confirm_dangerous(&mut self, confirm: bool) {
+        self.confirm_dangerous = confirm;
+    }
+
+    /// Pick a command from the list, unless it's dangerous and not confirmed yet
+    ///
+    /// `held` tells a key press (which can be held to confirm) from a click.
+    fn pick(&mut self, command: &'static Command, held: bool, now: Instant, choices: &ChoiceSource) -> Option<PaletteAction> {
+        if self.disabled_reason(command.id).is_some() {
+            return None;
+        }
+        if command.dangerous && self.confirm_dangerous {
+            let confirmed = if held {
+                self.confirm.press(command.id, now)
+            } else {
+                self.confirm.click(command.id, now)
+            };
+            if !confirmed {
+                return None;
+            }
+        }
+        self.confirm.reset();
+        self.choose_command(command, choices)
+    }
+
     /// Is palette visible?
     pub fn is_visible(&self) -> bool {
         self.visible
@@ -123,14 +813,13 @@ impl CommandPalette {
 
     /// Update filtered commands based on query
     fn update_filter(&mut self) {
+        let commands = COMMANDS.iter().filter(|cmd| !self.hidden.contains(&cmd.id));
         if self.query.is_empty() {
-            self.filtered = COMMANDS
-                .iter()
+            self.filtered = commands
                 .map(|cmd| CommandMatch { command: cmd, score: 0 })
                 .collect();
         } else {
-            let mut matches: Vec<CommandMatch> = COMMANDS
-                .iter()
+            let mut matches: Vec<CommandMatch> = commands
                 .filter_map(|cmd| {
                     // Match against label and keywords
                     let label_score = self.matcher.fuzzy_match(&cmd.label, &self.query);
@@ -151,116 +840,164 @@ impl CommandPalette {
         self.selected = 0;
     }
 
-    /// Show palette and return selected command ID
-    pub fn show(&mut self, ctx: &egui::Context, theme: &RuntimeTheme) -> Option<&'static str> {
+    /// Show palette and return the command once all its steps are answered
+    pub fn show(&mut self, ctx: &egui::Context, theme: &RuntimeTheme, choices: &ChoiceSource) -> Option<PaletteAction> {
         if !self.visible {
             return None;
         }
 
-        let mut executed = None;
+        let now = Instant::now();
+        let armed = self.confirm.armed(now).copied();
+        let hold = self.confirm.hold_progress(now);
+        let step = self.flow.as_ref().map(Flow::step);
+        let rows: Vec<Row> = match step {
+            None => self.filtered
+                .iter()
+                .map(|cmd_match| {
+                    let command = cmd_match.command;
+                    Row {
+                        label: command.label.to_string(),
+                        shortcut: command.shortcut,
+                        dangerous: command.dangerous,
+                        armed: armed == Some(command.id),
+                        disabled: self.disabled_reason(command.id).map(str::to_string),
+                    }
+                })
+                .collect(),
+            Some(PaletteStep::Choice { .. }) => self.filtered_choices()
+                .into_iter()
+                .map(Row::choice)
+                .collect(),
+            Some(PaletteStep::Input { .. }) => Vec::new(),
+        };
+        let header = self.flow.as_ref().map(|flow| {
+            (format!("{} › {}", flow.command.label.trim_end_matches('…'), flow.step().prompt()), flow.error.clone())
+        });
+        let hint = match step {
+            Some(step) => step.prompt(),
+            None => "Type to search commands...",
+        };
 
-        egui::Window::new("command_palette")
-            .title_bar(false)
-            .fixed_pos(egui::pos2(ctx.screen_rect().width() * 0.5 - 300.0, 100.0))
-            .fixed_size(egui::vec2(600.0, 400.0))
-            .frame(Frame::window(&ctx.style())
-                .fill(theme.surface)
-                .stroke(egui::Stroke::new(1.0, theme.border)))
+        let mut clicked_row = None;
+        let mut entered = false;
+        let mut submitted = None;
+        let mut back = false;
+
+        palette_list::window("command_palette", ctx, theme)
             .show(ctx, |ui| {
                 ui.vertical(|ui| {
-                    // Search input
-                    ui.horizontal(|ui| {
-                        ui.label(RichText::new("❯").font(mono_font(14.0)).color(theme.primary));
-
-                        let text_edit = egui::TextEdit::singleline(&mut self.query)
-                            .font(mono_font(14.0))
-                            .desired_width(550.0)
-                            .hint_text("Type to search commands...");
-
-                        let response = ui.add(text_edit);
+                    // Command and prompt of the current step
+                    if let Some((title, _)) = &header {
+                        ui.label(RichText::new(title).font(ui_font(11.0)).color(theme.text_dim));
+                    }
 
-                        // Auto-focus on open
-                        if response.changed() {
-                            self.update_filter();
+                    if palette_list::search_field(ui, &mut self.query, hint, theme) {
+                        match step {
+                            None => self.update_filter(),
+                            Some(_) => self.selected = 0,
                         }
+                    }
 
-                        response.request_focus();
-                    });
+                    if let Some((_, Some(error))) = &header {
+                        ui.label(RichText::new(error).font(ui_font(11.0)).color(theme.red));
+                    }
+
+                    if matches!(step, Some(PaletteStep::Input { .. })) {
+                        return;
+                    }
 
                     ui.separator();
 
-                    // Command list
-                    ScrollArea::vertical()
-                        .max_height(320.0)
-                        .show(ui, |ui| {
-                            for (idx, cmd_match) in self.filtered.iter().enumerate() {
-                                let is_selected = idx == self.selected;
-
-                                let bg_color = if is_selected {
-                                    theme.selection
-                                } else {
-                                    theme.surface
-                                };
-
-                                let text_color = if is_selected {
-                                    theme.text
-                                } else {
-                                    theme.text_dim
-                                };
-
-                                let frame = Frame::NONE
-                                    .fill(bg_color)
-                                    .inner_margin(egui::Margin { left: 8, right: 8, top: 4, bottom: 4 });
-
-                                frame.show(ui, |ui| {
-                                    ui.horizontal(|ui| {
-                                        ui.label(RichText::new(cmd_match.command.label)
-                                            .font(mono_font(12.0))
-                                            .color(text_color));
-
-                                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                            if let Some(shortcut) = cmd_match.command.shortcut {
-                                                ui.label(RichText::new(shortcut)
-                                                    .font(mono_font(10.0))
-                                                    .color(theme.text_dim));
-                                            }
-                                        });
-                                    });
-
-                                    if ui.interact(ui.max_rect(), ui.id().with(idx), egui::Sense::click()).clicked() {
-                                        executed = Some(cmd_match.command.id);
-                                    }
+                    // Command or choice list
+                    clicked_row = palette_list::rows(ui, theme, rows.len(), self.selected, |ui, idx, is_selected| {
+                        let Row { label, shortcut, dangerous, armed, disabled } = &rows[idx];
+                        if let Some(reason) = disabled {
+                            ui.horizontal(|ui| {
+                                ui.label(crate::theme::unavailable_text(label, 12.0, theme));
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    ui.label(crate::theme::unavailable_text(reason, 10.0, theme));
                                 });
+                            });
+                            return;
+                        }
+                        let color = match (dangerous, is_selected) {
+                            (true, true) => theme.red,
+                            (true, false) => theme.red.gamma_multiply(0.7),
+                            (false, true) => theme.text,
+                            (false, false) => theme.text_dim,
+                        };
+                        ui.horizontal(|ui| {
+                            let label_rect = ui.label(RichText::new(label).font(ui_font(12.0)).color(color)).rect;
+                            // Fills up while Enter is held
+                            if let (true, Some(progress)) = (*armed, hold) {
+                                let right = label_rect.left() + label_rect.width() * progress;
+                                ui.painter().hline(label_rect.left()..=right, label_rect.bottom() + 1.0, egui::Stroke::new(2.0, theme.red));
                             }
+
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if *armed {
+                                    ui.label(RichText::new("Press Enter again to confirm")
+                                        .font(ui_font(10.0))
+                                        .color(theme.red));
+                                } else if let Some(shortcut) = shortcut {
+                                    ui.label(RichText::new(crate::shortcuts::shortcut_label(shortcut))
+                                        .font(ui_font(10.0))
+                                        .color(theme.text_dim));
+                                }
+                            });
                         });
+                    });
+                    if rows.is_empty() && step.is_some() {
+                        ui.label(RichText::new("No matches").font(ui_font(12.0)).color(theme.text_dim));
+                    }
                 });
 
-                // Keyboard navigation
-                if ui.input(|i| i.key_pressed(Key::ArrowDown)) {
-                    if self.selected < self.filtered.len().saturating_sub(1) {
-                        self.selected += 1;
-                    }
-                }
-                if ui.input(|i| i.key_pressed(Key::ArrowUp)) {
-                    if self.selected > 0 {
-                        self.selected -= 1;
+                match palette_list::navigate(ui, &mut self.selected, rows.len()) {
+                    Some(ListKey::Enter { .. }) => {
+                        if matches!(step, Some(PaletteStep::Input { .. })) {
+                            submitted = Some(self.query.clone());
+                        } else if self.selected < rows.len() {
+                            clicked_row = Some(self.selected);
+                            entered = true;
+                        }
                     }
+                    Some(ListKey::Escape) => back = true,
+                    None => {}
                 }
-                if ui.input(|i| i.key_pressed(Key::Enter)) {
-                    if let Some(cmd_match) = self.filtered.get(self.selected) {
-                        executed = Some(cmd_match.command.id);
+            });
+
+        if back {
+            self.confirm.reset();
+            self.back(choices);
+            return None;
+        }
+        // A dangerous command waits for a second Enter, or Enter held down
+        if let Some(id) = armed {
+            let selected = self.filtered.get(self.selected).map(|m| m.command);
+            match selected.filter(|command| self.flow.is_none() && command.id == id) {
+                Some(command) => {
+                    if self.confirm.hold(ctx.input(|i| i.key_down(Key::Enter)), now) {
+                        self.confirm.reset();
+                        return self.choose_command(command, choices);
                     }
+                    ctx.request_repaint_after(Duration::from_millis(50));
                 }
-                if ui.input(|i| i.key_pressed(Key::Escape)) {
-                    self.visible = false;
+                None => self.confirm.reset(),
+            }
+        }
+        if let Some(idx) = clicked_row {
+            match &self.flow {
+                None => {
+                    let command = self.filtered.get(idx)?.command;
+                    return self.pick(command, entered, now, choices);
                 }
-            });
-
-        if executed.is_some() {
-            self.visible = false;
+                Some(_) => submitted = rows.into_iter().nth(idx).map(|row| row.label),
+            }
         }
-
-        executed
+        if let Some(value) = submitted {
+            return self.submit(value, choices);
+        }
+        None
     }
 }
 
@@ -269,3 +1006,176 @@ impl Default for CommandPalette {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static PLAIN: Command = Command {
+        id: "plain",
+        label: "Plain",
+        shortcut: None,
+        keywords: &[],
+        dangerous: false,
+        steps: &[],
+    };
+
+    static MOVE: Command = Command {
+        id: "move_to_group",
+        label: "Move to Group…",
+        shortcut: None,
+        keywords: &[],
+        dangerous: false,
+        steps: &[
+            PaletteStep::Choice { prompt: "Group" },
+            PaletteStep::Input { prompt: "Note", validate: not_blank },
+        ],
+    };
+
+    fn groups(command: &'static str, args: &[String]) -> Vec<String> {
+        assert_eq!(command, "move_to_group");
+        assert!(args.is_empty());
+        vec!["alpha".to_string(), "beta".to_string()]
+    }
+
+    fn open_palette() -> CommandPalette {
+        let mut palette = CommandPalette::new();
+        palette.toggle();
+        palette
+    }
+
+    #[test]
+    fn test_command_without_steps_finishes_at_once() {
+        let mut palette = open_palette();
+        let action = palette.choose_command(&PLAIN, &groups);
+        assert_eq!(action, Some(PaletteAction { command: "plain", args: vec![] }));
+        assert!(!palette.is_visible());
+    }
+
+    #[test]
+    fn test_steps_collect_arguments() {
+        let mut palette = open_palette();
+        assert_eq!(palette.choose_command(&MOVE, &groups), None);
+        assert!(palette.is_visible());
+        assert_eq!(palette.filtered_choices(), ["alpha", "beta"]);
+
+        palette.query = "bt".to_string();
+        assert_eq!(palette.filtered_choices(), ["beta"]);
+
+        // Only listed choices are accepted
+        assert_eq!(palette.submit("gamma".to_string(), &groups), None);
+        assert!(palette.flow.as_ref().unwrap().args.is_empty());
+
+        assert_eq!(palette.submit("beta".to_string(), &groups), None);
+        assert!(palette.query.is_empty());
+        assert!(matches!(palette.flow.as_ref().unwrap().step(), PaletteStep::Input { .. }));
+
+        // Validation failure keeps the step open with an error
+        assert_eq!(palette.submit("  ".to_string(), &groups), None);
+        assert_eq!(palette.flow.as_ref().unwrap().error.as_deref(), Some("Can't be empty"));
+
+        let action = palette.submit("ship it".to_string(), &groups);
+        assert_eq!(action, Some(PaletteAction {
+            command: "move_to_group",
+            args: vec!["beta".to_string(), "ship it".to_string()],
+        }));
+        assert!(!palette.is_visible());
+        assert!(palette.flow.is_none());
+    }
+
+    #[test]
+    fn test_escape_steps_back_then_closes() {
+        let mut palette = open_palette();
+        palette.choose_command(&MOVE, &groups);
+        palette.submit("alpha".to_string(), &groups);
+        palette.submit(String::new(), &groups);
+
+        // Back to the choice, with its list reloaded and the error cleared
+        palette.back(&groups);
+        let flow = palette.flow.as_ref().unwrap();
+        assert!(flow.args.is_empty() && flow.error.is_none());
+        assert_eq!(flow.choices.len(), 2);
+
+        // Back to the command list
+        palette.back(&groups);
+        assert!(palette.flow.is_none());
+        assert!(palette.is_visible());
+        assert_eq!(palette.filtered.len(), COMMANDS.len());
+
+        palette.back(&groups);
+        assert!(!palette.is_visible());
+    }
+
+    static CLOSE: Command = Command {
+        id: "close_tab",
+        label: "Close Tab",
+        shortcut: None,
+        keywords: &[],
+        dangerous: true,
+        steps: &[],
+    };
+
+    #[test]
+    fn test_dangerous_command_needs_a_second_enter() {
+        let start = Instant::now();
+        let mut palette = open_palette();
+        assert_eq!(palette.pick(&CLOSE, true, start, &groups), None);
+        assert!(palette.is_visible());
+        // Let go of Enter, then press it again in time
+        palette.confirm.hold(false, start + Duration::from_millis(100));
+        let action = palette.pick(&CLOSE, true, start + Duration::from_millis(1500), &groups);
+        assert_eq!(action.map(|a| a.command), Some("close_tab"));
+
+        // Too late: the second press only arms it again
+        let mut palette = open_palette();
+        palette.pick(&CLOSE, false, start, &groups);
+        assert_eq!(palette.pick(&CLOSE, false, start + Duration::from_secs(3), &groups), None);
+    }
+
+    #[test]
+    fn test_other_commands_and_disabled_confirmation_run_at_once() {
+        let start = Instant::now();
+        let mut palette = open_palette();
+        assert!(palette.pick(&PLAIN, true, start, &groups).is_some());
+
+        let mut palette = open_palette();
+        palette.set_confirm_dangerous(false);
+        assert!(palette.pick(&CLOSE, true, start, &groups).is_some());
+    }
+
+    #[test]
+    fn test_hidden_commands_are_left_out() {
+        let mut palette = open_palette();
+        palette.set_hidden(vec!["pause_file_watcher"]);
+        assert_eq!(palette.filtered.len(), COMMANDS.len() - 1);
+
+        palette.query = "file watcher".to_string();
+        palette.update_filter();
+        let labels: Vec<_> = palette.filtered.iter().map(|m| m.command.label).collect();
+        assert!(labels.contains(&"Resume File Watcher"));
+        assert!(!labels.contains(&"Pause File Watcher"));
+
+        // Still reachable by ID
+        let action = palette.start("pause_file_watcher", &groups);
+        assert_eq!(action.map(|a| a.command), Some("pause_file_watcher"));
+    }
+
+    #[test]
+    fn test_disabled_commands_stay_listed_but_do_not_run() {
+        let start = Instant::now();
+        let mut palette = open_palette();
+        palette.set_disabled(vec![("plain", "File watching unavailable: too many open files".to_string())]);
+        assert_eq!(palette.disabled_reason("plain"), Some("File watching unavailable: too many open files"));
+
+        // Enter, held Enter and clicks all do nothing, and the palette stays open
+        assert_eq!(palette.pick(&PLAIN, true, start, &groups), None);
+        assert_eq!(palette.pick(&PLAIN, false, start, &groups), None);
+        assert!(palette.is_visible() && palette.flow.is_none());
+
+        let mut palette = open_palette();
+        palette.set_disabled(vec![("pause_file_watcher", "no".to_string())]);
+        assert_eq!(palette.filtered.len(), COMMANDS.len());
+        assert_eq!(palette.start("pause_file_watcher", &groups), None);
+        assert!(palette.start("resume_file_watcher", &groups).is_some());
+    }
+}
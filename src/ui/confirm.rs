@@ -0,0 +1,164 @@
+//! Confirmation for dangerous actions
+//!
+//! A dangerous action runs on a second press within `CONFIRM_WINDOW`, or
+//! when the first press is held for `HOLD_TO_CONFIRM`. Any UI surface can
+//! keep a `ConfirmState` keyed by what the action applies to, so every
+//! confirmation behaves the same.
+
+use std::time::{Duration, Instant};
+
+/// Time allowed for the second press
+pub const CONFIRM_WINDOW: Duration = Duration::from_secs(2);
+
+/// Holding the first press this long confirms too
+pub const HOLD_TO_CONFIRM: Duration = Duration::from_millis(400);
+
+/// First press of an action, waiting for confirmation
+#[derive(Debug, Clone)]
+struct Armed<K> {
+    key: K,
+    at: Instant,
+    /// The press was let go, so a new press is a second one
+    released: bool,
+}
+
+/// Pending confirmation of at most one action
+#[derive(Debug, Clone)]
+pub struct ConfirmState<K> {
+    armed: Option<Armed<K>>,
+}
+
+impl<K: PartialEq + Clone> ConfirmState<K> {
+    pub fn new() -> Self {
+        Self { armed: None }
+    }
+
+    /// A press (key down or repeat) on `key`'s action; true when confirmed
+    ///
+    /// Repeats of a press that's still held don't count as a second press.
+    pub fn press(&mut self, key: K, now: Instant) -> bool {
+        match &self.armed {
+            Some(armed) if armed.key == key && !armed.released => false,
+            Some(armed) if armed.key == key && now.duration_since(armed.at) <= CONFIRM_WINDOW => {
+                self.armed = None;
+                true
+            }
+            _ => {
+                self.armed = Some(Armed { key, at: now, released: false });
+                false
+            }
+        }
+    }
+
+    /// A click on `key`'s action (a press that's let go at once); true when confirmed
+    pub fn click(&mut self, key: K, now: Instant) -> bool {
+        let confirmed = self.press(key, now);
+        if let Some(armed) = &mut self.armed {
+            armed.released = true;
+        }
+        confirmed
+    }
+
+    /// Whether the first press is still held down; true once held long enough
+    pub fn hold(&mut self, down: bool, now: Instant) -> bool {
+        let Some(armed) = &mut self.armed else {
+            return false;
+        };
+        if armed.released {
+            return false;
+        }
+        if !down {
+            armed.released = true;
+            return false;
+        }
+        if now.duration_since(armed.at) >= HOLD_TO_CONFIRM {
+            self.armed = None;
+            return true;
+        }
+        false
+    }
+
+    /// Action waiting for confirmation, if its window hasn't run out
+    pub fn armed(&self, now: Instant) -> Option<&K> {
+        self.armed
+            .as_ref()
+            .filter(|armed| !armed.released || now.duration_since(armed.at) <= CONFIRM_WINDOW)
+            .map(|armed| &armed.key)
+    }
+
+    /// How far along holding the first press is (0 to 1), while it's held
+    pub fn hold_progress(&self, now: Instant) -> Option<f32> {
+        let armed = self.armed.as_ref().filter(|armed| !armed.released)?;
+        Some((now.duration_since(armed.at).as_secs_f32() / HOLD_TO_CONFIRM.as_secs_f32()).min(1.0))
+    }
+
+    /// Forget the pending confirmation
+    pub fn reset(&mut self) {
+        self.armed = None;
+    }
+}
+
+impl<K: PartialEq + Clone> Default for ConfirmState<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_second_press_within_the_window_confirms() {
+        let start = Instant::now();
+        let mut confirm = ConfirmState::new();
+        assert!(!confirm.press("close_tab", start));
+        confirm.hold(false, start + ms(50));
+        assert_eq!(confirm.armed(start + ms(1000)), Some(&"close_tab"));
+        assert!(confirm.press("close_tab", start + ms(1900)));
+        assert_eq!(confirm.armed(start + ms(1900)), None);
+    }
+
+    #[test]
+    fn test_late_second_press_starts_over() {
+        let start = Instant::now();
+        let mut confirm = ConfirmState::new();
+        assert!(!confirm.click("close_tab", start));
+        assert_eq!(confirm.armed(start + CONFIRM_WINDOW + ms(1)), None);
+        assert!(!confirm.click("close_tab", start + CONFIRM_WINDOW + ms(1)));
+        assert!(confirm.click("close_tab", start + CONFIRM_WINDOW + ms(500)));
+    }
+
+    #[test]
+    fn test_pressing_another_action_rearms() {
+        let start = Instant::now();
+        let mut confirm = ConfirmState::new();
+        confirm.click("close_tab", start);
+        assert!(!confirm.click("close_pane", start + ms(100)));
+        assert_eq!(confirm.armed(start + ms(100)), Some(&"close_pane"));
+        assert!(confirm.click("close_pane", start + ms(200)));
+    }
+
+    #[test]
+    fn test_holding_confirms_and_repeats_dont_count() {
+        let start = Instant::now();
+        let mut confirm = ConfirmState::new();
+        confirm.press("close_tab", start);
+        // Key repeats while held
+        assert!(!confirm.press("close_tab", start + ms(100)));
+        assert!(!confirm.hold(true, start + ms(200)));
+        assert_eq!(confirm.hold_progress(start + ms(200)), Some(0.5));
+        assert!(confirm.hold(true, start + HOLD_TO_CONFIRM));
+
+        // Let go early: no hold, but a second press still works
+        confirm.press("close_tab", start);
+        assert!(!confirm.hold(false, start + ms(100)));
+        assert!(!confirm.hold(true, start + ms(900)));
+        assert_eq!(confirm.hold_progress(start + ms(900)), None);
+        assert!(confirm.press("close_tab", start + ms(900)));
+    }
+}
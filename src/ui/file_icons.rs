@@ -0,0 +1,303 @@
+//! File Icons
+//!
+//! Maps file names to a short glyph and an accent color for the sidebar.
+
+use egui::Color32;
+use crate::config::{FileIconStyle, RuntimeTheme};
+use crate::theme::ui_font;
+
+/// Nerd Font code points checked before using the Nerd glyph set
+const NERD_PROBE: &str = "\u{e7a8}\u{e60b}\u{f023}";
+
+/// Broad file category (decides the icon color)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconCategory {
+    Code,
+    Data,
+    Docs,
+    Shell,
+    Build,
+    Media,
+    Vcs,
+    Lock,
+    Other,
+}
+
+impl IconCategory {
+    /// Accent color for this category
+    pub fn color(&self, theme: &RuntimeTheme) -> Color32 {
+        match self {
+            IconCategory::Code => theme.cyan,
+            IconCategory::Data => theme.yellow,
+            IconCategory::Docs => theme.blue,
+            IconCategory::Shell => theme.green,
+            IconCategory::Build => theme.secondary,
+            IconCategory::Media => theme.magenta,
+            IconCategory::Vcs => theme.red,
+            IconCategory::Lock | IconCategory::Other => theme.text_dim,
+        }
+    }
+}
+
+/// Recognized file kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    C,
+    Cpp,
+    Html,
+    Css,
+    Json,
+    Toml,
+    Yaml,
+    Config,
+    Markdown,
+    Text,
+    Shell,
+    Make,
+    Docker,
+    Image,
+    Git,
+    Lock,
+    Other,
+}
+
+impl FileKind {
+    fn category(self) -> IconCategory {
+        match self {
+            FileKind::Rust | FileKind::Python | FileKind::JavaScript | FileKind::TypeScript |
+            FileKind::Go | FileKind::C | FileKind::Cpp | FileKind::Html | FileKind::Css => IconCategory::Code,
+            FileKind::Json | FileKind::Toml | FileKind::Yaml | FileKind::Config => IconCategory::Data,
+            FileKind::Markdown | FileKind::Text => IconCategory::Docs,
+            FileKind::Shell => IconCategory::Shell,
+            FileKind::Make | FileKind::Docker => IconCategory::Build,
+            FileKind::Image => IconCategory::Media,
+            FileKind::Git => IconCategory::Vcs,
+            FileKind::Lock => IconCategory::Lock,
+            FileKind::Other => IconCategory::Other,
+        }
+    }
+
+    /// Glyph that renders in any monospace font
+    fn ascii_glyph(self) -> &'static str {
+        match self {
+            FileKind::Rust => "λ",
+            FileKind::Python => "py",
+            FileKind::JavaScript => "js",
+            FileKind::TypeScript => "ts",
+            FileKind::Go => "go",
+            FileKind::C => "c",
+            FileKind::Cpp => "c+",
+            FileKind::Html => "<>",
+            FileKind::Css => "≋",
+            FileKind::Json => "{}",
+            FileKind::Toml => "◆",
+            FileKind::Yaml => "◇",
+            FileKind::Config => "≔",
+            FileKind::Markdown => "#",
+            FileKind::Text => "≡",
+            FileKind::Shell => "$",
+            FileKind::Make => "mk",
+            FileKind::Docker => "▤",
+            FileKind::Image => "▣",
+            FileKind::Git => "±",
+            FileKind::Lock => "⚙",
+            FileKind::Other => "-",
+        }
+    }
+
+    /// Nerd Font glyph
+    fn nerd_glyph(self) -> &'static str {
+        match self {
+            FileKind::Rust => "\u{e7a8}",
+            FileKind::Python => "\u{e606}",
+            FileKind::JavaScript => "\u{e74e}",
+            FileKind::TypeScript => "\u{e628}",
+            FileKind::Go => "\u{e627}",
+            FileKind::C => "\u{e61e}",
+            FileKind::Cpp => "\u{e61d}",
+            FileKind::Html => "\u{e736}",
+            FileKind::Css => "\u{e749}",
+            FileKind::Json => "\u{e60b}",
+            FileKind::Toml | FileKind::Yaml | FileKind::Config => "\u{e615}",
+            FileKind::Markdown => "\u{e609}",
+            FileKind::Text => "\u{f15c}",
+            FileKind::Shell => "\u{f489}",
+            FileKind::Make => "\u{f0ad}",
+            FileKind::Docker => "\u{f308}",
+            FileKind::Image => "\u{f1c5}",
+            FileKind::Git => "\u{e702}",
+            FileKind::Lock => "\u{f023}",
+            FileKind::Other => "\u{f15b}",
+        }
+    }
+}
+
+/// Icon for one sidebar row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileIcon {
+    pub glyph: &'static str,
+    pub category: IconCategory,
+}
+
+/// Icon for a file name in the given style
+///
+/// `Off` gives the plain tree marker; `Nerd` should already be resolved
+/// against the font (see [`resolve_style`]).
+pub fn file_icon(name: &str, style: FileIconStyle) -> FileIcon {
+    let kind = classify(name);
+    let glyph = match style {
+        FileIconStyle::Off => return FileIcon { glyph: "-", category: IconCategory::Other },
+        FileIconStyle::Ascii => kind.ascii_glyph(),
+        FileIconStyle::Nerd => kind.nerd_glyph(),
+    };
+    FileIcon { glyph, category: kind.category() }
+}
+
+/// Fall back to ASCII glyphs when the font lacks Nerd Font icons
+pub fn resolve_style(configured: FileIconStyle, nerd_supported: bool) -> FileIconStyle {
+    match configured {
+        FileIconStyle::Nerd if !nerd_supported => FileIconStyle::Ascii,
+        style => style,
+    }
+}
+
+/// Can the monospace font render Nerd Font icons?
+///
+/// egui's bundled icon font covers some of the same code points, so the
+/// probe glyphs must also be exactly one cell wide.
+pub fn nerd_glyphs_supported(ctx: &egui::Context) -> bool {
+    let font_id = ui_font(11.0);
+    ctx.fonts(|fonts| {
+        if !fonts.has_glyphs(&font_id, NERD_PROBE) {
+            return false;
+        }
+        let cell = fonts.glyph_width(&font_id, 'M');
+        NERD_PROBE.chars().all(|c| (fonts.glyph_width(&font_id, c) - cell).abs() < 0.5)
+    })
+}
+
+/// Work out the file kind from its name
+fn classify(name: &str) -> FileKind {
+    let lower = name.to_lowercase();
+
+    // Whole-name matches first (lockfiles, extensionless build files, dotfiles)
+    match lower.as_str() {
+        "cargo.lock" | "package-lock.json" | "yarn.lock" | "pnpm-lock.yaml" |
+        "poetry.lock" | "gemfile.lock" | "composer.lock" | "flake.lock" => return FileKind::Lock,
+        "makefile" | "gnumakefile" | "justfile" | "cmakelists.txt" => return FileKind::Make,
+        "dockerfile" | "containerfile" | ".dockerignore" => return FileKind::Docker,
+        ".gitignore" | ".gitattributes" | ".gitmodules" | ".gitconfig" => return FileKind::Git,
+        ".bashrc" | ".bash_profile" | ".zshrc" | ".zprofile" | ".profile" => return FileKind::Shell,
+        "license" | "licence" | "copying" => return FileKind::Text,
+        _ => {}
+    }
+    if lower.starts_with("dockerfile.") || lower.ends_with(".dockerfile") {
+        return FileKind::Docker;
+    }
+    if lower == ".env" || lower.starts_with(".env.") {
+        return FileKind::Config;
+    }
+
+    // Leading dot doesn't start an extension (".editorconfig" has none)
+    let Some((stem, ext)) = lower.rsplit_once('.') else {
+        return FileKind::Other;
+    };
+    if stem.is_empty() {
+        return FileKind::Config;
+    }
+
+    match ext {
+        "rs" => FileKind::Rust,
+        "py" | "pyi" => FileKind::Python,
+        "js" | "mjs" | "cjs" | "jsx" => FileKind::JavaScript,
+        "ts" | "tsx" | "mts" => FileKind::TypeScript,
+        "go" => FileKind::Go,
+        "c" | "h" => FileKind::C,
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => FileKind::Cpp,
+        "html" | "htm" => FileKind::Html,
+        "css" | "scss" | "sass" | "less" => FileKind::Css,
+        "json" | "jsonc" | "json5" => FileKind::Json,
+        "toml" => FileKind::Toml,
+        "yaml" | "yml" => FileKind::Yaml,
+        "ini" | "cfg" | "conf" | "env" => FileKind::Config,
+        "md" | "markdown" | "mdx" => FileKind::Markdown,
+        "txt" | "rst" | "log" => FileKind::Text,
+        "sh" | "bash" | "zsh" | "fish" => FileKind::Shell,
+        "mk" | "cmake" => FileKind::Make,
+        "png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "ico" | "bmp" => FileKind::Image,
+        "lock" => FileKind::Lock,
+        _ => FileKind::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ascii(name: &str) -> &'static str {
+        file_icon(name, FileIconStyle::Ascii).glyph
+    }
+
+    #[test]
+    fn test_extension_mapping() {
+        assert_eq!(ascii("main.rs"), "λ");
+        assert_eq!(ascii("package.json"), "{}");
+        assert_eq!(ascii("Cargo.toml"), "◆");
+        assert_eq!(ascii("README.md"), "#");
+        assert_eq!(ascii("build.SH"), "$");
+        assert_eq!(file_icon("main.rs", FileIconStyle::Ascii).category, IconCategory::Code);
+    }
+
+    #[test]
+    fn test_lockfiles() {
+        assert_eq!(ascii("Cargo.lock"), "⚙");
+        assert_eq!(ascii("package-lock.json"), "⚙");
+        assert_eq!(ascii("pnpm-lock.yaml"), "⚙");
+        assert_eq!(ascii("something.lock"), "⚙");
+    }
+
+    #[test]
+    fn test_names_without_extension() {
+        assert_eq!(ascii("Makefile"), "mk");
+        assert_eq!(ascii("Dockerfile"), "▤");
+        assert_eq!(ascii("Dockerfile.dev"), "▤");
+        assert_eq!(ascii("LICENSE"), "≡");
+        assert_eq!(ascii("random"), "-");
+    }
+
+    #[test]
+    fn test_dotfiles() {
+        assert_eq!(ascii(".gitignore"), "±");
+        assert_eq!(ascii(".zshrc"), "$");
+        assert_eq!(ascii(".env.local"), "≔");
+        // Unknown dotfiles are treated as config, not as an extension
+        assert_eq!(ascii(".editorconfig"), "≔");
+    }
+
+    #[test]
+    fn test_unknown_extension() {
+        let icon = file_icon("data.xyz", FileIconStyle::Ascii);
+        assert_eq!(icon.glyph, "-");
+        assert_eq!(icon.category, IconCategory::Other);
+    }
+
+    #[test]
+    fn test_off_style_uses_plain_marker() {
+        let icon = file_icon("main.rs", FileIconStyle::Off);
+        assert_eq!(icon.glyph, "-");
+        assert_eq!(icon.category, IconCategory::Other);
+    }
+
+    #[test]
+    fn test_nerd_falls_back_without_font_support() {
+        assert_eq!(resolve_style(FileIconStyle::Nerd, false), FileIconStyle::Ascii);
+        assert_eq!(resolve_style(FileIconStyle::Nerd, true), FileIconStyle::Nerd);
+        assert_eq!(resolve_style(FileIconStyle::Off, true), FileIconStyle::Off);
+        assert_eq!(file_icon("main.rs", FileIconStyle::Nerd).glyph, "\u{e7a8}");
+    }
+}
@@ -0,0 +1,141 @@
+//! Font Diagnostics dialog
+
+use egui::{Frame, Key, RichText};
+use crate::config::RuntimeTheme;
+use crate::font_diagnostics::{ProbeReport, Remedy};
+use crate::theme::ui_font;
+
+/// Dialog state
+pub struct FontDiagnosticsDialog {
+    visible: bool,
+    reports: Vec<ProbeReport>,
+    remedies: Vec<Remedy>,
+    /// Ask for a new run on the next frame, once changed fonts are in use
+    rerun: bool,
+}
+
+/// What the user picked in the dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontDiagnosticsAction {
+    Rerun,
+    Apply(Remedy),
+}
+
+impl FontDiagnosticsDialog {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            reports: Vec::new(),
+            remedies: Vec::new(),
+            rerun: false,
+        }
+    }
+
+    /// Show the results of a run
+    pub fn open(&mut self, reports: Vec<ProbeReport>, remedies: Vec<Remedy>) {
+        self.visible = true;
+        self.reports = reports;
+        self.remedies = remedies;
+    }
+
+    /// Run again on the next frame
+    pub fn rerun_next_frame(&mut self) {
+        self.rerun = true;
+    }
+
+    /// Is dialog visible?
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Show dialog and return the action picked
+    pub fn show(&mut self, ctx: &egui::Context, theme: &RuntimeTheme) -> Option<FontDiagnosticsAction> {
+        if !self.visible {
+            return None;
+        }
+        if std::mem::take(&mut self.rerun) {
+            ctx.request_repaint();
+            return Some(FontDiagnosticsAction::Rerun);
+        }
+
+        let mut action = None;
+        let mut close = false;
+
+        egui::Window::new("Font Diagnostics")
+            .collapsible(false)
+            .resizable(false)
+            .fixed_pos(egui::pos2(ctx.screen_rect().width() * 0.5 - 275.0, 100.0))
+            .fixed_size(egui::vec2(550.0, 0.0))
+            .frame(Frame::window(&ctx.style())
+                .fill(theme.surface)
+                .stroke(egui::Stroke::new(1.0, theme.border)))
+            .show(ctx, |ui| {
+                ui.label(RichText::new("Terminal font, hover a glyph to see which font drew it")
+                    .font(ui_font(11.0))
+                    .color(theme.text_dim));
+                ui.add_space(4.0);
+
+                egui::Grid::new("font_diagnostics_grid")
+                    .num_columns(3)
+                    .spacing([16.0, 6.0])
+                    .show(ui, |ui| {
+                        for report in &self.reports {
+                            ui.label(RichText::new(report.name).font(ui_font(12.0)).color(theme.text));
+
+                            ui.horizontal(|ui| {
+                                ui.spacing_mut().item_spacing.x = 2.0;
+                                for glyph in &report.glyphs {
+                                    let color = if glyph.font.is_some() { theme.text } else { theme.red };
+                                    let served_by = glyph.font.as_deref().unwrap_or("replacement character");
+                                    ui.label(RichText::new(glyph.glyph.to_string()).font(ui_font(14.0)).color(color))
+                                        .on_hover_text(format!("U+{:04X} · {}", glyph.glyph as u32, served_by));
+                                }
+                            });
+
+                            let missing = report.missing();
+                            let (status, color) = if missing == 0 {
+                                (format!("✓ {}", report.fonts().join(", ")), theme.green)
+                            } else {
+                                (format!("✗ {} of {} missing", missing, report.glyphs.len()), theme.red)
+                            };
+                            ui.label(RichText::new(status).font(ui_font(11.0)).color(color));
+                            ui.end_row();
+                        }
+                    });
+
+                if !self.remedies.is_empty() {
+                    ui.separator();
+                    for remedy in &self.remedies {
+                        ui.horizontal(|ui| {
+                            if ui.button(remedy.label()).clicked() {
+                                action = Some(FontDiagnosticsAction::Apply(*remedy));
+                            }
+                            ui.label(RichText::new(remedy.description())
+                                .font(ui_font(10.0))
+                                .color(theme.text_dim));
+                        });
+                    }
+                }
+
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Run Again").clicked() {
+                        action = Some(FontDiagnosticsAction::Rerun);
+                    }
+                    if ui.button("Close").clicked() {
+                        close = true;
+                    }
+                });
+
+                if ui.input(|i| i.key_pressed(Key::Escape)) {
+                    close = true;
+                }
+            });
+
+        if close {
+            self.visible = false;
+        }
+        action
+    }
+}
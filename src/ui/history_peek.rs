@@ -0,0 +1,156 @@
+//! History Peek overlay for recalling earlier commands from any pane
+
+use std::time::SystemTime;
+use egui::RichText;
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use crate::command_history::CommandEntry;
+use crate::config::RuntimeTheme;
+use crate::theme::ui_font;
+use super::palette_list::{self, ListKey};
+use super::status_bar::format_ago;
+
+/// What to do with the chosen command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryPeekAction {
+    /// Type it into the focused terminal without running it
+    Insert(String),
+    /// Type it and press Enter
+    Run(String),
+}
+
+/// History peek overlay state
+pub struct HistoryPeek {
+    visible: bool,
+    entries: Vec<CommandEntry>,
+    query: String,
+    /// Indices into `entries`, best match first
+    filtered: Vec<usize>,
+    selected: usize,
+    matcher: SkimMatcherV2,
+}
+
+impl HistoryPeek {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            entries: Vec::new(),
+            query: String::new(),
+            filtered: Vec::new(),
+            selected: 0,
+            matcher: SkimMatcherV2::default(),
+        }
+    }
+
+    /// Open the overlay with the given commands, newest first
+    pub fn open(&mut self, entries: Vec<CommandEntry>) {
+        self.visible = true;
+        self.entries = entries;
+        self.query.clear();
+        self.update_filter();
+    }
+
+    /// Is the overlay visible?
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Update filtered commands based on the query
+    fn update_filter(&mut self) {
+        let mut scored: Vec<(usize, i64)> = self.entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| {
+                if self.query.is_empty() {
+                    return Some((idx, 0));
+                }
+                self.matcher.fuzzy_match(&entry.command, &self.query).map(|score| (idx, score))
+            })
+            .collect();
+
+        // Stable, so equal scores stay newest first
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        self.filtered = scored.into_iter().map(|(idx, _)| idx).collect();
+        self.selected = 0;
+    }
+
+    /// Show the overlay and return the action for the chosen command
+    pub fn show(&mut self, ctx: &egui::Context, theme: &RuntimeTheme) -> Option<HistoryPeekAction> {
+        if !self.visible {
+            return None;
+        }
+
+        let result = palette_list::window("history_peek", ctx, theme)
+            .show(ctx, |ui| self.show_list(ui, theme))
+            .and_then(|r| r.inner)
+            .flatten();
+
+        if result.is_some() {
+            self.visible = false;
+        }
+
+        result
+    }
+
+    /// Command search list
+    fn show_list(&mut self, ui: &mut egui::Ui, theme: &RuntimeTheme) -> Option<HistoryPeekAction> {
+        if palette_list::search_field(ui, &mut self.query, "Search command history...", theme) {
+            self.update_filter();
+        }
+
+        ui.separator();
+
+        if self.entries.is_empty() {
+            ui.label(RichText::new("No commands yet. Prompts are found with the Copy Last Output prompt pattern.")
+                .font(ui_font(12.0))
+                .color(theme.text_dim));
+        }
+
+        let now = SystemTime::now();
+        let clicked = palette_list::rows(ui, theme, self.filtered.len(), self.selected, |ui, row, is_selected| {
+            let entry = &self.entries[self.filtered[row]];
+            ui.vertical(|ui| {
+                ui.label(RichText::new(&entry.command)
+                    .font(ui_font(12.0))
+                    .color(if is_selected { theme.text } else { theme.text_dim }));
+                let when = match entry.at {
+                    Some(at) => format_ago(Some(at), now),
+                    None => "scrollback".to_string(),
+                };
+                ui.label(RichText::new(format!("{} · {}", entry.source, when))
+                    .font(ui_font(10.0))
+                    .color(theme.secondary));
+            });
+        });
+        let mut chosen = clicked.map(|row| (self.filtered[row], false));
+
+        ui.label(RichText::new(format!(
+            "Enter to insert, {} to run",
+            crate::shortcuts::shortcut_label("Cmd+Enter")
+        ))
+            .font(ui_font(10.0))
+            .color(theme.text_dim));
+
+        match palette_list::navigate(ui, &mut self.selected, self.filtered.len()) {
+            Some(ListKey::Enter { primary }) => {
+                chosen = self.filtered.get(self.selected).map(|&idx| (idx, primary));
+            }
+            Some(ListKey::Escape) => self.visible = false,
+            None => {}
+        }
+
+        let (idx, run) = chosen?;
+        let command = self.entries[idx].command.clone();
+        Some(if run {
+            HistoryPeekAction::Run(command)
+        } else {
+            HistoryPeekAction::Insert(command)
+        })
+    }
+}
+
+impl Default for HistoryPeek {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,130 @@
+//! Import Settings Bundle dialog
+
+use egui::{Frame, Key, RichText};
+use std::path::PathBuf;
+use crate::config::RuntimeTheme;
+use crate::settings_bundle::{Component, ImportItem, Manifest};
+use crate::theme::ui_font;
+
+/// Bundle and components confirmed with Import
+#[derive(Debug, Clone)]
+pub struct ImportRequest {
+    pub bundle: PathBuf,
+    pub manifest: Manifest,
+    pub components: Vec<Component>,
+}
+
+/// A validated bundle waiting for confirmation
+struct PendingImport {
+    bundle: PathBuf,
+    manifest: Manifest,
+    /// Each component with whether it's checked
+    items: Vec<(ImportItem, bool)>,
+}
+
+/// Dialog state
+pub struct ImportDialog {
+    pending: Option<PendingImport>,
+}
+
+impl ImportDialog {
+    pub fn new() -> Self {
+        Self { pending: None }
+    }
+
+    /// Ask which components of a validated bundle to import (all checked)
+    pub fn open(&mut self, bundle: PathBuf, manifest: Manifest, plan: Vec<ImportItem>) {
+        self.pending = Some(PendingImport {
+            bundle,
+            manifest,
+            items: plan.into_iter().map(|item| (item, true)).collect(),
+        });
+    }
+
+    /// Is dialog visible?
+    pub fn is_visible(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Show dialog and return the request when Import is pressed
+    pub fn show(&mut self, ctx: &egui::Context, theme: &RuntimeTheme) -> Option<ImportRequest> {
+        let pending = self.pending.as_mut()?;
+
+        let mut confirmed = false;
+        let mut close = false;
+
+        egui::Window::new("Import Settings Bundle")
+            .collapsible(false)
+            .resizable(false)
+            .fixed_pos(egui::pos2(ctx.screen_rect().width() * 0.5 - 225.0, 100.0))
+            .fixed_size(egui::vec2(450.0, 0.0))
+            .frame(Frame::window(&ctx.style())
+                .fill(theme.surface)
+                .stroke(egui::Stroke::new(1.0, theme.border)))
+            .show(ctx, |ui| {
+                ui.label(RichText::new(pending.bundle.display().to_string())
+                    .font(ui_font(12.0))
+                    .color(theme.text));
+                ui.label(RichText::new(format!("Exported by VibeTerm {}", pending.manifest.app_version))
+                    .font(ui_font(11.0))
+                    .color(theme.text_dim));
+
+                ui.separator();
+
+                for (item, checked) in &mut pending.items {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(checked, RichText::new(item.component.label()).font(ui_font(12.0)));
+                        if item.overwrites {
+                            ui.label(RichText::new("replaces current")
+                                .font(ui_font(10.0))
+                                .color(theme.yellow));
+                        }
+                    });
+                }
+
+                ui.add_space(4.0);
+                ui.label(RichText::new("Replaced files are backed up to backups/ in the config folder first")
+                    .font(ui_font(10.0))
+                    .color(theme.text_dim));
+
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    let any_checked = pending.items.iter().any(|(_, checked)| *checked);
+                    if ui.add_enabled(any_checked, egui::Button::new("Import")).clicked() {
+                        confirmed = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        close = true;
+                    }
+                });
+
+                if ui.input(|i| i.key_pressed(Key::Escape)) {
+                    close = true;
+                }
+            });
+
+        if !confirmed {
+            if close {
+                self.pending = None;
+            }
+            return None;
+        }
+        let pending = self.pending.take()?;
+        Some(ImportRequest {
+            components: pending.items
+                .iter()
+                .filter(|(_, checked)| *checked)
+                .map(|(item, _)| item.component)
+                .collect(),
+            bundle: pending.bundle,
+            manifest: pending.manifest,
+        })
+    }
+}
+
+impl Default for ImportDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,143 @@
+//! Jump to Mark list for the focused pane's scrollback marks
+
+use std::time::SystemTime;
+use egui::RichText;
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use crate::config::RuntimeTheme;
+use crate::scrollback_marks::Mark;
+use crate::theme::ui_font;
+use super::palette_list::{self, ListKey};
+use super::status_bar::format_ago;
+
+/// Mark list state
+pub struct MarkList {
+    visible: bool,
+    marks: Vec<Mark>,
+    query: String,
+    /// Indices into `marks`, best match first
+    filtered: Vec<usize>,
+    selected: usize,
+    matcher: SkimMatcherV2,
+}
+
+impl MarkList {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            marks: Vec::new(),
+            query: String::new(),
+            filtered: Vec::new(),
+            selected: 0,
+            matcher: SkimMatcherV2::default(),
+        }
+    }
+
+    /// Open the list with a pane's marks, top to bottom
+    pub fn open(&mut self, marks: Vec<Mark>) {
+        self.visible = true;
+        self.marks = marks;
+        self.query.clear();
+        self.update_filter();
+        // Start at the newest mark
+        self.selected = self.filtered.len().saturating_sub(1);
+    }
+
+    /// Is the list visible?
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Update filtered marks based on the query
+    fn update_filter(&mut self) {
+        let mut scored: Vec<(usize, i64)> = self.marks
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, mark)| {
+                if self.query.is_empty() {
+                    return Some((idx, 0));
+                }
+                let text = format!("{} {}", mark.label, mark.preview);
+                self.matcher.fuzzy_match(&text, &self.query).map(|score| (idx, score))
+            })
+            .collect();
+
+        // Stable, so equal scores stay top to bottom
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        self.filtered = scored.into_iter().map(|(idx, _)| idx).collect();
+        self.selected = 0;
+    }
+
+    /// Show the list and return the index of the chosen mark
+    pub fn show(&mut self, ctx: &egui::Context, theme: &RuntimeTheme) -> Option<usize> {
+        if !self.visible {
+            return None;
+        }
+
+        let result = palette_list::window("mark_list", ctx, theme)
+            .show(ctx, |ui| self.show_list(ui, theme))
+            .and_then(|r| r.inner)
+            .flatten();
+
+        if result.is_some() {
+            self.visible = false;
+        }
+
+        result
+    }
+
+    /// Mark search list
+    fn show_list(&mut self, ui: &mut egui::Ui, theme: &RuntimeTheme) -> Option<usize> {
+        if palette_list::search_field(ui, &mut self.query, "Jump to mark...", theme) {
+            self.update_filter();
+        }
+
+        ui.separator();
+
+        if self.marks.is_empty() {
+            ui.label(RichText::new(format!(
+                "No marks in this pane. {} marks the current line.",
+                crate::shortcuts::shortcut_label("Cmd+Shift+M")
+            ))
+                .font(ui_font(12.0))
+                .color(theme.text_dim));
+        }
+
+        let now = SystemTime::now();
+        let mut chosen = palette_list::rows(ui, theme, self.filtered.len(), self.selected, |ui, row, is_selected| {
+            let mark = &self.marks[self.filtered[row]];
+            ui.vertical(|ui| {
+                let mut label = RichText::new(&mark.label)
+                    .font(ui_font(12.0))
+                    .color(if is_selected { theme.text } else { theme.text_dim });
+                if mark.trimmed {
+                    label = label.strikethrough();
+                }
+                ui.label(label);
+                let detail = if mark.trimmed {
+                    format!("{} · scrolled out of the history", format_ago(Some(mark.at), now))
+                } else {
+                    format!("{} · {}", format_ago(Some(mark.at), now), mark.preview)
+                };
+                ui.label(RichText::new(detail)
+                    .font(ui_font(10.0))
+                    .color(theme.secondary));
+            });
+        })
+        .map(|row| self.filtered[row]);
+
+        match palette_list::navigate(ui, &mut self.selected, self.filtered.len()) {
+            Some(ListKey::Enter { .. }) => chosen = self.filtered.get(self.selected).copied(),
+            Some(ListKey::Escape) => self.visible = false,
+            None => {}
+        }
+
+        chosen
+    }
+}
+
+impl Default for MarkList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
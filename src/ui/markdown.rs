@@ -0,0 +1,482 @@
+//! Markdown Rendering for the File Viewer
+//!
+//! Parses Markdown into a flat list of blocks once per file load and draws
+//! them with egui widgets: headings, emphasis, lists, block quotes, code
+//! fences, tables and links. HTML is left out.
+
+use egui::{FontId, Frame, Margin, RichText, Stroke, Ui};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use std::path::{Path, PathBuf};
+
+use crate::config::RuntimeTheme;
+use crate::theme::ui_font;
+
+/// Body text size
+const BODY_SIZE: f32 = 14.0;
+
+/// Heading sizes for levels 1 to 6
+const HEADING_SIZES: [f32; 6] = [24.0, 20.0, 17.0, 15.0, 14.0, 13.0];
+
+/// Indentation per list or quote level
+const INDENT: f32 = 18.0;
+
+/// Does `path` look like a Markdown file?
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"))
+}
+
+/// Inline text style
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct SpanStyle {
+    strong: bool,
+    emphasis: bool,
+    strikethrough: bool,
+    code: bool,
+}
+
+/// A run of text with one style
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Span {
+    text: String,
+    style: SpanStyle,
+    /// Link destination, as written
+    link: Option<String>,
+}
+
+/// A block of a rendered document
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Block {
+    Heading { level: usize, spans: Vec<Span> },
+    Paragraph { quote: usize, spans: Vec<Span> },
+    /// `marker` is empty for a later paragraph of the same item
+    ListItem { depth: usize, quote: usize, marker: String, spans: Vec<Span> },
+    Code { quote: usize, text: String },
+    /// The first row is the header
+    Table { rows: Vec<Vec<Vec<Span>>> },
+    Rule,
+}
+
+/// A parsed Markdown file
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownDocument {
+    blocks: Vec<Block>,
+}
+
+/// State while turning parser events into blocks
+#[derive(Default)]
+struct Builder {
+    blocks: Vec<Block>,
+    spans: Vec<Span>,
+    style: SpanStyle,
+    link: Option<String>,
+    heading: Option<usize>,
+    quote: usize,
+    /// Next number of each open list (`None` for bullet lists)
+    lists: Vec<Option<u64>>,
+    /// Marker of the list item whose text hasn't been flushed yet
+    marker: Option<String>,
+    code: Option<String>,
+    table: Option<Vec<Vec<Vec<Span>>>>,
+}
+
+impl Builder {
+    fn text(&mut self, text: &str, style: SpanStyle) {
+        self.spans.push(Span {
+            text: text.to_string(),
+            style,
+            link: self.link.clone(),
+        });
+    }
+
+    /// End the current line of inline text
+    fn flush(&mut self) {
+        if self.spans.is_empty() {
+            return;
+        }
+        let spans = std::mem::take(&mut self.spans);
+        let block = if let Some(level) = self.heading {
+            Block::Heading { level, spans }
+        } else if !self.lists.is_empty() {
+            Block::ListItem {
+                depth: self.lists.len() - 1,
+                quote: self.quote,
+                marker: self.marker.take().unwrap_or_default(),
+                spans,
+            }
+        } else {
+            Block::Paragraph { quote: self.quote, spans }
+        };
+        self.blocks.push(block);
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush();
+                self.heading = Some(level as usize);
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.quote += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.flush();
+                self.code = Some(String::new());
+            }
+            Tag::List(start) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "•".to_string(),
+                };
+                self.marker = Some(marker);
+            }
+            Tag::Table(_) => {
+                self.flush();
+                self.table = Some(Vec::new());
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(rows) = &mut self.table {
+                    rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => self.spans.clear(),
+            Tag::Emphasis => self.style.emphasis = true,
+            Tag::Strong => self.style.strong = true,
+            Tag::Strikethrough => self.style.strikethrough = true,
+            Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                self.link = Some(dest_url.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Item => self.flush(),
+            TagEnd::Heading(_) => {
+                self.flush();
+                self.heading = None;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quote = self.quote.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => {
+                if let Some(text) = self.code.take() {
+                    let text = text.strip_suffix('\n').unwrap_or(&text).to_string();
+                    self.blocks.push(Block::Code { quote: self.quote, text });
+                }
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+            }
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.spans);
+                if let Some(row) = self.table.as_mut().and_then(|rows| rows.last_mut()) {
+                    row.push(cell);
+                }
+            }
+            TagEnd::Table => {
+                if let Some(rows) = self.table.take() {
+                    self.blocks.push(Block::Table { rows });
+                }
+            }
+            TagEnd::Emphasis => self.style.emphasis = false,
+            TagEnd::Strong => self.style.strong = false,
+            TagEnd::Strikethrough => self.style.strikethrough = false,
+            TagEnd::Link | TagEnd::Image => self.link = None,
+            _ => {}
+        }
+    }
+}
+
+impl MarkdownDocument {
+    pub fn parse(source: &str) -> Self {
+        let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+        let mut builder = Builder::default();
+
+        for event in Parser::new_ext(source, options) {
+            match event {
+                Event::Start(tag) => builder.start(tag),
+                Event::End(tag) => builder.end(tag),
+                Event::Text(text) => match &mut builder.code {
+                    Some(code) => code.push_str(&text),
+                    None => builder.text(&text, builder.style),
+                },
+                Event::Code(text) => builder.text(&text, SpanStyle { code: true, ..builder.style }),
+                Event::SoftBreak => builder.text(" ", builder.style),
+                Event::HardBreak => builder.text("\n", builder.style),
+                Event::Rule => {
+                    builder.flush();
+                    builder.blocks.push(Block::Rule);
+                }
+                Event::TaskListMarker(checked) => {
+                    builder.marker = Some(if checked { "[x]" } else { "[ ]" }.to_string());
+                }
+                _ => {}
+            }
+        }
+        builder.flush();
+
+        Self { blocks: builder.blocks }
+    }
+
+    /// Draw the document and return the link the user clicked, if any
+    pub fn show(&self, ui: &mut Ui, theme: &RuntimeTheme) -> Option<String> {
+        let mut clicked = None;
+        for (index, block) in self.blocks.iter().enumerate() {
+            match block {
+                Block::Heading { level, spans } => {
+                    ui.add_space(6.0);
+                    let size = HEADING_SIZES[level.clamp(&1, &6) - 1];
+                    show_spans(ui, spans, FontId::proportional(size), true, theme, &mut clicked);
+                    if *level <= 2 {
+                        ui.separator();
+                    }
+                }
+                Block::Paragraph { quote, spans } => {
+                    quoted(ui, *quote, theme, |ui| {
+                        show_spans(ui, spans, FontId::proportional(BODY_SIZE), false, theme, &mut clicked);
+                    });
+                }
+                Block::ListItem { depth, quote, marker, spans } => {
+                    quoted(ui, *quote, theme, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.add_space(*depth as f32 * INDENT);
+                            let marker_width = INDENT;
+                            let (rect, _) = ui.allocate_exact_size(
+                                egui::vec2(marker_width, BODY_SIZE),
+                                egui::Sense::hover(),
+                            );
+                            ui.painter().text(
+                                rect.left_top(),
+                                egui::Align2::LEFT_TOP,
+                                marker,
+                                FontId::proportional(BODY_SIZE),
+                                theme.text_dim,
+                            );
+                            ui.vertical(|ui| {
+                                show_spans(ui, spans, FontId::proportional(BODY_SIZE), false, theme, &mut clicked);
+                            });
+                        });
+                    });
+                }
+                Block::Code { quote, text } => {
+                    quoted(ui, *quote, theme, |ui| {
+                        Frame::NONE
+                            .fill(theme.surface)
+                            .corner_radius(4.0)
+                            .inner_margin(Margin::same(8))
+                            .show(ui, |ui| {
+                                ui.set_min_width(ui.available_width());
+                                ui.label(RichText::new(text).font(ui_font(12.0)).color(theme.text));
+                            });
+                    });
+                }
+                Block::Table { rows } => {
+                    egui::Grid::new(ui.id().with(("markdown_table", index)))
+                        .striped(true)
+                        .spacing(egui::vec2(16.0, 4.0))
+                        .show(ui, |ui| {
+                            for (row_index, row) in rows.iter().enumerate() {
+                                for cell in row {
+                                    ui.horizontal_wrapped(|ui| {
+                                        let font = FontId::proportional(BODY_SIZE);
+                                        show_spans(ui, cell, font, row_index == 0, theme, &mut clicked);
+                                    });
+                                }
+                                ui.end_row();
+                            }
+                        });
+                }
+                Block::Rule => {
+                    ui.separator();
+                }
+            }
+            ui.add_space(4.0);
+        }
+        clicked
+    }
+}
+
+/// Indent `add_contents` with a bar for each block quote level
+fn quoted(ui: &mut Ui, quote: usize, theme: &RuntimeTheme, add_contents: impl FnOnce(&mut Ui)) {
+    if quote == 0 {
+        add_contents(ui);
+        return;
+    }
+    let response = Frame::NONE
+        .inner_margin(Margin { left: (quote as f32 * INDENT) as i8, ..Margin::ZERO })
+        .show(ui, add_contents)
+        .response;
+    for level in 0..quote {
+        let x = response.rect.left() + level as f32 * INDENT + 3.0;
+        ui.painter().line_segment(
+            [egui::pos2(x, response.rect.top()), egui::pos2(x, response.rect.bottom())],
+            Stroke::new(2.0, theme.border),
+        );
+    }
+}
+
+/// Lay out inline text, wrapping at the available width
+fn show_spans(
+    ui: &mut Ui,
+    spans: &[Span],
+    font: FontId,
+    strong: bool,
+    theme: &RuntimeTheme,
+    clicked: &mut Option<String>,
+) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        for span in spans {
+            let mut text = RichText::new(&span.text).color(theme.text);
+            text = if span.style.code {
+                text.font(ui_font(font.size - 1.0)).background_color(theme.surface)
+            } else {
+                text.font(font.clone())
+            };
+            if strong || span.style.strong {
+                text = text.strong();
+            }
+            if span.style.emphasis {
+                text = text.italics();
+            }
+            if span.style.strikethrough {
+                text = text.strikethrough();
+            }
+
+            match &span.link {
+                Some(link) => {
+                    let response = ui.add(egui::Link::new(text.color(theme.primary))).on_hover_text(link);
+                    if response.clicked() {
+                        *clicked = Some(link.clone());
+                    }
+                }
+                None => {
+                    ui.label(text);
+                }
+            }
+        }
+    });
+}
+
+/// Where a link in a Markdown file points
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// Opened in the browser
+    External(String),
+    /// A file next to the Markdown file
+    File(PathBuf),
+    /// A heading in the same document (not followed)
+    Anchor,
+}
+
+/// Resolve a link written in the Markdown file in `base_dir`
+pub fn link_target(link: &str, base_dir: &Path) -> LinkTarget {
+    if link.starts_with('#') {
+        return LinkTarget::Anchor;
+    }
+    let has_scheme = link
+        .split_once(':')
+        .is_some_and(|(scheme, _)| scheme.len() > 1 && scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '+'));
+    if has_scheme {
+        return LinkTarget::External(link.to_string());
+    }
+
+    let path = link.split(['#', '?']).next().unwrap_or(link).replace("%20", " ");
+    let path = Path::new(&path);
+    LinkTarget::File(if path.is_absolute() { path.to_path_buf() } else { base_dir.join(path) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(spans: &[Span]) -> String {
+        spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_blocks() {
+        let doc = MarkdownDocument::parse(
+            "# Title\n\nSome *nice* `code`.\n\n- one\n- two\n  1. nested\n\n> quoted\n\n```rust\nfn main() {}\n```\n\n---\n",
+        );
+        let kinds: Vec<String> = doc.blocks.iter().map(|block| match block {
+            Block::Heading { level, spans } => format!("h{} {}", level, text(spans)),
+            Block::Paragraph { quote, spans } => format!("p{} {}", quote, text(spans)),
+            Block::ListItem { depth, marker, spans, .. } => format!("li{} {} {}", depth, marker, text(spans)),
+            Block::Code { text, .. } => format!("code {}", text),
+            Block::Table { .. } => "table".to_string(),
+            Block::Rule => "rule".to_string(),
+        }).collect();
+        assert_eq!(kinds, [
+            "h1 Title",
+            "p0 Some nice code.",
+            "li0 • one",
+            "li0 • two",
+            "li1 1. nested",
+            "p1 quoted",
+            "code fn main() {}",
+            "rule",
+        ]);
+
+        let Block::Paragraph { spans, .. } = &doc.blocks[1] else {
+            panic!("expected a paragraph");
+        };
+        assert!(spans[1].style.emphasis);
+        assert!(spans[3].style.code);
+    }
+
+    #[test]
+    fn test_tables_links_and_tasks() {
+        let doc = MarkdownDocument::parse(
+            "| Key | Action |\n|-----|--------|\n| Cmd+T | [New tab](docs/tabs.md) |\n\n- [x] done\n- [ ] todo\n",
+        );
+        let Block::Table { rows } = &doc.blocks[0] else {
+            panic!("expected a table");
+        };
+        assert_eq!(rows.len(), 2);
+        assert_eq!(text(&rows[0][1]), "Action");
+        assert_eq!(rows[1][1][0].link.as_deref(), Some("docs/tabs.md"));
+
+        let markers: Vec<&str> = doc.blocks[1..].iter().filter_map(|block| match block {
+            Block::ListItem { marker, .. } => Some(marker.as_str()),
+            _ => None,
+        }).collect();
+        assert_eq!(markers, ["[x]", "[ ]"]);
+    }
+
+    #[test]
+    fn test_link_targets() {
+        let base = Path::new("/repo/docs");
+        assert_eq!(
+            link_target("https://example.com/a", base),
+            LinkTarget::External("https://example.com/a".to_string()),
+        );
+        assert_eq!(link_target("mailto:me@example.com", base), LinkTarget::External("mailto:me@example.com".to_string()));
+        assert_eq!(link_target("#usage", base), LinkTarget::Anchor);
+        assert_eq!(link_target("../README.md#install", base), LinkTarget::File(PathBuf::from("/repo/docs/../README.md")));
+        assert_eq!(link_target("My%20Notes.md", base), LinkTarget::File(PathBuf::from("/repo/docs/My Notes.md")));
+        assert_eq!(link_target("/etc/motd", base), LinkTarget::File(PathBuf::from("/etc/motd")));
+    }
+
+    #[test]
+    fn test_is_markdown() {
+        assert!(is_markdown(Path::new("README.md")));
+        assert!(is_markdown(Path::new("notes.MARKDOWN")));
+        assert!(!is_markdown(Path::new("main.rs")));
+        assert!(!is_markdown(Path::new("md")));
+    }
+}
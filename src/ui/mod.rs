@@ -7,9 +7,46 @@ mod sidebar;
 mod status_bar;
 mod command_palette;
 mod preferences;
+mod output_note;
+mod snippet_palette;
+mod run_all_dialog;
+mod file_icons;
+mod import_dialog;
+mod font_diagnostics_dialog;
+mod startup_report_dialog;
+mod clipboard_history_palette;
+mod palette_list;
+mod history_peek;
+mod ssh_palette;
+mod markdown;
+mod breadcrumb_bar;
+mod search_panel;
+mod pane_mirror;
+mod mark_list;
+mod confirm;
+mod stats_view;
+mod color_test_view;
 
-pub use tab_bar::{TabBar, TabInfo};
-pub use sidebar::{Sidebar, FileEntry, SidebarResponse};
-pub use status_bar::StatusBar;
+pub use tab_bar::{activity_age, clamp_tab_move, index_after_insert, index_after_move, new_tab_index, progress_color, TabBar, TabInfo, ACTIVITY_FADED};
+pub use breadcrumb_bar::{BreadcrumbBar, cd_command, shell_quote};
+pub use search_panel::SearchPanel;
+pub use sidebar::{Sidebar, FileEntry, SidebarResponse, FilterView, GitStatusSync, GIT_STATUS_CHUNK, filter_entries, update_tree_flags};
+pub use status_bar::{ContextStatus, StatusBar};
 pub use command_palette::CommandPalette;
-pub use preferences::{PreferencesWindow, PreferencesTab, PreferencesResponse, PreferencesCommand};
+pub use snippet_palette::SnippetPalette;
+pub use run_all_dialog::{RunAllDialog, RunAllRequest};
+pub use import_dialog::{ImportDialog, ImportRequest};
+pub use font_diagnostics_dialog::{FontDiagnosticsAction, FontDiagnosticsDialog};
+pub use startup_report_dialog::StartupReportDialog;
+pub use confirm::ConfirmState;
+pub use clipboard_history_palette::{ClipboardHistoryAction, ClipboardHistoryPalette};
+pub use history_peek::{HistoryPeek, HistoryPeekAction};
+pub use ssh_palette::{SshAction, SshPalette};
+pub use file_icons::{nerd_glyphs_supported, resolve_style};
+pub use markdown::{is_markdown, link_target, LinkTarget, MarkdownDocument};
+pub use pane_mirror::PaneMirror;
+pub use mark_list::MarkList;
+pub use stats_view::{show_stats, StatsCommand};
+pub use color_test_view::{show_color_test, ColorTestCommand};
+pub use output_note::{OutputNote, OutputNoteWindow, MAX_NOTES_PER_WORKSPACE};
+pub use preferences::{PreferencesWindow, PreferencesTab, PreferencesResponse, PreferencesCommand, ThemePreview};
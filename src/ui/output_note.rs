@@ -0,0 +1,152 @@
+//! Output Notes
+//!
+//! Floating windows holding pinned terminal output
+
+use egui::{Frame, RichText, ScrollArea};
+use serde::{Deserialize, Serialize};
+use crate::config::RuntimeTheme;
+use crate::theme::ui_font;
+
+/// Maximum number of notes per workspace
+pub const MAX_NOTES_PER_WORKSPACE: usize = 10;
+
+/// Maximum length of the default title
+const DEFAULT_TITLE_LEN: usize = 40;
+
+/// Snapshot of terminal output pinned into a floating note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputNote {
+    pub id: u64,
+    pub title: String,
+    pub text: String,
+}
+
+impl OutputNote {
+    /// Create a note titled after the first non-empty line of `text`
+    pub fn new(id: u64, text: impl Into<String>) -> Self {
+        let text = text.into();
+        let title = default_title(&text);
+        Self { id, title, text }
+    }
+}
+
+/// First non-empty line, truncated
+fn default_title(text: &str) -> String {
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("Output");
+    if line.chars().count() > DEFAULT_TITLE_LEN {
+        let truncated: String = line.chars().take(DEFAULT_TITLE_LEN).collect();
+        format!("{}…", truncated)
+    } else {
+        line.to_string()
+    }
+}
+
+/// Floating window for one output note
+pub struct OutputNoteWindow<'a> {
+    note: &'a mut OutputNote,
+    /// Distinguishes notes of different workspaces
+    workspace: usize,
+    /// Area the window stays inside
+    bounds: egui::Rect,
+    theme: &'a RuntimeTheme,
+}
+
+impl<'a> OutputNoteWindow<'a> {
+    pub fn new(
+        note: &'a mut OutputNote,
+        workspace: usize,
+        bounds: egui::Rect,
+        theme: &'a RuntimeTheme,
+    ) -> Self {
+        Self {
+            note,
+            workspace,
+            bounds,
+            theme,
+        }
+    }
+
+    /// Show the note window and return user actions
+    pub fn show(self, ctx: &egui::Context) -> OutputNoteResponse {
+        let mut response = OutputNoteResponse::default();
+        let theme = self.theme;
+        let note = self.note;
+
+        egui::Window::new(RichText::new(&note.title).font(ui_font(11.0)).color(theme.text))
+            .id(egui::Id::new(("output_note", self.workspace, note.id)))
+            .collapsible(true)
+            .resizable(true)
+            .default_size([360.0, 160.0])
+            .constrain_to(self.bounds)
+            .frame(Frame::window(&ctx.style())
+                .fill(theme.surface)
+                .stroke(egui::Stroke::new(1.0, theme.border)))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let title_response = ui.add(
+                        egui::TextEdit::singleline(&mut note.title)
+                            .font(ui_font(11.0))
+                            .desired_width(ui.available_width() - 90.0)
+                            .hint_text("Title")
+                    );
+                    response.editing = title_response.has_focus();
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button("✕").on_hover_text("Close note").clicked() {
+                            response.closed = true;
+                        }
+                        if ui.small_button("Copy").on_hover_text("Copy text to clipboard").clicked() {
+                            ui.ctx().copy_text(note.text.clone());
+                        }
+                    });
+                });
+
+                ui.separator();
+
+                ScrollArea::both()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        ui.add(egui::Label::new(
+                            RichText::new(&note.text)
+                                .font(ui_font(11.0))
+                                .color(theme.text)
+                        ).extend());
+                    });
+            });
+
+        response
+    }
+}
+
+/// Response from an output note window
+#[derive(Debug, Default)]
+pub struct OutputNoteResponse {
+    /// Close button clicked
+    pub closed: bool,
+    /// Title field has keyboard focus
+    pub editing: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_title_uses_first_line() {
+        let note = OutputNote::new(1, "\n  error[E0308]: mismatched types\n  --> src/main.rs:4:5\n");
+        assert_eq!(note.title, "error[E0308]: mismatched types");
+    }
+
+    #[test]
+    fn test_default_title_truncated() {
+        let long = "x".repeat(100);
+        let note = OutputNote::new(1, long);
+        assert_eq!(note.title.chars().count(), DEFAULT_TITLE_LEN + 1);
+        assert!(note.title.ends_with('…'));
+    }
+
+    #[test]
+    fn test_default_title_for_blank_text() {
+        assert_eq!(OutputNote::new(1, "  \n").title, "Output");
+    }
+}
@@ -0,0 +1,100 @@
+//! Searchable list shared by the palettes
+//!
+//! A prompt-style search field above a scrolling list with one highlighted
+//! row, moved with the arrow keys and chosen with Enter or a click.
+
+use egui::{Frame, Key, Modifiers, RichText, ScrollArea};
+use crate::config::RuntimeTheme;
+use crate::theme::ui_font;
+
+/// Key the list was closed or confirmed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKey {
+    /// Enter, with the primary modifier (Cmd, or Ctrl off macOS) held or not
+    Enter { primary: bool },
+    Escape,
+}
+
+/// Centered palette window
+pub fn window(id: &str, ctx: &egui::Context, theme: &RuntimeTheme) -> egui::Window<'static> {
+    egui::Window::new(id.to_string())
+        .title_bar(false)
+        .fixed_pos(egui::pos2(ctx.screen_rect().width() * 0.5 - 300.0, 100.0))
+        .fixed_size(egui::vec2(600.0, 400.0))
+        .frame(Frame::window(&ctx.style())
+            .fill(theme.surface)
+            .stroke(egui::Stroke::new(1.0, theme.border)))
+}
+
+/// Focused search field after a `❯` prompt; returns `true` when the query changed
+pub fn search_field(ui: &mut egui::Ui, query: &mut String, hint: &str, theme: &RuntimeTheme) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("❯").font(ui_font(14.0)).color(theme.primary));
+
+        let response = ui.add(egui::TextEdit::singleline(query)
+            .font(ui_font(14.0))
+            .desired_width(550.0)
+            .hint_text(hint));
+
+        // Auto-focus on open
+        response.request_focus();
+        response.changed()
+    })
+    .inner
+}
+
+/// Scrolling list of `count` rows drawn by `add_row(ui, row, is_selected)`;
+/// returns the clicked row
+pub fn rows(
+    ui: &mut egui::Ui,
+    theme: &RuntimeTheme,
+    count: usize,
+    selected: usize,
+    mut add_row: impl FnMut(&mut egui::Ui, usize, bool),
+) -> Option<usize> {
+    let mut clicked = None;
+    ScrollArea::vertical()
+        .max_height(320.0)
+        .show(ui, |ui| {
+            for row in 0..count {
+                let is_selected = row == selected;
+
+                let frame = Frame::NONE
+                    .fill(if is_selected { theme.selection } else { theme.surface })
+                    .inner_margin(egui::Margin { left: 8, right: 8, top: 4, bottom: 4 });
+
+                frame.show(ui, |ui| {
+                    add_row(ui, row, is_selected);
+
+                    if ui.interact(ui.max_rect(), ui.id().with(row), egui::Sense::click()).clicked() {
+                        clicked = Some(row);
+                    }
+                });
+            }
+        });
+    clicked
+}
+
+/// Move `selected` with the arrow keys; returns Enter or Escape if pressed
+///
+/// Enter and Escape are consumed, so the terminal the list closes onto
+/// doesn't also get them.
+pub fn navigate(ui: &egui::Ui, selected: &mut usize, count: usize) -> Option<ListKey> {
+    ui.input_mut(|i| {
+        if i.key_pressed(Key::ArrowDown) && *selected < count.saturating_sub(1) {
+            *selected += 1;
+        }
+        if i.key_pressed(Key::ArrowUp) && *selected > 0 {
+            *selected -= 1;
+        }
+        if i.consume_key(Modifiers::NONE, Key::Escape) {
+            Some(ListKey::Escape)
+        } else if i.consume_key(Modifiers::COMMAND, Key::Enter) {
+            Some(ListKey::Enter { primary: true })
+        } else if i.consume_key(Modifiers::NONE, Key::Enter) {
+            Some(ListKey::Enter { primary: false })
+        } else {
+            None
+        }
+    })
+}
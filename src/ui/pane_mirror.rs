@@ -0,0 +1,153 @@
+//! Pane Mirror window
+//!
+//! Read-only copy of one terminal in a window of its own, for screen
+//! sharing. It is painted from the terminal's grid: a second `TerminalView`
+//! on the same backend would resize the PTY to its own size every frame.
+//! Ambiguous-width characters follow `terminal.ambiguous_width`.
+
+use alacritty_terminal::grid::{Dimensions, Grid};
+use alacritty_terminal::index::Point;
+use alacritty_terminal::term::cell::{Cell, Flags};
+use alacritty_terminal::vte::ansi::{Color, NamedColor};
+use egui::{CentralPanel, Context, Frame, ViewportBuilder, ViewportClass, ViewportId};
+use egui_term::TerminalTheme;
+use crate::cell_width::painted_columns;
+use crate::config::AmbiguousWidth;
+use crate::theme::mono_font;
+
+/// Viewport ID of the mirror window
+const MIRROR_VIEWPORT_ID: &str = "vibeterm_pane_mirror";
+
+/// Mirror window showing one terminal grid
+pub struct PaneMirror<'a> {
+    title: String,
+    grid: &'a Grid<Cell>,
+    theme: &'a TerminalTheme,
+    font_size: f32,
+    ambiguous_width: AmbiguousWidth,
+}
+
+impl<'a> PaneMirror<'a> {
+    pub fn new(title: impl Into<String>, grid: &'a Grid<Cell>, theme: &'a TerminalTheme) -> Self {
+        Self {
+            title: title.into(),
+            grid,
+            theme,
+            font_size: 20.0,
+            ambiguous_width: AmbiguousWidth::Narrow,
+        }
+    }
+
+    /// Font size of the mirrored text (pt)
+    pub fn font_size(mut self, size: f32) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    /// How wide ambiguous-width characters are drawn
+    pub fn ambiguous_width(mut self, width: AmbiguousWidth) -> Self {
+        self.ambiguous_width = width;
+        self
+    }
+
+    /// Show the window; returns false once the user closed it
+    pub fn show(self, ctx: &Context) -> bool {
+        let mut open = true;
+        ctx.show_viewport_immediate(
+            ViewportId::from_hash_of(MIRROR_VIEWPORT_ID),
+            ViewportBuilder::default()
+                .with_title(format!("Mirror — {}", self.title))
+                .with_inner_size([1000.0, 640.0])
+                .with_close_button(true),
+            |ctx, class| {
+                if class == ViewportClass::Embedded {
+                    // No multi-window support: float it over the main window
+                    egui::Window::new(format!("Mirror — {}", self.title))
+                        .open(&mut open)
+                        .show(ctx, |ui| self.paint(ui));
+                    return;
+                }
+
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    open = false;
+                }
+                let background = self.theme.get_color(Color::Named(NamedColor::Background));
+                CentralPanel::default()
+                    .frame(Frame::NONE.fill(background).inner_margin(8))
+                    .show(ctx, |ui| self.paint(ui));
+            },
+        );
+        open
+    }
+
+    /// Paint the visible part of the grid, following its scroll position
+    fn paint(&self, ui: &mut egui::Ui) {
+        let font = mono_font(self.font_size);
+        let (cell_width, cell_height) = ui.fonts(|f| (f.glyph_width(&font, 'M'), f.row_height(&font)));
+        let size = egui::vec2(
+            cell_width * self.grid.columns() as f32,
+            cell_height * self.grid.screen_lines() as f32,
+        );
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+        let origin = response.rect.min;
+        let background = self.theme.get_color(Color::Named(NamedColor::Background));
+        painter.rect_filled(response.rect, 0.0, background);
+
+        let display_offset = self.grid.display_offset();
+        // Cell a wide ambiguous character was drawn over
+        let mut covered = None;
+        for indexed in self.grid.display_iter() {
+            let flags = indexed.cell.flags;
+            if flags.contains(Flags::WIDE_CHAR_SPACER) || covered == Some(indexed.point) {
+                continue;
+            }
+
+            let line = indexed.point.line.0 + display_offset as i32;
+            let next = Point::new(indexed.point.line, indexed.point.column + 1);
+            let next_blank = next.column.0 < self.grid.columns()
+                && next != self.grid.cursor.point
+                && is_blank(&self.grid[next]);
+            let columns = painted_columns(indexed.c, flags.contains(Flags::WIDE_CHAR), next_blank, self.ambiguous_width);
+            if columns == 2 && !flags.contains(Flags::WIDE_CHAR) {
+                covered = Some(next);
+            }
+            let width = cell_width * columns as f32;
+            let rect = egui::Rect::from_min_size(
+                origin + egui::vec2(cell_width * indexed.point.column.0 as f32, cell_height * line as f32),
+                egui::vec2(width, cell_height),
+            );
+
+            let (fg, bg) = cell_colors(self.theme, indexed.fg, indexed.bg, flags);
+            if bg != background {
+                painter.rect_filled(rect, 0.0, bg);
+            }
+
+            // Outline only: the mirror never has keyboard focus
+            if display_offset == 0 && indexed.point == self.grid.cursor.point {
+                painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, fg), egui::StrokeKind::Inside);
+            }
+
+            if indexed.c != ' ' && indexed.c != '\t' {
+                painter.text(rect.center(), egui::Align2::CENTER_CENTER, indexed.c, font.clone(), fg);
+            }
+        }
+    }
+}
+
+/// Empty cell on the default background
+fn is_blank(cell: &Cell) -> bool {
+    cell.c == ' ' && cell.bg == Color::Named(NamedColor::Background) && cell.flags.is_empty()
+}
+
+/// Colors a cell is drawn in, after dim and inverse
+pub fn cell_colors(theme: &TerminalTheme, fg: Color, bg: Color, flags: Flags) -> (egui::Color32, egui::Color32) {
+    let mut fg = theme.get_color(fg);
+    let mut bg = theme.get_color(bg);
+    if flags.intersects(Flags::DIM | Flags::DIM_BOLD) {
+        fg = fg.linear_multiply(0.7);
+    }
+    if flags.contains(Flags::INVERSE) {
+        std::mem::swap(&mut fg, &mut bg);
+    }
+    (fg, bg)
+}
@@ -9,18 +9,47 @@ use egui::{
     Align, Button, Frame, Layout, Margin, RichText, ScrollArea, Stroke, Vec2,
     ViewportBuilder, ViewportCommand, ViewportId,
 };
-use crate::config::{Config, RuntimeTheme, ThemeConfig, UiConfig};
-use crate::theme::mono_font;
+use crate::capabilities::{Capabilities, Capability};
+use crate::config::{Config, FileIconStyle, FontConfig, NewTerminalDir, RuntimeTheme, SidebarMode, SidebarOpenAction, TermId, ThemeConfig, UiConfig};
+use crate::theme::{ui_font, unavailable_text};
 
 /// Viewport ID for the preferences window
 const PREFERENCES_VIEWPORT_ID: &str = "preferences_viewport";
 
+/// Theme preset buttons (name, tooltip)
+const THEME_PRESETS: &[(&str, &str)] = &[
+    ("Dark Brown", "Warm, earthy brown theme (default)"),
+    ("Reset to Default", "Reset all colors to default values"),
+];
+
+/// Colors of a theme preset
+fn theme_preset(name: &str) -> Option<ThemeConfig> {
+    match name {
+        "Dark Brown" | "Reset to Default" => Some(ThemeConfig::default()),
+        _ => None,
+    }
+}
+
 /// Shared state between main app and preferences viewport
 pub struct PreferencesSharedState {
     pub temp_config: Mutex<Config>,
     pub current_config: Mutex<Config>,
     pub active_tab: Mutex<PreferencesTab>,
     pub theme: Mutex<RuntimeTheme>,
+    /// Theme last applied to the egui style
+    styled_theme: Mutex<Option<RuntimeTheme>>,
+    preview: Mutex<PreviewState>,
+    /// What works on this machine, for greying out what doesn't
+    capabilities: Mutex<Capabilities>,
+}
+
+/// Theme preset preview driven by hovering the preset buttons
+#[derive(Default)]
+struct PreviewState {
+    /// Preset under the pointer
+    hovered: Option<&'static str>,
+    /// A preset was clicked: keep showing it after the pointer leaves
+    pinned: bool,
 }
 
 impl PreferencesSharedState {
@@ -30,6 +59,9 @@ impl PreferencesSharedState {
             current_config: Mutex::new(config),
             active_tab: Mutex::new(PreferencesTab::General),
             theme: Mutex::new(theme),
+            styled_theme: Mutex::new(None),
+            preview: Mutex::new(PreviewState::default()),
+            capabilities: Mutex::new(Capabilities::all()),
         }
     }
 }
@@ -42,6 +74,18 @@ pub enum PreferencesCommand {
     SaveAndClose(Config),
     /// Cancel and close window
     Cancel,
+    /// Run a command palette command in the main window
+    PaletteCommand(&'static str),
+    /// Start, change or end a theme preset preview
+    PreviewTheme(ThemePreview),
+}
+
+/// Theme preview shown across the app while Preferences is open
+pub enum ThemePreview {
+    /// Show this theme without applying it
+    Show(Box<ThemeConfig>),
+    /// Go back to the config's theme
+    End,
 }
 
 /// Preferences window state
@@ -91,6 +135,10 @@ pub struct PreferencesResponse {
     pub apply_config: Option<Config>,
     /// If true, save config to disk
     pub save_config: bool,
+    /// Command palette command to start
+    pub palette_command: Option<&'static str>,
+    /// Theme preview to show or end
+    pub theme_preview: Option<ThemePreview>,
 }
 
 impl PreferencesWindow {
@@ -108,6 +156,11 @@ impl PreferencesWindow {
 
     /// Open the preferences window
     pub fn open(&mut self, config: Config) {
+        self.open_at(config, PreferencesTab::General);
+    }
+
+    /// Open the preferences window on `tab`
+    pub fn open_at(&mut self, config: Config, tab: PreferencesTab) {
         // Update shared state
         {
             let mut temp = self.shared_state.temp_config.lock().unwrap();
@@ -117,18 +170,21 @@ impl PreferencesWindow {
             let mut current = self.shared_state.current_config.lock().unwrap();
             *current = config.clone();
         }
-        {
-            let mut tab = self.shared_state.active_tab.lock().unwrap();
-            *tab = PreferencesTab::General;
-        }
+        *self.shared_state.active_tab.lock().unwrap() = tab;
         {
             let mut theme = self.shared_state.theme.lock().unwrap();
             *theme = RuntimeTheme::from(&config.theme);
         }
+        *self.shared_state.preview.lock().unwrap() = PreviewState::default();
 
         self.visible.store(true, Ordering::SeqCst);
     }
 
+    /// Settings needing something from `capabilities` that's missing are shown disabled
+    pub fn set_capabilities(&self, capabilities: Capabilities) {
+        *self.shared_state.capabilities.lock().unwrap() = capabilities;
+    }
+
     /// Close the preferences window
     pub fn close(&mut self) {
         self.visible.store(false, Ordering::SeqCst);
@@ -167,8 +223,15 @@ impl PreferencesWindow {
                     self.visible.store(false, Ordering::SeqCst);
                 }
                 PreferencesCommand::Cancel => {
+                    response.theme_preview = Some(ThemePreview::End);
                     self.visible.store(false, Ordering::SeqCst);
                 }
+                PreferencesCommand::PaletteCommand(id) => {
+                    response.palette_command = Some(id);
+                }
+                PreferencesCommand::PreviewTheme(preview) => {
+                    response.theme_preview = Some(preview);
+                }
             }
         }
 
@@ -228,13 +291,20 @@ impl PreferencesWindow {
             t.clone()
         };
 
-        // Apply theme to viewport
-        crate::theme::apply_theme(ctx, &theme);
+        // Restyle only when the theme changed: rebuilding the style every
+        // frame makes egui redo its layout caches
+        {
+            let mut styled = shared_state.styled_theme.lock().unwrap();
+            if styled.as_ref() != Some(&theme) {
+                crate::theme::apply_theme(ctx, &theme);
+                *styled = Some(theme.clone());
+            }
+        }
 
         // Handle keyboard shortcuts
         let should_close = ctx.input(|i| {
             i.key_pressed(egui::Key::Escape)
-                || (i.modifiers.command && i.key_pressed(egui::Key::W))
+                || (crate::shortcuts::primary_modifier(i.modifiers) && i.key_pressed(egui::Key::W))
         });
 
         if should_close {
@@ -277,7 +347,8 @@ impl PreferencesWindow {
                                 ui.horizontal(|ui| {
                                     ui.add_space(16.0);
                                     ui.vertical(|ui| {
-                                        Self::render_content(ui, shared_state, &theme);
+                                        let hovered = Self::render_content(ui, shared_state, command_tx, &theme);
+                                        Self::update_theme_preview(ui.ctx(), shared_state, command_tx, hovered);
                                     });
                                 });
                                 ui.add_space(16.0);
@@ -308,7 +379,7 @@ impl PreferencesWindow {
                     // Use Button widget for proper layout
                     let button = Button::new(
                         RichText::new(tab.label())
-                            .font(mono_font(13.0))
+                            .font(ui_font(13.0))
                             .color(if is_active { theme.background } else { theme.text })
                     )
                     .fill(if is_active { theme.primary } else { theme.surface })
@@ -334,7 +405,12 @@ impl PreferencesWindow {
             });
     }
 
-    fn render_content(ui: &mut egui::Ui, shared_state: &Arc<PreferencesSharedState>, theme: &RuntimeTheme) {
+    fn render_content(
+        ui: &mut egui::Ui,
+        shared_state: &Arc<PreferencesSharedState>,
+        command_tx: &Sender<PreferencesCommand>,
+        theme: &RuntimeTheme,
+    ) -> Option<&'static str> {
         ui.style_mut().spacing.item_spacing.y = 12.0;
 
         let active_tab = {
@@ -342,21 +418,59 @@ impl PreferencesWindow {
             *tab
         };
 
+        let capabilities = shared_state.capabilities.lock().unwrap().clone();
+
+        // Returns the hovered theme preset; other tabs have none
         match active_tab {
-            PreferencesTab::General => Self::render_general_tab(ui, shared_state, theme),
-            PreferencesTab::Appearance => Self::render_appearance_tab(ui, shared_state, theme),
+            PreferencesTab::General => Self::render_general_tab(ui, shared_state, command_tx, theme, &capabilities),
+            PreferencesTab::Appearance => return Self::render_appearance_tab(ui, shared_state, theme),
             PreferencesTab::Terminal => Self::render_terminal_tab(ui, shared_state, theme),
             PreferencesTab::FileTree => Self::render_filetree_tab(ui, shared_state, theme),
-            PreferencesTab::Advanced => Self::render_advanced_tab(ui, shared_state, theme),
+            PreferencesTab::Advanced => Self::render_advanced_tab(ui, shared_state, theme, &capabilities),
         }
+        None
+    }
+
+    /// Preview the hovered theme preset in the main window, or stop
+    ///
+    /// Commands go out only when the hovered preset changes, so the main
+    /// window switches theme once per hover instead of every frame.
+    fn update_theme_preview(
+        ctx: &egui::Context,
+        shared_state: &Arc<PreferencesSharedState>,
+        command_tx: &Sender<PreferencesCommand>,
+        hovered: Option<&'static str>,
+    ) {
+        let pinned = {
+            let mut preview = shared_state.preview.lock().unwrap();
+            if preview.hovered == hovered {
+                return;
+            }
+            preview.hovered = hovered;
+            preview.pinned
+        };
+        let command = match hovered.and_then(theme_preset) {
+            Some(theme) => ThemePreview::Show(Box::new(theme)),
+            // A clicked preset stays until Apply or Cancel
+            None if pinned => ThemePreview::Show(Box::new(shared_state.temp_config.lock().unwrap().theme.clone())),
+            None => ThemePreview::End,
+        };
+        let _ = command_tx.send(PreferencesCommand::PreviewTheme(command));
+        ctx.request_repaint_of(ViewportId::ROOT);
     }
 
-    fn render_general_tab(ui: &mut egui::Ui, shared_state: &Arc<PreferencesSharedState>, theme: &RuntimeTheme) {
-        ui.heading(RichText::new("General Settings").font(mono_font(16.0)).color(theme.text));
+    fn render_general_tab(
+        ui: &mut egui::Ui,
+        shared_state: &Arc<PreferencesSharedState>,
+        command_tx: &Sender<PreferencesCommand>,
+        theme: &RuntimeTheme,
+        capabilities: &Capabilities,
+    ) {
+        ui.heading(RichText::new("General Settings").font(ui_font(16.0)).color(theme.text));
         ui.add_space(8.0);
 
         // Font Settings Section
-        ui.label(RichText::new("Font").font(mono_font(13.0)).color(theme.text));
+        ui.label(RichText::new("Font").font(ui_font(13.0)).color(theme.text));
         ui.add_space(4.0);
 
         // Get and update config
@@ -366,48 +480,118 @@ impl PreferencesWindow {
             .num_columns(2)
             .spacing([40.0, 8.0])
             .show(ui, |ui| {
-                ui.label(RichText::new("Terminal Size").font(mono_font(12.0)).color(theme.text_dim))
+                ui.label(RichText::new("Terminal Size").font(ui_font(12.0)).color(theme.text_dim))
                     .on_hover_text("Font size for terminal text (10-24)");
                 ui.add(egui::Slider::new(&mut temp_config.font.terminal_size, 10.0..=24.0)
                     .suffix(" pt"));
                 ui.end_row();
 
-                ui.label(RichText::new("UI Size").font(mono_font(12.0)).color(theme.text_dim))
+                ui.label(RichText::new("UI Size").font(ui_font(12.0)).color(theme.text_dim))
                     .on_hover_text("Font size for UI elements (8-20)");
                 ui.add(egui::Slider::new(&mut temp_config.font.ui_size, 8.0..=20.0)
                     .suffix(" pt"));
                 ui.end_row();
+
+                ui.label(RichText::new("UI Scale").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Scale of sidebar, tab bar, status bar and pane label text (Cmd+Alt+Plus/Minus)");
+                ui.add(egui::Slider::new(&mut temp_config.font.ui_scale, FontConfig::UI_SCALES)
+                    .step_by(0.1)
+                    .custom_formatter(|scale, _| format!("{:.0}%", scale * 100.0))
+                    .custom_parser(|text| text.trim().trim_end_matches('%').parse::<f64>().ok().map(|percent| percent / 100.0)));
+                ui.end_row();
+
+                match capabilities.unavailable_reason(Capability::CjkFont) {
+                    None => {
+                        ui.label(RichText::new("CJK Fallback").font(ui_font(12.0)).color(theme.text_dim))
+                            .on_hover_text("Search the system for a Korean/Japanese/Chinese font");
+                        ui.checkbox(&mut temp_config.font.cjk_fallback, "");
+                    }
+                    Some(reason) => {
+                        ui.label(unavailable_text("CJK Fallback", 12.0, theme)).on_hover_text(&reason);
+                        ui.add_enabled(false, egui::Checkbox::without_text(&mut temp_config.font.cjk_fallback))
+                            .on_disabled_hover_text(reason);
+                    }
+                }
+                ui.end_row();
+
+                ui.label(RichText::new("Fallback Font").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Font file searched after the built-in fonts, e.g. a Nerd Font for icons");
+                ui.add(egui::TextEdit::singleline(&mut temp_config.font.fallback_font)
+                    .font(ui_font(12.0))
+                    .desired_width(240.0)
+                    .hint_text("~/.local/share/fonts/Font.ttf"));
+                ui.end_row();
             });
 
+        ui.add_space(4.0);
+        let button = Button::new(RichText::new(" Run Font Diagnostics ").font(ui_font(11.0)).color(theme.text))
+            .fill(theme.surface_light)
+            .stroke(Stroke::new(1.0, theme.border))
+            .corner_radius(4.0);
+        if ui.add(button).on_hover_text("Check which font draws box drawing, icon, CJK and emoji glyphs").clicked() {
+            // Results show in the main window
+            let _ = command_tx.send(PreferencesCommand::PaletteCommand("font_diagnostics"));
+            ui.ctx().send_viewport_cmd_to(ViewportId::ROOT, ViewportCommand::Focus);
+        }
+
         ui.add_space(16.0);
         ui.separator();
         ui.add_space(8.0);
 
         // Layout Settings Section
-        ui.label(RichText::new("Layout").font(mono_font(13.0)).color(theme.text));
+        ui.label(RichText::new("Layout").font(ui_font(13.0)).color(theme.text));
         ui.add_space(4.0);
 
         egui::Grid::new("layout_grid")
             .num_columns(2)
             .spacing([40.0, 8.0])
             .show(ui, |ui| {
-                ui.label(RichText::new("Sidebar Width").font(mono_font(12.0)).color(theme.text_dim))
+                ui.label(RichText::new("Sidebar Width").font(ui_font(12.0)).color(theme.text_dim))
                     .on_hover_text("Width of the file tree sidebar");
                 ui.add(egui::Slider::new(&mut temp_config.ui.sidebar_width, 150.0..=400.0)
                     .suffix(" px"));
                 ui.end_row();
 
-                ui.label(RichText::new("Tab Bar Height").font(mono_font(12.0)).color(theme.text_dim))
+                ui.label(RichText::new("Sidebar").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Auto-hide leaves a strip on the left edge; hover it or press Cmd+B to slide the sidebar out over the panes");
+                egui::ComboBox::from_id_salt("sidebar_mode")
+                    .selected_text(temp_config.ui.sidebar_mode.label())
+                    .show_ui(ui, |ui| {
+                        for mode in [SidebarMode::Pinned, SidebarMode::AutoHide, SidebarMode::Hidden] {
+                            ui.selectable_value(&mut temp_config.ui.sidebar_mode, mode, mode.label());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(RichText::new("Tab Bar Height").font(ui_font(12.0)).color(theme.text_dim))
                     .on_hover_text("Height of the top tab bar");
-                ui.add(egui::Slider::new(&mut temp_config.ui.tab_bar_height, 24.0..=40.0)
+                ui.add(egui::Slider::new(&mut temp_config.ui.tab_bar_height, UiConfig::TAB_BAR_HEIGHTS)
                     .suffix(" px"));
                 ui.end_row();
 
-                ui.label(RichText::new("Status Bar Height").font(mono_font(12.0)).color(theme.text_dim))
+                ui.label(RichText::new("Status Bar Height").font(ui_font(12.0)).color(theme.text_dim))
                     .on_hover_text("Height of the bottom status bar");
-                ui.add(egui::Slider::new(&mut temp_config.ui.status_bar_height, 16.0..=32.0)
+                ui.add(egui::Slider::new(&mut temp_config.ui.status_bar_height, UiConfig::STATUS_BAR_HEIGHTS)
                     .suffix(" px"));
                 ui.end_row();
+
+                ui.label(RichText::new("Resize Debounce").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Wait this long for pane size to settle before resizing the terminal");
+                ui.add(egui::Slider::new(&mut temp_config.ui.resize_debounce_ms, 0..=500)
+                    .suffix(" ms"));
+                ui.end_row();
+
+                ui.label(RichText::new("Run-All Stagger").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Delay between terminals when running a command in all panes");
+                ui.add(egui::Slider::new(&mut temp_config.ui.run_all_stagger_ms, 0..=1000)
+                    .suffix(" ms"));
+                ui.end_row();
+
+                ui.label(RichText::new("Inactive Pane Fade").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Blend unfocused terminals' colors toward the background (0 disables)");
+                ui.add(egui::Slider::new(&mut temp_config.ui.inactive_pane_desaturation, 0.0..=0.8)
+                    .fixed_decimals(2));
+                ui.end_row();
             });
 
         ui.add_space(16.0);
@@ -415,41 +599,84 @@ impl PreferencesWindow {
         ui.add_space(8.0);
 
         // Startup Behavior Section
-        ui.label(RichText::new("Startup").font(mono_font(13.0)).color(theme.text));
+        ui.label(RichText::new("Startup").font(ui_font(13.0)).color(theme.text));
         ui.add_space(4.0);
 
         ui.checkbox(&mut temp_config.ui.show_sidebar,
-            RichText::new("Show sidebar on startup").font(mono_font(12.0)).color(theme.text))
+            RichText::new("Show sidebar on startup").font(ui_font(12.0)).color(theme.text))
             .on_hover_text("Display the file tree sidebar when the app opens");
 
-        ui.checkbox(&mut temp_config.ui.enable_cwd_polling,
-            RichText::new("Enable directory tracking").font(mono_font(12.0)).color(theme.text))
-            .on_hover_text("Automatically update file tree when terminal changes directory");
+        ui.checkbox(&mut temp_config.ui.restore_session,
+            RichText::new("Restore tabs on startup").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Reopen last run's tabs and splits, with new shells in the same directories");
+
+        match capabilities.unavailable_reason(Capability::CwdTracking) {
+            None => {
+                ui.checkbox(&mut temp_config.ui.enable_cwd_polling,
+                    RichText::new("Enable directory tracking").font(ui_font(12.0)).color(theme.text))
+                    .on_hover_text("Automatically update file tree when terminal changes directory");
+            }
+            Some(reason) => {
+                ui.add_enabled(false, egui::Checkbox::new(&mut temp_config.ui.enable_cwd_polling,
+                    unavailable_text("Enable directory tracking", 12.0, theme)))
+                    .on_disabled_hover_text(reason);
+            }
+        }
+
+        ui.checkbox(&mut temp_config.ui.sidebar_follows_cd,
+            RichText::new("File tree follows cd").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Move the file tree to the focused terminal's directory once it stays there for a moment (needs directory tracking)");
+
+        ui.checkbox(&mut temp_config.ui.show_breadcrumbs,
+            RichText::new("Show path bar").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Show the focused terminal's directory above the panes; click a folder to cd there");
+
+        ui.checkbox(&mut temp_config.ui.highlight_root_panes,
+            RichText::new("Highlight root panes").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Red border and \"root\" badge on panes running as root, ⚠ on their tab");
+
+        ui.checkbox(&mut temp_config.ui.show_activity_strip,
+            RichText::new("Show tab activity").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Line under each tab that glows when its terminals print output and fades over 5 minutes");
+
+        ui.checkbox(&mut temp_config.ui.confirm_dangerous_commands,
+            RichText::new("Confirm dangerous commands").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Commands shown in red in the palette (closing tabs and panes, running in all panes) need Enter pressed twice or held");
+
+        ui.checkbox(&mut temp_config.ui.confirm_quit,
+            RichText::new("Confirm before quitting").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Ask before closing while jobs run, output is logged or background work is unfinished");
+
+        ui.checkbox(&mut temp_config.ui.single_instance,
+            RichText::new("Open new launches as tabs").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Launching VibeTerm again adds a tab to this window (--new-instance overrides; applies on next launch)");
     }
 
-    fn render_appearance_tab(ui: &mut egui::Ui, shared_state: &Arc<PreferencesSharedState>, theme: &RuntimeTheme) {
-        ui.heading(RichText::new("Appearance").font(mono_font(16.0)).color(theme.text));
+    /// Returns the theme preset under the pointer
+    fn render_appearance_tab(ui: &mut egui::Ui, shared_state: &Arc<PreferencesSharedState>, theme: &RuntimeTheme) -> Option<&'static str> {
+        ui.heading(RichText::new("Appearance").font(ui_font(16.0)).color(theme.text));
         ui.add_space(8.0);
 
         // Theme Presets Section
-        ui.label(RichText::new("Theme Presets").font(mono_font(13.0)).color(theme.text));
+        ui.label(RichText::new("Theme Presets").font(ui_font(13.0)).color(theme.text));
         ui.add_space(4.0);
 
         let mut temp_config = shared_state.temp_config.lock().unwrap();
 
+        // Hovering a preset previews it across the app
+        let mut hovered = None;
         ui.horizontal(|ui| {
-            if ui.button(RichText::new("Dark Brown").font(mono_font(12.0)))
-                .on_hover_text("Warm, earthy brown theme (default)")
-                .clicked()
-            {
-                temp_config.theme = ThemeConfig::default();
-            }
-
-            if ui.button(RichText::new("Reset to Default").font(mono_font(12.0)))
-                .on_hover_text("Reset all colors to default values")
-                .clicked()
-            {
-                temp_config.theme = ThemeConfig::default();
+            for (name, hint) in THEME_PRESETS {
+                let response = ui.button(RichText::new(*name).font(ui_font(12.0))).on_hover_text(*hint);
+                if response.hovered() {
+                    hovered = Some(*name);
+                }
+                if response.clicked() {
+                    if let Some(preset) = theme_preset(name) {
+                        temp_config.theme = preset;
+                        shared_state.preview.lock().unwrap().pinned = true;
+                    }
+                }
             }
         });
 
@@ -458,7 +685,7 @@ impl PreferencesWindow {
         ui.add_space(8.0);
 
         // UI Colors Section
-        ui.label(RichText::new("UI Colors").font(mono_font(13.0)).color(theme.text));
+        ui.label(RichText::new("UI Colors").font(ui_font(13.0)).color(theme.text));
         ui.add_space(4.0);
 
         egui::Grid::new("ui_colors_grid")
@@ -484,16 +711,75 @@ impl PreferencesWindow {
                 Self::color_picker_row(ui, theme, "Selection", &mut temp_config.theme.selection,
                     "Text selection background color");
             });
+        hovered
     }
 
     fn render_terminal_tab(ui: &mut egui::Ui, shared_state: &Arc<PreferencesSharedState>, theme: &RuntimeTheme) {
-        ui.heading(RichText::new("Terminal").font(mono_font(16.0)).color(theme.text));
+        ui.heading(RichText::new("Terminal").font(ui_font(16.0)).color(theme.text));
         ui.add_space(8.0);
 
         let mut temp_config = shared_state.temp_config.lock().unwrap();
 
-        // Reset button at the top
-        if ui.button(RichText::new("Reset ANSI Colors to Default").font(mono_font(12.0)))
+        egui::Grid::new("terminal_shell_grid")
+            .num_columns(2)
+            .spacing([40.0, 8.0])
+            .show(ui, |ui| {
+                ui.label(RichText::new("Shell").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Program new terminals run; empty uses $SHELL");
+                ui.add(egui::TextEdit::singleline(&mut temp_config.terminal.shell)
+                    .font(ui_font(12.0))
+                    .desired_width(240.0)
+                    .hint_text(std::env::var("SHELL").unwrap_or_default()));
+                ui.end_row();
+
+                ui.label(RichText::new("Report As").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Terminal type programs are told when they ask (device attributes); applies to the next query");
+                egui::ComboBox::from_id_salt("term_id")
+                    .selected_text(temp_config.terminal.term_id.label())
+                    .show_ui(ui, |ui| {
+                        for term_id in [TermId::Xterm, TermId::Vt220, TermId::Vt420] {
+                            ui.selectable_value(&mut temp_config.terminal.term_id, term_id, term_id.label())
+                                .on_hover_text(term_id.hint());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(RichText::new("New Terminals Start In").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Directory of the shell in a new split or tab");
+                egui::ComboBox::from_id_salt("new_terminal_dir")
+                    .selected_text(temp_config.terminal.new_terminal_dir.label())
+                    .show_ui(ui, |ui| {
+                        for dir in [NewTerminalDir::Inherit, NewTerminalDir::Home, NewTerminalDir::Launch] {
+                            ui.selectable_value(&mut temp_config.terminal.new_terminal_dir, dir, dir.label());
+                        }
+                    });
+                ui.end_row();
+            });
+
+        ui.add_space(8.0);
+
+        ui.checkbox(&mut temp_config.terminal.scroll_on_output,
+            RichText::new("Scroll to bottom on output").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("New output brings a scrolled-back terminal to the bottom");
+
+        ui.checkbox(&mut temp_config.terminal.scroll_on_keystroke,
+            RichText::new("Scroll to bottom on keystroke").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Typing brings a scrolled-back terminal to the bottom");
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut temp_config.terminal.show_wrap_markers,
+                RichText::new("Mark lines wrapped past").font(ui_font(12.0)).color(theme.text))
+                .on_hover_text("Rows a long line continues on get a marker in their left column");
+            ui.add_enabled(
+                temp_config.terminal.show_wrap_markers,
+                egui::DragValue::new(&mut temp_config.terminal.wrap_marker_columns).range(1..=100_000).suffix(" columns"),
+            );
+        });
+
+        ui.add_space(8.0);
+
+        // Reset button for the palette
+        if ui.button(RichText::new("Reset ANSI Colors to Default").font(ui_font(12.0)))
             .on_hover_text("Restore default ANSI color palette")
             .clicked()
         {
@@ -517,12 +803,12 @@ impl PreferencesWindow {
         }
 
         ui.add_space(8.0);
-        ui.label(RichText::new("ANSI Colors (16-color palette)").font(mono_font(13.0)).color(theme.text));
+        ui.label(RichText::new("ANSI Colors (16-color palette)").font(ui_font(13.0)).color(theme.text));
         ui.add_space(4.0);
 
         ui.columns(2, |columns| {
             // Normal colors (left column)
-            columns[0].label(RichText::new("Normal Colors").font(mono_font(12.0)).strong().color(theme.text));
+            columns[0].label(RichText::new("Normal Colors").font(ui_font(12.0)).strong().color(theme.text));
             columns[0].add_space(4.0);
 
             egui::Grid::new("normal_colors_grid")
@@ -540,7 +826,7 @@ impl PreferencesWindow {
                 });
 
             // Bright colors (right column)
-            columns[1].label(RichText::new("Bright Colors").font(mono_font(12.0)).strong().color(theme.text));
+            columns[1].label(RichText::new("Bright Colors").font(ui_font(12.0)).strong().color(theme.text));
             columns[1].add_space(4.0);
 
             egui::Grid::new("bright_colors_grid")
@@ -563,7 +849,7 @@ impl PreferencesWindow {
         ui.add_space(8.0);
 
         // Preview section
-        ui.label(RichText::new("Preview").font(mono_font(13.0)).color(theme.text));
+        ui.label(RichText::new("Preview").font(ui_font(13.0)).color(theme.text));
         ui.add_space(4.0);
 
         Frame::NONE
@@ -585,7 +871,7 @@ impl PreferencesWindow {
                     ];
                     for color in colors {
                         let c = crate::config::parse_hex_color(color);
-                        ui.label(RichText::new("X").font(mono_font(20.0)).color(c));
+                        ui.label(RichText::new("X").font(ui_font(20.0)).color(c));
                     }
                 });
                 ui.horizontal(|ui| {
@@ -601,24 +887,24 @@ impl PreferencesWindow {
                     ];
                     for color in bright_colors {
                         let c = crate::config::parse_hex_color(color);
-                        ui.label(RichText::new("X").font(mono_font(20.0)).color(c));
+                        ui.label(RichText::new("X").font(ui_font(20.0)).color(c));
                     }
                 });
             });
     }
 
     fn render_filetree_tab(ui: &mut egui::Ui, shared_state: &Arc<PreferencesSharedState>, theme: &RuntimeTheme) {
-        ui.heading(RichText::new("File Tree").font(mono_font(16.0)).color(theme.text));
+        ui.heading(RichText::new("File Tree").font(ui_font(16.0)).color(theme.text));
         ui.add_space(8.0);
 
         let mut temp_config = shared_state.temp_config.lock().unwrap();
 
         // Display Settings Section
-        ui.label(RichText::new("Display").font(mono_font(13.0)).color(theme.text));
+        ui.label(RichText::new("Display").font(ui_font(13.0)).color(theme.text));
         ui.add_space(4.0);
 
         ui.checkbox(&mut temp_config.ui.show_hidden_files,
-            RichText::new("Show hidden files").font(mono_font(12.0)).color(theme.text))
+            RichText::new("Show hidden files").font(ui_font(12.0)).color(theme.text))
             .on_hover_text("Display files and folders starting with '.'");
 
         ui.add_space(8.0);
@@ -627,16 +913,44 @@ impl PreferencesWindow {
             .num_columns(2)
             .spacing([40.0, 8.0])
             .show(ui, |ui| {
-                ui.label(RichText::new("Max Files").font(mono_font(12.0)).color(theme.text_dim))
+                ui.label(RichText::new("Max Files").font(ui_font(12.0)).color(theme.text_dim))
                     .on_hover_text("Maximum number of files to display (100-5000)");
                 ui.add(egui::Slider::new(&mut temp_config.ui.max_files, 100..=5000)
                     .logarithmic(true));
                 ui.end_row();
 
-                ui.label(RichText::new("Max Depth").font(mono_font(12.0)).color(theme.text_dim))
+                ui.label(RichText::new("Max Depth").font(ui_font(12.0)).color(theme.text_dim))
                     .on_hover_text("Maximum directory depth to traverse (1-20)");
                 ui.add(egui::Slider::new(&mut temp_config.ui.max_depth, 1..=20));
                 ui.end_row();
+
+                ui.label(RichText::new("File Icons").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Per-type file icons (Nerd Font falls back to ASCII if the font lacks them)");
+                egui::ComboBox::from_id_salt("file_icons")
+                    .selected_text(temp_config.ui.file_icons.label())
+                    .show_ui(ui, |ui| {
+                        for style in [FileIconStyle::Off, FileIconStyle::Ascii, FileIconStyle::Nerd] {
+                            ui.selectable_value(&mut temp_config.ui.file_icons, style, style.label());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(RichText::new("Double-Click Opens In").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Where a double-clicked file opens; Cmd+double-click always splits, Alt+double-click uses the external editor");
+                egui::ComboBox::from_id_salt("sidebar_open_action")
+                    .selected_text(temp_config.ui.sidebar_open_action.label())
+                    .show_ui(ui, |ui| {
+                        for action in [
+                            SidebarOpenAction::NewTab,
+                            SidebarOpenAction::SplitRight,
+                            SidebarOpenAction::SplitDown,
+                            SidebarOpenAction::ExternalEditor,
+                            SidebarOpenAction::ReplaceCurrentFileViewer,
+                        ] {
+                            ui.selectable_value(&mut temp_config.ui.sidebar_open_action, action, action.label());
+                        }
+                    });
+                ui.end_row();
             });
 
         ui.add_space(16.0);
@@ -644,18 +958,18 @@ impl PreferencesWindow {
         ui.add_space(8.0);
 
         // Ignore Patterns Section
-        ui.label(RichText::new("Ignore Patterns").font(mono_font(13.0)).color(theme.text));
+        ui.label(RichText::new("Ignore Patterns").font(ui_font(13.0)).color(theme.text));
         ui.add_space(4.0);
 
         ui.label(RichText::new("Files and directories to exclude (one per line)")
-            .font(mono_font(11.0))
+            .font(ui_font(11.0))
             .color(theme.text_dim));
 
         // Convert Vec<String> to multiline text
         let mut ignore_text = temp_config.ui.file_tree_ignore_patterns.join("\n");
 
         let text_edit = egui::TextEdit::multiline(&mut ignore_text)
-            .font(mono_font(11.0))
+            .font(ui_font(11.0))
             .desired_width(f32::INFINITY)
             .desired_rows(6);
 
@@ -672,9 +986,9 @@ impl PreferencesWindow {
 
         // Common patterns helper buttons
         ui.horizontal(|ui| {
-            ui.label(RichText::new("Quick add:").font(mono_font(11.0)).color(theme.text_dim));
+            ui.label(RichText::new("Quick add:").font(ui_font(11.0)).color(theme.text_dim));
 
-            if ui.button(RichText::new(".DS_Store").font(mono_font(11.0)))
+            if ui.button(RichText::new(".DS_Store").font(ui_font(11.0)))
                 .on_hover_text("Add macOS metadata files")
                 .clicked()
             {
@@ -683,7 +997,7 @@ impl PreferencesWindow {
                 }
             }
 
-            if ui.button(RichText::new("*.log").font(mono_font(11.0)))
+            if ui.button(RichText::new("*.log").font(ui_font(11.0)))
                 .on_hover_text("Add log files")
                 .clicked()
             {
@@ -692,7 +1006,7 @@ impl PreferencesWindow {
                 }
             }
 
-            if ui.button(RichText::new("build/").font(mono_font(11.0)))
+            if ui.button(RichText::new("build/").font(ui_font(11.0)))
                 .on_hover_text("Add build directories")
                 .clicked()
             {
@@ -701,7 +1015,7 @@ impl PreferencesWindow {
                 }
             }
 
-            if ui.button(RichText::new("Reset").font(mono_font(11.0)))
+            if ui.button(RichText::new("Reset").font(ui_font(11.0)))
                 .on_hover_text("Reset to default ignore patterns")
                 .clicked()
             {
@@ -710,169 +1024,165 @@ impl PreferencesWindow {
         });
     }
 
-    fn render_advanced_tab(ui: &mut egui::Ui, shared_state: &Arc<PreferencesSharedState>, theme: &RuntimeTheme) {
-        // We don't actually need the config here yet, but keep lock pattern consistent
-        let _temp_config = shared_state.temp_config.lock().unwrap();
+    fn render_advanced_tab(
+        ui: &mut egui::Ui,
+        shared_state: &Arc<PreferencesSharedState>,
+        theme: &RuntimeTheme,
+        capabilities: &Capabilities,
+    ) {
+        let mut temp_config = shared_state.temp_config.lock().unwrap();
 
-        ui.heading(RichText::new("Advanced").font(mono_font(16.0)).color(theme.text));
+        ui.heading(RichText::new("Advanced").font(ui_font(16.0)).color(theme.text));
         ui.add_space(8.0);
 
-        // Context Engine Section
-        ui.label(RichText::new("Context Engine").font(mono_font(13.0)).color(theme.text));
+        // Context Section
+        ui.label(RichText::new("Context").font(ui_font(13.0)).color(theme.text));
         ui.add_space(4.0);
 
-        // Note: ContextConfig fields (max_tokens, target_ratio, smart_context) are not currently implemented
-        ui.label(RichText::new("Context management settings")
-            .font(mono_font(11.0))
-            .color(theme.text_dim));
-        ui.add_space(4.0);
-        ui.label(RichText::new("(Settings will be available in future updates)")
-            .font(mono_font(10.0))
-            .color(theme.text_dim));
+        match capabilities.unavailable_reason(Capability::FileWatcher) {
+            None => {
+                ui.checkbox(&mut temp_config.context.enable_file_watcher,
+                    RichText::new("Watch files for changes").font(ui_font(12.0)).color(theme.text))
+                    .on_hover_text("Refresh the file tree and git status when files change");
+            }
+            Some(reason) => {
+                ui.add_enabled(false, egui::Checkbox::new(&mut temp_config.context.enable_file_watcher,
+                    unavailable_text("Watch files for changes", 12.0, theme)))
+                    .on_disabled_hover_text(reason);
+            }
+        }
+        ui.checkbox(&mut temp_config.context.enable_git_status,
+            RichText::new("Show git status").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Mark modified, staged and untracked files in the file tree");
 
-        ui.add_space(16.0);
-        ui.separator();
         ui.add_space(8.0);
 
-        // Performance Section
-        ui.label(RichText::new("Performance").font(mono_font(13.0)).color(theme.text));
-        ui.add_space(4.0);
+        egui::Grid::new("context_grid")
+            .num_columns(2)
+            .spacing([40.0, 8.0])
+            .show(ui, |ui| {
+                ui.label(RichText::new("Watcher Debounce").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Wait this long for file changes to settle before reacting");
+                ui.add_enabled(
+                    temp_config.context.enable_file_watcher && capabilities.is_available(Capability::FileWatcher),
+                    egui::Slider::new(&mut temp_config.context.watcher_debounce_ms, 50..=1000).suffix(" ms"),
+                );
+                ui.end_row();
 
-        ui.label(RichText::new("Rendering backend and acceleration settings")
-            .font(mono_font(11.0))
-            .color(theme.text_dim));
+                ui.label(RichText::new("Git Refresh").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("How often git status is re-read without file changes");
+                ui.add_enabled(
+                    temp_config.context.enable_git_status,
+                    egui::Slider::new(&mut temp_config.context.git_refresh_interval_secs, 1..=60).suffix(" s"),
+                );
+                ui.end_row();
 
-        ui.add_space(4.0);
+                ui.label(RichText::new("Auto Fetch").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Run git fetch for the active repo in the background so ahead/behind stays current (0 = off)");
+                ui.add_enabled(
+                    temp_config.context.enable_git_status,
+                    egui::Slider::new(&mut temp_config.context.git_auto_fetch_interval_mins, 0..=120).suffix(" min"),
+                );
+                ui.end_row();
 
-        // Note: These are UI-only toggles - actual implementation would require app restart
-        ui.horizontal(|ui| {
-            ui.checkbox(&mut false, RichText::new("GPU acceleration").font(mono_font(12.0)).color(theme.text_dim))
-                .on_hover_text("Enable GPU rendering (requires restart)");
+                ui.label(RichText::new("Scope Slow Status").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("When a full git status takes longer than this, only refresh expanded folders and open files (0 = off)");
+                ui.add_enabled(
+                    temp_config.context.enable_git_status,
+                    egui::Slider::new(&mut temp_config.context.git_scoped_threshold_ms, 0..=10000).suffix(" ms"),
+                );
+                ui.end_row();
 
-            ui.label(RichText::new("(Not yet implemented)")
-                .font(mono_font(10.0))
-                .color(theme.text_dim));
-        });
+                ui.label(RichText::new("Max Pinned Files").font(ui_font(12.0)).color(theme.text_dim))
+                    .on_hover_text("Least recently used pins are dropped above this limit");
+                ui.add(egui::Slider::new(&mut temp_config.context.max_pinned_files, 5..=200));
+                ui.end_row();
+            });
 
         ui.add_space(16.0);
         ui.separator();
         ui.add_space(8.0);
 
-        // Logging Section
-        ui.label(RichText::new("Logging").font(mono_font(13.0)).color(theme.text));
+        // Performance Section
+        ui.label(RichText::new("Performance").font(ui_font(13.0)).color(theme.text));
         ui.add_space(4.0);
 
-        ui.horizontal(|ui| {
-            ui.label(RichText::new("Log Level:").font(mono_font(12.0)).color(theme.text_dim))
-                .on_hover_text("Minimum severity level for log messages");
-
-            // Note: This would need to be added to Config struct
-            let mut current_level = 2; // Info
-            egui::ComboBox::from_id_salt("log_level")
-                .selected_text(match current_level {
-                    0 => "Off",
-                    1 => "Error",
-                    2 => "Warn",
-                    3 => "Info",
-                    4 => "Debug",
-                    5 => "Trace",
-                    _ => "Info",
-                })
-                .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut current_level, 0, "Off");
-                    ui.selectable_value(&mut current_level, 1, "Error");
-                    ui.selectable_value(&mut current_level, 2, "Warn");
-                    ui.selectable_value(&mut current_level, 3, "Info");
-                    ui.selectable_value(&mut current_level, 4, "Debug");
-                    ui.selectable_value(&mut current_level, 5, "Trace");
-                });
-        });
+        match capabilities.unavailable_reason(Capability::HardwareAcceleration) {
+            None => ui.label(RichText::new(format!("Renderer: OpenGL, hardware accelerated ({})", capabilities.platform))
+                .font(ui_font(11.0))
+                .color(theme.text_dim)),
+            Some(reason) => ui.label(unavailable_text(format!("Renderer: {}", reason), 11.0, theme)),
+        };
+
+        ui.checkbox(&mut temp_config.advanced.show_latency_overlay,
+            RichText::new("Show input latency overlay").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Display keypress to glyph latency of the focused terminal");
+
+        ui.checkbox(&mut temp_config.advanced.import_login_shell_env,
+            RichText::new("Import login shell environment").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Copy PATH, LANG, EDITOR and SSH_AUTH_SOCK from your login shell at startup (requires restart)");
 
         ui.add_space(16.0);
         ui.separator();
         ui.add_space(8.0);
 
-        // Experimental Features Section
-        ui.label(RichText::new("Experimental Features").font(mono_font(13.0)).color(theme.text));
+        // Updates Section
+        ui.label(RichText::new("Updates").font(ui_font(13.0)).color(theme.text));
         ui.add_space(4.0);
 
-        ui.label(RichText::new("These features are in development and may be unstable")
-            .font(mono_font(11.0))
-            .color(theme.text_dim));
-
-        ui.add_space(4.0);
-
-        ui.checkbox(&mut false, RichText::new("Enable split panes").font(mono_font(12.0)).color(theme.text_dim))
-            .on_hover_text("Allow splitting terminal into multiple panes");
-
-        ui.checkbox(&mut false, RichText::new("Enable tabs sync").font(mono_font(12.0)).color(theme.text_dim))
-            .on_hover_text("Synchronize tabs across sessions");
-
-        ui.checkbox(&mut false, RichText::new("Enable AI completions").font(mono_font(12.0)).color(theme.text_dim))
-            .on_hover_text("Show AI-powered command suggestions");
-    }
-
-    /// Parse a hex color string to Color32
-    fn parse_hex_to_color32(hex: &str) -> Option<egui::Color32> {
-        let hex = hex.strip_prefix('#').unwrap_or(hex);
-        if hex.len() != 6 {
-            return None;
-        }
-        let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
-        let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
-        let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
-        Some(egui::Color32::from_rgb(r, g, b))
-    }
+        ui.checkbox(&mut temp_config.advanced.check_for_updates,
+            RichText::new("Check for updates").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Check GitHub releases at most once per day (no telemetry, no auto-download)");
 
-    /// Convert Color32 to hex string
-    fn color32_to_hex(color: egui::Color32) -> String {
-        format!("#{:02X}{:02X}{:02X}", color.r(), color.g(), color.b())
+        ui.checkbox(&mut temp_config.advanced.local_stats,
+            RichText::new("Record usage stats").font(ui_font(12.0)).color(theme.text))
+            .on_hover_text("Keep time per project and commands per day in ~/.local/share/vibeterm for the Usage Stats tab (never sent anywhere)");
     }
 
     #[allow(dead_code)]
     fn color_picker(ui: &mut egui::Ui, theme: &RuntimeTheme, label: &str, hex: &mut String) {
         ui.horizontal(|ui| {
-            ui.label(RichText::new(format!("{}:", label)).font(mono_font(12.0)).color(theme.text_dim));
+            ui.label(RichText::new(format!("{}:", label)).font(ui_font(12.0)).color(theme.text_dim));
 
             // Parse current hex to Color32
-            let mut color = Self::parse_hex_to_color32(hex)
+            let mut color = crate::config::hex_to_color32(hex)
                 .unwrap_or(egui::Color32::from_rgb(46, 26, 22));
 
             // Show color picker button
             if ui.color_edit_button_srgba(&mut color).changed() {
                 // Convert back to hex string
-                *hex = Self::color32_to_hex(color);
+                *hex = crate::config::color32_to_hex(color);
             }
 
             // Hex input field (still editable)
             ui.add(
                 egui::TextEdit::singleline(hex)
                     .desired_width(80.0)
-                    .font(mono_font(11.0)),
+                    .font(ui_font(11.0)),
             );
         });
     }
 
     fn color_picker_row(ui: &mut egui::Ui, theme: &RuntimeTheme, label: &str, hex: &mut String, tooltip: &str) {
-        ui.label(RichText::new(label).font(mono_font(12.0)).color(theme.text_dim))
+        ui.label(RichText::new(label).font(ui_font(12.0)).color(theme.text_dim))
             .on_hover_text(tooltip);
 
         ui.horizontal(|ui| {
             // Parse current hex to Color32
-            let mut color = Self::parse_hex_to_color32(hex)
+            let mut color = crate::config::hex_to_color32(hex)
                 .unwrap_or(egui::Color32::from_rgb(46, 26, 22));
 
             // Show color picker button
             if ui.color_edit_button_srgba(&mut color).changed() {
                 // Convert back to hex string
-                *hex = Self::color32_to_hex(color);
+                *hex = crate::config::color32_to_hex(color);
             }
 
             // Hex input field (still editable)
             ui.add(
                 egui::TextEdit::singleline(hex)
                     .desired_width(90.0)
-                    .font(mono_font(11.0)),
+                    .font(ui_font(11.0)),
             );
         });
 
@@ -896,7 +1206,7 @@ impl PreferencesWindow {
                         // Save button
                         let save_btn = Button::new(
                             RichText::new(" Save ")
-                                .font(mono_font(13.0))
+                                .font(ui_font(13.0))
                                 .color(theme.background),
                         )
                         .fill(theme.secondary)
@@ -918,7 +1228,7 @@ impl PreferencesWindow {
                         // Apply button
                         let apply_btn = Button::new(
                             RichText::new(" Apply ")
-                                .font(mono_font(13.0))
+                                .font(ui_font(13.0))
                                 .color(theme.background),
                         )
                         .fill(theme.primary)
@@ -938,7 +1248,7 @@ impl PreferencesWindow {
                         // Cancel button
                         let cancel_btn = Button::new(
                             RichText::new(" Cancel ")
-                                .font(mono_font(13.0))
+                                .font(ui_font(13.0))
                                 .color(theme.text),
                         )
                         .fill(theme.surface_light)
@@ -953,9 +1263,25 @@ impl PreferencesWindow {
 
                         // Spacer to push buttons right
                         ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+                            // Settings bundle paths are asked for in the main window's palette
+                            for (label, id) in [
+                                (" Export Bundle… ", "export_settings_bundle"),
+                                (" Import Bundle… ", "import_settings_bundle"),
+                            ] {
+                                let button = Button::new(RichText::new(label).font(ui_font(11.0)).color(theme.text))
+                                    .fill(theme.surface_light)
+                                    .stroke(Stroke::new(1.0, theme.border))
+                                    .corner_radius(4.0);
+                                if ui.add(button).clicked() {
+                                    let _ = command_tx.send(PreferencesCommand::PaletteCommand(id));
+                                    ctx.send_viewport_cmd_to(ViewportId::ROOT, ViewportCommand::Focus);
+                                }
+                                ui.add_space(4.0);
+                            }
+                            ui.add_space(4.0);
                             ui.label(
                                 RichText::new("Changes will be applied immediately")
-                                    .font(mono_font(11.0))
+                                    .font(ui_font(11.0))
                                     .color(theme.text_dim),
                             );
                         });
@@ -0,0 +1,151 @@
+//! Run in All Panes dialog
+
+use egui::{Frame, Key, RichText};
+use crate::config::RuntimeTheme;
+use crate::run_all::{select_targets, RunScope, RunTarget};
+use crate::theme::ui_font;
+
+/// Command to run and where, confirmed with Execute
+#[derive(Debug, Clone)]
+pub struct RunAllRequest {
+    pub command: String,
+    /// Terminal IDs with a label for logging, in send order
+    pub terminals: Vec<(u64, String)>,
+    /// Labels of in-scope panes that can't receive input
+    pub skipped: Vec<String>,
+}
+
+/// Dialog state
+pub struct RunAllDialog {
+    visible: bool,
+    command: String,
+    scope: RunScope,
+    glob: String,
+    /// Focus the command field on the next frame
+    focus_command: bool,
+}
+
+impl RunAllDialog {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            command: String::new(),
+            scope: RunScope::CurrentWorkspace,
+            glob: String::new(),
+            focus_command: false,
+        }
+    }
+
+    /// Open the dialog, keeping the last command and scope
+    pub fn open(&mut self) {
+        self.visible = true;
+        self.focus_command = true;
+    }
+
+    /// Is dialog visible?
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Show dialog and return the request when Execute is pressed
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        theme: &RuntimeTheme,
+        targets: &[RunTarget],
+        active_workspace: usize,
+    ) -> Option<RunAllRequest> {
+        if !self.visible {
+            return None;
+        }
+
+        let mut request = None;
+        let mut close = false;
+
+        egui::Window::new("Run in All Panes")
+            .collapsible(false)
+            .resizable(false)
+            .fixed_pos(egui::pos2(ctx.screen_rect().width() * 0.5 - 250.0, 100.0))
+            .fixed_size(egui::vec2(500.0, 0.0))
+            .frame(Frame::window(&ctx.style())
+                .fill(theme.surface)
+                .stroke(egui::Stroke::new(1.0, theme.border)))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("❯").font(ui_font(14.0)).color(theme.primary));
+                    let response = ui.add(egui::TextEdit::singleline(&mut self.command)
+                        .font(ui_font(14.0))
+                        .desired_width(460.0)
+                        .hint_text("Command to run..."));
+                    if self.focus_command {
+                        response.request_focus();
+                        self.focus_command = false;
+                    }
+                });
+
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    for scope in RunScope::ALL {
+                        ui.radio_value(&mut self.scope, scope,
+                            RichText::new(scope.label()).font(ui_font(11.0)));
+                    }
+                });
+
+                if self.scope == RunScope::DirectoryGlob {
+                    ui.add(egui::TextEdit::singleline(&mut self.glob)
+                        .font(ui_font(12.0))
+                        .desired_width(f32::INFINITY)
+                        .hint_text("~/src/**/api"));
+                }
+
+                ui.separator();
+
+                // Preview
+                let selection = select_targets(targets, self.scope, active_workspace, &self.glob);
+                ui.label(RichText::new(format!("Will run in {} terminal(s)", selection.run.len()))
+                    .font(ui_font(12.0))
+                    .color(theme.text));
+                for target in &selection.skipped {
+                    ui.label(RichText::new(format!("Skipping read-only {}", target.label()))
+                        .font(ui_font(10.0))
+                        .color(theme.yellow));
+                }
+
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    let can_run = !self.command.trim().is_empty() && !selection.run.is_empty();
+                    if ui.add_enabled(can_run, egui::Button::new("Execute")).clicked() {
+                        request = Some(RunAllRequest {
+                            command: self.command.clone(),
+                            terminals: selection.run
+                                .iter()
+                                .filter_map(|t| Some((t.terminal_id?, t.label())))
+                                .collect(),
+                            skipped: selection.skipped.iter().map(|t| t.label()).collect(),
+                        });
+                    }
+                    if ui.button("Cancel").clicked() {
+                        close = true;
+                    }
+                });
+
+                if ui.input(|i| i.key_pressed(Key::Escape)) {
+                    close = true;
+                }
+            });
+
+        if close || request.is_some() {
+            self.visible = false;
+        }
+
+        request
+    }
+}
+
+impl Default for RunAllDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,472 @@
+//! Project Search Panel
+//!
+//! Replaces the sidebar tree while open: query field with case, word and
+//! regex toggles, include/exclude globs, and results grouped by file.
+//! The search itself runs in `project_search`; the panel only collects
+//! what it reports.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use egui::text::LayoutJob;
+use egui::{Button, Frame, Key, RichText, ScrollArea, TextFormat, Ui};
+use crate::config::RuntimeTheme;
+use crate::project_search::{FileMatches, SearchMessage, SearchOptions};
+use crate::theme::{tui, ui_font};
+
+/// Quiet time after an edit before the search starts
+pub const SEARCH_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Where the current search is at
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SearchState {
+    Idle,
+    Running,
+    Done { truncated: bool },
+    Failed(String),
+}
+
+/// A row of the result list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+    File(usize),
+    Match(usize, usize),
+}
+
+/// Project search state
+pub struct SearchPanel {
+    visible: bool,
+    options: SearchOptions,
+    /// Directory being searched
+    root: PathBuf,
+    results: Vec<FileMatches>,
+    state: SearchState,
+    /// Files whose matches are folded away
+    collapsed: HashSet<PathBuf>,
+    selected: Option<Row>,
+    /// Options changed and the search hasn't restarted yet
+    edited_at: Option<Instant>,
+    focus_pending: bool,
+}
+
+/// What the user did in the search panel
+#[derive(Debug, Default)]
+pub struct SearchPanelResponse {
+    /// File to open, at a line for a match row
+    pub open: Option<(PathBuf, Option<u32>)>,
+    pub close: bool,
+}
+
+impl SearchPanel {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            options: SearchOptions::default(),
+            root: PathBuf::new(),
+            results: Vec::new(),
+            state: SearchState::Idle,
+            collapsed: HashSet::new(),
+            selected: None,
+            edited_at: None,
+            focus_pending: false,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Open on `root`, searching again if it changed
+    pub fn open(&mut self, root: PathBuf, now: Instant) {
+        self.visible = true;
+        self.focus_pending = true;
+        if root != self.root {
+            self.root = root;
+            self.edited(now);
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn edited(&mut self, now: Instant) {
+        self.edited_at = Some(now);
+    }
+
+    /// Options to search with once edits have settled
+    ///
+    /// Clears the old results; an empty query just clears them.
+    pub fn take_due_search(&mut self, now: Instant) -> Option<SearchOptions> {
+        let edited_at = self.edited_at?;
+        if now.saturating_duration_since(edited_at) < SEARCH_DEBOUNCE {
+            return None;
+        }
+        self.edited_at = None;
+        self.results.clear();
+        self.selected = None;
+        if self.options.query.is_empty() {
+            self.state = SearchState::Idle;
+            return None;
+        }
+        self.state = SearchState::Running;
+        Some(self.options.clone())
+    }
+
+    /// Time until a pending search is due
+    pub fn pending_delay(&self, now: Instant) -> Option<Duration> {
+        let edited_at = self.edited_at?;
+        Some(SEARCH_DEBOUNCE.saturating_sub(now.saturating_duration_since(edited_at)))
+    }
+
+    /// Take in progress from the running search
+    pub fn push(&mut self, message: SearchMessage) {
+        match message {
+            SearchMessage::File(file) => self.results.push(file),
+            SearchMessage::Done { truncated } => self.state = SearchState::Done { truncated },
+            SearchMessage::Failed(error) => self.state = SearchState::Failed(error),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state == SearchState::Running
+    }
+
+    fn match_count(&self) -> usize {
+        self.results.iter().map(|file| file.matches.len()).sum()
+    }
+
+    /// Rows in display order, skipping folded files' matches
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (file_idx, file) in self.results.iter().enumerate() {
+            rows.push(Row::File(file_idx));
+            if !self.collapsed.contains(&file.path) {
+                rows.extend((0..file.matches.len()).map(|match_idx| Row::Match(file_idx, match_idx)));
+            }
+        }
+        rows
+    }
+
+    /// Move the selection by `delta` rows
+    fn move_selection(&mut self, delta: isize) {
+        let rows = self.rows();
+        if rows.is_empty() {
+            return;
+        }
+        let current = self.selected.and_then(|row| rows.iter().position(|r| *r == row));
+        let next = match current {
+            Some(idx) => idx.saturating_add_signed(delta).min(rows.len() - 1),
+            None => 0,
+        };
+        self.selected = Some(rows[next]);
+    }
+
+    /// File and line a row opens; file headers open at the top
+    fn target(&self, row: Row) -> Option<(PathBuf, Option<u32>)> {
+        match row {
+            Row::File(file_idx) => Some((self.results.get(file_idx)?.path.clone(), None)),
+            Row::Match(file_idx, match_idx) => {
+                let file = self.results.get(file_idx)?;
+                Some((file.path.clone(), Some(file.matches.get(match_idx)?.line)))
+            }
+        }
+    }
+
+    /// Show the panel in place of the sidebar tree
+    pub fn show(&mut self, ui: &mut Ui, theme: &RuntimeTheme) -> SearchPanelResponse {
+        let mut response = SearchPanelResponse::default();
+        let now = Instant::now();
+
+        Frame::NONE
+            .fill(theme.surface)
+            .show(ui, |ui| {
+                ui.vertical(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(format!("{}{} Search", tui::TOP_LEFT, tui::HORIZONTAL))
+                            .font(ui_font(12.0))
+                            .color(theme.text));
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.small_button("✕").on_hover_text("Back to files (Esc)").clicked() {
+                                response.close = true;
+                            }
+                        });
+                    });
+
+                    // Query and toggles
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(" ❯").font(ui_font(11.0)).color(theme.primary));
+                        let query = ui.add(
+                            egui::TextEdit::singleline(&mut self.options.query)
+                                .font(ui_font(11.0))
+                                .desired_width(ui.available_width() - 84.0)
+                                .hint_text("Search in project...")
+                        );
+                        if std::mem::take(&mut self.focus_pending) {
+                            query.request_focus();
+                        }
+                        let mut changed = query.changed();
+                        changed |= toggle(ui, theme, &mut self.options.case_sensitive, "Aa", "Match case");
+                        changed |= toggle(ui, theme, &mut self.options.whole_word, "ab", "Whole word");
+                        changed |= toggle(ui, theme, &mut self.options.regex, ".*", "Regular expression");
+                        if changed {
+                            self.edited(now);
+                        }
+                        // Enter and Esc take the focus off a single-line edit
+                        if query.has_focus() || query.lost_focus() {
+                            self.handle_keys(ui, &mut response);
+                        }
+                    });
+
+                    // Globs
+                    let mut globs_changed = false;
+                    for (label, text, hint) in [
+                        ("  +", &mut self.options.include, "Include, e.g. *.rs, src/**"),
+                        ("  -", &mut self.options.exclude, "Exclude, e.g. *.lock"),
+                    ] {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(label).font(ui_font(11.0)).color(theme.text_dim));
+                            let edit = ui.add(
+                                egui::TextEdit::singleline(text)
+                                    .font(ui_font(11.0))
+                                    .desired_width(ui.available_width() - 8.0)
+                                    .hint_text(hint)
+                            );
+                            globs_changed |= edit.changed();
+                        });
+                    }
+                    if globs_changed {
+                        self.edited(now);
+                    }
+
+                    self.show_summary(ui, theme);
+
+                    ui.label(RichText::new(format!("{}{}",
+                        tui::T_RIGHT,
+                        tui::HORIZONTAL.to_string().repeat(40)
+                    )).font(ui_font(12.0)).color(theme.border));
+
+                    ScrollArea::vertical()
+                        .id_salt("search_results")
+                        .show(ui, |ui| self.show_results(ui, theme, &mut response));
+                });
+            });
+
+        response
+    }
+
+    /// Up/Down move through the results, Enter opens, Esc closes
+    fn handle_keys(&mut self, ui: &Ui, response: &mut SearchPanelResponse) {
+        let (up, down, enter, escape) = ui.input(|i| {
+            (
+                i.key_pressed(Key::ArrowUp),
+                i.key_pressed(Key::ArrowDown),
+                i.key_pressed(Key::Enter),
+                i.key_pressed(Key::Escape),
+            )
+        });
+        if up {
+            self.move_selection(-1);
+        }
+        if down {
+            self.move_selection(1);
+        }
+        if enter {
+            response.open = self.selected.and_then(|row| self.target(row));
+        }
+        if escape {
+            response.close = true;
+        }
+    }
+
+    fn show_summary(&self, ui: &mut Ui, theme: &RuntimeTheme) {
+        let (text, color) = match &self.state {
+            SearchState::Idle => return,
+            SearchState::Failed(error) => (error.clone(), theme.red),
+            SearchState::Done { truncated: true } => (
+                format!("{} results in {} files (truncated)", self.match_count(), self.results.len()),
+                theme.yellow,
+            ),
+            SearchState::Running | SearchState::Done { .. } => (
+                format!("{} results in {} files", self.match_count(), self.results.len()),
+                theme.text_dim,
+            ),
+        };
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!(" {}", text)).font(ui_font(10.0)).color(color));
+            if self.is_running() {
+                ui.spinner();
+            }
+        });
+    }
+
+    fn show_results(&mut self, ui: &mut Ui, theme: &RuntimeTheme, response: &mut SearchPanelResponse) {
+        let mut toggle_fold = None;
+        for row in self.rows() {
+            let is_selected = self.selected == Some(row);
+            let bg_color = if is_selected { theme.selection } else { theme.surface };
+            let label = match row {
+                Row::File(file_idx) => {
+                    let file = &self.results[file_idx];
+                    let folded = self.collapsed.contains(&file.path);
+                    let marker = if folded { tui::FOLDER_CLOSED } else { tui::FOLDER_OPEN };
+                    let name = file.path.strip_prefix(&self.root).unwrap_or(&file.path);
+                    let mut job = LayoutJob::default();
+                    append(&mut job, &format!("{} ", marker), theme.text_dim, None);
+                    append(&mut job, &name.display().to_string(), theme.text, None);
+                    append(&mut job, &format!(" {}", file.matches.len()), theme.text_dim, None);
+                    job
+                }
+                Row::Match(file_idx, match_idx) => {
+                    let found = &self.results[file_idx].matches[match_idx];
+                    let mut job = LayoutJob::default();
+                    append(&mut job, &format!("{:>6}  ", found.line), theme.text_dim, None);
+                    let mut last = 0;
+                    for range in &found.ranges {
+                        append(&mut job, &found.text[last..range.start], theme.text_dim, None);
+                        append(&mut job, &found.text[range.clone()], theme.text, Some(theme.selection));
+                        last = range.end;
+                    }
+                    append(&mut job, &found.text[last..], theme.text_dim, None);
+                    job
+                }
+            };
+
+            let row_response = ui.add(Button::new(label).fill(bg_color).frame(false).wrap_mode(egui::TextWrapMode::Truncate));
+            if row_response.clicked() {
+                self.selected = Some(row);
+                match row {
+                    Row::File(file_idx) => toggle_fold = Some(self.results[file_idx].path.clone()),
+                    Row::Match(..) => response.open = self.target(row),
+                }
+            }
+            if row_response.double_clicked() {
+                if let Row::File(_) = row {
+                    response.open = self.target(row);
+                }
+            }
+            if is_selected {
+                row_response.scroll_to_me(None);
+            }
+        }
+        if let Some(path) = toggle_fold {
+            if !self.collapsed.remove(&path) {
+                self.collapsed.insert(path);
+            }
+        }
+    }
+}
+
+impl Default for SearchPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Small on/off button for a search option
+fn toggle(ui: &mut Ui, theme: &RuntimeTheme, value: &mut bool, label: &str, hint: &str) -> bool {
+    let color = if *value { theme.primary } else { theme.text_dim };
+    let button = Button::new(RichText::new(label).font(ui_font(10.0)).color(color))
+        .fill(if *value { theme.surface_light } else { theme.surface })
+        .small();
+    let clicked = ui.add(button).on_hover_text(hint).clicked();
+    if clicked {
+        *value = !*value;
+    }
+    clicked
+}
+
+fn append(job: &mut LayoutJob, text: &str, color: egui::Color32, background: Option<egui::Color32>) {
+    let mut format = TextFormat::simple(ui_font(11.0), color);
+    if let Some(background) = background {
+        format.background = background;
+    }
+    job.append(text, 0.0, format);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_search::LineMatch;
+
+    fn file(path: &str, lines: &[u32]) -> SearchMessage {
+        SearchMessage::File(FileMatches {
+            path: PathBuf::from(path),
+            matches: lines
+                .iter()
+                .map(|&line| LineMatch { line, text: "needle".to_string(), ranges: vec![std::ops::Range { start: 0, end: 6 }] })
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn test_search_waits_for_edits_to_settle() {
+        let mut panel = SearchPanel::new();
+        let now = Instant::now();
+        panel.open(PathBuf::from("/p"), now);
+        // Nothing to search for yet
+        assert_eq!(panel.take_due_search(now + SEARCH_DEBOUNCE), None);
+
+        panel.options.query = "needle".to_string();
+        panel.edited(now);
+        assert_eq!(panel.take_due_search(now + Duration::from_millis(100)), None);
+        assert_eq!(panel.pending_delay(now + Duration::from_millis(100)), Some(Duration::from_millis(150)));
+
+        let options = panel.take_due_search(now + SEARCH_DEBOUNCE).unwrap();
+        assert_eq!(options.query, "needle");
+        assert!(panel.is_running());
+        assert_eq!(panel.take_due_search(now + SEARCH_DEBOUNCE * 2), None);
+
+        // Reopening on the same root keeps the results
+        panel.push(file("/p/a.rs", &[1]));
+        panel.open(PathBuf::from("/p"), now);
+        assert_eq!(panel.results.len(), 1);
+    }
+
+    #[test]
+    fn test_new_search_clears_results() {
+        let mut panel = SearchPanel::new();
+        let now = Instant::now();
+        panel.options.query = "needle".to_string();
+        panel.open(PathBuf::from("/p"), now);
+        panel.take_due_search(now + SEARCH_DEBOUNCE);
+        panel.push(file("/p/a.rs", &[1, 5]));
+        panel.push(SearchMessage::Done { truncated: true });
+        assert_eq!(panel.match_count(), 2);
+        assert_eq!(panel.state, SearchState::Done { truncated: true });
+
+        panel.options.query = "needles".to_string();
+        panel.edited(now);
+        panel.take_due_search(now + SEARCH_DEBOUNCE);
+        assert!(panel.results.is_empty());
+        assert!(panel.is_running());
+    }
+
+    #[test]
+    fn test_keyboard_selection_and_targets() {
+        let mut panel = SearchPanel::new();
+        panel.push(file("/p/a.rs", &[3, 8]));
+        panel.push(file("/p/b.rs", &[1]));
+
+        panel.move_selection(1);
+        assert_eq!(panel.selected, Some(Row::File(0)));
+        assert_eq!(panel.target(Row::File(0)), Some((PathBuf::from("/p/a.rs"), None)));
+
+        panel.move_selection(2);
+        assert_eq!(panel.target(panel.selected.unwrap()), Some((PathBuf::from("/p/a.rs"), Some(8))));
+
+        // Folded files skip their matches
+        panel.collapsed.insert(PathBuf::from("/p/a.rs"));
+        assert_eq!(panel.rows(), [Row::File(0), Row::File(1), Row::Match(1, 0)]);
+
+        panel.selected = Some(Row::File(0));
+        panel.move_selection(10);
+        assert_eq!(panel.selected, Some(Row::Match(1, 0)));
+        panel.move_selection(-10);
+        assert_eq!(panel.selected, Some(Row::File(0)));
+    }
+}
@@ -2,12 +2,18 @@
 //!
 //! TUI-style file tree browser using box-drawing characters
 
-use egui::{Button, Frame, RichText, ScrollArea, Sense, Ui};
-use crate::config::RuntimeTheme;
+use egui::text::LayoutJob;
+use egui::{Button, Frame, Key, RichText, ScrollArea, Sense, TextFormat, Ui};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use crate::config::{FileIconStyle, RuntimeTheme};
 use crate::layout::PaneId;
-use crate::theme::{tui, mono_font};
+use crate::theme::{tui, ui_font};
+use super::file_icons::file_icon;
 use std::path::PathBuf;
-use crate::context::{FileGitStatus, RepoStatus};
+use crate::context::{FileGitStatus, GitStatusCache, RepoStatus};
+use crate::directory_scanner::TreeVisibility;
+use crate::thumbnails::ThumbnailSlot;
 
 /// File/directory entry for sidebar
 #[derive(Debug, Clone)]
@@ -18,10 +24,18 @@ pub struct FileEntry {
     pub is_expanded: bool,
     pub depth: usize,
     pub is_last: bool,  // Is this the last item at this level?
+    /// Per ancestor depth `0..depth`: does that ancestor have following siblings?
+    pub ancestors_continue: Vec<bool>,
     /// Git status for this file (v0.7.0)
     pub git_status: Option<FileGitStatus>,
     /// Whether this file is pinned (v0.7.0)
     pub is_pinned: bool,
+    /// Path relative to the repo root, for status lookups
+    pub repo_relative: Option<PathBuf>,
+    /// [`GitStatusCache::root_generation`] `repo_relative` belongs to (0 = none yet)
+    pub repo_root_generation: u64,
+    /// Matches an ignore pattern or is gitignored (only listed in Show All)
+    pub is_ignored: bool,
 }
 
 impl FileEntry {
@@ -33,12 +47,184 @@ impl FileEntry {
             is_expanded: false,
             depth,
             is_last: false,
+            ancestors_continue: Vec::new(),
             git_status: None,
             is_pinned: false,
+            repo_relative: None,
+            repo_root_generation: 0,
+            is_ignored: false,
         }
     }
 }
 
+/// Entries given git statuses per frame when applying them to a large tree
+pub const GIT_STATUS_CHUNK: usize = 1000;
+
+/// Applies git statuses to a sidebar tree, a chunk of entries per frame
+///
+/// Nothing is done while the cache generation stays the same, so refreshes
+/// that change nothing cost nothing.
+#[derive(Debug, Default)]
+pub struct GitStatusSync {
+    /// Generation applied to every entry
+    applied: Option<u64>,
+    /// Generation being applied and the next entry to give it
+    pending: Option<(u64, usize)>,
+}
+
+impl GitStatusSync {
+    /// Entries were added, removed or replaced: apply again from the start
+    pub fn invalidate(&mut self) {
+        self.applied = None;
+        self.pending = None;
+    }
+
+    /// Give up to `budget` entries their status
+    ///
+    /// Returns `true` once every entry has the cache's current statuses.
+    pub fn step(&mut self, entries: &mut [FileEntry], cache: &GitStatusCache, budget: usize) -> bool {
+        let generation = cache.generation();
+        if self.applied == Some(generation) {
+            return true;
+        }
+        let start = match self.pending {
+            Some((pending, next)) if pending == generation => next.min(entries.len()),
+            _ => 0,
+        };
+        let end = start.saturating_add(budget).min(entries.len());
+
+        let root_generation = cache.root_generation();
+        for entry in &mut entries[start..end] {
+            if entry.repo_root_generation != root_generation {
+                entry.repo_relative = cache.relative_path(&entry.path);
+                entry.repo_root_generation = root_generation;
+            }
+            let status = match &entry.repo_relative {
+                Some(relative) => cache.get_file_status(relative),
+                None => FileGitStatus::Clean,
+            };
+            entry.git_status = Some(status);
+        }
+
+        if end == entries.len() {
+            self.applied = Some(generation);
+            self.pending = None;
+            true
+        } else {
+            self.pending = Some((generation, end));
+            false
+        }
+    }
+}
+
+/// Recompute `is_last` and `ancestors_continue` from tree order
+///
+/// Call after building, inserting or removing entries; hidden siblings and
+/// filtered-out entries don't count. `entries` must be in tree order.
+pub fn update_tree_flags(entries: &mut [FileEntry]) {
+    // Backwards: an entry is last unless a sibling follows before the parent's subtree ends
+    let mut sibling_follows: Vec<bool> = Vec::new();
+    for entry in entries.iter_mut().rev() {
+        let depth = entry.depth;
+        entry.is_last = !sibling_follows.get(depth).copied().unwrap_or(false);
+        sibling_follows.resize(depth + 1, false);
+        sibling_follows[depth] = true;
+    }
+
+    // Forwards: carry each open ancestor's continuation down to its descendants
+    let mut continues: Vec<bool> = Vec::new();
+    for entry in entries.iter_mut() {
+        let depth = entry.depth;
+        continues.resize(depth, false);
+        entry.ancestors_continue = continues.clone();
+        continues.push(!entry.is_last);
+    }
+}
+
+/// Tree connector prefix for an entry (empty at the top level)
+pub fn tree_prefix(entry: &FileEntry) -> String {
+    if entry.depth == 0 {
+        return String::new();
+    }
+
+    // Top-level ancestors have no connector column
+    let mut prefix = String::new();
+    for depth in 1..entry.depth {
+        let continues = entry.ancestors_continue.get(depth).copied().unwrap_or(false);
+        prefix.push_str(if continues { tui::TREE_PIPE } else { tui::TREE_SPACE });
+    }
+
+    if entry.is_last {
+        prefix.push_str(tui::TREE_LAST);
+    } else {
+        prefix.push_str(tui::TREE_BRANCH);
+    }
+
+    prefix
+}
+
+/// Entry kept by the sidebar filter
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterMatch {
+    /// Index into the unfiltered entry list
+    pub index: usize,
+    /// Matched character positions in the entry name (empty for ancestors)
+    pub positions: Vec<usize>,
+}
+
+/// Filter entries by fuzzy-matching their names against `query`
+///
+/// Directories containing a match are kept as well so the tree stays
+/// readable. `entries` must be in tree order (parents before children).
+/// An empty query keeps everything.
+pub fn filter_entries(entries: &[FileEntry], query: &str) -> Vec<FilterMatch> {
+    let query = query.trim();
+    if query.is_empty() {
+        return (0..entries.len())
+            .map(|index| FilterMatch { index, positions: Vec::new() })
+            .collect();
+    }
+
+    let matcher = SkimMatcherV2::default();
+    let mut matches = Vec::new();
+    let mut included = vec![false; entries.len()];
+    // Directories enclosing the current entry, indexed by depth
+    let mut ancestors: Vec<usize> = Vec::new();
+
+    for (idx, entry) in entries.iter().enumerate() {
+        ancestors.truncate(entry.depth);
+
+        if let Some((_, positions)) = matcher.fuzzy_indices(&entry.name, query) {
+            for &ancestor in &ancestors {
+                if !included[ancestor] {
+                    included[ancestor] = true;
+                    matches.push(FilterMatch { index: ancestor, positions: Vec::new() });
+                }
+            }
+            included[idx] = true;
+            matches.push(FilterMatch { index: idx, positions });
+        }
+
+        if entry.is_dir {
+            ancestors.push(idx);
+        }
+    }
+
+    matches
+}
+
+/// Filter box state shown above the tree
+pub struct FilterView<'a> {
+    /// Current query
+    pub query: &'a str,
+    /// Matched name characters per entry (parallel to the sidebar entries)
+    pub highlights: &'a [Vec<usize>],
+    /// Deep scan for the filter is still running
+    pub scanning: bool,
+    /// Move keyboard focus into the filter box
+    pub request_focus: bool,
+}
+
 /// Sidebar file browser
 pub struct Sidebar<'a> {
     entries: &'a [FileEntry],
@@ -55,6 +241,16 @@ pub struct Sidebar<'a> {
     repo_status: Option<&'a RepoStatus>,
     /// Enable git status display
     show_git_status: bool,
+    /// Active filter, if any
+    filter: Option<FilterView<'a>>,
+    /// File icon style (already resolved against the font)
+    icon_style: FileIconStyle,
+    /// Which entries are listed
+    visibility: TreeVisibility,
+    /// Entry index and thumbnail shown in its hover tooltip
+    preview: Option<(usize, &'a ThumbnailSlot<egui::TextureHandle>)>,
+    /// There are earlier / later roots to go to
+    history: (bool, bool),
 }
 
 impl<'a> Sidebar<'a> {
@@ -79,9 +275,44 @@ impl<'a> Sidebar<'a> {
             loading,
             repo_status,
             show_git_status,
+            filter: None,
+            icon_style: FileIconStyle::Ascii,
+            visibility: TreeVisibility::Normal,
+            preview: None,
+            history: (false, false),
         }
     }
 
+    /// List the filter's matches instead of the tree
+    pub fn with_filter(mut self, filter: Option<FilterView<'a>>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// File icons, already resolved against the font
+    pub fn with_icon_style(mut self, icon_style: FileIconStyle) -> Self {
+        self.icon_style = icon_style;
+        self
+    }
+
+    /// Which entries the tree lists, for the header toggle
+    pub fn with_visibility(mut self, visibility: TreeVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Show a thumbnail tooltip while entry `index` is hovered
+    pub fn preview(mut self, preview: Option<(usize, &'a ThumbnailSlot<egui::TextureHandle>)>) -> Self {
+        self.preview = preview;
+        self
+    }
+
+    /// Enable the back and forward buttons
+    pub fn history(mut self, can_go_back: bool, can_go_forward: bool) -> Self {
+        self.history = (can_go_back, can_go_forward);
+        self
+    }
+
     /// Show the sidebar and return user actions
     pub fn show(&self, ui: &mut Ui) -> SidebarResponse {
         let mut response = SidebarResponse::default();
@@ -95,7 +326,7 @@ impl<'a> Sidebar<'a> {
                         ui.label(RichText::new(format!("{}{}",
                             tui::TOP_LEFT,
                             tui::HORIZONTAL.to_string().repeat(2),
-                        )).font(mono_font(12.0)).color(self.theme.border));
+                        )).font(ui_font(12.0)).color(self.theme.border));
 
                         // Pane mini-tabs
                         for (pane_id, _pane_dir) in self.panes {
@@ -110,7 +341,7 @@ impl<'a> Sidebar<'a> {
 
                             let btn = Button::new(
                                 RichText::new(&pane_label)
-                                    .font(mono_font(10.0))
+                                    .font(ui_font(10.0))
                                     .color(text_color)
                             )
                             .fill(self.theme.surface)
@@ -124,21 +355,34 @@ impl<'a> Sidebar<'a> {
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             ui.label(RichText::new(format!(" {}",
                                 tui::TOP_RIGHT
-                            )).font(mono_font(12.0)).color(self.theme.border));
+                            )).font(ui_font(12.0)).color(self.theme.border));
                         });
                     });
 
                     // Project root name below pane tabs with collapse/expand buttons
                     ui.horizontal(|ui| {
-                        ui.label(RichText::new(" ").font(mono_font(11.0)));
+                        ui.spacing_mut().item_spacing.x = 2.0;
+                        let (can_go_back, can_go_forward) = self.history;
+                        if ui.add_enabled(can_go_back, Button::new(RichText::new("◀").font(ui_font(9.0))).small())
+                            .on_hover_text(crate::shortcuts::shortcut_label("Back (Cmd+[)"))
+                            .clicked()
+                        {
+                            response.back = true;
+                        }
+                        if ui.add_enabled(can_go_forward, Button::new(RichText::new("▶").font(ui_font(9.0))).small())
+                            .on_hover_text(crate::shortcuts::shortcut_label("Forward (Cmd+])"))
+                            .clicked()
+                        {
+                            response.forward = true;
+                        }
                         ui.label(RichText::new(self.root_name)
-                            .font(mono_font(11.0))
+                            .font(ui_font(11.0))
                             .color(self.theme.text));
 
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             // Collapse all button
                             if ui.small_button("⊟")
-                                .on_hover_text("Collapse All (Cmd+Shift+C)")
+                                .on_hover_text(crate::shortcuts::shortcut_label("Collapse All (Cmd+Shift+C)"))
                                 .clicked()
                             {
                                 response.collapse_all = true;
@@ -146,19 +390,73 @@ impl<'a> Sidebar<'a> {
 
                             // Expand all button
                             if ui.small_button("⊞")
-                                .on_hover_text("Expand All (Cmd+Shift+E)")
+                                .on_hover_text(crate::shortcuts::shortcut_label("Expand All (Cmd+Shift+E)"))
                                 .clicked()
                             {
                                 response.expand_all = true;
                             }
+
+                            // Hidden/ignored entries toggle, highlighted unless Normal
+                            let eye_color = if self.visibility == TreeVisibility::Normal {
+                                self.theme.text_dim
+                            } else {
+                                self.theme.primary
+                            };
+                            if ui.small_button(RichText::new("👁").color(eye_color))
+                                .on_hover_text(format!("Showing: {} (click for {})", self.visibility.label(), self.visibility.next().label()))
+                                .clicked()
+                            {
+                                response.visibility_mode_changed = true;
+                            }
+
+                            // Filter toggle button
+                            if ui.small_button("🔍")
+                                .on_hover_text(crate::shortcuts::shortcut_label("Filter (Cmd+Shift+F)"))
+                                .clicked()
+                            {
+                                response.toggle_filter = true;
+                            }
                         });
                     });
 
+                    // Filter input
+                    if let Some(filter) = &self.filter {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(" ❯").font(ui_font(11.0)).color(self.theme.primary));
+
+                            let mut query = filter.query.to_string();
+                            let text_response = ui.add(
+                                egui::TextEdit::singleline(&mut query)
+                                    .font(ui_font(11.0))
+                                    .desired_width(ui.available_width() - 24.0)
+                                    .hint_text("Filter files...")
+                            );
+
+                            if filter.request_focus {
+                                text_response.request_focus();
+                            }
+                            if text_response.changed() {
+                                response.filter_changed = Some(query);
+                            }
+                            if text_response.lost_focus() {
+                                if ui.input(|i| i.key_pressed(Key::Enter)) {
+                                    response.filter_submitted = true;
+                                } else if ui.input(|i| i.key_pressed(Key::Escape)) {
+                                    response.filter_cleared = true;
+                                }
+                            }
+
+                            if filter.scanning {
+                                ui.spinner();
+                            }
+                        });
+                    }
+
                     // Separator line
                     ui.label(RichText::new(format!("{}{}",
                         tui::T_RIGHT,
                         tui::HORIZONTAL.to_string().repeat(40)
-                    )).font(mono_font(12.0)).color(self.theme.border));
+                    )).font(ui_font(12.0)).color(self.theme.border));
 
                     // Scrollable file list
                     ScrollArea::vertical()
@@ -169,7 +467,7 @@ impl<'a> Sidebar<'a> {
                                 if self.loading {
                                     ui.horizontal(|ui| {
                                         ui.label(RichText::new(" 🔄 Loading...")
-                                            .font(mono_font(11.0))
+                                            .font(ui_font(11.0))
                                             .color(self.theme.text_dim));
                                     });
                                     return;
@@ -179,7 +477,7 @@ impl<'a> Sidebar<'a> {
                                     let is_selected = self.selected_index == Some(idx);
 
                                     // Build tree prefix
-                                    let prefix = self.build_tree_prefix(entry);
+                                    let prefix = tree_prefix(entry);
 
                                     // Git status indicator (v0.7.0)
                                     let git_indicator = if self.show_git_status {
@@ -196,27 +494,31 @@ impl<'a> Sidebar<'a> {
                                     };
 
                                     // Icon based on type
-                                    let icon = if entry.is_dir {
-                                        if entry.is_expanded {
+                                    let (icon, icon_color) = if entry.is_dir {
+                                        let icon = if entry.is_expanded {
                                             tui::FOLDER_OPEN
                                         } else {
                                             tui::FOLDER_CLOSED
-                                        }
+                                        };
+                                        (icon.to_string(), None)
+                                    } else if self.icon_style == FileIconStyle::Off {
+                                        (tui::FILE.to_string(), None)
                                     } else {
-                                        tui::FILE
+                                        let icon = file_icon(&entry.name, self.icon_style);
+                                        (format!("{:^3}", icon.glyph), Some(icon.category.color(self.theme)))
                                     };
 
-                                    // Full line text with git/pin indicators
-                                    let text = format!("{}{} {}{}{}",
+                                    // Row text before the icon, with git/pin indicators
+                                    let lead = format!("{}{} {}",
                                         prefix,
                                         git_indicator,
                                         pin_indicator,
-                                        icon,
-                                        entry.name
                                     );
 
                                     let text_color = if is_selected {
                                         self.theme.text
+                                    } else if entry.is_ignored {
+                                        self.theme.text_dim.gamma_multiply(0.6)
                                     } else {
                                         self.theme.text_dim
                                     };
@@ -227,12 +529,30 @@ impl<'a> Sidebar<'a> {
                                         self.theme.surface
                                     };
 
+                                    // Highlight filter matches in the name
+                                    let highlights = self.filter
+                                        .as_ref()
+                                        .and_then(|f| f.highlights.get(idx))
+                                        .filter(|positions| !positions.is_empty());
+
+                                    let mut label = self.row_text(
+                                        &lead,
+                                        &icon,
+                                        icon_color.unwrap_or(text_color),
+                                        &entry.name,
+                                        highlights.map_or(&[][..], Vec::as_slice),
+                                        text_color,
+                                    );
+                                    if entry.is_ignored {
+                                        label.append(" (ignored)", 0.0, TextFormat {
+                                            font_id: ui_font(10.0),
+                                            color: self.theme.text_dim.gamma_multiply(0.6),
+                                            ..Default::default()
+                                        });
+                                    }
+
                                     // Clickable row
-                                    let btn = Button::new(
-                                        RichText::new(&text)
-                                            .font(mono_font(11.0))
-                                            .color(text_color)
-                                    )
+                                    let btn = Button::new(label)
                                     .fill(bg_color)
                                     .frame(false)
                                     .sense(Sense::click());
@@ -244,6 +564,12 @@ impl<'a> Sidebar<'a> {
                                         let rect = btn_response.rect;
                                         ui.painter().rect_filled(rect, 0.0, self.theme.surface_light);
                                     }
+                                    if btn_response.hovered() {
+                                        response.hovered = Some(idx);
+                                        if let Some((_, slot)) = self.preview.filter(|(index, _)| *index == idx) {
+                                            self.show_preview(ui, &btn_response, &entry.name, slot);
+                                        }
+                                    }
 
                                     // Handle click
                                     if btn_response.clicked() {
@@ -257,6 +583,22 @@ impl<'a> Sidebar<'a> {
                                     if btn_response.double_clicked() && !entry.is_dir {
                                         response.opened_file = Some(idx);
                                     }
+
+                                    btn_response.context_menu(|ui| {
+                                        if ui.button("Move to Trash")
+                                            .on_hover_text("Hold Shift for Delete Permanently")
+                                            .clicked()
+                                        {
+                                            response.trash = Some(idx);
+                                            ui.close_menu();
+                                        }
+                                        if ui.input(|i| i.modifiers.shift)
+                                            && ui.button("Delete Permanently…").clicked()
+                                        {
+                                            response.delete_permanently = Some(idx);
+                                            ui.close_menu();
+                                        }
+                                    });
                                 }
                             });
                         });
@@ -266,7 +608,7 @@ impl<'a> Sidebar<'a> {
                         ui.label(RichText::new(format!("{}{}",
                             tui::BOTTOM_LEFT,
                             tui::HORIZONTAL.to_string().repeat(40)
-                        )).font(mono_font(12.0)).color(self.theme.border));
+                        )).font(ui_font(12.0)).color(self.theme.border));
                     });
                 });
 
@@ -281,24 +623,65 @@ impl<'a> Sidebar<'a> {
         response
     }
 
-    /// Build tree-style prefix for entry
-    fn build_tree_prefix(&self, entry: &FileEntry) -> String {
-        if entry.depth == 0 {
-            return String::new();
-        }
+    /// Thumbnail tooltip next to a hovered image entry
+    fn show_preview(&self, ui: &Ui, row: &egui::Response, name: &str, slot: &ThumbnailSlot<egui::TextureHandle>) {
+        egui::show_tooltip_for(ui.ctx(), ui.layer_id(), row.id.with("thumbnail"), &row.rect, |ui| {
+            match slot {
+                ThumbnailSlot::Ready(thumbnail) => {
+                    ui.image(egui::load::SizedTexture::from_handle(&thumbnail.texture));
+                    ui.label(RichText::new(format!(
+                        "{} × {} · {}",
+                        thumbnail.width,
+                        thumbnail.height,
+                        crate::thumbnails::format_size(thumbnail.file_size),
+                    )).font(ui_font(10.0)).color(self.theme.text_dim));
+                }
+                ThumbnailSlot::Pending => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(RichText::new(name).font(ui_font(10.0)));
+                    });
+                }
+                ThumbnailSlot::Failed => {
+                    ui.label(RichText::new(name).font(ui_font(10.0)));
+                }
+            }
+        });
+    }
 
-        let mut prefix = String::new();
-        for _ in 0..entry.depth.saturating_sub(1) {
-            prefix.push_str(tui::TREE_PIPE);
+    /// Build row text: tree prefix, colored icon, and the name with filter
+    /// matches in the accent color
+    fn row_text(
+        &self,
+        lead: &str,
+        icon: &str,
+        icon_color: egui::Color32,
+        name: &str,
+        positions: &[usize],
+        color: egui::Color32,
+    ) -> LayoutJob {
+        let format = |color| TextFormat {
+            font_id: ui_font(11.0),
+            color,
+            ..Default::default()
+        };
+
+        let mut job = LayoutJob::default();
+        job.append(lead, 0.0, format(color));
+        job.append(icon, 0.0, format(icon_color));
+        if positions.is_empty() {
+            job.append(name, 0.0, format(color));
+            return job;
         }
-
-        if entry.is_last {
-            prefix.push_str(tui::TREE_LAST);
-        } else {
-            prefix.push_str(tui::TREE_BRANCH);
+        for (i, c) in name.chars().enumerate() {
+            let is_match = positions.contains(&i);
+            job.append(
+                c.encode_utf8(&mut [0; 4]),
+                0.0,
+                format(if is_match { self.theme.primary } else { color }),
+            );
         }
-
-        prefix
+        job
     }
 
     /// Get color for git status indicator
@@ -333,4 +716,228 @@ pub struct SidebarResponse {
     pub collapse_all: bool,
     /// Expand all directories requested
     pub expand_all: bool,
+    /// Filter box toggled via the header button
+    pub toggle_filter: bool,
+    /// Filter query edited
+    pub filter_changed: Option<String>,
+    /// Enter pressed in the filter box
+    pub filter_submitted: bool,
+    /// Esc pressed in the filter box
+    pub filter_cleared: bool,
+    /// "Move to Trash" chosen in an entry's context menu
+    pub trash: Option<usize>,
+    /// "Delete Permanently…" chosen (only offered while Shift is held)
+    pub delete_permanently: Option<usize>,
+    /// Eye button clicked: cycle the workspace's [`TreeVisibility`]
+    pub visibility_mode_changed: bool,
+    /// Entry under the pointer
+    pub hovered: Option<usize>,
+    /// Back button clicked: previous sidebar root
+    pub back: bool,
+    /// Forward button clicked: next sidebar root
+    pub forward: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, is_dir: bool, depth: usize) -> FileEntry {
+        FileEntry::new(name, PathBuf::from(name), is_dir, depth)
+    }
+
+    /// Repo with untracked `file3.txt`, and 10k sidebar entries in it
+    fn large_tree() -> (tempfile::TempDir, GitStatusCache, Vec<FileEntry>) {
+        let dir = tempfile::TempDir::new().unwrap();
+        git2::Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("file3.txt"), "x").unwrap();
+        let mut cache = GitStatusCache::new(std::time::Duration::from_secs(5));
+        cache.set_root(dir.path());
+        cache.refresh();
+        let root = cache.repo_root().unwrap().to_path_buf();
+        let entries = (0..10_000)
+            .map(|i| FileEntry::new(format!("file{}.txt", i), root.join(format!("file{}.txt", i)), false, 0))
+            .collect();
+        (dir, cache, entries)
+    }
+
+    #[test]
+    fn test_git_status_sync_chunks_and_skips_unchanged() {
+        let (_dir, cache, mut entries) = large_tree();
+        let mut sync = GitStatusSync::default();
+
+        let mut frames = 1;
+        while !sync.step(&mut entries, &cache, GIT_STATUS_CHUNK) {
+            frames += 1;
+        }
+        assert_eq!(frames, 10);
+        assert_eq!(entries[3].git_status, Some(FileGitStatus::Untracked));
+        assert_eq!(entries[4].git_status, Some(FileGitStatus::Clean));
+        assert!(entries.iter().all(|e| e.repo_relative.is_some()));
+
+        // Same generation: nothing left to do
+        entries[4].git_status = None;
+        assert!(sync.step(&mut entries, &cache, GIT_STATUS_CHUNK));
+        assert_eq!(entries[4].git_status, None);
+
+        sync.invalidate();
+        assert!(sync.step(&mut entries, &cache, usize::MAX));
+        assert_eq!(entries[4].git_status, Some(FileGitStatus::Clean));
+    }
+
+    #[test]
+    fn test_git_status_sync_reuses_work() {
+        let (_dir, cache, mut entries) = large_tree();
+        let mut sync = GitStatusSync::default();
+        while !sync.step(&mut entries, &cache, GIT_STATUS_CHUNK) {}
+
+        // An unchanged generation is done without touching a single entry
+        assert!(sync.step(&mut entries, &cache, 0));
+
+        // Reapplying keeps relative paths from the same root instead of
+        // working them out again
+        entries[4].repo_relative = Some(PathBuf::from("elsewhere.txt"));
+        sync.invalidate();
+        assert!(sync.step(&mut entries, &cache, usize::MAX));
+        assert_eq!(entries[4].repo_relative, Some(PathBuf::from("elsewhere.txt")));
+        assert!(entries.iter().all(|e| e.git_status.is_some()));
+    }
+
+    /// src/
+    ///   ui/
+    ///     sidebar.rs
+    ///   app.rs
+    /// README.md
+    fn sample_tree() -> Vec<FileEntry> {
+        vec![
+            entry("src", true, 0),
+            entry("ui", true, 1),
+            entry("sidebar.rs", false, 2),
+            entry("app.rs", false, 1),
+            entry("README.md", false, 0),
+        ]
+    }
+
+    fn indices(matches: &[FilterMatch]) -> Vec<usize> {
+        matches.iter().map(|m| m.index).collect()
+    }
+
+    #[test]
+    fn test_empty_query_keeps_everything() {
+        let entries = sample_tree();
+        let matches = filter_entries(&entries, "  ");
+        assert_eq!(indices(&matches), vec![0, 1, 2, 3, 4]);
+        assert!(matches.iter().all(|m| m.positions.is_empty()));
+    }
+
+    #[test]
+    fn test_match_includes_ancestors() {
+        let entries = sample_tree();
+        let matches = filter_entries(&entries, "sidebar");
+
+        // src/ and ui/ are kept as ancestors of sidebar.rs
+        assert_eq!(indices(&matches), vec![0, 1, 2]);
+        assert!(matches[0].positions.is_empty());
+        assert!(matches[1].positions.is_empty());
+        assert_eq!(matches[2].positions, vec![0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_ancestors_included_once_in_order() {
+        let entries = sample_tree();
+        let matches = filter_entries(&entries, ".rs");
+
+        // Both files share src/; it must appear once, before its children
+        assert_eq!(indices(&matches), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_sibling_directories_not_included() {
+        let entries = vec![
+            entry("docs", true, 0),
+            entry("guide.md", false, 1),
+            entry("src", true, 0),
+            entry("main.rs", false, 1),
+        ];
+        let matches = filter_entries(&entries, "main");
+        assert_eq!(indices(&matches), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_no_match() {
+        let entries = sample_tree();
+        assert!(filter_entries(&entries, "zzz").is_empty());
+    }
+
+    fn prefixes(entries: &[FileEntry]) -> Vec<String> {
+        entries.iter().map(tree_prefix).collect()
+    }
+
+    #[test]
+    fn test_tree_prefixes_three_levels() {
+        // src/
+        //   ui/            (not last: app.rs follows)
+        //     sidebar.rs   (last)
+        //   app.rs         (last)
+        // tests/           (last top-level)
+        //   deep/          (last)
+        //     case.rs
+        //     more.rs
+        let mut entries = vec![
+            entry("src", true, 0),
+            entry("ui", true, 1),
+            entry("sidebar.rs", false, 2),
+            entry("app.rs", false, 1),
+            entry("tests", true, 0),
+            entry("deep", true, 1),
+            entry("case.rs", false, 2),
+            entry("more.rs", false, 2),
+        ];
+        update_tree_flags(&mut entries);
+
+        assert_eq!(prefixes(&entries), vec![
+            "",
+            "├──",
+            "│  └──",
+            "└──",
+            "",
+            "└──",
+            // deep/ was the last child, so no stray pipe
+            "   ├──",
+            "   └──",
+        ]);
+    }
+
+    #[test]
+    fn test_tree_flags_after_insert() {
+        // Children inserted under a directory that was the last entry
+        let mut entries = vec![
+            entry("a", true, 0),
+            entry("b", true, 0),
+        ];
+        update_tree_flags(&mut entries);
+        assert!(!entries[0].is_last);
+        assert!(entries[1].is_last);
+
+        entries.insert(1, entry("a1", false, 1));
+        entries.insert(2, entry("a2", true, 1));
+        entries.insert(3, entry("a2x", false, 2));
+        update_tree_flags(&mut entries);
+        assert_eq!(prefixes(&entries), vec!["", "├──", "└──", "   └──", ""]);
+
+        // Removing the last child promotes its sibling
+        entries.drain(2..4);
+        update_tree_flags(&mut entries);
+        assert_eq!(prefixes(&entries), vec!["", "└──", ""]);
+    }
+
+    #[test]
+    fn test_ancestors_continue() {
+        let mut entries = sample_tree();
+        update_tree_flags(&mut entries);
+        // sidebar.rs: src has README.md after it, ui has app.rs after it
+        assert_eq!(entries[2].ancestors_continue, vec![true, true]);
+        assert_eq!(entries[3].ancestors_continue, vec![true]);
+        assert!(entries[4].is_last);
+    }
 }
@@ -0,0 +1,239 @@
+//! Snippet Palette for inserting saved command templates
+
+use std::collections::HashMap;
+use egui::{Key, Modifiers, RichText};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use crate::config::RuntimeTheme;
+use crate::snippets::{parse_template, placeholders, render_template, Segment, Snippet};
+use crate::theme::ui_font;
+use super::palette_list::{self, ListKey};
+
+/// Placeholder fill-in state for the chosen snippet
+struct FillIn {
+    name: String,
+    segments: Vec<Segment>,
+    placeholders: Vec<String>,
+    values: Vec<String>,
+    /// Focus the first field on the next frame
+    focus_first: bool,
+}
+
+/// Snippet palette state
+pub struct SnippetPalette {
+    visible: bool,
+    snippets: Vec<Snippet>,
+    query: String,
+    /// Indices into `snippets`, best match first
+    filtered: Vec<usize>,
+    selected: usize,
+    fill_in: Option<FillIn>,
+    error: Option<String>,
+    matcher: SkimMatcherV2,
+}
+
+impl SnippetPalette {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            snippets: Vec::new(),
+            query: String::new(),
+            filtered: Vec::new(),
+            selected: 0,
+            fill_in: None,
+            error: None,
+            matcher: SkimMatcherV2::default(),
+        }
+    }
+
+    /// Open the palette with the given snippets
+    pub fn open(&mut self, snippets: Vec<Snippet>) {
+        self.visible = true;
+        self.snippets = snippets;
+        self.query.clear();
+        self.fill_in = None;
+        self.error = None;
+        self.update_filter();
+    }
+
+    /// Is palette visible?
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Update filtered snippets based on query (name, command and tags)
+    fn update_filter(&mut self) {
+        let mut scored: Vec<(usize, i64)> = self.snippets
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, snippet)| {
+                if self.query.is_empty() {
+                    return Some((idx, 0));
+                }
+                let haystack = format!("{} {} {}", snippet.name, snippet.command, snippet.tags.join(" "));
+                self.matcher.fuzzy_match(&haystack, &self.query).map(|score| (idx, score))
+            })
+            .collect();
+
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        self.filtered = scored.into_iter().map(|(idx, _)| idx).collect();
+        self.selected = 0;
+    }
+
+    /// Pick a snippet: resolve immediately or ask for placeholder values
+    fn choose(&mut self, idx: usize) -> Option<String> {
+        let snippet = self.snippets.get(idx)?;
+        let segments = match parse_template(&snippet.command) {
+            Ok(segments) => segments,
+            Err(e) => {
+                self.error = Some(format!("{}: {}", snippet.name, e));
+                return None;
+            }
+        };
+
+        let names = placeholders(&segments);
+        if names.is_empty() {
+            return Some(render_template(&segments, &HashMap::new()));
+        }
+
+        self.fill_in = Some(FillIn {
+            name: snippet.name.clone(),
+            values: vec![String::new(); names.len()],
+            placeholders: names,
+            segments,
+            focus_first: true,
+        });
+        None
+    }
+
+    /// Show palette and return the resolved command to insert
+    pub fn show(&mut self, ctx: &egui::Context, theme: &RuntimeTheme) -> Option<String> {
+        if !self.visible {
+            return None;
+        }
+
+        let result = palette_list::window("snippet_palette", ctx, theme)
+            .show(ctx, |ui| {
+                if self.fill_in.is_some() {
+                    self.show_fill_in(ui, theme)
+                } else {
+                    self.show_list(ui, theme)
+                }
+            })
+            .and_then(|r| r.inner)
+            .flatten();
+
+        if result.is_some() {
+            self.visible = false;
+        }
+
+        result
+    }
+
+    /// Snippet search list
+    fn show_list(&mut self, ui: &mut egui::Ui, theme: &RuntimeTheme) -> Option<String> {
+        if palette_list::search_field(ui, &mut self.query, "Search snippets...", theme) {
+            self.update_filter();
+        }
+
+        ui.separator();
+
+        if let Some(error) = &self.error {
+            ui.label(RichText::new(error).font(ui_font(11.0)).color(theme.red));
+        }
+
+        if self.snippets.is_empty() {
+            ui.label(RichText::new("No snippets yet. Use \"Edit Snippets\" to add some.")
+                .font(ui_font(12.0))
+                .color(theme.text_dim));
+        }
+
+        let clicked = palette_list::rows(ui, theme, self.filtered.len(), self.selected, |ui, row, is_selected| {
+            let snippet = &self.snippets[self.filtered[row]];
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(&snippet.name)
+                        .font(ui_font(12.0))
+                        .color(if is_selected { theme.text } else { theme.text_dim }));
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        for tag in &snippet.tags {
+                            ui.label(RichText::new(format!("#{}", tag))
+                                .font(ui_font(10.0))
+                                .color(theme.secondary));
+                        }
+                    });
+                });
+                ui.label(RichText::new(&snippet.command)
+                    .font(ui_font(10.0))
+                    .color(theme.text_dim));
+            });
+        });
+        let mut chosen = clicked.map(|row| self.filtered[row]);
+
+        match palette_list::navigate(ui, &mut self.selected, self.filtered.len()) {
+            Some(ListKey::Enter { .. }) => chosen = self.filtered.get(self.selected).copied(),
+            Some(ListKey::Escape) => self.visible = false,
+            None => {}
+        }
+
+        chosen.and_then(|idx| self.choose(idx))
+    }
+
+    /// Placeholder fill-in dialog (Tab moves between fields)
+    fn show_fill_in(&mut self, ui: &mut egui::Ui, theme: &RuntimeTheme) -> Option<String> {
+        let fill_in = self.fill_in.as_mut()?;
+
+        ui.label(RichText::new(&fill_in.name).font(ui_font(14.0)).color(theme.primary));
+        ui.separator();
+
+        egui::Grid::new("snippet_fill_in")
+            .num_columns(2)
+            .spacing([16.0, 8.0])
+            .show(ui, |ui| {
+                for (idx, name) in fill_in.placeholders.iter().enumerate() {
+                    ui.label(RichText::new(name).font(ui_font(12.0)).color(theme.text_dim));
+                    let response = ui.add(egui::TextEdit::singleline(&mut fill_in.values[idx])
+                        .font(ui_font(12.0))
+                        .desired_width(400.0));
+                    if idx == 0 && fill_in.focus_first {
+                        response.request_focus();
+                    }
+                    ui.end_row();
+                }
+            });
+        fill_in.focus_first = false;
+
+        // Live preview of the resolved command
+        let values: HashMap<String, String> = fill_in.placeholders
+            .iter()
+            .cloned()
+            .zip(fill_in.values.iter().cloned())
+            .collect();
+        let resolved = render_template(&fill_in.segments, &values);
+
+        ui.separator();
+        ui.label(RichText::new(&resolved).font(ui_font(12.0)).color(theme.text));
+        ui.label(RichText::new("Enter to insert, Esc to go back")
+            .font(ui_font(10.0))
+            .color(theme.text_dim));
+
+        // Consumed, so the terminal doesn't also get them as the dialog closes
+        if ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Escape)) {
+            self.fill_in = None;
+            return None;
+        }
+        if ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Enter)) {
+            self.fill_in = None;
+            return Some(resolved);
+        }
+
+        None
+    }
+}
+
+impl Default for SnippetPalette {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,151 @@
+//! Connect to Host palette listing saved SSH hosts
+
+use egui::RichText;
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use crate::config::RuntimeTheme;
+use crate::ssh_config::SshHost;
+use crate::theme::ui_font;
+use super::palette_list::{self, ListKey};
+
+/// Where to open the chosen host
+#[derive(Debug, Clone, PartialEq)]
+pub enum SshAction {
+    NewTab(SshHost),
+    Split(SshHost),
+}
+
+/// SSH host palette state
+pub struct SshPalette {
+    visible: bool,
+    hosts: Vec<SshHost>,
+    query: String,
+    /// Indices into `hosts`, best match first
+    filtered: Vec<usize>,
+    selected: usize,
+    matcher: SkimMatcherV2,
+}
+
+impl SshPalette {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            hosts: Vec::new(),
+            query: String::new(),
+            filtered: Vec::new(),
+            selected: 0,
+            matcher: SkimMatcherV2::default(),
+        }
+    }
+
+    /// Open the palette with the given hosts, recently used first
+    pub fn open(&mut self, hosts: Vec<SshHost>) {
+        self.visible = true;
+        self.hosts = hosts;
+        self.query.clear();
+        self.update_filter();
+    }
+
+    /// Is the palette visible?
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Update filtered hosts based on the query
+    fn update_filter(&mut self) {
+        let mut scored: Vec<(usize, i64)> = self.hosts
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, host)| {
+                if self.query.is_empty() {
+                    return Some((idx, 0));
+                }
+                let text = format!("{} {}", host.name, host.detail());
+                self.matcher.fuzzy_match(&text, &self.query).map(|score| (idx, score))
+            })
+            .collect();
+
+        // Stable, so equal scores stay recently used first
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        self.filtered = scored.into_iter().map(|(idx, _)| idx).collect();
+        self.selected = 0;
+    }
+
+    /// Show the palette and return where to open the chosen host
+    pub fn show(&mut self, ctx: &egui::Context, theme: &RuntimeTheme) -> Option<SshAction> {
+        if !self.visible {
+            return None;
+        }
+
+        let result = palette_list::window("ssh_palette", ctx, theme)
+            .show(ctx, |ui| self.show_list(ui, theme))
+            .and_then(|r| r.inner)
+            .flatten();
+
+        if result.is_some() {
+            self.visible = false;
+        }
+
+        result
+    }
+
+    /// Host search list
+    fn show_list(&mut self, ui: &mut egui::Ui, theme: &RuntimeTheme) -> Option<SshAction> {
+        if palette_list::search_field(ui, &mut self.query, "Connect to host...", theme) {
+            self.update_filter();
+        }
+
+        ui.separator();
+
+        if self.hosts.is_empty() {
+            ui.label(RichText::new("No hosts yet. Add Host blocks to ~/.ssh/config or [[ssh_hosts]] to the config.")
+                .font(ui_font(12.0))
+                .color(theme.text_dim));
+        }
+
+        let clicked = palette_list::rows(ui, theme, self.filtered.len(), self.selected, |ui, row, is_selected| {
+            let host = &self.hosts[self.filtered[row]];
+            ui.horizontal(|ui| {
+                if let Some(tint) = host.tint {
+                    ui.label(RichText::new("●").font(ui_font(12.0)).color(tint));
+                }
+                ui.label(RichText::new(&host.name)
+                    .font(ui_font(12.0))
+                    .color(if is_selected { theme.text } else { theme.text_dim }));
+                ui.label(RichText::new(host.detail())
+                    .font(ui_font(10.0))
+                    .color(theme.secondary));
+            });
+        });
+        let mut chosen = clicked.map(|row| (self.filtered[row], false));
+
+        ui.label(RichText::new(format!(
+            "Enter to open in a new tab, {} to split",
+            crate::shortcuts::shortcut_label("Cmd+Enter")
+        ))
+            .font(ui_font(10.0))
+            .color(theme.text_dim));
+
+        match palette_list::navigate(ui, &mut self.selected, self.filtered.len()) {
+            Some(ListKey::Enter { primary }) => {
+                chosen = self.filtered.get(self.selected).map(|&idx| (idx, primary));
+            }
+            Some(ListKey::Escape) => self.visible = false,
+            None => {}
+        }
+
+        let (idx, split) = chosen?;
+        let host = self.hosts[idx].clone();
+        Some(if split {
+            SshAction::Split(host)
+        } else {
+            SshAction::NewTab(host)
+        })
+    }
+}
+
+impl Default for SshPalette {
+    fn default() -> Self {
+        Self::new()
+    }
+}
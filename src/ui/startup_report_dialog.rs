@@ -0,0 +1,91 @@
+//! Startup Report dialog
+
+use egui::{Frame, Key, RichText};
+use crate::config::RuntimeTheme;
+use crate::startup::{StartupReport, PHASES};
+use crate::theme::ui_font;
+
+/// Width of the bar of the longest phase
+const BAR_WIDTH: f32 = 240.0;
+
+/// Dialog state
+pub struct StartupReportDialog {
+    visible: bool,
+}
+
+impl StartupReportDialog {
+    pub fn new() -> Self {
+        Self { visible: false }
+    }
+
+    pub fn open(&mut self) {
+        self.visible = true;
+    }
+
+    /// Is dialog visible?
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Show each phase of `report` with a bar for its share of the longest
+    pub fn show(&mut self, ctx: &egui::Context, theme: &RuntimeTheme, report: &StartupReport) {
+        if !self.visible {
+            return;
+        }
+
+        let mut close = false;
+        let longest = report.phases().iter().map(|phase| phase.duration).max().unwrap_or_default();
+
+        egui::Window::new("Startup Report")
+            .collapsible(false)
+            .resizable(false)
+            .fixed_pos(egui::pos2(ctx.screen_rect().width() * 0.5 - 225.0, 100.0))
+            .fixed_size(egui::vec2(450.0, 0.0))
+            .frame(Frame::window(&ctx.style())
+                .fill(theme.surface)
+                .stroke(egui::Stroke::new(1.0, theme.border)))
+            .show(ctx, |ui| {
+                ui.label(RichText::new(format!("Launch to first frame: {} ms", report.total().as_millis()))
+                    .font(ui_font(12.0))
+                    .color(theme.text));
+                ui.add_space(4.0);
+
+                egui::Grid::new("startup_report_grid")
+                    .num_columns(3)
+                    .spacing([12.0, 4.0])
+                    .show(ui, |ui| {
+                        for name in PHASES {
+                            ui.label(RichText::new(*name).font(ui_font(11.0)).color(theme.text));
+                            let Some(duration) = report.duration(name) else {
+                                ui.label(RichText::new("—").font(ui_font(11.0)).color(theme.text_dim));
+                                ui.label(RichText::new("skipped").font(ui_font(10.0)).color(theme.text_dim));
+                                ui.end_row();
+                                continue;
+                            };
+                            ui.label(RichText::new(format!("{} ms", duration.as_millis()))
+                                .font(ui_font(11.0))
+                                .color(theme.text_dim));
+                            let share = if longest.is_zero() {
+                                0.0
+                            } else {
+                                duration.as_secs_f32() / longest.as_secs_f32()
+                            };
+                            let (rect, _) = ui.allocate_exact_size(egui::vec2(BAR_WIDTH, 10.0), egui::Sense::hover());
+                            ui.painter().rect_filled(rect, 2.0, theme.surface_light);
+                            let bar = egui::Rect::from_min_size(rect.min, egui::vec2(BAR_WIDTH * share, rect.height()));
+                            ui.painter().rect_filled(bar, 2.0, theme.primary);
+                            ui.end_row();
+                        }
+                    });
+
+                ui.add_space(8.0);
+                if ui.button("Close").clicked() || ui.input(|i| i.key_pressed(Key::Escape)) {
+                    close = true;
+                }
+            });
+
+        if close {
+            self.visible = false;
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Usage Stats tab: charts of the local usage stats
+
+use egui::{Color32, RichText};
+use crate::config::RuntimeTheme;
+use crate::stats::{format_duration, StatsSummary};
+use crate::theme::{ui_font, ui_scaled};
+
+/// Projects listed in the time chart
+const MAX_PROJECTS: usize = 8;
+
+/// Height of the column charts
+const CHART_HEIGHT: f32 = 90.0;
+
+/// Button pressed in the Usage Stats tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsCommand {
+    /// Read the stats file again
+    Refresh,
+    /// Delete the stats file
+    DeleteAll,
+}
+
+/// Show the summary (`None` while it's read); `recording` is whether stats are being collected
+pub fn show_stats(ui: &mut egui::Ui, summary: Option<&StatsSummary>, recording: bool, colors: &RuntimeTheme) -> Option<StatsCommand> {
+    let mut command = None;
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Usage Stats").font(ui_font(13.0)).color(colors.text));
+        ui.label(RichText::new("stored on this machine only").font(ui_font(11.0)).color(colors.text_dim));
+        if ui.small_button("Refresh").clicked() {
+            command = Some(StatsCommand::Refresh);
+        }
+        if ui.small_button("Delete all stats").on_hover_text("Delete everything recorded so far").clicked() {
+            command = Some(StatsCommand::DeleteAll);
+        }
+    });
+    if !recording {
+        ui.label(RichText::new("Recording is off. Turn on advanced.local_stats in Preferences to collect stats.")
+            .font(ui_font(11.0))
+            .color(colors.yellow));
+    }
+    ui.add_space(6.0);
+
+    let Some(summary) = summary else {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(RichText::new("Reading stats…").font(ui_font(11.0)).color(colors.text_dim));
+        });
+        return command;
+    };
+    egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        heading(ui, "Time per project, last 7 days", colors);
+        if summary.project_time.is_empty() {
+            ui.label(RichText::new("Nothing recorded yet").font(ui_font(11.0)).color(colors.text_dim));
+        }
+        let longest = summary.project_time.first().map_or(1, |(_, seconds)| *seconds).max(1);
+        for (project, seconds) in summary.project_time.iter().take(MAX_PROJECTS) {
+            project_bar(ui, project, *seconds as f32 / longest as f32, &format_duration(*seconds), colors);
+        }
+
+        heading(ui, "Commands per day", colors);
+        let days: Vec<(String, u32)> = summary.commands_per_day
+            .iter()
+            .map(|(date, count)| (date.clone(), *count))
+            .collect();
+        column_chart(ui, &days, colors.primary, colors);
+
+        heading(ui, "Busiest hours, last 7 days", colors);
+        let hours: Vec<(String, u32)> = summary.commands_by_hour
+            .iter()
+            .enumerate()
+            .map(|(hour, count)| (format!("{:02}:00", hour), *count))
+            .collect();
+        column_chart(ui, &hours, colors.secondary, colors);
+
+        ui.add_space(8.0);
+        ui.label(RichText::new(format!(
+            "{} tabs and {} panes opened in the last 7 days",
+            summary.tabs_opened, summary.panes_opened
+        ))
+            .font(ui_font(11.0))
+            .color(colors.text_dim));
+    });
+    command
+}
+
+fn heading(ui: &mut egui::Ui, text: &str, colors: &RuntimeTheme) {
+    ui.add_space(10.0);
+    ui.label(RichText::new(text).font(ui_font(12.0)).color(colors.text));
+    ui.add_space(2.0);
+}
+
+/// A project's path, a bar `fraction` of the longest, and its time
+fn project_bar(ui: &mut egui::Ui, project: &str, fraction: f32, time: &str, colors: &RuntimeTheme) {
+    let name = std::path::Path::new(project)
+        .file_name()
+        .map_or(project.to_string(), |name| name.to_string_lossy().to_string());
+    ui.horizontal(|ui| {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui_scaled(140.0), ui_scaled(16.0)),
+            egui::Sense::hover(),
+        );
+        let galley = ui.painter().layout_no_wrap(name, ui_font(11.0), colors.text_dim);
+        ui.painter().with_clip_rect(rect).galley(rect.left_center() - egui::vec2(0.0, galley.size().y / 2.0), galley, colors.text_dim);
+        response.on_hover_text(project);
+
+        let width = ui_scaled(260.0);
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(width, ui_scaled(10.0)), egui::Sense::hover());
+        ui.painter().rect_filled(rect, 2.0, colors.border);
+        let bar = egui::Rect::from_min_size(rect.min, egui::vec2((width * fraction).max(2.0), rect.height()));
+        ui.painter().rect_filled(bar, 2.0, colors.primary);
+        ui.label(RichText::new(time).font(ui_font(11.0)).color(colors.text));
+    });
+}
+
+/// One column per `(label, count)`, labelled with the first and last labels
+fn column_chart(ui: &mut egui::Ui, columns: &[(String, u32)], color: Color32, colors: &RuntimeTheme) {
+    let most = columns.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+    let column_width = ui_scaled(14.0);
+    let gap = ui_scaled(3.0);
+    let size = egui::vec2(columns.len() as f32 * (column_width + gap), ui_scaled(CHART_HEIGHT));
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    ui.painter().hline(rect.x_range(), rect.bottom(), egui::Stroke::new(1.0, colors.border));
+
+    for (index, (label, count)) in columns.iter().enumerate() {
+        let left = rect.left() + index as f32 * (column_width + gap);
+        let slot = egui::Rect::from_x_y_ranges(left..=left + column_width, rect.y_range());
+        let height = rect.height() * *count as f32 / most as f32;
+        let bar = egui::Rect::from_min_max(egui::pos2(slot.left(), slot.bottom() - height), slot.right_bottom());
+        if *count > 0 {
+            ui.painter().rect_filled(bar, 1.0, color);
+        }
+        let response = ui.interact(slot, ui.id().with((label, index)), egui::Sense::hover());
+        if response.hovered() {
+            ui.painter().rect_stroke(slot, 1.0, egui::Stroke::new(1.0, colors.text_dim), egui::StrokeKind::Inside);
+        }
+        response.on_hover_text(format!("{}: {}", label, count));
+    }
+
+    if let (Some((first, _)), Some((last, _))) = (columns.first(), columns.last()) {
+        ui.horizontal(|ui| {
+            ui.set_width(size.x);
+            ui.label(RichText::new(first).font(ui_font(10.0)).color(colors.text_dim));
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.label(RichText::new(last).font(ui_font(10.0)).color(colors.text_dim));
+            });
+        });
+    }
+}
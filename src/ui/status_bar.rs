@@ -2,28 +2,282 @@
 //!
 //! TUI-style bottom status bar with box-drawing characters
 
-use egui::{Frame, RichText, Ui};
-use crate::config::RuntimeTheme;
-use crate::theme::{tui, mono_font};
+use egui::{Button, Frame, RichText, Ui};
+use std::time::SystemTime;
+use crate::config::{RuntimeTheme, StatusSegment};
+use crate::context::{FetchStatus, RefreshInfo, RepoStatus, WatcherState};
+use crate::theme::{tui, ui_font};
+
+/// Overflow button holding the segments that don't fit
+const OVERFLOW_LABEL: &str = " » ";
+
+/// Widths a segment can be drawn at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentSize {
+    /// Higher keeps its full width longer
+    pub priority: u8,
+    pub min: f32,
+    pub preferred: f32,
+}
+
+/// How a segment is drawn after layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fit {
+    Full,
+    /// At its minimum width
+    Short,
+    /// Only in the overflow popover
+    Hidden,
+}
+
+/// Fit segments into `available` px
+///
+/// Lowest priority first (of equals, the later one), segments shrink to
+/// their minimum width, then fold into an overflow button `overflow` px
+/// wide. Shrunk segments that fit again once others are folded grow
+/// back, highest priority first.
+pub fn fit_segments(sizes: &[SegmentSize], available: f32, overflow: f32) -> Vec<Fit> {
+    let width = |fits: &[Fit]| -> f32 {
+        sizes
+            .iter()
+            .zip(fits)
+            .map(|(size, fit)| match fit {
+                Fit::Full => size.preferred,
+                Fit::Short => size.min,
+                Fit::Hidden => 0.0,
+            })
+            .sum()
+    };
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| (sizes[i].priority, std::cmp::Reverse(i)));
+
+    let mut fits = vec![Fit::Full; sizes.len()];
+    for &i in &order {
+        if width(&fits) <= available {
+            return fits;
+        }
+        fits[i] = Fit::Short;
+    }
+    if width(&fits) <= available {
+        return fits;
+    }
+
+    let room = available - overflow;
+    for &i in &order {
+        if width(&fits) <= room {
+            break;
+        }
+        fits[i] = Fit::Hidden;
+    }
+    for &i in order.iter().rev() {
+        if fits[i] == Fit::Short {
+            fits[i] = Fit::Full;
+            if width(&fits) > room {
+                fits[i] = Fit::Short;
+            }
+        }
+    }
+    fits
+}
+
+/// Which segments give way first in a narrow window
+fn segment_priority(segment: StatusSegment) -> u8 {
+    match segment {
+        StatusSegment::Shortcuts => 0,
+        StatusSegment::Version => 1,
+        StatusSegment::AppName => 2,
+        StatusSegment::Panes => 3,
+        StatusSegment::Update => 4,
+        StatusSegment::Dnd => 5,
+        StatusSegment::Context => 6,
+    }
+}
+
+/// Is `segment` drawn in the right-hand group?
+fn aligned_right(segment: StatusSegment) -> bool {
+    matches!(segment, StatusSegment::Update | StatusSegment::Version | StatusSegment::Dnd | StatusSegment::Context)
+}
+
+/// Watcher and git health shown in the context popover
+pub struct ContextStatus<'a> {
+    pub watcher: WatcherState,
+    pub git_enabled: bool,
+    /// Git status paused for this session
+    pub git_paused: bool,
+    /// Status of the active repository, `None` if not in a repo
+    pub repo: Option<&'a RepoStatus>,
+    pub last_refresh: Option<RefreshInfo>,
+    pub pinned_count: usize,
+    /// Background fetch state, `None` unless auto fetch is enabled
+    pub fetch: Option<FetchStatus>,
+}
+
+/// Overall context health (indicator color)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextHealth {
+    /// Watcher active and git repo found
+    Healthy,
+    /// Only one of the two is working
+    Partial,
+    /// Watcher and git integration are both disabled
+    Disabled,
+}
+
+impl ContextStatus<'_> {
+    pub fn health(&self) -> ContextHealth {
+        let watcher_on = self.watcher.enabled && !self.watcher.paused;
+        let git_on = self.git_enabled && !self.git_paused;
+        if !watcher_on && !git_on {
+            ContextHealth::Disabled
+        } else if self.watcher.active && self.repo.is_some() {
+            ContextHealth::Healthy
+        } else {
+            ContextHealth::Partial
+        }
+    }
+}
+
+/// "5m ago" style age of `time`, or "never"
+pub(super) fn format_ago(time: Option<SystemTime>, now: SystemTime) -> String {
+    let Some(time) = time else {
+        return "never".to_string();
+    };
+    let secs = now.duration_since(time).map(|d| d.as_secs()).unwrap_or(0);
+    match secs {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
 
 /// Status bar at the bottom of the window
 pub struct StatusBar<'a> {
     pane_count: usize,
     focused_pane: usize,
     theme: &'a RuntimeTheme,
+    /// Newer release version, if the update check found one
+    update_available: Option<&'a str>,
+    context: ContextStatus<'a>,
+    /// Leave out the shortcut hints and version (narrow windows)
+    compact: bool,
+    /// Height of the bar (px)
+    height: f32,
+    /// Do Not Disturb is on
+    dnd: bool,
+    /// Segments in the order drawn
+    segments: &'a [StatusSegment],
 }
 
 impl<'a> StatusBar<'a> {
-    pub fn new(pane_count: usize, focused_pane: usize, theme: &'a RuntimeTheme) -> Self {
+    pub fn new(
+        pane_count: usize,
+        focused_pane: usize,
+        theme: &'a RuntimeTheme,
+        update_available: Option<&'a str>,
+        context: ContextStatus<'a>,
+    ) -> Self {
         Self {
             pane_count,
             focused_pane,
             theme,
+            update_available,
+            context,
+            compact: false,
+            height: crate::theme::STATUS_BAR_HEIGHT,
+            dnd: false,
+            segments: &StatusSegment::ALL,
         }
     }
 
-    /// Show the status bar
-    pub fn show(&self, ui: &mut Ui) {
+    /// Segments to show, in order (`ui.status_segments`)
+    pub fn segments(mut self, segments: &'a [StatusSegment]) -> Self {
+        self.segments = segments;
+        self
+    }
+
+    /// Show the bell crossed out
+    pub fn dnd(mut self, dnd: bool) -> Self {
+        self.dnd = dnd;
+        self
+    }
+
+    /// Height of the bar; its text is centered in it
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Leave out the shortcut hints and version
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    /// Segments to draw, in order: configured, present and not left out by `compact`
+    fn present_segments(&self) -> Vec<StatusSegment> {
+        let mut present: Vec<StatusSegment> = Vec::new();
+        for &segment in self.segments {
+            let shown = match segment {
+                StatusSegment::Update => self.update_available.is_some(),
+                StatusSegment::Shortcuts | StatusSegment::Version => !self.compact,
+                _ => true,
+            };
+            if shown && !present.contains(&segment) {
+                present.push(segment);
+            }
+        }
+        present
+    }
+
+    /// Text of a plain segment at full and minimum width; `None` for buttons
+    fn segment_text(&self, segment: StatusSegment) -> Option<(String, String)> {
+        match segment {
+            StatusSegment::AppName => Some((" VibeTerm ".to_string(), " VT ".to_string())),
+            StatusSegment::Panes => {
+                let indicators: Vec<&str> = (0..self.pane_count)
+                    .map(|i| if i == self.focused_pane { tui::PANE_FOCUSED } else { tui::PANE_UNFOCUSED })
+                    .collect();
+                Some((
+                    format!("Panes: {} ", indicators.join(" ")),
+                    format!("{} {}/{} ", tui::PANE_FOCUSED, self.focused_pane + 1, self.pane_count),
+                ))
+            }
+            StatusSegment::Shortcuts => Some(("^D:Split ^W:Close ^Tab:Switch ".to_string(), "^D ^W ^Tab ".to_string())),
+            StatusSegment::Version => {
+                let version = format!(" v{} ", env!("CARGO_PKG_VERSION"));
+                Some((version.clone(), version))
+            }
+            StatusSegment::Update | StatusSegment::Dnd | StatusSegment::Context => None,
+        }
+    }
+
+    /// Widths `segment` needs, separator included
+    fn segment_size(&self, ui: &Ui, segment: StatusSegment) -> SegmentSize {
+        let width = |text: &str| {
+            ui.fonts(|fonts| fonts.layout_no_wrap(text.to_string(), ui_font(11.0), egui::Color32::WHITE).size().x)
+        };
+        let separator = width(tui::SEPARATOR);
+        let (preferred, min) = match (self.segment_text(segment), segment) {
+            (Some((full, short)), _) => (width(&full), width(&short)),
+            (None, StatusSegment::Update) => {
+                let version = self.update_available.unwrap_or_default();
+                (width(&format!(" v{} available ", version)), width(&format!(" v{} ", version)))
+            }
+            (None, StatusSegment::Dnd) => (width("🔔"), width("🔔")),
+            (None, _) => {
+                let stale = self.context.fetch.as_ref().is_some_and(|fetch| fetch.stale);
+                let dot = width(" ● ") + if stale { width("stale ") } else { 0.0 };
+                (dot, dot)
+            }
+        };
+        SegmentSize { priority: segment_priority(segment), min: min + separator, preferred: preferred + separator }
+    }
+
+    /// Show the status bar and return user actions
+    pub fn show(&self, ui: &mut Ui) -> StatusBarResponse {
+        let mut response = StatusBarResponse::default();
+
         Frame::NONE
             .fill(self.theme.surface)
             .show(ui, |ui| {
@@ -34,54 +288,411 @@ impl<'a> StatusBar<'a> {
                     egui::Stroke::new(1.0, self.theme.border),
                 );
 
-                ui.horizontal(|ui| {
+                let segments = self.present_segments();
+                let sizes: Vec<SegmentSize> = segments.iter().map(|&segment| self.segment_size(ui, segment)).collect();
+                let overflow_width = ui.fonts(|fonts| {
+                    fonts.layout_no_wrap(OVERFLOW_LABEL.to_string(), ui_font(11.0), egui::Color32::WHITE).size().x
+                });
+                let fits = fit_segments(&sizes, ui.available_width(), overflow_width);
+                let hidden: Vec<StatusSegment> = segments
+                    .iter()
+                    .zip(&fits)
+                    .filter(|(_, fit)| **fit == Fit::Hidden)
+                    .map(|(&segment, _)| segment)
+                    .collect();
+                let (left, right): (Vec<_>, Vec<_>) = segments
+                    .iter()
+                    .zip(&fits)
+                    .filter(|(_, fit)| **fit != Fit::Hidden)
+                    .map(|(&segment, &fit)| (segment, fit))
+                    .partition(|(segment, _)| !aligned_right(*segment));
+
+                let size = egui::vec2(ui.available_width(), self.height);
+                ui.allocate_ui_with_layout(size, egui::Layout::left_to_right(egui::Align::Center), |ui| {
                     ui.spacing_mut().item_spacing.x = 0.0;
 
-                    // App name
-                    ui.label(RichText::new(" VibeTerm ")
-                        .font(mono_font(11.0))
-                        .color(self.theme.primary));
-
-                    ui.label(RichText::new(tui::SEPARATOR)
-                        .font(mono_font(11.0))
-                        .color(self.theme.border));
-
-                    // Pane indicator with TUI symbols
-                    let pane_indicators: String = (0..self.pane_count)
-                        .map(|i| {
-                            if i == self.focused_pane {
-                                tui::PANE_FOCUSED
-                            } else {
-                                tui::PANE_UNFOCUSED
+                    for (i, &(segment, fit)) in left.iter().enumerate() {
+                        if i > 0 {
+                            self.separator(ui);
+                        }
+                        self.show_segment(ui, segment, fit, &mut response);
+                    }
+
+                    if !hidden.is_empty() {
+                        self.show_overflow(ui, &hidden, &mut response);
+                    }
+
+                    // Right-aligned segments, drawn from the right edge
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        for (i, &(segment, fit)) in right.iter().rev().enumerate() {
+                            if i > 0 {
+                                self.separator(ui);
+                            }
+                            self.show_segment(ui, segment, fit, &mut response);
+                        }
+                    });
+                });
+            });
+
+        response
+    }
+
+    fn separator(&self, ui: &mut Ui) {
+        ui.label(RichText::new(tui::SEPARATOR)
+            .font(ui_font(11.0))
+            .color(self.theme.border));
+    }
+
+    /// Draw one segment in the bar
+    fn show_segment(&self, ui: &mut Ui, segment: StatusSegment, fit: Fit, response: &mut StatusBarResponse) {
+        if let Some((full, short)) = self.segment_text(segment) {
+            let color = if segment == StatusSegment::AppName { self.theme.primary } else { self.theme.text_dim };
+            let label = ui.label(RichText::new(if fit == Fit::Short { &short } else { &full })
+                .font(ui_font(11.0))
+                .color(color));
+            if fit == Fit::Short {
+                label.on_hover_text(full.trim());
+            }
+            return;
+        }
+        match segment {
+            StatusSegment::Update => {
+                let version = self.update_available.unwrap_or_default();
+                let text = if fit == Fit::Short {
+                    format!(" v{} ", version)
+                } else {
+                    format!(" v{} available ", version)
+                };
+                let badge = Button::new(RichText::new(text).font(ui_font(11.0)).color(self.theme.background))
+                    .fill(self.theme.secondary)
+                    .frame(false);
+                if ui.add(badge).on_hover_text("Open the release page").clicked() {
+                    response.update_clicked = true;
+                }
+            }
+            StatusSegment::Dnd => {
+                let (bell, color, hover) = if self.dnd {
+                    ("🔕", self.theme.yellow, "Do Not Disturb is on\nClick to turn it off")
+                } else {
+                    ("🔔", self.theme.text_dim, "Click to turn on Do Not Disturb")
+                };
+                let bell = Button::new(RichText::new(bell).font(ui_font(11.0)).color(color)).frame(false);
+                if ui.add(bell).on_hover_text(hover).clicked() {
+                    response.dnd_clicked = true;
+                }
+            }
+            _ => self.show_context_indicator(ui, response),
+        }
+    }
+
+    /// "»" button listing the segments that didn't fit, in full
+    fn show_overflow(&self, ui: &mut Ui, hidden: &[StatusSegment], response: &mut StatusBarResponse) {
+        let button = ui.add(Button::new(RichText::new(OVERFLOW_LABEL).font(ui_font(11.0)).color(self.theme.text_dim)).frame(false))
+            .on_hover_text("More status");
+        let popup_id = ui.make_persistent_id("status_overflow_popover");
+        if button.clicked() {
+            ui.memory_mut(|mem| mem.toggle_popup(popup_id));
+        }
+
+        egui::popup::popup_above_or_below_widget(
+            ui,
+            popup_id,
+            &button,
+            egui::AboveOrBelow::Above,
+            egui::PopupCloseBehavior::CloseOnClickOutside,
+            |ui| {
+                ui.set_min_width(220.0);
+                for &segment in hidden {
+                    if let Some((full, _)) = self.segment_text(segment) {
+                        ui.label(RichText::new(full.trim()).font(ui_font(11.0)).color(self.theme.text));
+                        continue;
+                    }
+                    match segment {
+                        StatusSegment::Update => {
+                            let version = self.update_available.unwrap_or_default();
+                            if ui.button(format!("v{} available", version)).clicked() {
+                                response.update_clicked = true;
+                            }
+                        }
+                        StatusSegment::Dnd => {
+                            let text = if self.dnd { "Turn Off Do Not Disturb" } else { "Turn On Do Not Disturb" };
+                            if ui.button(text).clicked() {
+                                response.dnd_clicked = true;
                             }
-                        })
-                        .collect::<Vec<_>>()
-                        .join(" ");
+                        }
+                        _ => {
+                            ui.separator();
+                            self.show_context_details(ui, response);
+                        }
+                    }
+                }
+            },
+        );
+    }
 
-                    ui.label(RichText::new(format!("Panes: {} ", pane_indicators))
-                        .font(mono_font(11.0))
-                        .color(self.theme.text_dim));
+    /// Context health dot; clicking it toggles the details popover
+    fn show_context_indicator(&self, ui: &mut Ui, response: &mut StatusBarResponse) {
+        let color = match self.context.health() {
+            ContextHealth::Healthy => self.theme.green,
+            ContextHealth::Partial => self.theme.yellow,
+            ContextHealth::Disabled => self.theme.text_dim,
+        };
 
-                    ui.label(RichText::new(tui::SEPARATOR)
-                        .font(mono_font(11.0))
-                        .color(self.theme.border));
+        let dot = ui.add(
+            Button::new(RichText::new(" ● ").font(ui_font(11.0)).color(color))
+                .frame(false)
+        ).on_hover_text(if self.context.repo.is_some_and(|repo| repo.approximate) {
+            "Context status\nGit counts are approximate on this large repo"
+        } else {
+            "Context status"
+        });
 
-                    // Keyboard shortcuts
-                    ui.label(RichText::new("^D:Split ^W:Close ^Tab:Switch ")
-                        .font(mono_font(11.0))
-                        .color(self.theme.text_dim));
+        // Drawn right to left, so this lands just before the dot
+        if let Some(fetch) = self.context.fetch.as_ref().filter(|f| f.stale) {
+            ui.label(RichText::new("stale ").font(ui_font(11.0)).color(self.theme.yellow))
+                .on_hover_text(format!("Last fetch: {}", format_ago(fetch.last_fetch, SystemTime::now())));
+        }
 
-                    // Right-aligned version
-                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.label(RichText::new(format!(" v{} ", env!("CARGO_PKG_VERSION")))
-                            .font(mono_font(11.0))
-                            .color(self.theme.text_dim));
+        let popup_id = ui.make_persistent_id("context_status_popover");
+        if dot.clicked() {
+            ui.memory_mut(|mem| mem.toggle_popup(popup_id));
+        }
 
-                        ui.label(RichText::new(tui::SEPARATOR)
-                            .font(mono_font(11.0))
-                            .color(self.theme.border));
-                    });
-                });
+        egui::popup::popup_above_or_below_widget(
+            ui,
+            popup_id,
+            &dot,
+            egui::AboveOrBelow::Above,
+            egui::PopupCloseBehavior::CloseOnClickOutside,
+            |ui| {
+                ui.set_min_width(280.0);
+                self.show_context_details(ui, response);
+            },
+        );
+    }
+
+    /// Popover contents: watcher, git and pinned file details
+    fn show_context_details(&self, ui: &mut Ui, response: &mut StatusBarResponse) {
+        let theme = self.theme;
+        let context = &self.context;
+        let label = |ui: &mut Ui, text: &str| {
+            ui.label(RichText::new(text).font(ui_font(11.0)).color(theme.text_dim));
+        };
+        let value = |ui: &mut Ui, text: String, color: egui::Color32| {
+            ui.label(RichText::new(text).font(ui_font(11.0)).color(color));
+        };
+
+        egui::Grid::new("context_status_grid")
+            .num_columns(2)
+            .spacing([12.0, 4.0])
+            .show(ui, |ui| {
+                label(ui, "Watcher");
+                let watcher = &context.watcher;
+                if !watcher.enabled {
+                    value(ui, "disabled".to_string(), theme.text_dim);
+                } else if watcher.paused {
+                    value(ui, "paused".to_string(), theme.yellow);
+                } else if watcher.active {
+                    value(ui, format!("active ({} paths)", watcher.watched_paths), theme.green);
+                } else {
+                    value(ui, "inactive".to_string(), theme.yellow);
+                }
+                ui.end_row();
+
+                label(ui, "Last error");
+                match &watcher.last_error {
+                    Some(error) => value(ui, error.clone(), theme.red),
+                    None => value(ui, "none".to_string(), theme.text_dim),
+                }
+                ui.end_row();
+
+                label(ui, "Git");
+                if !context.git_enabled {
+                    value(ui, "disabled".to_string(), theme.text_dim);
+                } else if context.git_paused {
+                    value(ui, "paused".to_string(), theme.yellow);
+                } else if let Some(repo) = context.repo {
+                    let mut text = format!("{} ({})", repo.root.display(), repo.branch);
+                    if repo.ahead > 0 || repo.behind > 0 {
+                        text.push_str(&format!(" ↑{} ↓{}", repo.ahead, repo.behind));
+                    }
+                    value(ui, text, theme.text);
+                } else {
+                    value(ui, "not a repo".to_string(), theme.yellow);
+                }
+                ui.end_row();
+
+                if let Some(repo) = context.repo.filter(|_| context.git_enabled && !context.git_paused) {
+                    label(ui, "Changes");
+                    let counts = format!(
+                        "{} modified, {} staged, {} untracked",
+                        repo.modified_count, repo.staged_count, repo.untracked_count
+                    );
+                    if repo.approximate {
+                        ui.label(RichText::new(format!("~{}", counts)).font(ui_font(11.0)).color(theme.text_dim))
+                            .on_hover_text("Approximate: this repo is large, so only expanded folders and open files are refreshed often. Counts come from a slower full pass.");
+                    } else {
+                        value(ui, counts, theme.text);
+                    }
+                    ui.end_row();
+                }
+
+                label(ui, "Last refresh");
+                match context.last_refresh {
+                    Some(info) => value(
+                        ui,
+                        format!(
+                            "{}s ago, took {}ms{}",
+                            info.at.elapsed().as_secs(),
+                            info.duration.as_millis(),
+                            if info.scoped { " (scoped)" } else { "" }
+                        ),
+                        theme.text,
+                    ),
+                    None => value(ui, "never".to_string(), theme.text_dim),
+                }
+                ui.end_row();
+
+                if let Some(fetch) = &context.fetch {
+                    label(ui, "Last fetch");
+                    let ago = format_ago(fetch.last_fetch, SystemTime::now());
+                    if fetch.in_flight {
+                        value(ui, format!("{}, fetching…", ago), theme.text);
+                    } else if let Some(error) = &fetch.last_error {
+                        value(ui, format!("{}, failed: {}", ago, error), theme.red);
+                    } else {
+                        value(ui, ago, if fetch.stale { theme.yellow } else { theme.text });
+                    }
+                    ui.end_row();
+                }
+
+                label(ui, "Pinned files");
+                value(ui, context.pinned_count.to_string(), theme.text);
+                ui.end_row();
             });
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.add_enabled(context.git_enabled && !context.git_paused, Button::new("Refresh Git Now")).clicked() {
+                response.refresh_git = true;
+            }
+            if let Some(fetch) = &context.fetch {
+                if ui.add_enabled(!fetch.in_flight, Button::new("Fetch Now")).clicked() {
+                    response.fetch_now = true;
+                }
+            }
+            if ui.add_enabled(context.watcher.enabled && !context.watcher.paused, Button::new("Restart Watcher")).clicked() {
+                response.restart_watcher = true;
+            }
+        });
+    }
+}
+
+/// Response from status bar interaction
+#[derive(Debug, Default)]
+pub struct StatusBarResponse {
+    /// Update badge was clicked
+    pub update_clicked: bool,
+    /// "Refresh Git Now" clicked in the context popover
+    pub refresh_git: bool,
+    /// "Fetch Now" clicked in the context popover
+    pub fetch_now: bool,
+    /// "Restart Watcher" clicked in the context popover
+    pub restart_watcher: bool,
+    /// Bell icon clicked (toggle Do Not Disturb)
+    pub dnd_clicked: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(watcher_enabled: bool, watcher_active: bool, git_enabled: bool, repo: Option<&RepoStatus>) -> ContextStatus<'_> {
+        ContextStatus {
+            watcher: WatcherState {
+                enabled: watcher_enabled,
+                active: watcher_active,
+                ..WatcherState::default()
+            },
+            git_enabled,
+            git_paused: false,
+            repo,
+            last_refresh: None,
+            pinned_count: 0,
+            fetch: None,
+        }
+    }
+
+    #[test]
+    fn test_context_health() {
+        let repo = RepoStatus::default();
+        assert_eq!(status(true, true, true, Some(&repo)).health(), ContextHealth::Healthy);
+        assert_eq!(status(true, true, true, None).health(), ContextHealth::Partial);
+        assert_eq!(status(true, false, true, Some(&repo)).health(), ContextHealth::Partial);
+        assert_eq!(status(false, false, false, None).health(), ContextHealth::Disabled);
+
+        // Paused subsystems count as off
+        let mut paused = status(true, false, true, Some(&repo));
+        paused.watcher.paused = true;
+        assert_eq!(paused.health(), ContextHealth::Partial);
+        paused.git_paused = true;
+        assert_eq!(paused.health(), ContextHealth::Disabled);
+    }
+
+    #[test]
+    fn test_format_ago() {
+        let now = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        let ago = |secs| Some(now - std::time::Duration::from_secs(secs));
+        assert_eq!(format_ago(None, now), "never");
+        assert_eq!(format_ago(ago(5), now), "just now");
+        assert_eq!(format_ago(ago(300), now), "5m ago");
+        assert_eq!(format_ago(ago(7200), now), "2h ago");
+        assert_eq!(format_ago(ago(3 * 86400), now), "3d ago");
+        // Clock skew reads as just now
+        assert_eq!(format_ago(Some(now + std::time::Duration::from_secs(60)), now), "just now");
+    }
+
+    #[test]
+    fn test_fit_segments() {
+        use Fit::{Full, Hidden, Short};
+        let size = |priority, min, preferred| SegmentSize { priority, min, preferred };
+        let segments = [size(0, 10.0, 30.0), size(2, 20.0, 20.0), size(1, 5.0, 15.0)];
+        // Available width, overflow button width, expected
+        let cases: [(f32, f32, [Fit; 3]); 7] = [
+            (100.0, 4.0, [Full, Full, Full]),
+            (65.0, 4.0, [Full, Full, Full]),
+            // The lowest priority shrinks first, then the next
+            (60.0, 4.0, [Short, Full, Full]),
+            (40.0, 4.0, [Short, Full, Short]),
+            (35.0, 4.0, [Short, Full, Short]),
+            // Folding the lowest leaves no room to grow the other back
+            (34.0, 4.0, [Hidden, Full, Short]),
+            (10.0, 4.0, [Hidden, Hidden, Hidden]),
+        ];
+        for (available, overflow, expected) in cases {
+            assert_eq!(fit_segments(&segments, available, overflow), expected, "{} px", available);
+            // Same input, same layout
+            assert_eq!(fit_segments(&segments, available, overflow), fit_segments(&segments, available, overflow));
+        }
+
+        // Once something folds, shrunk segments with room grow back
+        let segments = [size(0, 50.0, 50.0), size(1, 10.0, 30.0)];
+        assert_eq!(fit_segments(&segments, 50.0, 5.0), [Hidden, Full]);
+
+        // Of equal priorities the later one gives way first
+        let segments = [size(1, 10.0, 20.0), size(1, 10.0, 20.0)];
+        assert_eq!(fit_segments(&segments, 30.0, 5.0), [Full, Short]);
+        assert_eq!(fit_segments(&segments, 15.0, 5.0), [Short, Hidden]);
+
+        assert!(fit_segments(&[], 0.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn test_segment_priorities_are_distinct() {
+        let mut priorities: Vec<u8> = StatusSegment::ALL.iter().map(|&segment| segment_priority(segment)).collect();
+        priorities.sort_unstable();
+        priorities.dedup();
+        assert_eq!(priorities.len(), StatusSegment::ALL.len());
     }
 }
@@ -2,22 +2,142 @@
 //!
 //! TUI-style workspace tabs using box-drawing characters
 
-use egui::{Button, Frame, PointerButton, RichText, Ui};
-use crate::config::RuntimeTheme;
-use crate::theme::{tui, mono_font};
+use std::time::{Duration, Instant};
+use egui::{Button, Color32, Frame, PointerButton, RichText, Ui};
+use crate::config::{NewTabPosition, RuntimeTheme};
+use crate::progress::{Progress, ProgressState};
+use crate::theme::{tui, ui_font};
+
+/// Output this recent lights the activity strip in full
+pub const ACTIVITY_FRESH: Duration = Duration::from_secs(5);
+
+/// By this age the strip has faded from primary to dim text color
+const ACTIVITY_DIM: Duration = Duration::from_secs(60);
+
+/// By this age the strip is gone
+pub const ACTIVITY_FADED: Duration = Duration::from_secs(5 * 60);
+
+/// Opacity of activity strips while muted
+const MUTED_ACTIVITY: f32 = 0.35;
+
+/// Width of a pinned tab's chip (px, before UI scale)
+const PINNED_TAB_WIDTH: f32 = 32.0;
+
+/// Time for an indeterminate progress line to fade out and back in (s)
+const PROGRESS_PULSE_PERIOD: f64 = 1.2;
+
+/// Index a tab moved from `from` toward `to` may take
+///
+/// Pinned tabs stay ahead of the others, so neither kind crosses the
+/// boundary of the pinned group.
+pub fn clamp_tab_move(pinned: &[bool], from: usize, to: usize) -> usize {
+    let pinned_count = pinned.iter().filter(|&&pinned| pinned).count();
+    if pinned[from] {
+        to.min(pinned_count - 1)
+    } else {
+        to.clamp(pinned_count, pinned.len() - 1)
+    }
+}
+
+/// Index a new tab takes among `len` tabs, never inside the pinned group
+pub fn new_tab_index(position: NewTabPosition, active: usize, len: usize, pinned_count: usize) -> usize {
+    match position {
+        NewTabPosition::End => len,
+        NewTabPosition::AfterCurrent => (active + 1).clamp(pinned_count.min(len), len),
+    }
+}
+
+/// Index the tab at `index` has once a new tab is inserted at `at`
+pub fn index_after_insert(index: usize, at: usize) -> usize {
+    if index >= at {
+        index + 1
+    } else {
+        index
+    }
+}
+
+/// Index the tab at `index` has once the tab at `from` moves to `to`
+pub fn index_after_move(index: usize, from: usize, to: usize) -> usize {
+    if index == from {
+        return to;
+    }
+    let after_removal = if index > from { index - 1 } else { index };
+    index_after_insert(after_removal, to)
+}
+
+/// Activity strip color for output `age` old
+pub fn activity_color(age: Duration, theme: &RuntimeTheme) -> Color32 {
+    let progress = |from: Duration, to: Duration| {
+        (age.saturating_sub(from).as_secs_f32() / (to - from).as_secs_f32()).min(1.0)
+    };
+    if age <= ACTIVITY_FRESH {
+        theme.primary
+    } else if age < ACTIVITY_DIM {
+        theme.primary.lerp_to_gamma(theme.text_dim, progress(ACTIVITY_FRESH, ACTIVITY_DIM))
+    } else if age < ACTIVITY_FADED {
+        theme.text_dim.gamma_multiply(1.0 - progress(ACTIVITY_DIM, ACTIVITY_FADED))
+    } else {
+        Color32::TRANSPARENT
+    }
+}
+
+/// Color of a progress line at `time` (s): primary, red for an error and
+/// yellow while paused; indeterminate progress pulses
+pub fn progress_color(progress: Progress, theme: &RuntimeTheme, time: f64) -> Color32 {
+    match progress.state {
+        ProgressState::Normal => theme.primary,
+        ProgressState::Error => theme.red,
+        ProgressState::Paused => theme.yellow,
+        ProgressState::Indeterminate => {
+            let phase = (time / PROGRESS_PULSE_PERIOD).fract() * std::f64::consts::TAU;
+            theme.primary.gamma_multiply(0.3 + 0.35 * (1.0 + phase.cos() as f32))
+        }
+    }
+}
+
+/// "12 s ago" style age of the last output
+pub fn activity_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => format!("{} s ago", secs),
+        60..3600 => format!("{} m ago", secs / 60),
+        _ => format!("{} h ago", secs / 3600),
+    }
+}
 
 /// Tab bar with TUI aesthetic
 pub struct TabBar<'a> {
     tabs: &'a [TabInfo],
     active_tab: usize,
     theme: &'a RuntimeTheme,
+    /// Show only tab numbers (narrow windows)
+    compact: bool,
+    /// Current time, when the activity strip is shown
+    activity_now: Option<Instant>,
+    /// Extra hover content for a tab
+    hover_details: Option<HoverDetails<'a>>,
+    /// Height of the bar (px)
+    height: f32,
+    /// Draw activity strips faintly (Do Not Disturb)
+    muted: bool,
 }
 
+/// Draws extra hover content for the tab at an index
+type HoverDetails<'a> = &'a dyn Fn(usize, &mut Ui);
+
 /// Information about a tab
 #[derive(Debug, Clone)]
 pub struct TabInfo {
     pub name: String,
     pub is_modified: bool,
+    /// Latest output of any terminal in the tab
+    pub last_output: Option<Instant>,
+    /// Drawn as a compact chip ahead of the other tabs
+    pub pinned: bool,
+    /// Its shells haven't started yet
+    pub dormant: bool,
+    /// Progress its programs reported
+    pub progress: Option<Progress>,
 }
 
 impl TabInfo {
@@ -25,8 +145,32 @@ impl TabInfo {
         Self {
             name: name.into(),
             is_modified: false,
+            last_output: None,
+            pinned: false,
+            dormant: false,
+            progress: None,
         }
     }
+
+    pub fn dormant(mut self, dormant: bool) -> Self {
+        self.dormant = dormant;
+        self
+    }
+
+    pub fn pinned(mut self, pinned: bool) -> Self {
+        self.pinned = pinned;
+        self
+    }
+
+    pub fn last_output(mut self, last_output: Option<Instant>) -> Self {
+        self.last_output = last_output;
+        self
+    }
+
+    pub fn progress(mut self, progress: Option<Progress>) -> Self {
+        self.progress = progress;
+        self
+    }
 }
 
 impl<'a> TabBar<'a> {
@@ -35,9 +179,44 @@ impl<'a> TabBar<'a> {
             tabs,
             active_tab,
             theme,
+            compact: false,
+            activity_now: None,
+            hover_details: None,
+            height: crate::theme::TAB_BAR_HEIGHT,
+            muted: false,
         }
     }
 
+    /// Keep showing activity, but faintly
+    pub fn muted(mut self, muted: bool) -> Self {
+        self.muted = muted;
+        self
+    }
+
+    /// Height of the bar; tabs fill it, so their underline sits at its bottom
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Show only tab numbers, with names on hover
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    /// Draw the activity strip under each tab, aged against `now`
+    pub fn activity(mut self, now: Option<Instant>) -> Self {
+        self.activity_now = now;
+        self
+    }
+
+    /// Add content below the tab's hover text, given the tab index
+    pub fn hover_details(mut self, details: HoverDetails<'a>) -> Self {
+        self.hover_details = Some(details);
+        self
+    }
+
     /// Show the tab bar and return user actions
     pub fn show(&self, ui: &mut Ui) -> TabBarResponse {
         let mut response = TabBarResponse::default();
@@ -46,7 +225,8 @@ impl<'a> TabBar<'a> {
         Frame::NONE
             .fill(self.theme.surface)
             .show(ui, |ui| {
-                ui.horizontal(|ui| {
+                let size = egui::vec2(ui.available_width(), self.height);
+                ui.allocate_ui_with_layout(size, egui::Layout::left_to_right(egui::Align::Center), |ui| {
                     ui.spacing_mut().item_spacing.x = 0.0;
 
                     // Track tab rectangles for drag-and-drop
@@ -57,7 +237,7 @@ impl<'a> TabBar<'a> {
                         let is_active = idx == self.active_tab;
 
                         // Tab number (1-9 for keyboard shortcuts)
-                        let number = if idx < 9 {
+                        let number = if idx < 9 || self.compact || tab.pinned {
                             format!("{}", idx + 1)
                         } else {
                             " ".to_string()
@@ -66,7 +246,12 @@ impl<'a> TabBar<'a> {
                         // Tab text with TUI indicators
                         let indicator = if is_active { tui::TAB_ACTIVE } else { tui::TAB_INACTIVE };
                         let modified = if tab.is_modified { tui::TAB_MODIFIED } else { "" };
-                        let text = format!(" {}{} {}{} ", indicator, number, tab.name, modified);
+                        let sleeping = if tab.dormant { " zzz" } else { "" };
+                        let text = if self.compact || tab.pinned {
+                            format!(" {}{}{} ", indicator, number, modified)
+                        } else {
+                            format!(" {}{} {}{}{} ", indicator, number, tab.name, modified, sleeping)
+                        };
 
                         let text_color = if is_active {
                             self.theme.text
@@ -81,11 +266,26 @@ impl<'a> TabBar<'a> {
                         };
 
                         // Create clickable tab button
-                        let tab_btn = Button::new(RichText::new(&text).font(mono_font(12.0)).color(text_color))
+                        let tab_btn = Button::new(RichText::new(&text).font(ui_font(12.0)).color(text_color))
                             .fill(bg_color)
-                            .frame(false);
+                            .frame(false)
+                            .min_size(egui::vec2(if tab.pinned { crate::theme::ui_scaled(PINNED_TAB_WIDTH) } else { 0.0 }, self.height));
 
-                        let tab_response = ui.add(tab_btn);
+                        let mut tab_response = ui.add(tab_btn);
+                        let names_hidden = self.compact || tab.pinned;
+                        if names_hidden || tab.dormant || self.hover_details.is_some() {
+                            tab_response = tab_response.on_hover_ui(|ui| {
+                                if names_hidden {
+                                    ui.label(&tab.name);
+                                }
+                                if tab.dormant {
+                                    ui.label("Not started yet: its shells start when you open it");
+                                }
+                                if let Some(details) = self.hover_details {
+                                    details(idx, ui);
+                                }
+                            });
+                        }
 
                         // Store tab rectangle for drag detection
                         tab_rects.push((idx, tab_response.rect));
@@ -99,6 +299,26 @@ impl<'a> TabBar<'a> {
                             }
                         }
 
+                        // Activity strip (the active tab's indicator covers it)
+                        if let (Some(now), Some(last_output)) = (self.activity_now, tab.last_output) {
+                            let mut color = activity_color(now.saturating_duration_since(last_output), self.theme);
+                            if self.muted {
+                                color = color.gamma_multiply(MUTED_ACTIVITY);
+                            }
+                            if color != Color32::TRANSPARENT && tab.pinned {
+                                // Chips are too narrow for a strip to read
+                                let rect = tab_response.rect;
+                                ui.painter().circle_filled(egui::pos2(rect.right() - 5.0, rect.top() + 6.0), 2.5, color);
+                            } else if color != Color32::TRANSPARENT {
+                                let rect = tab_response.rect;
+                                let strip_rect = egui::Rect::from_min_max(
+                                    egui::pos2(rect.left(), rect.bottom() - 2.0),
+                                    rect.right_bottom(),
+                                );
+                                ui.painter().rect_filled(strip_rect, 0.0, color);
+                            }
+                        }
+
                         // Active tab bottom indicator
                         if is_active {
                             let rect = tab_response.rect;
@@ -109,22 +329,49 @@ impl<'a> TabBar<'a> {
                             ui.painter().rect_filled(indicator_rect, 0.0, self.theme.primary);
                         }
 
+                        // Progress line, above the strip and the indicator
+                        if let Some(progress) = tab.progress {
+                            let rect = tab_response.rect;
+                            let fraction = progress.percent.map_or(1.0, |percent| percent as f32 / 100.0);
+                            let line_rect = egui::Rect::from_min_size(
+                                egui::pos2(rect.left(), rect.bottom() - 4.0),
+                                egui::vec2(rect.width() * fraction, 2.0),
+                            );
+                            let color = progress_color(progress, self.theme, ui.input(|i| i.time));
+                            ui.painter().rect_filled(line_rect, 0.0, color);
+                        }
+
                         // Handle clicks - use clicked() for left click
                         if tab_response.clicked() {
                             response.selected_tab = Some(idx);
                         }
 
-                        // Middle-click to close
+                        // Middle-click to close, except pinned tabs
                         if tab_response.clicked_by(PointerButton::Middle) {
-                            response.closed_tab = Some(idx);
+                            if tab.pinned {
+                                response.close_refused = Some(idx);
+                            } else {
+                                response.closed_tab = Some(idx);
+                            }
                         }
 
+                        tab_response.context_menu(|ui| {
+                            if ui.button(if tab.pinned { "Unpin Tab" } else { "Pin Tab" }).clicked() {
+                                response.toggled_pin = Some(idx);
+                                ui.close_menu();
+                            }
+                            if ui.button("Close Tab").clicked() {
+                                response.closed_tab = Some(idx);
+                                ui.close_menu();
+                            }
+                        });
+
                         // Separator between tabs
-                        ui.label(RichText::new(format!("{}", tui::VERTICAL)).font(mono_font(12.0)).color(self.theme.border));
+                        ui.label(RichText::new(format!("{}", tui::VERTICAL)).font(ui_font(12.0)).color(self.theme.border));
                     }
 
                     // New tab button [+]
-                    let plus_btn = Button::new(RichText::new(" + ").font(mono_font(12.0)).color(self.theme.text_dim))
+                    let plus_btn = Button::new(RichText::new(" + ").font(ui_font(12.0)).color(self.theme.text_dim))
                         .fill(self.theme.surface)
                         .frame(false);
 
@@ -135,7 +382,7 @@ impl<'a> TabBar<'a> {
                     // Fill remaining space
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         // Right side info (optional)
-                        ui.label(RichText::new(format!("{}", tui::VERTICAL)).font(mono_font(12.0)).color(self.theme.border));
+                        ui.label(RichText::new(format!("{}", tui::VERTICAL)).font(ui_font(12.0)).color(self.theme.border));
                     });
 
                     // Store tab rectangles in response
@@ -159,7 +406,104 @@ impl<'a> TabBar<'a> {
 pub struct TabBarResponse {
     pub selected_tab: Option<usize>,
     pub closed_tab: Option<usize>,
+    /// Pinned tab middle-clicked; those close from their context menu only
+    pub close_refused: Option<usize>,
+    /// Tab to pin or unpin
+    pub toggled_pin: Option<usize>,
     pub new_tab_requested: bool,
     pub tab_rects: Vec<(usize, egui::Rect)>,
     pub tab_hovered: Option<usize>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ThemeConfig;
+
+    #[test]
+    fn test_activity_color_fades() {
+        let theme = RuntimeTheme::from(&ThemeConfig::default());
+        let secs = Duration::from_secs;
+
+        assert_eq!(activity_color(Duration::ZERO, &theme), theme.primary);
+        assert_eq!(activity_color(ACTIVITY_FRESH, &theme), theme.primary);
+        assert_eq!(activity_color(ACTIVITY_DIM, &theme), theme.text_dim);
+        assert_eq!(activity_color(ACTIVITY_FADED, &theme), Color32::TRANSPARENT);
+        assert_eq!(activity_color(secs(3600), &theme), Color32::TRANSPARENT);
+
+        // Alpha only goes down with age
+        let alphas: Vec<u8> = (0..=300).step_by(10).map(|s| activity_color(secs(s), &theme).a()).collect();
+        assert!(alphas.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", alphas);
+        let midway = activity_color(secs(30), &theme);
+        assert!(midway != theme.primary && midway != theme.text_dim);
+    }
+
+    #[test]
+    fn test_progress_color_follows_state() {
+        let theme = RuntimeTheme::from(&ThemeConfig::default());
+        let progress = |state| Progress { state, percent: Some(40) };
+        assert_eq!(progress_color(progress(ProgressState::Normal), &theme, 0.0), theme.primary);
+        assert_eq!(progress_color(progress(ProgressState::Error), &theme, 0.0), theme.red);
+        assert_eq!(progress_color(progress(ProgressState::Paused), &theme, 0.0), theme.yellow);
+
+        // Indeterminate progress is brightest at the start of a pulse
+        let pulse = |time| progress_color(Progress { state: ProgressState::Indeterminate, percent: None }, &theme, time);
+        assert_eq!(pulse(0.0), pulse(PROGRESS_PULSE_PERIOD));
+        assert!(pulse(PROGRESS_PULSE_PERIOD / 2.0).a() < pulse(0.0).a());
+    }
+
+    #[test]
+    fn test_clamp_tab_move_keeps_pinned_group() {
+        let pinned = [true, true, false, false, false];
+        // Pinned tabs reorder among themselves only
+        assert_eq!(clamp_tab_move(&pinned, 0, 1), 1);
+        assert_eq!(clamp_tab_move(&pinned, 0, 4), 1);
+        assert_eq!(clamp_tab_move(&pinned, 1, 0), 0);
+        // Others can't pass in front of them
+        assert_eq!(clamp_tab_move(&pinned, 4, 0), 2);
+        assert_eq!(clamp_tab_move(&pinned, 2, 4), 4);
+        assert_eq!(clamp_tab_move(&pinned, 3, 2), 2);
+
+        // Pinning a tab moves it to the end of the group, unpinning to the start of the rest
+        assert_eq!(clamp_tab_move(&[true, false, true, false], 2, 1), 1);
+        assert_eq!(clamp_tab_move(&[false, true, false], 0, 1), 1);
+        assert_eq!(clamp_tab_move(&[false, false], 1, 0), 0);
+        assert_eq!(clamp_tab_move(&[true], 0, 3), 0);
+    }
+
+    #[test]
+    fn test_new_tab_index_after_current_skips_pinned() {
+        assert_eq!(new_tab_index(NewTabPosition::End, 2, 12, 0), 12);
+        assert_eq!(new_tab_index(NewTabPosition::AfterCurrent, 2, 12, 0), 3);
+        assert_eq!(new_tab_index(NewTabPosition::AfterCurrent, 11, 12, 0), 12);
+        // From a pinned tab, the new one goes right after the group
+        assert_eq!(new_tab_index(NewTabPosition::AfterCurrent, 0, 5, 3), 3);
+        assert_eq!(new_tab_index(NewTabPosition::AfterCurrent, 0, 2, 2), 2);
+    }
+
+    #[test]
+    fn test_indices_follow_inserts_and_moves() {
+        // Tabs at or past the insertion point shift right
+        assert_eq!(index_after_insert(2, 3), 2);
+        assert_eq!(index_after_insert(3, 3), 4);
+        assert_eq!(index_after_insert(5, 3), 6);
+
+        // The moved tab lands on `to`; those it passes shift toward `from`
+        assert_eq!(index_after_move(1, 1, 4), 4);
+        assert_eq!(index_after_move(3, 1, 4), 2);
+        assert_eq!(index_after_move(4, 1, 4), 3);
+        assert_eq!(index_after_move(5, 1, 4), 5);
+        assert_eq!(index_after_move(2, 4, 1), 3);
+        assert_eq!(index_after_move(1, 4, 1), 2);
+        assert_eq!(index_after_move(0, 4, 1), 0);
+        assert_eq!(index_after_move(3, 3, 3), 3);
+    }
+
+    #[test]
+    fn test_activity_age() {
+        assert_eq!(activity_age(Duration::from_millis(400)), "0 s ago");
+        assert_eq!(activity_age(Duration::from_secs(12)), "12 s ago");
+        assert_eq!(activity_age(Duration::from_secs(200)), "3 m ago");
+        assert_eq!(activity_age(Duration::from_secs(7300)), "2 h ago");
+    }
+}
@@ -0,0 +1,195 @@
+//! Opt-in update check against GitHub releases
+//!
+//! Fetches the latest release tag at most once per day and compares it with
+//! the running version. No telemetry is sent and nothing is downloaded; the
+//! result is only used to show a badge in the status bar.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+
+/// GitHub API endpoint for the latest release
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/0113bernoyoun/vibeterm/releases/latest";
+
+/// Release page opened when the update badge is clicked
+pub const RELEASES_PAGE_URL: &str = "https://github.com/0113bernoyoun/vibeterm/releases/latest";
+
+/// Minimum time between two network checks
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Network timeout for the release request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Persisted result of the last update check
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateState {
+    /// Unix timestamp (seconds) of the last successful check
+    pub last_checked: u64,
+    /// Latest release version seen at that time (without leading 'v')
+    pub latest_version: Option<String>,
+}
+
+impl UpdateState {
+    /// Get state file path
+    pub fn state_path() -> PathBuf {
+        Config::config_dir().join("update_state.toml")
+    }
+
+    /// Load state from disk, falling back to an empty state
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::state_path())
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save state to disk
+    pub fn save(&self) -> Result<(), String> {
+        std::fs::create_dir_all(Config::config_dir())
+            .map_err(|e| format!("Failed to create config dir: {}", e))?;
+
+        let toml_string = toml::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize update state: {}", e))?;
+
+        std::fs::write(Self::state_path(), toml_string)
+            .map_err(|e| format!("Failed to write update state: {}", e))
+    }
+
+    /// Newer version recorded by the last check, if any
+    pub fn available_update(&self) -> Option<&str> {
+        self.latest_version
+            .as_deref()
+            .filter(|latest| is_newer(latest, env!("CARGO_PKG_VERSION")))
+    }
+}
+
+/// Current Unix time in seconds
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Should a network check run now?
+///
+/// Returns true if no check has happened yet or `interval` has passed.
+/// A timestamp in the future (clock skew) also triggers a check.
+pub fn should_check_now(last_checked: u64, now: u64, interval: Duration) -> bool {
+    if last_checked == 0 || last_checked > now {
+        return true;
+    }
+    now - last_checked >= interval.as_secs()
+}
+
+/// Parse a release tag like "v0.9.0" into a semver version
+fn parse_version(tag: &str) -> Option<semver::Version> {
+    let trimmed = tag.trim();
+    let trimmed = trimmed.strip_prefix('v').unwrap_or(trimmed);
+    semver::Version::parse(trimmed).ok()
+}
+
+/// Is `latest` a newer version than `current`?
+///
+/// Unparseable versions are never considered newer.
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+#[derive(Deserialize)]
+struct ReleaseResponse {
+    tag_name: String,
+}
+
+/// Fetch the latest release version from GitHub (blocking)
+fn fetch_latest_version() -> anyhow::Result<String> {
+    let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+    let release: ReleaseResponse = agent
+        .get(LATEST_RELEASE_URL)
+        .set("User-Agent", concat!("vibeterm/", env!("CARGO_PKG_VERSION")))
+        .set("Accept", "application/vnd.github+json")
+        .call()?
+        .into_json()?;
+
+    let tag = release.tag_name.trim();
+    Ok(tag.strip_prefix('v').unwrap_or(tag).to_string())
+}
+
+/// Run the update check on the tokio runtime
+///
+/// Sends the latest version to `tx` if it is newer than the running one.
+/// Network failures are logged at debug level only.
+pub fn spawn_check(
+    runtime: &tokio::runtime::Runtime,
+    tx: tokio::sync::mpsc::UnboundedSender<String>,
+) {
+    runtime.spawn(async move {
+        let result = tokio::task::spawn_blocking(fetch_latest_version).await;
+
+        match result {
+            Ok(Ok(latest)) => {
+                let state = UpdateState {
+                    last_checked: now_secs(),
+                    latest_version: Some(latest.clone()),
+                };
+                if let Err(e) = state.save() {
+                    log::debug!("Failed to save update state: {}", e);
+                }
+
+                if is_newer(&latest, env!("CARGO_PKG_VERSION")) {
+                    log::info!("Update available: v{}", latest);
+                    let _ = tx.send(latest);
+                }
+            }
+            Ok(Err(e)) => log::debug!("Update check failed: {}", e),
+            Err(e) => log::debug!("Update check task failed: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_comparison() {
+        assert!(is_newer("v0.9.0", "0.7.0"));
+        assert!(is_newer("0.7.1", "0.7.0"));
+        assert!(is_newer("1.0.0", "0.99.99"));
+        assert!(!is_newer("v0.7.0", "0.7.0"));
+        assert!(!is_newer("0.6.9", "0.7.0"));
+        // Pre-releases sort below the release
+        assert!(!is_newer("0.7.0-beta.1", "0.7.0"));
+        assert!(is_newer("0.8.0-beta.1", "0.7.0"));
+    }
+
+    #[test]
+    fn test_invalid_versions_are_not_newer() {
+        assert!(!is_newer("nightly", "0.7.0"));
+        assert!(!is_newer("", "0.7.0"));
+        assert!(!is_newer("0.9.0", "garbage"));
+    }
+
+    #[test]
+    fn test_should_check_now() {
+        let day = CHECK_INTERVAL;
+        let now = 1_700_000_000;
+
+        // Never checked
+        assert!(should_check_now(0, now, day));
+        // Checked an hour ago
+        assert!(!should_check_now(now - 3600, now, day));
+        // Checked exactly one interval ago
+        assert!(should_check_now(now - day.as_secs(), now, day));
+        // Checked long ago
+        assert!(should_check_now(now - 3 * day.as_secs(), now, day));
+        // Clock went backwards
+        assert!(should_check_now(now + 3600, now, day));
+    }
+}
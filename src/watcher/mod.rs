@@ -37,7 +37,7 @@
 //!
 //! ```ignore
 //! use vibeterm::watcher::{FileWatcherService, WatcherConfig};
-//! use std::time::Duration;
+//! use std::time::{Duration, Instant};
 //! use std::path::Path;
 //!
 //! // Create with custom config
@@ -53,7 +53,7 @@
 //! watcher.watch(Path::new("/path/to/project"))?;
 //!
 //! // Poll for events in your event loop
-//! for event in watcher.poll() {
+//! for event in watcher.poll(Instant::now()) {
 //!     println!("File system event: {:?}", event);
 //! }
 //! ```
@@ -8,7 +8,7 @@ use notify::{
     RecommendedWatcher,
     RecursiveMode,
     Watcher,
-    event::{CreateKind, ModifyKind, RemoveKind, RenameMode},
+    event::{ModifyKind, RenameMode},
 };
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
@@ -32,6 +32,31 @@ pub enum WatcherEvent {
     Error(String),
 }
 
+impl WatcherEvent {
+    /// Directory whose listing is affected by this event
+    ///
+    /// Creations, deletions and renames change the parent's listing. For
+    /// other changes the path itself is used if it is an existing directory
+    /// (e.g. FSEvents reporting a metadata change on the parent), otherwise
+    /// its parent.
+    pub fn affected_dir(&self) -> Option<PathBuf> {
+        let path = match self {
+            WatcherEvent::Created(p) |
+            WatcherEvent::Deleted(p) |
+            WatcherEvent::Renamed(_, p) => return p.parent().map(Path::to_path_buf),
+            WatcherEvent::Modified(p) |
+            WatcherEvent::Changed(p) => p,
+            WatcherEvent::Error(_) => return None,
+        };
+
+        if path.is_dir() {
+            Some(path.clone())
+        } else {
+            path.parent().map(Path::to_path_buf)
+        }
+    }
+}
+
 /// Configuration for the file watcher
 #[derive(Debug, Clone)]
 pub struct WatcherConfig {
@@ -149,18 +174,32 @@ impl FileWatcherService {
         }
 
         match event.kind {
-            notify::EventKind::Create(CreateKind::File) |
-            notify::EventKind::Create(CreateKind::Folder) => {
+            // FSEvents often reports Create(Any)/Remove(Any), so match every kind
+            notify::EventKind::Create(_) => {
                 Some(WatcherEvent::Created(path))
             }
             notify::EventKind::Modify(ModifyKind::Data(_)) |
             notify::EventKind::Modify(ModifyKind::Metadata(_)) => {
                 Some(WatcherEvent::Modified(path))
             }
-            notify::EventKind::Remove(RemoveKind::File) |
-            notify::EventKind::Remove(RemoveKind::Folder) => {
+            notify::EventKind::Remove(_) => {
+                Some(WatcherEvent::Deleted(path))
+            }
+            notify::EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
                 Some(WatcherEvent::Deleted(path))
             }
+            notify::EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                Some(WatcherEvent::Created(path))
+            }
+            // Unpaired rename (FSEvents): whether it's the old or new name
+            // depends on whether the path still exists
+            notify::EventKind::Modify(ModifyKind::Name(RenameMode::Any)) => {
+                if path.exists() {
+                    Some(WatcherEvent::Created(path))
+                } else {
+                    Some(WatcherEvent::Deleted(path))
+                }
+            }
             notify::EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
                 if event.paths.len() >= 2 {
                     Some(WatcherEvent::Renamed(
@@ -177,10 +216,9 @@ impl FileWatcherService {
 
     /// Poll for events (non-blocking)
     ///
-    /// Returns debounced events - call this every frame
-    pub fn poll(&mut self) -> Vec<WatcherEvent> {
-        let now = Instant::now();
-
+    /// Returns debounced events - call this every frame. Debouncing is
+    /// measured against `now`.
+    pub fn poll(&mut self, now: Instant) -> Vec<WatcherEvent> {
         // Collect raw events
         while let Ok(result) = self.raw_rx.try_recv() {
             match result {
@@ -288,4 +326,103 @@ mod tests {
         assert!(result.is_ok());
         assert!(watcher.watched_paths().len() == 1);
     }
+
+    fn notify_event(kind: notify::EventKind, path: &Path) -> NotifyEvent {
+        NotifyEvent::new(kind).add_path(path.to_path_buf())
+    }
+
+    #[test]
+    fn test_convert_any_kinds() {
+        use notify::event::{CreateKind, RemoveKind};
+
+        let watcher = FileWatcherService::new(WatcherConfig::default()).unwrap();
+        let path = Path::new("/project/src/newdir");
+
+        let created = watcher.convert_event(notify_event(notify::EventKind::Create(CreateKind::Any), path));
+        assert!(matches!(created, Some(WatcherEvent::Created(p)) if p == path));
+
+        let deleted = watcher.convert_event(notify_event(notify::EventKind::Remove(RemoveKind::Any), path));
+        assert!(matches!(deleted, Some(WatcherEvent::Deleted(p)) if p == path));
+
+        // Unpaired rename of a path that doesn't exist is a removal
+        let renamed = watcher.convert_event(notify_event(
+            notify::EventKind::Modify(ModifyKind::Name(RenameMode::Any)),
+            path,
+        ));
+        assert!(matches!(renamed, Some(WatcherEvent::Deleted(_))));
+    }
+
+    #[test]
+    fn test_affected_dir() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().to_path_buf();
+        let sub = root.join("sub");
+        fs::create_dir(&sub).unwrap();
+
+        // Create/delete always affect the parent, even for directories
+        assert_eq!(WatcherEvent::Created(sub.clone()).affected_dir(), Some(root.clone()));
+        assert_eq!(WatcherEvent::Deleted(sub.clone()).affected_dir(), Some(root.clone()));
+        assert_eq!(
+            WatcherEvent::Renamed(root.join("old"), sub.clone()).affected_dir(),
+            Some(root.clone())
+        );
+
+        // Changes to an existing directory affect the directory itself
+        assert_eq!(WatcherEvent::Changed(sub.clone()).affected_dir(), Some(sub.clone()));
+        assert_eq!(WatcherEvent::Modified(sub.clone()).affected_dir(), Some(sub.clone()));
+
+        // Changes to files (or vanished paths) affect the parent
+        assert_eq!(WatcherEvent::Changed(sub.join("gone.txt")).affected_dir(), Some(sub.clone()));
+        assert_eq!(WatcherEvent::Error("x".to_string()).affected_dir(), None);
+    }
+
+    /// Poll until an event with the given path shows up or the timeout passes
+    fn wait_for_event(watcher: &mut FileWatcherService, path: &Path) -> Option<WatcherEvent> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            for event in watcher.poll(Instant::now()) {
+                let matches = match &event {
+                    WatcherEvent::Created(p) |
+                    WatcherEvent::Modified(p) |
+                    WatcherEvent::Changed(p) |
+                    WatcherEvent::Renamed(_, p) => p == path,
+                    _ => false,
+                };
+                if matches {
+                    return Some(event);
+                }
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        None
+    }
+
+    #[test]
+    #[ignore = "uses the real OS watcher; timing varies across CI platforms"]
+    fn test_real_create_events_affect_parent() {
+        let temp = TempDir::new().unwrap();
+        // FSEvents reports canonical paths (/private/var/... on macOS)
+        let root = temp.path().canonicalize().unwrap();
+
+        let config = WatcherConfig {
+            debounce: Duration::from_millis(50),
+            ignore_patterns: vec![],
+            ..Default::default()
+        };
+        let mut watcher = FileWatcherService::new(config).unwrap();
+        watcher.watch(&root).unwrap();
+        // Give the backend time to start delivering events
+        std::thread::sleep(Duration::from_millis(500));
+
+        let file = root.join("new_file.txt");
+        fs::write(&file, "hello").unwrap();
+        let event = wait_for_event(&mut watcher, &file).expect("no event for new file");
+        assert_eq!(event.affected_dir(), Some(root.clone()));
+
+        let dir = root.join("new_dir");
+        fs::create_dir(&dir).unwrap();
+        let event = wait_for_event(&mut watcher, &dir).expect("no event for new directory");
+        assert!(matches!(event, WatcherEvent::Created(_)), "unexpected event: {:?}", event);
+        assert_eq!(event.affected_dir(), Some(root.clone()));
+    }
 }